@@ -0,0 +1,82 @@
+use serde_json::Value as JsonValue;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::utils::decode_msgpack;
+
+static NEXT_REQ_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Turn a bind endpoint into one a local client can connect to
+/// (`tcp://*:N` / `tcp://0.0.0.0:N` become `tcp://127.0.0.1:N`).
+pub fn connect_endpoint(endpoint: &str) -> String {
+    for wildcard in ["tcp://*:", "tcp://0.0.0.0:"] {
+        if let Some(port) = endpoint.strip_prefix(wildcard) {
+            return format!("tcp://127.0.0.1:{}", port);
+        }
+    }
+    endpoint.to_string()
+}
+
+/// Minimal blocking DEALER client speaking the msgpack RPC protocol
+pub struct RpcClient {
+    sock: zmq::Socket,
+}
+
+impl RpcClient {
+    pub fn connect(ctx: &zmq::Context, endpoint: &str, timeout_ms: i32) -> zmq::Result<Self> {
+        let sock = ctx.socket(zmq::DEALER)?;
+        sock.set_linger(0)?;
+        sock.set_rcvtimeo(timeout_ms)?;
+        sock.set_sndtimeo(timeout_ms)?;
+        sock.connect(&connect_endpoint(endpoint))?;
+        Ok(Self { sock })
+    }
+
+    /// Send one request and wait for its response envelope
+    pub fn call(&self, op: &str, args: JsonValue) -> Result<JsonValue, String> {
+        let req_id = format!("cli-{}", NEXT_REQ_ID.fetch_add(1, Ordering::Relaxed));
+        let req = serde_json::json!({"v": 1, "req_id": req_id, "op": op, "args": args});
+        let raw = rmp_serde::to_vec_named(&req).map_err(|e| e.to_string())?;
+        self.sock
+            .send(raw, 0)
+            .map_err(|e| format!("send failed: {}", e))?;
+
+        // Skip stale replies (e.g. for an earlier request that timed out).
+        loop {
+            let body = match self.sock.recv_bytes(0) {
+                Ok(b) => b,
+                Err(zmq::Error::EAGAIN) => return Err("timed out".to_string()),
+                Err(e) => return Err(format!("recv failed: {}", e)),
+            };
+            let resp = decode_msgpack(&body).ok_or("invalid response")?;
+            if resp.get("req_id").and_then(|x| x.as_str()) == Some(req_id.as_str()) {
+                return Ok(resp);
+            }
+        }
+    }
+
+    /// Like `call`, but maps `ok: false` responses to their error message
+    pub fn call_ok(&self, op: &str, args: JsonValue) -> Result<JsonValue, String> {
+        let resp = self.call(op, args)?;
+        if resp.get("ok").and_then(|x| x.as_bool()) == Some(true) {
+            return Ok(resp.get("result").cloned().unwrap_or(JsonValue::Null));
+        }
+        let err = &resp["error"];
+        Err(format!(
+            "{}: {}",
+            err.get("code").and_then(|x| x.as_str()).unwrap_or("ERROR"),
+            err.get("message").and_then(|x| x.as_str()).unwrap_or("")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_binds_map_to_loopback() {
+        assert_eq!(connect_endpoint("tcp://*:38865"), "tcp://127.0.0.1:38865");
+        assert_eq!(connect_endpoint("tcp://0.0.0.0:1"), "tcp://127.0.0.1:1");
+        assert_eq!(connect_endpoint("ipc:///tmp/x.sock"), "ipc:///tmp/x.sock");
+    }
+}
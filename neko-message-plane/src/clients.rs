@@ -0,0 +1,162 @@
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::utils::{hex_encode, now_ts};
+
+#[derive(Debug, Clone)]
+pub struct ClientStats {
+    pub first_seen: f64,
+    pub last_seen: f64,
+    pub requests: u64,
+    pub ops: HashMap<String, u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClientView {
+    pub identity: String,
+    pub requests: u64,
+    pub first_seen: f64,
+    pub last_seen: f64,
+    pub idle_secs: f64,
+    pub ops: HashMap<String, u64>,
+}
+
+/// Per-identity request tracking keyed by the ROUTER envelope's first frame
+#[derive(Debug)]
+pub struct ClientRegistry {
+    pub max_entries: usize,
+    pub ttl_secs: f64,
+    pub entries: DashMap<Vec<u8>, ClientStats>,
+}
+
+impl ClientRegistry {
+    pub fn new(max_entries: usize, ttl_secs: f64) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            ttl_secs,
+            entries: DashMap::new(),
+        }
+    }
+
+    pub fn record(&self, identity: &[u8], op: &str) {
+        let ts = now_ts();
+        if let Some(mut st) = self.entries.get_mut(identity) {
+            st.last_seen = ts;
+            st.requests = st.requests.saturating_add(1);
+            *st.ops.entry(op.to_string()).or_insert(0) += 1;
+            return;
+        }
+
+        if self.entries.len() >= self.max_entries {
+            self.evict_idle(ts);
+        }
+        if self.entries.len() >= self.max_entries {
+            self.evict_oldest();
+        }
+
+        let mut ops = HashMap::new();
+        ops.insert(op.to_string(), 1);
+        self.entries.insert(
+            identity.to_vec(),
+            ClientStats {
+                first_seen: ts,
+                last_seen: ts,
+                requests: 1,
+                ops,
+            },
+        );
+    }
+
+    /// Drop identities idle for longer than the TTL; returns how many were removed
+    pub fn evict_idle(&self, now: f64) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, st| now - st.last_seen <= self.ttl_secs);
+        before.saturating_sub(self.entries.len())
+    }
+
+    fn evict_oldest(&self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by(|a, b| a.last_seen.total_cmp(&b.last_seen))
+            .map(|e| e.key().clone());
+        if let Some(k) = oldest {
+            self.entries.remove(&k);
+        }
+    }
+
+    /// Snapshot of tracked clients, optionally limited to those seen within `idle_secs`
+    pub fn snapshot(&self, idle_secs: Option<f64>) -> Vec<ClientView> {
+        let now = now_ts();
+        let mut out: Vec<ClientView> = self
+            .entries
+            .iter()
+            .filter_map(|e| {
+                let idle = (now - e.last_seen).max(0.0);
+                if let Some(max_idle) = idle_secs {
+                    if idle > max_idle {
+                        return None;
+                    }
+                }
+                Some(ClientView {
+                    identity: hex_encode(e.key()),
+                    requests: e.requests,
+                    first_seen: e.first_seen,
+                    last_seen: e.last_seen,
+                    idle_secs: idle,
+                    ops: e.ops.clone(),
+                })
+            })
+            .collect();
+        out.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.identity.cmp(&b.identity)));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_per_op_counts() {
+        let reg = ClientRegistry::new(16, 600.0);
+        reg.record(b"\x00\x01", "ping");
+        reg.record(b"\x00\x01", "bus.publish");
+        reg.record(b"\x00\x01", "bus.publish");
+        reg.record(b"\xff", "ping");
+
+        let snap = reg.snapshot(None);
+        assert_eq!(snap.len(), 2);
+        assert_eq!(snap[0].identity, "0001");
+        assert_eq!(snap[0].requests, 3);
+        assert_eq!(snap[0].ops.get("bus.publish"), Some(&2));
+        assert_eq!(snap[1].identity, "ff");
+    }
+
+    #[test]
+    fn stays_bounded_and_evicts_idle() {
+        let reg = ClientRegistry::new(2, 600.0);
+        reg.record(b"a", "ping");
+        reg.record(b"b", "ping");
+        reg.record(b"c", "ping");
+        assert_eq!(reg.entries.len(), 2);
+        assert!(reg.entries.contains_key(b"c".as_slice()));
+
+        let removed = reg.evict_idle(now_ts() + 601.0);
+        assert_eq!(removed, 2);
+        assert!(reg.snapshot(None).is_empty());
+    }
+
+    #[test]
+    fn idle_filter_excludes_stale_clients() {
+        let reg = ClientRegistry::new(16, 600.0);
+        reg.record(b"old", "ping");
+        reg.record(b"new", "ping");
+        reg.entries.get_mut(b"old".as_slice()).unwrap().last_seen -= 120.0;
+
+        let snap = reg.snapshot(Some(60.0));
+        assert_eq!(snap.len(), 1);
+        assert_eq!(snap[0].identity, hex_encode(b"new"));
+    }
+}
@@ -1,4 +1,54 @@
 use clap::Parser;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::ipc;
+use crate::types::{default_store_configs, StoreConfig, QUERY_MAX_LIMIT};
+
+/// One `--store-config name=maxlen:topic_max[:get_recent_max_limit[:query_max_limit]]` entry;
+/// empty or omitted fields keep the default
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreOverride {
+    pub name: String,
+    pub maxlen: Option<usize>,
+    pub topic_max: Option<usize>,
+    pub get_recent_max_limit: Option<usize>,
+    pub query_max_limit: Option<usize>,
+}
+
+pub fn parse_store_override(s: &str) -> Result<StoreOverride, String> {
+    let (name, spec) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected name=maxlen:topic_max, got {:?}", s))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("store name is empty".to_string());
+    }
+    let mut parts = spec.split(':');
+    let mut field = |what: &str| -> Result<Option<usize>, String> {
+        match parts.next().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(v) => match v.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(Some(n)),
+                _ => Err(format!("invalid {} for store {}: {:?}", what, name, v)),
+            },
+        }
+    };
+    let maxlen = field("maxlen")?;
+    let topic_max = field("topic_max")?;
+    let get_recent_max_limit = field("get_recent_max_limit")?;
+    let query_max_limit = field("query_max_limit")?;
+    if parts.next().is_some() {
+        return Err(format!("too many fields for store {}: {:?}", name, spec));
+    }
+    Ok(StoreOverride {
+        name: name.to_string(),
+        maxlen,
+        topic_max,
+        get_recent_max_limit,
+        query_max_limit,
+    })
+}
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "neko-message-plane")]
@@ -38,6 +88,54 @@ pub struct Cli {
 
     #[arg(long, default_value_t = 0)]
     pub workers: usize,
+
+    /// Directory for ipc:// sockets; derives rpc/ingest/pub endpoints under it
+    #[arg(long)]
+    pub ipc_dir: Option<String>,
+
+    /// Max number of client identities tracked for bus.clients
+    #[arg(long, default_value_t = 4096)]
+    pub clients_max: usize,
+
+    /// Client identities idle longer than this are evicted
+    #[arg(long, default_value_t = 600)]
+    pub clients_ttl_secs: u64,
+
+    /// Token required by admin ops (bus.clients); admin ops are refused when unset
+    #[arg(long)]
+    pub admin_token: Option<String>,
+
+    /// Forward accepted ingest messages to a standby instance's ingest endpoint
+    #[arg(long)]
+    pub replicate_to: Option<String>,
+
+    /// Also forward RPC bus.publish events (re-encoded as delta_batch items)
+    #[arg(long, default_value_t = false)]
+    pub replicate_publishes: bool,
+
+    /// Outbound replication queue size; messages beyond it are dropped
+    #[arg(long, default_value_t = 10000)]
+    pub replicate_queue: usize,
+
+    /// Bind, run a loopback ping/publish/get_recent/replay/SUB check, print a JSON report and exit
+    #[arg(long, default_value_t = false)]
+    pub self_check: bool,
+
+    /// Per-step timeout for --self-check
+    #[arg(long, default_value_t = 2000)]
+    pub self_check_timeout_ms: u64,
+
+    /// Client mode: ping the rpc endpoint (given, or the configured one) and exit 0/1
+    #[arg(long, value_name = "ENDPOINT", num_args = 0..=1, default_missing_value = "")]
+    pub ping: Option<String>,
+
+    /// Timeout for --ping
+    #[arg(long, default_value_t = 1000)]
+    pub ping_timeout_ms: u64,
+
+    /// Per-store override or extra store, as name=maxlen:topic_max[:get_recent_max_limit[:query_max_limit]] (repeatable)
+    #[arg(long = "store-config", value_name = "NAME=MAXLEN:TOPIC_MAX[:RECENT_MAX[:QUERY_MAX]]", value_parser = parse_store_override)]
+    pub store_config: Vec<StoreOverride>,
 }
 
 pub fn env_or(key: &str, default: &str) -> String {
@@ -109,6 +207,101 @@ impl Cli {
                 .and_then(|s| s.parse::<usize>().ok())
                 .unwrap_or(0);
         }
+        if self.ipc_dir.is_none() {
+            self.ipc_dir = std::env::var("NEKO_MESSAGE_PLANE_IPC_DIR")
+                .ok()
+                .filter(|s| !s.is_empty());
+        }
+        if self.clients_max == 4096 {
+            self.clients_max = std::env::var("NEKO_MESSAGE_PLANE_CLIENTS_MAX")
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(4096);
+        }
+        if self.clients_ttl_secs == 600 {
+            self.clients_ttl_secs = std::env::var("NEKO_MESSAGE_PLANE_CLIENTS_TTL_SECS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(600);
+        }
+        if self.admin_token.is_none() {
+            self.admin_token = std::env::var("NEKO_MESSAGE_PLANE_ADMIN_TOKEN")
+                .ok()
+                .filter(|s| !s.is_empty());
+        }
+        if self.replicate_to.is_none() {
+            self.replicate_to = std::env::var("NEKO_MESSAGE_PLANE_REPLICATE_TO")
+                .ok()
+                .filter(|s| !s.is_empty());
+        }
+        if !self.replicate_publishes {
+            self.replicate_publishes = std::env::var("NEKO_MESSAGE_PLANE_REPLICATE_PUBLISHES")
+                .ok()
+                .map(|s| matches!(s.to_lowercase().as_str(), "true" | "1" | "yes" | "on"))
+                .unwrap_or(false);
+        }
+        if self.replicate_queue == 10000 {
+            self.replicate_queue = std::env::var("NEKO_MESSAGE_PLANE_REPLICATE_QUEUE")
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(10000);
+        }
+        if self.store_config.is_empty() {
+            if let Ok(raw) = std::env::var("NEKO_MESSAGE_PLANE_STORE_CONFIG") {
+                for item in raw.split([',', ';']).map(str::trim).filter(|s| !s.is_empty()) {
+                    match parse_store_override(item) {
+                        Ok(o) => self.store_config.push(o),
+                        Err(e) => log::warn!("[message_plane] ignoring NEKO_MESSAGE_PLANE_STORE_CONFIG entry: {}", e),
+                    }
+                }
+            }
+        }
+        self.apply_ipc_dir();
+    }
+
+    /// Effective per-store settings: built-in defaults with --store-config applied on top.
+    /// Stores not among the defaults start from the global store_maxlen/topic_max.
+    pub fn store_configs(&self) -> BTreeMap<String, StoreConfig> {
+        let mut stores =
+            default_store_configs(self.store_maxlen, self.topic_max, self.get_recent_max_limit);
+        for o in &self.store_config {
+            let cfg = stores.entry(o.name.clone()).or_insert(StoreConfig {
+                maxlen: self.store_maxlen,
+                topic_max: self.topic_max,
+                get_recent_max_limit: self.get_recent_max_limit,
+                query_max_limit: QUERY_MAX_LIMIT,
+            });
+            if let Some(v) = o.maxlen {
+                cfg.maxlen = v;
+            }
+            if let Some(v) = o.topic_max {
+                cfg.topic_max = v;
+            }
+            if let Some(v) = o.get_recent_max_limit {
+                cfg.get_recent_max_limit = v;
+            }
+            if let Some(v) = o.query_max_limit {
+                cfg.query_max_limit = v;
+            }
+        }
+        stores
+    }
+
+    /// Derive ipc:// endpoints under ipc_dir for endpoints still at their tcp defaults
+    pub fn apply_ipc_dir(&mut self) {
+        let dir = match self.ipc_dir.as_deref() {
+            Some(d) => Path::new(d).to_path_buf(),
+            None => return,
+        };
+        if self.rpc_endpoint == "tcp://127.0.0.1:38865" {
+            self.rpc_endpoint = ipc::ipc_endpoint(&dir, ipc::RPC_SOCKET_NAME);
+        }
+        if self.ingest_endpoint == "tcp://127.0.0.1:38867" {
+            self.ingest_endpoint = ipc::ipc_endpoint(&dir, ipc::INGEST_SOCKET_NAME);
+        }
+        if self.pub_endpoint == "tcp://127.0.0.1:38866" {
+            self.pub_endpoint = ipc::ipc_endpoint(&dir, ipc::PUB_SOCKET_NAME);
+        }
     }
     
     /// Get effective worker count (0 means auto-detect CPU cores)
@@ -131,5 +324,75 @@ impl Cli {
             if self.validate_payload_bytes { "true" } else { "false" },
         );
         std::env::set_var("NEKO_MESSAGE_PLANE_GET_RECENT_MAX_LIMIT", self.get_recent_max_limit.to_string());
+        std::env::set_var("NEKO_MESSAGE_PLANE_ZMQ_RPC_ENDPOINT", &self.rpc_endpoint);
+        std::env::set_var("NEKO_MESSAGE_PLANE_ZMQ_INGEST_ENDPOINT", &self.ingest_endpoint);
+        std::env::set_var("NEKO_MESSAGE_PLANE_ZMQ_PUB_ENDPOINT", &self.pub_endpoint);
+        if let Some(token) = &self.admin_token {
+            std::env::set_var("NEKO_MESSAGE_PLANE_ADMIN_TOKEN", token);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> Cli {
+        let mut argv = vec!["neko-message-plane"];
+        argv.extend_from_slice(args);
+        Cli::parse_from(argv)
+    }
+
+    #[test]
+    fn parses_store_overrides() {
+        let o = parse_store_override("runs=50000:300").unwrap();
+        assert_eq!((o.name.as_str(), o.maxlen, o.topic_max), ("runs", Some(50000), Some(300)));
+        let o = parse_store_override("events=:42").unwrap();
+        assert_eq!((o.maxlen, o.topic_max), (None, Some(42)));
+        assert!(parse_store_override("runs").is_err());
+        assert!(parse_store_override("=1:2").is_err());
+        assert!(parse_store_override("runs=abc").is_err());
+        assert!(parse_store_override("runs=0").is_err());
+        assert!(parse_store_override("runs=1:2:3:4:5").is_err());
+        let o = parse_store_override("export=::50000").unwrap();
+        assert_eq!((o.maxlen, o.get_recent_max_limit, o.query_max_limit), (None, Some(50000), None));
+    }
+
+    #[test]
+    fn overrides_apply_and_unspecified_stores_keep_defaults() {
+        let c = cli(&["--store-config", "runs=50000:300", "--store-config", "audit=100"]);
+        let stores = c.store_configs();
+        let defaults = default_store_configs(c.store_maxlen, c.topic_max, c.get_recent_max_limit);
+
+        assert_eq!((stores["runs"].maxlen, stores["runs"].topic_max), (50000, 300));
+        assert_eq!(stores["runs"].get_recent_max_limit, 1000);
+        assert_eq!((stores["audit"].maxlen, stores["audit"].topic_max), (100, c.topic_max));
+        assert_eq!(stores["audit"].query_max_limit, QUERY_MAX_LIMIT);
+        for name in ["messages", "events", "lifecycle", "export", "memory"] {
+            assert_eq!(stores[name], defaults[name], "{}", name);
+        }
+    }
+
+    #[test]
+    fn defaults_match_builtin_ratios() {
+        let stores = cli(&[]).store_configs();
+        assert_eq!((stores["messages"].maxlen, stores["messages"].topic_max), (20000, 2000));
+        assert_eq!((stores["runs"].maxlen, stores["runs"].topic_max), (500, 200));
+        assert_eq!(stores.len(), 6);
+    }
+
+    #[test]
+    fn read_limits_are_per_store() {
+        let c = cli(&[
+            "--get-recent-max-limit",
+            "50",
+            "--store-config",
+            "export=::5000:20000",
+        ]);
+        let stores = c.store_configs();
+        assert_eq!(stores["messages"].get_recent_max_limit, 50);
+        assert_eq!(stores["messages"].query_max_limit, QUERY_MAX_LIMIT);
+        assert_eq!(stores["export"].get_recent_max_limit, 5000);
+        assert_eq!(stores["export"].query_max_limit, 20000);
     }
 }
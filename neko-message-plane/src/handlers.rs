@@ -4,15 +4,16 @@ use std::sync::mpsc;
 use std::sync::Arc;
 use std::sync::OnceLock;
 
-use crate::query::eval_plan;
+use crate::query::{eval_plan, validate_plan};
 use crate::rpc::{
-    rpc_err, rpc_ok, RpcGetRecentResult, RpcGetSinceResult, RpcHealthResult, RpcPublishResult, RpcQueryResult,
-    RpcReplayResult,
+    rpc_err, rpc_ok, ReqCtx, RpcClientsResult, RpcGetRecentResult, RpcGetSinceResult, RpcHealthResult, RpcPublishBatchResult, RpcPublishResult, RpcQueryResult,
+    RpcReplayResult, RpcStatsResult, RpcStoreInfoResult,
 };
-use crate::types::{Event, MpState, PubMsg};
-use crate::utils::{json_obj, mp_get, mp_get_str, mp_to_json, now_ts};
+use crate::types::{Event, MpState, PubMsg, QUERY_MAX_LIMIT};
+use crate::utils::{cap_trace_id, json_obj, mp_get, mp_get_str, mp_to_json, now_ts};
 
 static VALIDATE_MODE: OnceLock<String> = OnceLock::new();
+static ADMIN_TOKEN: OnceLock<Option<String>> = OnceLock::new();
 
 // ============ PERF MARKER FUNCTIONS ============
 // These functions are used for perf profiling to identify code sections.
@@ -83,6 +84,26 @@ fn get_validate_mode() -> &'static str {
     })
 }
 
+fn get_admin_token() -> Option<&'static str> {
+    ADMIN_TOKEN
+        .get_or_init(|| {
+            std::env::var("NEKO_MESSAGE_PLANE_ADMIN_TOKEN")
+                .ok()
+                .filter(|s| !s.is_empty())
+        })
+        .as_deref()
+}
+
+/// Admin ops are refused unless a token is configured and the envelope's `admin_token` matches it;
+/// returns the UNAUTHORIZED message when refused
+fn admin_denied(configured: Option<&str>, presented: Option<&str>) -> Option<&'static str> {
+    match configured {
+        None => Some("admin ops are disabled; start the server with --admin-token"),
+        Some(token) if presented == Some(token) => None,
+        Some(_) => Some("admin token required"),
+    }
+}
+
 /// Handle RPC request in MessagePack format
 pub fn handle_rpc_mp(
     req: &MpValue,
@@ -90,7 +111,12 @@ pub fn handle_rpc_mp(
     pub_tx: Option<&mpsc::Sender<PubMsg>>,
 ) -> Vec<u8> {
     let req_id = mp_get_str(req, "req_id").unwrap_or("");
+    let trace_id = mp_get_str(req, "trace_id").map(cap_trace_id).filter(|s| !s.is_empty());
+    let ctx = ReqCtx { req_id, trace_id };
     let op = mp_get_str(req, "op").unwrap_or("");
+    if let Some(t) = trace_id {
+        log::debug!("[message_plane] rpc op={} req_id={} trace_id={}", op, req_id, t);
+    }
     let args = mp_get(req, "args").cloned().unwrap_or(MpValue::Nil);
     let args_obj = args.as_map().cloned().unwrap_or_default();
 
@@ -105,14 +131,14 @@ pub fn handle_rpc_mp(
         ("warn", None) => 1,
         ("strict", Some(vv)) => vv.as_i64().unwrap_or(-1),
         ("strict", None) => {
-            return rpc_err(req_id, "BAD_VERSION", "missing protocol version", None)
+            return rpc_err(&ctx, "BAD_VERSION", "missing protocol version", None)
         }
         (_, Some(vv)) => vv.as_i64().unwrap_or(1),
         (_, None) => 1,
     };
     if v != 1 {
         return rpc_err(
-            req_id,
+            &ctx,
             "BAD_VERSION",
             &format!("unsupported protocol version: {}", v),
             None,
@@ -121,43 +147,63 @@ pub fn handle_rpc_mp(
 
     if op == "ping" || op == "health" {
         return rpc_ok(
-            req_id,
+            &ctx,
             RpcHealthResult {
                 ok: true,
                 ts: now_ts(),
+                endpoints: &state.endpoints,
             },
         );
     }
 
     if op == "bus.get_recent" {
-        return handle_get_recent_mp(req_id, &args_obj, state);
+        return handle_get_recent_mp(&ctx, &args_obj, state);
     }
 
     if op == "bus.replay" {
-        return handle_replay_mp(req_id, &args, &mode, state);
+        return handle_replay_mp(&ctx, &args, &mode, state);
     }
 
     if op == "bus.query" {
-        return handle_query_mp(req_id, &args, &mode, state);
+        return handle_query_mp(&ctx, &args, &mode, state);
     }
 
     if op == "bus.get_since" {
-        return handle_get_since_mp(req_id, &args_obj, state);
+        return handle_get_since_mp(&ctx, &args_obj, state);
     }
 
     if op == "bus.publish" {
-        return handle_publish_mp(req_id, &args, state, pub_tx);
+        return handle_publish_mp(&ctx, &args, state, pub_tx);
+    }
+
+    if op == "bus.publish_batch" {
+        return handle_publish_batch_mp(&ctx, &args, state, pub_tx);
+    }
+
+    if op == "bus.stats" {
+        return handle_stats_mp(&ctx, state);
+    }
+
+    if op == "store.info" {
+        return handle_store_info_mp(&ctx, &args, state);
+    }
+
+    if op == "bus.clients" {
+        if let Some(msg) = admin_denied(get_admin_token(), mp_get_str(req, "admin_token")) {
+            return rpc_err(&ctx, "UNAUTHORIZED", msg, None);
+        }
+        return handle_clients_mp(&ctx, &args, state);
     }
 
     if strict {
-        return rpc_err(req_id, "UNKNOWN_OP", &format!("unknown op: {}", op), None);
+        return rpc_err(&ctx, "UNKNOWN_OP", &format!("unknown op: {}", op), None);
     }
-    rpc_err(req_id, "UNKNOWN_OP", &format!("unknown op: {}", op), None)
+    rpc_err(&ctx, "UNKNOWN_OP", &format!("unknown op: {}", op), None)
 }
 
 #[inline(never)]
 fn handle_get_recent_mp(
-    req_id: &str,
+    ctx: &ReqCtx<'_>,
     args_obj: &[(MpValue, MpValue)],
     state: &Arc<MpState>,
 ) -> Vec<u8> {
@@ -201,21 +247,14 @@ fn handle_get_recent_mp(
             }
         }
     }
-    let max_limit = std::env::var("NEKO_MESSAGE_PLANE_GET_RECENT_MAX_LIMIT")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(1000);
-    if limit > max_limit {
-        limit = max_limit;
-    }
 
     // PERF: wait for store lock
     perf_marker_wait_begin();
     let items = match state.store(&store) {
-        Some(s) => s.get_recent("", &topic, limit),
+        Some(s) => s.get_recent("", &topic, limit.min(s.get_recent_max_limit)),
         None => {
             perf_marker_wait_end();
-            return rpc_err(req_id, "BAD_STORE", "invalid store", None);
+            return rpc_err(ctx, "BAD_STORE", "invalid store", None);
         }
     };
     perf_marker_wait_end();
@@ -228,7 +267,7 @@ fn handle_get_recent_mp(
     // PERF: serialize phase
     perf_marker_serialize_begin();
     let result = rpc_ok(
-        req_id,
+        ctx,
         RpcGetRecentResult {
             store,
             topic,
@@ -241,7 +280,7 @@ fn handle_get_recent_mp(
 }
 
 fn handle_get_since_mp(
-    req_id: &str,
+    ctx: &ReqCtx<'_>,
     args_obj: &[(MpValue, MpValue)],
     state: &Arc<MpState>,
 ) -> Vec<u8> {
@@ -289,14 +328,6 @@ fn handle_get_since_mp(
             }
         }
     }
-    let max_limit = std::env::var("NEKO_MESSAGE_PLANE_GET_RECENT_MAX_LIMIT")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(1000);
-    if limit > max_limit {
-        limit = max_limit;
-    }
-
     let topic_opt = if topic == "all" || topic == "*" {
         None
     } else {
@@ -304,13 +335,13 @@ fn handle_get_since_mp(
     };
 
     let items = match state.store(&store) {
-        Some(s) => s.get_since("", topic_opt, after_seq, limit),
-        None => return rpc_err(req_id, "BAD_STORE", "invalid store", None),
+        Some(s) => s.get_since("", topic_opt, after_seq, limit.min(s.get_recent_max_limit)),
+        None => return rpc_err(ctx, "BAD_STORE", "invalid store", None),
     };
 
     let out_items = events_to_mp_vec(&items, false);
     rpc_ok(
-        req_id,
+        ctx,
         RpcGetSinceResult {
             store,
             topic,
@@ -322,7 +353,7 @@ fn handle_get_since_mp(
 
 #[inline(never)]
 fn handle_replay_mp(
-    req_id: &str,
+    ctx: &ReqCtx<'_>,
     args: &MpValue,
     mode: &str,
     state: &Arc<MpState>,
@@ -330,12 +361,12 @@ fn handle_replay_mp(
     if mode == "strict" {
         let st_raw = mp_get_str(args, "store").or_else(|| mp_get_str(args, "bus"));
         if st_raw.is_none() {
-            return rpc_err(req_id, "BAD_ARGS", "invalid args: missing store", None);
+            return rpc_err(ctx, "BAD_ARGS", "invalid args: missing store", None);
         }
         let plan_raw = mp_get(args, "plan").or_else(|| mp_get(args, "trace"));
         if !matches!(plan_raw, Some(v) if v.is_map()) {
             return rpc_err(
-                req_id,
+                ctx,
                 "BAD_ARGS",
                 "invalid args: missing/invalid plan",
                 None,
@@ -354,36 +385,42 @@ fn handle_replay_mp(
     let plan_mp = mp_get(args, "plan").or_else(|| mp_get(args, "trace"));
     let plan_mp = match plan_mp {
         Some(v) if v.is_map() => v,
-        _ => return rpc_err(req_id, "BAD_ARGS", "plan is required", None),
+        _ => return rpc_err(ctx, "BAD_ARGS", "plan is required", None),
     };
     let plan_json = match mp_to_json(plan_mp) {
         Some(j) => j,
-        None => return rpc_err(req_id, "BAD_ARGS", "invalid plan", None),
+        None => return rpc_err(ctx, "BAD_ARGS", "invalid plan", None),
     };
     let light = mp_get(args, "light")
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
+    if let Err(e) = validate_plan(&plan_json) {
+        let details = MpValue::Map(vec![(MpValue::from("path"), MpValue::from(e.path.as_str()))]);
+        return rpc_err(
+            ctx,
+            "BAD_ARGS",
+            &format!("invalid plan at {}: {}", e.path, e.message),
+            Some(details),
+        );
+    }
+
     // PERF: wait for store lock + eval_plan (full scan)
     perf_marker_wait_begin();
-    let items = match state.store(store_name) {
-        Some(store_ref) => eval_plan(&*store_ref, &plan_json),
+    let (items, max_limit) = match state.store(store_name) {
+        Some(store_ref) => (eval_plan(&store_ref, &plan_json), store_ref.get_recent_max_limit),
         None => {
             perf_marker_wait_end();
-            return rpc_err(req_id, "BAD_STORE", "invalid store", None);
+            return rpc_err(ctx, "BAD_STORE", "invalid store", None);
         }
     };
     perf_marker_wait_end();
 
     let mut items = match items {
         Some(v) => v,
-        None => return rpc_err(req_id, "BAD_ARGS", "unsupported plan", None),
+        None => return rpc_err(ctx, "BAD_ARGS", "unsupported plan", None),
     };
 
-    let max_limit = std::env::var("NEKO_MESSAGE_PLANE_GET_RECENT_MAX_LIMIT")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(1000);
     if items.len() > max_limit {
         items.truncate(max_limit);
     }
@@ -396,7 +433,7 @@ fn handle_replay_mp(
     // PERF: serialize phase
     perf_marker_serialize_begin();
     let result = rpc_ok(
-        req_id,
+        ctx,
         RpcReplayResult {
             store: store_name.to_string(),
             items: out_items,
@@ -408,7 +445,7 @@ fn handle_replay_mp(
 }
 
 fn handle_query_mp(
-    req_id: &str,
+    ctx: &ReqCtx<'_>,
     args: &MpValue,
     mode: &str,
     state: &Arc<MpState>,
@@ -424,23 +461,27 @@ fn handle_query_mp(
         .unwrap_or(200) as i64;
     if limit <= 0 {
         if mode == "strict" {
-            return rpc_err(req_id, "BAD_ARGS", "invalid args: limit<=0", None);
+            return rpc_err(ctx, "BAD_ARGS", "invalid args: limit<=0", None);
         }
         if mode == "warn" {
             log::warn!("[message_plane] invalid args for bus.query: limit<=0");
         }
         limit = 200;
     }
-    if limit > 10000 {
+    let max_limit = state
+        .store(store)
+        .map(|s| s.query_max_limit)
+        .unwrap_or(QUERY_MAX_LIMIT) as i64;
+    if limit > max_limit {
         if mode == "warn" {
-            log::warn!("[message_plane] bus.query clamp limit {} -> 10000", limit);
+            log::warn!("[message_plane] bus.query clamp limit {} -> {}", limit, max_limit);
         }
-        limit = 10000;
+        limit = max_limit;
     }
 
     if topic.is_empty() {
         if mode == "strict" {
-            return rpc_err(req_id, "BAD_ARGS", "invalid args: empty topic", None);
+            return rpc_err(ctx, "BAD_ARGS", "invalid args: empty topic", None);
         }
         if mode == "warn" {
             log::warn!("[message_plane] invalid args for bus.query: empty topic; using '*'");
@@ -541,7 +582,7 @@ fn handle_query_mp(
 
     let out_items = events_to_mp_vec(&out, light);
     rpc_ok(
-        req_id,
+        ctx,
         RpcQueryResult {
             store: store.to_string(),
             topic: topic.to_string(),
@@ -551,12 +592,19 @@ fn handle_query_mp(
     )
 }
 
-fn handle_publish_mp(
-    req_id: &str,
-    args: &MpValue,
+/// Upper bound on items per bus.publish_batch request
+const PUBLISH_BATCH_MAX_ITEMS: usize = 1000;
+
+/// Validate and publish one event; returns the stored event as a msgpack map or an
+/// error code/message pair
+fn publish_one(
+    trace_id: Option<&str>,
+    store: &str,
+    topic: &str,
+    payload: &MpValue,
     state: &Arc<MpState>,
     pub_tx: Option<&mpsc::Sender<PubMsg>>,
-) -> Vec<u8> {
+) -> Result<MpValue, (&'static str, &'static str)> {
     let topic_name_max_len = std::env::var("NEKO_MESSAGE_PLANE_TOPIC_NAME_MAX_LEN")
         .ok()
         .and_then(|s| s.parse::<usize>().ok())
@@ -566,17 +614,14 @@ fn handle_publish_mp(
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(262144);
 
-    let store = mp_get_str(args, "store").unwrap_or("messages");
-    let topic = mp_get_str(args, "topic").unwrap_or("");
     if topic.is_empty() {
-        return rpc_err(req_id, "BAD_ARGS", "topic is required", None);
+        return Err(("BAD_ARGS", "topic is required"));
     }
     if topic.len() > topic_name_max_len {
-        return rpc_err(req_id, "BAD_ARGS", "topic too long", None);
+        return Err(("BAD_ARGS", "topic too long"));
     }
 
-    let payload = mp_get(args, "payload").cloned().unwrap_or(MpValue::Nil);
-    let payload_bytes = rmp_serde::to_vec_named(&payload).unwrap_or_default();
+    let payload_bytes = rmp_serde::to_vec_named(payload).unwrap_or_default();
     
     // Store-specific payload size limits
     let effective_max_bytes = if store == "runs" {
@@ -593,19 +638,28 @@ fn handle_publish_mp(
         } else {
             "payload too large"
         };
-        return rpc_err(req_id, "BAD_ARGS", msg, None);
+        return Err(("BAD_ARGS", msg));
     }
 
-    let payload_json = match mp_to_json(&payload) {
+    let payload_json = match mp_to_json(payload) {
         Some(j) => j,
-        None => return rpc_err(req_id, "BAD_ARGS", "invalid payload", None),
+        None => return Err(("BAD_ARGS", "invalid payload")),
     };
 
     let ev = match state.store(store) {
-        Some(s) => s.publish(store, topic, payload_json),
-        None => return rpc_err(req_id, "BAD_STORE", "invalid store", None),
+        Some(s) => {
+            if !s.meta.contains_key(topic) && s.meta.len() >= s.topic_max {
+                return Err(("BAD_ARGS", "too many topics"));
+            }
+            s.publish(store, topic, payload_json, trace_id)
+        }
+        None => return Err(("BAD_STORE", "invalid store")),
     };
 
+    if let Some(rep) = &state.replicator {
+        rep.forward_publish(store, topic, &ev.payload_json, trace_id);
+    }
+
     if let Some(tx) = pub_tx {
         let _ = tx.send(PubMsg {
             topic: ev.topic.as_bytes().to_vec(),
@@ -628,12 +682,141 @@ fn handle_publish_mp(
     ev_map.push((MpValue::from("topic"), MpValue::from(ev.topic.as_ref())));
     ev_map.push((MpValue::from("payload"), (*ev.payload_mp).clone()));
     ev_map.push((MpValue::from("index"), (*ev.index_mp).clone()));
+    Ok(MpValue::Map(ev_map))
+}
+
+fn handle_publish_mp(
+    ctx: &ReqCtx<'_>,
+    args: &MpValue,
+    state: &Arc<MpState>,
+    pub_tx: Option<&mpsc::Sender<PubMsg>>,
+) -> Vec<u8> {
+    let store = mp_get_str(args, "store").unwrap_or("messages");
+    let topic = mp_get_str(args, "topic").unwrap_or("");
+    let payload = mp_get(args, "payload").cloned().unwrap_or(MpValue::Nil);
+    match publish_one(ctx.trace_id, store, topic, &payload, state, pub_tx) {
+        Ok(event) => rpc_ok(ctx, RpcPublishResult { accepted: true, event }),
+        Err((code, msg)) => rpc_err(ctx, code, msg, None),
+    }
+}
+
+/// bus.publish_batch: `args.items` is a list of `{topic, payload, store?, trace_id?}`.
+/// Every item is attempted; failures are reported per item instead of failing the request.
+fn handle_publish_batch_mp(
+    ctx: &ReqCtx<'_>,
+    args: &MpValue,
+    state: &Arc<MpState>,
+    pub_tx: Option<&mpsc::Sender<PubMsg>>,
+) -> Vec<u8> {
+    let default_store = mp_get_str(args, "store").unwrap_or("messages");
+    let items = match mp_get(args, "items").and_then(|v| v.as_array()) {
+        Some(items) => items,
+        None => return rpc_err(ctx, "BAD_ARGS", "items must be a list", None),
+    };
+    if items.len() > PUBLISH_BATCH_MAX_ITEMS {
+        return rpc_err(
+            ctx,
+            "BAD_ARGS",
+            &format!("too many items (max {})", PUBLISH_BATCH_MAX_ITEMS),
+            None,
+        );
+    }
+
+    let mut accepted = 0;
+    let results = items
+        .iter()
+        .map(|item| {
+            let outcome = if item.is_map() {
+                let store = mp_get_str(item, "store").unwrap_or(default_store);
+                let topic = mp_get_str(item, "topic").unwrap_or("");
+                let trace_id = mp_get_str(item, "trace_id")
+                    .map(cap_trace_id)
+                    .filter(|s| !s.is_empty())
+                    .or(ctx.trace_id);
+                let payload = mp_get(item, "payload").cloned().unwrap_or(MpValue::Nil);
+                publish_one(trace_id, store, topic, &payload, state, pub_tx)
+            } else {
+                Err(("BAD_ARGS", "item must be a map"))
+            };
+            match outcome {
+                Ok(event) => {
+                    accepted += 1;
+                    MpValue::Map(vec![
+                        (MpValue::from("ok"), MpValue::from(true)),
+                        (MpValue::from("event"), event),
+                    ])
+                }
+                Err((code, msg)) => MpValue::Map(vec![
+                    (MpValue::from("ok"), MpValue::from(false)),
+                    (
+                        MpValue::from("error"),
+                        MpValue::Map(vec![
+                            (MpValue::from("code"), MpValue::from(code)),
+                            (MpValue::from("message"), MpValue::from(msg)),
+                        ]),
+                    ),
+                ]),
+            }
+        })
+        .collect::<Vec<_>>();
 
     rpc_ok(
-        req_id,
-        RpcPublishResult {
-            accepted: true,
-            event: MpValue::Map(ev_map),
+        ctx,
+        RpcPublishBatchResult {
+            accepted,
+            rejected: results.len() - accepted,
+            items: results,
+        },
+    )
+}
+
+fn handle_stats_mp(ctx: &ReqCtx<'_>, state: &Arc<MpState>) -> Vec<u8> {
+    let stores = state
+        .stores
+        .iter()
+        .map(|e| (e.key().clone(), e.value().get_metrics()))
+        .collect();
+    rpc_ok(
+        ctx,
+        RpcStatsResult {
+            ts: now_ts(),
+            stores,
+            clients: state.clients.entries.len(),
+            replication: state.replicator.as_ref().map(|r| r.stats()),
+        },
+    )
+}
+
+fn handle_store_info_mp(ctx: &ReqCtx<'_>, args: &MpValue, state: &Arc<MpState>) -> Vec<u8> {
+    let stores = match mp_get_str(args, "store").or_else(|| mp_get_str(args, "bus")) {
+        Some(name) => match state.store(name) {
+            Some(s) => std::iter::once((name.to_string(), s.info())).collect(),
+            None => return rpc_err(ctx, "BAD_STORE", "invalid store", None),
+        },
+        None => state
+            .stores
+            .iter()
+            .map(|e| (e.key().clone(), e.value().info()))
+            .collect(),
+    };
+    rpc_ok(ctx, RpcStoreInfoResult { stores })
+}
+
+fn handle_clients_mp(ctx: &ReqCtx<'_>, args: &MpValue, state: &Arc<MpState>) -> Vec<u8> {
+    let idle_secs = match mp_get(args, "idle_secs") {
+        None | Some(MpValue::Nil) => None,
+        Some(v) => match v.as_f64().or_else(|| v.as_i64().map(|n| n as f64)) {
+            Some(n) if n >= 0.0 => Some(n),
+            _ => return rpc_err(ctx, "BAD_ARGS", "idle_secs must be a non-negative number", None),
+        },
+    };
+    let items = state.clients.snapshot(idle_secs);
+    rpc_ok(
+        ctx,
+        RpcClientsResult {
+            total: state.clients.entries.len(),
+            ttl_secs: state.clients.ttl_secs,
+            items,
         },
     )
 }
@@ -686,6 +869,24 @@ pub fn handle_rpc(
     req: &JsonValue,
     state: &Arc<MpState>,
     pub_tx: Option<&mpsc::Sender<PubMsg>>,
+) -> JsonValue {
+    let trace_id = req
+        .get("trace_id")
+        .and_then(|x| x.as_str())
+        .map(cap_trace_id)
+        .filter(|s| !s.is_empty());
+    let mut resp = handle_rpc_json(req, trace_id, state, pub_tx);
+    if let (Some(t), Some(obj)) = (trace_id, resp.as_object_mut()) {
+        obj.insert("trace_id".to_string(), JsonValue::from(t));
+    }
+    resp
+}
+
+fn handle_rpc_json(
+    req: &JsonValue,
+    trace_id: Option<&str>,
+    state: &Arc<MpState>,
+    pub_tx: Option<&mpsc::Sender<PubMsg>>,
 ) -> JsonValue {
     let req_obj = match json_obj(req) {
         Some(o) => o,
@@ -714,10 +915,6 @@ pub fn handle_rpc(
         .ok()
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(128);
-    let topic_max = std::env::var("NEKO_MESSAGE_PLANE_TOPIC_MAX")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(2000);
     let payload_max_bytes = std::env::var("NEKO_MESSAGE_PLANE_PAYLOAD_MAX_BYTES")
         .ok()
         .and_then(|s| s.parse::<usize>().ok())
@@ -754,7 +951,8 @@ pub fn handle_rpc(
     }
 
     if op == "ping" || op == "health" {
-        return serde_json::json!({"v":1,"req_id":req_id,"ok":true,"result":{"ok":true,"ts": now_ts()},"error":null});
+        let endpoints = serde_json::to_value(&state.endpoints).unwrap_or(JsonValue::Null);
+        return serde_json::json!({"v":1,"req_id":req_id,"ok":true,"result":{"ok":true,"ts": now_ts(),"endpoints":endpoints},"error":null});
     }
 
     if op == "bus.get_recent" {
@@ -766,24 +964,17 @@ pub fn handle_rpc(
             .get("topic")
             .and_then(|x| x.as_str())
             .unwrap_or("all");
-        let mut limit = args_obj
+        let limit = args_obj
             .get("limit")
             .and_then(|x| x.as_u64())
             .unwrap_or(200) as usize;
-        let max_limit = std::env::var("NEKO_MESSAGE_PLANE_GET_RECENT_MAX_LIMIT")
-            .ok()
-            .and_then(|s| s.parse::<usize>().ok())
-            .unwrap_or(1000);
-        if limit > max_limit {
-            limit = max_limit;
-        }
         let light = args_obj
             .get("light")
             .and_then(|x| x.as_bool())
             .unwrap_or(false);
 
         let items = match state.store(store) {
-            Some(s) => s.get_recent("", topic, limit),
+            Some(s) => s.get_recent("", topic, limit.min(s.get_recent_max_limit)),
             None => {
                 return serde_json::json!({"v":1,"req_id":req_id,"ok":false,"result":null,"error":{"code":"BAD_STORE","message":"invalid store","details":null}});
             }
@@ -852,16 +1043,20 @@ pub fn handle_rpc(
         let ev = match state.store(store) {
             Some(s) => {
                 let is_new_topic = !s.meta.contains_key(topic);
-                if is_new_topic && s.meta.len() >= topic_max {
+                if is_new_topic && s.meta.len() >= s.topic_max {
                     return serde_json::json!({"v":1,"req_id":req_id,"ok":false,"result":null,"error":{"code":"BAD_ARGS","message":"too many topics","details":null}});
                 }
-                s.publish(store, topic, payload)
+                s.publish(store, topic, payload, trace_id)
             }
             None => {
                 return serde_json::json!({"v":1,"req_id":req_id,"ok":false,"result":null,"error":{"code":"BAD_STORE","message":"invalid store","details":null}});
             }
         };
 
+        if let Some(rep) = &state.replicator {
+            rep.forward_publish(store, topic, &ev.payload_json, trace_id);
+        }
+
         // Publish to pub socket via the pub thread.
         if let Some(tx) = pub_tx {
             if std::env::var("NEKO_MESSAGE_PLANE_PUB_ENABLED")
@@ -893,5 +1088,228 @@ pub fn handle_rpc(
         }},"error":null});
     }
 
+    if op == "bus.clients" {
+        if let Some(msg) = admin_denied(get_admin_token(), req_obj.get("admin_token").and_then(|x| x.as_str())) {
+            return serde_json::json!({"v":1,"req_id":req_id,"ok":false,"result":null,"error":{"code":"UNAUTHORIZED","message":msg,"details":null}});
+        }
+        let idle_secs = match args_obj.get("idle_secs") {
+            None | Some(JsonValue::Null) => None,
+            Some(v) => match v.as_f64() {
+                Some(n) if n >= 0.0 => Some(n),
+                _ => {
+                    return serde_json::json!({"v":1,"req_id":req_id,"ok":false,"result":null,"error":{"code":"BAD_ARGS","message":"idle_secs must be a non-negative number","details":null}});
+                }
+            },
+        };
+        let result = RpcClientsResult {
+            total: state.clients.entries.len(),
+            ttl_secs: state.clients.ttl_secs,
+            items: state.clients.snapshot(idle_secs),
+        };
+        let result = serde_json::to_value(&result).unwrap_or(JsonValue::Null);
+        return serde_json::json!({"v":1,"req_id":req_id,"ok":true,"result":result,"error":null});
+    }
+
     serde_json::json!({"v":1,"req_id":req_id,"ok":false,"result":null,"error":{"code":"UNKNOWN_OP","message":format!("unknown op: {}", op),"details":null}})
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::ClientRegistry;
+    use crate::types::StoreConfig;
+    use crate::utils::decode_msgpack_value;
+
+    fn mp_req(v: JsonValue) -> MpValue {
+        let raw = rmp_serde::to_vec_named(&v).unwrap();
+        decode_msgpack_value(&raw).unwrap()
+    }
+
+    fn mp_resp(raw: &[u8]) -> JsonValue {
+        mp_to_json(&decode_msgpack_value(raw).unwrap()).unwrap()
+    }
+
+    fn test_state() -> Arc<MpState> {
+        let stores = crate::types::default_store_configs(100, 100, 1000);
+        Arc::new(MpState::new(&stores, ClientRegistry::new(16, 60.0), None))
+    }
+
+    #[test]
+    fn trace_id_flows_from_publish_to_reads_and_pub_frames() {
+        let state = test_state();
+        let (tx, rx) = mpsc::channel();
+
+        let publish = mp_req(serde_json::json!({
+            "v": 1, "req_id": "r1", "trace_id": "trace-abc", "op": "bus.publish",
+            "args": {"store": "messages", "topic": "t", "payload": {"k": 1}},
+        }));
+        let resp = mp_resp(&handle_rpc_mp(&publish, &state, Some(&tx)));
+        assert_eq!(resp["ok"], true);
+        assert_eq!(resp["trace_id"], "trace-abc");
+        assert_eq!(resp["result"]["event"]["index"]["trace_id"], "trace-abc");
+
+        let frame = rx.try_recv().unwrap();
+        assert_eq!(mp_resp(&frame.body)["index"]["trace_id"], "trace-abc");
+
+        let recent = mp_req(serde_json::json!({
+            "v": 1, "req_id": "r2", "op": "bus.get_recent",
+            "args": {"store": "messages", "topic": "t", "limit": 10},
+        }));
+        let resp = mp_resp(&handle_rpc_mp(&recent, &state, None));
+        assert_eq!(resp["ok"], true);
+        assert!(resp.get("trace_id").is_none());
+        assert_eq!(resp["result"]["items"][0]["index"]["trace_id"], "trace-abc");
+    }
+
+    #[test]
+    fn json_responses_echo_trace_id() {
+        let state = test_state();
+        let req = serde_json::json!({"v": 1, "req_id": "r1", "trace_id": "t-1", "op": "nope"});
+        let resp = handle_rpc(&req, &state, None);
+        assert_eq!(resp["ok"], false);
+        assert_eq!(resp["trace_id"], "t-1");
+    }
+
+    #[test]
+    fn long_trace_ids_are_capped() {
+        let long = "é".repeat(100);
+        let capped = cap_trace_id(&long);
+        assert!(capped.len() <= crate::utils::TRACE_ID_MAX_LEN);
+        assert!(long.starts_with(capped));
+    }
+
+    #[test]
+    fn store_info_reports_effective_settings() {
+        let mut stores = crate::types::default_store_configs(100, 100, 1000);
+        stores.insert(
+            "audit".to_string(),
+            StoreConfig { maxlen: 7, topic_max: 1, get_recent_max_limit: 5, query_max_limit: 5 },
+        );
+        let state = Arc::new(MpState::new(&stores, ClientRegistry::new(16, 60.0), None));
+
+        let req = mp_req(serde_json::json!({"v": 1, "req_id": "r", "op": "store.info", "args": {}}));
+        let resp = mp_resp(&handle_rpc_mp(&req, &state, None));
+        assert_eq!(resp["result"]["stores"]["audit"]["maxlen"], 7);
+        assert_eq!(resp["result"]["stores"]["messages"]["maxlen"], 100);
+
+        let publish = |topic: &str| {
+            let req = mp_req(serde_json::json!({
+                "v": 1, "req_id": "p", "op": "bus.publish",
+                "args": {"store": "audit", "topic": topic, "payload": {}},
+            }));
+            mp_resp(&handle_rpc_mp(&req, &state, None))
+        };
+        assert_eq!(publish("a")["ok"], true);
+        assert_eq!(publish("b")["error"]["message"], "too many topics");
+
+        let req = mp_req(serde_json::json!({"v": 1, "req_id": "r", "op": "store.info", "args": {"store": "audit"}}));
+        let resp = mp_resp(&handle_rpc_mp(&req, &state, None));
+        assert_eq!(resp["result"]["stores"]["audit"]["topics"], 1);
+        assert!(resp["result"]["stores"].get("messages").is_none());
+    }
+
+    #[test]
+    fn read_limits_are_clamped_per_store() {
+        let mut stores = crate::types::default_store_configs(100, 100, 1000);
+        stores.get_mut("messages").unwrap().get_recent_max_limit = 3;
+        stores.get_mut("messages").unwrap().query_max_limit = 2;
+        stores.get_mut("export").unwrap().get_recent_max_limit = 50;
+        let state = Arc::new(MpState::new(&stores, ClientRegistry::new(16, 60.0), None));
+        for store in ["messages", "export"] {
+            let s = state.store(store).unwrap();
+            for i in 0..10 {
+                s.publish(store, "t", serde_json::json!({"i": i}), None);
+            }
+        }
+
+        let count = |op: &str, store: &str, args: JsonValue| {
+            let mut args = args;
+            args["store"] = JsonValue::from(store);
+            args["topic"] = JsonValue::from("t");
+            let req = serde_json::json!({"v": 1, "req_id": "r", "op": op, "args": args});
+            let resp = mp_resp(&handle_rpc_mp(&mp_req(req.clone()), &state, None));
+            let n = resp["result"]["items"].as_array().unwrap().len();
+            if op == "bus.get_recent" {
+                let json = handle_rpc(&req, &state, None);
+                assert_eq!(json["result"]["items"].as_array().unwrap().len(), n);
+            }
+            n
+        };
+
+        let limit = serde_json::json!({"limit": 8});
+        assert_eq!(count("bus.get_recent", "messages", limit.clone()), 3);
+        assert_eq!(count("bus.get_recent", "export", limit.clone()), 8);
+        assert_eq!(count("bus.get_since", "messages", limit.clone()), 3);
+        assert_eq!(count("bus.query", "messages", limit.clone()), 2);
+        assert_eq!(count("bus.query", "export", limit), 8);
+
+        let plan = serde_json::json!({"plan": {"kind": "get", "params": {"params": {"topic": "t", "max_count": 8}}}});
+        assert_eq!(count("bus.replay", "messages", plan.clone()), 3);
+        assert_eq!(count("bus.replay", "export", plan), 8);
+
+        let req = mp_req(serde_json::json!({"v": 1, "req_id": "r", "op": "store.info", "args": {"store": "messages"}}));
+        let info = mp_resp(&handle_rpc_mp(&req, &state, None));
+        assert_eq!(info["result"]["stores"]["messages"]["get_recent_max_limit"], 3);
+        assert_eq!(info["result"]["stores"]["messages"]["query_max_limit"], 2);
+    }
+
+    #[test]
+    fn publish_batch_reports_errors_per_item() {
+        let state = test_state();
+        let (tx, rx) = mpsc::channel();
+        let req = mp_req(serde_json::json!({
+            "v": 1, "req_id": "b", "trace_id": "batch", "op": "bus.publish_batch",
+            "args": {"store": "messages", "items": [
+                {"topic": "a", "payload": {"i": 0}},
+                {"topic": "", "payload": {"i": 1}},
+                {"topic": "b", "payload": {"i": 2}, "trace_id": "own"},
+                {"topic": "c", "store": "nope", "payload": {}},
+                "not a map",
+            ]},
+        }));
+        let resp = mp_resp(&handle_rpc_mp(&req, &state, Some(&tx)));
+        assert_eq!(resp["ok"], true);
+        assert_eq!(resp["result"]["accepted"], 2);
+        assert_eq!(resp["result"]["rejected"], 3);
+
+        let items = resp["result"]["items"].as_array().unwrap();
+        assert_eq!(items[0]["event"]["payload"]["i"], 0);
+        assert_eq!(items[0]["event"]["index"]["trace_id"], "batch");
+        assert_eq!(items[1]["error"]["message"], "topic is required");
+        assert_eq!(items[2]["event"]["index"]["trace_id"], "own");
+        assert_eq!(items[3]["error"]["code"], "BAD_STORE");
+        assert_eq!(items[4]["error"]["message"], "item must be a map");
+        assert_eq!(rx.try_iter().count(), 2);
+
+        let too_many = mp_req(serde_json::json!({
+            "v": 1, "req_id": "b", "op": "bus.publish_batch",
+            "args": {"items": vec![serde_json::json!({"topic": "t", "payload": {}}); PUBLISH_BATCH_MAX_ITEMS + 1]},
+        }));
+        let resp = mp_resp(&handle_rpc_mp(&too_many, &state, None));
+        assert_eq!(resp["error"]["code"], "BAD_ARGS");
+    }
+
+    #[test]
+    fn admin_ops_need_a_configured_matching_token() {
+        assert!(admin_denied(None, None).is_some());
+        assert!(admin_denied(None, Some("anything")).is_some());
+        assert!(admin_denied(Some("secret"), None).is_some());
+        assert!(admin_denied(Some("secret"), Some("guess")).is_some());
+        assert_eq!(admin_denied(Some("secret"), Some("secret")), None);
+    }
+
+    #[test]
+    fn bus_clients_is_refused_on_both_transports_without_a_token() {
+        // The tests never configure NEKO_MESSAGE_PLANE_ADMIN_TOKEN, so admin ops stay disabled
+        let state = test_state();
+        let envelope = serde_json::json!({"v": 1, "req_id": "c", "op": "bus.clients", "admin_token": "guess", "args": {}});
+
+        let resp = mp_resp(&handle_rpc_mp(&mp_req(envelope.clone()), &state, None));
+        assert_eq!(resp["error"]["code"], "UNAUTHORIZED");
+        assert!(resp["error"]["message"].as_str().unwrap().contains("--admin-token"));
+
+        let resp = handle_rpc(&envelope, &state, None);
+        assert_eq!(resp["error"]["code"], "UNAUTHORIZED");
+        assert!(resp["error"]["message"].as_str().unwrap().contains("--admin-token"));
+    }
+}
@@ -0,0 +1,137 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const RPC_SOCKET_NAME: &str = "rpc.sock";
+pub const INGEST_SOCKET_NAME: &str = "ingest.sock";
+pub const PUB_SOCKET_NAME: &str = "pub.sock";
+
+/// Build an `ipc://` endpoint for a socket file inside `dir`
+pub fn ipc_endpoint(dir: &Path, name: &str) -> String {
+    format!("ipc://{}", dir.join(name).display())
+}
+
+/// Return the socket file path for an `ipc://` endpoint (None for other transports)
+pub fn ipc_path(endpoint: &str) -> Option<PathBuf> {
+    endpoint
+        .strip_prefix("ipc://")
+        .filter(|p| !p.is_empty() && !p.starts_with('@'))
+        .map(PathBuf::from)
+}
+
+/// Create the socket directory, restricting it to the current user on unix
+pub fn prepare_dir(dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Remove a socket file left behind by a previous (crashed) run.
+///
+/// Returns Ok(true) if a stale file was removed, Ok(false) if there was nothing to do,
+/// and an AddrInUse error if another process is still listening on the socket.
+pub fn remove_stale_socket(endpoint: &str) -> io::Result<bool> {
+    let path = match ipc_path(endpoint) {
+        Some(p) => p,
+        None => return Ok(false),
+    };
+    let meta = match std::fs::symlink_metadata(&path) {
+        Ok(m) => m,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if !meta.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} is in use by another process", path.display()),
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = meta;
+
+    std::fs::remove_file(&path)?;
+    Ok(true)
+}
+
+/// Best-effort removal of socket files on graceful shutdown
+pub fn cleanup_sockets(endpoints: &[&str]) {
+    for ep in endpoints {
+        if let Some(path) = ipc_path(ep) {
+            match std::fs::remove_file(&path) {
+                Ok(()) => log::debug!("[message_plane] removed socket file {}", path.display()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("[message_plane] failed to remove socket file {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    fn temp_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neko_mp_ipc_{}_{}", tag, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        prepare_dir(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn stale_socket_is_removed_and_rebindable() {
+        let dir = temp_dir("stale");
+        let endpoint = ipc_endpoint(&dir, RPC_SOCKET_NAME);
+        let path = ipc_path(&endpoint).unwrap();
+
+        // Simulate a crashed run: the listener is gone but its socket file remains.
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        assert!(remove_stale_socket(&endpoint).unwrap());
+        assert!(!path.exists());
+
+        let ctx = zmq::Context::new();
+        let sock = ctx.socket(zmq::ROUTER).unwrap();
+        sock.set_linger(0).unwrap();
+        sock.bind(&endpoint).unwrap();
+        drop(sock);
+
+        cleanup_sockets(&[endpoint.as_str()]);
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn live_socket_is_left_alone() {
+        let dir = temp_dir("live");
+        let endpoint = ipc_endpoint(&dir, INGEST_SOCKET_NAME);
+        let path = ipc_path(&endpoint).unwrap();
+
+        let _listener = UnixListener::bind(&path).unwrap();
+        let err = remove_stale_socket(&endpoint).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        assert!(path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn non_ipc_endpoints_are_ignored() {
+        assert!(!remove_stale_socket("tcp://127.0.0.1:38865").unwrap());
+        assert_eq!(ipc_path("tcp://127.0.0.1:38865"), None);
+    }
+}
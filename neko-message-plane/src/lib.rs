@@ -0,0 +1,14 @@
+mod buffer_pool;
+pub mod client;
+mod clients;
+pub mod config;
+mod handlers;
+mod ipc;
+mod query;
+mod replication;
+pub mod rpc;
+pub mod sdnotify;
+pub mod selfcheck;
+pub mod server;
+mod types;
+mod utils;
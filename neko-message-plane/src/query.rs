@@ -428,6 +428,75 @@ pub fn apply_binary_op(left: Vec<Arc<Event>>, right: Vec<Arc<Event>>, op: &str)
     None
 }
 
+const UNARY_OPS: &[&str] = &[
+    "limit",
+    "sort",
+    "filter",
+    "where_eq",
+    "where_in",
+    "where_contains",
+    "where_regex",
+];
+const BINARY_OPS: &[&str] = &["merge", "intersection", "difference"];
+
+/// A structural problem in a replay plan, located by its JSON-path-like node path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanError {
+    pub path: String,
+    pub message: String,
+}
+
+/// Check the plan tree shape before evaluating it, so clients learn which node is wrong
+pub fn validate_plan(node: &JsonValue) -> Result<(), PlanError> {
+    validate_node(node, "$")
+}
+
+fn validate_node(node: &JsonValue, path: &str) -> Result<(), PlanError> {
+    let err = |message: String| {
+        Err(PlanError {
+            path: path.to_string(),
+            message,
+        })
+    };
+    let obj = match node.as_object() {
+        Some(o) => o,
+        None => return err("node must be a map".to_string()),
+    };
+    if let Some(p) = obj.get("params") {
+        if !p.is_object() && !p.is_null() {
+            return err("params must be a map".to_string());
+        }
+    }
+    let kind = obj.get("kind").and_then(|v| v.as_str()).unwrap_or("");
+    let op = obj.get("op").and_then(|v| v.as_str()).unwrap_or("");
+    match kind {
+        "get" => Ok(()),
+        "unary" => {
+            if !UNARY_OPS.contains(&op) {
+                return err(format!("unsupported unary op {:?}", op));
+            }
+            match obj.get("child") {
+                Some(child) => validate_node(child, &format!("{}.child", path)),
+                None => err("unary node requires child".to_string()),
+            }
+        }
+        "binary" => {
+            if !BINARY_OPS.contains(&op) {
+                return err(format!("unsupported binary op {:?}", op));
+            }
+            for side in ["left", "right"] {
+                match obj.get(side) {
+                    Some(n) => validate_node(n, &format!("{}.{}", path, side))?,
+                    None => return err(format!("binary node requires {}", side)),
+                }
+            }
+            Ok(())
+        }
+        "" => err("missing kind".to_string()),
+        other => err(format!("unknown kind {:?}", other)),
+    }
+}
+
 pub fn eval_plan(store: &Store, node: &JsonValue) -> Option<Vec<Arc<Event>>> {
     let obj = node.as_object()?;
     let kind = obj.get("kind")?.as_str().unwrap_or("");
@@ -450,10 +519,7 @@ pub fn eval_plan(store: &Store, node: &JsonValue) -> Option<Vec<Arc<Event>>> {
             .or_else(|| p.get("limit"))
             .and_then(|v| v.as_i64())
             .unwrap_or(200);
-        let max_limit = std::env::var("NEKO_MESSAGE_PLANE_GET_RECENT_MAX_LIMIT")
-            .ok()
-            .and_then(|s| s.parse::<i64>().ok())
-            .unwrap_or(1000);
+        let max_limit = store.get_recent_max_limit as i64;
         let mut limit_i = max_count;
         if limit_i > max_limit {
             limit_i = max_limit;
@@ -563,3 +629,39 @@ pub fn eval_plan(store: &Store, node: &JsonValue) -> Option<Vec<Arc<Event>>> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn valid_plans_pass() {
+        let get = json!({"kind": "get", "params": {"params": {"topic": "t"}}});
+        let plan = json!({
+            "kind": "binary", "op": "merge",
+            "left": {"kind": "unary", "op": "limit", "params": {"n": 3}, "child": get},
+            "right": get,
+        });
+        assert_eq!(validate_plan(&plan), Ok(()));
+    }
+
+    #[test]
+    fn errors_carry_node_path() {
+        let plan = json!({
+            "kind": "binary", "op": "merge",
+            "left": {"kind": "get"},
+            "right": {"kind": "unary", "op": "explode", "child": {"kind": "get"}},
+        });
+        let e = validate_plan(&plan).unwrap_err();
+        assert_eq!(e.path, "$.right");
+        assert!(e.message.contains("explode"));
+
+        let plan = json!({"kind": "unary", "op": "limit", "child": {"kind": "bogus"}});
+        assert_eq!(validate_plan(&plan).unwrap_err().path, "$.child");
+
+        let plan = json!({"kind": "binary", "op": "merge", "left": {"kind": "get"}});
+        let e = validate_plan(&plan).unwrap_err();
+        assert_eq!((e.path.as_str(), e.message.as_str()), ("$", "binary node requires right"));
+    }
+}
@@ -0,0 +1,157 @@
+use crossbeam::channel::{self, Receiver, Sender, TrySendError};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplicationStats {
+    pub endpoint: String,
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    pub sent: u64,
+    pub dropped: u64,
+}
+
+/// Forwards ingest traffic to a standby instance's ingest endpoint over PUSH.
+///
+/// The outbound queue is bounded: when the peer falls behind, new messages are
+/// dropped (and counted) instead of stalling the ingest thread.
+#[derive(Debug)]
+pub struct Replicator {
+    pub endpoint: String,
+    pub replicate_publishes: bool,
+    capacity: usize,
+    tx: Sender<Vec<u8>>,
+    sent: Arc<AtomicU64>,
+    dropped: AtomicU64,
+}
+
+impl Replicator {
+    fn with_channel(endpoint: &str, capacity: usize, replicate_publishes: bool) -> (Self, Receiver<Vec<u8>>) {
+        let capacity = capacity.max(1);
+        let (tx, rx) = channel::bounded::<Vec<u8>>(capacity);
+        (
+            Self {
+                endpoint: endpoint.to_string(),
+                replicate_publishes,
+                capacity,
+                tx,
+                sent: Arc::new(AtomicU64::new(0)),
+                dropped: AtomicU64::new(0),
+            },
+            rx,
+        )
+    }
+
+    /// Connect a PUSH socket to `endpoint` and spawn the sender thread
+    pub fn start(ctx: &zmq::Context, endpoint: &str, capacity: usize, replicate_publishes: bool) -> zmq::Result<Self> {
+        let (rep, rx) = Self::with_channel(endpoint, capacity, replicate_publishes);
+        let push = ctx.socket(zmq::PUSH)?;
+        push.set_linger(0).ok();
+        push.set_sndhwm(capacity.min(i32::MAX as usize) as i32).ok();
+        push.connect(endpoint)?;
+
+        let sent = Arc::clone(&rep.sent);
+        let ep = endpoint.to_string();
+        thread::spawn(move || {
+            log::info!("[replication] forwarding ingest traffic to {}", ep);
+            while let Ok(raw) = rx.recv() {
+                match push.send(raw, 0) {
+                    Ok(()) => {
+                        sent.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => log::warn!("[replication] send to {} failed: {}", ep, e),
+                }
+            }
+        });
+        Ok(rep)
+    }
+
+    /// Queue a raw ingest message for the peer; drops it if the queue is full
+    pub fn forward(&self, raw: Vec<u8>) {
+        match self.tx.try_send(raw) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                let n = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                if n == 1 || n.is_multiple_of(1000) {
+                    log::warn!("[replication] peer {} is behind; dropped {} messages", self.endpoint, n);
+                }
+            }
+        }
+    }
+
+    /// Re-encode an RPC publish as a single-item delta_batch for the peer
+    pub fn forward_publish(
+        &self,
+        store: &str,
+        topic: &str,
+        payload: &JsonValue,
+        trace_id: Option<&str>,
+    ) {
+        if !self.replicate_publishes {
+            return;
+        }
+        let mut item = serde_json::json!({"store": store, "topic": topic, "payload": payload});
+        if let Some(t) = trace_id {
+            item["trace_id"] = JsonValue::from(t);
+        }
+        let msg = serde_json::json!({"kind": "delta_batch", "items": [item]});
+        if let Ok(raw) = rmp_serde::to_vec_named(&msg) {
+            self.forward(raw);
+        }
+    }
+
+    pub fn stats(&self) -> ReplicationStats {
+        ReplicationStats {
+            endpoint: self.endpoint.clone(),
+            queue_depth: self.tx.len(),
+            queue_capacity: self.capacity,
+            sent: self.sent.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::decode_msgpack;
+
+    #[test]
+    fn full_queue_drops_and_counts() {
+        let (rep, _rx) = Replicator::with_channel("tcp://127.0.0.1:1", 2, true);
+        for _ in 0..5 {
+            rep.forward(vec![0x80]);
+        }
+        let st = rep.stats();
+        assert_eq!(st.queue_depth, 2);
+        assert_eq!(st.dropped, 3);
+    }
+
+    #[test]
+    fn publishes_are_reencoded_as_delta_batch() {
+        let ctx = zmq::Context::new();
+        let pull = ctx.socket(zmq::PULL).unwrap();
+        pull.set_rcvtimeo(2000).unwrap();
+        pull.bind("inproc://replication-test").unwrap();
+
+        let rep = Replicator::start(&ctx, "inproc://replication-test", 16, true).unwrap();
+        rep.forward_publish("events", "t1", &serde_json::json!({"k": 1}), None);
+
+        let raw = pull.recv_bytes(0).unwrap();
+        let msg = decode_msgpack(&raw).unwrap();
+        assert_eq!(msg["kind"], "delta_batch");
+        assert_eq!(msg["items"][0]["store"], "events");
+        assert_eq!(msg["items"][0]["topic"], "t1");
+        assert_eq!(msg["items"][0]["payload"]["k"], 1);
+    }
+
+    #[test]
+    fn publishes_skipped_unless_enabled() {
+        let (rep, rx) = Replicator::with_channel("tcp://127.0.0.1:1", 4, false);
+        rep.forward_publish("messages", "t", &JsonValue::Null, None);
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -1,6 +1,12 @@
 use rmpv::Value as MpValue;
 use serde::Serialize;
 
+use std::collections::BTreeMap;
+
+use crate::clients::ClientView;
+use crate::replication::ReplicationStats;
+use crate::types::{StoreInfo, StoreMetrics};
+
 #[derive(Serialize)]
 pub struct RpcError {
     pub code: String,
@@ -9,10 +15,19 @@ pub struct RpcError {
     pub details: Option<MpValue>,
 }
 
+/// Per-request identifiers echoed back in every response envelope
+#[derive(Debug, Clone, Copy)]
+pub struct ReqCtx<'a> {
+    pub req_id: &'a str,
+    pub trace_id: Option<&'a str>,
+}
+
 #[derive(Serialize)]
 pub struct RpcEnvelope<T: Serialize> {
     pub v: i32,
     pub req_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
     pub ok: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<T>,
@@ -20,10 +35,11 @@ pub struct RpcEnvelope<T: Serialize> {
     pub error: Option<RpcError>,
 }
 
-pub fn rpc_ok<T: Serialize>(req_id: &str, result: T) -> Vec<u8> {
+pub fn rpc_ok<T: Serialize>(ctx: &ReqCtx<'_>, result: T) -> Vec<u8> {
     rmp_serde::to_vec_named(&RpcEnvelope {
         v: 1,
-        req_id: req_id.to_string(),
+        req_id: ctx.req_id.to_string(),
+        trace_id: ctx.trace_id.map(|t| t.to_string()),
         ok: true,
         result: Some(result),
         error: None,
@@ -31,10 +47,11 @@ pub fn rpc_ok<T: Serialize>(req_id: &str, result: T) -> Vec<u8> {
     .unwrap_or_default()
 }
 
-pub fn rpc_err(req_id: &str, code: &str, message: &str, details: Option<MpValue>) -> Vec<u8> {
+pub fn rpc_err(ctx: &ReqCtx<'_>, code: &str, message: &str, details: Option<MpValue>) -> Vec<u8> {
     rmp_serde::to_vec_named(&RpcEnvelope::<MpValue> {
         v: 1,
-        req_id: req_id.to_string(),
+        req_id: ctx.req_id.to_string(),
+        trace_id: ctx.trace_id.map(|t| t.to_string()),
         ok: false,
         result: None,
         error: Some(RpcError {
@@ -46,10 +63,19 @@ pub fn rpc_err(req_id: &str, code: &str, message: &str, details: Option<MpValue>
     .unwrap_or_default()
 }
 
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RpcEndpoints {
+    pub rpc: String,
+    pub ingest: String,
+    #[serde(rename = "pub")]
+    pub pub_: String,
+}
+
 #[derive(Serialize)]
-pub struct RpcHealthResult {
+pub struct RpcHealthResult<'a> {
     pub ok: bool,
     pub ts: f64,
+    pub endpoints: &'a RpcEndpoints,
 }
 
 /// Lightweight event view for serialization without cloning MpValue
@@ -93,6 +119,13 @@ pub struct RpcPublishResult {
     pub event: MpValue,
 }
 
+#[derive(Serialize)]
+pub struct RpcPublishBatchResult {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub items: Vec<MpValue>,
+}
+
 #[derive(Serialize)]
 pub struct RpcGetSinceResult {
     pub store: String,
@@ -100,3 +133,24 @@ pub struct RpcGetSinceResult {
     pub items: Vec<MpValue>,
     pub after_seq: u64,
 }
+
+#[derive(Serialize)]
+pub struct RpcClientsResult {
+    pub total: usize,
+    pub ttl_secs: f64,
+    pub items: Vec<ClientView>,
+}
+
+#[derive(Serialize)]
+pub struct RpcStoreInfoResult {
+    pub stores: BTreeMap<String, StoreInfo>,
+}
+
+#[derive(Serialize)]
+pub struct RpcStatsResult {
+    pub ts: f64,
+    pub stores: BTreeMap<String, StoreMetrics>,
+    pub clients: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replication: Option<ReplicationStats>,
+}
@@ -0,0 +1,90 @@
+//! Minimal sd_notify(3) client. Everything is a no-op unless NOTIFY_SOCKET is set.
+
+use std::io;
+use std::time::Duration;
+
+/// Send a state string (e.g. "READY=1") to systemd; Ok(false) when not under systemd
+pub fn notify(state: &str) -> io::Result<bool> {
+    match std::env::var("NOTIFY_SOCKET") {
+        Ok(addr) if !addr.is_empty() => send_to(&addr, state).map(|_| true),
+        _ => Ok(false),
+    }
+}
+
+/// Interval at which WATCHDOG=1 should be sent (half of WatchdogSec), if enabled for us
+pub fn watchdog_interval() -> Option<Duration> {
+    let usec = std::env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    if usec == 0 {
+        return None;
+    }
+    Some(Duration::from_micros(usec / 2))
+}
+
+#[cfg(unix)]
+fn send_to(addr: &str, state: &str) -> io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let sock = UnixDatagram::unbound()?;
+    if let Some(name) = addr.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::linux::net::SocketAddrExt;
+            let sa = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+            sock.send_to_addr(state.as_bytes(), &sa)?;
+            return Ok(());
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = name;
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "abstract notify socket"));
+        }
+    }
+    sock.send_to(state.as_bytes(), addr)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_to(_addr: &str, _state: &str) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixDatagram;
+
+    #[test]
+    fn sends_state_to_notify_socket() {
+        let dir = std::env::temp_dir().join(format!("np-sdnotify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notify.sock");
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        send_to(path.to_str().unwrap(), "READY=1").unwrap();
+        let mut buf = [0u8; 64];
+        let n = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"READY=1");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn sends_state_to_abstract_socket() {
+        use std::os::linux::net::SocketAddrExt;
+        let name = format!("np-sdnotify-test-{}", std::process::id());
+        let sa = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+        let server = UnixDatagram::bind_addr(&sa).unwrap();
+
+        send_to(&format!("@{}", name), "WATCHDOG=1").unwrap();
+        let mut buf = [0u8; 64];
+        let n = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"WATCHDOG=1");
+    }
+}
@@ -0,0 +1,127 @@
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::time::{Duration, Instant};
+
+use crate::client::{connect_endpoint, RpcClient};
+use crate::utils::{decode_msgpack, now_ts};
+
+const STORE: &str = "messages";
+const TOPIC: &str = "self_check";
+
+#[derive(Debug, Serialize)]
+pub struct StepResult {
+    pub name: &'static str,
+    pub ok: bool,
+    pub ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SelfCheckReport {
+    pub ok: bool,
+    pub steps: Vec<StepResult>,
+}
+
+fn step<F>(steps: &mut Vec<StepResult>, name: &'static str, f: F)
+where
+    F: FnOnce() -> Result<(), String>,
+{
+    let t0 = Instant::now();
+    let res = f();
+    steps.push(StepResult {
+        name,
+        ok: res.is_ok(),
+        ms: t0.elapsed().as_secs_f64() * 1000.0,
+        error: res.err(),
+    });
+}
+
+fn expect_marker(items: &JsonValue, marker: &str) -> Result<(), String> {
+    let found = items
+        .as_array()
+        .map(|a| a.iter().any(|ev| ev["payload"]["marker"] == marker))
+        .unwrap_or(false);
+    if found {
+        Ok(())
+    } else {
+        Err("published event not returned".to_string())
+    }
+}
+
+/// Run ping/publish/get_recent/replay/SUB against the live sockets.
+/// Every step is bounded by `timeout_ms`.
+pub fn run(
+    ctx: &zmq::Context,
+    rpc_endpoint: &str,
+    pub_endpoint: Option<&str>,
+    timeout_ms: i32,
+) -> SelfCheckReport {
+    let mut steps = Vec::new();
+    let marker = format!("self-check-{}-{}", std::process::id(), now_ts());
+
+    let client = match RpcClient::connect(ctx, rpc_endpoint, timeout_ms) {
+        Ok(c) => c,
+        Err(e) => {
+            steps.push(StepResult {
+                name: "connect",
+                ok: false,
+                ms: 0.0,
+                error: Some(e.to_string()),
+            });
+            return SelfCheckReport { ok: false, steps };
+        }
+    };
+
+    // Connect SUB early so the subscription is in place before we publish.
+    let sub = pub_endpoint.map(|ep| -> zmq::Result<zmq::Socket> {
+        let s = ctx.socket(zmq::SUB)?;
+        s.set_linger(0)?;
+        s.set_rcvtimeo(100)?;
+        s.set_subscribe(b"")?;
+        s.connect(&connect_endpoint(ep))?;
+        Ok(s)
+    });
+
+    step(&mut steps, "ping", || client.call_ok("ping", serde_json::json!({})).map(|_| ()));
+
+    step(&mut steps, "publish", || {
+        let args = serde_json::json!({"store": STORE, "topic": TOPIC, "payload": {"marker": marker}});
+        client.call_ok("bus.publish", args).map(|_| ())
+    });
+
+    step(&mut steps, "get_recent", || {
+        let args = serde_json::json!({"store": STORE, "topic": TOPIC, "limit": 10});
+        let res = client.call_ok("bus.get_recent", args)?;
+        expect_marker(&res["items"], &marker)
+    });
+
+    step(&mut steps, "replay", || {
+        let plan = serde_json::json!({"kind": "get", "params": {"params": {"topic": TOPIC, "max_count": 10}}});
+        let res = client.call_ok("bus.replay", serde_json::json!({"store": STORE, "plan": plan}))?;
+        expect_marker(&res["items"], &marker)
+    });
+
+    if let Some(sub) = sub {
+        step(&mut steps, "sub", || {
+            let sub = sub.map_err(|e| e.to_string())?;
+            let sub_marker = format!("{}-sub", marker);
+            let deadline = Instant::now() + Duration::from_millis(timeout_ms.max(0) as u64);
+            // PUB drops messages until the subscription propagates, so keep publishing.
+            while Instant::now() < deadline {
+                let args = serde_json::json!({"store": STORE, "topic": TOPIC, "payload": {"marker": sub_marker}});
+                client.call_ok("bus.publish", args)?;
+                while let Ok(parts) = sub.recv_multipart(0) {
+                    let body = parts.last().and_then(|b| decode_msgpack(b));
+                    if body.is_some_and(|b| b["payload"]["marker"] == sub_marker.as_str()) {
+                        return Ok(());
+                    }
+                }
+            }
+            Err("no event received on pub socket".to_string())
+        });
+    }
+
+    let ok = steps.iter().all(|s| s.ok);
+    SelfCheckReport { ok, steps }
+}
@@ -0,0 +1,618 @@
+//! Server startup, shared by the binary and by embedders such as the Python wheel
+
+use crossbeam::channel;
+use serde_json::Value as JsonValue;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::clients::ClientRegistry;
+use crate::config::Cli;
+use crate::handlers::{handle_rpc, handle_rpc_mp};
+use crate::ipc;
+use crate::replication::Replicator;
+use crate::rpc::RpcEndpoints;
+use crate::types::{MpState, PubMsg};
+use crate::utils::{cap_trace_id, decode_json, decode_msgpack, decode_msgpack_value, mp_get_str, now_ts};
+
+/// A running message plane: ingest thread, worker pool and the ROUTER loop
+pub struct Server {
+    endpoints: RpcEndpoints,
+    pub_enabled: bool,
+    shutdown: Arc<AtomicBool>,
+    ticks: Arc<AtomicU64>,
+    router: Option<JoinHandle<()>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Endpoints as bound; wildcard tcp ports are resolved to the real ones
+    pub fn endpoints(&self) -> &RpcEndpoints {
+        &self.endpoints
+    }
+
+    pub fn pub_enabled(&self) -> bool {
+        self.pub_enabled
+    }
+
+    /// Flag that stops the server when set; safe to set from a signal handler
+    pub fn shutdown_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.shutdown)
+    }
+
+    /// Number of ROUTER loop iterations so far; a stuck loop stops advancing it
+    pub fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+
+    /// False once stop() ran or the ROUTER loop exited on its own (e.g. poll error)
+    pub fn is_running(&self) -> bool {
+        !self.shutdown.load(Ordering::SeqCst) && self.router.as_ref().is_some_and(|h| !h.is_finished())
+    }
+
+    /// Graceful shutdown: stop the loops, join every thread and remove ipc socket files.
+    /// Calling it again is a no-op.
+    pub fn stop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        let router = match self.router.take() {
+            Some(h) => h,
+            None => return,
+        };
+        // The router owns the task sender, so workers drain and exit once it is gone
+        let _ = router.join();
+        for h in self.threads.drain(..) {
+            let _ = h.join();
+        }
+        let mut bound: Vec<&str> = vec![&self.endpoints.rpc, &self.endpoints.ingest];
+        if self.pub_enabled {
+            bound.push(&self.endpoints.pub_);
+        }
+        ipc::cleanup_sockets(&bound);
+        log::info!("[message_plane] stopped");
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Bind the endpoint or report which one failed
+fn bind(sock: &zmq::Socket, endpoint: &str) -> Result<String, String> {
+    sock.bind(endpoint)
+        .map_err(|e| format!("cannot bind {}: {}", endpoint, e))?;
+    Ok(match sock.get_last_endpoint() {
+        Ok(Ok(ep)) => ep,
+        _ => endpoint.to_string(),
+    })
+}
+
+/// Bind all sockets on `ctx` and start serving in background threads.
+/// `cli` is used as-is: call `apply_env_overrides`/`export_to_env` first if wanted.
+/// Handlers still read validate mode, limits and the admin token from the process env.
+pub fn start(ctx: &zmq::Context, cli: &Cli) -> Result<Server, String> {
+    let topic_name_max_len = cli.topic_name_max_len;
+    let payload_max_bytes = cli.payload_max_bytes;
+    let validate_payload_bytes = cli.validate_payload_bytes;
+    let pub_enabled = cli.pub_enabled;
+    let n_workers = cli.get_workers();
+
+    log::info!("[message_plane] starting with {} worker threads", n_workers);
+
+    if let Some(dir) = cli.ipc_dir.as_deref() {
+        ipc::prepare_dir(Path::new(dir))
+            .map_err(|e| format!("failed to prepare ipc dir {}: {}", dir, e))?;
+    }
+    for ep in [&cli.rpc_endpoint, &cli.ingest_endpoint, &cli.pub_endpoint] {
+        match ipc::remove_stale_socket(ep) {
+            Ok(true) => log::warn!("[message_plane] removed stale socket file for {}", ep),
+            Ok(false) => {}
+            Err(e) => return Err(format!("cannot bind {}: {}", ep, e)),
+        }
+    }
+
+    let clients = ClientRegistry::new(cli.clients_max, cli.clients_ttl_secs as f64);
+    let replicator = match cli.replicate_to.as_deref() {
+        Some(ep) => Some(
+            Replicator::start(ctx, ep, cli.replicate_queue, cli.replicate_publishes)
+                .map_err(|e| format!("failed to connect replication peer {}: {}", ep, e))?,
+        ),
+        None => None,
+    };
+
+    let sock = |kind: zmq::SocketType| -> Result<zmq::Socket, String> {
+        let s = ctx.socket(kind).map_err(|e| format!("cannot create socket: {}", e))?;
+        s.set_linger(0).ok();
+        Ok(s)
+    };
+    let router = sock(zmq::ROUTER)?;
+    let pull = sock(zmq::PULL)?;
+    // Wake up periodically so RPC publishes queued on pub_rx get flushed without ingest traffic
+    pull.set_rcvtimeo(10).ok();
+    let pub_sock = sock(zmq::PUB)?;
+
+    let mut bound: Vec<String> = Vec::new();
+    let bound_all = (|| {
+        let ingest = bind(&pull, &cli.ingest_endpoint)?;
+        bound.push(ingest.clone());
+        let pub_ = if pub_enabled {
+            let ep = bind(&pub_sock, &cli.pub_endpoint)?;
+            bound.push(ep.clone());
+            ep
+        } else {
+            cli.pub_endpoint.clone()
+        };
+        let rpc = bind(&router, &cli.rpc_endpoint)?;
+        Ok::<_, String>(RpcEndpoints { rpc, ingest, pub_ })
+    })();
+    let endpoints = match bound_all {
+        Ok(eps) => eps,
+        Err(e) => {
+            ipc::cleanup_sockets(&bound.iter().map(String::as_str).collect::<Vec<_>>());
+            return Err(e);
+        }
+    };
+    log::info!("[message_plane] rpc server bound: {}", endpoints.rpc);
+
+    let mut state = MpState::new(&cli.store_configs(), clients, replicator);
+    state.endpoints = endpoints.clone();
+    let state = Arc::new(state);
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let ticks = Arc::new(AtomicU64::new(0));
+    let mut threads = Vec::with_capacity(n_workers + 1);
+
+    let (pub_tx, pub_rx) = mpsc::channel::<PubMsg>();
+    let (task_tx, task_rx) = channel::unbounded::<(Vec<Vec<u8>>, Vec<u8>)>();
+    let (result_tx, result_rx) = channel::unbounded::<(Vec<Vec<u8>>, Vec<u8>)>();
+
+    // Ingest thread
+    {
+        let state = Arc::clone(&state);
+        let shutdown = Arc::clone(&shutdown);
+        threads.push(thread::spawn(move || {
+            while !shutdown.load(Ordering::SeqCst) {
+                // Flush any queued pub messages from RPC side.
+                if pub_enabled {
+                    for _ in 0..256 {
+                        match pub_rx.try_recv() {
+                            Ok(pm) => {
+                                let _ = pub_sock.send_multipart(&[pm.topic, pm.body], 0);
+                            }
+                            Err(mpsc::TryRecvError::Empty) => break,
+                            Err(mpsc::TryRecvError::Disconnected) => break,
+                        }
+                    }
+                }
+
+                let raw = match pull.recv_bytes(0) {
+                    Ok(b) => b,
+                    Err(zmq::Error::EAGAIN) => continue,
+                    Err(_) => {
+                        std::thread::yield_now();
+                        continue;
+                    }
+                };
+
+                let msg = match decode_msgpack(&raw) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                let obj = match msg.as_object() {
+                    Some(o) => o,
+                    None => continue,
+                };
+
+                if let Some(rep) = &state.replicator {
+                    rep.forward(raw);
+                }
+
+                let kind = obj.get("kind").and_then(|x| x.as_str()).unwrap_or("delta_batch");
+                if kind == "snapshot" {
+                    handle_snapshot(&state, obj, topic_name_max_len, payload_max_bytes, validate_payload_bytes, pub_enabled, &pub_sock);
+                    continue;
+                }
+
+                handle_delta_batch(&state, obj, topic_name_max_len, payload_max_bytes, validate_payload_bytes, pub_enabled, &pub_sock);
+            }
+        }));
+    }
+
+    // Worker threads pool
+    for worker_id in 0..n_workers {
+        let task_rx = task_rx.clone();
+        let result_tx = result_tx.clone();
+        let state = Arc::clone(&state);
+        let pub_tx = pub_tx.clone();
+
+        threads.push(thread::spawn(move || {
+            log::debug!("[worker-{}] started", worker_id);
+            loop {
+                let (envelope, body) = match task_rx.recv() {
+                    Ok(task) => task,
+                    Err(_) => {
+                        log::debug!("[worker-{}] channel closed, exiting", worker_id);
+                        break;
+                    }
+                };
+
+                let identity = envelope.first().map(|f| f.as_slice()).unwrap_or(&[]);
+                let resp_raw = if let Some(v) = decode_msgpack_value(&body) {
+                    state.clients.record(identity, mp_get_str(&v, "op").unwrap_or(""));
+                    handle_rpc_mp(&v, &state, Some(&pub_tx))
+                } else {
+                    let req = decode_msgpack(&body).or_else(|| decode_json(&body)).unwrap_or(JsonValue::Null);
+                    state.clients.record(identity, req.get("op").and_then(|x| x.as_str()).unwrap_or(""));
+                    let resp = handle_rpc(&req, &state, Some(&pub_tx));
+                    rmp_serde::to_vec_named(&resp).unwrap_or_default()
+                };
+
+                if result_tx.send((envelope, resp_raw)).is_err() {
+                    log::error!("[worker-{}] failed to send result, exiting", worker_id);
+                    break;
+                }
+            }
+            log::debug!("[worker-{}] stopped", worker_id);
+        }));
+    }
+    drop(task_rx);
+    drop(result_tx);
+
+    // RPC loop: receive requests and send responses
+    let router = {
+        let shutdown = Arc::clone(&shutdown);
+        let ticks = Arc::clone(&ticks);
+        thread::spawn(move || {
+            router_loop(&router, task_tx, result_rx, &state, n_workers, &shutdown, &ticks)
+        })
+    };
+
+    Ok(Server {
+        endpoints,
+        pub_enabled,
+        shutdown,
+        ticks,
+        router: Some(router),
+        threads,
+    })
+}
+
+fn router_loop(
+    router: &zmq::Socket,
+    task_tx: channel::Sender<(Vec<Vec<u8>>, Vec<u8>)>,
+    result_rx: channel::Receiver<(Vec<Vec<u8>>, Vec<u8>)>,
+    state: &Arc<MpState>,
+    n_workers: usize,
+    shutdown: &AtomicBool,
+    ticks: &AtomicU64,
+) {
+    // Event-driven loop using ZMQ poller
+    let mut items = [router.as_poll_item(zmq::POLLIN)];
+    
+    // Optimize poll timeout based on worker count
+    // Single thread: 1ms for lower latency
+    // Multi-thread: 100ms to reduce CPU usage
+    let poll_timeout = if n_workers == 1 { 1 } else { 100 };
+    let mut last_client_evict = now_ts();
+
+    while !shutdown.load(Ordering::SeqCst) {
+        ticks.fetch_add(1, Ordering::Relaxed);
+
+        // Periodically drop idle client identities so the tracker stays bounded
+        let now = now_ts();
+        if now - last_client_evict >= 10.0 {
+            state.clients.evict_idle(now);
+            last_client_evict = now;
+        }
+
+        // Poll for incoming requests (blocks efficiently)
+        match zmq::poll(&mut items, poll_timeout) {
+            Ok(_) => {
+                // Check if router has incoming messages
+                if items[0].is_readable() {
+                    // Receive all available requests (batch processing)
+                    loop {
+                        match router.recv_multipart(zmq::DONTWAIT) {
+                            Ok(parts) => {
+                                if parts.len() >= 2 {
+                                    let envelope = parts[..parts.len() - 1].to_vec();
+                                    let body = parts[parts.len() - 1].clone();
+                                    
+                                    if task_tx.send((envelope, body)).is_err() {
+                                        log::error!("[message_plane] failed to send task to workers");
+                                        break;
+                                    }
+                                }
+                            }
+                            Err(zmq::Error::EAGAIN) => {
+                                // No more messages available
+                                break;
+                            }
+                            Err(e) => {
+                                log::error!("[message_plane] recv error: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            Err(zmq::Error::EAGAIN) => {
+                // Timeout, continue to check results
+            }
+            Err(zmq::Error::EINTR) => {
+                // Interrupted by a signal; the loop condition picks up shutdown
+                continue;
+            }
+            Err(e) => {
+                log::error!("[message_plane] poll error: {}", e);
+                break;
+            }
+        }
+
+        // Batch send all pending responses (up to 100 at once)
+        let mut sent = 0;
+        loop {
+            match result_rx.try_recv() {
+                Ok((envelope, resp_raw)) => {
+                    let mut out = Vec::with_capacity(envelope.len() + 1);
+                    for f in envelope {
+                        out.push(f);
+                    }
+                    out.push(resp_raw);
+                    
+                    if router.send_multipart(out, 0).is_err() {
+                        log::error!("[message_plane] failed to send response");
+                    }
+                    
+                    sent += 1;
+                    // Avoid blocking too long, send up to 100 responses per iteration
+                    if sent >= 100 {
+                        break;
+                    }
+                }
+                Err(channel::TryRecvError::Empty) => {
+                    // No more results ready
+                    break;
+                }
+                Err(channel::TryRecvError::Disconnected) => {
+                    log::error!("[message_plane] result channel disconnected, exiting");
+                    shutdown.store(true, Ordering::SeqCst);
+                    break;
+                }
+            }
+        }
+    }
+    // The ingest thread watches the same flag; make sure it stops if the loop broke on an error
+    shutdown.store(true, Ordering::SeqCst);
+}
+
+fn handle_snapshot(
+    state: &Arc<MpState>,
+    obj: &serde_json::Map<String, JsonValue>,
+    topic_name_max_len: usize,
+    payload_max_bytes: usize,
+    validate_payload_bytes: bool,
+    pub_enabled: bool,
+    pub_sock: &zmq::Socket,
+) {
+    let store = obj
+        .get("store")
+        .or_else(|| obj.get("bus"))
+        .and_then(|x| x.as_str())
+        .unwrap_or("messages");
+    let topic = obj.get("topic").and_then(|x| x.as_str()).unwrap_or("snapshot.all");
+    if topic.is_empty() || topic.len() > topic_name_max_len {
+        return;
+    }
+    let items = obj.get("items").and_then(|x| x.as_array()).cloned().unwrap_or_default();
+    let mode = obj.get("mode").and_then(|x| x.as_str()).unwrap_or("replace");
+    let trace_id = obj
+        .get("trace_id")
+        .and_then(|x| x.as_str())
+        .map(cap_trace_id)
+        .filter(|s| !s.is_empty());
+    let mut records: Vec<JsonValue> = Vec::with_capacity(items.len());
+    for it in items {
+        if !it.is_object() {
+            continue;
+        }
+        if validate_payload_bytes {
+            if let Ok(b) = rmp_serde::to_vec_named(&it) {
+                if b.len() > payload_max_bytes {
+                    continue;
+                }
+            } else {
+                continue;
+            }
+        }
+        records.push(it);
+    }
+
+    if let Some(store_ref) = state.store(store) {
+        let is_new_topic = !store_ref.meta.contains_key(topic);
+        if is_new_topic && store_ref.meta.len() >= store_ref.topic_max {
+            return;
+        }
+
+        let events = if mode == "append" {
+            let mut out = Vec::with_capacity(records.len());
+            for rec in records {
+                out.push(store_ref.publish(store, topic, rec, trace_id));
+            }
+            out
+        } else {
+            store_ref.replace_topic(store, topic, records, trace_id)
+        };
+        
+        if pub_enabled {
+            for ev in events {
+                let topic_bytes = format!("{}.{}", ev.store.as_ref(), ev.topic.as_ref()).as_bytes().to_vec();
+                let pub_map: Vec<(rmpv::Value, rmpv::Value)> = vec![
+                    (rmpv::Value::from("seq"), rmpv::Value::from(ev.seq as i64)),
+                    (rmpv::Value::from("ts"), rmpv::Value::from(ev.ts)),
+                    (rmpv::Value::from("store"), rmpv::Value::from(ev.store.as_ref())),
+                    (rmpv::Value::from("topic"), rmpv::Value::from(ev.topic.as_ref())),
+                    (rmpv::Value::from("payload"), (*ev.payload_mp).clone()),
+                    (rmpv::Value::from("index"), (*ev.index_mp).clone()),
+                ];
+                let body = rmp_serde::to_vec_named(&rmpv::Value::Map(pub_map)).unwrap_or_default();
+                let _ = pub_sock.send_multipart(&[topic_bytes, body], 0);
+            }
+        }
+    }
+}
+
+fn handle_delta_batch(
+    state: &Arc<MpState>,
+    obj: &serde_json::Map<String, JsonValue>,
+    topic_name_max_len: usize,
+    payload_max_bytes: usize,
+    validate_payload_bytes: bool,
+    pub_enabled: bool,
+    pub_sock: &zmq::Socket,
+) {
+    let items = obj.get("items").and_then(|x| x.as_array()).cloned().unwrap_or_default();
+    let batch_trace_id = obj.get("trace_id").and_then(|x| x.as_str());
+    for it in items {
+        let it_obj = match it.as_object() {
+            Some(o) => o,
+            None => continue,
+        };
+        let store = it_obj
+            .get("store")
+            .or_else(|| it_obj.get("bus"))
+            .and_then(|x| x.as_str())
+            .unwrap_or("messages");
+        let topic = it_obj.get("topic").and_then(|x| x.as_str()).unwrap_or("all");
+        if topic.is_empty() || topic.len() > topic_name_max_len {
+            continue;
+        }
+        // A per-item trace_id wins over the batch-level one.
+        let trace_id = it_obj
+            .get("trace_id")
+            .and_then(|x| x.as_str())
+            .or(batch_trace_id)
+            .map(cap_trace_id)
+            .filter(|s| !s.is_empty());
+        let payload = it_obj.get("payload").cloned().unwrap_or(JsonValue::Null);
+        let payload = if payload.is_object() {
+            payload
+        } else {
+            serde_json::json!({"value": payload})
+        };
+
+        if validate_payload_bytes {
+            if let Ok(b) = rmp_serde::to_vec_named(&payload) {
+                if b.len() > payload_max_bytes {
+                    continue;
+                }
+            } else {
+                continue;
+            }
+        }
+
+        let ev = match state.store(store) {
+            Some(store_ref) => {
+                let is_new_topic = !store_ref.meta.contains_key(topic);
+                if is_new_topic && store_ref.meta.len() >= store_ref.topic_max {
+                    continue;
+                }
+                store_ref.publish(store, topic, payload, trace_id)
+            }
+            None => continue,
+        };
+
+        if pub_enabled {
+            let topic_bytes = format!("{}.{}", ev.store.as_ref(), ev.topic.as_ref()).as_bytes().to_vec();
+            let pub_map: Vec<(rmpv::Value, rmpv::Value)> = vec![
+                (rmpv::Value::from("seq"), rmpv::Value::from(ev.seq as i64)),
+                (rmpv::Value::from("ts"), rmpv::Value::from(ev.ts)),
+                (rmpv::Value::from("store"), rmpv::Value::from(ev.store.as_ref())),
+                (rmpv::Value::from("topic"), rmpv::Value::from(ev.topic.as_ref())),
+                (rmpv::Value::from("payload"), (*ev.payload_mp).clone()),
+                (rmpv::Value::from("index"), (*ev.index_mp).clone()),
+            ];
+            let body = rmp_serde::to_vec_named(&rmpv::Value::Map(pub_map)).unwrap_or_default();
+            let _ = pub_sock.send_multipart(&[topic_bytes, body], 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_state() -> Arc<MpState> {
+        let stores = crate::types::default_store_configs(100, 100, 1000);
+        Arc::new(MpState::new(&stores, ClientRegistry::new(16, 60.0), None))
+    }
+
+    /// An XPUB socket stands in for the PUB socket so the test can wait until its SUB is subscribed
+    fn pub_pair(ctx: &zmq::Context, name: &str) -> (zmq::Socket, zmq::Socket) {
+        let endpoint = format!("inproc://{name}");
+        let pub_sock = ctx.socket(zmq::XPUB).unwrap();
+        pub_sock.bind(&endpoint).unwrap();
+        let sub = ctx.socket(zmq::SUB).unwrap();
+        sub.connect(&endpoint).unwrap();
+        sub.set_subscribe(b"").unwrap();
+        sub.set_rcvtimeo(2000).unwrap();
+        pub_sock.recv_bytes(0).unwrap();
+        (pub_sock, sub)
+    }
+
+    fn frame_trace_id(sub: &zmq::Socket) -> JsonValue {
+        let frames = sub.recv_multipart(0).unwrap();
+        let body: JsonValue = rmp_serde::from_slice(&frames[1]).unwrap();
+        body["index"]["trace_id"].clone()
+    }
+
+    #[test]
+    fn delta_batch_trace_id_reaches_pub_frames_and_stored_events() {
+        let state = test_state();
+        let ctx = zmq::Context::new();
+        let (pub_sock, sub) = pub_pair(&ctx, "delta-batch-trace");
+
+        let batch = serde_json::json!({
+            "kind": "delta_batch",
+            "trace_id": "batch-1",
+            "items": [
+                {"store": "messages", "topic": "t", "payload": {"k": 1}},
+                {"store": "messages", "topic": "t", "payload": {"k": 2}, "trace_id": "item-2"},
+            ],
+        });
+        handle_delta_batch(&state, batch.as_object().unwrap(), 128, 1 << 20, true, true, &pub_sock);
+
+        assert_eq!(frame_trace_id(&sub), "batch-1");
+        assert_eq!(frame_trace_id(&sub), "item-2");
+        let store = state.store("messages").unwrap();
+        let stored: Vec<_> = store.get_recent("messages", "t", 10).iter().map(|ev| ev.index_json["trace_id"].clone()).collect();
+        assert_eq!(stored, ["batch-1", "item-2"]);
+    }
+
+    #[test]
+    fn snapshot_trace_id_reaches_pub_frames_and_stored_events() {
+        let state = test_state();
+        let ctx = zmq::Context::new();
+        let (pub_sock, sub) = pub_pair(&ctx, "snapshot-trace");
+
+        for mode in ["replace", "append"] {
+            let snapshot = serde_json::json!({
+                "kind": "snapshot",
+                "store": "messages",
+                "topic": mode,
+                "mode": mode,
+                "trace_id": format!("snap-{mode}"),
+                "items": [{"k": 1}],
+            });
+            handle_snapshot(&state, snapshot.as_object().unwrap(), 128, 1 << 20, true, true, &pub_sock);
+
+            assert_eq!(frame_trace_id(&sub), format!("snap-{mode}").as_str());
+            let store = state.store("messages").unwrap();
+            let stored = store.get_recent("messages", mode, 10);
+            assert_eq!(stored[0].index_json["trace_id"], format!("snap-{mode}").as_str());
+        }
+    }
+}
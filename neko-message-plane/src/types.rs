@@ -1,6 +1,6 @@
 use rmpv::Value as MpValue;
 use serde_json::Value as JsonValue;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use dashmap::DashMap;
@@ -8,6 +8,9 @@ use parking_lot::RwLock;
 use std::sync::atomic::{AtomicU64, Ordering};
 use serde::Serialize;
 
+use crate::clients::ClientRegistry;
+use crate::replication::Replicator;
+use crate::rpc::RpcEndpoints;
 use crate::utils::extract_index;
 
 #[derive(Debug, Clone, Serialize)]
@@ -19,6 +22,16 @@ pub struct StoreMetrics {
     pub total_queries: u64,
 }
 
+/// Effective settings of a store, as reported by store.info
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreInfo {
+    pub maxlen: usize,
+    pub topic_max: usize,
+    pub get_recent_max_limit: usize,
+    pub query_max_limit: usize,
+    pub topics: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct Event {
     pub seq: u64,
@@ -42,8 +55,11 @@ pub struct TopicMeta {
 #[derive(Debug)]
 pub struct Store {
     pub maxlen: usize,
-    #[allow(dead_code)]
     pub topic_max: usize,
+    /// Cap for get_recent/get_since/replay reads
+    pub get_recent_max_limit: usize,
+    /// Cap for bus.query reads
+    pub query_max_limit: usize,
     pub next_seq: AtomicU64,
     pub topics: DashMap<String, Arc<RwLock<VecDeque<Arc<Event>>>>>,
     pub meta: DashMap<String, TopicMeta>,
@@ -57,10 +73,12 @@ pub struct Store {
 }
 
 impl Store {
-    pub fn new(maxlen: usize, topic_max: usize) -> Self {
+    pub fn new(cfg: &StoreConfig) -> Self {
         Self {
-            maxlen,
-            topic_max,
+            maxlen: cfg.maxlen,
+            topic_max: cfg.topic_max,
+            get_recent_max_limit: cfg.get_recent_max_limit,
+            query_max_limit: cfg.query_max_limit,
             next_seq: AtomicU64::new(1),
             topics: DashMap::new(),
             meta: DashMap::new(),
@@ -72,6 +90,16 @@ impl Store {
         }
     }
     
+    pub fn info(&self) -> StoreInfo {
+        StoreInfo {
+            maxlen: self.maxlen,
+            topic_max: self.topic_max,
+            get_recent_max_limit: self.get_recent_max_limit,
+            query_max_limit: self.query_max_limit,
+            topics: self.meta.len(),
+        }
+    }
+
     pub fn get_metrics(&self) -> StoreMetrics {
         let total_events = self.next_seq.load(Ordering::Relaxed).saturating_sub(1);
         StoreMetrics {
@@ -84,14 +112,17 @@ impl Store {
     }
 
     #[inline]
-    pub fn publish(&self, store: &str, topic: &str, payload: JsonValue) -> Arc<Event> {
+    pub fn publish(&self, store: &str, topic: &str, payload: JsonValue, trace_id: Option<&str>) -> Arc<Event> {
         let ts = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map(|d| d.as_secs_f64())
             .unwrap_or(0.0);
         let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
 
-        let idx = extract_index(&payload, ts);
+        let mut idx = extract_index(&payload, ts);
+        if let (Some(t), Some(obj)) = (trace_id, idx.as_object_mut()) {
+            obj.insert("trace_id".to_string(), JsonValue::from(t));
+        }
         let payload_json = Arc::new(payload);
         let index_json = Arc::new(idx);
 
@@ -118,9 +149,10 @@ impl Store {
         });
 
         // Get or create topic queue
-        let queue = self.topics.entry(topic.to_string()).or_insert_with(|| {
+        // Clone the Arc out so the shard guard is not held across later map lookups.
+        let queue = Arc::clone(&self.topics.entry(topic.to_string()).or_insert_with(|| {
             Arc::new(RwLock::new(VecDeque::with_capacity(self.maxlen.min(4096))))
-        });
+        }));
         
         // Write to queue
         {
@@ -146,12 +178,19 @@ impl Store {
         ev
     }
 
-    pub fn replace_topic(&self, store: &str, topic: &str, items: Vec<JsonValue>) -> Vec<Arc<Event>> {
+    pub fn replace_topic(
+        &self,
+        store: &str,
+        topic: &str,
+        items: Vec<JsonValue>,
+        trace_id: Option<&str>,
+    ) -> Vec<Arc<Event>> {
         let mut out = Vec::with_capacity(items.len());
         
-        let queue = self.topics.entry(topic.to_string()).or_insert_with(|| {
+        // Clone the Arc out so the shard guard is not held across later map lookups.
+        let queue = Arc::clone(&self.topics.entry(topic.to_string()).or_insert_with(|| {
             Arc::new(RwLock::new(VecDeque::with_capacity(self.maxlen.min(4096))))
-        });
+        }));
         queue.write().clear();
 
         let ts = SystemTime::now()
@@ -167,7 +206,7 @@ impl Store {
             },
         );
         for p in items {
-            let ev = self.publish(store, topic, p);
+            let ev = self.publish(store, topic, p, trace_id);
             out.push(ev);
         }
         out
@@ -240,52 +279,77 @@ impl Store {
     }
 }
 
-#[derive(Debug)]
-pub struct MpState {
-    #[allow(dead_code)]
+/// Default cap for bus.query reads
+pub const QUERY_MAX_LIMIT: usize = 10000;
+
+/// Capacity and read-limit settings for a single store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StoreConfig {
     pub maxlen: usize,
-    #[allow(dead_code)]
     pub topic_max: usize,
+    pub get_recent_max_limit: usize,
+    pub query_max_limit: usize,
+}
+
+/// Built-in stores, sized relative to the global store_maxlen/topic_max;
+/// read limits default to the global get_recent_max_limit and QUERY_MAX_LIMIT.
+pub fn default_store_configs(
+    maxlen: usize,
+    topic_max: usize,
+    get_recent_max_limit: usize,
+) -> BTreeMap<String, StoreConfig> {
+    let mut m = BTreeMap::new();
+    let mut add = |name: &str, maxlen: usize, topic_max: usize| {
+        m.insert(
+            name.to_string(),
+            StoreConfig {
+                maxlen,
+                topic_max,
+                get_recent_max_limit,
+                query_max_limit: QUERY_MAX_LIMIT,
+            },
+        );
+    };
+
+    // messages: high-frequency read/write, needs full capacity
+    add("messages", maxlen, topic_max);
+    // events: medium-frequency writes, moderate capacity
+    add("events", (maxlen / 2).max(10000), (topic_max / 2).max(1000));
+    // lifecycle: low-frequency critical events, small capacity
+    add("lifecycle", (maxlen / 20).max(1000), (topic_max / 4).max(500));
+    // runs: low-frequency large objects, very small capacity
+    add("runs", (maxlen / 40).max(500), (topic_max / 10).max(200));
+    // export: temporary buffer, moderate capacity
+    add("export", (maxlen / 4).max(5000), (topic_max / 4).max(500));
+    // memory: context storage, moderate capacity
+    add("memory", (maxlen / 10).max(2000), (topic_max / 2).max(1000));
+    m
+}
+
+#[derive(Debug)]
+pub struct MpState {
     pub stores: DashMap<String, Store>,
+    pub clients: ClientRegistry,
+    pub replicator: Option<Replicator>,
+    /// Endpoints actually bound, reported by ping/health
+    pub endpoints: RpcEndpoints,
 }
 
 impl MpState {
-    pub fn new(maxlen: usize, topic_max: usize) -> Self {
+    pub fn new(
+        store_configs: &BTreeMap<String, StoreConfig>,
+        clients: ClientRegistry,
+        replicator: Option<Replicator>,
+    ) -> Self {
         let stores = DashMap::new();
-        
-        // Bus-specific configurations for optimal memory usage
-        // messages: high-frequency read/write, needs full capacity
-        stores.insert("messages".to_string(), Store::new(maxlen, topic_max));
-        
-        // events: medium-frequency writes, moderate capacity
-        let events_maxlen = (maxlen / 2).max(10000);
-        let events_topic_max = (topic_max / 2).max(1000);
-        stores.insert("events".to_string(), Store::new(events_maxlen, events_topic_max));
-        
-        // lifecycle: low-frequency critical events, small capacity
-        let lifecycle_maxlen = (maxlen / 20).max(1000);
-        let lifecycle_topic_max = (topic_max / 4).max(500);
-        stores.insert("lifecycle".to_string(), Store::new(lifecycle_maxlen, lifecycle_topic_max));
-        
-        // runs: low-frequency large objects, very small capacity
-        let runs_maxlen = (maxlen / 40).max(500);
-        let runs_topic_max = (topic_max / 10).max(200);
-        stores.insert("runs".to_string(), Store::new(runs_maxlen, runs_topic_max));
-        
-        // export: temporary buffer, moderate capacity
-        let export_maxlen = (maxlen / 4).max(5000);
-        let export_topic_max = (topic_max / 4).max(500);
-        stores.insert("export".to_string(), Store::new(export_maxlen, export_topic_max));
-        
-        // memory: context storage, moderate capacity
-        let memory_maxlen = (maxlen / 10).max(2000);
-        let memory_topic_max = (topic_max / 2).max(1000);
-        stores.insert("memory".to_string(), Store::new(memory_maxlen, memory_topic_max));
-        
+        for (name, cfg) in store_configs {
+            stores.insert(name.clone(), Store::new(cfg));
+        }
         Self {
-            maxlen,
-            topic_max,
             stores,
+            clients,
+            replicator,
+            endpoints: RpcEndpoints::default(),
         }
     }
 
@@ -299,3 +363,51 @@ pub struct PubMsg {
     pub topic: Vec<u8>,
     pub body: Vec<u8>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    const CFG: StoreConfig = StoreConfig {
+        maxlen: 16,
+        topic_max: 16,
+        get_recent_max_limit: 16,
+        query_max_limit: 16,
+    };
+
+    /// Runs `f` on another thread and fails instead of hanging when it deadlocks
+    fn within_timeout<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(f());
+        });
+        rx.recv_timeout(Duration::from_secs(5)).expect("store call deadlocked")
+    }
+
+    #[test]
+    fn publish_to_new_topic_does_not_deadlock() {
+        let store = Arc::new(Store::new(&CFG));
+        let s = Arc::clone(&store);
+        within_timeout(move || {
+            s.publish("main", "t", serde_json::json!({"n": 1}), None);
+            s.publish("main", "t", serde_json::json!({"n": 2}), None);
+        });
+        let recent = store.get_recent("main", "t", 10);
+        assert_eq!(recent.iter().map(|e| e.seq).collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn replace_topic_does_not_deadlock() {
+        let store = Arc::new(Store::new(&CFG));
+        let s = Arc::clone(&store);
+        let events = within_timeout(move || {
+            s.publish("main", "t", serde_json::json!({"n": 0}), None);
+            s.replace_topic("main", "t", vec![serde_json::json!({"n": 1}), serde_json::json!({"n": 2})], None)
+        });
+        assert_eq!(events.len(), 2);
+        assert_eq!(store.get_recent("main", "t", 10).len(), 2);
+    }
+}
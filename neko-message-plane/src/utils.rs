@@ -10,6 +10,29 @@ pub fn now_ts() -> f64 {
         .unwrap_or(0.0)
 }
 
+/// Lowercase hex rendering for arbitrary bytes (e.g. ZMQ identities)
+pub fn hex_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+pub const TRACE_ID_MAX_LEN: usize = 128;
+
+/// Clamp a caller-supplied trace_id to TRACE_ID_MAX_LEN bytes (on a char boundary)
+pub fn cap_trace_id(s: &str) -> &str {
+    if s.len() <= TRACE_ID_MAX_LEN {
+        return s;
+    }
+    let mut end = TRACE_ID_MAX_LEN;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
 pub fn json_obj(v: &JsonValue) -> Option<&serde_json::Map<String, JsonValue>> {
     v.as_object()
 }
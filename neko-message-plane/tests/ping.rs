@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+const BIN: &str = env!("CARGO_BIN_EXE_neko-message-plane");
+
+struct Server {
+    child: Child,
+    dir: PathBuf,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn start_server(name: &str) -> Server {
+    let dir = std::env::temp_dir().join(format!("np-ping-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let child = Command::new(BIN)
+        .arg("--ipc-dir")
+        .arg(&dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn server");
+    Server { child, dir }
+}
+
+fn ping(endpoint: &str, timeout_ms: u64) -> Output {
+    Command::new(BIN)
+        .arg("--ping")
+        .arg(endpoint)
+        .arg("--ping-timeout-ms")
+        .arg(timeout_ms.to_string())
+        .output()
+        .expect("run ping")
+}
+
+#[test]
+fn ping_succeeds_against_running_server() {
+    let server = start_server("ok");
+    let endpoint = format!("ipc://{}/rpc.sock", server.dir.display());
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let out = loop {
+        let out = ping(&endpoint, 500);
+        if out.status.success() || Instant::now() > deadline {
+            break out;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("pong from"));
+}
+
+#[test]
+fn ping_fails_without_server() {
+    let dir = std::env::temp_dir().join(format!("np-ping-none-{}", std::process::id()));
+    let endpoint = format!("ipc://{}/rpc.sock", dir.display());
+
+    let t0 = Instant::now();
+    let out = ping(&endpoint, 200);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(t0.elapsed() < Duration::from_secs(5));
+}
@@ -1,5 +1,62 @@
 from __future__ import annotations
 
+import atexit
+
+from ._native import (
+    AsyncMessagePlaneClient,
+    AsyncSubscriber,
+    BadArgs,
+    BadStore,
+    BadVersion,
+    EmbeddedServer,
+    ExtType,
+    MessagePlaneClient,
+    MessagePlaneError,
+    Overloaded,
+    Plan,
+    ReadyTimeoutError,
+    Subscriber,
+    Timeout,
+    Unauthorized,
+    UnknownOp,
+    close_all,
+    native_version,
+    packb,
+    pool_stats,
+    start_embedded,
+    unpackb,
+)
+from .cursor import Batch, Cursor
 from .runtime import get_binary_path, run
 
-__all__ = ["get_binary_path", "run"]
+# Pooled sockets must be closed before the zmq context is torn down at exit
+atexit.register(close_all)
+
+__all__ = [
+    "AsyncMessagePlaneClient",
+    "AsyncSubscriber",
+    "BadArgs",
+    "BadStore",
+    "BadVersion",
+    "Batch",
+    "Cursor",
+    "EmbeddedServer",
+    "ExtType",
+    "MessagePlaneClient",
+    "MessagePlaneError",
+    "Overloaded",
+    "Plan",
+    "ReadyTimeoutError",
+    "Subscriber",
+    "Timeout",
+    "Unauthorized",
+    "UnknownOp",
+    "close_all",
+    "get_binary_path",
+    "native_version",
+    "packb",
+    "pool_stats",
+    "run",
+    "start_embedded",
+    "unpackb",
+]
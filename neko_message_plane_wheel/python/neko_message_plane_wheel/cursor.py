@@ -0,0 +1,101 @@
+from __future__ import annotations
+
+import json
+import os
+from pathlib import Path
+from typing import Any
+
+
+class Batch:
+    """Events returned by Cursor.fetch(). Used as a context manager, the cursor file is
+    only advanced when the block exits without an exception; otherwise (or if the process
+    dies first) the same events are delivered again by the next fetch."""
+
+    def __init__(self, cursor: Cursor, events: list[dict[str, Any]]):
+        self._cursor = cursor
+        self.events = events
+        self.high_water = max((ev["seq"] for ev in events), default=cursor.position)
+
+    def __enter__(self) -> list[dict[str, Any]]:
+        return self.events
+
+    def __exit__(self, exc_type, exc, tb) -> bool:
+        if exc_type is None:
+            self.commit()
+        return False
+
+    def __iter__(self):
+        return iter(self.events)
+
+    def __len__(self) -> int:
+        return len(self.events)
+
+    def commit(self) -> None:
+        """Persist the highest seq of this batch; committing twice is harmless."""
+        self._cursor.commit(self.high_water)
+
+
+class Cursor:
+    """Catch-up position (last processed seq) for one store/topic, persisted to `path`.
+
+    Delivery is at-least-once: fetch() always starts after the last committed seq, so a
+    crash between fetch and commit re-delivers events instead of skipping them. Not safe
+    to share between threads or processes.
+    """
+
+    def __init__(self, client, path: str | os.PathLike, store: str = "messages", topic: str = "all"):
+        self.client = client
+        self.path = Path(path)
+        self.store = store
+        self.topic = topic
+        self._position = self._load()
+
+    @property
+    def position(self) -> int:
+        """Last committed seq (0 when nothing was committed yet)."""
+        return self._position
+
+    def fetch(self, limit: int = 200) -> Batch:
+        """Events after the committed position, oldest first, at most `limit`."""
+        events = self.client.get_since(self.store, self.topic, after_seq=self._position, limit=limit)
+        return Batch(self, events)
+
+    def commit(self, seq: int) -> None:
+        """Atomically record `seq` as processed; never moves the position backwards."""
+        if seq <= self._position:
+            return
+        self._save(seq)
+        self._position = seq
+
+    def _load(self) -> int:
+        try:
+            raw = self.path.read_text(encoding="utf-8")
+        except FileNotFoundError:
+            return 0
+        state = json.loads(raw)
+        if (state.get("store"), state.get("topic")) != (self.store, self.topic):
+            raise ValueError(
+                f"{self.path} tracks {state.get('store')}/{state.get('topic')}, not {self.store}/{self.topic}"
+            )
+        return int(state["after_seq"])
+
+    def _save(self, seq: int) -> None:
+        # Write a temp file next to the target and rename it over, so readers only ever
+        # see the old or the new position.
+        self.path.parent.mkdir(parents=True, exist_ok=True)
+        tmp = self.path.with_name(f".{self.path.name}.{os.getpid()}.tmp")
+        data = json.dumps({"store": self.store, "topic": self.topic, "after_seq": seq})
+        with open(tmp, "w", encoding="utf-8") as f:
+            f.write(data)
+            f.flush()
+            os.fsync(f.fileno())
+        os.replace(tmp, self.path)
+        if os.name == "posix":
+            fd = os.open(self.path.parent, os.O_RDONLY)
+            try:
+                os.fsync(fd)
+            finally:
+                os.close(fd)
+
+    def __repr__(self) -> str:
+        return f"Cursor(path={str(self.path)!r}, store={self.store!r}, topic={self.topic!r}, position={self._position})"
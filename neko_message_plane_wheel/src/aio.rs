@@ -0,0 +1,360 @@
+use pyo3::exceptions::{PyRuntimeError, PyStopAsyncIteration};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::client::{default_rpc_endpoint, exchange, into_result, CallError, Request};
+use crate::convert::mp_to_py;
+use crate::subscriber::{default_pub_endpoint, Recv, SubSocket};
+
+/// How often the subscriber thread re-checks whether its waiter is still interested
+const WAITER_CHECK_MS: i64 = 100;
+
+/// Events read for a waiter that was cancelled before its future could be resolved
+type Leftovers = Arc<Mutex<VecDeque<PyObject>>>;
+
+/// Scheduled onto the event loop thread to resolve a future. A future that is already done
+/// (cancelled) is left alone; a subscriber event meant for it goes back to `leftovers`.
+#[pyclass]
+struct Resolve {
+    leftovers: Option<Leftovers>,
+}
+
+#[pymethods]
+impl Resolve {
+    fn __call__(&self, fut: &Bound<'_, PyAny>, value: PyObject, exc: Option<PyObject>) -> PyResult<()> {
+        if fut.call_method0("done")?.is_truthy()? {
+            if let (Some(left), None) = (&self.leftovers, &exc) {
+                left.lock().unwrap_or_else(|e| e.into_inner()).push_back(value);
+            }
+            return Ok(());
+        }
+        match exc {
+            Some(e) => fut.call_method1("set_exception", (e,))?,
+            None => fut.call_method1("set_result", (value,))?,
+        };
+        Ok(())
+    }
+}
+
+/// Hand an outcome from a worker thread to the future's event loop
+fn complete(
+    py: Python<'_>,
+    event_loop: &PyObject,
+    fut: &PyObject,
+    outcome: PyResult<PyObject>,
+    leftovers: Option<&Leftovers>,
+) {
+    let (value, exc) = match outcome {
+        Ok(v) => (v, None),
+        Err(e) => (py.None(), Some(e.into_value(py).into_any())),
+    };
+    let resolve = Resolve {
+        leftovers: leftovers.cloned(),
+    };
+    // The loop may already be closed; there is nobody left to deliver to
+    let _ = event_loop.call_method1(py, "call_soon_threadsafe", (resolve, fut, value, exc));
+}
+
+fn future_done(py: Python<'_>, fut: &PyObject) -> bool {
+    fut.call_method0(py, "done")
+        .and_then(|d| d.is_truthy(py))
+        .unwrap_or(true)
+}
+
+/// Future plus the loop it belongs to, created on the calling (loop) thread
+fn new_future(py: Python<'_>) -> PyResult<(PyObject, PyObject)> {
+    let event_loop = py.import_bound("asyncio")?.call_method0("get_running_loop")?;
+    let fut = event_loop.call_method0("create_future")?;
+    Ok((event_loop.unbind(), fut.unbind()))
+}
+
+fn timeout_error(py: Python<'_>) -> PyErr {
+    match py
+        .import_bound("asyncio")
+        .and_then(|m| m.getattr("TimeoutError"))
+        .and_then(|t| t.call1(("request timed out",)))
+    {
+        Ok(e) => PyErr::from_value_bound(e),
+        Err(e) => e,
+    }
+}
+
+/// Done-callback that flags the worker when the awaiting task cancels its future
+#[pyclass]
+struct CancelOnDone(Arc<AtomicBool>);
+
+#[pymethods]
+impl CancelOnDone {
+    fn __call__(&self, fut: &Bound<'_, PyAny>) -> PyResult<()> {
+        if fut.call_method0("cancelled")?.is_truthy()? {
+            self.0.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+/// asyncio client: every method returns an awaitable resolved from a background thread, so
+/// the event loop never blocks on zmq. Uses the same socket pool as MessagePlaneClient.
+#[pyclass(module = "neko_message_plane_wheel._native", frozen)]
+pub struct AsyncMessagePlaneClient {
+    #[pyo3(get)]
+    endpoint: String,
+    #[pyo3(get)]
+    pub_endpoint: String,
+    #[pyo3(get)]
+    timeout_ms: i32,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl AsyncMessagePlaneClient {
+    /// Each in-flight call gets its own short-lived thread. Cancelling the awaiting task sets
+    /// a flag the thread checks while waiting for the reply; the half-finished socket is then
+    /// closed rather than returned to the pool.
+    fn submit(&self, py: Python<'_>, req: Request, timeout_ms: Option<i32>) -> PyResult<PyObject> {
+        let (event_loop, fut) = new_future(py)?;
+        let cancel = Arc::new(AtomicBool::new(false));
+        fut.call_method1(py, "add_done_callback", (CancelOnDone(Arc::clone(&cancel)),))?;
+
+        let endpoint = self.endpoint.clone();
+        let timeout_ms = timeout_ms.unwrap_or(self.timeout_ms);
+        let in_flight = Arc::clone(&self.in_flight);
+        let fut_ret = fut.clone_ref(py);
+        in_flight.fetch_add(1, Ordering::SeqCst);
+        let spawned = std::thread::Builder::new()
+            .name("mp-async-call".into())
+            .spawn(move || {
+                let resp = exchange(&endpoint, timeout_ms, req.op, req.args, Some(&cancel));
+                Python::with_gil(|py| {
+                    let outcome = match resp {
+                        Ok(r) => into_result(py, r).and_then(|res| req.reply.to_py(py, &res)),
+                        Err(CallError::Timeout) => Err(timeout_error(py)),
+                        Err(e) => Err(e.into_py_err(py)),
+                    };
+                    complete(py, &event_loop, &fut, outcome, None);
+                });
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            });
+        if let Err(e) = spawned {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            return Err(PyRuntimeError::new_err(format!("cannot start call thread: {}", e)));
+        }
+        Ok(fut_ret)
+    }
+}
+
+#[pymethods]
+impl AsyncMessagePlaneClient {
+    #[new]
+    #[pyo3(signature = (rpc_endpoint=None, pub_endpoint=None, timeout_ms=5000))]
+    fn new(rpc_endpoint: Option<String>, pub_endpoint: Option<String>, timeout_ms: i32) -> Self {
+        Self {
+            endpoint: rpc_endpoint.unwrap_or_else(default_rpc_endpoint),
+            pub_endpoint: pub_endpoint.unwrap_or_else(default_pub_endpoint),
+            timeout_ms,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    #[pyo3(signature = (timeout_ms=None))]
+    fn ping(&self, py: Python<'_>, timeout_ms: Option<i32>) -> PyResult<PyObject> {
+        self.submit(py, Request::ping(), timeout_ms)
+    }
+
+    #[pyo3(signature = (store, topic, payload, timeout_ms=None))]
+    fn publish(
+        &self,
+        py: Python<'_>,
+        store: &str,
+        topic: &str,
+        payload: &Bound<'_, PyDict>,
+        timeout_ms: Option<i32>,
+    ) -> PyResult<PyObject> {
+        self.submit(py, Request::publish(store, topic, payload)?, timeout_ms)
+    }
+
+    #[pyo3(signature = (store="messages", topic="all", limit=200, light=false, timeout_ms=None))]
+    fn get_recent(
+        &self,
+        py: Python<'_>,
+        store: &str,
+        topic: &str,
+        limit: u64,
+        light: bool,
+        timeout_ms: Option<i32>,
+    ) -> PyResult<PyObject> {
+        self.submit(py, Request::get_recent(store, topic, limit, light), timeout_ms)
+    }
+
+    #[pyo3(signature = (store="messages", topic="all", after_seq=0, limit=200, timeout_ms=None))]
+    fn get_since(
+        &self,
+        py: Python<'_>,
+        store: &str,
+        topic: &str,
+        after_seq: u64,
+        limit: u64,
+        timeout_ms: Option<i32>,
+    ) -> PyResult<PyObject> {
+        self.submit(py, Request::get_since(store, topic, after_seq, limit), timeout_ms)
+    }
+
+    #[pyo3(signature = (store="messages", timeout_ms=None, **filters))]
+    fn query(
+        &self,
+        py: Python<'_>,
+        store: &str,
+        timeout_ms: Option<i32>,
+        filters: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        self.submit(py, Request::query(store, filters)?, timeout_ms)
+    }
+
+    #[pyo3(signature = (store, plan, light=false, timeout_ms=None))]
+    fn replay(
+        &self,
+        py: Python<'_>,
+        store: &str,
+        plan: &Bound<'_, PyAny>,
+        light: bool,
+        timeout_ms: Option<i32>,
+    ) -> PyResult<PyObject> {
+        self.submit(py, Request::replay(store, plan, light)?, timeout_ms)
+    }
+
+    /// Async iterator over the PUB stream (same event dicts as Subscriber)
+    #[pyo3(signature = (prefixes=None))]
+    fn subscribe(&self, prefixes: Option<Vec<String>>) -> PyResult<AsyncSubscriber> {
+        AsyncSubscriber::open(self.pub_endpoint.clone(), prefixes)
+    }
+
+    /// Calls still waiting for a reply (including cancelled ones that are winding down)
+    #[getter]
+    fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AsyncMessagePlaneClient(rpc_endpoint={:?}, pub_endpoint={:?}, timeout_ms={})",
+            self.endpoint, self.pub_endpoint, self.timeout_ms
+        )
+    }
+}
+
+struct Waiter {
+    event_loop: PyObject,
+    fut: PyObject,
+}
+
+/// Serve waiters one at a time: wait for the next event (or close) and resolve the
+/// waiter's future with it. A waiter whose future was cancelled is skipped; an event that
+/// raced with the cancellation comes back through `leftovers` and goes to the next waiter.
+fn pump(sock: Arc<SubSocket>, waiters: mpsc::Receiver<Waiter>, leftovers: Leftovers) {
+    loop {
+        let w = match waiters.recv_timeout(Duration::from_millis(WAITER_CHECK_MS as u64)) {
+            Ok(w) => w,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let got = loop {
+            let (done, pending) = Python::with_gil(|py| {
+                let pending = leftovers.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+                (future_done(py, &w.fut), pending)
+            });
+            if let Some(ev) = pending {
+                if done {
+                    leftovers.lock().unwrap_or_else(|e| e.into_inner()).push_front(ev);
+                    break None;
+                }
+                break Some(Ok(ev));
+            }
+            if done {
+                break None;
+            }
+            match sock.recv_slice(WAITER_CHECK_MS) {
+                Ok(Recv::Idle) => continue,
+                Ok(Recv::Event(v)) => break Some(Python::with_gil(|py| mp_to_py(py, &v))),
+                Ok(Recv::Closed) => break Some(Err(PyStopAsyncIteration::new_err(()))),
+                Err(e) => break Some(Err(PyRuntimeError::new_err(format!("subscriber recv failed: {}", e)))),
+            }
+        };
+        if let Some(outcome) = got {
+            Python::with_gil(|py| complete(py, &w.event_loop, &w.fut, outcome, Some(&leftovers)));
+        }
+    }
+}
+
+/// `async for ev in client.subscribe([...])`; close() ends the iteration
+#[pyclass(module = "neko_message_plane_wheel._native", frozen)]
+pub struct AsyncSubscriber {
+    #[pyo3(get)]
+    endpoint: String,
+    sock: Arc<SubSocket>,
+    waiters: Mutex<Option<mpsc::Sender<Waiter>>>,
+    leftovers: Leftovers,
+}
+
+impl AsyncSubscriber {
+    fn open(endpoint: String, prefixes: Option<Vec<String>>) -> PyResult<Self> {
+        let sock = Arc::new(SubSocket::open(&endpoint, prefixes)?);
+        Ok(Self {
+            endpoint,
+            sock,
+            waiters: Mutex::new(None),
+            leftovers: Default::default(),
+        })
+    }
+}
+
+#[pymethods]
+impl AsyncSubscriber {
+    fn __aiter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __anext__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        if self.sock.is_closed() {
+            return Err(PyStopAsyncIteration::new_err(()));
+        }
+        let (event_loop, fut) = new_future(py)?;
+        let waiter = Waiter {
+            event_loop,
+            fut: fut.clone_ref(py),
+        };
+        let mut tx = self.waiters.lock().unwrap_or_else(|e| e.into_inner());
+        if tx.is_none() {
+            let (new_tx, rx) = mpsc::channel();
+            let sock = Arc::clone(&self.sock);
+            let leftovers = Arc::clone(&self.leftovers);
+            std::thread::Builder::new()
+                .name("mp-async-sub".into())
+                .spawn(move || pump(sock, rx, leftovers))
+                .map_err(|e| PyRuntimeError::new_err(format!("cannot start subscriber thread: {}", e)))?;
+            *tx = Some(new_tx);
+        }
+        tx.as_ref()
+            .expect("sender initialized above")
+            .send(waiter)
+            .map_err(|_| PyStopAsyncIteration::new_err(()))?;
+        Ok(fut)
+    }
+
+    /// End iteration: pending and future `__anext__` calls raise StopAsyncIteration
+    fn close(&self, py: Python<'_>) {
+        py.allow_threads(|| {
+            self.sock.close();
+            // Dropping the sender lets the pump thread exit once it has drained its queue
+            self.waiters.lock().unwrap_or_else(|e| e.into_inner()).take();
+        });
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.sock.is_closed()
+    }
+}
@@ -0,0 +1,599 @@
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use rmpv::Value as MpValue;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::convert::{mp_get, mp_to_py, py_to_mp};
+use crate::errors::{error_from_envelope, ready_timeout, rpc_error};
+use crate::plan::Plan;
+use crate::pool;
+
+pub const DEFAULT_RPC_ENDPOINT: &str = "tcp://127.0.0.1:38865";
+
+/// While waiting for a reply, how often a cancellable call checks its flag
+const CANCEL_CHECK_MS: i64 = 50;
+
+/// Most requests a pipeline keeps in flight on one socket
+const PIPELINE_WINDOW: usize = 256;
+
+/// Items per bus.publish_batch request (the server's limit); larger batches are split
+pub const PUBLISH_BATCH_CHUNK: usize = 1000;
+
+/// wait_until_ready: backoff cap between attempts and per-attempt ping timeout cap
+const READY_MAX_INTERVAL_MS: u64 = 1000;
+const READY_ATTEMPT_TIMEOUT_MS: u64 = 1000;
+
+/// Process-wide zmq context shared by every socket the wheel creates
+pub fn zmq_context() -> &'static zmq::Context {
+    static CTX: OnceLock<zmq::Context> = OnceLock::new();
+    CTX.get_or_init(zmq::Context::new)
+}
+
+pub fn default_rpc_endpoint() -> String {
+    std::env::var("NEKO_MESSAGE_PLANE_ZMQ_RPC_ENDPOINT")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| DEFAULT_RPC_ENDPOINT.to_string())
+}
+
+#[derive(Debug)]
+pub enum CallError {
+    Timeout,
+    Cancelled,
+    Transport(String),
+    Protocol(String),
+}
+
+impl CallError {
+    pub fn into_py_err(self, py: Python<'_>) -> PyErr {
+        match self {
+            CallError::Timeout => rpc_error(py, "TIMEOUT", "request timed out", None),
+            CallError::Cancelled => rpc_error(py, "CANCELLED", "request cancelled", None),
+            CallError::Transport(m) => rpc_error(py, "TRANSPORT", &m, None),
+            CallError::Protocol(m) => rpc_error(py, "PROTOCOL", &m, None),
+        }
+    }
+}
+
+/// One DEALER socket speaking the msgpack RPC envelope protocol (no Python types here)
+pub struct Conn {
+    sock: zmq::Socket,
+    next_id: u64,
+    timeout_ms: i32,
+}
+
+impl Conn {
+    pub fn connect(endpoint: &str, timeout_ms: i32) -> Result<Self, CallError> {
+        let setup = || -> zmq::Result<zmq::Socket> {
+            let sock = zmq_context().socket(zmq::DEALER)?;
+            sock.set_linger(0)?;
+            sock.set_rcvtimeo(timeout_ms)?;
+            sock.set_sndtimeo(timeout_ms)?;
+            sock.connect(endpoint)?;
+            Ok(sock)
+        };
+        let sock = setup().map_err(|e| CallError::Transport(format!("{}: {}", endpoint, e)))?;
+        Ok(Self {
+            sock,
+            next_id: 0,
+            timeout_ms,
+        })
+    }
+
+    pub fn set_timeout(&mut self, timeout_ms: i32) -> Result<(), CallError> {
+        self.sock
+            .set_rcvtimeo(timeout_ms)
+            .and_then(|_| self.sock.set_sndtimeo(timeout_ms))
+            .map_err(|e| CallError::Transport(e.to_string()))?;
+        self.timeout_ms = timeout_ms;
+        Ok(())
+    }
+
+    fn req_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("py-{}-{}", std::process::id(), self.next_id)
+    }
+
+    /// Encode one request envelope and send it; returns its req_id
+    fn send_request(&mut self, op: &str, args: MpValue) -> Result<String, CallError> {
+        let req_id = self.req_id();
+        let req = MpValue::Map(vec![
+            (MpValue::from("v"), MpValue::from(1)),
+            (MpValue::from("req_id"), MpValue::from(req_id.as_str())),
+            (MpValue::from("op"), MpValue::from(op)),
+            (MpValue::from("args"), args),
+        ]);
+        let mut raw = Vec::new();
+        rmpv::encode::write_value(&mut raw, &req).map_err(|e| CallError::Protocol(e.to_string()))?;
+        self.sock.send(raw, 0).map_err(|e| match e {
+            zmq::Error::EAGAIN => CallError::Timeout,
+            e => CallError::Transport(e.to_string()),
+        })?;
+        Ok(req_id)
+    }
+
+    /// Wait up to `wait_ms` (-1 = forever) for one response envelope; Ok(None) when nothing arrived
+    fn recv_response(&mut self, wait_ms: i64) -> Result<Option<MpValue>, CallError> {
+        let ready = self
+            .sock
+            .poll(zmq::POLLIN, wait_ms)
+            .map_err(|e| CallError::Transport(e.to_string()))?;
+        if ready == 0 {
+            return Ok(None);
+        }
+        let body = match self.sock.recv_bytes(zmq::DONTWAIT) {
+            Ok(b) => b,
+            Err(zmq::Error::EAGAIN) => return Ok(None),
+            Err(e) => return Err(CallError::Transport(e.to_string())),
+        };
+        rmpv::decode::read_value(&mut body.as_slice())
+            .map(Some)
+            .map_err(|e| CallError::Protocol(format!("invalid response: {}", e)))
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        (self.timeout_ms >= 0).then(|| Instant::now() + Duration::from_millis(self.timeout_ms as u64))
+    }
+
+    /// Send one request and return the full response envelope. With `cancel`, gives up
+    /// with `CallError::Cancelled` soon after the flag is set.
+    pub fn call(&mut self, op: &str, args: MpValue, cancel: Option<&AtomicBool>) -> Result<MpValue, CallError> {
+        let req_id = self.send_request(op, args)?;
+        let deadline = self.deadline();
+        // Replies to earlier requests that timed out may still arrive; skip them.
+        loop {
+            let mut wait = deadline.map_or(-1, |d| d.saturating_duration_since(Instant::now()).as_millis() as i64);
+            if cancel.is_some() {
+                wait = if wait < 0 { CANCEL_CHECK_MS } else { wait.min(CANCEL_CHECK_MS) };
+            }
+            match self.recv_response(wait)? {
+                Some(resp) if mp_get(&resp, "req_id").and_then(|v| v.as_str()) == Some(req_id.as_str()) => {
+                    return Ok(resp)
+                }
+                Some(_) => {}
+                None => {
+                    if cancel.is_some_and(|c| c.load(Ordering::SeqCst)) {
+                        return Err(CallError::Cancelled);
+                    }
+                    if deadline.is_some_and(|d| Instant::now() >= d) {
+                        return Err(CallError::Timeout);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Send many requests without waiting for each reply, keeping at most PIPELINE_WINDOW
+    /// outstanding (ROUTER drops replies to a peer whose queue is full). Responses are matched
+    /// by req_id and returned in input order; `None` marks a request that got no reply
+    /// before the timeout, which restarts whenever a reply arrives.
+    pub fn pipeline(&mut self, reqs: Vec<(String, MpValue)>) -> Result<Vec<Option<MpValue>>, CallError> {
+        let total = reqs.len();
+        let mut out: Vec<Option<MpValue>> = vec![None; total];
+        let mut pending: HashMap<String, usize> = HashMap::new();
+        let mut queued = reqs.into_iter().enumerate();
+        let mut received = 0;
+        let mut deadline = self.deadline();
+        while received < total {
+            while pending.len() < PIPELINE_WINDOW {
+                let Some((i, (op, args))) = queued.next() else { break };
+                let req_id = self.send_request(&op, args)?;
+                pending.insert(req_id, i);
+            }
+            let wait = deadline.map_or(-1, |d| d.saturating_duration_since(Instant::now()).as_millis() as i64);
+            match self.recv_response(wait)? {
+                Some(resp) => {
+                    let slot = mp_get(&resp, "req_id")
+                        .and_then(|v| v.as_str())
+                        .and_then(|id| pending.remove(id));
+                    if let Some(i) = slot {
+                        out[i] = Some(resp);
+                        received += 1;
+                        deadline = self.deadline();
+                    }
+                }
+                None if deadline.is_some_and(|d| Instant::now() >= d) => break,
+                None => {}
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Extract `result` from a response envelope, raising MessagePlaneError for `ok: false`
+pub fn into_result(py: Python<'_>, resp: MpValue) -> PyResult<MpValue> {
+    if mp_get(&resp, "ok").and_then(|v| v.as_bool()) != Some(true) {
+        return Err(error_from_envelope(py, &resp));
+    }
+    match resp {
+        MpValue::Map(entries) => Ok(entries
+            .into_iter()
+            .find(|(k, _)| k.as_str() == Some("result"))
+            .map(|(_, v)| v)
+            .unwrap_or(MpValue::Nil)),
+        _ => Ok(MpValue::Nil),
+    }
+}
+
+fn args_map(pairs: Vec<(&str, MpValue)>) -> MpValue {
+    MpValue::Map(pairs.into_iter().map(|(k, v)| (MpValue::from(k), v)).collect())
+}
+
+/// Which part of an op's `result` is handed back to Python
+#[derive(Clone, Copy)]
+pub enum Reply {
+    Whole,
+    Event,
+    Items,
+}
+
+impl Reply {
+    pub fn to_py(self, py: Python<'_>, result: &MpValue) -> PyResult<PyObject> {
+        match self {
+            Reply::Whole => mp_to_py(py, result),
+            Reply::Event => mp_to_py(py, mp_get(result, "event").unwrap_or(&MpValue::Nil)),
+            Reply::Items => match mp_get(result, "items") {
+                Some(items) => mp_to_py(py, items),
+                None => Ok(PyList::empty_bound(py).into_py(py)),
+            },
+        }
+    }
+}
+
+/// One RPC with its arguments already converted from Python; shared by the blocking and
+/// asyncio clients so both send exactly the same envelopes
+pub struct Request {
+    pub op: &'static str,
+    pub args: MpValue,
+    pub reply: Reply,
+}
+
+impl Request {
+    pub fn ping() -> Self {
+        Self {
+            op: "ping",
+            args: MpValue::Map(vec![]),
+            reply: Reply::Whole,
+        }
+    }
+
+    pub fn publish(store: &str, topic: &str, payload: &Bound<'_, PyDict>) -> PyResult<Self> {
+        Ok(Self {
+            op: "bus.publish",
+            args: args_map(vec![
+                ("store", MpValue::from(store)),
+                ("topic", MpValue::from(topic)),
+                ("payload", py_to_mp(payload.as_any())?),
+            ]),
+            reply: Reply::Event,
+        })
+    }
+
+    pub fn get_recent(store: &str, topic: &str, limit: u64, light: bool) -> Self {
+        Self {
+            op: "bus.get_recent",
+            args: args_map(vec![
+                ("store", MpValue::from(store)),
+                ("topic", MpValue::from(topic)),
+                ("limit", MpValue::from(limit)),
+                ("light", MpValue::from(light)),
+            ]),
+            reply: Reply::Items,
+        }
+    }
+
+    pub fn get_since(store: &str, topic: &str, after_seq: u64, limit: u64) -> Self {
+        Self {
+            op: "bus.get_since",
+            args: args_map(vec![
+                ("store", MpValue::from(store)),
+                ("topic", MpValue::from(topic)),
+                ("after_seq", MpValue::from(after_seq)),
+                ("limit", MpValue::from(limit)),
+            ]),
+            reply: Reply::Items,
+        }
+    }
+
+    pub fn query(store: &str, filters: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut args = vec![(MpValue::from("store"), MpValue::from(store))];
+        if let Some(f) = filters {
+            for (k, v) in f.iter() {
+                args.push((py_to_mp(&k)?, py_to_mp(&v)?));
+            }
+        }
+        Ok(Self {
+            op: "bus.query",
+            args: MpValue::Map(args),
+            reply: Reply::Items,
+        })
+    }
+
+    pub fn replay(store: &str, plan: &Bound<'_, PyAny>, light: bool) -> PyResult<Self> {
+        Ok(Self {
+            op: "bus.replay",
+            args: args_map(vec![
+                ("store", MpValue::from(store)),
+                ("plan", Plan::node_from_py(plan)?),
+                ("light", MpValue::from(light)),
+            ]),
+            reply: Reply::Items,
+        })
+    }
+}
+
+/// Socket I/O for one request on a pooled socket; must run without the GIL
+pub fn exchange(
+    endpoint: &str,
+    timeout_ms: i32,
+    op: &str,
+    args: MpValue,
+    cancel: Option<&AtomicBool>,
+) -> Result<MpValue, CallError> {
+    let mut lease = pool::checkout(endpoint, timeout_ms)?;
+    // A failed or cancelled DEALER may hold a half-finished exchange; dropping the lease closes it
+    let resp = lease.conn.call(op, args, cancel)?;
+    lease.release();
+    Ok(resp)
+}
+
+/// Pipelined requests on one pooled socket; the socket goes back to the pool only if every
+/// request got its reply
+fn exchange_pipeline(endpoint: &str, timeout_ms: i32, reqs: Vec<(String, MpValue)>) -> Result<Vec<Option<MpValue>>, CallError> {
+    let mut lease = pool::checkout(endpoint, timeout_ms)?;
+    let out = lease.conn.pipeline(reqs)?;
+    if out.iter().all(Option::is_some) {
+        lease.release();
+    }
+    Ok(out)
+}
+
+/// `result` of a response envelope, or the MessagePlaneError it carries (returned, not raised)
+fn result_or_error(py: Python<'_>, resp: Option<MpValue>) -> PyResult<PyObject> {
+    match resp {
+        Some(resp) => match into_result(py, resp) {
+            Ok(res) => mp_to_py(py, &res),
+            Err(e) => Ok(e.into_value(py).into_any()),
+        },
+        None => Ok(CallError::Timeout.into_py_err(py).into_value(py).into_any()),
+    }
+}
+
+fn pipeline_item(item: &Bound<'_, PyAny>) -> PyResult<(String, MpValue)> {
+    let (op, args) = if let Ok(d) = item.downcast::<PyDict>() {
+        (d.get_item("op")?, d.get_item("args")?)
+    } else if let Ok((op, args)) = item.extract::<(Bound<'_, PyAny>, Bound<'_, PyAny>)>() {
+        (Some(op), Some(args))
+    } else {
+        (None, None)
+    };
+    let op = match op {
+        Some(op) => op.extract::<String>()?,
+        None => return Err(PyTypeError::new_err("pipeline items must be (op, args) or {\"op\": ..., \"args\": ...}")),
+    };
+    let args = match args {
+        Some(a) if !a.is_none() => py_to_mp(&a)?,
+        _ => MpValue::Map(vec![]),
+    };
+    Ok((op, args))
+}
+
+/// Blocking client for the message plane RPC socket. Holds no socket of its own: each
+/// call borrows one from the process-wide pool, so instances are cheap and can be shared
+/// between threads.
+#[pyclass(module = "neko_message_plane_wheel._native", frozen)]
+pub struct MessagePlaneClient {
+    #[pyo3(get)]
+    endpoint: String,
+    #[pyo3(get)]
+    timeout_ms: i32,
+}
+
+impl MessagePlaneClient {
+    /// Arguments are converted before and the result after, with the GIL held; the pool
+    /// lock and the zmq send/recv happen inside allow_threads so a slow server never stalls
+    /// other Python threads.
+    pub fn call(&self, py: Python<'_>, op: &str, args: MpValue) -> PyResult<MpValue> {
+        let resp = py
+            .allow_threads(|| exchange(&self.endpoint, self.timeout_ms, op, args, None))
+            .map_err(|e| e.into_py_err(py))?;
+        into_result(py, resp)
+    }
+
+    /// Ping until the server answers `ok` or `total_timeout_ms` passes, doubling the pause
+    /// between attempts up to READY_MAX_INTERVAL_MS. Runs without the GIL; returns the
+    /// number of attempts, or the attempts plus the last failure.
+    fn poll_ready(&self, total_timeout_ms: u64, interval_ms: u64) -> Result<u32, (u32, Result<MpValue, CallError>)> {
+        let deadline = Instant::now() + Duration::from_millis(total_timeout_ms);
+        let mut pause = Duration::from_millis(interval_ms.max(1));
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let left = deadline.saturating_duration_since(Instant::now());
+            let attempt_ms = (left.as_millis() as u64).clamp(1, READY_ATTEMPT_TIMEOUT_MS) as i32;
+            let req = Request::ping();
+            let last = match exchange(&self.endpoint, attempt_ms, req.op, req.args, None) {
+                Ok(resp) if mp_get(&resp, "ok").and_then(|v| v.as_bool()) == Some(true) => return Ok(attempts),
+                other => other,
+            };
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Err((attempts, last));
+            }
+            std::thread::sleep(pause.min(left));
+            pause = (pause * 2).min(Duration::from_millis(READY_MAX_INTERVAL_MS));
+        }
+    }
+
+    fn run(&self, py: Python<'_>, req: Request) -> PyResult<PyObject> {
+        let res = self.call(py, req.op, req.args)?;
+        req.reply.to_py(py, &res)
+    }
+}
+
+#[pymethods]
+impl MessagePlaneClient {
+    #[new]
+    #[pyo3(signature = (rpc_endpoint=None, timeout_ms=5000))]
+    fn new(rpc_endpoint: Option<String>, timeout_ms: i32) -> Self {
+        Self {
+            endpoint: rpc_endpoint.unwrap_or_else(default_rpc_endpoint),
+            timeout_ms,
+        }
+    }
+
+    /// Round-trip a ping; returns the server's health result
+    #[pyo3(signature = (timeout_ms=None))]
+    fn ping(&self, py: Python<'_>, timeout_ms: Option<i32>) -> PyResult<PyObject> {
+        let req = Request::ping();
+        let resp = py
+            .allow_threads(|| exchange(&self.endpoint, timeout_ms.unwrap_or(self.timeout_ms), req.op, req.args, None))
+            .map_err(|e| e.into_py_err(py))?;
+        req.reply.to_py(py, &into_result(py, resp)?)
+    }
+
+    /// Round-trip a ping and return the latency in milliseconds
+    #[pyo3(signature = (timeout_ms=None))]
+    fn ping_with_latency(&self, py: Python<'_>, timeout_ms: Option<i32>) -> PyResult<f64> {
+        let req = Request::ping();
+        let (resp, elapsed) = py
+            .allow_threads(|| {
+                let started = Instant::now();
+                let resp = exchange(&self.endpoint, timeout_ms.unwrap_or(self.timeout_ms), req.op, req.args, None)?;
+                Ok((resp, started.elapsed()))
+            })
+            .map_err(|e: CallError| e.into_py_err(py))?;
+        into_result(py, resp)?;
+        Ok(elapsed.as_secs_f64() * 1000.0)
+    }
+
+    /// Block until the server answers a ping, retrying with exponential backoff starting at
+    /// `interval_ms`. Raises ReadyTimeoutError (a TimeoutError) carrying the last failure.
+    #[pyo3(signature = (total_timeout_ms=10000, interval_ms=50))]
+    fn wait_until_ready(&self, py: Python<'_>, total_timeout_ms: u64, interval_ms: u64) -> PyResult<u32> {
+        let started = Instant::now();
+        match py.allow_threads(|| self.poll_ready(total_timeout_ms, interval_ms)) {
+            Ok(attempts) => Ok(attempts),
+            Err((attempts, last)) => {
+                let last = match last {
+                    Ok(resp) => error_from_envelope(py, &resp),
+                    Err(e) => e.into_py_err(py),
+                };
+                Err(ready_timeout(py, started.elapsed().as_millis(), attempts, last))
+            }
+        }
+    }
+
+    /// Publish one event and return it as stored (with seq/ts/index)
+    fn publish(&self, py: Python<'_>, store: &str, topic: &str, payload: &Bound<'_, PyDict>) -> PyResult<PyObject> {
+        self.run(py, Request::publish(store, topic, payload)?)
+    }
+
+    #[pyo3(signature = (store="messages", topic="all", limit=200, light=false))]
+    fn get_recent(&self, py: Python<'_>, store: &str, topic: &str, limit: u64, light: bool) -> PyResult<PyObject> {
+        self.run(py, Request::get_recent(store, topic, limit, light))
+    }
+
+    /// Events with seq > after_seq in ascending seq order (bus.get_since); "all"/"*" spans topics
+    #[pyo3(signature = (store="messages", topic="all", after_seq=0, limit=200))]
+    fn get_since(&self, py: Python<'_>, store: &str, topic: &str, after_seq: u64, limit: u64) -> PyResult<PyObject> {
+        self.run(py, Request::get_since(store, topic, after_seq, limit))
+    }
+
+    /// bus.query; keyword arguments are passed through as filters (topic, plugin_id, source,
+    /// kind, type, priority_min, since_ts, until_ts, limit, light)
+    #[pyo3(signature = (store="messages", **filters))]
+    fn query(&self, py: Python<'_>, store: &str, filters: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.run(py, Request::query(store, filters)?)
+    }
+
+    /// bus.replay with a Plan (or an equivalent dict plan); returns the matching events
+    #[pyo3(signature = (store, plan, light=false))]
+    fn replay(&self, py: Python<'_>, store: &str, plan: &Bound<'_, PyAny>, light: bool) -> PyResult<PyObject> {
+        self.run(py, Request::replay(store, plan, light)?)
+    }
+
+    /// Publish many events with bus.publish_batch (split into chunks of 1000, pipelined).
+    /// Each item is `{"topic", "payload"[, "store", "trace_id"]}`; returns one entry per item
+    /// in order: the stored event dict, or a MessagePlaneError instance for a rejected item.
+    #[pyo3(signature = (items, store="messages"))]
+    fn publish_batch(&self, py: Python<'_>, items: Vec<Bound<'_, PyAny>>, store: &str) -> PyResult<Vec<PyObject>> {
+        let items = items.iter().map(py_to_mp).collect::<PyResult<Vec<_>>>()?;
+        let sizes: Vec<usize> = items.chunks(PUBLISH_BATCH_CHUNK).map(<[MpValue]>::len).collect();
+        let reqs = items
+            .chunks(PUBLISH_BATCH_CHUNK)
+            .map(|chunk| {
+                let args = args_map(vec![
+                    ("store", MpValue::from(store)),
+                    ("items", MpValue::Array(chunk.to_vec())),
+                ]);
+                ("bus.publish_batch".to_string(), args)
+            })
+            .collect();
+        let resps = py
+            .allow_threads(|| exchange_pipeline(&self.endpoint, self.timeout_ms, reqs))
+            .map_err(|e| e.into_py_err(py))?;
+
+        let mut out = Vec::with_capacity(items.len());
+        for (resp, n) in resps.into_iter().zip(sizes) {
+            let result = match resp {
+                Some(resp) => into_result(py, resp),
+                None => Err(CallError::Timeout.into_py_err(py)),
+            };
+            match result {
+                Ok(res) => {
+                    let entries = mp_get(&res, "items").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+                    for entry in entries {
+                        if mp_get(&entry, "ok").and_then(|v| v.as_bool()) == Some(true) {
+                            out.push(Reply::Event.to_py(py, &entry)?);
+                        } else {
+                            out.push(error_from_envelope(py, &entry).into_value(py).into_any());
+                        }
+                    }
+                }
+                // The whole chunk failed (e.g. timeout): every item in it gets the error
+                Err(e) => {
+                    let err = e.into_value(py).into_any();
+                    out.extend(std::iter::repeat_with(|| err.clone_ref(py)).take(n));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Send several raw requests back to back on one socket and collect the replies.
+    /// `requests` holds `(op, args)` tuples or `{"op", "args"}` dicts; returns each reply's
+    /// `result` in input order, or a MessagePlaneError instance for a failed request.
+    fn pipeline(&self, py: Python<'_>, requests: Vec<Bound<'_, PyAny>>) -> PyResult<Vec<PyObject>> {
+        let reqs = requests.iter().map(pipeline_item).collect::<PyResult<Vec<_>>>()?;
+        let resps = py
+            .allow_threads(|| exchange_pipeline(&self.endpoint, self.timeout_ms, reqs))
+            .map_err(|e| e.into_py_err(py))?;
+        resps.into_iter().map(|r| result_or_error(py, r)).collect()
+    }
+
+    /// Close the idle pooled sockets for this client's endpoint; the next call reconnects
+    fn close(&self, py: Python<'_>) {
+        py.allow_threads(|| pool::close_endpoint(&self.endpoint));
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MessagePlaneClient(rpc_endpoint={:?}, timeout_ms={})", self.endpoint, self.timeout_ms)
+    }
+}
+
+/// Close every pooled socket, e.g. from an atexit hook before interpreter shutdown
+#[pyfunction]
+pub fn close_all(py: Python<'_>) {
+    py.allow_threads(pool::close_all);
+}
+
+/// Idle pooled sockets per endpoint
+#[pyfunction]
+pub fn pool_stats(py: Python<'_>) -> HashMap<String, usize> {
+    py.allow_threads(pool::idle_counts)
+}
@@ -0,0 +1,201 @@
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
+use rmpv::Value as MpValue;
+
+/// Deeper structures are rejected instead of overflowing the native stack
+const MAX_DEPTH: usize = 512;
+
+/// msgpack extension value: an application-defined type code plus raw payload bytes
+#[pyclass(module = "neko_message_plane_wheel._native", frozen)]
+pub struct ExtType {
+    #[pyo3(get)]
+    code: i8,
+    data: Vec<u8>,
+}
+
+#[pymethods]
+impl ExtType {
+    #[new]
+    fn new(code: i8, data: Vec<u8>) -> Self {
+        Self { code, data }
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.data)
+    }
+
+    fn __eq__(&self, other: &Bound<'_, PyAny>) -> bool {
+        other
+            .downcast::<ExtType>()
+            .is_ok_and(|o| o.get().code == self.code && o.get().data == self.data)
+    }
+
+    fn __hash__(&self) -> u64 {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+        let mut h = DefaultHasher::new();
+        (self.code, &self.data).hash(&mut h);
+        h.finish()
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("ExtType(code={}, data={})", self.code, self.data(py).repr()?))
+    }
+}
+
+/// Convert a Python object into a msgpack value
+pub fn py_to_mp(obj: &Bound<'_, PyAny>) -> PyResult<MpValue> {
+    py_to_mp_depth(obj, 0)
+}
+
+fn py_to_mp_depth(obj: &Bound<'_, PyAny>, depth: usize) -> PyResult<MpValue> {
+    if depth > MAX_DEPTH {
+        return Err(PyValueError::new_err(format!(
+            "object nested deeper than {} levels (cyclic?)",
+            MAX_DEPTH
+        )));
+    }
+    if obj.is_none() {
+        return Ok(MpValue::Nil);
+    }
+    // bool is a subclass of int, so it has to be checked first
+    if let Ok(b) = obj.downcast::<PyBool>() {
+        return Ok(MpValue::Boolean(b.is_true()));
+    }
+    if obj.is_instance_of::<PyLong>() {
+        if let Ok(n) = obj.extract::<i64>() {
+            return Ok(MpValue::from(n));
+        }
+        if let Ok(n) = obj.extract::<u64>() {
+            return Ok(MpValue::from(n));
+        }
+        return Err(PyValueError::new_err("int out of range for msgpack (must fit in i64 or u64)"));
+    }
+    if let Ok(f) = obj.downcast::<PyFloat>() {
+        return Ok(MpValue::F64(f.value()));
+    }
+    if let Ok(s) = obj.downcast::<PyString>() {
+        return Ok(MpValue::from(s.to_str()?));
+    }
+    if let Ok(b) = obj.downcast::<PyBytes>() {
+        return Ok(MpValue::Binary(b.as_bytes().to_vec()));
+    }
+    if let Ok(b) = obj.downcast::<PyByteArray>() {
+        return Ok(MpValue::Binary(b.to_vec()));
+    }
+    if let Ok(d) = obj.downcast::<PyDict>() {
+        let mut out = Vec::with_capacity(d.len());
+        for (k, v) in d.iter() {
+            out.push((py_to_mp_depth(&k, depth + 1)?, py_to_mp_depth(&v, depth + 1)?));
+        }
+        return Ok(MpValue::Map(out));
+    }
+    if let Ok(l) = obj.downcast::<PyList>() {
+        return l
+            .iter()
+            .map(|x| py_to_mp_depth(&x, depth + 1))
+            .collect::<PyResult<Vec<_>>>()
+            .map(MpValue::Array);
+    }
+    if let Ok(t) = obj.downcast::<PyTuple>() {
+        return t
+            .iter()
+            .map(|x| py_to_mp_depth(&x, depth + 1))
+            .collect::<PyResult<Vec<_>>>()
+            .map(MpValue::Array);
+    }
+    if let Ok(e) = obj.downcast::<ExtType>() {
+        let e = e.get();
+        return Ok(MpValue::Ext(e.code, e.data.clone()));
+    }
+    Err(PyTypeError::new_err(format!(
+        "cannot convert {} to msgpack (supported: None, bool, int, float, str, bytes, bytearray, dict, list, tuple, ExtType)",
+        obj.get_type().name()?
+    )))
+}
+
+/// Convert a msgpack value into a Python object
+pub fn mp_to_py(py: Python<'_>, v: &MpValue) -> PyResult<PyObject> {
+    Ok(match v {
+        MpValue::Nil => py.None(),
+        MpValue::Boolean(b) => b.into_py(py),
+        MpValue::Integer(n) => match n.as_i64() {
+            Some(i) => i.into_py(py),
+            None => n.as_u64().unwrap_or(0).into_py(py),
+        },
+        MpValue::F32(f) => (*f as f64).into_py(py),
+        MpValue::F64(f) => f.into_py(py),
+        MpValue::String(s) => match s.as_str() {
+            Some(st) => st.into_py(py),
+            None => PyBytes::new_bound(py, s.as_bytes()).into_py(py),
+        },
+        MpValue::Binary(b) => PyBytes::new_bound(py, b).into_py(py),
+        MpValue::Array(items) => {
+            let list = PyList::empty_bound(py);
+            for it in items {
+                list.append(mp_to_py(py, it)?)?;
+            }
+            list.into_py(py)
+        }
+        MpValue::Map(entries) => {
+            let dict = PyDict::new_bound(py);
+            for (k, val) in entries {
+                dict.set_item(mp_key_to_py(py, k)?, mp_to_py(py, val)?)?;
+            }
+            dict.into_py(py)
+        }
+        MpValue::Ext(code, data) => ExtType {
+            code: *code,
+            data: data.clone(),
+        }
+        .into_py(py),
+    })
+}
+
+/// Map keys must be hashable, so arrays used as keys become tuples
+fn mp_key_to_py(py: Python<'_>, k: &MpValue) -> PyResult<PyObject> {
+    match k {
+        MpValue::Array(items) => {
+            let keys = items.iter().map(|it| mp_key_to_py(py, it)).collect::<PyResult<Vec<_>>>()?;
+            Ok(PyTuple::new_bound(py, keys).into_py(py))
+        }
+        MpValue::Map(_) => Err(PyTypeError::new_err("msgpack map used as a map key is not supported")),
+        _ => mp_to_py(py, k),
+    }
+}
+
+/// Look up a string key in a msgpack map
+pub fn mp_get<'a>(v: &'a MpValue, key: &str) -> Option<&'a MpValue> {
+    match v {
+        MpValue::Map(entries) => entries
+            .iter()
+            .find(|(k, _)| k.as_str() == Some(key))
+            .map(|(_, v)| v),
+        _ => None,
+    }
+}
+
+/// Serialize a Python object to msgpack bytes
+#[pyfunction]
+pub fn packb<'py>(py: Python<'py>, obj: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+    let v = py_to_mp(obj)?;
+    let mut out = Vec::new();
+    rmpv::encode::write_value(&mut out, &v).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(PyBytes::new_bound(py, &out))
+}
+
+/// Deserialize exactly one msgpack value from bytes
+#[pyfunction]
+pub fn unpackb(py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+    let mut rd = data;
+    let v = rmpv::decode::read_value(&mut rd)
+        .map_err(|e| PyValueError::new_err(format!("invalid msgpack data: {}", e)))?;
+    if !rd.is_empty() {
+        return Err(PyValueError::new_err(format!(
+            "{} bytes of extra data after the msgpack value",
+            rd.len()
+        )));
+    }
+    mp_to_py(py, &v)
+}
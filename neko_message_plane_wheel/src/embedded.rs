@@ -0,0 +1,169 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use clap::Parser;
+use neko_message_plane::config::Cli;
+use neko_message_plane::server::{self, Server};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyList, PyTuple};
+
+use crate::client::zmq_context;
+use crate::pool;
+
+/// Client-only switches that make no sense for an in-process server
+const CLIENT_ONLY_KEYS: &[&str] = &["ping", "ping_timeout_ms", "self_check", "self_check_timeout_ms"];
+
+/// Read by the request handlers from NEKO_MESSAGE_PLANE_* env vars, so they are
+/// process-wide and cannot differ per embedded instance
+const PROCESS_WIDE_KEYS: &[&str] = &[
+    "validate_mode",
+    "admin_token",
+    "topic_name_max_len",
+    "payload_max_bytes",
+    "validate_payload_bytes",
+];
+
+/// Turn a config dict into a Cli, defaulting to unique inproc endpoints and one worker
+fn cli_from_config(config: Option<&Bound<'_, PyDict>>) -> PyResult<Cli> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    let mut args = vec!["neko-message-plane".to_string()];
+    let mut flags: Vec<(String, bool)> = Vec::new();
+    let mut given = Vec::new();
+    if let Some(config) = config {
+        for (k, v) in config.iter() {
+            let key: String = k.extract()?;
+            if CLIENT_ONLY_KEYS.contains(&key.as_str()) {
+                return Err(PyValueError::new_err(format!("{:?} is not supported by start_embedded", key)));
+            }
+            if PROCESS_WIDE_KEYS.contains(&key.as_str()) {
+                return Err(PyValueError::new_err(format!(
+                    "{:?} is process-wide; set NEKO_MESSAGE_PLANE_{} instead",
+                    key,
+                    key.to_uppercase()
+                )));
+            }
+            given.push(key.clone());
+            if v.is_none() {
+                continue;
+            }
+            if let Ok(b) = v.downcast::<PyBool>() {
+                flags.push((key, b.is_true()));
+                continue;
+            }
+            let flag = format!("--{}", key.replace('_', "-"));
+            let values: Vec<Bound<'_, PyAny>> = if v.is_instance_of::<PyList>() || v.is_instance_of::<PyTuple>() {
+                v.iter()?.collect::<PyResult<_>>()?
+            } else {
+                vec![v]
+            };
+            for value in values {
+                args.push(flag.clone());
+                args.push(value.str()?.to_string());
+            }
+        }
+    }
+
+    if !given.iter().any(|k| k == "ipc_dir") {
+        let id = format!("neko-mp-embedded-{}-{}", std::process::id(), NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        for (key, name) in [("rpc_endpoint", "rpc"), ("ingest_endpoint", "ingest"), ("pub_endpoint", "pub")] {
+            if !given.iter().any(|k| k == key) {
+                args.push(format!("--{}", key.replace('_', "-")));
+                args.push(format!("inproc://{}-{}", id, name));
+            }
+        }
+    }
+    if !given.iter().any(|k| k == "workers") {
+        args.extend(["--workers".to_string(), "1".to_string()]);
+    }
+
+    let mut cli = Cli::try_parse_from(&args).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    for (key, value) in flags {
+        match key.as_str() {
+            "pub_enabled" => cli.pub_enabled = value,
+            "replicate_publishes" => cli.replicate_publishes = value,
+            _ => return Err(PyValueError::new_err(format!("unknown boolean option {:?}", key))),
+        }
+    }
+    // Endpoints not given are still at their tcp defaults; put them under ipc_dir
+    cli.apply_ipc_dir();
+    Ok(cli)
+}
+
+/// Message plane running inside this process, sharing the wheel's zmq context
+#[pyclass(module = "neko_message_plane_wheel._native", frozen)]
+pub struct EmbeddedServer {
+    server: Mutex<Option<Server>>,
+    #[pyo3(get)]
+    rpc_endpoint: String,
+    #[pyo3(get)]
+    ingest_endpoint: String,
+    /// None when the PUB socket is disabled
+    #[pyo3(get)]
+    pub_endpoint: Option<String>,
+}
+
+#[pymethods]
+impl EmbeddedServer {
+    /// False after stop() or if the server loop died on its own
+    #[getter]
+    fn running(&self) -> bool {
+        self.server
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(Server::is_running)
+    }
+
+    /// Graceful shutdown: stop the loops, join threads, drop pooled sockets to it. Idempotent.
+    fn stop(&self, py: Python<'_>) {
+        let server = self.server.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(mut server) = server {
+            py.allow_threads(|| server.stop());
+            pool::close_endpoint(&self.rpc_endpoint);
+        }
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc: &Bound<'_, PyAny>,
+        _tb: &Bound<'_, PyAny>,
+    ) -> bool {
+        self.stop(py);
+        false
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "EmbeddedServer(rpc_endpoint={:?}, running={})",
+            self.rpc_endpoint,
+            if self.running() { "True" } else { "False" }
+        )
+    }
+}
+
+/// Start a message plane inside this process. `config` takes the server's CLI options
+/// as snake_case keys (e.g. {"workers": 2, "store_config": ["runs=100:10"]}); endpoints
+/// default to fresh inproc:// addresses and tcp "*" ports are resolved after binding.
+#[pyfunction]
+#[pyo3(signature = (config=None))]
+pub fn start_embedded(py: Python<'_>, config: Option<&Bound<'_, PyDict>>) -> PyResult<EmbeddedServer> {
+    let cli = cli_from_config(config)?;
+    let server = py
+        .allow_threads(|| server::start(zmq_context(), &cli))
+        .map_err(PyRuntimeError::new_err)?;
+    let eps = server.endpoints().clone();
+    Ok(EmbeddedServer {
+        rpc_endpoint: eps.rpc,
+        ingest_endpoint: eps.ingest,
+        pub_endpoint: server.pub_enabled().then_some(eps.pub_),
+        server: Mutex::new(Some(server)),
+    })
+}
@@ -0,0 +1,79 @@
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyTimeoutError};
+use pyo3::prelude::*;
+use rmpv::Value as MpValue;
+
+use crate::convert::{mp_get, mp_to_py};
+
+create_exception!(
+    _native,
+    MessagePlaneError,
+    PyException,
+    "Error returned by the message plane; carries .code, .message and .details"
+);
+
+create_exception!(_native, BadArgs, MessagePlaneError, "BAD_ARGS: request arguments failed validation");
+create_exception!(_native, BadStore, MessagePlaneError, "BAD_STORE: the store does not exist");
+create_exception!(_native, BadVersion, MessagePlaneError, "BAD_VERSION: protocol version missing or unsupported");
+create_exception!(_native, UnknownOp, MessagePlaneError, "UNKNOWN_OP: the server does not implement the op");
+create_exception!(_native, Overloaded, MessagePlaneError, "OVERLOADED: the server shed the request; retry later");
+create_exception!(_native, Timeout, MessagePlaneError, "TIMEOUT: no response within the client timeout");
+create_exception!(_native, Unauthorized, MessagePlaneError, "UNAUTHORIZED: admin op without a matching admin_token");
+
+create_exception!(
+    _native,
+    ReadyTimeoutError,
+    PyTimeoutError,
+    "wait_until_ready() gave up; .last_error (also __cause__) is the last ping failure"
+);
+
+/// Build a ReadyTimeoutError chained to the last ping failure
+pub fn ready_timeout(py: Python<'_>, waited_ms: u128, attempts: u32, last: PyErr) -> PyErr {
+    let err = ReadyTimeoutError::new_err(format!(
+        "message plane not ready after {} ms ({} attempts): {}",
+        waited_ms,
+        attempts,
+        last.value_bound(py)
+    ));
+    let _ = err.value_bound(py).setattr("last_error", last.value_bound(py));
+    err.set_cause(py, Some(last));
+    err
+}
+
+/// Build the MessagePlaneError subclass for `code` (the base class for unknown codes)
+/// with code/message/details attributes set
+pub fn rpc_error(py: Python<'_>, code: &str, message: &str, details: Option<&MpValue>) -> PyErr {
+    let text = format!("{}: {}", code, message);
+    let err = match code {
+        "BAD_ARGS" => BadArgs::new_err(text),
+        "BAD_STORE" => BadStore::new_err(text),
+        "BAD_VERSION" => BadVersion::new_err(text),
+        "UNKNOWN_OP" => UnknownOp::new_err(text),
+        "OVERLOADED" => Overloaded::new_err(text),
+        "TIMEOUT" => Timeout::new_err(text),
+        "UNAUTHORIZED" => Unauthorized::new_err(text),
+        _ => MessagePlaneError::new_err(text),
+    };
+    let value = err.value_bound(py);
+    let details = match details {
+        Some(d) => mp_to_py(py, d).unwrap_or_else(|_| py.None()),
+        None => py.None(),
+    };
+    // Setting attributes on a fresh exception instance cannot fail in practice
+    let _ = value.setattr("code", code);
+    let _ = value.setattr("message", message);
+    let _ = value.setattr("details", details);
+    err
+}
+
+/// Map an `ok: false` response envelope to a MessagePlaneError
+pub fn error_from_envelope(py: Python<'_>, resp: &MpValue) -> PyErr {
+    let err = mp_get(resp, "error");
+    let field = |name: &str| err.and_then(|e| mp_get(e, name)).and_then(|v| v.as_str());
+    rpc_error(
+        py,
+        field("code").unwrap_or("ERROR"),
+        field("message").unwrap_or(""),
+        err.and_then(|e| mp_get(e, "details")).filter(|d| !d.is_nil()),
+    )
+}
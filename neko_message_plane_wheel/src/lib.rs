@@ -1,12 +1,42 @@
 use pyo3::prelude::*;
 
+mod aio;
+mod client;
+mod convert;
+mod embedded;
+mod errors;
+mod plan;
+mod pool;
+mod subscriber;
+
 #[pyfunction]
 fn native_version() -> &'static str {
     env!("CARGO_PKG_VERSION")
 }
 
 #[pymodule]
-fn _native(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+fn _native(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(native_version, m)?)?;
+    m.add_function(wrap_pyfunction!(convert::packb, m)?)?;
+    m.add_function(wrap_pyfunction!(convert::unpackb, m)?)?;
+    m.add_class::<convert::ExtType>()?;
+    m.add_function(wrap_pyfunction!(client::close_all, m)?)?;
+    m.add_function(wrap_pyfunction!(client::pool_stats, m)?)?;
+    m.add_class::<client::MessagePlaneClient>()?;
+    m.add_class::<subscriber::Subscriber>()?;
+    m.add_class::<aio::AsyncMessagePlaneClient>()?;
+    m.add_class::<aio::AsyncSubscriber>()?;
+    m.add_class::<plan::Plan>()?;
+    m.add_function(wrap_pyfunction!(embedded::start_embedded, m)?)?;
+    m.add_class::<embedded::EmbeddedServer>()?;
+    m.add("MessagePlaneError", m.py().get_type_bound::<errors::MessagePlaneError>())?;
+    m.add("BadArgs", m.py().get_type_bound::<errors::BadArgs>())?;
+    m.add("BadStore", m.py().get_type_bound::<errors::BadStore>())?;
+    m.add("BadVersion", m.py().get_type_bound::<errors::BadVersion>())?;
+    m.add("UnknownOp", m.py().get_type_bound::<errors::UnknownOp>())?;
+    m.add("Overloaded", m.py().get_type_bound::<errors::Overloaded>())?;
+    m.add("Timeout", m.py().get_type_bound::<errors::Timeout>())?;
+    m.add("Unauthorized", m.py().get_type_bound::<errors::Unauthorized>())?;
+    m.add("ReadyTimeoutError", m.py().get_type_bound::<errors::ReadyTimeoutError>())?;
     Ok(())
 }
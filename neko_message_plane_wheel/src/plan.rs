@@ -0,0 +1,167 @@
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rmpv::Value as MpValue;
+
+use crate::convert::{mp_to_py, py_to_mp};
+
+fn map(pairs: Vec<(&str, MpValue)>) -> MpValue {
+    MpValue::Map(pairs.into_iter().map(|(k, v)| (MpValue::from(k), v)).collect())
+}
+
+fn kwargs_map(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Vec<(MpValue, MpValue)>> {
+    let mut out = Vec::new();
+    if let Some(kw) = kwargs {
+        for (k, v) in kw.iter() {
+            out.push((py_to_mp(&k)?, py_to_mp(&v)?));
+        }
+    }
+    Ok(out)
+}
+
+/// Immutable bus.replay plan tree; every builder method returns a new Plan
+#[pyclass(module = "neko_message_plane_wheel._native", frozen)]
+#[derive(Clone)]
+pub struct Plan {
+    pub node: MpValue,
+}
+
+impl Plan {
+    fn unary(&self, op: &str, params: MpValue) -> Plan {
+        Plan {
+            node: map(vec![
+                ("kind", MpValue::from("unary")),
+                ("op", MpValue::from(op)),
+                ("params", params),
+                ("child", self.node.clone()),
+            ]),
+        }
+    }
+
+    fn binary(&self, op: &str, other: &Plan) -> Plan {
+        Plan {
+            node: map(vec![
+                ("kind", MpValue::from("binary")),
+                ("op", MpValue::from(op)),
+                ("left", self.node.clone()),
+                ("right", other.node.clone()),
+            ]),
+        }
+    }
+
+    /// Accept either a Plan or a hand-written dict plan
+    pub fn node_from_py(obj: &Bound<'_, PyAny>) -> PyResult<MpValue> {
+        if let Ok(p) = obj.downcast::<Plan>() {
+            return Ok(p.get().node.clone());
+        }
+        if obj.is_instance_of::<PyDict>() {
+            return py_to_mp(obj);
+        }
+        Err(PyTypeError::new_err("plan must be a Plan or a dict"))
+    }
+}
+
+#[pymethods]
+impl Plan {
+    /// Leaf node reading the most recent events of a topic; extra keyword arguments
+    /// (plugin_id, source, kind, type, priority_min, since_ts) filter the read
+    #[staticmethod]
+    #[pyo3(signature = (topic="all", limit=200, **filters))]
+    fn get(topic: &str, limit: u64, filters: Option<&Bound<'_, PyDict>>) -> PyResult<Plan> {
+        let mut p = vec![
+            (MpValue::from("topic"), MpValue::from(topic)),
+            (MpValue::from("max_count"), MpValue::from(limit)),
+        ];
+        p.extend(kwargs_map(filters)?);
+        Ok(Plan {
+            node: map(vec![
+                ("kind", MpValue::from("get")),
+                ("params", map(vec![("params", MpValue::Map(p))])),
+            ]),
+        })
+    }
+
+    /// Wrap an existing dict plan
+    #[staticmethod]
+    fn from_dict(plan: &Bound<'_, PyDict>) -> PyResult<Plan> {
+        Ok(Plan {
+            node: py_to_mp(plan.as_any())?,
+        })
+    }
+
+    /// Server-side filter op (plugin_id, source, kind, type, priority_min, since_ts,
+    /// until_ts, *_re regexes, content_re, strict)
+    #[pyo3(signature = (**criteria))]
+    fn filter(&self, criteria: Option<&Bound<'_, PyDict>>) -> PyResult<Plan> {
+        Ok(self.unary("filter", MpValue::Map(kwargs_map(criteria)?)))
+    }
+
+    fn where_eq(&self, field: &str, value: &Bound<'_, PyAny>) -> PyResult<Plan> {
+        let params = map(vec![("field", MpValue::from(field)), ("value", py_to_mp(value)?)]);
+        Ok(self.unary("where_eq", params))
+    }
+
+    fn where_in(&self, field: &str, values: &Bound<'_, PyAny>) -> PyResult<Plan> {
+        let params = map(vec![("field", MpValue::from(field)), ("values", py_to_mp(values)?)]);
+        Ok(self.unary("where_in", params))
+    }
+
+    fn where_contains(&self, field: &str, value: &str) -> Plan {
+        let params = map(vec![("field", MpValue::from(field)), ("value", MpValue::from(value))]);
+        self.unary("where_contains", params)
+    }
+
+    #[pyo3(signature = (field, pattern, strict=true))]
+    fn where_regex(&self, field: &str, pattern: &str, strict: bool) -> Plan {
+        let params = map(vec![
+            ("field", MpValue::from(field)),
+            ("pattern", MpValue::from(pattern)),
+            ("strict", MpValue::from(strict)),
+        ]);
+        self.unary("where_regex", params)
+    }
+
+    /// Sort by one field or a list of fields (server default: timestamp/created_at/time)
+    #[pyo3(signature = (by=None, reverse=false))]
+    fn sort(&self, by: Option<&Bound<'_, PyAny>>, reverse: bool) -> PyResult<Plan> {
+        let mut params = Vec::new();
+        if let Some(b) = by {
+            params.push((MpValue::from("by"), py_to_mp(b)?));
+        }
+        params.push((MpValue::from("reverse"), MpValue::from(reverse)));
+        Ok(self.unary("sort", MpValue::Map(params)))
+    }
+
+    fn limit(&self, n: i64) -> Plan {
+        self.unary("limit", map(vec![("n", MpValue::from(n))]))
+    }
+
+    fn merge(&self, other: &Plan) -> Plan {
+        self.binary("merge", other)
+    }
+
+    fn intersection(&self, other: &Plan) -> Plan {
+        self.binary("intersection", other)
+    }
+
+    fn difference(&self, other: &Plan) -> Plan {
+        self.binary("difference", other)
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        mp_to_py(py, &self.node)
+    }
+
+    /// Structural equality with another Plan or a dict plan (key order does not matter)
+    fn __eq__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> PyResult<bool> {
+        let other = match Plan::node_from_py(other) {
+            Ok(n) => mp_to_py(py, &n)?,
+            Err(_) => return Ok(false),
+        };
+        self.to_dict(py)?.bind(py).eq(other)
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("Plan({})", self.to_dict(py)?.bind(py).repr()?))
+    }
+}
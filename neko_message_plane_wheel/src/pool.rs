@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use crate::client::{CallError, Conn};
+
+/// Idle sockets kept per endpoint; extra ones are closed when returned
+pub const MAX_IDLE_PER_ENDPOINT: usize = 8;
+
+#[derive(Default)]
+struct Pool {
+    /// Bumped by close_all() so sockets checked out before it are not put back
+    generation: u64,
+    idle: HashMap<String, Vec<Conn>>,
+}
+
+fn pool() -> MutexGuard<'static, Pool> {
+    static POOL: OnceLock<Mutex<Pool>> = OnceLock::new();
+    POOL.get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// A socket borrowed from the process-wide pool. Only one thread uses it at a time;
+/// call `release` to hand it back, dropping it instead closes the socket.
+pub struct Lease {
+    endpoint: String,
+    generation: u64,
+    pub conn: Conn,
+}
+
+impl Lease {
+    pub fn release(self) {
+        let mut p = pool();
+        if p.generation != self.generation {
+            return;
+        }
+        let idle = p.idle.entry(self.endpoint).or_default();
+        if idle.len() < MAX_IDLE_PER_ENDPOINT {
+            idle.push(self.conn);
+        }
+    }
+}
+
+/// Take an idle socket for `endpoint` or connect a new one. Never call with the GIL held.
+pub fn checkout(endpoint: &str, timeout_ms: i32) -> Result<Lease, CallError> {
+    let (reused, generation) = {
+        let mut p = pool();
+        let conn = p.idle.get_mut(endpoint).and_then(Vec::pop);
+        (conn, p.generation)
+    };
+    let conn = match reused {
+        Some(mut c) => {
+            c.set_timeout(timeout_ms)?;
+            c
+        }
+        None => Conn::connect(endpoint, timeout_ms)?,
+    };
+    Ok(Lease {
+        endpoint: endpoint.to_string(),
+        generation,
+        conn,
+    })
+}
+
+/// Close idle sockets for one endpoint
+pub fn close_endpoint(endpoint: &str) {
+    pool().idle.remove(endpoint);
+}
+
+/// Close every idle socket; sockets in use are closed when their call finishes
+pub fn close_all() {
+    let mut p = pool();
+    p.generation += 1;
+    p.idle.clear();
+}
+
+/// Number of idle pooled sockets per endpoint
+pub fn idle_counts() -> HashMap<String, usize> {
+    pool().idle.iter().map(|(k, v)| (k.clone(), v.len())).collect()
+}
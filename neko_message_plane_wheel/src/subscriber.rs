@@ -0,0 +1,187 @@
+use pyo3::exceptions::{PyRuntimeError, PyStopIteration};
+use pyo3::prelude::*;
+use rmpv::Value as MpValue;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::client::zmq_context;
+use crate::convert::mp_to_py;
+
+pub const DEFAULT_PUB_ENDPOINT: &str = "tcp://127.0.0.1:38866";
+
+/// How long one blocking slice may wait before returning to Python to check signals/close()
+const POLL_SLICE_MS: i64 = 100;
+
+pub enum Recv {
+    Event(MpValue),
+    Idle,
+    Closed,
+}
+
+pub fn default_pub_endpoint() -> String {
+    std::env::var("NEKO_MESSAGE_PLANE_ZMQ_PUB_ENDPOINT")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| DEFAULT_PUB_ENDPOINT.to_string())
+}
+
+/// SUB socket plus close flag, shared by the blocking and asyncio subscribers
+pub struct SubSocket {
+    sock: Mutex<Option<zmq::Socket>>,
+    closed: AtomicBool,
+}
+
+impl SubSocket {
+    pub fn open(endpoint: &str, prefixes: Option<Vec<String>>) -> PyResult<Self> {
+        let prefixes = match prefixes {
+            Some(p) if !p.is_empty() => p,
+            _ => vec![String::new()],
+        };
+        let setup = || -> zmq::Result<zmq::Socket> {
+            let sock = zmq_context().socket(zmq::SUB)?;
+            sock.set_linger(0)?;
+            for p in &prefixes {
+                sock.set_subscribe(p.as_bytes())?;
+            }
+            sock.connect(endpoint)?;
+            Ok(sock)
+        };
+        let sock = setup().map_err(|e| PyRuntimeError::new_err(format!("{}: {}", endpoint, e)))?;
+        Ok(Self {
+            sock: Mutex::new(Some(sock)),
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    /// Wait up to `slice_ms` for one decodable event. Runs without the GIL.
+    pub fn recv_slice(&self, slice_ms: i64) -> Result<Recv, zmq::Error> {
+        if self.is_closed() {
+            return Ok(Recv::Closed);
+        }
+        let guard = self.sock.lock().unwrap_or_else(|e| e.into_inner());
+        let sock = match guard.as_ref() {
+            Some(s) => s,
+            None => return Ok(Recv::Closed),
+        };
+        let deadline = Instant::now() + Duration::from_millis(slice_ms.max(0) as u64);
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now()).as_millis() as i64;
+            if sock.poll(zmq::POLLIN, left)? == 0 {
+                return Ok(Recv::Idle);
+            }
+            let frames = sock.recv_multipart(zmq::DONTWAIT)?;
+            // [topic, body]; anything that is not a msgpack map (e.g. legacy JSON bodies) is skipped
+            let body = match frames.last() {
+                Some(b) if frames.len() >= 2 => b,
+                _ => continue,
+            };
+            if let Ok(v @ MpValue::Map(_)) = rmpv::decode::read_value(&mut body.as_slice()) {
+                return Ok(Recv::Event(v));
+            }
+            if Instant::now() >= deadline {
+                return Ok(Recv::Idle);
+            }
+        }
+    }
+
+    /// Mark closed and drop the socket; waits for an in-progress slice, so call without the GIL
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        *self.sock.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+}
+
+/// Iterator over the PUB stream; yields {seq, ts, store, topic, index, payload} dicts
+#[pyclass(module = "neko_message_plane_wheel._native")]
+pub struct Subscriber {
+    #[pyo3(get)]
+    endpoint: String,
+    sock: SubSocket,
+}
+
+impl Subscriber {
+    /// Receive with an optional overall timeout; None means block until an event or close()
+    fn recv_inner(&self, py: Python<'_>, timeout_ms: Option<i64>) -> PyResult<Option<MpValue>> {
+        let deadline = timeout_ms.map(|t| Instant::now() + Duration::from_millis(t.max(0) as u64));
+        loop {
+            let slice = match deadline {
+                Some(d) => (d.saturating_duration_since(Instant::now()).as_millis() as i64).min(POLL_SLICE_MS),
+                None => POLL_SLICE_MS,
+            };
+            let res = py
+                .allow_threads(|| self.sock.recv_slice(slice))
+                .map_err(|e| PyRuntimeError::new_err(format!("subscriber recv failed: {}", e)))?;
+            match res {
+                Recv::Event(v) => return Ok(Some(v)),
+                Recv::Closed => return Err(PyStopIteration::new_err(())),
+                Recv::Idle => {}
+            }
+            py.check_signals()?;
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl Subscriber {
+    #[new]
+    #[pyo3(signature = (pub_endpoint=None, prefixes=None))]
+    fn new(pub_endpoint: Option<String>, prefixes: Option<Vec<String>>) -> PyResult<Self> {
+        let endpoint = pub_endpoint.unwrap_or_else(default_pub_endpoint);
+        let sock = SubSocket::open(&endpoint, prefixes)?;
+        Ok(Self { endpoint, sock })
+    }
+
+    /// Next event, or None if nothing arrives within timeout_ms (None = wait forever).
+    /// Raises StopIteration once the subscriber is closed.
+    #[pyo3(signature = (timeout_ms=None))]
+    fn recv(&self, py: Python<'_>, timeout_ms: Option<i64>) -> PyResult<Option<PyObject>> {
+        match self.recv_inner(py, timeout_ms)? {
+            Some(v) => Ok(Some(mp_to_py(py, &v)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        match self.recv_inner(py, None)? {
+            Some(v) => mp_to_py(py, &v),
+            None => Err(PyStopIteration::new_err(())),
+        }
+    }
+
+    /// Stop iteration and close the socket; safe to call from another thread
+    fn close(&self, py: Python<'_>) {
+        py.allow_threads(|| self.sock.close());
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.sock.is_closed()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc: &Bound<'_, PyAny>,
+        _tb: &Bound<'_, PyAny>,
+    ) -> bool {
+        self.close(py);
+        false
+    }
+}
@@ -0,0 +1,152 @@
+from __future__ import annotations
+
+import os
+import socket
+import subprocess
+import threading
+import time
+from dataclasses import dataclass
+from pathlib import Path
+
+import pytest
+
+from neko_message_plane_wheel import (
+    AsyncMessagePlaneClient,
+    MessagePlaneClient,
+    MessagePlaneError,
+    get_binary_path,
+    start_embedded,
+)
+
+_REPO = Path(__file__).resolve().parents[2]
+
+
+def _server_binary() -> str:
+    # Prefer an explicit override, then a local build of the sibling crate, then the bundled binary.
+    override = os.getenv("NEKO_MESSAGE_PLANE_RUST_BIN")
+    if override:
+        return override
+    for profile in ("release", "debug"):
+        p = _REPO / "neko-message-plane" / "target" / profile / "neko-message-plane"
+        if p.is_file():
+            return str(p)
+    return get_binary_path()
+
+
+@dataclass
+class MessagePlaneServer:
+    proc: subprocess.Popen
+    ipc_dir: Path
+
+    @property
+    def rpc_endpoint(self) -> str:
+        return f"ipc://{self.ipc_dir}/rpc.sock"
+
+    @property
+    def pub_endpoint(self) -> str:
+        return f"ipc://{self.ipc_dir}/pub.sock"
+
+    @property
+    def ingest_endpoint(self) -> str:
+        return f"ipc://{self.ipc_dir}/ingest.sock"
+
+    def client(self, timeout_ms: int = 2000) -> MessagePlaneClient:
+        return MessagePlaneClient(self.rpc_endpoint, timeout_ms)
+
+    def async_client(self, timeout_ms: int = 2000) -> AsyncMessagePlaneClient:
+        return AsyncMessagePlaneClient(self.rpc_endpoint, self.pub_endpoint, timeout_ms)
+
+
+def start_server(ipc_dir: Path, *extra_args: str) -> MessagePlaneServer:
+    # One worker: the server then polls every 1ms instead of 100ms, so sequential calls
+    # (and the batch benchmark baseline) are not dominated by the poll interval.
+    proc = subprocess.Popen(
+        [_server_binary(), "--ipc-dir", str(ipc_dir), "--workers", "1", *extra_args],
+        stdout=subprocess.DEVNULL,
+        stderr=subprocess.DEVNULL,
+    )
+    return MessagePlaneServer(proc=proc, ipc_dir=ipc_dir)
+
+
+def wait_ready(server: MessagePlaneServer, timeout: float = 10.0) -> None:
+    client = server.client(timeout_ms=200)
+    deadline = time.monotonic() + timeout
+    while True:
+        try:
+            client.ping()
+            return
+        except MessagePlaneError:
+            if time.monotonic() > deadline or server.proc.poll() is not None:
+                raise
+            time.sleep(0.05)
+
+
+def stop_server(server: MessagePlaneServer) -> None:
+    server.proc.terminate()
+    try:
+        server.proc.wait(timeout=5)
+    except subprocess.TimeoutExpired:
+        server.proc.kill()
+        server.proc.wait()
+
+
+@pytest.fixture
+def message_plane(tmp_path):
+    server = start_server(tmp_path / "mp")
+    try:
+        wait_ready(server)
+        yield server
+    finally:
+        stop_server(server)
+
+
+@pytest.fixture
+def client(message_plane):
+    c = message_plane.client()
+    yield c
+    c.close()
+
+
+@pytest.fixture
+def embedded_plane():
+    # In-process server on inproc:// endpoints; no binary or socket files needed
+    server = start_embedded()
+    try:
+        yield server
+    finally:
+        server.stop()
+
+
+@pytest.fixture
+def embedded_client(embedded_plane):
+    c = MessagePlaneClient(embedded_plane.rpc_endpoint, 2000)
+    yield c
+    c.close()
+
+
+@pytest.fixture
+def slow_server():
+    # Accepts TCP connections but never completes the zmq handshake, so every call blocks
+    # until the client's timeout.
+    srv = socket.socket()
+    srv.bind(("127.0.0.1", 0))
+    srv.listen()
+    conns = []
+    stop = threading.Event()
+
+    def accept():
+        srv.settimeout(0.05)
+        while not stop.is_set():
+            try:
+                conns.append(srv.accept()[0])
+            except OSError:
+                pass
+
+    t = threading.Thread(target=accept, daemon=True)
+    t.start()
+    yield f"tcp://127.0.0.1:{srv.getsockname()[1]}"
+    stop.set()
+    t.join()
+    for c in conns:
+        c.close()
+    srv.close()
@@ -0,0 +1,129 @@
+from __future__ import annotations
+
+import asyncio
+import time
+
+import pytest
+
+from neko_message_plane_wheel import AsyncMessagePlaneClient, MessagePlaneError, Plan, pool_stats
+
+
+async def _wait_idle(client, timeout=2.0):
+    deadline = time.monotonic() + timeout
+    while client.in_flight:
+        assert time.monotonic() < deadline, "call thread did not finish"
+        await asyncio.sleep(0.02)
+
+
+@pytest.mark.asyncio
+async def test_calls_return_awaitables(message_plane):
+    c = message_plane.async_client()
+    assert (await c.ping())["ok"] is True
+
+    events = await asyncio.gather(*(c.publish("messages", "aio", {"i": i}) for i in range(10)))
+    assert sorted(ev["payload"]["i"] for ev in events) == list(range(10))
+
+    items = await c.get_recent("messages", "aio", limit=50)
+    assert len(items) == 10
+    assert len(await c.query("messages", topic="aio", limit=3)) == 3
+    replayed = await c.replay("messages", Plan.get(topic="aio").where_eq("i", 4))
+    assert [it["payload"] for it in replayed] == [{"i": 4}]
+    await _wait_idle(c)
+
+
+@pytest.mark.asyncio
+async def test_server_errors_raise_message_plane_error(message_plane):
+    c = message_plane.async_client()
+    with pytest.raises(MessagePlaneError) as ei:
+        await c.get_recent("no-such-store")
+    assert ei.value.code == "BAD_STORE"
+
+
+@pytest.mark.asyncio
+async def test_per_call_timeout_raises_asyncio_timeout(slow_server):
+    c = AsyncMessagePlaneClient(slow_server, timeout_ms=10_000)
+    started = time.monotonic()
+    with pytest.raises(asyncio.TimeoutError):
+        await c.ping(timeout_ms=200)
+    assert time.monotonic() - started < 2
+
+
+@pytest.mark.asyncio
+async def test_cancellation_releases_request_state(slow_server):
+    c = AsyncMessagePlaneClient(slow_server, timeout_ms=30_000)
+    task = asyncio.ensure_future(c.ping())
+    await asyncio.sleep(0.1)
+    assert c.in_flight == 1
+    task.cancel()
+    with pytest.raises(asyncio.CancelledError):
+        await task
+    # The worker notices the cancellation long before the 30s timeout and closes its socket
+    await _wait_idle(c)
+    assert slow_server not in pool_stats()
+
+
+@pytest.mark.asyncio
+async def test_event_loop_keeps_running_during_calls(slow_server):
+    c = AsyncMessagePlaneClient(slow_server, timeout_ms=300)
+    ticks = 0
+
+    async def tick():
+        nonlocal ticks
+        while True:
+            ticks += 1
+            await asyncio.sleep(0.01)
+
+    ticker = asyncio.ensure_future(tick())
+    with pytest.raises(asyncio.TimeoutError):
+        await c.ping()
+    ticker.cancel()
+    assert ticks > 10
+
+
+async def _publish_until_received(client, sub, topic, payload, timeout=5.0):
+    # PUB drops messages until the subscription has propagated, so keep publishing.
+    deadline = time.monotonic() + timeout
+    while time.monotonic() < deadline:
+        await client.publish("messages", topic, payload)
+        try:
+            ev = await asyncio.wait_for(sub.__anext__(), 0.1)
+        except asyncio.TimeoutError:
+            continue
+        if ev["topic"] == topic and ev["payload"] == payload:
+            return ev
+    raise AssertionError("event not received on the PUB stream")
+
+
+@pytest.mark.asyncio
+async def test_subscribe_async_iteration(message_plane):
+    c = message_plane.async_client()
+    sub = c.subscribe(["aio.sub"])
+    await _publish_until_received(c, sub, "aio.sub.ready", {"ready": True})
+
+    for i in range(3):
+        await c.publish("messages", "aio.sub.x", {"i": i})
+        await c.publish("messages", "ignored", {"i": i})
+    got = []
+    async for ev in sub:
+        if ev["topic"] == "aio.sub.x":
+            got.append(ev["payload"]["i"])
+        if len(got) == 3:
+            sub.close()
+    assert got == [0, 1, 2]
+    assert sub.closed
+    with pytest.raises(StopAsyncIteration):
+        await sub.__anext__()
+
+
+@pytest.mark.asyncio
+async def test_cancelled_anext_does_not_lose_events(message_plane):
+    c = message_plane.async_client()
+    sub = c.subscribe(["aio.cancel"])
+    await _publish_until_received(c, sub, "aio.cancel.ready", {"ready": True})
+
+    with pytest.raises(asyncio.TimeoutError):
+        await asyncio.wait_for(sub.__anext__(), 0.2)
+    await c.publish("messages", "aio.cancel.after", {"n": 1})
+    ev = await asyncio.wait_for(sub.__anext__(), 2)
+    assert ev["payload"] == {"n": 1}
+    sub.close()
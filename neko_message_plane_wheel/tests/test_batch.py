@@ -0,0 +1,85 @@
+from __future__ import annotations
+
+import time
+
+from neko_message_plane_wheel import MessagePlaneError
+
+
+def test_publish_batch_returns_events_in_order(client):
+    items = [{"topic": "batch", "payload": {"i": i}} for i in range(5)]
+    results = client.publish_batch(items)
+    assert [r["payload"]["i"] for r in results] == list(range(5))
+    seqs = [r["seq"] for r in results]
+    assert seqs == sorted(seqs)
+    assert len(client.get_recent("messages", "batch", limit=50)) == 5
+
+
+def test_publish_batch_surfaces_errors_per_item(client):
+    results = client.publish_batch(
+        [
+            {"topic": "ok", "payload": {"n": 1}},
+            {"topic": "", "payload": {"n": 2}},
+            {"topic": "ok", "payload": {"n": 3}, "store": "no-such-store"},
+            {"topic": "ok", "payload": {"n": 4}},
+        ]
+    )
+    assert results[0]["payload"] == {"n": 1}
+    assert isinstance(results[1], MessagePlaneError) and results[1].code == "BAD_ARGS"
+    assert isinstance(results[2], MessagePlaneError) and results[2].code == "BAD_STORE"
+    assert results[3]["payload"] == {"n": 4}
+
+
+def test_publish_batch_splits_large_batches(client):
+    results = client.publish_batch([{"topic": "big", "payload": {"i": i}} for i in range(2500)])
+    assert len(results) == 2500
+    assert all(not isinstance(r, MessagePlaneError) for r in results)
+    assert [r["payload"]["i"] for r in results[995:1005]] == list(range(995, 1005))
+
+
+def test_pipeline_matches_replies_to_requests(client):
+    results = client.pipeline(
+        [
+            ("bus.publish", {"store": "messages", "topic": "pipe", "payload": {"k": 1}}),
+            {"op": "ping"},
+            ("bus.get_recent", {"store": "nope"}),
+            {"op": "no.such.op", "args": {}},
+            ("bus.get_recent", {"store": "messages", "topic": "pipe"}),
+        ]
+    )
+    assert results[0]["event"]["payload"] == {"k": 1}
+    assert results[1]["ok"] is True
+    assert isinstance(results[2], MessagePlaneError) and results[2].code == "BAD_STORE"
+    assert isinstance(results[3], MessagePlaneError) and results[3].code == "UNKNOWN_OP"
+    assert [it["payload"] for it in results[4]["items"]] == [{"k": 1}]
+
+
+def test_pipeline_more_requests_than_window(client):
+    n = 1500
+    results = client.pipeline([("bus.publish", {"topic": "win", "payload": {"i": i}}) for i in range(n)])
+    assert [r["event"]["payload"]["i"] for r in results] == list(range(n))
+
+
+def test_benchmark_batch_and_pipeline_vs_sequential(client):
+    # Documents the speedup; the assertions are deliberately loose so slow CI hosts pass.
+    n = 2000
+
+    started = time.perf_counter()
+    for i in range(n):
+        client.publish("messages", "bench.seq", {"i": i})
+    sequential = time.perf_counter() - started
+
+    started = time.perf_counter()
+    client.publish_batch([{"topic": "bench.batch", "payload": {"i": i}} for i in range(n)])
+    batched = time.perf_counter() - started
+
+    started = time.perf_counter()
+    client.pipeline([("bus.publish", {"topic": "bench.pipe", "payload": {"i": i}}) for i in range(n)])
+    pipelined = time.perf_counter() - started
+
+    print(
+        f"\n{n} publishes: sequential {n / sequential:,.0f}/s, "
+        f"publish_batch {n / batched:,.0f}/s ({sequential / batched:.1f}x), "
+        f"pipeline {n / pipelined:,.0f}/s ({sequential / pipelined:.1f}x)"
+    )
+    assert batched < sequential
+    assert pipelined < sequential
@@ -0,0 +1,89 @@
+from __future__ import annotations
+
+import threading
+
+import pytest
+
+from neko_message_plane_wheel import MessagePlaneClient, MessagePlaneError
+
+
+def test_ping(client):
+    res = client.ping()
+    assert res["ok"] is True
+
+
+def test_publish_then_get_recent(client):
+    ev = client.publish("messages", "chat", {"text": "hi", "n": 1, "nested": {"a": [1, 2.5, None]}})
+    assert ev["topic"] == "chat"
+    assert ev["payload"] == {"text": "hi", "n": 1, "nested": {"a": [1, 2.5, None]}}
+
+    client.publish("messages", "chat", {"text": "again"})
+    items = client.get_recent("messages", "chat", limit=10)
+    assert [it["payload"]["text"] for it in items] == ["hi", "again"]
+    assert items[0]["seq"] < items[1]["seq"]
+
+    light = client.get_recent("messages", "chat", limit=10, light=True)
+    assert all("payload" not in it for it in light)
+
+
+def test_query_passes_filters(client):
+    client.publish("events", "t", {"plugin_id": "a", "priority": 1})
+    client.publish("events", "t", {"plugin_id": "b", "priority": 5})
+
+    items = client.query("events", topic="t", plugin_id="b")
+    assert [it["payload"]["plugin_id"] for it in items] == ["b"]
+
+    items = client.query("events", topic="t", priority_min=2)
+    assert [it["payload"]["priority"] for it in items] == [5]
+
+
+def test_server_errors_raise_message_plane_error(client):
+    with pytest.raises(MessagePlaneError) as ei:
+        client.get_recent("no-such-store", "t")
+    assert ei.value.code == "BAD_STORE"
+    assert ei.value.message == "invalid store"
+
+
+def test_timeout_without_server(tmp_path):
+    c = MessagePlaneClient(f"ipc://{tmp_path}/missing.sock", 100)
+    with pytest.raises(MessagePlaneError) as ei:
+        c.ping()
+    assert ei.value.code == "TIMEOUT"
+
+
+def test_gil_released_during_blocking_call(slow_server, client):
+    counter = [0]
+    stop = threading.Event()
+
+    def spin():
+        while not stop.is_set():
+            counter[0] += 1
+
+    t = threading.Thread(target=spin)
+    t.start()
+    try:
+        slow = MessagePlaneClient(slow_server, 300)
+        before = counter[0]
+        with pytest.raises(MessagePlaneError) as ei:
+            slow.ping()
+        assert ei.value.code == "TIMEOUT"
+        assert counter[0] - before > 1000
+    finally:
+        stop.set()
+        t.join()
+
+    # Another client keeps working while one is stuck on the slow server
+    outcome = []
+
+    def blocked_call():
+        try:
+            slow.ping()
+        except MessagePlaneError as e:
+            outcome.append(e.code)
+
+    t = threading.Thread(target=blocked_call)
+    t.start()
+    assert client.ping()["ok"] is True
+    assert t.is_alive()
+    t.join()
+    assert outcome == ["TIMEOUT"]
@@ -0,0 +1,87 @@
+from __future__ import annotations
+
+import random
+
+import pytest
+
+from neko_message_plane_wheel import ExtType, packb, unpackb
+
+
+def _scalar(rng: random.Random):
+    return rng.choice(
+        [
+            None,
+            True,
+            False,
+            rng.randint(-(2**63), 2**63 - 1),
+            rng.randint(2**63, 2**64 - 1),
+            rng.randint(-40, 40),
+            rng.uniform(-1e9, 1e9),
+            "".join(rng.choice("abcxyzé猫 ") for _ in range(rng.randint(0, 8))),
+            bytes(rng.getrandbits(8) for _ in range(rng.randint(0, 8))),
+            ExtType(rng.randint(-128, 127), bytes(rng.getrandbits(8) for _ in range(rng.randint(0, 4)))),
+        ]
+    )
+
+
+def _key(rng: random.Random, depth: int):
+    # Any hashable value that survives a round trip; arrays used as keys come back as tuples
+    if depth < 2 and rng.random() < 0.2:
+        return tuple(_key(rng, depth + 1) for _ in range(rng.randint(0, 3)))
+    return _scalar(rng)
+
+
+def _value(rng: random.Random, depth: int = 0):
+    r = rng.random()
+    if depth < 4 and r < 0.25:
+        return [_value(rng, depth + 1) for _ in range(rng.randint(0, 5))]
+    if depth < 4 and r < 0.5:
+        return {_key(rng, depth): _value(rng, depth + 1) for _ in range(rng.randint(0, 5))}
+    return _scalar(rng)
+
+
+@pytest.mark.parametrize("seed", list(range(200)))
+def test_random_nested_roundtrip(seed):
+    obj = _value(random.Random(seed))
+    assert unpackb(packb(obj)) == obj
+
+
+def test_non_string_keys_and_bytes():
+    obj = {1: b"\x00\xff", b"k": [1, 2], (1, "a"): {None: True}, 2.5: ExtType(7, b"xy")}
+    out = unpackb(packb(obj))
+    assert out == obj
+    assert isinstance(out[2.5], ExtType)
+    assert out[2.5].code == 7 and out[2.5].data == b"xy"
+
+
+def test_tuples_and_bytearray_pack_as_array_and_bin():
+    assert unpackb(packb((1, [2, (3,)]))) == [1, [2, [3]]]
+    assert unpackb(packb(bytearray(b"ab"))) == b"ab"
+
+
+def test_wire_format_is_standard_msgpack():
+    assert packb({"a": 1}) == b"\x81\xa1a\x01"
+    assert packb(ExtType(5, b"\x01")) == b"\xd4\x05\x01"
+    assert unpackb(b"\x93\x01\xc0\xc3") == [1, None, True]
+
+
+@pytest.mark.parametrize("bad", [{1, 2}, frozenset(), object(), 1j])
+def test_unsupported_types_raise_type_error(bad):
+    with pytest.raises(TypeError, match="cannot convert"):
+        packb({"nested": [bad]})
+
+
+def test_out_of_range_int_and_cycles_raise_value_error():
+    with pytest.raises(ValueError, match="out of range"):
+        packb(2**64)
+    cyclic = []
+    cyclic.append(cyclic)
+    with pytest.raises(ValueError, match="nested deeper"):
+        packb(cyclic)
+
+
+def test_unpackb_rejects_bad_input():
+    with pytest.raises(ValueError, match="invalid msgpack"):
+        unpackb(b"\x92\x01")
+    with pytest.raises(ValueError, match="extra data"):
+        unpackb(b"\x01\x02")
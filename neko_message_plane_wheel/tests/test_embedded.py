@@ -0,0 +1,118 @@
+from __future__ import annotations
+
+import pytest
+from test_subscriber import _publish_until_received
+
+from neko_message_plane_wheel import (
+    AsyncMessagePlaneClient,
+    EmbeddedServer,
+    MessagePlaneClient,
+    MessagePlaneError,
+    Subscriber,
+    start_embedded,
+)
+
+
+def test_example_publish_and_read_back(embedded_plane, embedded_client):
+    # Typical use: start a server in-process and talk to it like an external one.
+    assert isinstance(embedded_plane, EmbeddedServer)
+    assert embedded_plane.running
+    assert embedded_plane.rpc_endpoint.startswith("inproc://")
+
+    ev = embedded_client.publish("messages", "embedded", {"n": 1})
+    assert ev["seq"] >= 1
+    items = embedded_client.get_recent("messages", "embedded", limit=10)
+    assert [it["payload"] for it in items] == [{"n": 1}]
+
+
+def test_ping_reports_resolved_endpoints(embedded_plane, embedded_client):
+    eps = embedded_client.ping()["endpoints"]
+    assert eps == {
+        "rpc": embedded_plane.rpc_endpoint,
+        "ingest": embedded_plane.ingest_endpoint,
+        "pub": embedded_plane.pub_endpoint,
+    }
+
+
+def test_subscriber_on_inproc_pub(embedded_plane, embedded_client):
+    with Subscriber(embedded_plane.pub_endpoint, ["embedded.sub"]) as sub:
+        ev = _publish_until_received(embedded_client, sub, "embedded.sub", {"k": 2})
+        assert ev["store"] == "messages"
+
+
+@pytest.mark.asyncio
+async def test_async_client(embedded_plane):
+    c = AsyncMessagePlaneClient(embedded_plane.rpc_endpoint, embedded_plane.pub_endpoint, 2000)
+    assert (await c.ping())["ok"] is True
+    await c.publish("messages", "embedded.aio", {"i": 1})
+    assert len(await c.get_recent("messages", "embedded.aio")) == 1
+
+
+def test_instances_are_isolated():
+    with start_embedded() as a, start_embedded() as b:
+        assert a.rpc_endpoint != b.rpc_endpoint
+        MessagePlaneClient(a.rpc_endpoint, 2000).publish("messages", "iso", {"x": 1})
+        assert MessagePlaneClient(b.rpc_endpoint, 2000).get_recent("messages", "iso") == []
+
+
+def test_tcp_wildcard_ports_are_resolved():
+    config = {
+        "rpc_endpoint": "tcp://127.0.0.1:*",
+        "ingest_endpoint": "tcp://127.0.0.1:*",
+        "pub_endpoint": "tcp://127.0.0.1:*",
+    }
+    with start_embedded(config) as srv:
+        for ep in (srv.rpc_endpoint, srv.ingest_endpoint, srv.pub_endpoint):
+            assert ep.startswith("tcp://127.0.0.1:")
+            assert int(ep.rsplit(":", 1)[1]) > 0
+        assert MessagePlaneClient(srv.rpc_endpoint, 2000).ping()["ok"] is True
+
+
+def test_config_options_are_applied():
+    with start_embedded({"store_config": ["runs=3:10"], "pub_enabled": False, "workers": 2}) as srv:
+        assert srv.pub_endpoint is None
+        c = MessagePlaneClient(srv.rpc_endpoint, 2000)
+        for i in range(5):
+            c.publish("runs", "capped", {"i": i})
+        assert [it["payload"]["i"] for it in c.get_recent("runs", "capped", limit=10)] == [2, 3, 4]
+
+
+def test_ipc_dir_sockets_removed_on_stop(tmp_path):
+    ipc_dir = tmp_path / "mp"
+    srv = start_embedded({"ipc_dir": str(ipc_dir)})
+    assert srv.rpc_endpoint == f"ipc://{ipc_dir}/rpc.sock"
+    assert (ipc_dir / "rpc.sock").exists()
+    srv.stop()
+    assert not srv.running
+    assert not any(ipc_dir.iterdir())
+
+
+def test_stop_is_graceful_and_idempotent():
+    srv = start_embedded()
+    c = MessagePlaneClient(srv.rpc_endpoint, 200)
+    assert c.ping()["ok"] is True
+    srv.stop()
+    srv.stop()
+    assert not srv.running
+    assert "running=False" in repr(srv)
+    with pytest.raises(MessagePlaneError):
+        c.ping()
+
+
+@pytest.mark.parametrize(
+    "config, match",
+    [
+        ({"no_such_option": 1}, "no-such-option"),
+        ({"workers": "many"}, "many"),
+        ({"self_check": True}, "not supported"),
+        ({"admin_token": "t"}, "NEKO_MESSAGE_PLANE_ADMIN_TOKEN"),
+    ],
+)
+def test_bad_config_raises_value_error(config, match):
+    with pytest.raises(ValueError, match=match):
+        start_embedded(config)
+
+
+def test_bind_failure_raises_runtime_error(embedded_plane):
+    with pytest.raises(RuntimeError, match="cannot bind"):
+        start_embedded({"rpc_endpoint": embedded_plane.rpc_endpoint})
@@ -0,0 +1,86 @@
+from __future__ import annotations
+
+import pytest
+from conftest import start_server, stop_server, wait_ready
+
+from neko_message_plane_wheel import (
+    AsyncMessagePlaneClient,
+    BadArgs,
+    BadStore,
+    BadVersion,
+    MessagePlaneClient,
+    MessagePlaneError,
+    Overloaded,
+    Timeout,
+    Unauthorized,
+    UnknownOp,
+)
+
+
+@pytest.mark.parametrize("cls", [BadArgs, BadStore, BadVersion, UnknownOp, Overloaded, Timeout, Unauthorized])
+def test_subclasses_share_the_base(cls):
+    assert issubclass(cls, MessagePlaneError)
+    assert issubclass(cls, Exception)
+
+
+def test_bad_args(client):
+    with pytest.raises(BadArgs) as ei:
+        client.publish("messages", "", {"x": 1})
+    assert ei.value.code == "BAD_ARGS"
+    assert ei.value.message
+
+
+def test_bad_args_carries_details(client):
+    bad = {"kind": "unary", "op": "explode", "child": {"kind": "get", "params": {}}}
+    with pytest.raises(BadArgs) as ei:
+        client.replay("messages", bad)
+    assert ei.value.details == {"path": "$"}
+
+
+def test_bad_store(client):
+    with pytest.raises(BadStore) as ei:
+        client.get_recent("no-such-store", "t")
+    assert ei.value.code == "BAD_STORE"
+    assert ei.value.details is None
+
+
+def test_base_class_still_catches_everything(client):
+    with pytest.raises(MessagePlaneError):
+        client.get_recent("no-such-store", "t")
+
+
+def test_client_timeout(tmp_path):
+    c = MessagePlaneClient(f"ipc://{tmp_path}/missing.sock", 100)
+    with pytest.raises(Timeout) as ei:
+        c.ping()
+    assert ei.value.code == "TIMEOUT"
+
+
+def test_pipeline_and_batch_return_typed_instances(client):
+    results = client.pipeline([("no.such.op", {}), ("bus.get_recent", {"store": "no-such-store"})])
+    assert isinstance(results[0], UnknownOp)
+    assert isinstance(results[1], BadStore)
+
+    results = client.publish_batch([{"topic": "", "payload": {}}, {"topic": "t", "payload": {}, "store": "nope"}])
+    assert isinstance(results[0], BadArgs)
+    assert isinstance(results[1], BadStore)
+
+
+def test_unauthorized(tmp_path):
+    server = start_server(tmp_path / "mp", "--admin-token", "secret")
+    try:
+        wait_ready(server)
+        [res] = server.client().pipeline([("bus.clients", {})])
+        assert isinstance(res, Unauthorized)
+        assert res.code == "UNAUTHORIZED"
+    finally:
+        stop_server(server)
+
+
+@pytest.mark.asyncio
+async def test_async_client_raises_subclasses(message_plane):
+    c = AsyncMessagePlaneClient(message_plane.rpc_endpoint, message_plane.pub_endpoint, 2000)
+    with pytest.raises(BadStore):
+        await c.get_recent("no-such-store")
+    with pytest.raises(BadArgs):
+        await c.publish("messages", "", {})
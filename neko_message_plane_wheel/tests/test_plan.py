@@ -0,0 +1,77 @@
+from __future__ import annotations
+
+import pytest
+
+from neko_message_plane_wheel import MessagePlaneError, Plan
+
+
+def _get(topic="all", limit=200, **extra):
+    return {"kind": "get", "params": {"params": {"topic": topic, "max_count": limit, **extra}}}
+
+
+def test_get_matches_dict():
+    assert Plan.get(topic="chat", limit=10).to_dict() == _get("chat", 10)
+    assert Plan.get(topic="chat", limit=10, plugin_id="p") == _get("chat", 10, plugin_id="p")
+
+
+def test_chained_unary_ops_match_dict():
+    plan = Plan.get(topic="t", limit=50).where_eq("kind", "msg").sort("seq", reverse=True).limit(5)
+    expected = {
+        "kind": "unary",
+        "op": "limit",
+        "params": {"n": 5},
+        "child": {
+            "kind": "unary",
+            "op": "sort",
+            "params": {"by": "seq", "reverse": True},
+            "child": {
+                "kind": "unary",
+                "op": "where_eq",
+                "params": {"field": "kind", "value": "msg"},
+                "child": _get("t", 50),
+            },
+        },
+    }
+    assert plan.to_dict() == expected
+    assert plan == expected
+
+
+def test_filter_and_merge_match_dict():
+    left = Plan.get(topic="a").filter(plugin_id="x", priority_min=2)
+    right = Plan.get(topic="b")
+    expected = {
+        "kind": "binary",
+        "op": "merge",
+        "left": {
+            "kind": "unary",
+            "op": "filter",
+            "params": {"plugin_id": "x", "priority_min": 2},
+            "child": _get("a"),
+        },
+        "right": _get("b"),
+    }
+    assert left.merge(right) == expected
+    assert Plan.from_dict(expected) == left.merge(right)
+    assert left.merge(right) != right.merge(left)
+
+
+def test_replay_with_plan_and_dict(client):
+    for i in range(6):
+        client.publish("messages", "replay", {"i": i, "kind": "even" if i % 2 == 0 else "odd"})
+
+    plan = Plan.get(topic="replay", limit=100).where_eq("kind", "even").sort("i").limit(2)
+    items = client.replay("messages", plan)
+    assert [it["payload"]["i"] for it in items] == [0, 2]
+    assert client.replay("messages", plan.to_dict()) == items
+
+    light = client.replay("messages", plan, light=True)
+    assert all("payload" not in it for it in light)
+
+
+def test_invalid_plan_reports_node_path(client):
+    bad = {"kind": "binary", "op": "merge", "left": _get("t"), "right": {"kind": "unary", "op": "explode"}}
+    with pytest.raises(MessagePlaneError) as ei:
+        client.replay("messages", bad)
+    assert ei.value.code == "BAD_ARGS"
+    assert "$.right" in str(ei.value)
+    assert ei.value.details == {"path": "$.right"}
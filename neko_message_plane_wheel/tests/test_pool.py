@@ -0,0 +1,64 @@
+from __future__ import annotations
+
+import threading
+
+import pytest
+
+from neko_message_plane_wheel import MessagePlaneClient, MessagePlaneError, close_all, pool_stats
+
+
+def test_short_lived_clients_share_one_socket(message_plane):
+    for _ in range(50):
+        assert message_plane.client().ping()["ok"] is True
+    assert pool_stats()[message_plane.rpc_endpoint] == 1
+
+
+@pytest.mark.parametrize("shared", [True, False])
+def test_concurrent_publish_from_many_threads(message_plane, shared):
+    threads, per_thread = 8, 25
+    common = message_plane.client()
+    seqs: list[int] = []
+    errors: list[BaseException] = []
+    lock = threading.Lock()
+    start = threading.Barrier(threads)
+
+    def worker(n: int):
+        try:
+            start.wait()
+            for i in range(per_thread):
+                c = common if shared else message_plane.client()
+                ev = c.publish("messages", "hammer", {"thread": n, "i": i})
+                assert ev["payload"] == {"thread": n, "i": i}
+                with lock:
+                    seqs.append(ev["seq"])
+        except BaseException as e:  # noqa: BLE001 - surfaced by the assertion below
+            errors.append(e)
+
+    ts = [threading.Thread(target=worker, args=(n,)) for n in range(threads)]
+    for t in ts:
+        t.start()
+    for t in ts:
+        t.join()
+
+    assert errors == []
+    assert len(set(seqs)) == threads * per_thread
+    items = common.get_recent("messages", "hammer", limit=threads * per_thread)
+    assert len(items) == threads * per_thread
+    assert 1 <= pool_stats()[message_plane.rpc_endpoint] <= threads
+
+
+def test_close_all_drops_idle_sockets_and_calls_reconnect(message_plane):
+    c = message_plane.client()
+    c.ping()
+    assert message_plane.rpc_endpoint in pool_stats()
+    close_all()
+    assert pool_stats() == {}
+    assert c.ping()["ok"] is True
+    assert pool_stats()[message_plane.rpc_endpoint] == 1
+
+
+def test_failed_calls_do_not_return_socket_to_pool(tmp_path):
+    endpoint = f"ipc://{tmp_path}/missing.sock"
+    with pytest.raises(MessagePlaneError):
+        MessagePlaneClient(endpoint, 50).ping()
+    assert endpoint not in pool_stats()
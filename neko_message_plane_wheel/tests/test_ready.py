@@ -0,0 +1,66 @@
+from __future__ import annotations
+
+import threading
+import time
+
+import pytest
+from conftest import start_server, stop_server
+
+from neko_message_plane_wheel import MessagePlaneClient, MessagePlaneError, ReadyTimeoutError
+
+
+def test_ping_with_latency(client):
+    ms = client.ping_with_latency()
+    assert isinstance(ms, float)
+    assert 0 < ms < 1000
+    assert client.ping(timeout_ms=500)["ok"] is True
+
+
+def test_wait_until_ready_with_delayed_server(tmp_path):
+    ipc_dir = tmp_path / "mp"
+    started = []
+
+    def start_later():
+        time.sleep(0.5)
+        started.append(start_server(ipc_dir))
+
+    t = threading.Thread(target=start_later)
+    t.start()
+    try:
+        client = MessagePlaneClient(f"ipc://{ipc_dir}/rpc.sock", 1000)
+        attempts = client.wait_until_ready(total_timeout_ms=15_000, interval_ms=20)
+        assert attempts >= 1
+        assert client.ping()["ok"] is True
+    finally:
+        t.join()
+        for server in started:
+            stop_server(server)
+
+
+def test_wait_until_ready_without_server(tmp_path):
+    client = MessagePlaneClient(f"ipc://{tmp_path}/missing.sock", 1000)
+    counter = [0]
+    stop = threading.Event()
+
+    def spin():
+        while not stop.is_set():
+            counter[0] += 1
+
+    t = threading.Thread(target=spin)
+    t.start()
+    began = time.monotonic()
+    try:
+        with pytest.raises(ReadyTimeoutError) as ei:
+            client.wait_until_ready(total_timeout_ms=400, interval_ms=10)
+    finally:
+        stop.set()
+        t.join()
+    elapsed = time.monotonic() - began
+
+    assert isinstance(ei.value, TimeoutError)
+    assert isinstance(ei.value.last_error, MessagePlaneError)
+    assert ei.value.last_error.code == "TIMEOUT"
+    assert ei.value.__cause__ is ei.value.last_error
+    assert 0.35 < elapsed < 2.0
+    # The retry loop runs without the GIL
+    assert counter[0] > 1000
@@ -0,0 +1,91 @@
+from __future__ import annotations
+
+import threading
+import time
+
+import pytest
+
+from neko_message_plane_wheel import Subscriber
+
+
+def _publish_until_received(client, sub, topic, payload, timeout=5.0):
+    # PUB drops messages until the subscription has propagated, so keep publishing.
+    deadline = time.monotonic() + timeout
+    while time.monotonic() < deadline:
+        client.publish("messages", topic, payload)
+        ev = sub.recv(timeout_ms=100)
+        while ev is not None:
+            if ev["topic"] == topic and ev["payload"] == payload:
+                return ev
+            ev = sub.recv(timeout_ms=10)
+    raise AssertionError("event not received on the PUB stream")
+
+
+def test_receive_published_event(message_plane, client):
+    with Subscriber(message_plane.pub_endpoint) as sub:
+        ev = _publish_until_received(client, sub, "sub.basic", {"k": 1})
+        assert set(ev) >= {"seq", "ts", "store", "topic", "index", "payload"}
+        assert ev["store"] == "messages"
+
+
+def test_prefix_filtering(message_plane, client):
+    with Subscriber(message_plane.pub_endpoint, ["wanted"]) as sub:
+        _publish_until_received(client, sub, "wanted.a", {"n": 0})
+        client.publish("messages", "other", {"n": 1})
+        client.publish("messages", "wanted.b", {"n": 2})
+        ev = sub.recv(timeout_ms=2000)
+        assert ev["topic"] == "wanted.b"
+
+
+def test_recv_timeout_returns_none(message_plane):
+    with Subscriber(message_plane.pub_endpoint, ["nothing-here"]) as sub:
+        t0 = time.monotonic()
+        assert sub.recv(timeout_ms=200) is None
+        assert time.monotonic() - t0 < 2.0
+
+
+def test_iteration_and_close_from_another_thread(message_plane, client):
+    sub = Subscriber(message_plane.pub_endpoint)
+    _publish_until_received(client, sub, "sub.iter", {"warm": True})
+
+    received = []
+
+    def consume():
+        for ev in sub:
+            received.append(ev)
+
+    t = threading.Thread(target=consume)
+    t.start()
+    for i in range(3):
+        client.publish("messages", "sub.iter", {"i": i})
+    deadline = time.monotonic() + 5
+    while len(received) < 3 and time.monotonic() < deadline:
+        time.sleep(0.02)
+
+    sub.close()
+    t.join(timeout=5)
+    assert not t.is_alive()
+    assert sub.closed
+    assert [ev["payload"]["i"] for ev in received[:3]] == [0, 1, 2]
+    with pytest.raises(StopIteration):
+        next(sub)
+
+
+def test_gil_released_while_waiting(message_plane):
+    counter = [0]
+    stop = threading.Event()
+
+    def spin():
+        while not stop.is_set():
+            counter[0] += 1
+
+    t = threading.Thread(target=spin)
+    t.start()
+    try:
+        with Subscriber(message_plane.pub_endpoint, ["nothing-here"]) as sub:
+            before = counter[0]
+            sub.recv(timeout_ms=300)
+            assert counter[0] - before > 1000
+    finally:
+        stop.set()
+        t.join()
@@ -0,0 +1,1355 @@
+//! Typed entry points for other Rust tools: pack, unpack and check configured through option
+//! builders, returning the same reports the CLI prints and a [`NekoCliError`] that says what kind
+//! of failure it was. The binary is built on these. The reports and enums below are re-exported
+//! from the internal `core` module, which is not part of the supported API.
+//!
+//! ```no_run
+//! use neko_plugin_cli::{CheckOptions, PackOptions, UnpackOptions};
+//!
+//! let report = neko_plugin_cli::pack(&PackOptions::new().root("/srv/neko").out("dist/bundle.zip").manifest_version(2))?;
+//! println!("packed {} plugin(s), {} bytes", report.plugins.len(), report.size);
+//!
+//! let unpacked = neko_plugin_cli::unpack(&UnpackOptions::new("dist/bundle.zip").root("/srv/other").force(true))?;
+//! let checked = neko_plugin_cli::check(&CheckOptions::new().root("/srv/other"))?;
+//! assert!(checked.errors.is_empty(), "{} plugin(s) installed with errors", unpacked.installed.len());
+//! # Ok::<(), neko_plugin_cli::NekoCliError>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use ed25519_dalek::SigningKey;
+use serde::Serialize;
+
+use crate::core;
+
+pub use crate::core::{
+    AppliedProfile, AppliedProfileStatus, ApplyProfiles, BundleMeta, CheckCacheStats, CheckFinding, CheckReport,
+    CompressionKind, ExcludeProfile, FileDiff, FileDiffKind, HashAlgo, HashCacheStats, Manifest, PackDryRun, PackEntry,
+    PackProgress, PlannedPluginSummary, PluginPackItem, ProgressFn, PythonLockReport, PythonOnlineReport, Severity,
+    SplitOutput, SymlinkPolicy, TomlFix, UnpackBackup, UnpackPreview, UnpackPreviewItem, UnpackPreviewSummary,
+    UnpackProgress, UnpackProgressFn, UnpackResult, UnpackedExtra, UnpackedPlugin, UnpackedSymlink, Verification,
+    VerifyIssue, VerifyReport, WrittenFiles,
+};
+
+pub type Result<T> = std::result::Result<T, NekoCliError>;
+
+/// Why a pack, unpack or check failed
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum NekoCliError {
+    /// A file could not be read or written; `message` says which and what for
+    #[error("{message}")]
+    Io {
+        message: String,
+        path: Option<PathBuf>,
+        #[source]
+        source: std::io::Error,
+    },
+    /// A bundle is unreadable or does not match its manifest (checksums, signature, format_version)
+    #[error("{message}")]
+    Manifest { message: String, path: Option<PathBuf> },
+    /// Plugins or options are invalid: a bad plugin.toml, an unknown plugin id, options that
+    /// cannot be combined, a check that could not run
+    #[error("{message}")]
+    Validation { message: String, path: Option<PathBuf> },
+    /// Unpacking would overwrite installed plugins that differ from the bundle
+    /// ([`UnpackOptions::fail_on_conflict`])
+    #[error("{message}")]
+    Conflict { message: String, plugins: Vec<String> },
+}
+
+impl NekoCliError {
+    /// The file the error is about, when there is one
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            NekoCliError::Io { path, .. } | NekoCliError::Manifest { path, .. } | NekoCliError::Validation { path, .. } => {
+                path.as_deref()
+            }
+            NekoCliError::Conflict { .. } => None,
+        }
+    }
+
+    /// Sort an error from `core`: anything caused by an io::Error is Io, otherwise it is a
+    /// Manifest error when it came out of reading a bundle and a Validation error when not
+    fn classify(e: anyhow::Error, reading_bundle: bool) -> Self {
+        // Already typed, unless core added context around it since (downcast would see through that)
+        let e = if e.chain().next().is_some_and(|c| c.is::<NekoCliError>()) {
+            match e.downcast::<NekoCliError>() {
+                Ok(typed) => return typed,
+                Err(e) => e,
+            }
+        } else {
+            e
+        };
+        let path = e.downcast_ref::<core::PathContext>().map(|c| c.path.clone());
+        if let Some(io) = e.chain().find_map(|c| c.downcast_ref::<std::io::Error>()) {
+            // The io::Error becomes the source, so the message stops just before it
+            let message: Vec<String> = e
+                .chain()
+                .take_while(|c| !c.is::<std::io::Error>())
+                .map(ToString::to_string)
+                .collect();
+            let source = match io.raw_os_error() {
+                Some(code) => std::io::Error::from_raw_os_error(code),
+                None => std::io::Error::new(io.kind(), io.to_string()),
+            };
+            let message = if message.is_empty() { io.kind().to_string() } else { message.join(": ") };
+            return NekoCliError::Io { message, path, source };
+        }
+        let message = format!("{e:#}");
+        if reading_bundle || e.chain().any(|c| c.is::<zip::result::ZipError>()) {
+            NekoCliError::Manifest { message, path }
+        } else {
+            NekoCliError::Validation { message, path }
+        }
+    }
+
+    fn validation(message: impl Into<String>) -> Self {
+        NekoCliError::Validation {
+            message: message.into(),
+            path: None,
+        }
+    }
+}
+
+/// `?` on a core result inside the pack and check steps
+trait Classify<T> {
+    fn validation_err(self) -> Result<T>;
+    fn bundle_err(self) -> Result<T>;
+}
+
+impl<T> Classify<T> for anyhow::Result<T> {
+    fn validation_err(self) -> Result<T> {
+        self.map_err(|e| NekoCliError::classify(e, false))
+    }
+
+    fn bundle_err(self) -> Result<T> {
+        self.map_err(|e| NekoCliError::classify(e, true))
+    }
+}
+
+fn repo_root(root: Option<&Path>) -> Result<PathBuf> {
+    match root {
+        Some(p) => Ok(p.to_path_buf()),
+        None => std::env::current_dir()
+            .context("failed to get cwd")
+            .and_then(core::find_repo_root)
+            .validation_err(),
+    }
+}
+
+/// What to pack and how; everything left unset falls back to the `--spec` file, the repo's
+/// bundle defaults and then the CLI defaults, exactly as `neko_plugin_cli pack` does
+#[derive(Debug, Clone)]
+pub struct PackOptions {
+    root: Option<PathBuf>,
+    overrides: PackOverridesSeed,
+    respect_gitignore: bool,
+    symlinks: core::SymlinkPolicy,
+    hash_cache: bool,
+    cache_dir: Option<PathBuf>,
+    no_hash: bool,
+    source_date_epoch: Option<i64>,
+    allow_invalid: bool,
+    with_deps: bool,
+    include_disabled: bool,
+    base: Option<PathBuf>,
+    sign_key: Option<PathBuf>,
+    verify: bool,
+    max_size: Option<u64>,
+    warn_size: Option<u64>,
+    max_name_len: usize,
+    spec: Option<PathBuf>,
+}
+
+/// core::PackOverrides is consumed by BundleSpec::apply, so the options keep a cloneable copy
+#[derive(Debug, Clone, Default)]
+struct PackOverridesSeed {
+    plugin_ids: Vec<String>,
+    excludes: Vec<String>,
+    include_root: Vec<String>,
+    bundle: core::BundleMeta,
+    exclude_profile: Option<core::ExcludeProfile>,
+    hash: Option<core::HashAlgo>,
+    compression: Option<core::CompressionKind>,
+    compression_level: Option<i64>,
+    manifest_version: Option<u32>,
+    out: Option<PathBuf>,
+}
+
+impl Default for PackOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackOptions {
+    pub fn new() -> Self {
+        PackOptions {
+            root: None,
+            overrides: PackOverridesSeed::default(),
+            respect_gitignore: false,
+            symlinks: core::SymlinkPolicy::default(),
+            hash_cache: true,
+            cache_dir: None,
+            no_hash: false,
+            source_date_epoch: None,
+            allow_invalid: false,
+            with_deps: false,
+            include_disabled: false,
+            base: None,
+            sign_key: None,
+            verify: false,
+            max_size: None,
+            warn_size: None,
+            max_name_len: core::ZIP_NAME_MAX_LEN,
+            spec: None,
+        }
+    }
+
+    /// Repo root; found from the current directory when unset
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Plugin ids to pack; all plugins when empty
+    pub fn plugins<I: IntoIterator<Item = S>, S: Into<String>>(mut self, ids: I) -> Self {
+        self.overrides.plugin_ids = ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Output zip (`-` is not a file here; use [`PreparedPack::write_to`]). With
+    /// [`PreparedPack::write_split`] it names the output directory.
+    pub fn out(mut self, out: impl Into<PathBuf>) -> Self {
+        self.overrides.out = Some(out.into());
+        self
+    }
+
+    pub fn excludes(mut self, patterns: Vec<String>) -> Self {
+        self.overrides.excludes = patterns;
+        self
+    }
+
+    pub fn exclude_profile(mut self, profile: core::ExcludeProfile) -> Self {
+        self.overrides.exclude_profile = Some(profile);
+        self
+    }
+
+    pub fn respect_gitignore(mut self, on: bool) -> Self {
+        self.respect_gitignore = on;
+        self
+    }
+
+    pub fn symlinks(mut self, policy: core::SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Reuse digests of unchanged files from the hash cache (on by default)
+    pub fn hash_cache(mut self, on: bool) -> Self {
+        self.hash_cache = on;
+        self
+    }
+
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Repo-level files stored under extras/ in the bundle
+    pub fn include_root(mut self, entries: Vec<String>) -> Self {
+        self.overrides.include_root = entries;
+        self
+    }
+
+    pub fn bundle(mut self, meta: core::BundleMeta) -> Self {
+        self.overrides.bundle = meta;
+        self
+    }
+
+    pub fn hash(mut self, algo: core::HashAlgo) -> Self {
+        self.overrides.hash = Some(algo);
+        self
+    }
+
+    /// Skip folder hashing (`--no-md5`)
+    pub fn no_hash(mut self, on: bool) -> Self {
+        self.no_hash = on;
+        self
+    }
+
+    pub fn manifest_version(mut self, version: u32) -> Self {
+        self.overrides.manifest_version = Some(version);
+        self
+    }
+
+    pub fn compression(mut self, method: core::CompressionKind) -> Self {
+        self.overrides.compression = Some(method);
+        self
+    }
+
+    pub fn compression_level(mut self, level: i64) -> Self {
+        self.overrides.compression_level = Some(level);
+        self
+    }
+
+    /// Fixed timestamps for reproducible output; see [`core::resolve_source_date_epoch`]
+    pub fn source_date_epoch(mut self, epoch: Option<i64>) -> Self {
+        self.source_date_epoch = epoch;
+        self
+    }
+
+    pub fn allow_invalid(mut self, on: bool) -> Self {
+        self.allow_invalid = on;
+        self
+    }
+
+    pub fn with_deps(mut self, on: bool) -> Self {
+        self.with_deps = on;
+        self
+    }
+
+    pub fn include_disabled(mut self, on: bool) -> Self {
+        self.include_disabled = on;
+        self
+    }
+
+    /// Previous bundle whose entries are copied for unchanged plugins
+    pub fn base(mut self, base: impl Into<PathBuf>) -> Self {
+        self.base = Some(base.into());
+        self
+    }
+
+    /// ed25519 secret key file to sign the bundle with (needs manifest_version 2)
+    pub fn sign_key(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sign_key = Some(path.into());
+        self
+    }
+
+    /// Re-read the written zip against its manifest; a bundle that fails is deleted
+    pub fn verify(mut self, on: bool) -> Self {
+        self.verify = on;
+        self
+    }
+
+    pub fn max_size(mut self, bytes: Option<u64>) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    pub fn warn_size(mut self, bytes: Option<u64>) -> Self {
+        self.warn_size = bytes;
+        self
+    }
+
+    pub fn max_name_len(mut self, len: usize) -> Self {
+        self.max_name_len = len;
+        self
+    }
+
+    /// Declarative bundle.toml; explicit options take precedence
+    pub fn spec(mut self, path: impl Into<PathBuf>) -> Self {
+        self.spec = Some(path.into());
+        self
+    }
+
+    /// Resolve the options and select the plugins, without hashing or writing anything
+    pub fn prepare(&self) -> Result<PreparedPack> {
+        PreparedPack::new(self)
+    }
+}
+
+/// Pack options resolved against the repo: the selected plugins, exclude rules, extras and output
+/// path. Every pack mode of the CLI starts from one of these.
+pub struct PreparedPack {
+    plugins: Vec<core::PluginPackItem>,
+    excludes: core::Excludes,
+    extras: Vec<core::ExtraFile>,
+    bundle: core::BundleMeta,
+    out: PathBuf,
+    named: bool,
+    hash: core::HashAlgo,
+    no_hash: bool,
+    manifest_version: u32,
+    compression: core::PackCompression,
+    max_name_len: usize,
+    source_date_epoch: Option<i64>,
+    max_size: Option<u64>,
+    warn_size: Option<u64>,
+    base: Option<PathBuf>,
+    sign_key: Option<SigningKey>,
+    verify: bool,
+    skipped_disabled: Vec<String>,
+    warnings: Vec<String>,
+    out_given: bool,
+}
+
+/// A written bundle
+#[derive(Debug, Serialize)]
+pub struct PackReport {
+    /// None when the bundle went to a writer
+    pub path: Option<PathBuf>,
+    pub size: u64,
+    /// Ids of the packed plugins
+    pub plugins: Vec<String>,
+    pub manifest: core::Manifest,
+    /// Plugins copied from the base bundle
+    pub reused: Option<Vec<String>>,
+    /// Fingerprint of the signing key
+    pub signed_by: Option<String>,
+    pub verify: Option<core::VerifyReport>,
+    pub hash_cache: Option<core::HashCacheStats>,
+    /// Disabled plugins left out of the selection
+    pub skipped_disabled: Vec<String>,
+    /// Everything logged as a warning along the way
+    pub warnings: Vec<String>,
+}
+
+struct Written {
+    manifest: core::Manifest,
+    signed_by: Option<String>,
+    verify: Option<core::VerifyReport>,
+    warnings: Vec<String>,
+}
+
+impl PreparedPack {
+    fn new(options: &PackOptions) -> Result<Self> {
+        let repo_root = repo_root(options.root.as_deref())?;
+        let plugins_dir = repo_root.join("plugin").join("plugins");
+        let seed = options.overrides.clone();
+        let requested = seed.plugin_ids.clone();
+        let mut args = core::PackOverrides {
+            plugin_ids: seed.plugin_ids,
+            excludes: seed.excludes,
+            include_root: seed.include_root,
+            bundle: seed.bundle,
+            exclude_profile: seed.exclude_profile,
+            hash: seed.hash,
+            compression: seed.compression,
+            compression_level: seed.compression_level,
+            manifest_version: seed.manifest_version,
+            out: seed.out,
+        };
+        let mut warnings = Vec::new();
+        let defaults = core::load_repo_bundle_defaults(&repo_root).validation_err()?;
+        warn_all(&mut warnings, defaults.warnings.iter().cloned());
+        if let Some(path) = &options.spec {
+            let spec = core::load_bundle_spec(path).validation_err()?;
+            warn_all(&mut warnings, spec.warnings.iter().cloned());
+            args = spec
+                .apply(&plugins_dir, args, &defaults.meta, options.source_date_epoch)
+                .validation_err()?;
+        } else {
+            args.bundle = args.bundle.or(&defaults.meta);
+        }
+        let profile = args.exclude_profile.unwrap_or_default();
+        let mut excludes = core::build_excludes_for(profile, &args.excludes)
+            .validation_err()?
+            .symlinks(options.symlinks);
+        if let Some(profile) = args.exclude_profile {
+            log::info!("exclude profile {} ({})", profile.as_str(), profile.patterns().join(", "));
+        }
+        if options.respect_gitignore {
+            excludes = excludes.respect_gitignore(&repo_root);
+        }
+        if options.hash_cache {
+            excludes = excludes.hash_cache(&repo_root, options.cache_dir.as_deref());
+        }
+        let extras = core::resolve_extras(&repo_root, &args.include_root).validation_err()?;
+
+        let ids: Option<&[String]> = if args.plugin_ids.is_empty() { None } else { Some(&args.plugin_ids) };
+        let mut plugins = if options.with_deps {
+            core::scan_plugins_for_pack_with_deps(&plugins_dir, &args.plugin_ids)
+        } else {
+            core::scan_plugins_for_pack(&plugins_dir, ids)
+        }
+        .validation_err()?;
+        let mut skipped_disabled = Vec::new();
+        if !options.include_disabled {
+            skipped_disabled = core::skip_disabled_plugins(&plugins_dir, &mut plugins, &requested).validation_err()?;
+            for id in &skipped_disabled {
+                log::info!("skipping disabled plugin {}", id);
+            }
+        }
+        if plugins.is_empty() {
+            return Err(NekoCliError::validation("no plugins found to pack"));
+        }
+        let validated = core::validate_pack_plugins(&plugins, options.allow_invalid).validation_err()?;
+        warn_all(&mut warnings, validated);
+        for p in &plugins {
+            log::debug!("packing plugin '{}' {} from {}", p.id, p.version, p.path.display());
+        }
+
+        let named = !args.plugin_ids.is_empty();
+        let out_given = args.out.is_some();
+        let out = args.out.unwrap_or_else(|| core::default_pack_output(&plugins, named));
+        let manifest_version = args.manifest_version.unwrap_or(1);
+        let sign_key = options.sign_key.as_deref().map(core::read_signing_key).transpose().validation_err()?;
+        if sign_key.is_some() && manifest_version < 2 {
+            return Err(NekoCliError::validation(
+                "--sign-key requires --manifest-version 2 (its per-file checksums are what the signature covers)",
+            ));
+        }
+        if options.base.is_some() && options.no_hash {
+            return Err(NekoCliError::validation(
+                "--base needs folder hashes to find unchanged plugins; it cannot be combined with --no-md5",
+            ));
+        }
+        Ok(PreparedPack {
+            plugins,
+            excludes,
+            extras,
+            bundle: args.bundle,
+            out,
+            named,
+            hash: args.hash.unwrap_or_default(),
+            no_hash: options.no_hash,
+            manifest_version,
+            compression: core::PackCompression {
+                method: args.compression.unwrap_or_default(),
+                level: args.compression_level,
+            },
+            max_name_len: options.max_name_len,
+            source_date_epoch: options.source_date_epoch,
+            max_size: options.max_size,
+            warn_size: options.warn_size,
+            base: options.base.clone(),
+            sign_key,
+            verify: options.verify,
+            skipped_disabled,
+            warnings,
+            out_given,
+        })
+    }
+
+    pub fn plugins(&self) -> &[core::PluginPackItem] {
+        &self.plugins
+    }
+
+    /// The zip that [`write`](Self::write) creates: the `out` option or the default bundle name
+    pub fn out_path(&self) -> &Path {
+        &self.out
+    }
+
+    pub fn skipped_disabled(&self) -> &[String] {
+        &self.skipped_disabled
+    }
+
+    /// Warnings so far (repo defaults, spec, plugin.toml validation)
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Name the bundle is derived from when it goes to a stream rather than a file
+    fn stream_name(&self) -> PathBuf {
+        core::default_pack_output(&self.plugins, self.named)
+    }
+
+    /// Hash the plugins and return the manifest a pack would write (`pack --list-only`)
+    pub fn manifest(mut self) -> Result<core::Manifest> {
+        core::compute_plugin_hash_for_pack(&mut self.plugins, &self.excludes, self.hash, self.no_hash, None)
+            .validation_err()?;
+        let name_hint = if core::is_stream_path(&self.out) { self.stream_name() } else { self.out.clone() };
+        self.plan(&name_hint)?.into_manifest().validation_err()
+    }
+
+    /// Files and sizes a pack would write, without reading file contents (`pack --dry-run`)
+    pub fn dry_run(self) -> Result<core::PackDryRun> {
+        let mut report = self.plan(&self.out)?.into_dry_run(&self.out, 10);
+        report.apply_size_budget(self.max_size, self.warn_size);
+        Ok(report)
+    }
+
+    fn plan(&self, name_hint: &Path) -> Result<core::PackPlan> {
+        core::plan_pack(
+            name_hint,
+            &self.plugins,
+            &self.excludes,
+            &self.extras,
+            self.bundle.clone(),
+            self.manifest_version,
+            self.compression,
+            self.max_name_len,
+            self.source_date_epoch,
+        )
+        .validation_err()
+    }
+
+    /// Write the bundle to [`out_path`](Self::out_path)
+    pub fn write(self, progress: Option<core::ProgressFn<'_>>) -> Result<PackReport> {
+        if core::is_stream_path(&self.out) {
+            return Err(NekoCliError::validation("--out - writes to a stream; use PreparedPack::write_to"));
+        }
+        let out = self.out.clone();
+        self.write_file(&out, progress)
+    }
+
+    /// Build the bundle in a scratch file and copy it to `sink` (`pack --out -`)
+    pub fn write_to(self, sink: &mut dyn Write, progress: Option<core::ProgressFn<'_>>) -> Result<PackReport> {
+        let spool = core::StreamSpool::new(&self.stream_name()).validation_err()?;
+        let mut report = self.write_file(spool.path(), progress)?;
+        report.path = None;
+        report.size = spool.copy_to(sink).validation_err()?;
+        Ok(report)
+    }
+
+    fn write_file(mut self, path: &Path, progress: Option<core::ProgressFn<'_>>) -> Result<PackReport> {
+        let mut base = self.base.as_deref().map(core::PackBase::open).transpose().validation_err()?;
+        let mut plugins = std::mem::take(&mut self.plugins);
+        let written = self.write_one(&mut plugins, path, base.as_mut(), progress)?;
+        let reused = base.as_ref().map(|b| report_reuse(b, plugins.len()));
+        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        self.warnings.extend(written.warnings);
+        Ok(PackReport {
+            path: Some(path.to_path_buf()),
+            size,
+            plugins: plugins.iter().map(|p| p.id.clone()).collect(),
+            manifest: written.manifest,
+            reused,
+            signed_by: written.signed_by,
+            verify: written.verify,
+            hash_cache: self.excludes.hash_cache_stats(),
+            skipped_disabled: self.skipped_disabled,
+            warnings: self.warnings,
+        })
+    }
+
+    /// One zip per plugin in the `out` directory (the current directory when unset); failed
+    /// plugins are recorded unless `fail_fast` (`pack --split`)
+    pub fn write_split(
+        self,
+        fail_fast: bool,
+        progress: Option<core::ProgressFn<'_>>,
+    ) -> Result<BTreeMap<String, core::SplitOutput>> {
+        let out_dir = if self.out_given { self.out.clone() } else { PathBuf::from(".") };
+        if core::is_stream_path(&out_dir) {
+            return Err(NekoCliError::validation("--out - cannot be combined with --split"));
+        }
+        let mut base = self.base.as_deref().map(core::PackBase::open).transpose().validation_err()?;
+        let outputs = core::pack_split(&out_dir, &self.plugins, fail_fast, |one, path| {
+            self.write_one(one, path, base.as_mut(), progress)
+                .map(drop)
+                .map_err(anyhow::Error::from)
+        })
+        .validation_err()?;
+        if let Some(base) = &base {
+            report_reuse(base, self.plugins.len());
+        }
+        Ok(outputs)
+    }
+
+    fn write_one(
+        &self,
+        plugins: &mut [core::PluginPackItem],
+        path: &Path,
+        base: Option<&mut core::PackBase>,
+        progress: Option<core::ProgressFn<'_>>,
+    ) -> Result<Written> {
+        core::compute_plugin_hash_for_pack(plugins, &self.excludes, self.hash, self.no_hash, progress).validation_err()?;
+        let manifest = core::pack_to_zip(
+            path,
+            plugins,
+            &self.excludes,
+            &self.extras,
+            self.bundle.clone(),
+            self.manifest_version,
+            self.compression,
+            self.max_name_len,
+            self.source_date_epoch,
+            self.max_size,
+            base,
+            progress,
+        )
+        .validation_err()?;
+        let mut warnings = Vec::new();
+        let size_warning = self.warn_size.map(|w| core::check_warn_size(path, w)).transpose().validation_err()?;
+        warn_all(&mut warnings, size_warning.flatten());
+        let signed_by = match &self.sign_key {
+            Some(key) => {
+                let fingerprint = core::sign_bundle(path, key, self.source_date_epoch).validation_err()?;
+                log::info!("signed with key {}", fingerprint);
+                Some(fingerprint)
+            }
+            None => None,
+        };
+        let verify = if self.verify {
+            let report = core::verify_pack_output(path).bundle_err()?;
+            log::info!(
+                "verified {} entries ({} folder hashes, {} file checksums)",
+                report.entries,
+                report.folder_hashes,
+                report.file_checksums
+            );
+            Some(report)
+        } else {
+            None
+        };
+        Ok(Written {
+            manifest,
+            signed_by,
+            verify,
+            warnings,
+        })
+    }
+
+}
+
+fn report_reuse(base: &core::PackBase, total: usize) -> Vec<String> {
+    log::info!("reused {} of {} plugins from {}", base.reused().len(), total, base.path().display());
+    base.reused().to_vec()
+}
+
+fn warn_all(warnings: &mut Vec<String>, new: impl IntoIterator<Item = String>) {
+    for w in new {
+        log::warn!("{}", w);
+        warnings.push(w);
+    }
+}
+
+/// Pack into a single zip, like `neko_plugin_cli pack`
+pub fn pack(options: &PackOptions) -> Result<PackReport> {
+    options.prepare()?.write(None)
+}
+
+/// What to unpack where, like `neko_plugin_cli unpack`
+#[derive(Debug, Clone)]
+pub struct UnpackOptions {
+    zip_path: PathBuf,
+    root: Option<PathBuf>,
+    dest: Option<PathBuf>,
+    force: bool,
+    force_folders: Vec<String>,
+    fail_on_conflict: bool,
+    excludes: Vec<String>,
+    verify_key: Option<PathBuf>,
+    require_signature: bool,
+    with_extras: bool,
+    extras_dest: Option<PathBuf>,
+    allow_symlinks: bool,
+    backup: bool,
+    backup_dir: Option<PathBuf>,
+    no_verify: bool,
+    assume_version: Option<u32>,
+    apply_profiles: core::ApplyProfiles,
+    check: bool,
+    check_strict: bool,
+    prune: bool,
+    max_uncompressed_bytes: u64,
+    staged: bool,
+    staging_dir: Option<PathBuf>,
+    no_manifest: bool,
+}
+
+impl UnpackOptions {
+    /// `zip_path` may be relative to the current directory or to the repo root
+    pub fn new(zip_path: impl Into<PathBuf>) -> Self {
+        UnpackOptions {
+            zip_path: zip_path.into(),
+            root: None,
+            dest: None,
+            force: false,
+            force_folders: Vec::new(),
+            fail_on_conflict: false,
+            excludes: Vec::new(),
+            verify_key: None,
+            require_signature: false,
+            with_extras: false,
+            extras_dest: None,
+            allow_symlinks: false,
+            backup: false,
+            backup_dir: None,
+            no_verify: false,
+            assume_version: None,
+            apply_profiles: core::ApplyProfiles::default(),
+            check: false,
+            check_strict: false,
+            prune: false,
+            max_uncompressed_bytes: core::UNPACK_MAX_UNCOMPRESSED_BYTES,
+            staged: false,
+            staging_dir: None,
+            no_manifest: false,
+        }
+    }
+
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Plugin directory to install into; `<root>/plugin/plugins` when unset
+    pub fn dest(mut self, dest: impl Into<PathBuf>) -> Self {
+        self.dest = Some(dest.into());
+        self
+    }
+
+    /// Overwrite installed plugins that differ from the bundle
+    pub fn force(mut self, on: bool) -> Self {
+        self.force = on;
+        self
+    }
+
+    /// Overwrite only these installed plugin folders
+    pub fn force_folders(mut self, folders: Vec<String>) -> Self {
+        self.force_folders = folders;
+        self
+    }
+
+    /// Fail with [`NekoCliError::Conflict`] before installing anything when a plugin would be
+    /// skipped for differing from the installed one, instead of skipping it
+    pub fn fail_on_conflict(mut self, on: bool) -> Self {
+        self.fail_on_conflict = on;
+        self
+    }
+
+    pub fn excludes(mut self, patterns: Vec<String>) -> Self {
+        self.excludes = patterns;
+        self
+    }
+
+    /// Check the bundle signature with this public key file
+    pub fn verify_key(mut self, path: impl Into<PathBuf>, require_signature: bool) -> Self {
+        self.verify_key = Some(path.into());
+        self.require_signature = require_signature;
+        self
+    }
+
+    /// Install the bundle's extras into `dest`, or the repo root when None
+    pub fn with_extras(mut self, dest: Option<PathBuf>) -> Self {
+        self.with_extras = true;
+        self.extras_dest = dest;
+        self
+    }
+
+    pub fn allow_symlinks(mut self, on: bool) -> Self {
+        self.allow_symlinks = on;
+        self
+    }
+
+    /// Back up plugins before overwriting them, into `dir` or `<dest>/_backups`
+    pub fn backup(mut self, dir: Option<PathBuf>) -> Self {
+        self.backup = true;
+        self.backup_dir = dir;
+        self
+    }
+
+    pub fn no_verify(mut self, on: bool) -> Self {
+        self.no_verify = on;
+        self
+    }
+
+    pub fn assume_version(mut self, version: Option<u32>) -> Self {
+        self.assume_version = version;
+        self
+    }
+
+    pub fn apply_profiles(mut self, mode: core::ApplyProfiles) -> Self {
+        self.apply_profiles = mode;
+        self
+    }
+
+    /// Check the installed plugins afterwards; `strict` undoes this run's installs on errors
+    pub fn check(mut self, strict: bool) -> Self {
+        self.check = true;
+        self.check_strict = strict;
+        self
+    }
+
+    pub fn prune(mut self, on: bool) -> Self {
+        self.prune = on;
+        self
+    }
+
+    pub fn max_uncompressed_bytes(mut self, bytes: u64) -> Self {
+        self.max_uncompressed_bytes = bytes;
+        self
+    }
+
+    /// Extract into a staging directory first (`dir` or `<dest>/.neko_staging`)
+    pub fn staged(mut self, dir: Option<PathBuf>) -> Self {
+        self.staged = true;
+        self.staging_dir = dir;
+        self
+    }
+
+    /// Treat the zip as a plain plugin zip without manifest.toml
+    pub fn no_manifest(mut self, on: bool) -> Self {
+        self.no_manifest = on;
+        self
+    }
+
+    pub fn zip_path(&self) -> &Path {
+        &self.zip_path
+    }
+
+    pub fn repo_root(&self) -> Result<PathBuf> {
+        repo_root(self.root.as_deref())
+    }
+
+    pub fn dest_dir(&self) -> Result<PathBuf> {
+        match &self.dest {
+            Some(dest) => Ok(dest.clone()),
+            None => Ok(self.repo_root()?.join("plugin").join("plugins")),
+        }
+    }
+
+    /// The zip on disk: as given when it exists, else relative to the current directory or the
+    /// repo root
+    pub fn resolve_zip_path(&self) -> Result<PathBuf> {
+        let repo_root = self.repo_root()?;
+        resolve_zip_path(&self.zip_path, &repo_root)
+            .with_context(|| format!("failed to locate zip: {}", self.zip_path.display()))
+            .validation_err()
+    }
+
+    fn excludes_and_signature(&self) -> Result<(core::Excludes, Option<core::SignaturePolicy>)> {
+        let excludes = core::build_excludes(&self.excludes).validation_err()?;
+        let signature = match &self.verify_key {
+            Some(path) => Some(core::SignaturePolicy {
+                key: core::read_verifying_key(path).validation_err()?,
+                required: self.require_signature,
+            }),
+            None => None,
+        };
+        Ok((excludes, signature))
+    }
+}
+
+fn resolve_zip_path(input: &Path, repo_root: &Path) -> anyhow::Result<PathBuf> {
+    if input.is_absolute() {
+        return Ok(input.to_path_buf());
+    }
+
+    let cwd = std::env::current_dir().context("failed to get cwd")?;
+    let candidates = [
+        input.to_path_buf(),
+        cwd.join(input),
+        repo_root.join(input),
+        repo_root
+            .join("plugin")
+            .join("tool")
+            .join("neko_plugin_cli")
+            .join(input),
+    ];
+
+    for c in &candidates {
+        if c.is_file() {
+            return Ok(c.to_path_buf());
+        }
+    }
+
+    anyhow::bail!(
+        "zip not found; tried: {}",
+        candidates
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    )
+}
+
+/// Unpack a bundle into the plugin directory, like `neko_plugin_cli unpack`
+pub fn unpack(options: &UnpackOptions) -> Result<core::UnpackResult> {
+    unpack_with_progress(options, None)
+}
+
+pub fn unpack_with_progress(options: &UnpackOptions, progress: Option<core::UnpackProgressFn<'_>>) -> Result<core::UnpackResult> {
+    let zip_path = options.resolve_zip_path()?;
+    let (excludes, signature) = options.excludes_and_signature()?;
+    let dest_dir = options.dest_dir()?;
+    if options.fail_on_conflict {
+        let preview = core::preview_unpack_report(
+            &zip_path,
+            &dest_dir,
+            options.force,
+            &excludes,
+            signature.as_ref(),
+            options.assume_version,
+            options.no_manifest,
+        )
+        .bundle_err()?;
+        fail_on_conflicts(options, &preview)?;
+    }
+    let setup = UnpackSetup::new(options, &dest_dir)?;
+    core::unpack_zip(
+        &zip_path,
+        &dest_dir,
+        options.force,
+        &excludes,
+        signature.as_ref(),
+        setup.extras_dest.as_deref(),
+        options.allow_symlinks,
+        setup.backup_dir.as_deref(),
+        options.no_verify,
+        options.assume_version,
+        options.apply_profiles,
+        setup.check.as_ref(),
+        &options.force_folders,
+        options.prune,
+        options.max_uncompressed_bytes,
+        setup.staging_dir.as_deref(),
+        options.no_manifest,
+        progress,
+    )
+    .bundle_err()
+}
+
+/// unpack for a bundle that is not on disk, e.g. read from stdin; `source` names it in errors
+/// and the options' zip path is ignored
+pub fn unpack_reader<R: Read + Seek + Clone + Send + Sync>(
+    reader: R,
+    source: &Path,
+    options: &UnpackOptions,
+    progress: Option<core::UnpackProgressFn<'_>>,
+) -> Result<core::UnpackResult> {
+    let (excludes, signature) = options.excludes_and_signature()?;
+    let dest_dir = options.dest_dir()?;
+    if options.fail_on_conflict {
+        let preview = core::preview_reader(
+            reader.clone(),
+            source,
+            &dest_dir,
+            options.force,
+            &excludes,
+            signature.as_ref(),
+            options.assume_version,
+            options.no_manifest,
+        )
+        .bundle_err()?;
+        fail_on_conflicts(options, &preview)?;
+    }
+    let setup = UnpackSetup::new(options, &dest_dir)?;
+    core::unpack_reader(
+        reader,
+        source,
+        &dest_dir,
+        options.force,
+        &excludes,
+        signature.as_ref(),
+        setup.extras_dest.as_deref(),
+        options.allow_symlinks,
+        setup.backup_dir.as_deref(),
+        options.no_verify,
+        options.assume_version,
+        options.apply_profiles,
+        setup.check.as_ref(),
+        &options.force_folders,
+        options.prune,
+        options.max_uncompressed_bytes,
+        setup.staging_dir.as_deref(),
+        options.no_manifest,
+        progress,
+    )
+    .bundle_err()
+}
+
+/// What unpack would install or skip, without writing anything (`unpack --dry-run`)
+pub fn preview_unpack(options: &UnpackOptions) -> Result<core::UnpackPreview> {
+    let zip_path = options.resolve_zip_path()?;
+    let (excludes, signature) = options.excludes_and_signature()?;
+    core::preview_unpack_report(
+        &zip_path,
+        &options.dest_dir()?,
+        options.force,
+        &excludes,
+        signature.as_ref(),
+        options.assume_version,
+        options.no_manifest,
+    )
+    .bundle_err()
+}
+
+/// preview_unpack for a bundle that is not on disk
+pub fn preview_unpack_reader<R: Read + Seek>(reader: R, source: &Path, options: &UnpackOptions) -> Result<core::UnpackPreview> {
+    let (excludes, signature) = options.excludes_and_signature()?;
+    core::preview_reader(
+        reader,
+        source,
+        &options.dest_dir()?,
+        options.force,
+        &excludes,
+        signature.as_ref(),
+        options.assume_version,
+        options.no_manifest,
+    )
+    .bundle_err()
+}
+
+fn fail_on_conflicts(options: &UnpackOptions, preview: &core::UnpackPreview) -> Result<()> {
+    let plugins: Vec<String> = preview
+        .plugins
+        .iter()
+        .filter(|p| p.conflict && !p.will_install && !options.force_folders.contains(&p.folder))
+        .map(|p| p.id.clone())
+        .collect();
+    if plugins.is_empty() {
+        return Ok(());
+    }
+    Err(NekoCliError::Conflict {
+        message: format!(
+            "installed plugins differ from the bundle: {} (use --force to overwrite)",
+            plugins.join(", ")
+        ),
+        plugins,
+    })
+}
+
+/// The directories and post-install check derived from the unpack options
+struct UnpackSetup {
+    extras_dest: Option<PathBuf>,
+    backup_dir: Option<PathBuf>,
+    staging_dir: Option<PathBuf>,
+    check: Option<core::UnpackCheck>,
+}
+
+impl UnpackSetup {
+    fn new(options: &UnpackOptions, dest_dir: &Path) -> Result<Self> {
+        let needs_root = (options.with_extras && options.extras_dest.is_none()) || options.check || options.check_strict;
+        let repo_root = if needs_root { Some(options.repo_root()?) } else { None };
+        let extras_dest = match (&options.extras_dest, &repo_root) {
+            (Some(dir), _) if options.with_extras => Some(dir.clone()),
+            (None, Some(root)) if options.with_extras => Some(root.clone()),
+            _ => None,
+        };
+        let backup_dir = match &options.backup_dir {
+            Some(dir) => Some(dir.clone()),
+            None if options.backup => Some(dest_dir.join(core::UNPACK_BACKUP_DIR)),
+            None => None,
+        };
+        let staging_dir = match &options.staging_dir {
+            Some(dir) => Some(dir.clone()),
+            None if options.staged => Some(dest_dir.join(core::UNPACK_STAGING_DIR)),
+            None => None,
+        };
+        let check = match &repo_root {
+            Some(root) if options.check || options.check_strict => {
+                let mut checks = core::resolve_check_flags(false, false, false, false, false, false);
+                checks.strict_naming = core::load_repo_check_config(root).validation_err()?.strict_naming;
+                Some(core::UnpackCheck {
+                    sdk_version: core::read_sdk_version(root).validation_err()?,
+                    checks,
+                    strict: options.check_strict,
+                })
+            }
+            _ => None,
+        };
+        Ok(UnpackSetup {
+            extras_dest,
+            backup_dir,
+            staging_dir,
+            check,
+        })
+    }
+}
+
+/// Which checks to run, like `neko_plugin_cli check`; with no pass selected all of them run
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    root: Option<PathBuf>,
+    plugin_id: Option<String>,
+    passes: [bool; 6],
+    profile_warn_size: u64,
+    zip: Option<PathBuf>,
+    skip_disabled: bool,
+    cache: bool,
+    cache_dir: Option<PathBuf>,
+    fix: Option<bool>,
+    py_syntax: Option<bool>,
+    python: Option<bool>,
+    lock: Option<(PathBuf, bool)>,
+    baseline: Option<(PathBuf, bool)>,
+}
+
+/// A single check pass, for [`CheckOptions::pass`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckPass {
+    Id,
+    Deps,
+    Base,
+    Toml,
+    Entry,
+    Profiles,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CheckOptions {
+    pub fn new() -> Self {
+        CheckOptions {
+            root: None,
+            plugin_id: None,
+            passes: [false; 6],
+            profile_warn_size: core::PROFILE_WARN_SIZE,
+            zip: None,
+            skip_disabled: false,
+            cache: true,
+            cache_dir: None,
+            fix: None,
+            py_syntax: None,
+            python: None,
+            lock: None,
+            baseline: None,
+        }
+    }
+
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Check one plugin (dependencies and id conflicts still see all of them)
+    pub fn plugin(mut self, id: impl Into<String>) -> Self {
+        self.plugin_id = Some(id.into());
+        self
+    }
+
+    /// Run this pass; once any pass is selected only the selected ones run
+    pub fn pass(mut self, pass: CheckPass) -> Self {
+        self.passes[pass as usize] = true;
+        self
+    }
+
+    /// Warn about profile files larger than this; 0 turns it off
+    pub fn profile_warn_size(mut self, bytes: u64) -> Self {
+        self.profile_warn_size = bytes;
+        self
+    }
+
+    /// Check the plugins inside a bundle instead of the repo's
+    pub fn zip(mut self, path: impl Into<PathBuf>) -> Self {
+        self.zip = Some(path.into());
+        self
+    }
+
+    pub fn skip_disabled(mut self, on: bool) -> Self {
+        self.skip_disabled = on;
+        self
+    }
+
+    /// Reuse unchanged plugins' findings from the check cache (on by default)
+    pub fn cache(mut self, on: bool) -> Self {
+        self.cache = on;
+        self
+    }
+
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Rewrite plugin.toml for fixable findings first (`dry_run` only lists the edits);
+    /// the edits end up in the report's `fixes`
+    pub fn fix(mut self, dry_run: bool) -> Self {
+        self.fix = Some(dry_run);
+        self
+    }
+
+    /// Compile the plugins' .py files offline; `strict` makes a missing interpreter an error
+    pub fn py_syntax(mut self, strict: bool) -> Self {
+        self.py_syntax = Some(strict);
+        self
+    }
+
+    /// Resolve the plugins' Python dependencies with uv; `strict` makes a failure an error
+    pub fn python(mut self, strict: bool) -> Self {
+        self.python = Some(strict);
+        self
+    }
+
+    /// With [`python`](Self::python): write the lockfile, or compare against it when `verify`
+    pub fn lock(mut self, path: impl Into<PathBuf>, verify: bool) -> Self {
+        self.lock = Some((path.into(), verify));
+        self
+    }
+
+    /// Suppress the findings recorded in a baseline file; `write` records this run's findings first
+    pub fn baseline(mut self, path: impl Into<PathBuf>, write: bool) -> Self {
+        self.baseline = Some((path.into(), write));
+        self
+    }
+
+    pub fn repo_root(&self) -> Result<PathBuf> {
+        repo_root(self.root.as_deref())
+    }
+}
+
+/// Run the checks, like `neko_plugin_cli check --json`. Findings are in the report; an Err means
+/// the check itself could not run.
+pub fn check(options: &CheckOptions) -> Result<core::CheckReport> {
+    let repo_root = options.repo_root()?;
+    let plugins_dir = repo_root.join("plugin").join("plugins");
+    let plugin_id = options.plugin_id.as_deref();
+    let sdk_version = core::read_sdk_version(&repo_root).validation_err()?;
+
+    let [id, deps, base, toml, entry, profiles] = options.passes;
+    let mut checks = core::resolve_check_flags(id, deps, base, toml, entry, profiles);
+    checks.strict_naming = core::load_repo_check_config(&repo_root).validation_err()?.strict_naming;
+    checks.profile_warn_size = options.profile_warn_size;
+    checks.skip_disabled = options.skip_disabled;
+    // A bundle has no folders to hash, so its findings are never cached
+    let cache = if !options.cache || options.zip.is_some() {
+        None
+    } else {
+        Some(core::CheckCache::open(&repo_root, options.cache_dir.as_deref()).validation_err()?)
+    };
+    let fixes = match options.fix {
+        Some(dry_run) => {
+            let before = core::run_checks(&plugins_dir, plugin_id, &sdk_version, checks, cache.as_ref()).validation_err()?;
+            Some(core::fix_check_findings(&plugins_dir, &before, &sdk_version, dry_run).validation_err()?)
+        }
+        None => None,
+    };
+    // With fix this re-runs the check to report what remains
+    let mut report = match &options.zip {
+        Some(zip) => core::run_checks_zip(zip, plugin_id, &plugins_dir, &sdk_version, checks).bundle_err()?,
+        None => core::run_checks(&plugins_dir, plugin_id, &sdk_version, checks, cache.as_ref()).validation_err()?,
+    };
+    report.fixes = fixes;
+
+    if let Some(strict) = options.py_syntax {
+        let findings = core::run_py_syntax_check(&plugins_dir, plugin_id, strict, cache.as_ref()).validation_err()?;
+        report.attach_py_syntax(findings);
+    }
+    report.cache = cache.as_ref().map(core::CheckCache::stats);
+    if let Some(stats) = &report.cache {
+        for id in &stats.cached {
+            log::debug!("plugin {}: findings reused from cache", id);
+        }
+        for id in &stats.checked {
+            log::debug!("plugin {}: checked", id);
+        }
+    }
+
+    if let Some(strict) = options.python {
+        let mut online =
+            core::run_python_online_check(&repo_root, &plugins_dir, plugin_id, strict, options.cache_dir.as_deref())
+                .validation_err()?;
+        if let Some((path, verify)) = &options.lock {
+            core::apply_python_lock(&repo_root, &mut online, path, *verify).validation_err()?;
+            if !verify {
+                log::info!("wrote python lockfile {}", path.display());
+            }
+        }
+        report.attach_python_online(online);
+    }
+
+    if let Some((path, write)) = &options.baseline {
+        let known = if *write {
+            let known = report.to_baseline().validation_err()?;
+            known.write(path).validation_err()?;
+            log::info!("wrote {} finding(s) to baseline {}", known.findings.len(), path.display());
+            known
+        } else {
+            core::CheckBaseline::read(path).validation_err()?
+        };
+        report.apply_baseline(&known).validation_err()?;
+    }
+    Ok(report)
+}
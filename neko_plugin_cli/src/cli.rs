@@ -3,11 +3,45 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 
-use crate::core;
+use neko_plugin_cli::{api, core};
 use crate::tui;
+use crate::watch;
+
+/// `check` exit status: warnings with --warnings-as-errors (warnings alone exit 0)
+const CHECK_EXIT_WARNINGS: i32 = 1;
+/// `check` exit status: at least one error finding
+const CHECK_EXIT_ERRORS: i32 = 2;
+/// `check` exit status: the check itself could not run (unreadable repo, bad plugin.toml syntax, ...)
+const CHECK_EXIT_INTERNAL: i32 = 3;
+/// `verify` exit status: the bundle has integrity problems
+const VERIFY_EXIT_FAILED: i32 = 1;
+/// `verify` exit status: the zip or its manifest could not be read at all
+const VERIFY_EXIT_UNREADABLE: i32 = 2;
+
+/// An error that ends the process with `code` instead of 1
+#[derive(Debug)]
+pub(crate) struct ExitStatus {
+    pub(crate) code: i32,
+    message: String,
+}
+
+impl ExitStatus {
+    fn new(code: i32, message: String) -> Self {
+        ExitStatus { code, message }
+    }
+}
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ExitStatus {}
 
 pub(crate) fn run() -> Result<()> {
     let cli = Cli::parse();
+    init_logging(cli.quiet, cli.verbose);
 
     match cli.command {
         Commands::Add { left, right } => {
@@ -17,17 +51,26 @@ pub(crate) fn run() -> Result<()> {
         Commands::Version => {
             println!("{}", neko_plugin_cli::version());
         }
-        Commands::Info { root, json } => {
-            let info = core::collect_info(root.as_deref())?;
-            if json {
-                println!("{}", serde_json::to_string_pretty(&info)?);
-            } else {
-                println!("N.E.K.O version: {}", info.neko_version);
-                println!("Repo root: {}", info.repo_root.display());
-                println!("Plugin count: {}", info.plugins.len());
-                for p in &info.plugins {
-                    println!("- {} v{} ({})", p.id, p.version, p.entry);
-                }
+        Commands::Info { root, json, format, columns, sizes, sort, compat, bundles } => {
+            let info = core::collect_info(root.as_deref(), core::InfoOptions { sizes, sort, compat, bundles })?;
+            let format = if json { core::InfoFormat::Json } else { format };
+            print!("{}", core::render_info(&info, format, columns.as_deref())?);
+        }
+        Commands::List {
+            root,
+            id_match,
+            version_req,
+            entry_contains,
+            json,
+            format,
+            fail_if_empty,
+        } => {
+            let format = if json { core::ListFormat::Json } else { format };
+            let filter = core::PluginFilter::new(id_match.as_deref(), version_req.as_deref(), entry_contains.as_deref())?;
+            let plugins = core::list_plugins(root.as_deref(), &filter)?;
+            print!("{}", core::render_plugin_list(&plugins, format)?);
+            if fail_if_empty && plugins.is_empty() {
+                anyhow::bail!("no plugins matched (--fail-if-empty)");
             }
         }
         Commands::Pack {
@@ -36,7 +79,34 @@ pub(crate) fn run() -> Result<()> {
             out,
             jobs,
             exclude,
+            exclude_profile,
+            respect_gitignore,
+            symlinks,
+            no_hash_cache,
+            cache_dir,
+            include_root,
             no_md5,
+            hash,
+            manifest_version,
+            compression,
+            compression_level,
+            reproducible,
+            progress,
+            allow_invalid,
+            with_deps,
+            include_disabled,
+            base,
+            sign_key,
+            verify,
+            split,
+            fail_fast,
+            max_size,
+            warn_size,
+            max_name_len,
+            spec,
+            dry_run,
+            list_only,
+            json,
             bundle_name,
             bundle_version,
             bundle_author,
@@ -45,33 +115,121 @@ pub(crate) fn run() -> Result<()> {
                 rayon::ThreadPoolBuilder::new().num_threads(n).build_global().ok();
             }
 
-            let repo_root = match root {
-                Some(p) => p,
-                None => core::find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
-            };
+            let mut options = api::PackOptions::new()
+                .plugins(plugin_id)
+                .excludes(exclude)
+                .include_root(include_root)
+                .bundle(core::BundleMeta {
+                    name: bundle_name,
+                    version: bundle_version,
+                    author: bundle_author,
+                })
+                .respect_gitignore(respect_gitignore)
+                .symlinks(symlinks)
+                .hash_cache(!no_hash_cache)
+                .no_hash(no_md5)
+                .source_date_epoch(core::resolve_source_date_epoch(reproducible)?)
+                .allow_invalid(allow_invalid)
+                .with_deps(with_deps)
+                .include_disabled(include_disabled)
+                .verify(verify)
+                .max_size(max_size)
+                .warn_size(warn_size)
+                .max_name_len(max_name_len);
+            if let Some(root) = root {
+                options = options.root(root);
+            }
+            if let Some(out) = out {
+                options = options.out(out);
+            }
+            if let Some(profile) = exclude_profile {
+                options = options.exclude_profile(profile);
+            }
+            if let Some(dir) = cache_dir {
+                options = options.cache_dir(dir);
+            }
+            if let Some(hash) = hash {
+                options = options.hash(hash);
+            }
+            if let Some(version) = manifest_version {
+                options = options.manifest_version(version);
+            }
+            if let Some(method) = compression {
+                options = options.compression(method);
+            }
+            if let Some(level) = compression_level {
+                options = options.compression_level(level);
+            }
+            if let Some(base) = base {
+                options = options.base(base);
+            }
+            if let Some(key) = sign_key {
+                options = options.sign_key(key);
+            }
+            if let Some(spec) = spec {
+                options = options.spec(spec);
+            }
+            let prepared = options.prepare()?;
+
+            if list_only {
+                println!("{}", serde_json::to_string_pretty(&prepared.manifest()?)?);
+                return Ok(());
+            }
+            if dry_run {
+                // Hashing reads every file, which is exactly what a dry run should avoid
+                let report = prepared.dry_run()?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print_pack_dry_run(&report)?;
+                    for w in &report.budget_warnings {
+                        log::warn!("{}", w);
+                    }
+                }
+                return Ok(());
+            }
 
-            let plugins_dir = repo_root.join("plugin").join("plugins");
-            let excludes = core::build_excludes(&exclude)?;
+            let printer = PackProgressPrinter::new();
+            let on_progress = |ev: core::PackProgress<'_>| printer.on_event(ev);
+            let progress_cb: Option<core::ProgressFn<'_>> = if progress { Some(&on_progress) } else { None };
 
-            let plugin_ids_ref: Option<&[String]> = if plugin_id.is_empty() { None } else { Some(&plugin_id) };
-            let mut plugins = core::scan_plugins_for_pack(&plugins_dir, plugin_ids_ref)?;
-            if plugins.is_empty() {
-                anyhow::bail!("no plugins found to pack");
+            if split {
+                let outputs = prepared.write_split(fail_fast, progress_cb)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&outputs)?);
+                } else {
+                    for (id, output) in &outputs {
+                        match output {
+                            core::SplitOutput::Packed { path, size } => {
+                                println!("{} -> {} ({})", id, path.display(), core::human_bytes(*size))
+                            }
+                            core::SplitOutput::Failed { error } => log::error!("plugin '{}': {}", id, error),
+                        }
+                    }
+                }
+                let failed = outputs
+                    .values()
+                    .filter(|o| matches!(o, core::SplitOutput::Failed { .. }))
+                    .count();
+                if failed > 0 {
+                    anyhow::bail!("{} of {} plugins failed to pack", failed, outputs.len());
+                }
+                return Ok(());
             }
 
-            core::compute_plugin_md5_for_pack(&mut plugins, &excludes, no_md5)?;
+            if core::is_stream_path(prepared.out_path()) {
+                // stdout carries the zip, so everything human-readable goes to stderr
+                let mut stdout = std::io::stdout().lock();
+                if std::io::IsTerminal::is_terminal(&stdout) {
+                    anyhow::bail!("refusing to write a zip to a terminal; redirect or pipe --out -");
+                }
+                let report = prepared.write_to(&mut stdout, progress_cb)?;
+                log::info!("wrote {} to stdout", core::human_bytes(report.size));
+                return Ok(());
+            }
 
-            let out_path = out.unwrap_or_else(|| core::default_pack_output(&plugins, !plugin_id.is_empty()));
-            core::pack_to_zip(
-                &out_path,
-                &plugins,
-                &excludes,
-                core::BundleMeta {
-                    name: bundle_name,
-                    version: bundle_version,
-                    author: bundle_author,
-                },
-            )?;
+            let out_path = prepared.out_path().to_path_buf();
+            prepared.write(progress_cb)?;
             println!("{}", out_path.display());
         }
         Commands::Check {
@@ -81,73 +239,526 @@ pub(crate) fn run() -> Result<()> {
             id,
             deps,
             base,
+            toml,
+            entry,
+            profiles,
+            profile_warn_size,
+            zip,
+            graph,
+            warnings_as_errors,
+            baseline,
+            write_baseline,
+            fix,
+            dry_run,
+            py_syntax,
+            py_syntax_strict,
             python,
             python_strict,
+            lock,
+            verify_lock,
             cache_dir,
+            no_cache,
+            skip_disabled,
+        } => {
+            let report = (|| -> Result<core::CheckReport> {
+                let mut options = api::CheckOptions::new()
+                    .profile_warn_size(profile_warn_size)
+                    .skip_disabled(skip_disabled)
+                    .cache(!no_cache);
+                for (on, pass) in [
+                    (id, api::CheckPass::Id),
+                    (deps, api::CheckPass::Deps),
+                    (base, api::CheckPass::Base),
+                    (toml, api::CheckPass::Toml),
+                    (entry, api::CheckPass::Entry),
+                    (profiles, api::CheckPass::Profiles),
+                ] {
+                    if on {
+                        options = options.pass(pass);
+                    }
+                }
+                if let Some(root) = root {
+                    options = options.root(root);
+                }
+                if let Some(id) = &plugin_id {
+                    options = options.plugin(id);
+                }
+                if let Some(zip) = zip {
+                    options = options.zip(zip);
+                }
+                if let Some(dir) = cache_dir {
+                    options = options.cache_dir(dir);
+                }
+                if fix {
+                    options = options.fix(dry_run);
+                }
+                if py_syntax {
+                    options = options.py_syntax(py_syntax_strict);
+                }
+                if python {
+                    options = options.python(python_strict);
+                }
+                if let Some(path) = lock {
+                    options = options.lock(path, verify_lock);
+                }
+                if let Some(path) = baseline {
+                    options = options.baseline(path, write_baseline);
+                }
+                let report = api::check(&options)?;
+                if let Some(fixes) = &report.fixes {
+                    print_fixes(fixes, dry_run, json || graph.is_some());
+                }
+
+                if let Some(format) = graph {
+                    // stdout carries only the graph so it can be piped into dot
+                    let plugins_dir = options.repo_root()?.join("plugin").join("plugins");
+                    print!("{}", core::dependency_graph(&plugins_dir, plugin_id.as_deref(), format)?);
+                    for e in &report.errors {
+                        log::error!("{}", e);
+                    }
+                    for w in &report.warnings {
+                        log::warn!("{}", w);
+                    }
+                } else if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print_check_report(&report);
+                }
+                Ok(report)
+            })()
+            .map_err(|e| ExitStatus::new(CHECK_EXIT_INTERNAL, format!("{e:#}")))?;
+
+            if !report.errors.is_empty() {
+                return Err(ExitStatus::new(CHECK_EXIT_ERRORS, "check failed".to_string()).into());
+            }
+            if warnings_as_errors && !report.warnings.is_empty() {
+                return Err(ExitStatus::new(
+                    CHECK_EXIT_WARNINGS,
+                    "check found warnings (--warnings-as-errors)".to_string(),
+                )
+                .into());
+            }
+        }
+        Commands::Unpack {
+            zip_path,
+            root,
+            dest,
+            force,
+            exclude,
+            verify_key,
+            require_signature,
+            with_extras,
+            extras_dest,
+            allow_symlinks,
+            backup,
+            backup_dir,
+            no_verify,
+            assume_version,
+            apply_profiles,
+            check,
+            check_strict,
+            interactive,
+            prune,
+            max_uncompressed_bytes,
+            staged,
+            staging_dir,
+            no_manifest,
+            jobs,
+            report,
+            dry_run,
+            json,
+        } => {
+            if let Some(n) = jobs {
+                rayon::ThreadPoolBuilder::new().num_threads(n).build_global().ok();
+            }
+
+            let mut options = api::UnpackOptions::new(&zip_path)
+                .force(force)
+                .excludes(exclude)
+                .allow_symlinks(allow_symlinks)
+                .no_verify(no_verify)
+                .assume_version(assume_version)
+                .apply_profiles(apply_profiles)
+                .prune(prune)
+                .max_uncompressed_bytes(max_uncompressed_bytes)
+                .no_manifest(no_manifest);
+            if let Some(root) = root {
+                options = options.root(root);
+            }
+            if let Some(dest) = dest {
+                options = options.dest(dest);
+            }
+            if let Some(key) = verify_key {
+                options = options.verify_key(key, require_signature);
+            }
+            if with_extras {
+                options = options.with_extras(extras_dest);
+            }
+            if backup || backup_dir.is_some() {
+                options = options.backup(backup_dir);
+            }
+            if staged || staging_dir.is_some() {
+                options = options.staged(staging_dir);
+            }
+            if check || check_strict {
+                options = options.check(check_strict);
+            }
+            let dest_dir = options.dest_dir()?;
+            let stream = core::is_stream_path(&zip_path);
+            if dry_run {
+                let preview = if stream {
+                    let mut data = Vec::new();
+                    std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut data).context("failed to read zip from stdin")?;
+                    api::preview_unpack_reader(std::io::Cursor::new(data), Path::new("<stdin>"), &options)?
+                } else {
+                    api::preview_unpack(&options)?
+                };
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&preview)?);
+                } else {
+                    print_unpack_dry_run(&preview, &dest_dir);
+                }
+                return Ok(());
+            }
+            // Prompts need a person at the terminal; a zip read from stdin also rules them out
+            if interactive
+                && !json
+                && !stream
+                && std::io::IsTerminal::is_terminal(&std::io::stdin())
+                && std::io::IsTerminal::is_terminal(&std::io::stdout())
+            {
+                let preview = api::preview_unpack(&options.clone().force(false))?;
+                match prompt_unpack_conflicts(&preview.plugins, &mut std::io::stdin().lock(), &mut std::io::stdout())? {
+                    Some(folders) => options = options.force_folders(folders),
+                    None => anyhow::bail!("unpack aborted; nothing was installed"),
+                }
+            }
+            // The progress line rewrites itself with \r, so it is only drawn on a terminal
+            let printer = UnpackProgressPrinter::new();
+            let on_progress = |ev: core::UnpackProgress<'_>| printer.on_event(ev);
+            let show_progress = log::log_enabled!(log::Level::Info) && !json && std::io::IsTerminal::is_terminal(&std::io::stderr());
+            let progress_cb: Option<core::UnpackProgressFn<'_>> = if show_progress { Some(&on_progress) } else { None };
+            let started = std::time::Instant::now();
+            // Everything up to the result is collected so --report also records a failed run
+            let outcome = if stream {
+                let mut data = Vec::new();
+                std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut data)
+                    .context("failed to read zip from stdin")
+                    .and_then(|_| {
+                        let reader = std::io::Cursor::new(std::sync::Arc::<[u8]>::from(data));
+                        Ok(api::unpack_reader(reader, Path::new("<stdin>"), &options, progress_cb)?)
+                    })
+            } else {
+                api::unpack_with_progress(&options, progress_cb).map_err(anyhow::Error::from)
+            };
+            if outcome.is_err() {
+                printer.clear();
+            }
+            if let Some(path) = &report {
+                let source = if stream {
+                    "<stdin>".to_string()
+                } else {
+                    zip_path.display().to_string()
+                };
+                let written = core::UnpackReport::new(&source, &dest_dir, started.elapsed(), &outcome).write(path);
+                match written {
+                    Err(e) if outcome.is_ok() => return Err(e),
+                    Err(e) => log::warn!("{:#}", e),
+                    Ok(()) => {}
+                }
+            }
+            let result = outcome?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+                return Ok(());
+            }
+            if let Some(fingerprint) = &result.signed_by {
+                log::info!("signature verified (key {})", fingerprint);
+            }
+            for w in &result.warnings {
+                log::warn!("{}", w);
+            }
+            for p in &result.installed {
+                match p.verification {
+                    Some(v) => log::info!("plugin '{}' {}; {}", p.id, p.reason, v.describe()),
+                    None => log::info!("plugin '{}' {}", p.id, p.reason),
+                }
+            }
+            for p in result.installed.iter().filter(|p| !p.filtered.is_empty()) {
+                log::info!("plugin '{}' skipped {} file(s) by --exclude", p.id, p.filtered.len());
+            }
+            for p in result.installed.iter().filter(|p| !p.pruned.is_empty()) {
+                log::info!("plugin '{}' pruned {} file(s) the bundle no longer has", p.id, p.pruned.len());
+                for f in &p.pruned {
+                    log::debug!("plugin '{}' pruned {}", p.id, f);
+                }
+            }
+            for p in &result.skipped {
+                log::info!("plugin '{}' skipped: {}", p.id, p.reason);
+                for f in &p.changed_files {
+                    log::debug!("plugin '{}' {:?}: {}", p.id, f.kind, f.path);
+                }
+            }
+            for e in &result.extras_installed {
+                log::info!("extra '{}' -> {} ({})", e.path, e.target.display(), e.reason);
+            }
+            for e in &result.extras_skipped {
+                log::info!("extra '{}' skipped: {}", e.path, e.reason);
+            }
+            for l in &result.symlinks {
+                log::info!("symlink {} -> {}", l.path, l.target);
+            }
+            for a in &result.profiles {
+                log::info!("profile '{}' of plugin '{}' {}", a.path, a.id, a.status.describe());
+            }
+            for b in &result.backups {
+                log::info!("plugin '{}' backed up to {}", b.id, b.path.display());
+            }
+            if let Some(report) = &result.check {
+                log::info!(
+                    "checked {} installed plugin(s) against SDK_VERSION {}: {} error(s), {} warning(s)",
+                    report.plugins_checked,
+                    report.sdk_version,
+                    report.errors.len(),
+                    report.warnings.len()
+                );
+                for e in &report.errors {
+                    log::error!("{}", e);
+                }
+                for w in &report.warnings {
+                    log::warn!("{}", w);
+                }
+            }
+            println!("{}", dest_dir.display());
+        }
+
+        Commands::New {
+            id,
+            root,
+            name,
+            author,
+            sdk_supported,
+            template,
+        } => {
+            let options = core::ScaffoldOptions {
+                name,
+                author,
+                sdk_supported,
+                template,
+            };
+            let created = core::scaffold_plugin(root.as_deref(), &id, &options)?;
+            println!("created {}", created.path.display());
+            for file in &created.files {
+                println!("  {}", file);
+            }
+        }
+
+        Commands::Remove {
+            plugin_id,
+            root,
+            force,
+            dry_run,
+            backup,
+            backup_dir,
+            json,
         } => {
             let repo_root = match root {
                 Some(p) => p,
                 None => core::find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
             };
+            let backup_dir = match backup_dir {
+                Some(dir) => Some(dir),
+                None if backup => Some(repo_root.join("plugin").join("plugins").join(core::UNPACK_BACKUP_DIR)),
+                None => None,
+            };
+            let options = core::RemoveOptions {
+                force,
+                dry_run,
+                backup_dir,
+            };
+            let report = core::remove_plugins(Some(&repo_root), &plugin_id, &options)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                for d in &report.dependents {
+                    log::warn!("plugin '{}' depends on '{}'", d.id, d.depends_on);
+                }
+                let verb = if report.dry_run { "would remove" } else { "removed" };
+                for r in &report.removed {
+                    if let Some(path) = &r.backup {
+                        log::info!("plugin '{}' backed up to {}", r.id, path.display());
+                    }
+                    println!("{} {} {} ({})", verb, r.id, r.version, r.path.display());
+                }
+            }
+        }
 
-            let plugins_dir = repo_root.join("plugin").join("plugins");
-            let sdk_version = core::read_sdk_version(&repo_root)?;
+        Commands::Watch {
+            plugin_id,
+            root,
+            debounce,
+            exec,
+        } => {
+            watch::run(root, plugin_id, std::time::Duration::from_millis(debounce), exec)?;
+        }
 
-            let checks = core::resolve_check_flags(id, deps, base);
-            let mut report = core::run_checks(&plugins_dir, plugin_id.as_deref(), &sdk_version, checks)?;
+        Commands::Disable { plugin_id, root } => {
+            let report = core::set_plugins_disabled(root.as_deref(), &plugin_id, true)?;
+            print_toggle_report(&report);
+        }
 
-            if python {
-                let (py_rep, mut py_errs, mut py_warns) = core::run_python_online_check(
-                    &repo_root,
-                    &plugins_dir,
-                    plugin_id.as_deref(),
-                    python_strict,
-                    cache_dir.as_deref(),
-                )?;
-                report.errors.append(&mut py_errs);
-                report.warnings.append(&mut py_warns);
-                report.python_online = Some(py_rep);
-                report.errors.sort();
-                report.warnings.sort();
-            }
+        Commands::Enable { plugin_id, root } => {
+            let report = core::set_plugins_disabled(root.as_deref(), &plugin_id, false)?;
+            print_toggle_report(&report);
+        }
 
+        Commands::Clean {
+            root,
+            cache,
+            tmp,
+            all,
+            dry_run,
+            cache_dir,
+            json,
+        } => {
+            let repo_root = match root {
+                Some(p) => p,
+                None => core::find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
+            };
+            let options = core::CleanOptions {
+                cache: cache || all,
+                tmp: tmp || all,
+                dry_run,
+                cache_dir,
+            };
+            let report = core::clean(&repo_root, &options)?;
             if json {
                 println!("{}", serde_json::to_string_pretty(&report)?);
             } else {
-                println!("SDK_VERSION: {}", report.sdk_version);
-                println!("Plugins checked: {}", report.plugins_checked);
-                println!("Errors: {}", report.errors.len());
-                println!("Warnings: {}", report.warnings.len());
-                for e in &report.errors {
-                    println!("ERROR: {}", e);
+                for s in &report.skipped {
+                    log::warn!("skipped {}: {}", s.path.display(), s.reason);
                 }
-                for w in &report.warnings {
-                    println!("WARN: {}", w);
+                let verb = if report.dry_run { "would remove" } else { "removed" };
+                for e in &report.removed {
+                    println!("{} {} ({})", verb, e.path.display(), core::human_bytes(e.bytes));
                 }
+                let total = if report.dry_run { "reclaimable" } else { "reclaimed" };
+                println!("{} {} in {} item(s)", total, core::human_bytes(report.bytes), report.removed.len());
             }
+        }
 
-            if !report.errors.is_empty() {
-                anyhow::bail!("check failed");
+        Commands::Diff {
+            old,
+            new,
+            against_installed,
+            root,
+            dest,
+            json,
+        } => {
+            let diff = match new {
+                Some(new) => core::diff_bundles(&old, &new)?,
+                None => {
+                    debug_assert!(against_installed);
+                    let dest_dir = match dest {
+                        Some(dir) => dir,
+                        None => {
+                            let repo_root = match root {
+                                Some(p) => p,
+                                None => core::find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
+                            };
+                            repo_root.join("plugin").join("plugins")
+                        }
+                    };
+                    core::diff_bundle_installed(&old, &dest_dir)?
+                }
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{}", core::format_bundle_diff(&diff));
             }
         }
-        Commands::Unpack {
+
+        Commands::Verify {
             zip_path,
-            root,
-            dest,
-            force,
+            verify_key,
+            require_signature,
+            json,
+        } => {
+            let signature = match verify_key {
+                Some(path) => Some(core::SignaturePolicy {
+                    key: core::read_verifying_key(&path)?,
+                    required: require_signature,
+                }),
+                None => None,
+            };
+            let report = core::verify_bundle_report(&zip_path, signature.as_ref())
+                .map_err(|e| ExitStatus::new(VERIFY_EXIT_UNREADABLE, format!("{e:#}")))?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_verify_report(&report, &zip_path);
+            }
+            if !report.issues.is_empty() {
+                return Err(ExitStatus::new(VERIFY_EXIT_FAILED, "verify failed".to_string()).into());
+            }
+        }
+
+        Commands::Migrate {
+            zip_path,
+            out,
+            in_place,
+            format_version,
+            sign_key,
+            reproducible,
+            bundle_name,
+            bundle_version,
+            bundle_author,
+            json,
         } => {
+            let out = match out {
+                Some(p) => p,
+                None => {
+                    debug_assert!(in_place);
+                    zip_path.clone()
+                }
+            };
+            let options = core::MigrateOptions {
+                format_version,
+                bundle: core::BundleMeta {
+                    name: bundle_name,
+                    version: bundle_version,
+                    author: bundle_author,
+                },
+                sign_key: sign_key.as_deref().map(core::read_signing_key).transpose()?,
+                source_date_epoch: core::resolve_source_date_epoch(reproducible)?,
+            };
+            let report = core::migrate_bundle(&zip_path, &out, &options)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_migrate_report(&report);
+            }
+        }
+
+        Commands::Deps { plugin_id, root, json } => {
             let repo_root = match root {
                 Some(p) => p,
                 None => core::find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
             };
+            let tree = core::dependency_tree(&repo_root.join("plugin").join("plugins"), plugin_id.as_deref())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tree)?);
+            } else {
+                print!("{}", core::format_dependency_tree(&tree));
+            }
+        }
 
-            let dest_dir = dest.unwrap_or_else(|| repo_root.join("plugin").join("plugins"));
-            let excludes = core::build_excludes(&[])?;
-
-            let zip_path = resolve_zip_path(&zip_path, &repo_root)
-                .with_context(|| format!("failed to locate zip: {}", zip_path.display()))?;
-            core::unpack_zip(&zip_path, &dest_dir, force, &excludes)?;
-            println!("{}", dest_dir.display());
+        Commands::Keygen { out, force } => {
+            let key = core::generate_signing_key(&out, force)?;
+            println!("secret key: {}", key.secret_key.display());
+            println!("public key: {}", key.public_key.display());
+            println!("fingerprint: {}", key.fingerprint);
         }
 
         Commands::Tui { root } => {
@@ -164,9 +775,45 @@ pub(crate) fn run() -> Result<()> {
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    #[arg(short, long, global = true, conflicts_with = "verbose", help = "只输出错误（进度与 INFO/WARN 日志都不显示） / Only print errors (no progress, INFO or WARN log lines)")]
+    quiet: bool,
+
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, help = "输出更多日志（-v 调试，-vv 追踪） / Print more log lines (-v debug, -vv trace)")]
+    verbose: u8,
+}
+
+/// Log lines go to stderr as `LEVEL: message`, so stdout stays machine-readable. -q/-v set this
+/// crate's level; without them RUST_LOG applies, and the default is INFO here and WARN elsewhere.
+fn init_logging(quiet: bool, verbose: u8) {
+    let flag_level = match (quiet, verbose) {
+        (true, _) => Some(log::LevelFilter::Error),
+        (false, 0) => None,
+        (false, 1) => Some(log::LevelFilter::Debug),
+        (false, _) => Some(log::LevelFilter::Trace),
+    };
+    let mut builder = env_logger::Builder::new();
+    if std::env::var_os("RUST_LOG").is_some() {
+        builder.parse_default_env();
+    } else {
+        builder
+            .filter_level(log::LevelFilter::Warn)
+            .filter_module("neko_plugin_cli", log::LevelFilter::Info);
+    }
+    if let Some(level) = flag_level {
+        builder.filter_module("neko_plugin_cli", level);
+    }
+    builder
+        .format(|buf, record| {
+            use std::io::Write;
+            writeln!(buf, "{}: {}", record.level(), record.args())
+        })
+        .target(env_logger::Target::Stderr)
+        .init();
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     #[command(about = "自检：验证 CLI 与库函数连通性 / Sanity: verify CLI and library wiring")]
     Add {
@@ -184,11 +831,54 @@ enum Commands {
         #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
         root: Option<PathBuf>,
 
-        #[arg(long, help = "输出 JSON / Output JSON")]
+        #[arg(long, conflicts_with = "format", help = "输出 JSON（同 --format json） / Output JSON (same as --format json)")]
+        json: bool,
+
+        #[arg(long, value_enum, default_value_t = core::InfoFormat::Text, help = "输出格式：text、table（按列对齐）、json 或 yaml / Output format: text, table (aligned columns), json or yaml")]
+        format: core::InfoFormat,
+
+        #[arg(long, value_enum, value_delimiter = ',', help = "要显示的插件字段及顺序（逗号分隔，默认 id,version,entry,folder，--sizes 时再加 files,size,excluded，--compat 时再加 compat） / Plugin fields to show, in order (comma-separated, default id,version,entry,folder, plus files,size,excluded with --sizes and compat with --compat)")]
+        columns: Option<Vec<core::InfoColumn>>,
+
+        #[arg(long, help = "统计每个插件的文件数与大小（按默认排除规则，另列被排除的字节数）及总计 / Count each plugin's files and bytes (after the default excludes, with the excluded bytes listed separately) plus totals")]
+        sizes: bool,
+
+        #[arg(long, value_enum, default_value_t = core::InfoSort::Id, help = "插件排序：id 或 size（从大到小，隐含 --sizes） / Plugin order: id or size (largest first, implies --sizes)")]
+        sort: core::InfoSort,
+
+        #[arg(long, help = "按当前 SDK_VERSION 标出每个插件的兼容性（ok/untested/unsupported/conflict，与 check 的 base 检查一致） / Mark each plugin's compatibility with the current SDK_VERSION (ok/untested/unsupported/conflict, as check's base pass sees it)")]
+        compat: bool,
+
+        #[arg(long, help = "按来源整合包汇总已安装插件（unpack 写入的来源记录：安装时间、之后是否被修改） / Group installed plugins by the bundle they came from (unpack's provenance records: when installed, whether modified since)")]
+        bundles: bool,
+    },
+
+    #[command(about = "按 id/版本/entry 筛选并列出插件 / List plugins, filtered by id, version or entry")]
+    List {
+        #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+
+        #[arg(long = "match", value_name = "REGEX", help = "只列出 id 完整匹配该正则的插件（如 'chat.*'） / Only plugins whose whole id matches this regex (e.g. 'chat.*')")]
+        id_match: Option<String>,
+
+        #[arg(long, value_name = "REQ", help = "只列出版本满足该 semver 范围的插件（如 '>=1.2, <2'） / Only plugins whose version satisfies this semver requirement (e.g. '>=1.2, <2')")]
+        version_req: Option<String>,
+
+        #[arg(long, value_name = "TEXT", help = "只列出 entry 包含该文本的插件 / Only plugins whose entry contains this text")]
+        entry_contains: Option<String>,
+
+        #[arg(long, conflicts_with = "format", help = "输出 JSON（同 --format json） / Output JSON (same as --format json)")]
         json: bool,
+
+        #[arg(long, value_enum, default_value_t = core::ListFormat::Ids, help = "输出格式：ids（每行一个 id）、json 或 table / Output format: ids (one per line), json or table")]
+        format: core::ListFormat,
+
+        #[arg(long, help = "没有插件匹配时以状态 1 退出（用于 CI） / Exit with status 1 when no plugin matches (for CI)")]
+        fail_if_empty: bool,
     },
 
-    #[command(about = "打包插件为 zip（含 manifest 与 md5） / Pack plugins into zip (with manifest + md5)")]
+    #[command(about = "打包插件为 zip（含 manifest 与哈希） / Pack plugins into zip (with manifest + hash)")]
+    #[command(group(clap::ArgGroup::new("json_report").args(["dry_run", "split", "list_only"]).multiple(true)))]
     Pack {
         #[arg(help = "插件 ID（可多次指定；省略则打包全部插件） / Plugin id(s) (repeatable; omit to pack all)")]
         plugin_id: Vec<String>,
@@ -196,18 +886,99 @@ enum Commands {
         #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
         root: Option<PathBuf>,
 
-        #[arg(long, help = "输出 zip 路径（可选） / Output zip path (optional)")]
+        #[arg(long, help = "输出 zip 路径（可选；- 表示写到 stdout） / Output zip path (optional; - streams the zip to stdout)")]
         out: Option<PathBuf>,
 
-        #[arg(long, help = "md5 计算并行度（可选） / Parallel jobs for md5 (optional)")]
+        #[arg(long, help = "哈希计算并行度（可选） / Parallel jobs for hashing (optional)")]
         jobs: Option<usize>,
 
         #[arg(long, help = "额外排除 glob（可多次指定） / Extra exclude globs (repeatable)")]
         exclude: Vec<String>,
 
-        #[arg(long, help = "跳过 md5（更快但无法用于一致性跳过） / Skip md5 (faster, but no identical-skip)")]
+        #[arg(long, value_enum, help = "内置排除方案：minimal（默认，缓存/VCS/日志）、dev（另排除工具缓存与 node_modules）、release（另排除 tests、docs、*.md 与示例数据） / Built-in exclusion profile: minimal (default: caches, VCS, logs), dev (also tool caches and node_modules), release (also tests, docs, *.md and example data)")]
+        exclude_profile: Option<core::ExcludeProfile>,
+
+        #[arg(long, help = "同时按仓库与插件目录中的 .gitignore 排除文件（哈希与打包一致） / Also exclude files ignored by .gitignore files in the repo and plugin folders (applies to hashing and packing alike)")]
+        respect_gitignore: bool,
+
+        #[arg(long, value_enum, default_value_t, help = "插件目录中符号链接的处理方式：skip 跳过，follow 按普通文件打包其内容，error 报错并列出 / How to treat symlinks in plugin folders: skip them, follow them (packed as regular content) or error listing them")]
+        symlinks: core::SymlinkPolicy,
+
+        #[arg(long, help = "不使用哈希缓存（按大小与修改时间复用未变文件的摘要），每次重新读取全部文件 / Do not use the hash cache (which reuses digests of files with unchanged size and mtime); re-read every file")]
+        no_hash_cache: bool,
+
+        #[arg(long, conflicts_with = "no_hash_cache", help = "覆盖哈希缓存目录 / Override the hash cache dir")]
+        cache_dir: Option<PathBuf>,
+
+        #[arg(long, value_name = "PATH[:ZIP_PATH]", help = "把仓库根目录下的文件放入 bundle 的 extras/（可多次指定） / Add a repo-level file under extras/ in the bundle (repeatable)")]
+        include_root: Vec<String>,
+
+        #[arg(long, visible_alias = "no-hash", help = "跳过哈希（更快但无法用于一致性跳过） / Skip hashing (faster, but no identical-skip)")]
         no_md5: bool,
 
+        #[arg(long, value_enum, help = "插件目录哈希算法（写入 manifest，用于一致性跳过；默认 md5） / Folder hash algorithm (written to manifest, used for identical-skip; default md5)")]
+        hash: Option<core::HashAlgo>,
+
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=core::MANIFEST_VERSION_MAX as i64), help = "manifest 格式版本（2 含逐文件 sha256 校验；默认 1） / Manifest format version (2 adds per-file sha256 checksums; default 1)")]
+        manifest_version: Option<u32>,
+
+        #[arg(long, value_enum, help = "压缩方式（zstd 需以 zstd feature 编译；默认 deflate） / Compression method (zstd requires the zstd feature; default deflate)")]
+        compression: Option<core::CompressionKind>,
+
+        #[arg(long, allow_negative_numbers = true, help = "压缩级别（deflate/bzip2: 1-9，zstd: -7-22，stored 不可用） / Compression level (deflate/bzip2: 1-9, zstd: -7..22, not for stored)")]
+        compression_level: Option<i64>,
+
+        #[arg(long, help = "可复现输出：固定时间戳（取 SOURCE_DATE_EPOCH，默认 1980-01-01）；设置 SOURCE_DATE_EPOCH 时自动启用 / Reproducible output: fixed timestamps (SOURCE_DATE_EPOCH, default 1980-01-01); implied when SOURCE_DATE_EPOCH is set")]
+        reproducible: bool,
+
+        #[arg(long, help = "只列出将要打包的文件与 manifest，不写出 zip / List files and the manifest that would be written, without creating the zip")]
+        dry_run: bool,
+
+        #[arg(long, requires = "json", conflicts_with_all = ["dry_run", "split", "base", "sign_key", "verify"], help = "只计算哈希并以 JSON 输出将要写入的 manifest，不写出 zip（需 --json） / Hash the plugins and print the manifest that would be written as JSON, without creating the zip (requires --json)")]
+        list_only: bool,
+
+        #[arg(long, help = "在 stderr 输出打包进度（已写字节数与预计剩余时间） / Print pack progress (bytes written, ETA) to stderr")]
+        progress: bool,
+
+        #[arg(long, help = "plugin.toml 校验失败时仍然打包（错误降为警告） / Pack even if plugin.toml validation fails (errors become warnings)")]
+        allow_invalid: bool,
+
+        #[arg(long, help = "同时打包所选插件的（传递）依赖；依赖缺失时报错 / Also pack the (transitive) dependencies of the selected plugins; error if one is missing")]
+        with_deps: bool,
+
+        #[arg(long, help = "也打包已用 disable 停用的插件 / Also pack plugins turned off with disable")]
+        include_disabled: bool,
+
+        #[arg(long, conflicts_with_all = ["dry_run", "no_md5"], help = "增量打包：目录哈希与该旧 bundle 一致的插件直接复制其已压缩条目 / Incremental pack: copy the already-compressed entries of plugins whose folder hash matches this previous bundle")]
+        base: Option<PathBuf>,
+
+        #[arg(long, conflicts_with = "dry_run", help = "用 ed25519 私钥签名 manifest（需 --manifest-version 2） / Sign the manifest with an ed25519 secret key (requires --manifest-version 2)")]
+        sign_key: Option<PathBuf>,
+
+        #[arg(long, conflicts_with = "dry_run", help = "写出后重新读取 zip 并按 manifest 校验；失败则删除输出 / Re-read the written zip and check it against the manifest; delete the output on failure")]
+        verify: bool,
+
+        #[arg(long, conflicts_with = "dry_run", help = "每个插件单独输出一个 zip 到 --out 目录（默认当前目录） / Write one zip per plugin into the --out directory (default: current dir)")]
+        split: bool,
+
+        #[arg(long, requires = "split", help = "--split 时任一插件失败即停止 / With --split, stop at the first plugin that fails")]
+        fail_fast: bool,
+
+        #[arg(long, value_parser = core::parse_size, help = "输出 zip 超过该大小即中止并删除（如 500MB、2GiB） / Abort and delete the output once the zip exceeds this size (e.g. 500MB, 2GiB)")]
+        max_size: Option<u64>,
+
+        #[arg(long, value_parser = core::parse_size, help = "输出 zip 超过该大小时按插件列出占用（仅警告） / Warn with per-plugin sizes when the zip exceeds this size")]
+        warn_size: Option<u64>,
+
+        #[arg(long, default_value_t = core::ZIP_NAME_MAX_LEN, help = "zip 条目名的最大长度（按 UTF-16 计；超出时列出全部文件并中止） / Longest allowed zip entry name, in UTF-16 units (longer ones are all listed and abort the pack)")]
+        max_name_len: usize,
+
+        #[arg(long, help = "从声明式 bundle.toml 读取打包设置；命令行参数优先 / Read pack settings from a bundle.toml spec; command-line flags take precedence")]
+        spec: Option<PathBuf>,
+
+        #[arg(long, requires = "json_report", help = "以 JSON 输出 dry-run、--list-only 或 --split 结果 / Output the dry-run, --list-only or --split report as JSON")]
+        json: bool,
+
         #[arg(long, help = "整合包名称（用于 profiles 命名空间与重命名；默认取输出 zip 文件名） / Bundle name (for profiles namespacing; default derived from output zip name)")]
         bundle_name: Option<String>,
 
@@ -218,7 +989,10 @@ enum Commands {
         bundle_author: Option<String>,
     },
 
-    #[command(about = "检查插件冲突与兼容性 / Check plugin conflicts and compatibility")]
+    #[command(
+        about = "检查插件冲突与兼容性 / Check plugin conflicts and compatibility",
+        after_help = "退出码 / Exit status: 0 无错误 / no errors (warnings alone included), 1 有警告且指定了 --warnings-as-errors / warnings with --warnings-as-errors, 2 有错误 / errors, 3 检查无法运行 / the check could not run"
+    )]
     Check {
         #[arg(help = "插件 ID（可选；省略则检查全部插件） / Plugin id (optional; omit to check all)")]
         plugin_id: Option<String>,
@@ -229,7 +1003,7 @@ enum Commands {
         #[arg(long, help = "输出 JSON / Output JSON")]
         json: bool,
 
-        #[arg(long, help = "只检查插件 ID 冲突 / Only check plugin id conflicts")]
+        #[arg(long, help = "只检查插件 ID 冲突、重复的 entry 模块与仅大小写不同的目录名 / Only check plugin id conflicts, duplicate entry modules and folder names differing only by case")]
         id: bool,
 
         #[arg(long, help = "只检查插件依赖关系 / Only check plugin dependencies")]
@@ -238,19 +1012,71 @@ enum Commands {
         #[arg(long, help = "只检查 SDK(base) 兼容性 / Only check SDK(base) compatibility")]
         base: bool,
 
+        #[arg(long, help = "只校验 plugin.toml 字段（id/version/entry） / Only validate plugin.toml fields (id/version/entry)")]
+        toml: bool,
+
+        #[arg(long, help = "只检查 entry 指向的文件与属性（module[:attr] / path.py[:attr]） / Only check the entry file and attribute (module[:attr] / path.py[:attr])")]
+        entry: bool,
+
+        #[arg(long, help = "只校验 profiles.toml 与 profiles/*.toml（语法、空文件、跨文件重名） / Only validate profiles.toml and profiles/*.toml (syntax, empty files, names defined twice)")]
+        profiles: bool,
+
+        #[arg(long, value_parser = core::parse_size, default_value_t = core::PROFILE_WARN_SIZE, help = "profile 文件超过该大小时警告（默认 64 KiB，0 关闭） / Warn about profile files larger than this (default 64 KiB, 0 turns it off)")]
+        profile_warn_size: u64,
+
+        #[arg(long, value_name = "BUNDLE_ZIP", conflicts_with_all = ["graph", "fix", "py_syntax", "python"], help = "不解包，直接检查整合包 zip 中的插件（只做 id/deps/base 检查，并核对 manifest 与 plugin.toml 的版本） / Check the plugins inside a bundle zip without unpacking it (id/deps/base checks only, plus manifest vs plugin.toml versions)")]
+        zip: Option<PathBuf>,
+
+        #[arg(long, value_enum, conflicts_with = "json", help = "把依赖图（dot 或 mermaid）输出到 stdout 代替报告，问题以 ERROR:/WARN: 输出到 stderr / Print the dependency graph (dot or mermaid) to stdout instead of the report; problems go to stderr as ERROR:/WARN:")]
+        graph: Option<core::GraphFormat>,
+
+        #[arg(long, help = "有警告时也以非零状态退出（退出码 1） / Exit non-zero (status 1) when there are warnings")]
+        warnings_as_errors: bool,
+
+        #[arg(long, help = "基线文件（JSON，或 .toml）：其中记录的已知问题不再算作错误/警告，只计入 baselined / Baseline file (JSON, or .toml): findings it lists no longer count as errors or warnings, only as baselined")]
+        baseline: Option<PathBuf>,
+
+        #[arg(long, requires = "baseline", help = "把本次的全部问题写入 --baseline 文件 / Write every finding of this run to the --baseline file")]
+        write_baseline: bool,
+
+        #[arg(long, help = "就地修正 plugin.toml 中可机械修正的问题（version-missing-patch、sdk-supported-missing、dep-pinned），保留注释与顺序，然后重新检查 / Rewrite plugin.toml in place for mechanically fixable findings (version-missing-patch, sdk-supported-missing, dep-pinned), keeping comments and order, then check again")]
+        fix: bool,
+
+        #[arg(long, requires = "fix", help = "只显示 --fix 将做的修改，不写入 / Show the --fix edits without writing them")]
+        dry_run: bool,
+
+        #[arg(long, help = "离线检查插件 .py 文件的语法（python -m py_compile，跳过默认排除的文件） / Check the syntax of plugin .py files offline (python -m py_compile, skipping the default excludes)")]
+        py_syntax: bool,
+
+        #[arg(long, requires = "py_syntax", help = "语法检查严格模式（找不到 Python 解释器算 error） / Strict syntax check (a missing Python interpreter is an error)")]
+        py_syntax_strict: bool,
+
         #[arg(long, help = "运行 Python 在线依赖试算（uv pip compile） / Run python online dependency resolution (uv pip compile)")]
         python: bool,
 
         #[arg(long, help = "Python 在线检查严格模式（uv 缺失/失败算 error） / Strict python-online check (missing/failure is error)")]
         python_strict: bool,
 
-        #[arg(long, help = "覆盖 Python 在线检查缓存目录 / Override cache dir for python-online check")]
+        #[arg(long, requires = "python", help = "把 uv 解析结果写入稳定的锁文件（文件头记录输入哈希），并在旁边写出同名 .in 输入文件 / Write the uv-compiled result to a stable lockfile (header records the inputs' hash) and its inputs to a .in file next to it")]
+        lock: Option<PathBuf>,
+
+        #[arg(long, requires = "lock", help = "不写锁文件，而是重新解析并在结果与 --lock 文件不同（忽略注释行）时报错 / Instead of writing the lockfile, compile again and fail when the result differs from the --lock file (comment lines ignored)")]
+        verify_lock: bool,
+
+        #[arg(long, help = "覆盖缓存目录（检查结果缓存与 Python 在线检查） / Override the cache dir (check result cache and python-online check)")]
         cache_dir: Option<PathBuf>,
+
+        #[arg(long, help = "不读取也不写入检查结果缓存 / Neither read nor write the check result cache")]
+        no_cache: bool,
+
+        #[arg(long, help = "跳过已用 disable 停用的插件（依赖仍可指向它们） / Skip plugins turned off with disable (dependencies may still point at them)")]
+        skip_disabled: bool,
     },
 
-    #[command(about = "解包插件 zip 到插件目录（冲突告警；md5 相同自动跳过） / Unpack plugin zip into plugin dir (warn conflicts; skip identical by md5)")]
+    #[command(about = "解包插件 zip 到插件目录（冲突告警；哈希相同自动跳过） / Unpack plugin zip into plugin dir (warn conflicts; skip identical by hash)")]
+    #[command(group(clap::ArgGroup::new("overwrite").args(["force", "interactive"])))]
     Unpack {
-        #[arg(help = "bundle zip 路径 / Bundle zip path")]
+        #[arg(help = "bundle zip 路径（- 表示从 stdin 读取） / Bundle zip path (- reads the zip from stdin)")]
         zip_path: PathBuf,
 
         #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
@@ -261,6 +1087,268 @@ enum Commands {
 
         #[arg(long, help = "强制覆盖已有文件/插件 / Force overwrite existing plugins/files")]
         force: bool,
+
+        #[arg(long, help = "不解出插件中匹配该 glob 的文件（相对插件目录，可多次指定） / Do not extract plugin files matching this glob (relative to the plugin folder; repeatable)")]
+        exclude: Vec<String>,
+
+        #[arg(long, help = "解包前用该公钥校验 bundle 签名 / Verify the bundle signature with this public key before extracting")]
+        verify_key: Option<PathBuf>,
+
+        #[arg(long, requires = "verify_key", help = "拒绝未签名的 bundle / Reject unsigned bundles")]
+        require_signature: bool,
+
+        #[arg(long, help = "同时解出 bundle 的 extras/ 文件（已存在时需 --force 才覆盖） / Also extract the bundle's extras/ files (existing ones are only replaced with --force)")]
+        with_extras: bool,
+
+        #[arg(long, requires = "with_extras", help = "extras 的目标目录（默认仓库根目录） / Destination for extras (default: repo root)")]
+        extras_dest: Option<PathBuf>,
+
+        #[arg(long, help = "允许按 zip 中的符号链接条目创建链接（指向目标目录之外的链接仍会跳过） / Create links for symlink entries in the zip (links pointing outside the destination are still skipped)")]
+        allow_symlinks: bool,
+
+        #[arg(long, help = "用 --force 覆盖插件前先把原目录（不含排除的文件）复制到备份目录 / Before --force overwrites a plugin, copy its folder (minus excluded files) to the backup dir")]
+        backup: bool,
+
+        #[arg(long, help = "备份目录（默认 <dest>/.backups，指定即启用 --backup） / Backup dir (default <dest>/.backups; implies --backup)")]
+        backup_dir: Option<PathBuf>,
+
+        #[arg(long, help = "解包时不按 manifest 校验每个文件的 sha256（更快） / Skip checking extracted files against the manifest's per-file sha256 (faster)")]
+        no_verify: bool,
+
+        #[arg(long, value_name = "VERSION", value_parser = clap::value_parser!(u32).range(1..=core::MANIFEST_VERSION_MAX as i64), help = "应急用：把比本程序更新的 manifest 按该版本读取（新增字段会被忽略） / Emergency escape hatch: read a manifest newer than this build as this version (newer fields are ignored)")]
+        assume_version: Option<u32>,
+
+        #[arg(long, value_enum, default_value_t = core::ApplyProfiles::Skip, help = "把 bundle 附带的 profiles 写入插件的 profiles.toml / profiles/：skip 不写，merge 只补缺失的文件，overwrite 覆盖 / Copy bundled profiles into the plugin's profiles.toml / profiles/: skip leaves them alone, merge only adds missing files, overwrite replaces")]
+        apply_profiles: core::ApplyProfiles,
+
+        #[arg(long, help = "解包后对新安装的插件运行 check（SDK 版本、依赖、plugin.toml），只报告 / After unpacking, run check (SDK version, dependencies, plugin.toml) on the installed plugins and report")]
+        check: bool,
+
+        #[arg(long, help = "同 --check，但有错误时撤销本次安装并以非零状态退出 / Like --check, but errors undo this run's installs and exit nonzero")]
+        check_strict: bool,
+
+        #[arg(long, conflicts_with_all = ["force", "dry_run"], help = "终端中对每个与已有插件冲突的插件逐个询问：覆盖 / 跳过 / 查看差异 / 中止（非终端或 --json 时不生效） / In a terminal, ask for each plugin that conflicts with an installed one: overwrite / skip / diff summary / abort (ignored without a terminal or with --json)")]
+        interactive: bool,
+
+        #[arg(long, requires = "overwrite", conflicts_with = "dry_run", help = "覆盖插件时删除 bundle 中已不存在的文件（排除规则匹配的文件与 profiles 保留；需 --force 或 --interactive） / When overwriting a plugin, delete files the bundle no longer has (files matched by the excludes and profiles are kept; requires --force or --interactive)")]
+        prune: bool,
+
+        #[arg(long, value_parser = core::parse_size, default_value_t = core::UNPACK_MAX_UNCOMPRESSED_BYTES, help = "bundle 按声明大小或实际解压量超过该值即拒绝（如 20GiB；默认 8 GiB） / Refuse bundles whose declared or extracted uncompressed size exceeds this (e.g. 20GiB; default 8 GiB)")]
+        max_uncompressed_bytes: u64,
+
+        #[arg(long, conflicts_with = "dry_run", help = "先把全部插件解到暂存目录并校验，全部成功后再逐个切换到位；切换失败时回滚已切换的插件 / Extract and verify every plugin in the staging dir first, then swap them all into place; a failed swap rolls back the plugins already swapped")]
+        staged: bool,
+
+        #[arg(long, value_name = "PATH", conflicts_with = "dry_run", help = "暂存目录（默认 <dest>/.neko_unpack_tmp，指定即启用 --staged；与 dest 在同一文件系统时切换最快） / Staging dir (default <dest>/.neko_unpack_tmp; implies --staged; swaps are fastest on the same file system as dest)")]
+        staging_dir: Option<PathBuf>,
+
+        #[arg(long, help = "把 zip 当作单个插件目录解包（plugin.toml 位于顶层或下一级目录），忽略其中的 manifest；缺少 manifest.toml 时会自动识别并警告 / Unpack the zip as a single plugin folder (plugin.toml at the top level or one directory deep), ignoring any manifest; zips without manifest.toml are detected automatically, with a warning")]
+        no_manifest: bool,
+
+        #[arg(long, help = "同时解包的插件数（默认 CPU 核数） / Number of plugins extracted in parallel (default: number of CPUs)")]
+        jobs: Option<usize>,
+
+        #[arg(long, value_name = "PATH", conflicts_with = "dry_run", help = "把本次解包的 JSON 报告写入该文件（失败时也会写出） / Write a JSON report of this unpack run to this file (also on failure)")]
+        report: Option<PathBuf>,
+
+        #[arg(long, conflicts_with = "with_extras", help = "只预览每个插件将安装还是跳过，不写出任何文件 / Preview which plugins would be installed or skipped, without writing anything")]
+        dry_run: bool,
+
+        #[arg(long, help = "以 JSON 输出解包结果（含备份位置）或 dry-run 报告 / Output the unpack result (including backups) or the dry-run report as JSON")]
+        json: bool,
+    },
+
+    #[command(about = "创建插件骨架 plugin/plugins/<id>/ / Scaffold a new plugin in plugin/plugins/<id>/")]
+    New {
+        #[arg(help = "插件 ID（同时作为目录名） / Plugin id (also the folder name)")]
+        id: String,
+
+        #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+
+        #[arg(long, help = "显示名称（默认由 id 生成） / Display name (derived from the id by default)")]
+        name: Option<String>,
+
+        #[arg(long, help = "作者，写入 [plugin.author] / Author, written to [plugin.author]")]
+        author: Option<String>,
+
+        #[arg(long, value_name = "REQ", help = "[plugin.sdk] supported 范围（默认从当前 SDK_VERSION 到下一个主版本） / [plugin.sdk] supported range (default: current SDK_VERSION up to the next major)")]
+        sdk_supported: Option<String>,
+
+        #[arg(long, value_name = "DIR", help = "用自定义模板目录代替内置骨架（替换 {{id}}/{{name}}/{{author}}/{{sdk_supported}}） / Copy this template dir instead of the built-in skeleton (substitutes {{id}}/{{name}}/{{author}}/{{sdk_supported}})")]
+        template: Option<PathBuf>,
+    },
+
+    #[command(about = "删除插件（先检查依赖它的插件） / Remove plugins, checking for dependents first")]
+    Remove {
+        #[arg(required = true, help = "要删除的插件 ID（可多次指定） / Plugin id(s) to remove (repeatable)")]
+        plugin_id: Vec<String>,
+
+        #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+
+        #[arg(long, help = "即使有其他插件依赖也删除 / Remove even when other plugins depend on them")]
+        force: bool,
+
+        #[arg(long, help = "只报告将删除的插件与依赖它们的插件，不做修改 / Only report what would be removed and its dependents")]
+        dry_run: bool,
+
+        #[arg(long, help = "删除前把插件目录（不含排除的文件）复制到备份目录 / Copy each plugin folder (minus excluded files) to the backup dir before deleting it")]
+        backup: bool,
+
+        #[arg(long, help = "备份目录（默认 plugin/plugins/.backups，指定即启用 --backup） / Backup dir (default plugin/plugins/.backups; implies --backup)")]
+        backup_dir: Option<PathBuf>,
+
+        #[arg(long, help = "以 JSON 输出删除结果 / Output the removal report as JSON")]
+        json: bool,
+    },
+
+    #[command(about = "监视插件文件，变化后重新检查 / Watch plugin files and re-run check on changes")]
+    Watch {
+        #[arg(help = "只监视该插件（可选） / Only watch this plugin (optional)")]
+        plugin_id: Option<String>,
+
+        #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+
+        #[arg(long, value_name = "MS", default_value_t = watch::WATCH_DEBOUNCE_MS, help = "最后一次变化后等待的毫秒数 / Milliseconds to wait after the last change")]
+        debounce: u64,
+
+        #[arg(long, value_name = "CMD", help = "检查没有错误时运行的 shell 命令 / Shell command to run after a check without errors")]
+        exec: Option<String>,
+    },
+
+    #[command(about = "停用插件（记录在 plugin/plugins/.disabled.toml，不删除目录） / Disable plugins (recorded in plugin/plugins/.disabled.toml, folders stay)")]
+    Disable {
+        #[arg(required = true, help = "要停用的插件 ID（可多次指定） / Plugin id(s) to disable (repeatable)")]
+        plugin_id: Vec<String>,
+
+        #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+    },
+
+    #[command(about = "重新启用用 disable 停用的插件 / Re-enable plugins turned off with disable")]
+    Enable {
+        #[arg(required = true, help = "要启用的插件 ID（可多次指定） / Plugin id(s) to enable (repeatable)")]
+        plugin_id: Vec<String>,
+
+        #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+    },
+
+    #[command(about = "清理缓存与中断留下的临时文件 / Remove caches and leftover temporary files")]
+    #[command(group(clap::ArgGroup::new("targets").args(["cache", "tmp", "all"]).multiple(true).required(true)))]
+    Clean {
+        #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+
+        #[arg(long, help = "清理缓存目录（检查结果缓存、哈希缓存与 Python 在线检查） / Clear the cache dir (check result cache, hash cache and python-online check)")]
+        cache: bool,
+
+        #[arg(long, help = "清理仓库内残留的 *.zip.tmp 与 .neko_unpack_tmp / Remove stray *.zip.tmp files and .neko_unpack_tmp dirs under the repo")]
+        tmp: bool,
+
+        #[arg(long, help = "等同 --cache --tmp / Same as --cache --tmp")]
+        all: bool,
+
+        #[arg(long, help = "只列出将删除的内容，不做修改 / Only list what would be removed")]
+        dry_run: bool,
+
+        #[arg(long, help = "覆盖缓存目录 / Override the cache dir")]
+        cache_dir: Option<PathBuf>,
+
+        #[arg(long, help = "以 JSON 输出清理结果 / Output the clean report as JSON")]
+        json: bool,
+    },
+
+    #[command(about = "比较两个整合包，或整合包与已安装的插件 / Compare two bundles, or a bundle with the installed plugins")]
+    Diff {
+        #[arg(help = "旧的 bundle zip（--against-installed 时为要安装的 bundle） / Old bundle zip (with --against-installed, the bundle to install)")]
+        old: PathBuf,
+
+        #[arg(required_unless_present = "against_installed", conflicts_with = "against_installed", help = "新的 bundle zip / New bundle zip")]
+        new: Option<PathBuf>,
+
+        #[arg(long, help = "与已安装的插件比较：显示安装该 bundle 会带来的变化 / Compare with the installed plugins: show what installing the bundle would change")]
+        against_installed: bool,
+
+        #[arg(long, conflicts_with = "new", help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+
+        #[arg(long, conflicts_with = "new", help = "已安装插件目录（默认 <repo_root>/plugin/plugins） / Installed plugin dir (default <repo_root>/plugin/plugins)")]
+        dest: Option<PathBuf>,
+
+        #[arg(long, help = "以 JSON 输出差异 / Output the differences as JSON")]
+        json: bool,
+    },
+
+    #[command(about = "校验整合包完整性（与 pack --verify 相同的检查） / Check a bundle's integrity (the checks of pack --verify)")]
+    Verify {
+        #[arg(help = "bundle zip 路径 / Bundle zip path")]
+        zip_path: PathBuf,
+
+        #[arg(long, help = "同时用该公钥校验 bundle 签名 / Also verify the bundle signature with this public key")]
+        verify_key: Option<PathBuf>,
+
+        #[arg(long, requires = "verify_key", help = "未签名的 bundle 视为失败 / Fail unsigned bundles")]
+        require_signature: bool,
+
+        #[arg(long, help = "以 JSON 输出校验报告 / Output the verification report as JSON")]
+        json: bool,
+    },
+
+    #[command(about = "升级整合包 manifest 的格式版本（插件内容按原字节复制） / Upgrade a bundle manifest's format version (plugin payloads are copied byte for byte)")]
+    Migrate {
+        #[arg(help = "要迁移的 bundle zip / Bundle zip to migrate")]
+        zip_path: PathBuf,
+
+        #[arg(long, required_unless_present = "in_place", conflicts_with = "in_place", help = "输出 zip 路径 / Output zip path")]
+        out: Option<PathBuf>,
+
+        #[arg(long, help = "直接替换原 zip（校验通过后才替换） / Replace the input zip (only once the result verifies)")]
+        in_place: bool,
+
+        #[arg(long = "to", value_name = "VERSION", help = "目标 manifest 格式版本（默认最新；不支持降级） / Target manifest format version (default latest; no downgrades)")]
+        format_version: Option<u32>,
+
+        #[arg(long, help = "用 ed25519 私钥重新签名（原签名总会被移除） / Re-sign with an ed25519 secret key (an existing signature is always dropped)")]
+        sign_key: Option<PathBuf>,
+
+        #[arg(long, help = "可复现输出：签名时间戳取 SOURCE_DATE_EPOCH（默认 1980-01-01） / Reproducible output: signature timestamp from SOURCE_DATE_EPOCH (default 1980-01-01)")]
+        reproducible: bool,
+
+        #[arg(long, help = "补全缺失的整合包名称 / Fill in a missing bundle name")]
+        bundle_name: Option<String>,
+
+        #[arg(long, help = "补全缺失的整合包版本 / Fill in a missing bundle version")]
+        bundle_version: Option<String>,
+
+        #[arg(long, help = "补全缺失的整合包作者 / Fill in a missing bundle author")]
+        bundle_author: Option<String>,
+
+        #[arg(long, help = "以 JSON 输出迁移结果 / Output the migration report as JSON")]
+        json: bool,
+    },
+
+    #[command(about = "以树形显示插件依赖 / Print the plugin dependency tree")]
+    Deps {
+        #[arg(help = "根插件 ID（省略则显示所有不被依赖的插件） / Root plugin id (omit for every plugin nothing depends on)")]
+        plugin_id: Option<String>,
+
+        #[arg(long, help = "仓库根目录（可选，默认自动探测） / Repo root (optional, auto-detect by default)")]
+        root: Option<PathBuf>,
+
+        #[arg(long, help = "以 JSON 输出依赖树 / Output the tree as JSON")]
+        json: bool,
+    },
+
+    #[command(about = "生成 bundle 签名用的 ed25519 密钥对 / Generate an ed25519 key pair for bundle signing")]
+    Keygen {
+        #[arg(long, help = "私钥输出路径（公钥写入 <out>.pub） / Secret key path (public key goes to <out>.pub)")]
+        out: PathBuf,
+
+        #[arg(long, help = "覆盖已有密钥文件 / Overwrite existing key files")]
+        force: bool,
     },
 
     #[command(about = "终端图形界面（支持鼠标/进度条） / Terminal UI (mouse + progress)")]
@@ -270,35 +1358,406 @@ enum Commands {
     },
 }
 
-fn resolve_zip_path(input: &Path, repo_root: &Path) -> Result<PathBuf> {
-    if input.is_absolute() {
-        return Ok(input.to_path_buf());
-    }
-
-    let cwd = std::env::current_dir().context("failed to get cwd")?;
-    let candidates = [
-        input.to_path_buf(),
-        cwd.join(input),
-        repo_root.join(input),
-        repo_root
-            .join("plugin")
-            .join("tool")
-            .join("neko_plugin_cli")
-            .join(input),
-    ];
-
-    for c in &candidates {
-        if c.is_file() {
-            return Ok(c.to_path_buf());
-        }
-    }
-
-    anyhow::bail!(
-        "zip not found; tried: {}",
-        candidates
-            .iter()
-            .map(|p| p.display().to_string())
-            .collect::<Vec<_>>()
-            .join(" | ")
-    )
+/// Plain-text `pack --progress` output on stderr
+struct PackProgressPrinter {
+    state: std::sync::Mutex<PackProgressState>,
+}
+
+struct PackProgressState {
+    started: std::time::Instant,
+    last_line: std::time::Instant,
+    plugins: usize,
+    plugin_index: usize,
+    total_bytes: u64,
+    written: u64,
+}
+
+impl PackProgressPrinter {
+    fn new() -> Self {
+        let now = std::time::Instant::now();
+        Self {
+            state: std::sync::Mutex::new(PackProgressState {
+                started: now,
+                last_line: now,
+                plugins: 0,
+                plugin_index: 0,
+                total_bytes: 0,
+                written: 0,
+            }),
+        }
+    }
+
+    fn on_event(&self, ev: core::PackProgress<'_>) {
+        let mut st = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match ev {
+            core::PackProgress::Hashed { plugin, done, total } => {
+                eprintln!("hashing [{}/{}] {}", done, total, plugin);
+            }
+            core::PackProgress::Start { plugins, files, bytes } => {
+                st.started = std::time::Instant::now();
+                st.plugins = plugins;
+                st.total_bytes = bytes;
+                eprintln!("packing {} plugins, {} files, {}", plugins, files, core::human_bytes(bytes));
+            }
+            core::PackProgress::PluginStart { plugin, files, bytes } => {
+                st.plugin_index += 1;
+                eprintln!(
+                    "[{}/{}] {}: {} files, {}",
+                    st.plugin_index,
+                    st.plugins,
+                    plugin,
+                    files,
+                    core::human_bytes(bytes)
+                );
+            }
+            core::PackProgress::FileWritten { bytes, .. } => {
+                st.written += bytes;
+                if st.last_line.elapsed() >= std::time::Duration::from_secs(1) {
+                    st.last_line = std::time::Instant::now();
+                    eprintln!("    {}", st.overall());
+                }
+            }
+            core::PackProgress::PluginDone { plugin } => {
+                eprintln!("[{}/{}] {} done, {}", st.plugin_index, st.plugins, plugin, st.overall());
+            }
+        }
+    }
+}
+
+impl PackProgressState {
+    fn overall(&self) -> String {
+        let pct = if self.total_bytes == 0 {
+            100.0
+        } else {
+            self.written as f64 * 100.0 / self.total_bytes as f64
+        };
+        let eta = if self.written == 0 {
+            "?".to_string()
+        } else {
+            let elapsed = self.started.elapsed().as_secs_f64();
+            let remaining = elapsed * (self.total_bytes.saturating_sub(self.written)) as f64 / self.written as f64;
+            format!("{:.0}s", remaining)
+        };
+        format!(
+            "{} / {} ({:.0}%), ETA {}",
+            core::human_bytes(self.written),
+            core::human_bytes(self.total_bytes),
+            pct,
+            eta
+        )
+    }
+}
+
+/// Single self-overwriting stderr line for unpack: percentage and the plugin being extracted
+struct UnpackProgressPrinter {
+    state: std::sync::Mutex<UnpackProgressState>,
+}
+
+struct UnpackProgressState {
+    plugin: String,
+    last_draw: Option<std::time::Instant>,
+    width: usize,
+}
+
+impl UnpackProgressPrinter {
+    fn new() -> Self {
+        Self {
+            state: std::sync::Mutex::new(UnpackProgressState {
+                plugin: String::new(),
+                last_draw: None,
+                width: 0,
+            }),
+        }
+    }
+
+    fn on_event(&self, ev: core::UnpackProgress<'_>) {
+        let mut st = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match ev {
+            core::UnpackProgress::PluginStart { plugin, .. } => {
+                st.plugin = plugin.to_string();
+                st.last_draw = None;
+            }
+            core::UnpackProgress::FileExtracted { index, total, .. } => {
+                // Redraw at most ten times a second; many small files would otherwise flood the terminal
+                if st.last_draw.is_some_and(|t| t.elapsed() < std::time::Duration::from_millis(100)) {
+                    return;
+                }
+                st.last_draw = Some(std::time::Instant::now());
+                let pct = (index * 100).checked_div(total).unwrap_or(100);
+                let line = format!("unpacking {:>3}% ({}/{}) {}", pct, index, total, st.plugin);
+                eprint!("\r{:<width$}", line, width = st.width);
+                st.width = line.chars().count();
+            }
+            core::UnpackProgress::PluginSkipped { .. } => {}
+            core::UnpackProgress::Done { .. } => st.clear(),
+        }
+    }
+
+    fn clear(&self) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl UnpackProgressState {
+    fn clear(&mut self) {
+        if self.width > 0 {
+            eprint!("\r{:width$}\r", "", width = self.width);
+            self.width = 0;
+        }
+    }
+}
+
+/// Ask about each conflicting plugin in `items`; returns the folders to overwrite, or None if the user aborted.
+/// End of input counts as abort so a closed terminal never installs anything.
+fn prompt_unpack_conflicts(
+    items: &[core::UnpackPreviewItem],
+    input: &mut dyn std::io::BufRead,
+    output: &mut dyn std::io::Write,
+) -> Result<Option<Vec<String>>> {
+    let conflicts: Vec<_> = items.iter().filter(|i| i.conflict).collect();
+    let mut overwrite = Vec::new();
+    for (n, item) in conflicts.iter().enumerate() {
+        loop {
+            write!(
+                output,
+                "[{}/{}] plugin '{}' ({}) differs from the installed copy. [o]verwrite, [s]kip, [d]iff summary, [a]bort? ",
+                n + 1,
+                conflicts.len(),
+                item.id,
+                item.folder
+            )?;
+            output.flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer).context("failed to read answer")? == 0 {
+                writeln!(output)?;
+                return Ok(None);
+            }
+            match answer.trim().to_ascii_lowercase().as_str() {
+                "o" | "overwrite" => {
+                    overwrite.push(item.folder.clone());
+                    break;
+                }
+                "s" | "skip" => break,
+                "d" | "diff" => {
+                    if item.changed_files.is_empty() {
+                        writeln!(output, "    no per-file checksums in this bundle; only the folder hash differs")?;
+                    }
+                    for f in &item.changed_files {
+                        writeln!(output, "    {:?}: {}", f.kind, f.path)?;
+                    }
+                }
+                "a" | "abort" => return Ok(None),
+                other => writeln!(output, "    unknown answer '{}'; expected o, s, d or a", other)?,
+            }
+        }
+    }
+    Ok(Some(overwrite))
+}
+
+/// Diff-style summary of `check --fix`; on stderr when stdout carries JSON or a graph
+fn print_fixes(fixes: &[core::TomlFix], dry_run: bool, to_stderr: bool) {
+    let verb = if dry_run { "would fix" } else { "fixed" };
+    let mut lines = Vec::new();
+    let mut current: Option<&str> = None;
+    for f in fixes {
+        if current != Some(f.folder.as_str()) {
+            lines.push(format!("{} {}/plugin.toml:", verb, f.folder));
+            current = Some(&f.folder);
+        }
+        if let Some(old) = &f.old {
+            lines.push(format!("  - {} = {:?}", f.key, old));
+        }
+        lines.push(format!("  + {} = {:?}  [{}]", f.key, f.new, f.code));
+    }
+    if fixes.is_empty() {
+        lines.push("nothing to fix".to_string());
+    }
+    for line in lines {
+        if to_stderr {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Findings grouped by plugin, repository-wide ones first
+fn print_check_report(report: &core::CheckReport) {
+    println!("SDK_VERSION: {}", report.sdk_version);
+    println!("Plugins checked: {}", report.plugins_checked);
+    println!("Errors: {}", report.errors.len());
+    println!("Warnings: {}", report.warnings.len());
+    if report.baselined > 0 {
+        println!("Baselined: {}", report.baselined);
+    }
+    let mut current: Option<Option<&str>> = None;
+    for f in &report.findings {
+        let plugin = f.plugin_id.as_deref();
+        if current != Some(plugin) {
+            match plugin {
+                Some(id) => println!("plugin {}:", id),
+                None => println!("repository:"),
+            }
+            current = Some(plugin);
+        }
+        let label = match f.severity {
+            core::Severity::Error => "ERROR",
+            core::Severity::Warning => "WARN",
+        };
+        println!("  {} [{}] {}", label, f.code, f.message);
+    }
+}
+
+fn print_migrate_report(report: &core::MigrateReport) {
+    println!(
+        "migrated: {} (format_version {} -> {})",
+        report.out.display(),
+        report.from_version,
+        report.to_version
+    );
+    if report.file_checksums > 0 {
+        println!("file checksums: {}", report.file_checksums);
+    }
+    if !report.filled.is_empty() {
+        println!("filled bundle fields: {}", report.filled.join(", "));
+    }
+    if report.dropped_signature && report.signed_by.is_none() {
+        log::warn!("dropped manifest.sig (it covered the old manifest); pass --sign-key to re-sign");
+    }
+    if let Some(fingerprint) = &report.signed_by {
+        println!("signed: {fingerprint}");
+    }
+    println!("verify: ok ({} plugin(s), {} entries)", report.verify.plugins, report.verify.entries);
+}
+
+fn print_verify_report(report: &core::VerifyReport, zip_path: &Path) {
+    println!(
+        "Checked {} plugin(s), {} entries ({}), {} folder hash(es), {} file checksum(s)",
+        report.plugins,
+        report.entries,
+        core::human_bytes(report.bytes),
+        report.folder_hashes,
+        report.file_checksums
+    );
+    match (&report.signed_by, report.signed) {
+        (Some(fingerprint), _) => println!("Signature: valid (key {})", fingerprint),
+        (None, true) => println!("Signature: present, not checked (use --verify-key)"),
+        (None, false) => println!("Signature: none"),
+    }
+    for issue in &report.issues {
+        println!("  ERROR [{}] {}", issue.code, issue.message);
+    }
+    if report.issues.is_empty() {
+        println!("PASS {}", zip_path.display());
+    } else {
+        println!("FAIL {}: {} problem(s)", zip_path.display(), report.issues.len());
+    }
+}
+
+fn print_toggle_report(report: &core::ToggleReport) {
+    let state = if report.disabled { "disabled" } else { "enabled" };
+    for id in &report.changed {
+        println!("{} {}", state, id);
+    }
+    for id in &report.unchanged {
+        log::info!("plugin {} is already {}", id, state);
+    }
+}
+
+fn print_unpack_dry_run(preview: &core::UnpackPreview, dest_dir: &Path) {
+    println!("Dry run: would unpack into {}", dest_dir.display());
+    if let Some(bundle) = &preview.summary.bundle {
+        let mut line = format!("Bundle: {}", bundle.name.as_deref().unwrap_or_default());
+        if let Some(version) = &bundle.version {
+            line.push_str(&format!(" {}", version));
+        }
+        if let Some(author) = &bundle.author {
+            line.push_str(&format!(" by {}", author));
+        }
+        println!("{}", line);
+    }
+    print!("{}", core::format_unpack_preview(&preview.plugins));
+    println!("Summary: {} to install, {} to skip", preview.summary.install, preview.summary.skip);
+}
+
+fn print_pack_dry_run(report: &core::PackDryRun) -> Result<()> {
+    println!("Dry run: would write {}", report.out_path.display());
+    println!(
+        "Total: {} files, {} uncompressed",
+        report.total_files,
+        core::human_bytes(report.total_bytes)
+    );
+    for p in &report.plugins {
+        println!("- {} ({}): {} files, {}", p.id, p.folder, p.file_count, core::human_bytes(p.bytes));
+    }
+    if !report.largest_files.is_empty() {
+        println!("Largest files:");
+        for f in &report.largest_files {
+            println!("  {:>10}  {}", core::human_bytes(f.size), f.zip_path);
+        }
+    }
+    println!("\nmanifest.toml:");
+    print!("{}", toml::to_string(&report.manifest).context("failed to serialize manifest")?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, conflict: bool, changed: &[(&str, core::FileDiffKind)]) -> core::UnpackPreviewItem {
+        core::UnpackPreviewItem {
+            id: id.to_string(),
+            folder: format!("{}_dir", id),
+            will_install: !conflict,
+            reason: String::new(),
+            changed_files: changed
+                .iter()
+                .map(|(path, kind)| core::FileDiff { path: path.to_string(), kind: *kind })
+                .collect(),
+            conflict,
+        }
+    }
+
+    fn prompt(items: &[core::UnpackPreviewItem], answers: &str) -> (Option<Vec<String>>, String) {
+        let mut input = std::io::Cursor::new(answers.as_bytes().to_vec());
+        let mut output = Vec::new();
+        let chosen = prompt_unpack_conflicts(items, &mut input, &mut output).unwrap();
+        (chosen, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_prompt_only_asks_about_conflicts() {
+        let items = [item("new", false, &[]), item("alpha", true, &[]), item("beta", true, &[])];
+        let (chosen, out) = prompt(&items, "o\nskip\n");
+        assert_eq!(chosen, Some(vec!["alpha_dir".to_string()]));
+        assert!(out.contains("[1/2] plugin 'alpha' (alpha_dir)"));
+        assert!(out.contains("[2/2] plugin 'beta' (beta_dir)"));
+        assert!(!out.contains("'new'"));
+    }
+
+    #[test]
+    fn test_prompt_diff_summary_then_asks_again() {
+        let items = [item(
+            "alpha",
+            true,
+            &[("a.py", core::FileDiffKind::Modified), ("b.py", core::FileDiffKind::Missing)],
+        )];
+        let (chosen, out) = prompt(&items, "d\nx\nO\n");
+        assert_eq!(chosen, Some(vec!["alpha_dir".to_string()]));
+        assert!(out.contains("    Modified: a.py\n    Missing: b.py\n"));
+        assert!(out.contains("unknown answer 'x'"));
+        assert_eq!(out.matches("[1/1]").count(), 3);
+
+        let (_, out) = prompt(&[item("alpha", true, &[])], "d\ns\n");
+        assert!(out.contains("no per-file checksums"));
+    }
+
+    #[test]
+    fn test_prompt_abort_and_eof_install_nothing() {
+        let items = [item("alpha", true, &[]), item("beta", true, &[])];
+        assert_eq!(prompt(&items, "o\na\n").0, None);
+        assert_eq!(prompt(&items, "o\n").0, None);
+        assert_eq!(prompt(&[item("new", false, &[])], "").0, Some(Vec::new()));
+    }
 }
@@ -2,1425 +2,9959 @@ use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
-use chrono::{SecondsFormat, Utc};
+use chrono::{DateTime, Datelike, SecondsFormat, Timelike, Utc};
 use directories::ProjectDirs;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use md5::Context as Md5Context;
+use rand_core::OsRng;
 use rayon::prelude::*;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use semver::{Version, VersionReq};
+use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 use zip::read::ZipArchive;
 use zip::write::FileOptions;
 use zip::CompressionMethod;
 
-#[derive(Debug, Clone, Default)]
-pub(crate) struct BundleMeta {
-    pub(crate) name: Option<String>,
-    pub(crate) version: Option<String>,
-    pub(crate) author: Option<String>,
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BundleMeta {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub(crate) struct CheckFlags {
-    pub(crate) id: bool,
-    pub(crate) deps: bool,
-    pub(crate) base: bool,
+impl BundleMeta {
+    /// Fill the fields `self` leaves unset from `fallback`
+    pub fn or(self, fallback: &BundleMeta) -> BundleMeta {
+        BundleMeta {
+            name: self.name.or_else(|| fallback.name.clone()),
+            version: self.version.or_else(|| fallback.version.clone()),
+            author: self.author.or_else(|| fallback.author.clone()),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Clone)]
-pub(crate) struct UnpackPreviewItem {
-    pub(crate) id: String,
-    pub(crate) folder: String,
-    pub(crate) will_install: bool,
-    pub(crate) reason: String,
+/// Digest used for a plugin folder's identical-skip hash
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgo {
+    #[default]
+    Md5,
+    Sha256,
 }
 
-pub(crate) fn resolve_check_flags(id: bool, deps: bool, base: bool) -> CheckFlags {
-    if id || deps || base {
-        return CheckFlags { id, deps, base };
+impl HashAlgo {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HashAlgo::Md5 => "md5",
+            HashAlgo::Sha256 => "sha256",
+        }
     }
-    CheckFlags {
-        id: true,
-        deps: true,
-        base: true,
+}
+
+impl std::str::FromStr for HashAlgo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "md5" => Ok(HashAlgo::Md5),
+            "sha256" => Ok(HashAlgo::Sha256),
+            other => anyhow::bail!("unsupported hash algorithm: {} (expected md5 or sha256)", other),
+        }
     }
 }
 
-#[derive(Debug, Serialize)]
-pub(crate) struct CheckReport {
-    pub(crate) sdk_version: String,
-    pub(crate) plugins_checked: usize,
-    pub(crate) errors: Vec<String>,
-    pub(crate) warnings: Vec<String>,
-    pub(crate) python_online: Option<PythonOnlineReport>,
+/// What pack does with symlinks inside plugin folders
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SymlinkPolicy {
+    /// Leave links out of the bundle and the folder hash
+    #[default]
+    Skip,
+    /// Pack what the link points at as regular content
+    Follow,
+    /// Refuse to pack a plugin folder that contains links
+    Error,
 }
 
-#[derive(Debug, Serialize)]
-pub(crate) struct PythonOnlineReport {
-    pub(crate) enabled: bool,
-    pub(crate) uv_found: bool,
-    pub(crate) requirements_in: String,
-    pub(crate) compiled_txt: String,
-    pub(crate) exit_code: Option<i32>,
+impl std::str::FromStr for SymlinkPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "skip" => Ok(SymlinkPolicy::Skip),
+            "follow" => Ok(SymlinkPolicy::Follow),
+            "error" => Ok(SymlinkPolicy::Error),
+            other => anyhow::bail!("unsupported symlink policy: {} (expected skip, follow or error)", other),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-struct PluginDependencyDecl {
-    id: String,
-    recommended: Option<String>,
-    supported: Option<String>,
-    untested: Option<String>,
-    conflicts: Vec<String>,
+/// Zip compression method for pack output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionKind {
+    #[default]
+    Deflate,
+    Stored,
+    Bzip2,
+    Zstd,
 }
 
-#[derive(Debug, Clone)]
-struct PluginSdkDecl {
-    recommended: Option<String>,
-    supported: Option<String>,
-    untested: Option<String>,
-    conflicts: Vec<String>,
+impl CompressionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CompressionKind::Deflate => "deflate",
+            CompressionKind::Stored => "stored",
+            CompressionKind::Bzip2 => "bzip2",
+            CompressionKind::Zstd => "zstd",
+        }
+    }
+
+    /// Accepted `--compression-level` values; None when the method takes no level
+    pub fn level_range(self) -> Option<std::ops::RangeInclusive<i64>> {
+        match self {
+            CompressionKind::Deflate => Some(1..=9),
+            CompressionKind::Stored => None,
+            CompressionKind::Bzip2 => Some(1..=9),
+            CompressionKind::Zstd => Some(-7..=22),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-struct PluginRecord {
-    folder: String,
-    id: String,
-    name: String,
-    version: String,
-    entry: String,
-    sdk: PluginSdkDecl,
-    deps: Vec<PluginDependencyDecl>,
+impl std::str::FromStr for CompressionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "deflate" => Ok(CompressionKind::Deflate),
+            "stored" => Ok(CompressionKind::Stored),
+            "bzip2" => Ok(CompressionKind::Bzip2),
+            "zstd" => Ok(CompressionKind::Zstd),
+            other => anyhow::bail!("unsupported compression method: {} (expected deflate, stored, bzip2 or zstd)", other),
+        }
+    }
 }
 
-pub(crate) fn read_sdk_version(repo_root: &Path) -> Result<Version> {
-    let path = repo_root.join("plugin").join("sdk").join("version.py");
-    let text = fs::read_to_string(&path)
-        .with_context(|| format!("failed to read {}", path.display()))?;
-    let re = Regex::new(r#"SDK_VERSION\s*=\s*\"([^\"]+)\""#)?;
-    let caps = re
-        .captures(&text)
-        .ok_or_else(|| anyhow::anyhow!("failed to find SDK_VERSION in {}", path.display()))?;
-    let raw = caps.get(1).map(|m| m.as_str()).unwrap_or("0.0.0");
-    Ok(Version::parse(raw).with_context(|| format!("invalid SDK_VERSION '{raw}'"))?)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PackCompression {
+    pub method: CompressionKind,
+    pub level: Option<i64>,
 }
 
-fn parse_req(req: &str) -> Result<VersionReq> {
-    VersionReq::parse(req).with_context(|| format!("invalid version requirement: {req}"))
+impl PackCompression {
+    fn file_options(self) -> Result<FileOptions<'static, ()>> {
+        if let Some(level) = self.level {
+            match self.method.level_range() {
+                None => anyhow::bail!("compression method {} does not take a level", self.method.as_str()),
+                Some(range) if !range.contains(&level) => anyhow::bail!(
+                    "compression level {} out of range for {} ({}..={})",
+                    level,
+                    self.method.as_str(),
+                    range.start(),
+                    range.end()
+                ),
+                Some(_) => {}
+            }
+        }
+        let method = match self.method {
+            CompressionKind::Deflate => CompressionMethod::Deflated,
+            CompressionKind::Stored => CompressionMethod::Stored,
+            CompressionKind::Bzip2 => CompressionMethod::Bzip2,
+            #[cfg(feature = "zstd")]
+            CompressionKind::Zstd => CompressionMethod::Zstd,
+            #[cfg(not(feature = "zstd"))]
+            CompressionKind::Zstd => anyhow::bail!("zstd support is not compiled in (rebuild with --features zstd)"),
+        };
+        Ok(FileOptions::default()
+            .compression_method(method)
+            .compression_level(self.level))
+    }
 }
 
-fn any_req_matches(reqs: &[String], v: &Version) -> bool {
-    reqs.iter().filter_map(|s| VersionReq::parse(s).ok()).any(|r| r.matches(v))
+/// Earliest timestamp a zip entry can carry (1980-01-01T00:00:00Z)
+pub const ZIP_EPOCH: i64 = 315_532_800;
+
+/// Timestamp for reproducible packs: SOURCE_DATE_EPOCH when set (which also turns reproducible
+/// mode on), otherwise ZIP_EPOCH if `reproducible` is requested; None means "use the clock".
+pub fn resolve_source_date_epoch(reproducible: bool) -> Result<Option<i64>> {
+    match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(v) if !v.trim().is_empty() => {
+            let epoch = v
+                .trim()
+                .parse::<i64>()
+                .with_context(|| format!("invalid SOURCE_DATE_EPOCH: {}", v))?;
+            Ok(Some(epoch))
+        }
+        _ => Ok(reproducible.then_some(ZIP_EPOCH)),
+    }
 }
 
-pub(crate) fn run_checks(
-    plugins_dir: &Path,
-    plugin_id: Option<&str>,
-    sdk_version: &Version,
-    checks: CheckFlags,
-) -> Result<CheckReport> {
-    let mut plugins = read_plugin_records(plugins_dir, plugin_id)?;
-    let plugins_checked = plugins.len();
+fn zip_datetime(epoch: i64) -> Result<zip::DateTime> {
+    let t = DateTime::<Utc>::from_timestamp(epoch.max(ZIP_EPOCH), 0)
+        .with_context(|| format!("timestamp out of range: {}", epoch))?;
+    zip::DateTime::from_date_and_time(
+        t.year() as u16,
+        t.month() as u8,
+        t.day() as u8,
+        t.hour() as u8,
+        t.minute() as u8,
+        t.second() as u8,
+    )
+    .map_err(|_| anyhow::anyhow!("timestamp {} cannot be stored in a zip entry", epoch))
+}
 
-    let mut errors: Vec<String> = Vec::new();
-    let mut warnings: Vec<String> = Vec::new();
+enum FolderHasher {
+    Md5(Md5Context),
+    Sha256(Sha256),
+}
 
-    if checks.id {
-        check_id_conflicts(&plugins, &mut errors);
+impl FolderHasher {
+    fn new(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Md5 => FolderHasher::Md5(Md5Context::new()),
+            HashAlgo::Sha256 => FolderHasher::Sha256(Sha256::new()),
+        }
     }
-    if checks.base {
-        check_sdk_compat(&plugins, sdk_version, &mut errors, &mut warnings)?;
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            FolderHasher::Md5(h) => h.consume(data),
+            FolderHasher::Sha256(h) => h.update(data),
+        }
     }
-    if checks.deps {
-        check_dependencies(&plugins, &mut errors, &mut warnings)?;
+
+    fn finish_hex(self) -> String {
+        match self {
+            FolderHasher::Md5(h) => format!("{:x}", h.compute()),
+            FolderHasher::Sha256(h) => format!("{:x}", h.finalize()),
+        }
     }
+}
 
-    errors.sort();
-    warnings.sort();
+#[derive(Clone, Copy, Debug)]
+pub struct CheckFlags {
+    pub id: bool,
+    pub deps: bool,
+    pub base: bool,
+    /// Validate plugin.toml fields (validate_plugin_toml)
+    pub toml: bool,
+    /// Resolve each plugin's `entry` to a file in its folder and look for the attribute (check_entries)
+    pub entry: bool,
+    /// With `id`: every plugin id must equal its folder name (`[tool.neko.check] strict_naming`)
+    pub strict_naming: bool,
+    /// Parse profiles.toml and profiles/*.toml (check_profiles)
+    pub profiles: bool,
+    /// With `profiles`: warn about profile files larger than this; 0 turns the warning off
+    pub profile_warn_size: u64,
+    /// Leave plugins listed in `.disabled.toml` out of run_checks; they still satisfy dependencies
+    pub skip_disabled: bool,
+}
 
-    plugins.sort_by(|a, b| a.id.cmp(&b.id));
+/// Default `check --profile-warn-size`: profiles are copied into every bundle
+pub const PROFILE_WARN_SIZE: u64 = 64 << 10;
 
-    Ok(CheckReport {
-        sdk_version: sdk_version.to_string(),
-        plugins_checked,
-        errors,
-        warnings,
-        python_online: None,
-    })
+#[derive(Debug, Serialize, Clone)]
+pub struct UnpackPreviewItem {
+    pub id: String,
+    pub folder: String,
+    pub will_install: bool,
+    pub reason: String,
+    /// Files that differ from the bundle; only known for format_version 2 manifests
+    pub changed_files: Vec<FileDiff>,
+    /// An installed plugin differs (or cannot be compared), so installing means overwriting it
+    #[serde(skip)]
+    pub conflict: bool,
 }
 
-fn resolve_cache_dir(repo_root: &Path, override_dir: Option<&Path>) -> PathBuf {
-    if let Some(p) = override_dir {
-        return p.to_path_buf();
-    }
-    if let Some(dirs) = ProjectDirs::from("io", "neko", "neko_plugin_cli") {
-        return dirs.cache_dir().to_path_buf();
-    }
-    repo_root.join(".cache")
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileDiffKind {
+    /// Content differs from the bundled file
+    Modified,
+    /// In the bundle but not in the installed folder
+    Missing,
+    /// In the installed folder but not in the bundle
+    Extra,
 }
 
-pub(crate) fn run_python_online_check(
-    repo_root: &Path,
-    plugins_dir: &Path,
-    plugin_id: Option<&str>,
-    strict: bool,
-    cache_dir_override: Option<&Path>,
-) -> Result<(PythonOnlineReport, Vec<String>, Vec<String>)> {
-    let plugins = read_plugin_records(plugins_dir, plugin_id)?;
+#[derive(Debug, Serialize, Clone)]
+pub struct FileDiff {
+    pub path: String,
+    pub kind: FileDiffKind,
+}
 
-    let cache_root = resolve_cache_dir(repo_root, cache_dir_override)
-        .join("neko_plugin_cli")
-        .join("check_python");
-    fs::create_dir_all(&cache_root)
-        .with_context(|| format!("failed to create cache dir {}", cache_root.display()))?;
+/// Which plugins unpack_zip wrote and which it left alone
+#[derive(Debug, Serialize, Default)]
+pub struct UnpackResult {
+    pub installed: Vec<UnpackedPlugin>,
+    pub skipped: Vec<UnpackedPlugin>,
+    pub warnings: Vec<String>,
+    /// Fingerprint of the key whose signature was verified, if any
+    pub signed_by: Option<String>,
+    /// extras/ files; only handled when an extras destination is given
+    pub extras_installed: Vec<UnpackedExtra>,
+    pub extras_skipped: Vec<UnpackedExtra>,
+    /// Symlink entries recreated as links; only with `--allow-symlinks`
+    pub symlinks: Vec<UnpackedSymlink>,
+    /// Copies of overwritten plugin folders; only with `--backup`
+    pub backups: Vec<UnpackBackup>,
+    /// Bundled profiles copied into (or kept out of) the live profiles; only with `--apply-profiles merge|overwrite`
+    pub profiles: Vec<AppliedProfile>,
+    /// run_checks on the installed plugins; only with `--check`
+    pub check: Option<CheckReport>,
+    /// The manifest's [bundle] table; None for bundles packed without one
+    pub bundle: Option<BundleMeta>,
+}
 
-    let requirements_in = cache_root.join("requirements.in");
-    let compiled_txt = cache_root.join("compiled.txt");
-    let stderr_txt = cache_root.join("compile.stderr.txt");
+/// `unpack --report`: what one unpack run did, for CI logs and audits. Written whether or not the
+/// run succeeded; a run that stopped part way lists the plugins it got to.
+#[derive(Debug, Serialize)]
+pub struct UnpackReport {
+    /// The zip path as given, or a label like `<stdin>`
+    pub source: String,
+    pub dest: PathBuf,
+    pub ok: bool,
+    /// Why the run failed; None when it succeeded
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// The manifest's [bundle] table; None when the bundle has none or could not be read
+    pub bundle: Option<BundleMeta>,
+    pub signed_by: Option<String>,
+    pub plugins: Vec<UnpackReportPlugin>,
+    pub summary: UnpackReportSummary,
+    pub warnings: Vec<String>,
+    /// What a staged unpack put back after failing to move a plugin into place
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollback: Option<UnpackRollback>,
+}
 
-    let _ = fs::remove_file(&compiled_txt);
-    let _ = fs::remove_file(&stderr_txt);
+#[derive(Debug, Serialize)]
+pub struct UnpackReportPlugin {
+    pub id: String,
+    pub folder: String,
+    pub action: UnpackAction,
+    /// The reason unpack gives, e.g. "identical (md5 match)"; the error for a failed plugin
+    pub reason: String,
+    pub files: usize,
+    pub bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_files: Vec<FileDiff>,
+}
 
-    let mut reqs: Vec<String> = Vec::new();
-    reqs.extend(read_pyproject_dependencies(&repo_root.join("pyproject.toml"))?);
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnpackAction {
+    Installed,
+    Overwritten,
+    Skipped,
+    Failed,
+}
 
-    for p in &plugins {
-        let pp = repo_root
-            .join("plugin")
-            .join("plugins")
-            .join(&p.folder)
-            .join("pyproject.toml");
-        if pp.is_file() {
-            reqs.extend(read_pyproject_dependencies(&pp)?);
+#[derive(Debug, Serialize, Default)]
+pub struct UnpackReportSummary {
+    pub installed: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+impl UnpackReport {
+    /// Build the report from what unpack_zip / unpack_reader returned
+    pub fn new(source: &str, dest: &Path, duration: std::time::Duration, outcome: &Result<UnpackResult>) -> UnpackReport {
+        let rollback = outcome
+            .as_ref()
+            .err()
+            .and_then(|e| e.downcast_ref::<UnpackStopped>())
+            .and_then(|stopped| stopped.rollback.clone());
+        let (done, failed, error) = match outcome {
+            Ok(result) => (Some(result), None, None),
+            Err(e) => match e.downcast_ref::<UnpackStopped>() {
+                Some(stopped) => {
+                    // The cause below the "unpack stopped at plugin ..." context
+                    let context = stopped.to_string();
+                    let cause: Vec<String> = e
+                        .chain()
+                        .map(|c| c.to_string())
+                        .skip_while(|c| *c != context)
+                        .skip(1)
+                        .collect();
+                    (Some(&stopped.done), Some((&stopped.plugin, cause.join(": "))), Some(format!("{:#}", e)))
+                }
+                None => (None, None, Some(format!("{:#}", e))),
+            },
+        };
+        let mut plugins = Vec::new();
+        if let Some(done) = done {
+            for p in &done.installed {
+                let action = if p.overwrote { UnpackAction::Overwritten } else { UnpackAction::Installed };
+                plugins.push(UnpackReportPlugin::new(p, action, p.reason.clone()));
+            }
+        }
+        if let Some((p, cause)) = failed {
+            plugins.push(UnpackReportPlugin::new(p, UnpackAction::Failed, cause));
+        }
+        if let Some(done) = done {
+            for p in &done.skipped {
+                plugins.push(UnpackReportPlugin::new(p, UnpackAction::Skipped, p.reason.clone()));
+            }
+        }
+        let mut summary = UnpackReportSummary::default();
+        for p in &plugins {
+            match p.action {
+                UnpackAction::Installed => summary.installed += 1,
+                UnpackAction::Overwritten => summary.overwritten += 1,
+                UnpackAction::Skipped => summary.skipped += 1,
+                UnpackAction::Failed => summary.failed += 1,
+            }
+            summary.files += p.files;
+            summary.bytes += p.bytes;
+        }
+        UnpackReport {
+            source: source.to_string(),
+            dest: dest.to_path_buf(),
+            ok: outcome.is_ok(),
+            error,
+            duration_ms: duration.as_millis() as u64,
+            bundle: done.and_then(|d| d.bundle.clone()),
+            signed_by: done.and_then(|d| d.signed_by.clone()),
+            plugins,
+            summary,
+            warnings: done.map(|d| d.warnings.clone()).unwrap_or_default(),
+            rollback,
         }
     }
 
-    reqs.retain(|r| {
-        let s = r.trim();
-        if s.is_empty() {
-            return false;
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        fs::write(path, json).with_context(|| PathContext::new("failed to write report", path))
+    }
+}
+
+impl UnpackReportPlugin {
+    fn new(p: &UnpackedPlugin, action: UnpackAction, reason: String) -> UnpackReportPlugin {
+        let written = p.written.unwrap_or_default();
+        UnpackReportPlugin {
+            id: p.id.clone(),
+            folder: p.folder.clone(),
+            action,
+            reason,
+            files: written.files,
+            bytes: written.bytes,
+            verification: p.verification.filter(|_| action != UnpackAction::Failed),
+            changed_files: p.changed_files.clone(),
         }
-        let lower = s.to_ascii_lowercase();
-        !(lower == "n.e.k.o" || lower.starts_with("n.e.k.o ") || lower.starts_with("n.e.k.o;")
-            || lower == "n-e-k-o" || lower.starts_with("n-e-k-o ") || lower.starts_with("n-e-k-o;"))
-    });
+    }
+}
 
-    reqs.sort();
-    reqs.dedup();
+/// `unpack --check`: run the plugin checks on the installed plugins once they are in place
+pub struct UnpackCheck {
+    pub sdk_version: Version,
+    pub checks: CheckFlags,
+    /// Errors undo this run's installs instead of only being reported (`--check-strict`)
+    pub strict: bool,
+}
 
-    let mut f = fs::File::create(&requirements_in)
-        .with_context(|| format!("failed to write {}", requirements_in.display()))?;
-    writeln!(f, "-e {}", repo_root.display())?;
-    for r in &reqs {
-        if r.trim().is_empty() {
-            continue;
+/// Output format of `check --graph`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz, for `dot -Tsvg`
+    Dot,
+    /// Mermaid flowchart, for Markdown docs
+    Mermaid,
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            other => anyhow::bail!("unsupported graph format: {} (expected dot or mermaid)", other),
         }
-        writeln!(f, "{}", r)?;
     }
+}
 
-    let mut errors: Vec<String> = Vec::new();
-    let mut warnings: Vec<String> = Vec::new();
+/// What `unpack --apply-profiles` does with bundled profiles besides storing them under _bundle_profiles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ApplyProfiles {
+    /// Leave the live profiles alone
+    #[default]
+    Skip,
+    /// Copy bundled profiles that do not exist in the plugin yet
+    Merge,
+    /// Replace the plugin's profiles with the bundled ones
+    Overwrite,
+}
 
-    let output = Command::new("uv")
-        .arg("pip")
-        .arg("compile")
-        .arg(requirements_in.as_os_str())
-        .arg("-o")
-        .arg(compiled_txt.as_os_str())
-        .output();
+impl std::str::FromStr for ApplyProfiles {
+    type Err = anyhow::Error;
 
-    match output {
-        Err(e) => {
-            let msg = format!("python-online check skipped: failed to execute uv ({})", e);
-            if strict {
-                errors.push(msg);
-            } else {
-                warnings.push(msg);
-            }
-            Ok((
-                PythonOnlineReport {
-                    enabled: true,
-                    uv_found: false,
-                    requirements_in: requirements_in.display().to_string(),
-                    compiled_txt: compiled_txt.display().to_string(),
-                    exit_code: None,
-                },
-                errors,
-                warnings,
-            ))
-        }
-        Ok(out) => {
-            let code = out.status.code();
-            if !out.stderr.is_empty() {
-                let _ = fs::write(&stderr_txt, &out.stderr);
-            }
-            if !out.status.success() {
-                let stderr = String::from_utf8_lossy(&out.stderr);
-                errors.push(format!(
-                    "python-online dependency resolution failed (see {}): {}",
-                    stderr_txt.display(),
-                    stderr.lines().take(20).collect::<Vec<_>>().join("\n")
-                ));
-            }
-            Ok((
-                PythonOnlineReport {
-                    enabled: true,
-                    uv_found: true,
-                    requirements_in: requirements_in.display().to_string(),
-                    compiled_txt: compiled_txt.display().to_string(),
-                    exit_code: code,
-                },
-                errors,
-                warnings,
-            ))
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "skip" => Ok(ApplyProfiles::Skip),
+            "merge" => Ok(ApplyProfiles::Merge),
+            "overwrite" => Ok(ApplyProfiles::Overwrite),
+            other => anyhow::bail!("unknown apply-profiles mode: {} (expected skip, merge or overwrite)", other),
         }
     }
 }
 
-fn read_pyproject_dependencies(pyproject_path: &Path) -> Result<Vec<String>> {
-    let txt = fs::read_to_string(pyproject_path)
-        .with_context(|| format!("failed to read {}", pyproject_path.display()))?;
-    let val: toml::Value = toml::from_str(&txt)
-        .with_context(|| format!("failed to parse {}", pyproject_path.display()))?;
-    let deps = val
-        .get("project")
-        .and_then(|v| v.get("dependencies"))
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|x| x.as_str().map(|s| s.to_string()))
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_default();
-    Ok(deps)
+#[derive(Debug, Serialize, Clone)]
+pub struct AppliedProfile {
+    pub id: String,
+    /// Path relative to the plugin folder, e.g. `profiles/dev.toml`
+    pub path: String,
+    pub status: AppliedProfileStatus,
 }
 
-fn read_plugin_records(plugins_dir: &Path, plugin_id: Option<&str>) -> Result<Vec<PluginRecord>> {
-    let mut out = Vec::new();
-    if !plugins_dir.is_dir() {
-        return Ok(out);
-    }
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AppliedProfileStatus {
+    /// Copied; the plugin did not have the file
+    Applied,
+    /// Replaced a different file (`overwrite`)
+    Overwritten,
+    /// Left alone; the plugin already has the same content
+    Identical,
+    /// Left alone; the plugin has its own version (`merge`)
+    Kept,
+    /// Left alone; the bundle predates recording where profiles came from
+    UnknownPath,
+}
 
-    for entry in fs::read_dir(plugins_dir)
-        .with_context(|| format!("failed to read dir {}", plugins_dir.display()))?
-    {
-        let entry = entry?;
-        let path = entry.path();
-        if !path.is_dir() {
-            continue;
+impl AppliedProfileStatus {
+    pub fn describe(self) -> &'static str {
+        match self {
+            AppliedProfileStatus::Applied => "applied",
+            AppliedProfileStatus::Overwritten => "overwritten",
+            AppliedProfileStatus::Identical => "skipped: identical",
+            AppliedProfileStatus::Kept => "skipped: exists (use --apply-profiles overwrite to replace)",
+            AppliedProfileStatus::UnknownPath => "skipped: the bundle does not record its original path",
         }
+    }
+}
 
-        let folder = match path.file_name().and_then(|v| v.to_str()) {
-            Some(s) => s.to_string(),
-            None => continue,
-        };
+#[derive(Debug, Serialize, Clone)]
+pub struct UnpackBackup {
+    pub id: String,
+    pub folder: String,
+    /// `<backup dir>/<folder>_<timestamp>`
+    pub path: PathBuf,
+}
 
-        let plugin_toml = path.join("plugin.toml");
-        if !plugin_toml.is_file() {
-            continue;
-        }
+#[derive(Debug, Serialize, Clone)]
+pub struct UnpackedSymlink {
+    /// `<folder>/<rel>` under the destination
+    pub path: String,
+    pub target: String,
+}
 
-        let txt = fs::read_to_string(&plugin_toml)
-            .with_context(|| format!("failed to read {}", plugin_toml.display()))?;
-        let val: toml::Value = toml::from_str(&txt)
-            .with_context(|| format!("failed to parse {}", plugin_toml.display()))?;
-        let plugin = val.get("plugin");
-
-        let id = plugin
-            .and_then(|v| v.get("id"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-        if let Some(want) = plugin_id {
-            if id != want {
-                continue;
-            }
-        }
+#[derive(Debug, Serialize, Clone)]
+pub struct UnpackedExtra {
+    /// Path inside extras/
+    pub path: String,
+    pub target: PathBuf,
+    pub reason: String,
+}
 
-        let name = plugin
-            .and_then(|v| v.get("name"))
-            .and_then(|v| v.as_str())
-            .unwrap_or(&id)
-            .to_string();
-        let version = plugin
-            .and_then(|v| v.get("version"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-        let entry_str = plugin
-            .and_then(|v| v.get("entry"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+#[derive(Debug, Serialize, Clone)]
+pub struct UnpackedPlugin {
+    pub id: String,
+    pub folder: String,
+    pub reason: String,
+    pub changed_files: Vec<FileDiff>,
+    /// How the extracted files were checked; only set for installed plugins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    /// Files removed from an overwritten plugin because the bundle no longer has them (`--prune`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pruned: Vec<String>,
+    /// Bundled files left out because they match the unpack excludes (`--exclude`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filtered: Vec<String>,
+    /// Files extracted into the plugin folder; only set for installed plugins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub written: Option<WrittenFiles>,
+    /// An existing folder was replaced (`--force` or chosen)
+    #[serde(skip)]
+    pub overwrote: bool,
+}
 
-        let sdk_tbl = val.get("plugin").and_then(|p| p.get("sdk"));
-        let sdk = PluginSdkDecl {
-            recommended: sdk_tbl
-                .and_then(|v| v.get("recommended"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            supported: sdk_tbl
-                .and_then(|v| v.get("supported"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            untested: sdk_tbl
-                .and_then(|v| v.get("untested"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            conflicts: sdk_tbl
-                .and_then(|v| v.get("conflicts"))
-                .and_then(|v| v.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|x| x.as_str().map(|s| s.to_string()))
-                        .collect::<Vec<_>>()
-                })
-                .unwrap_or_default(),
-        };
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WrittenFiles {
+    pub files: usize,
+    pub bytes: u64,
+}
 
-        let deps = val
-            .get("plugin")
-            .and_then(|p| p.get("dependency"))
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|d| {
-                        let id = d.get("id")?.as_str()?.to_string();
-                        let conflicts = d
-                            .get("conflicts")
-                            .and_then(|v| v.as_array())
-                            .map(|c| {
-                                c.iter()
-                                    .filter_map(|x| x.as_str().map(|s| s.to_string()))
-                                    .collect::<Vec<_>>()
-                            })
-                            .unwrap_or_default();
-                        Some(PluginDependencyDecl {
-                            id,
-                            recommended: d.get("recommended").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            supported: d.get("supported").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            untested: d.get("untested").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            conflicts,
-                        })
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default();
+/// Whether an installed plugin's files were checked against the manifest's per-file checksums
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verification {
+    /// Every file listed in the manifest was extracted and matched its size and sha256
+    Verified,
+    /// Checking was turned off (`--no-verify`)
+    Skipped,
+    /// The manifest has no per-file checksums (format_version 1)
+    NoChecksums,
+}
 
-        out.push(PluginRecord {
-            folder,
-            id,
-            name,
-            version,
-            entry: entry_str,
-            sdk,
-            deps,
-        });
+impl Verification {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Verification::Verified => "checksums verified",
+            Verification::Skipped => "checksums not verified (--no-verify)",
+            Verification::NoChecksums => "no per-file checksums in the manifest",
+        }
     }
+}
 
-    out.sort_by(|a, b| a.id.cmp(&b.id));
-    Ok(out)
+/// Error context that keeps the offending path, so callers can report it without parsing messages
+#[derive(Debug)]
+pub struct PathContext {
+    message: String,
+    pub path: PathBuf,
 }
 
-fn check_id_conflicts(plugins: &[PluginRecord], errors: &mut Vec<String>) {
-    use std::collections::HashMap;
-    let mut map: HashMap<&str, Vec<&str>> = HashMap::new();
-    for p in plugins {
-        map.entry(&p.id).or_default().push(&p.folder);
-    }
-    for (id, folders) in map {
-        if folders.len() > 1 {
-            errors.push(format!(
-                "plugin id conflict: id={} folders={}",
-                id,
-                folders.join(",")
-            ));
+impl PathContext {
+    pub fn new(what: &str, path: &Path) -> Self {
+        Self {
+            message: format!("{} {}", what, path.display()),
+            path: path.to_path_buf(),
         }
     }
 }
 
-fn check_sdk_compat(
-    plugins: &[PluginRecord],
-    sdk_version: &Version,
-    errors: &mut Vec<String>,
-    warnings: &mut Vec<String>,
-) -> Result<()> {
-    for p in plugins {
-        if any_req_matches(&p.sdk.conflicts, sdk_version) {
-            errors.push(format!(
-                "plugin {} conflicts with SDK_VERSION {} (conflicts={:?})",
-                p.id, sdk_version, p.sdk.conflicts
-            ));
-            continue;
-        }
-
-        let supported_ok = p
-            .sdk
-            .supported
-            .as_deref()
-            .map(|r| parse_req(r).map(|req| req.matches(sdk_version)))
-            .transpose()?
-            .unwrap_or(true);
-
-        if supported_ok {
-            continue;
-        }
+impl std::fmt::Display for PathContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
 
-        let untested_ok = p
-            .sdk
-            .untested
-            .as_deref()
-            .map(|r| parse_req(r).map(|req| req.matches(sdk_version)))
-            .transpose()?
-            .unwrap_or(false);
-
-        if untested_ok {
-            warnings.push(format!(
-                "plugin {} SDK_VERSION {} is in untested range ({})",
-                p.id,
-                sdk_version,
-                p.sdk.untested.clone().unwrap_or_default()
-            ));
-        } else {
-            errors.push(format!(
-                "plugin {} SDK_VERSION {} not supported (supported={:?} untested={:?})",
-                p.id, sdk_version, p.sdk.supported, p.sdk.untested
-            ));
+impl CheckFlags {
+    /// The enabled passes, e.g. "id,deps"; part of the check cache key
+    fn tag(&self) -> String {
+        let passes = [
+            ("id", self.id),
+            ("deps", self.deps),
+            ("base", self.base),
+            ("toml", self.toml),
+            ("entry", self.entry),
+            ("profiles", self.profiles),
+        ];
+        let mut tag = passes
+            .iter()
+            .filter(|(_, on)| *on)
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(",");
+        if self.profiles {
+            tag.push_str(&format!(",warn_size={}", self.profile_warn_size));
         }
+        tag
     }
-    Ok(())
 }
 
-pub(crate) fn preview_unpack(
-    zip_path: &Path,
-    dest_dir: &Path,
-    force: bool,
-    excludes: &GlobSet,
-) -> Result<Vec<UnpackPreviewItem>> {
-    let f = fs::File::open(zip_path)
-        .with_context(|| format!("failed to open zip {}", zip_path.display()))?;
-    let mut archive = ZipArchive::new(f).context("failed to read zip")?;
+pub fn resolve_check_flags(id: bool, deps: bool, base: bool, toml: bool, entry: bool, profiles: bool) -> CheckFlags {
+    if id || deps || base || toml || entry || profiles {
+        return CheckFlags {
+            id,
+            deps,
+            base,
+            toml,
+            entry,
+            strict_naming: false,
+            profiles,
+            profile_warn_size: PROFILE_WARN_SIZE,
+            skip_disabled: false,
+        };
+    }
+    CheckFlags {
+        id: true,
+        deps: true,
+        base: true,
+        toml: true,
+        entry: true,
+        strict_naming: false,
+        profiles: true,
+        profile_warn_size: PROFILE_WARN_SIZE,
+        skip_disabled: false,
+    }
+}
 
-    let manifest = read_manifest(&mut archive)?;
+/// How serious a check finding is; errors sort first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
 
-    let mut items = Vec::new();
+/// One problem found by `check`. `code` is stable across releases (e.g. `dep-missing`) so CI can
+/// match on it; `message` is for people.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckFinding {
+    pub code: &'static str,
+    pub severity: Severity,
+    /// The plugin the finding is about; None for repository-wide findings
+    pub plugin_id: Option<String>,
+    pub message: String,
+}
 
-    for p in &manifest.plugins {
-        let folder_rel = p.folder.trim_end_matches('/');
-        let folder_name = folder_rel
-            .split('/')
-            .nth(1)
-            .ok_or_else(|| anyhow::anyhow!("invalid manifest folder: {}", p.folder))?
-            .to_string();
+impl CheckFinding {
+    fn error(code: &'static str, plugin_id: Option<&str>, message: String) -> Self {
+        Self::new(code, Severity::Error, plugin_id, message)
+    }
 
-        let target_folder = dest_dir.join(&folder_name);
+    fn warning(code: &'static str, plugin_id: Option<&str>, message: String) -> Self {
+        Self::new(code, Severity::Warning, plugin_id, message)
+    }
 
-        if !target_folder.is_dir() {
-            items.push(UnpackPreviewItem {
-                id: p.id.clone(),
-                folder: folder_name,
-                will_install: true,
-                reason: "destination folder does not exist; will install / 目标目录不存在，将安装".to_string(),
-            });
-            continue;
+    fn new(code: &'static str, severity: Severity, plugin_id: Option<&str>, message: String) -> Self {
+        CheckFinding {
+            code,
+            severity,
+            plugin_id: plugin_id.map(str::to_string),
+            message,
         }
+    }
 
-        // Folder exists already
-        if let Some(md5_expected) = &p.md5 {
-            let md5_local = folder_md5(&target_folder, excludes)?;
-            if &md5_local == md5_expected {
-                items.push(UnpackPreviewItem {
-                    id: p.id.clone(),
-                    folder: folder_name,
-                    will_install: false,
-                    reason:
-                        "existing plugin is identical (md5 match); will skip / 已有插件 md5 一致，将跳过"
-                            .to_string(),
-                });
-                continue;
-            }
-
-            if !force {
-                items.push(UnpackPreviewItem {
-                    id: p.id.clone(),
-                    folder: folder_name,
-                    will_install: false,
-                    reason:
-                        "existing plugin differs; use --force to overwrite / 已有插件不同，需使用 --force 覆盖"
-                            .to_string(),
-                });
-            } else {
-                items.push(UnpackPreviewItem {
-                    id: p.id.clone(),
-                    folder: folder_name,
-                    will_install: true,
-                    reason:
-                        "existing plugin differs; will overwrite (--force) / 已有插件不同，将使用 --force 覆盖"
-                            .to_string(),
-                });
-            }
-        } else {
-            // No md5 info in manifest
-            if !force {
-                items.push(UnpackPreviewItem {
-                    id: p.id.clone(),
-                    folder: folder_name,
-                    will_install: false,
-                    reason:
-                        "existing folder without md5; use --force to overwrite / 目标目录已存在且无 md5，需使用 --force 覆盖"
-                            .to_string(),
-                });
-            } else {
-                items.push(UnpackPreviewItem {
-                    id: p.id.clone(),
-                    folder: folder_name,
-                    will_install: true,
-                    reason:
-                        "existing folder without md5; will overwrite (--force) / 目标目录已存在且无 md5，将使用 --force 覆盖"
-                            .to_string(),
-                });
-            }
+    /// The finding's `--baseline` fingerprint. The message hash ignores absolute paths and
+    /// whitespace runs so a baseline written on one machine matches on another.
+    fn baseline_entry(&self, abs_path: &Regex) -> BaselineEntry {
+        let normalized = abs_path.replace_all(&self.message, "${1}<path>");
+        let normalized = normalized.split_whitespace().collect::<Vec<_>>().join(" ");
+        let hash = format!("{:x}", Sha256::digest(normalized.as_bytes()));
+        BaselineEntry {
+            code: self.code.to_string(),
+            plugin_id: self.plugin_id.clone(),
+            hash: hash[..16].to_string(),
+            message: normalized,
         }
     }
+}
 
-    Ok(items)
+/// Absolute paths (Unix or Windows) inside a finding message
+fn abs_path_re() -> Result<Regex> {
+    Ok(Regex::new(r#"(^|[\s(=`'"])(?:/|[A-Za-z]:[\\/])[^\s,;)`'"]*"#)?)
 }
 
-fn check_dependencies(
-    plugins: &[PluginRecord],
-    errors: &mut Vec<String>,
-    warnings: &mut Vec<String>,
-) -> Result<()> {
-    use std::collections::HashMap;
-    let mut by_id: HashMap<&str, &PluginRecord> = HashMap::new();
-    for p in plugins {
-        by_id.insert(&p.id, p);
+#[derive(Debug, Serialize)]
+pub struct CheckReport {
+    pub sdk_version: String,
+    pub plugins_checked: usize,
+    /// Every finding, grouped by plugin (repository-wide ones first), errors before warnings
+    pub findings: Vec<CheckFinding>,
+    /// Messages of the error findings, sorted; derived from `findings` for older consumers
+    pub errors: Vec<String>,
+    /// Messages of the warning findings, sorted; derived from `findings`
+    pub warnings: Vec<String>,
+    /// Findings a `--baseline` suppressed; they are not in `findings`, `errors` or `warnings`
+    pub baselined: usize,
+    pub python_online: Option<PythonOnlineReport>,
+    /// plugin.toml edits of `--fix`, made before this report was computed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixes: Option<Vec<TomlFix>>,
+    /// Which plugins' findings came from the check cache; None with `--no-cache`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CheckCacheStats>,
+}
+
+impl CheckReport {
+    fn new(sdk_version: &Version, plugins_checked: usize, findings: Vec<CheckFinding>) -> Self {
+        let mut report = CheckReport {
+            sdk_version: sdk_version.to_string(),
+            plugins_checked,
+            findings: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            baselined: 0,
+            python_online: None,
+            fixes: None,
+            cache: None,
+        };
+        report.add_findings(findings);
+        report
     }
 
-    for p in plugins {
-        let pv = Version::parse(&p.version).unwrap_or_else(|_| Version::new(0, 0, 0));
-        let _ = pv;
+    fn add_findings(&mut self, findings: Vec<CheckFinding>) {
+        self.findings.extend(findings);
+        self.findings.sort_by(|a, b| {
+            (&a.plugin_id, a.severity, &a.message).cmp(&(&b.plugin_id, b.severity, &b.message))
+        });
+        let messages = |severity: Severity| -> Vec<String> {
+            let mut out: Vec<String> = self
+                .findings
+                .iter()
+                .filter(|f| f.severity == severity)
+                .map(|f| f.message.clone())
+                .collect();
+            out.sort();
+            out
+        };
+        self.errors = messages(Severity::Error);
+        self.warnings = messages(Severity::Warning);
+    }
 
-        for dep in &p.deps {
-            let target = match by_id.get(dep.id.as_str()) {
-                Some(t) => *t,
-                None => {
-                    errors.push(format!("plugin {} depends on missing plugin {}", p.id, dep.id));
-                    continue;
-                }
-            };
+    /// Merge the result of run_python_online_check into this report
+    pub fn attach_python_online(&mut self, (report, findings): (PythonOnlineReport, Vec<CheckFinding>)) {
+        self.add_findings(findings);
+        self.python_online = Some(report);
+    }
 
-            let tv = match Version::parse(&target.version) {
-                Ok(v) => v,
-                Err(_) => {
-                    errors.push(format!(
-                        "plugin {} dependency {} has invalid version {}",
-                        p.id, dep.id, target.version
-                    ));
-                    continue;
-                }
-            };
+    /// Merge the findings of run_py_syntax_check into this report
+    pub fn attach_py_syntax(&mut self, findings: Vec<CheckFinding>) {
+        self.add_findings(findings);
+    }
 
-            if any_req_matches(&dep.conflicts, &tv) {
-                errors.push(format!(
-                    "plugin {} dependency {} version {} hits conflicts {:?}",
-                    p.id, dep.id, tv, dep.conflicts
-                ));
-                continue;
-            }
+    /// Drop the findings `baseline` lists, counting them in `baselined`
+    pub fn apply_baseline(&mut self, baseline: &CheckBaseline) -> Result<()> {
+        use std::collections::HashSet;
+        let abs_path = abs_path_re()?;
+        let known: HashSet<(&str, Option<&str>, &str)> = baseline
+            .findings
+            .iter()
+            .map(|e| (e.code.as_str(), e.plugin_id.as_deref(), e.hash.as_str()))
+            .collect();
+        let (suppressed, kept): (Vec<CheckFinding>, Vec<CheckFinding>) =
+            std::mem::take(&mut self.findings).into_iter().partition(|f| {
+                let e = f.baseline_entry(&abs_path);
+                known.contains(&(e.code.as_str(), e.plugin_id.as_deref(), e.hash.as_str()))
+            });
+        self.baselined += suppressed.len();
+        self.add_findings(kept);
+        Ok(())
+    }
 
-            let supported_ok = dep
-                .supported
-                .as_deref()
-                .map(|r| parse_req(r).map(|req| req.matches(&tv)))
-                .transpose()?
-                .unwrap_or(true);
+    /// A baseline suppressing every finding of this report (`--write-baseline`)
+    pub fn to_baseline(&self) -> Result<CheckBaseline> {
+        let abs_path = abs_path_re()?;
+        let mut findings: Vec<BaselineEntry> = self.findings.iter().map(|f| f.baseline_entry(&abs_path)).collect();
+        findings.sort();
+        findings.dedup_by(|a, b| (&a.code, &a.plugin_id, &a.hash) == (&b.code, &b.plugin_id, &b.hash));
+        Ok(CheckBaseline {
+            version: CHECK_BASELINE_VERSION,
+            findings,
+        })
+    }
+}
 
-            if supported_ok {
-                continue;
-            }
+const CHECK_BASELINE_VERSION: u32 = 1;
 
-            let untested_ok = dep
-                .untested
-                .as_deref()
-                .map(|r| parse_req(r).map(|req| req.matches(&tv)))
-                .transpose()?
-                .unwrap_or(false);
+/// Known findings `check --baseline` suppresses; JSON, or TOML when the file name ends in `.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CheckBaseline {
+    pub version: u32,
+    #[serde(default)]
+    pub findings: Vec<BaselineEntry>,
+}
 
-            if untested_ok {
-                warnings.push(format!(
-                    "plugin {} dependency {} version {} is in untested range ({})",
-                    p.id,
-                    dep.id,
-                    tv,
-                    dep.untested.clone().unwrap_or_default()
-                ));
-            } else {
-                errors.push(format!(
-                    "plugin {} dependency {} version {} not supported (supported={:?} untested={:?})",
-                    p.id, dep.id, tv, dep.supported, dep.untested
-                ));
-            }
+/// Fingerprint of one finding: code, plugin and a hash of its normalized message
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_id: Option<String>,
+    pub hash: String,
+    /// The normalized message that was hashed, for people reading the file; not matched
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+}
+
+impl CheckBaseline {
+    fn is_toml(path: &Path) -> bool {
+        path.extension().is_some_and(|e| e.eq_ignore_ascii_case("toml"))
+    }
+
+    pub fn read(path: &Path) -> Result<CheckBaseline> {
+        let text = fs::read_to_string(path).with_context(|| PathContext::new("failed to read baseline", path))?;
+        let baseline: CheckBaseline = if Self::is_toml(path) {
+            toml::from_str(&text).with_context(|| PathContext::new("failed to parse baseline", path))?
+        } else {
+            serde_json::from_str(&text).with_context(|| PathContext::new("failed to parse baseline", path))?
+        };
+        if baseline.version != CHECK_BASELINE_VERSION {
+            anyhow::bail!(
+                "unsupported baseline version {} in {} (expected {})",
+                baseline.version,
+                path.display(),
+                CHECK_BASELINE_VERSION
+            );
         }
+        Ok(baseline)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let text = if Self::is_toml(path) {
+            toml::to_string(self).context("failed to serialize baseline")?
+        } else {
+            let mut json = serde_json::to_string_pretty(self)?;
+            json.push('\n');
+            json
+        };
+        fs::write(path, text).with_context(|| PathContext::new("failed to write baseline", path))
     }
-    Ok(())
 }
 
-#[derive(Debug, Serialize)]
-pub(crate) struct InfoOutput {
-    pub(crate) neko_version: String,
-    pub(crate) repo_root: PathBuf,
-    pub(crate) plugins: Vec<PluginMeta>,
+const CHECK_CACHE_VERSION: u32 = 1;
+
+/// Findings of one plugin for one pass, valid while `key` matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedCheck {
+    key: String,
+    folder: PathBuf,
+    findings: Vec<CachedFinding>,
+    /// The toml pass rejected a range, so base and deps skipped the plugin
+    #[serde(default)]
+    unusable_ranges: bool,
 }
 
-#[derive(Debug, Serialize)]
-pub(crate) struct PluginMeta {
-    pub(crate) id: String,
-    pub(crate) version: String,
-    pub(crate) entry: String,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFinding {
+    code: String,
+    severity: Severity,
+    plugin_id: Option<String>,
+    message: String,
 }
 
-#[derive(Debug, Serialize, Clone)]
-struct Manifest {
-    format_version: u32,
-    neko_base_version: String,
-    packed_at: String,
-    root_layout: String,
-    bundle: Option<ManifestBundle>,
-    bundle_profiles_root: Option<String>,
-    plugins: Vec<ManifestPlugin>,
+impl CachedFinding {
+    fn of(f: &CheckFinding) -> Self {
+        CachedFinding {
+            code: f.code.to_string(),
+            severity: f.severity,
+            plugin_id: f.plugin_id.clone(),
+            message: f.message.clone(),
+        }
+    }
+
+    fn to_finding(&self) -> CheckFinding {
+        CheckFinding {
+            code: intern_finding_code(&self.code),
+            severity: self.severity,
+            plugin_id: self.plugin_id.clone(),
+            message: self.message.clone(),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Clone)]
-struct ManifestBundle {
-    name: String,
-    version: Option<String>,
-    author: Option<String>,
+/// The `&'static str` for a finding code read back from the check cache; each distinct code is
+/// leaked once per process
+fn intern_finding_code(code: &str) -> &'static str {
+    static CODES: Mutex<std::collections::BTreeSet<&'static str>> = Mutex::new(std::collections::BTreeSet::new());
+    let mut codes = CODES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(known) = codes.get(code) {
+        return known;
+    }
+    let leaked: &'static str = Box::leak(code.to_string().into_boxed_str());
+    codes.insert(leaked);
+    leaked
 }
 
-#[derive(Debug, Serialize, Clone)]
-struct ManifestPlugin {
-    id: String,
-    name: String,
-    version: String,
-    entry: String,
-    folder: String,
-    md5: Option<String>,
-    bundled_profiles: Vec<String>,
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckCacheData {
+    version: u32,
+    /// Keyed by "{pass}:{folder}"; a plugin whose inputs changed overwrites its slot
+    slots: std::collections::BTreeMap<String, CachedCheck>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ManifestDe {
-    format_version: u32,
-    neko_base_version: String,
-    packed_at: String,
-    root_layout: String,
-    bundle: Option<ManifestBundleDe>,
-    bundle_profiles_root: Option<String>,
-    plugins: Vec<ManifestPluginDe>,
+/// Which plugins `check` took from the cache and which it had to check
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckCacheStats {
+    /// Plugin ids whose findings were all reused
+    pub cached: std::collections::BTreeSet<String>,
+    /// Plugin ids checked afresh for at least one pass
+    pub checked: std::collections::BTreeSet<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ManifestBundleDe {
-    name: String,
-    version: Option<String>,
-    author: Option<String>,
+#[derive(Debug, Default)]
+struct CheckCacheState {
+    data: CheckCacheData,
+    stats: CheckCacheStats,
+    dirty: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct ManifestPluginDe {
-    id: String,
-    name: String,
-    version: String,
-    entry: String,
-    folder: String,
-    md5: Option<String>,
-    bundled_profiles: Option<Vec<String>>,
+/// Per-plugin check findings, saved as JSON when dropped (`check --no-cache` skips it). An entry
+/// is keyed by the md5 folder hash of the plugin (standard excludes), the SDK version, the check
+/// flags and the CLI version; the deps pass also keys on the folder hash of every plugin a
+/// dependency names, since its findings depend on their versions. Id conflicts and dependency
+/// cycles span all plugins and are always recomputed.
+#[derive(Debug)]
+pub struct CheckCache {
+    path: PathBuf,
+    excludes: Excludes,
+    state: Mutex<CheckCacheState>,
 }
 
-fn sanitize_for_filename(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    for ch in s.chars() {
-        let keep = ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.');
-        out.push(if keep { ch } else { '_' });
+impl CheckCache {
+    /// A missing, unreadable or outdated cache file simply starts an empty cache. Folder hashes go
+    /// through the pack hash cache, so unchanged files are not re-read either.
+    pub fn open(repo_root: &Path, cache_dir: Option<&Path>) -> Result<CheckCache> {
+        let path = resolve_cache_dir(repo_root, cache_dir)
+            .join("neko_plugin_cli")
+            .join("check_cache.json");
+        let data = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CheckCacheData>(&bytes).ok())
+            .filter(|d| d.version == CHECK_CACHE_VERSION)
+            .unwrap_or_else(|| CheckCacheData {
+                version: CHECK_CACHE_VERSION,
+                ..Default::default()
+            });
+        Ok(CheckCache {
+            path,
+            excludes: build_excludes(&[])?.hash_cache(repo_root, cache_dir),
+            state: Mutex::new(CheckCacheState {
+                data,
+                ..Default::default()
+            }),
+        })
     }
-    if out.is_empty() {
-        "bundle".to_string()
-    } else {
-        out
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CheckCacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
     }
-}
 
-fn derive_bundle_name(out_path: &Path) -> String {
-    out_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .map(|s| sanitize_for_filename(s))
-        .unwrap_or_else(|| "bundle".to_string())
-}
+    pub fn stats(&self) -> CheckCacheStats {
+        self.lock().stats.clone()
+    }
 
-fn read_file_to_zip<W: Write + std::io::Seek>(
-    zip: &mut zip::ZipWriter<W>,
-    zip_path: &str,
-    src: &Path,
-    options: FileOptions<()>,
-) -> Result<()> {
-    zip.start_file(zip_path, options)?;
-    let mut f = fs::File::open(src).with_context(|| format!("failed to open {}", src.display()))?;
-    let mut buf = [0u8; 1024 * 64];
-    loop {
-        let n = f.read(&mut buf)?;
-        if n == 0 {
-            break;
+    /// Not memoized: `check --fix` edits plugin.toml between two runs on the same cache
+    fn folder_hash(&self, plugin_dir: &Path) -> Result<String> {
+        folder_hash(plugin_dir, &self.excludes, HashAlgo::Md5)
+    }
+
+    /// Cache key of the `check_records` passes for `p`
+    fn checks_key(
+        &self,
+        plugins_dir: &Path,
+        p: &PluginRecord,
+        available: &[PluginRecord],
+        sdk_version: &Version,
+        checks: CheckFlags,
+    ) -> Result<String> {
+        let mut parts = vec![
+            format!("sdk={}", sdk_version),
+            format!("plugin={}", self.folder_hash(&plugins_dir.join(&p.folder))?),
+        ];
+        if checks.deps {
+            for dep in &p.deps {
+                // Same choice check_dependencies makes when an id is taken twice: the last one
+                let target = match available.iter().rev().find(|a| a.id == dep.id) {
+                    Some(t) => self.folder_hash(&plugins_dir.join(&t.folder))?,
+                    None => "missing".to_string(),
+                };
+                parts.push(format!("dep:{}={}", dep.id, target));
+            }
         }
-        zip.write_all(&buf[..n])?;
+        Ok(Self::key(&parts))
     }
-    Ok(())
-}
 
-fn collect_profile_files(plugin_dir: &Path) -> Vec<PathBuf> {
-    let mut out: Vec<PathBuf> = Vec::new();
-    let p1 = plugin_dir.join("profiles.toml");
-    if p1.is_file() {
-        out.push(p1);
+    /// Cache key of run_py_syntax_check for the plugin folder `plugin_dir`
+    fn py_syntax_key(&self, plugin_dir: &Path) -> Result<String> {
+        Ok(Self::key(&[format!("plugin={}", self.folder_hash(plugin_dir)?)]))
     }
-    let pdir = plugin_dir.join("profiles");
-    if pdir.is_dir() {
-        for e in WalkDir::new(&pdir).follow_links(false) {
-            if let Ok(e) = e {
-                if e.file_type().is_file() {
-                    out.push(e.path().to_path_buf());
-                }
-            }
+
+    fn key(parts: &[String]) -> String {
+        let mut h = Sha256::new();
+        h.update(format!("{}\0{}\0", env!("CARGO_PKG_VERSION"), CHECK_CACHE_VERSION));
+        for part in parts {
+            h.update(part.as_bytes());
+            h.update([0u8]);
         }
+        format!("{:x}", h.finalize())
     }
-    out.sort();
-    out
-}
 
-#[derive(Debug, Clone)]
-pub(crate) struct PluginPackItem {
-    pub(crate) id: String,
-    pub(crate) name: String,
-    pub(crate) version: String,
-    pub(crate) entry: String,
-    pub(crate) folder: String,
-    pub(crate) path: PathBuf,
-    pub(crate) md5: Option<String>,
-}
+    fn slot(pass: &str, plugin_dir: &Path) -> Result<(String, PathBuf)> {
+        let dir = std::path::absolute(plugin_dir).with_context(|| PathContext::new("failed to resolve", plugin_dir))?;
+        Ok((format!("{}:{}", pass, dir.display()), dir))
+    }
 
-pub(crate) fn find_repo_root(mut start: PathBuf) -> Result<PathBuf> {
-    for _ in 0..10 {
-        let pyproject = start.join("pyproject.toml");
-        let plugins_dir = start.join("plugin").join("plugins");
-        if pyproject.is_file() && plugins_dir.is_dir() {
-            return Ok(start);
+    /// The stored findings of `pass` for the plugin in `plugin_dir` when `key` still matches
+    fn get(&self, pass: &str, plugin_dir: &Path, key: &str, plugin_id: &str) -> Result<Option<(Vec<CheckFinding>, bool)>> {
+        let (slot, _) = Self::slot(pass, plugin_dir)?;
+        let mut state = self.lock();
+        let hit = state
+            .data
+            .slots
+            .get(&slot)
+            .filter(|c| c.key == key)
+            .map(|c| (c.findings.iter().map(CachedFinding::to_finding).collect(), c.unusable_ranges));
+        if hit.is_some() {
+            if !state.stats.checked.contains(plugin_id) {
+                state.stats.cached.insert(plugin_id.to_string());
+            }
+        } else {
+            state.stats.cached.remove(plugin_id);
+            state.stats.checked.insert(plugin_id.to_string());
         }
-        if !start.pop() {
-            break;
+        Ok(hit)
+    }
+
+    fn put(&self, pass: &str, plugin_dir: &Path, key: String, findings: &[CheckFinding], unusable_ranges: bool) -> Result<()> {
+        let (slot, folder) = Self::slot(pass, plugin_dir)?;
+        let entry = CachedCheck {
+            key,
+            folder,
+            findings: findings.iter().map(CachedFinding::of).collect(),
+            unusable_ranges,
+        };
+        let mut state = self.lock();
+        state.data.slots.insert(slot, entry);
+        state.dirty = true;
+        Ok(())
+    }
+
+    /// Entries of deleted plugin folders are dropped; the file is replaced atomically
+    fn save(&self) -> Result<()> {
+        let mut state = self.lock();
+        if !state.dirty {
+            return Ok(());
         }
+        state.data.slots.retain(|_, c| c.folder.is_dir());
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir).with_context(|| PathContext::new("failed to create cache dir", dir))?;
+        let tmp = self.path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp, serde_json::to_vec(&state.data)?)
+            .with_context(|| PathContext::new("failed to write", &tmp))?;
+        fs::rename(&tmp, &self.path).with_context(|| PathContext::new("failed to write", &self.path))?;
+        state.dirty = false;
+        Ok(())
     }
-    anyhow::bail!("failed to locate repo root from cwd")
 }
 
-fn read_neko_base_version(repo_root: &Path) -> Result<String> {
-    let pyproject_path = repo_root.join("pyproject.toml");
-    let pyproject_text = fs::read_to_string(&pyproject_path)
-        .with_context(|| format!("failed to read {}", pyproject_path.display()))?;
-    let pyproject: toml::Value = toml::from_str(&pyproject_text)
-        .with_context(|| format!("failed to parse {}", pyproject_path.display()))?;
-    Ok(pyproject
-        .get("project")
-        .and_then(|v| v.get("version"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown")
-        .to_string())
+impl Drop for CheckCache {
+    // Best effort, like HashCache: a cache that cannot be written only costs speed next time
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
 }
 
-pub(crate) fn collect_info(root: Option<&Path>) -> Result<InfoOutput> {
-    let repo_root = match root {
-        Some(p) => p.to_path_buf(),
-        None => find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
-    };
+#[derive(Debug, Serialize)]
+pub struct PythonOnlineReport {
+    pub enabled: bool,
+    pub uv_found: bool,
+    pub requirements_in: String,
+    pub compiled_txt: String,
+    pub exit_code: Option<i32>,
+    /// What `--lock` wrote or `--verify-lock` compared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock: Option<PythonLockReport>,
+}
 
-    let pyproject_path = repo_root.join("pyproject.toml");
-    let pyproject_text = fs::read_to_string(&pyproject_path)
-        .with_context(|| format!("failed to read {}", pyproject_path.display()))?;
+/// The lockfile of `check --python --lock`
+#[derive(Debug, Serialize)]
+pub struct PythonLockReport {
+    pub path: String,
+    /// The requirements.in the lock was compiled from, written next to it
+    pub requirements_in: String,
+    /// sha256 of that requirements.in, also recorded in the lock header
+    pub inputs_sha256: String,
+    /// True when the lock was written, false when it was verified
+    pub written: bool,
+    /// Requirement lines of the lock the fresh compile dropped (`--verify-lock`)
+    pub removed: Vec<String>,
+    /// Requirement lines the fresh compile added (`--verify-lock`)
+    pub added: Vec<String>,
+}
 
-    let pyproject: toml::Value = toml::from_str(&pyproject_text)
-        .with_context(|| format!("failed to parse {}", pyproject_path.display()))?;
+#[derive(Debug, Clone)]
+struct PluginDependencyDecl {
+    id: String,
+    recommended: Option<String>,
+    supported: Option<String>,
+    untested: Option<String>,
+    conflicts: Vec<String>,
+}
 
-    let neko_version = pyproject
-        .get("project")
-        .and_then(|v| v.get("version"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown")
-        .to_string();
+#[derive(Debug, Clone)]
+struct PluginSdkDecl {
+    recommended: Option<String>,
+    supported: Option<String>,
+    untested: Option<String>,
+    conflicts: Vec<String>,
+}
 
-    let plugins_dir = repo_root.join("plugin").join("plugins");
-    let plugins = scan_plugins(&plugins_dir)?;
+#[derive(Debug, Clone)]
+struct PluginRecord {
+    folder: String,
+    id: String,
+    version: String,
+    entry: String,
+    sdk: PluginSdkDecl,
+    deps: Vec<PluginDependencyDecl>,
+}
 
-    Ok(InfoOutput {
-        neko_version,
-        repo_root,
-        plugins,
-    })
+pub fn read_sdk_version(repo_root: &Path) -> Result<Version> {
+    let path = repo_root.join("plugin").join("sdk").join("version.py");
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let re = Regex::new(r#"SDK_VERSION\s*=\s*\"([^\"]+)\""#)?;
+    let caps = re
+        .captures(&text)
+        .ok_or_else(|| anyhow::anyhow!("failed to find SDK_VERSION in {}", path.display()))?;
+    let raw = caps.get(1).map(|m| m.as_str()).unwrap_or("0.0.0");
+    Ok(Version::parse(raw).with_context(|| format!("invalid SDK_VERSION '{raw}'"))?)
 }
 
-fn scan_plugins(plugins_dir: &Path) -> Result<Vec<PluginMeta>> {
-    let mut out = Vec::new();
-    if !plugins_dir.is_dir() {
-        return Ok(out);
+fn parse_req(req: &str) -> Result<VersionReq> {
+    VersionReq::parse(req).with_context(|| format!("invalid version requirement: {req}"))
+}
+
+/// `1` or `1.2` padded to `1.0.0` / `1.2.0`; None for anything else
+fn padded_version(version: &str) -> Option<String> {
+    let parts: Vec<&str> = version.split('.').collect();
+    if parts.len() > 2 || !parts.iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    let mut padded = parts.join(".");
+    for _ in parts.len()..3 {
+        padded.push_str(".0");
     }
+    Version::parse(&padded).ok().map(|v| v.to_string())
+}
 
-    for entry in fs::read_dir(plugins_dir)
-        .with_context(|| format!("failed to read dir {}", plugins_dir.display()))?
-    {
-        let entry = entry?;
-        let path = entry.path();
-        if !path.is_dir() {
-            continue;
+/// The version an exact `=x.y.z` requirement pins
+fn pinned_version(req: &str) -> Option<Version> {
+    let req = VersionReq::parse(req).ok()?;
+    let [c] = req.comparators.as_slice() else {
+        return None;
+    };
+    if c.op != semver::Op::Exact || !c.pre.is_empty() {
+        return None;
+    }
+    Some(Version::new(c.major, c.minor?, c.patch?))
+}
+
+/// Finding codes `check --fix` rewrites
+pub const FIXABLE_CODES: &[&str] = &["version-missing-patch", "sdk-supported-missing", "dep-pinned"];
+
+/// One plugin.toml edit made (or with `--dry-run` proposed) by `check --fix`
+#[derive(Debug, Clone, Serialize)]
+pub struct TomlFix {
+    pub folder: String,
+    pub code: &'static str,
+    /// Key path below [plugin], as in validation messages
+    pub key: String,
+    /// None when the key was added
+    pub old: Option<String>,
+    pub new: String,
+}
+
+/// `check --fix`: rewrite the plugin.toml of each plugin with a FIXABLE_CODES finding in `report`.
+/// Versions get their missing components, [plugin.sdk] gets a `supported` range seeded from
+/// `sdk_version`, and `=x.y.z` dependency pins on exactly that version become `^x.y.z`. Comments
+/// and key order are kept; with `dry_run` nothing is written.
+pub fn fix_check_findings(
+    plugins_dir: &Path,
+    report: &CheckReport,
+    sdk_version: &Version,
+    dry_run: bool,
+) -> Result<Vec<TomlFix>> {
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+    let mut wanted: BTreeMap<&str, BTreeSet<&'static str>> = BTreeMap::new();
+    for f in report.findings.iter().filter(|f| FIXABLE_CODES.contains(&f.code)) {
+        if let Some(id) = &f.plugin_id {
+            wanted.entry(id).or_default().insert(f.code);
         }
+    }
+    let mut available = read_plugin_records(plugins_dir, None)?;
+    available.sort_by(|a, b| a.folder.cmp(&b.folder));
+    let versions: HashMap<&str, &str> = available.iter().map(|p| (p.id.as_str(), p.version.as_str())).collect();
 
-        let plugin_toml = path.join("plugin.toml");
-        if !plugin_toml.is_file() {
+    let mut fixes = Vec::new();
+    for p in &available {
+        let Some(codes) = wanted.get(p.id.as_str()) else {
+            continue;
+        };
+        let path = plugins_dir.join(&p.folder).join("plugin.toml");
+        let text = fs::read_to_string(&path).with_context(|| PathContext::new("failed to read", &path))?;
+        let mut doc: toml_edit::DocumentMut = text.parse().with_context(|| PathContext::new("failed to parse", &path))?;
+        let Some(plugin) = doc.get_mut("plugin").and_then(toml_edit::Item::as_table_like_mut) else {
             continue;
+        };
+        let mut edits: Vec<(&'static str, String, Option<String>, String)> = Vec::new();
+        for &code in codes {
+            match code {
+                "version-missing-patch" => {
+                    let item = plugin.get_mut("version");
+                    if let Some((item, padded)) = item.and_then(|i| i.as_str().and_then(padded_version).map(|v| (i, v))) {
+                        edits.push((code, "version".to_string(), set_toml_string(item, &padded), padded));
+                    }
+                }
+                "sdk-supported-missing" => {
+                    let sdk = plugin.get_mut("sdk").and_then(toml_edit::Item::as_table_like_mut);
+                    if let Some(sdk) = sdk.filter(|t| !t.contains_key("supported")) {
+                        let range = seeded_sdk_range(sdk_version);
+                        sdk.insert("supported", toml_edit::value(range.as_str()));
+                        edits.push((code, "sdk.supported".to_string(), None, range));
+                    }
+                }
+                "dep-pinned" => {
+                    let Some(deps) = plugin.get_mut("dependency").and_then(toml_edit::Item::as_array_of_tables_mut) else {
+                        continue;
+                    };
+                    for (i, dep) in deps.iter_mut().enumerate() {
+                        let target = dep.get("id").and_then(|v| v.as_str()).and_then(|id| versions.get(id));
+                        let target = target.and_then(|v| Version::parse(v).ok());
+                        let Some(item) = dep.get_mut("supported") else {
+                            continue;
+                        };
+                        let pinned = item.as_str().and_then(pinned_version);
+                        if let (Some(pinned), Some(target)) = (pinned, target)
+                            && pinned == target
+                        {
+                            let caret = format!("^{}", pinned);
+                            edits.push((code, format!("dependency[{}].supported", i), set_toml_string(item, &caret), caret));
+                        }
+                    }
+                }
+                _ => {}
+            }
         }
+        if edits.is_empty() {
+            continue;
+        }
+        if !dry_run {
+            fs::write(&path, doc.to_string()).with_context(|| PathContext::new("failed to write", &path))?;
+        }
+        fixes.extend(edits.into_iter().map(|(code, key, old, new)| TomlFix {
+            folder: p.folder.clone(),
+            code,
+            key,
+            old,
+            new,
+        }));
+    }
+    Ok(fixes)
+}
 
-        let txt = fs::read_to_string(&plugin_toml)
-            .with_context(|| format!("failed to read {}", plugin_toml.display()))?;
-        let val: toml::Value = toml::from_str(&txt)
-            .with_context(|| format!("failed to parse {}", plugin_toml.display()))?;
-
-        let plugin = val.get("plugin");
-        let id = plugin
-            .and_then(|v| v.get("id"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-        let version = plugin
-            .and_then(|v| v.get("version"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-        let entry_str = plugin
-            .and_then(|v| v.get("entry"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+/// Replace a string value in place, keeping its surrounding whitespace and comments; returns the old value
+fn set_toml_string(item: &mut toml_edit::Item, new: &str) -> Option<String> {
+    let value = item.as_value_mut()?;
+    let old = value.as_str().map(str::to_string);
+    let decor = value.decor().clone();
+    *value = toml_edit::Value::from(new);
+    *value.decor_mut() = decor;
+    old
+}
 
-        out.push(PluginMeta {
-            id,
-            version,
-            entry: entry_str,
-        });
+/// `supported` for a plugin known to work with `sdk`: from it up to the next breaking release
+fn seeded_sdk_range(sdk: &Version) -> String {
+    if sdk.major > 0 {
+        format!(">={}.{}.{}, <{}.0.0", sdk.major, sdk.minor, sdk.patch, sdk.major + 1)
+    } else {
+        format!(">=0.{}.{}, <0.{}.0", sdk.minor, sdk.patch, sdk.minor + 1)
     }
+}
 
-    out.sort_by(|a, b| a.id.cmp(&b.id));
-    Ok(out)
+fn any_req_matches(reqs: &[String], v: &Version) -> bool {
+    reqs.iter().filter_map(|s| VersionReq::parse(s).ok()).any(|r| r.matches(v))
 }
 
-pub(crate) fn scan_plugins_for_pack(
+/// Run the `checks` passes over every plugin (or just `plugin_id`). With a `cache`, plugins whose
+/// inputs did not change reuse their earlier findings.
+pub fn run_checks(
     plugins_dir: &Path,
-    plugin_ids: Option<&[String]>,
-) -> Result<Vec<PluginPackItem>> {
-    let mut out = Vec::new();
-    if !plugins_dir.is_dir() {
-        return Ok(out);
+    plugin_id: Option<&str>,
+    sdk_version: &Version,
+    checks: CheckFlags,
+    cache: Option<&CheckCache>,
+) -> Result<CheckReport> {
+    let plugins = read_plugin_records(plugins_dir, plugin_id)?;
+    let mut selected = plugins.clone();
+    if checks.skip_disabled {
+        let disabled = read_disabled_plugins(plugins_dir)?;
+        selected.retain(|p| !disabled.contains(&p.id));
     }
+    check_records(plugins_dir, selected, &plugins, sdk_version, checks, cache)
+}
 
-    for entry in fs::read_dir(plugins_dir)
-        .with_context(|| format!("failed to read dir {}", plugins_dir.display()))?
-    {
-        let entry = entry?;
-        let path = entry.path();
-        if !path.is_dir() {
-            continue;
-        }
+/// run_checks limited to `ids`, e.g. the plugins an unpack just installed; dependencies and id
+/// conflicts are still resolved against every plugin in `plugins_dir`
+pub fn run_checks_for(
+    plugins_dir: &Path,
+    ids: &[String],
+    sdk_version: &Version,
+    checks: CheckFlags,
+) -> Result<CheckReport> {
+    let all = read_plugin_records(plugins_dir, None)?;
+    let selected = all.iter().filter(|p| ids.contains(&p.id)).cloned().collect();
+    check_records(plugins_dir, selected, &all, sdk_version, checks, None)
+}
 
-        let folder = match path.file_name().and_then(|v| v.to_str()) {
-            Some(s) => s.to_string(),
-            None => continue,
+/// run_checks for the plugins inside a bundle, read from the archive without unpacking it. Only
+/// the id, deps and base passes run, since the others read plugin files. Dependencies resolve
+/// against the bundle plus the plugins already in `plugins_dir` it would not replace. Manifest
+/// entries whose plugin.toml is missing or disagrees with the manifest are reported as errors.
+pub fn run_checks_zip(
+    zip_path: &Path,
+    plugin_id: Option<&str>,
+    plugins_dir: &Path,
+    sdk_version: &Version,
+    mut checks: CheckFlags,
+) -> Result<CheckReport> {
+    let mut archive = open_bundle(zip_path)?;
+    let mut findings: Vec<CheckFinding> = Vec::new();
+    let read_toml = |archive: &mut ZipArchive<fs::File>, name: &str| -> Result<Option<String>> {
+        let mut file = match archive.by_name(name) {
+            Ok(f) => f,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(e).with_context(|| PathContext::new("failed to read zip", zip_path)),
         };
+        let mut text = String::new();
+        file.read_to_string(&mut text)
+            .with_context(|| format!("failed to read {} in {}", name, zip_path.display()))?;
+        Ok(Some(text))
+    };
+
+    let mut bundled: Vec<PluginRecord> = Vec::new();
+    if let Some(prefix) = plain_plugin_prefix(&archive, zip_path, false)? {
+        let name = format!("{}plugin.toml", prefix);
+        let text = read_toml(&mut archive, &name)?.unwrap_or_default();
+        let record = parse_plugin_record(prefix.trim_end_matches('/').to_string(), &text, None)
+            .with_context(|| format!("failed to parse {} in {}", name, zip_path.display()))?;
+        bundled.extend(record.map(|mut r| {
+            if r.folder.is_empty() {
+                r.folder = r.id.clone();
+            }
+            r
+        }));
+    } else {
+        let manifest = read_manifest(&mut archive)?;
+        for mp in &manifest.plugins {
+            let folder_rel = mp.folder.trim_end_matches('/');
+            let folder_name = folder_rel
+                .split('/')
+                .nth(1)
+                .ok_or_else(|| anyhow::anyhow!("invalid manifest folder: {}", mp.folder))?
+                .to_string();
+            let name = format!("{}/plugin.toml", folder_rel);
+            let Some(text) = read_toml(&mut archive, &name)? else {
+                findings.push(CheckFinding::error(
+                    "zip-plugin-toml-missing",
+                    Some(&mp.id),
+                    format!("plugin {}: {} is missing from the bundle", mp.id, name),
+                ));
+                continue;
+            };
+            let Some(record) = parse_plugin_record(folder_name, &text, None)
+                .with_context(|| format!("failed to parse {} in {}", name, zip_path.display()))?
+            else {
+                continue;
+            };
+            if record.id != mp.id {
+                findings.push(CheckFinding::error(
+                    "manifest-mismatch",
+                    Some(&mp.id),
+                    format!("plugin {}: manifest lists id '{}' but {} declares '{}'", mp.id, mp.id, name, record.id),
+                ));
+            }
+            if record.version != mp.version {
+                findings.push(CheckFinding::error(
+                    "manifest-mismatch",
+                    Some(&mp.id),
+                    format!(
+                        "plugin {}: manifest lists version {} but {} declares {}",
+                        mp.id, mp.version, name, record.version
+                    ),
+                ));
+            }
+            bundled.push(record);
+        }
+    }
+    if let Some(want) = plugin_id {
+        bundled.retain(|p| p.id == want);
+        findings.retain(|f| f.plugin_id.as_deref() == Some(want));
+    }
+
+    let mut available: Vec<PluginRecord> = read_plugin_records(plugins_dir, None)?
+        .into_iter()
+        .filter(|local| !bundled.iter().any(|b| b.id == local.id))
+        .collect();
+    available.extend(bundled.iter().cloned());
+
+    // check_record only touches plugins_dir for these passes
+    checks.toml = false;
+    checks.entry = false;
+    checks.profiles = false;
+    let mut report = check_records(plugins_dir, bundled, &available, sdk_version, checks, None)?;
+    report.add_findings(findings);
+    Ok(report)
+}
+
+/// Check `plugins`; `available` is what their dependencies may point at
+fn check_records(
+    plugins_dir: &Path,
+    mut plugins: Vec<PluginRecord>,
+    available: &[PluginRecord],
+    sdk_version: &Version,
+    checks: CheckFlags,
+    cache: Option<&CheckCache>,
+) -> Result<CheckReport> {
+    let plugins_checked = plugins.len();
+
+    let mut findings: Vec<CheckFinding> = Vec::new();
+
+    // Plugins whose ranges the toml pass rejected; the cycle pass cannot evaluate them
+    let mut unusable_ranges: Vec<String> = Vec::new();
+    let pass = format!("checks[{}]", checks.tag());
+    // Plugins are checked in parallel; merging the per-plugin results in order keeps the report,
+    // and which error wins when several plugins fail, independent of scheduling
+    let per_plugin: Vec<Result<(Vec<CheckFinding>, bool)>> = plugins
+        .par_iter()
+        .map(|p| {
+            let plugin_dir = plugins_dir.join(&p.folder);
+            let key = cache
+                .map(|c| c.checks_key(plugins_dir, p, available, sdk_version, checks))
+                .transpose()?;
+            let hit = match (cache, &key) {
+                (Some(c), Some(key)) => c.get(&pass, &plugin_dir, key, &p.id)?,
+                _ => None,
+            };
+            if let Some(hit) = hit {
+                return Ok(hit);
+            }
+            let (found, unusable) = check_record(plugins_dir, p, available, sdk_version, checks)?;
+            if let (Some(c), Some(key)) = (cache, key) {
+                c.put(&pass, &plugin_dir, key, &found, unusable)?;
+            }
+            Ok((found, unusable))
+        })
+        .collect();
+    for (p, result) in plugins.iter().zip(per_plugin) {
+        let (found, unusable) = result?;
+        if unusable {
+            unusable_ranges.push(p.folder.clone());
+        }
+        findings.extend(found);
+    }
+    if checks.id {
+        check_id_conflicts(&plugins, &mut findings);
+        check_entry_collisions(&plugins, &mut findings);
+        check_folder_collisions(&plugins, &mut findings);
+        if checks.strict_naming {
+            check_id_matches_folder(&plugins, &mut findings);
+        }
+    }
+    if checks.deps {
+        let evaluable: Vec<PluginRecord> = plugins
+            .iter()
+            .filter(|p| !unusable_ranges.contains(&p.folder))
+            .cloned()
+            .collect();
+        check_dependency_cycles(&evaluable, available, &mut findings);
+    }
+
+    plugins.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(CheckReport::new(sdk_version, plugins_checked, findings))
+}
+
+/// The passes that look at `p` alone (and, for deps, the plugins it names), which is what the
+/// check cache stores. The bool is set when the toml pass rejected a range, so the base and deps
+/// passes could not evaluate the plugin.
+fn check_record(
+    plugins_dir: &Path,
+    p: &PluginRecord,
+    available: &[PluginRecord],
+    sdk_version: &Version,
+    checks: CheckFlags,
+) -> Result<(Vec<CheckFinding>, bool)> {
+    let mut findings: Vec<CheckFinding> = Vec::new();
+    let mut unusable_ranges = false;
+    if checks.toml {
+        let mut v = validate_plugin_toml(&plugins_dir.join(&p.folder))?;
+        unusable_ranges = v.has_range_errors();
+        // The entry pass reports a bad entry with more detail
+        if checks.entry {
+            v.errors.retain(|i| i.field != "entry");
+        }
+        let id = Some(p.id.as_str());
+        findings.extend(v.errors.iter().map(|i| CheckFinding::error(i.code, id, v.describe_one(i))));
+        findings.extend(v.warnings.iter().map(|i| CheckFinding::warning(i.code, id, v.describe_one(i))));
+    }
+    let one = std::slice::from_ref(p);
+    if checks.entry {
+        check_entries(plugins_dir, one, &mut findings)?;
+    }
+    if checks.profiles {
+        check_profiles(plugins_dir, p, checks.profile_warn_size, &mut findings)?;
+    }
+    if !unusable_ranges {
+        if checks.base {
+            check_sdk_compat(one, sdk_version, &mut findings)?;
+        }
+        if checks.deps {
+            check_dependencies(one, available, &mut findings)?;
+        }
+    }
+    Ok((findings, unusable_ranges))
+}
+
+fn resolve_cache_dir(repo_root: &Path, override_dir: Option<&Path>) -> PathBuf {
+    if let Some(p) = override_dir {
+        return p.to_path_buf();
+    }
+    if let Some(dirs) = ProjectDirs::from("io", "neko", "neko_plugin_cli") {
+        return dirs.cache_dir().to_path_buf();
+    }
+    repo_root.join(".cache")
+}
+
+pub fn run_python_online_check(
+    repo_root: &Path,
+    plugins_dir: &Path,
+    plugin_id: Option<&str>,
+    strict: bool,
+    cache_dir_override: Option<&Path>,
+) -> Result<(PythonOnlineReport, Vec<CheckFinding>)> {
+    let plugins = read_plugin_records(plugins_dir, plugin_id)?;
+
+    let cache_root = resolve_cache_dir(repo_root, cache_dir_override)
+        .join("neko_plugin_cli")
+        .join("check_python");
+    fs::create_dir_all(&cache_root)
+        .with_context(|| format!("failed to create cache dir {}", cache_root.display()))?;
+
+    let requirements_in = cache_root.join("requirements.in");
+    let compiled_txt = cache_root.join("compiled.txt");
+    let stderr_txt = cache_root.join("compile.stderr.txt");
+
+    let _ = fs::remove_file(&compiled_txt);
+    let _ = fs::remove_file(&stderr_txt);
+
+    let mut reqs: Vec<String> = Vec::new();
+    reqs.extend(read_pyproject_dependencies(&repo_root.join("pyproject.toml"))?);
+
+    for p in &plugins {
+        let pp = repo_root
+            .join("plugin")
+            .join("plugins")
+            .join(&p.folder)
+            .join("pyproject.toml");
+        if pp.is_file() {
+            reqs.extend(read_pyproject_dependencies(&pp)?);
+        }
+    }
+
+    reqs.retain(|r| {
+        let s = r.trim();
+        if s.is_empty() {
+            return false;
+        }
+        let lower = s.to_ascii_lowercase();
+        !(lower == "n.e.k.o" || lower.starts_with("n.e.k.o ") || lower.starts_with("n.e.k.o;")
+            || lower == "n-e-k-o" || lower.starts_with("n-e-k-o ") || lower.starts_with("n-e-k-o;"))
+    });
+
+    reqs.sort();
+    reqs.dedup();
+
+    let mut f = fs::File::create(&requirements_in)
+        .with_context(|| format!("failed to write {}", requirements_in.display()))?;
+    writeln!(f, "-e {}", repo_root.display())?;
+    for r in &reqs {
+        if r.trim().is_empty() {
+            continue;
+        }
+        writeln!(f, "{}", r)?;
+    }
+
+    let mut findings: Vec<CheckFinding> = Vec::new();
+
+    let output = Command::new("uv")
+        .arg("pip")
+        .arg("compile")
+        .arg(requirements_in.as_os_str())
+        .arg("-o")
+        .arg(compiled_txt.as_os_str())
+        .output();
+
+    match output {
+        Err(e) => {
+            let msg = format!("python-online check skipped: failed to execute uv ({})", e);
+            let severity = if strict { Severity::Error } else { Severity::Warning };
+            findings.push(CheckFinding::new("python-uv-missing", severity, None, msg));
+            Ok((
+                PythonOnlineReport {
+                    enabled: true,
+                    uv_found: false,
+                    requirements_in: requirements_in.display().to_string(),
+                    compiled_txt: compiled_txt.display().to_string(),
+                    exit_code: None,
+                    lock: None,
+                },
+                findings,
+            ))
+        }
+        Ok(out) => {
+            let code = out.status.code();
+            if !out.stderr.is_empty() {
+                let _ = fs::write(&stderr_txt, &out.stderr);
+            }
+            if !out.status.success() {
+                let stderr = String::from_utf8_lossy(&out.stderr);
+                findings.push(CheckFinding::error(
+                    "python-resolution-failed",
+                    None,
+                    format!(
+                        "python-online dependency resolution failed (see {}): {}",
+                        stderr_txt.display(),
+                        stderr.lines().take(20).collect::<Vec<_>>().join("\n")
+                    ),
+                ));
+            }
+            Ok((
+                PythonOnlineReport {
+                    enabled: true,
+                    uv_found: true,
+                    requirements_in: requirements_in.display().to_string(),
+                    compiled_txt: compiled_txt.display().to_string(),
+                    exit_code: code,
+                    lock: None,
+                },
+                findings,
+            ))
+        }
+    }
+}
+
+const PYTHON_LOCK_HEADER: &str = "# Generated by neko_plugin_cli check --python --lock; do not edit";
+const PYTHON_LOCK_INPUTS: &str = "# inputs-sha256: ";
+
+/// The requirements.in written next to `lock`: `requirements.txt` gets `requirements.in`
+fn python_lock_inputs_path(lock: &Path) -> Result<PathBuf> {
+    if lock.extension().is_some_and(|e| e == "in") {
+        anyhow::bail!("lockfile {} must not end in .in (that name is used for its inputs)", lock.display());
+    }
+    Ok(lock.with_extension("in"))
+}
+
+/// Requirement lines of a lock or compile output: comments and blank lines do not count
+fn python_lock_requirements(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// `check --python --lock`: write the uv-compiled output of run_python_online_check to `lock`, with
+/// a header recording the sha256 of its inputs, and those inputs to the requirements.in next to
+/// it. With `verify`, write nothing and instead report a `python-lock-mismatch` error when the
+/// fresh compile differs from `lock` in anything but comments. The cache paths and the repo root
+/// are rewritten to the emitted requirements.in and `.` so the files are the same on every machine.
+pub fn apply_python_lock(
+    repo_root: &Path,
+    (report, findings): &mut (PythonOnlineReport, Vec<CheckFinding>),
+    lock: &Path,
+    verify: bool,
+) -> Result<()> {
+    let inputs_path = python_lock_inputs_path(lock)?;
+    let inputs_name = inputs_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "requirements.in".to_string());
+    let normalize = |text: &str| {
+        text.replace(&report.requirements_in, &inputs_name)
+            .replace(&repo_root.display().to_string(), ".")
+    };
+
+    if report.exit_code != Some(0) {
+        // The resolution failure (or missing uv) is already a finding; say what it means for the lock
+        let action = if verify { "verified" } else { "written" };
+        findings.push(CheckFinding::error(
+            "python-lock-unverified",
+            None,
+            format!("python lockfile {} not {}: dependency resolution did not succeed", lock.display(), action),
+        ));
+        return Ok(());
+    }
+
+    let requirements_in = fs::read_to_string(&report.requirements_in)
+        .with_context(|| format!("failed to read {}", report.requirements_in))?;
+    let requirements_in = normalize(&requirements_in);
+    let inputs_sha256 = format!("{:x}", Sha256::digest(requirements_in.as_bytes()));
+    let compiled = fs::read_to_string(&report.compiled_txt)
+        .with_context(|| format!("failed to read {}", report.compiled_txt))?;
+    let compiled = normalize(&compiled);
+
+    let mut lock_report = PythonLockReport {
+        path: lock.display().to_string(),
+        requirements_in: inputs_path.display().to_string(),
+        inputs_sha256,
+        written: !verify,
+        removed: Vec::new(),
+        added: Vec::new(),
+    };
+
+    if verify {
+        let committed = match fs::read_to_string(lock) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                findings.push(CheckFinding::error(
+                    "python-lock-missing",
+                    None,
+                    format!("python lockfile {} does not exist (write it with --lock)", lock.display()),
+                ));
+                report.lock = Some(lock_report);
+                return Ok(());
+            }
+            Err(e) => return Err(e).with_context(|| PathContext::new("failed to read", lock)),
+        };
+        let old = python_lock_requirements(&committed);
+        let new = python_lock_requirements(&compiled);
+        lock_report.removed = old.iter().filter(|l| !new.contains(l)).cloned().collect();
+        lock_report.added = new.iter().filter(|l| !old.contains(l)).cloned().collect();
+        if old != new {
+            let mut changes: Vec<String> = lock_report.removed.iter().map(|l| format!("-{}", l)).collect();
+            changes.extend(lock_report.added.iter().map(|l| format!("+{}", l)));
+            if changes.is_empty() {
+                changes.push("(order changed)".to_string());
+            }
+            findings.push(CheckFinding::error(
+                "python-lock-mismatch",
+                None,
+                format!(
+                    "python dependencies changed since {} was written: {}",
+                    lock.display(),
+                    changes.join(", ")
+                ),
+            ));
+        }
+    } else {
+        // uv's own leading comments name the throwaway command line; the header replaces them
+        let body: Vec<&str> = compiled.lines().skip_while(|l| l.starts_with('#')).collect();
+        let mut text = format!("{}\n{}{}\n", PYTHON_LOCK_HEADER, PYTHON_LOCK_INPUTS, lock_report.inputs_sha256);
+        for line in body {
+            text.push_str(line);
+            text.push('\n');
+        }
+        if let Some(dir) = lock.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| PathContext::new("failed to create", dir))?;
+        }
+        fs::write(lock, text).with_context(|| PathContext::new("failed to write", lock))?;
+        fs::write(&inputs_path, &requirements_in).with_context(|| PathContext::new("failed to write", &inputs_path))?;
+    }
+    report.lock = Some(lock_report);
+    Ok(())
+}
+
+/// Files handed to one `python -m py_compile` run
+const PY_SYNTAX_BATCH: usize = 200;
+
+/// The first of `python3`, `python` and `uv run python` that starts, as program plus leading args
+fn find_python_interpreter() -> Option<Vec<String>> {
+    let candidates: [&[&str]; 3] = [&["python3"], &["python"], &["uv", "run", "--no-project", "python"]];
+    candidates.into_iter().find_map(|cmd| {
+        let ok = Command::new(cmd[0])
+            .args(&cmd[1..])
+            .arg("--version")
+            .output()
+            .is_ok_and(|out| out.status.success());
+        ok.then(|| cmd.iter().map(|s| s.to_string()).collect())
+    })
+}
+
+/// Compile every `.py` file of each plugin (minus the standard excludes) with `python -m py_compile`
+/// and report each syntax error with its file and line. Nothing is written into the plugin folders.
+/// With a `cache`, unchanged plugins reuse their earlier findings without starting Python.
+pub fn run_py_syntax_check(
+    plugins_dir: &Path,
+    plugin_id: Option<&str>,
+    strict: bool,
+    cache: Option<&CheckCache>,
+) -> Result<Vec<CheckFinding>> {
+    let plugins = read_plugin_records(plugins_dir, plugin_id)?;
+    let mut cached: Vec<CheckFinding> = Vec::new();
+    let mut pending: Vec<(&PluginRecord, Option<String>)> = Vec::new();
+    for p in &plugins {
+        let Some(cache) = cache else {
+            pending.push((p, None));
+            continue;
+        };
+        let plugin_dir = plugins_dir.join(&p.folder);
+        let key = cache.py_syntax_key(&plugin_dir)?;
+        match cache.get("py_syntax", &plugin_dir, &key, &p.id)? {
+            Some((found, _)) => cached.extend(found),
+            None => pending.push((p, Some(key))),
+        }
+    }
+    if pending.is_empty() {
+        return Ok(cached);
+    }
+    let Some(python) = find_python_interpreter() else {
+        let severity = if strict { Severity::Error } else { Severity::Warning };
+        cached.push(CheckFinding::new(
+            "py-syntax-no-python",
+            severity,
+            None,
+            "py-syntax check skipped: no python3, python or uv found".to_string(),
+        ));
+        return Ok(cached);
+    };
+    let excludes = build_excludes(&[])?;
+    let location = Regex::new(r#"^\s*File "(.+)", line (\d+)"#)?;
+    // Bytecode goes to a scratch dir so no __pycache__ appears next to the sources
+    let pycache = std::env::temp_dir().join(format!("neko_plugin_cli_pycache_{}", std::process::id()));
+    let findings = pending
+        .par_iter()
+        .map(|(p, key)| -> Result<Vec<CheckFinding>> {
+            let files: Vec<PathBuf> = list_plugin_files(&plugins_dir.join(&p.folder), &excludes)?
+                .into_iter()
+                .filter(|(rel, _)| rel.ends_with(".py"))
+                .map(|(_, path)| path)
+                .collect();
+            let mut found = Vec::new();
+            let mut rest = files.as_slice();
+            while !rest.is_empty() {
+                let batch = &rest[..rest.len().min(PY_SYNTAX_BATCH)];
+                let out = Command::new(&python[0])
+                    .args(&python[1..])
+                    .args(["-m", "py_compile"])
+                    .args(batch)
+                    .env("PYTHONPYCACHEPREFIX", &pycache)
+                    .output()
+                    .with_context(|| format!("failed to run {}", python.join(" ")))?;
+                if out.status.success() {
+                    rest = &rest[batch.len()..];
+                    continue;
+                }
+                // py_compile stops at the first file that fails; carry on after it
+                let stderr = String::from_utf8_lossy(&out.stderr);
+                let message = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+                let failed = stderr.lines().find_map(|l| {
+                    let c = location.captures(l)?;
+                    let i = batch.iter().position(|f| f.as_path() == Path::new(&c[1]))?;
+                    Some((i, c[2].to_string()))
+                });
+                let Some((i, line)) = failed else {
+                    found.push(CheckFinding::error(
+                        "py-syntax-error",
+                        Some(&p.id),
+                        format!("py_compile failed in {}: {}", p.folder, message),
+                    ));
+                    break;
+                };
+                let rel = batch[i].strip_prefix(plugins_dir).unwrap_or(&batch[i]).to_string_lossy().replace('\\', "/");
+                found.push(CheckFinding::error(
+                    "py-syntax-error",
+                    Some(&p.id),
+                    format!("{}:{}: {}", rel, line, message),
+                ));
+                rest = &rest[i + 1..];
+            }
+            if let (Some(cache), Some(key)) = (cache, key) {
+                cache.put("py_syntax", &plugins_dir.join(&p.folder), key.clone(), &found, false)?;
+            }
+            Ok(found)
+        })
+        .collect::<Result<Vec<_>>>();
+    let _ = fs::remove_dir_all(&pycache);
+    cached.extend(findings?.into_iter().flatten());
+    Ok(cached)
+}
+
+fn read_pyproject_dependencies(pyproject_path: &Path) -> Result<Vec<String>> {
+    let txt = fs::read_to_string(pyproject_path)
+        .with_context(|| format!("failed to read {}", pyproject_path.display()))?;
+    let val: toml::Value = toml::from_str(&txt)
+        .with_context(|| format!("failed to parse {}", pyproject_path.display()))?;
+    let deps = val
+        .get("project")
+        .and_then(|v| v.get("dependencies"))
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|x| x.as_str().map(|s| s.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    Ok(deps)
+}
+
+/// Every plugin folder with a plugin.toml (or just the one declaring `plugin_id`), sorted by id.
+/// The plugin.toml files are parsed in parallel; when several fail, the first folder by name
+/// reports its error, so the outcome does not depend on scheduling.
+fn read_plugin_records(plugins_dir: &Path, plugin_id: Option<&str>) -> Result<Vec<PluginRecord>> {
+    if !plugins_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut folders: Vec<(String, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(plugins_dir)
+        .with_context(|| format!("failed to read dir {}", plugins_dir.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let folder = match path.file_name().and_then(|v| v.to_str()) {
+            Some(s) => s.to_string(),
+            None => continue,
+        };
+
+        let plugin_toml = path.join("plugin.toml");
+        if !plugin_toml.is_file() {
+            continue;
+        }
+        folders.push((folder, plugin_toml));
+    }
+    folders.sort();
+
+    let parsed: Vec<Result<Option<PluginRecord>>> = folders
+        .into_par_iter()
+        .map(|(folder, plugin_toml)| read_plugin_record(folder, &plugin_toml, plugin_id))
+        .collect();
+    let mut out = Vec::new();
+    for record in parsed {
+        out.extend(record?);
+    }
+    out.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(out)
+}
+
+/// Parse one plugin.toml; None when `plugin_id` is given and the plugin declares another id
+fn read_plugin_record(folder: String, plugin_toml: &Path, plugin_id: Option<&str>) -> Result<Option<PluginRecord>> {
+    let txt = fs::read_to_string(plugin_toml)
+        .with_context(|| format!("failed to read {}", plugin_toml.display()))?;
+    parse_plugin_record(folder, &txt, plugin_id).with_context(|| format!("failed to parse {}", plugin_toml.display()))
+}
+
+/// read_plugin_record for plugin.toml text from anywhere, e.g. a bundle
+fn parse_plugin_record(folder: String, txt: &str, plugin_id: Option<&str>) -> Result<Option<PluginRecord>> {
+    let val: toml::Value = toml::from_str(txt)?;
+    let plugin = val.get("plugin");
+
+    let id = plugin
+        .and_then(|v| v.get("id"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+    if let Some(want) = plugin_id
+        && id != want
+    {
+        return Ok(None);
+    }
+
+    let version = plugin
+        .and_then(|v| v.get("version"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let entry_str = plugin
+        .and_then(|v| v.get("entry"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let sdk_tbl = val.get("plugin").and_then(|p| p.get("sdk"));
+    let sdk = PluginSdkDecl {
+        recommended: sdk_tbl
+            .and_then(|v| v.get("recommended"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        supported: sdk_tbl
+            .and_then(|v| v.get("supported"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        untested: sdk_tbl
+            .and_then(|v| v.get("untested"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        conflicts: sdk_tbl
+            .and_then(|v| v.get("conflicts"))
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|x| x.as_str().map(|s| s.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default(),
+    };
+
+    let deps = val
+        .get("plugin")
+        .and_then(|p| p.get("dependency"))
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|d| {
+                    let id = d.get("id")?.as_str()?.to_string();
+                    let conflicts = d
+                        .get("conflicts")
+                        .and_then(|v| v.as_array())
+                        .map(|c| {
+                            c.iter()
+                                .filter_map(|x| x.as_str().map(|s| s.to_string()))
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    Some(PluginDependencyDecl {
+                        id,
+                        recommended: d.get("recommended").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                        supported: d.get("supported").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                        untested: d.get("untested").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                        conflicts,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    Ok(Some(PluginRecord {
+        folder,
+        id,
+        version,
+        entry: entry_str,
+        sdk,
+        deps,
+    }))
+}
+
+/// Top-level plugin.toml keys validate_plugin_toml knows; others only produce a warning
+const PLUGIN_TOML_KEYS: &[&str] = &["plugin"];
+const PLUGIN_ID_MAX_LEN: usize = 64;
+/// Keys allowed in [plugin.sdk]; also the range keys of a [[plugin.dependency]]
+const PLUGIN_RANGE_KEYS: &[&str] = &["recommended", "supported", "untested", "conflicts"];
+
+/// One problem with a plugin.toml field
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub field: String,
+    pub message: String,
+    /// Finding code when `check` reports the issue
+    #[serde(skip)]
+    pub code: &'static str,
+}
+
+/// Result of validate_plugin_toml for one plugin folder
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PluginValidation {
+    pub folder: String,
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl PluginValidation {
+    fn issue(&mut self, error: bool, field: &str, message: String) {
+        let code = if error { "toml-invalid" } else { "toml-unknown-key" };
+        self.issue_code(error, field, code, message);
+    }
+
+    fn issue_code(&mut self, error: bool, field: &str, code: &'static str, message: String) {
+        let issue = ValidationIssue {
+            field: field.to_string(),
+            message,
+            code,
+        };
+        if error {
+            self.errors.push(issue);
+        } else {
+            self.warnings.push(issue);
+        }
+    }
+
+    fn describe<'a>(&'a self, issues: &'a [ValidationIssue]) -> impl Iterator<Item = String> + 'a {
+        issues.iter().map(|i| self.describe_one(i))
+    }
+
+    fn describe_one(&self, i: &ValidationIssue) -> String {
+        format!("{}/plugin.toml: {}: {}", self.folder, i.field, i.message)
+    }
+
+    /// "<folder>/plugin.toml: <field>: <problem>" for every error
+    pub fn error_messages(&self) -> impl Iterator<Item = String> + '_ {
+        self.describe(&self.errors)
+    }
+
+    pub fn warning_messages(&self) -> impl Iterator<Item = String> + '_ {
+        self.describe(&self.warnings)
+    }
+
+    /// An sdk or dependency range is unusable, so the base and deps checks cannot evaluate it
+    fn has_range_errors(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|i| i.field.starts_with("sdk") || i.field.starts_with("dependency"))
+    }
+
+    /// `value` under `path` must be a version requirement, or an array of them for `conflicts`
+    fn version_reqs(&mut self, path: &str, key: &str, value: &toml::Value) {
+        if key != "conflicts" {
+            self.version_req(path, value);
+            return;
+        }
+        let Some(items) = value.as_array() else {
+            self.issue(
+                true,
+                path,
+                format!("must be an array of version requirements, not {}", value.type_str()),
+            );
+            return;
+        };
+        for (i, item) in items.iter().enumerate() {
+            self.version_req(&format!("{}[{}]", path, i), item);
+        }
+    }
+
+    fn version_req(&mut self, path: &str, value: &toml::Value) {
+        match value.as_str() {
+            None => self.issue(
+                true,
+                path,
+                format!("must be a version requirement string, not {}", value.type_str()),
+            ),
+            Some(req) => {
+                if let Err(e) = VersionReq::parse(req) {
+                    self.issue(true, path, format!("`{}` is not a valid version requirement ({})", req, e));
+                }
+            }
+        }
+    }
+}
+
+fn plugin_id_problem(id: &str) -> Option<String> {
+    if id.is_empty() {
+        return Some("must not be empty".to_string());
+    }
+    if id.len() > PLUGIN_ID_MAX_LEN {
+        return Some(format!("`{}` is longer than {} characters", id, PLUGIN_ID_MAX_LEN));
+    }
+    if !id.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return Some(format!("`{}` must start with an ASCII letter or digit", id));
+    }
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+        return Some(format!("`{}` may only contain ASCII letters, digits, `_`, `-` and `.`", id));
+    }
+    None
+}
+
+/// Files an entry like `pkg.module:main` may point at: the module inside the plugin folder, or
+/// (for entries named after the folder) the folder's package in the plugins dir
+fn entry_candidates(plugin_dir: &Path, entry: &str) -> Vec<PathBuf> {
+    let module = entry.split(':').next().unwrap_or_default().trim();
+    if module.ends_with(".py") {
+        return vec![plugin_dir.join(module)];
+    }
+    let rel = module.replace('.', "/");
+    let mut roots = vec![plugin_dir];
+    roots.extend(plugin_dir.parent());
+    roots
+        .into_iter()
+        .flat_map(|root| [root.join(format!("{}.py", rel)), root.join(&rel).join("__init__.py")])
+        .collect()
+}
+
+/// Check the fields packing relies on: `id` (charset and length), `version` (semver) and
+/// `entry` (non-empty, pointing at an existing file), plus the [plugin.sdk] keys and every
+/// [[plugin.dependency]] (an `id`, and ranges that parse as version requirements). Issues name
+/// the key path below [plugin], e.g. `dependency[1].supported`. Unknown top-level keys are
+/// warnings. Shared by `pack` and `check`.
+pub fn validate_plugin_toml(plugin_dir: &Path) -> Result<PluginValidation> {
+    let plugin_toml = plugin_dir.join("plugin.toml");
+    let txt = fs::read_to_string(&plugin_toml)
+        .with_context(|| format!("failed to read {}", plugin_toml.display()))?;
+    let val: toml::Table = toml::from_str(&txt)
+        .with_context(|| format!("failed to parse {}", plugin_toml.display()))?;
+    let mut v = PluginValidation {
+        folder: plugin_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    for key in val.keys().filter(|k| !PLUGIN_TOML_KEYS.contains(&k.as_str())) {
+        v.issue(false, key, "unknown top-level key (ignored)".to_string());
+    }
+    let Some(plugin) = val.get("plugin").and_then(|p| p.as_table()) else {
+        v.issue(true, "plugin", "missing [plugin] table".to_string());
+        return Ok(v);
+    };
+    let field = |key: &str| -> std::result::Result<&str, String> {
+        match plugin.get(key) {
+            None => Err("missing".to_string()),
+            Some(value) => value.as_str().ok_or_else(|| format!("must be a string, not {}", value.type_str())),
+        }
+    };
+
+    match field("id") {
+        Ok(id) => {
+            if let Some(problem) = plugin_id_problem(id) {
+                v.issue(true, "id", problem);
+            }
+        }
+        Err(problem) => v.issue(true, "id", problem),
+    }
+    match field("version") {
+        Ok(version) => {
+            if let Err(e) = Version::parse(version) {
+                let message = format!("`{}` is not a valid semver version ({})", version, e);
+                match padded_version(version) {
+                    Some(padded) => v.issue_code(
+                        true,
+                        "version",
+                        "version-missing-patch",
+                        format!("{}; did you mean `{}`?", message, padded),
+                    ),
+                    None => v.issue(true, "version", message),
+                }
+            }
+        }
+        Err(problem) => v.issue(true, "version", problem),
+    }
+    match field("entry") {
+        Ok(entry) if entry.trim().is_empty() => v.issue(true, "entry", "must not be empty".to_string()),
+        Ok(entry) => {
+            let candidates = entry_candidates(plugin_dir, entry);
+            if !candidates.iter().any(|c| c.is_file()) {
+                let looked: Vec<String> = candidates
+                    .iter()
+                    .map(|c| c.strip_prefix(plugin_dir.parent().unwrap_or(plugin_dir)).unwrap_or(c).display().to_string())
+                    .collect();
+                v.issue(
+                    true,
+                    "entry",
+                    format!("`{}` does not point at an existing file (looked for {})", entry, looked.join(", ")),
+                );
+            }
+        }
+        Err(problem) => v.issue(true, "entry", problem),
+    }
+
+    match plugin.get("sdk") {
+        None => {}
+        Some(sdk) => match sdk.as_table() {
+            None => v.issue(true, "sdk", format!("must be a table, not {}", sdk.type_str())),
+            Some(sdk) => {
+                for (key, value) in sdk {
+                    let path = format!("sdk.{}", key);
+                    if PLUGIN_RANGE_KEYS.contains(&key.as_str()) {
+                        v.version_reqs(&path, key, value);
+                    } else {
+                        v.issue(true, &path, format!("unknown key (expected {})", PLUGIN_RANGE_KEYS.join(", ")));
+                    }
+                }
+            }
+        },
+    }
+
+    match plugin.get("dependency") {
+        None => {}
+        Some(deps) => match deps.as_array() {
+            None => v.issue(
+                true,
+                "dependency",
+                format!("must be an array of tables ([[plugin.dependency]]), not {}", deps.type_str()),
+            ),
+            Some(deps) => {
+                for (i, dep) in deps.iter().enumerate() {
+                    let path = format!("dependency[{}]", i);
+                    let Some(dep) = dep.as_table() else {
+                        v.issue(true, &path, format!("must be a table, not {}", dep.type_str()));
+                        continue;
+                    };
+                    match dep.get("id") {
+                        None => v.issue(true, &format!("{}.id", path), "missing".to_string()),
+                        Some(id) => match id.as_str() {
+                            None => v.issue(true, &format!("{}.id", path), format!("must be a string, not {}", id.type_str())),
+                            Some(id) => {
+                                if let Some(problem) = plugin_id_problem(id) {
+                                    v.issue(true, &format!("{}.id", path), problem);
+                                }
+                            }
+                        },
+                    }
+                    for key in PLUGIN_RANGE_KEYS {
+                        if let Some(value) = dep.get(*key) {
+                            v.version_reqs(&format!("{}.{}", path, key), key, value);
+                        }
+                    }
+                }
+            }
+        },
+    }
+    Ok(v)
+}
+
+/// `entry` must be `module[:attr]` or a relative `path.py[:attr]` resolving to a file inside the
+/// plugin folder. An attribute that a simple scan of that file does not find is only a warning,
+/// since it may be created at import time.
+fn check_entries(plugins_dir: &Path, plugins: &[PluginRecord], findings: &mut Vec<CheckFinding>) -> Result<()> {
+    let module_re = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*(\.[A-Za-z_][A-Za-z0-9_]*)*$")?;
+    let attr_re = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$")?;
+    for p in plugins {
+        let id = Some(p.id.as_str());
+        let entry = p.entry.trim();
+        if entry.is_empty() {
+            findings.push(CheckFinding::error("entry-missing", id, format!("plugin {} has no entry", p.id)));
+            continue;
+        }
+        let (module, attr) = match entry.split_once(':') {
+            Some((module, attr)) => (module.trim(), Some(attr.trim())),
+            None => (entry, None),
+        };
+        let module_ok = if module.ends_with(".py") {
+            is_safe_rel_path(module)
+        } else {
+            module_re.is_match(module)
+        };
+        if !module_ok || attr.is_some_and(|a| !attr_re.is_match(a)) {
+            findings.push(CheckFinding::error(
+                "entry-malformed",
+                id,
+                format!(
+                    "plugin {} entry `{}` is not `module[:attr]` or a relative `path.py[:attr]`",
+                    p.id, entry
+                ),
+            ));
+            continue;
+        }
+
+        let plugin_dir = plugins_dir.join(&p.folder);
+        let candidates: Vec<PathBuf> = entry_candidates(&plugin_dir, entry)
+            .into_iter()
+            .filter(|c| c.starts_with(&plugin_dir))
+            .collect();
+        let shown = |c: &Path| c.strip_prefix(plugins_dir).unwrap_or(c).display().to_string();
+        let Some(file) = candidates.iter().find(|c| c.is_file()) else {
+            let looked: Vec<String> = candidates.iter().map(|c| shown(c)).collect();
+            findings.push(CheckFinding::error(
+                "entry-file-missing",
+                id,
+                format!(
+                    "plugin {} entry `{}` points at a missing file (looked for {})",
+                    p.id,
+                    entry,
+                    looked.join(", ")
+                ),
+            ));
+            continue;
+        };
+        let Some(attr) = attr else {
+            continue;
+        };
+        let bytes = fs::read(file).with_context(|| PathContext::new("failed to read", file))?;
+        let defines = Regex::new(&format!(
+            r"(?m)^\s*(?:(?:async\s+)?def|class)\s+{0}\b|^\s*{0}\s*(?::[^=\n]*)?=|^\s*(?:from\s+\S+\s+)?import\b.*\b{0}\b",
+            regex::escape(attr)
+        ))?;
+        if !defines.is_match(&String::from_utf8_lossy(&bytes)) {
+            findings.push(CheckFinding::warning(
+                "entry-attr-missing",
+                id,
+                format!(
+                    "plugin {} entry `{}`: no def, class, assignment or import of `{}` found in {}",
+                    p.id,
+                    entry,
+                    attr,
+                    shown(file)
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validate every plugin about to be packed. Errors abort with one line per problem unless
+/// `allow_invalid`, which turns them into warnings; the warnings are returned for the caller to print.
+pub fn validate_pack_plugins(plugins: &[PluginPackItem], allow_invalid: bool) -> Result<Vec<String>> {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for p in plugins {
+        let v = validate_plugin_toml(&p.path)?;
+        errors.extend(v.error_messages());
+        warnings.extend(v.warning_messages());
+    }
+    if errors.is_empty() {
+        return Ok(warnings);
+    }
+    if allow_invalid {
+        warnings.extend(errors.into_iter().map(|e| format!("{} (packed anyway: --allow-invalid)", e)));
+        return Ok(warnings);
+    }
+    anyhow::bail!(
+        "invalid plugin.toml (use --allow-invalid to pack anyway):\n  {}",
+        errors.join("\n  ")
+    )
+}
+
+fn check_id_conflicts(plugins: &[PluginRecord], findings: &mut Vec<CheckFinding>) {
+    use std::collections::HashMap;
+    let mut map: HashMap<&str, Vec<&str>> = HashMap::new();
+    for p in plugins {
+        map.entry(&p.id).or_default().push(&p.folder);
+    }
+    for (id, folders) in map {
+        if folders.len() > 1 {
+            findings.push(CheckFinding::error(
+                "id-conflict",
+                Some(id),
+                format!("plugin id conflict: id={} folders={}", id, folders.join(",")),
+            ));
+        }
+    }
+}
+
+/// Parse profiles.toml and every `.toml` under profiles/ of `p`: syntax errors (with file and
+/// line), files defining no profile, a profile name defined by two files, and files over `warn_size`
+fn check_profiles(plugins_dir: &Path, p: &PluginRecord, warn_size: u64, findings: &mut Vec<CheckFinding>) -> Result<()> {
+    use std::collections::BTreeMap;
+    let plugin_dir = plugins_dir.join(&p.folder);
+    let id = Some(p.id.as_str());
+    // Profile name -> the file defining it first
+    let mut defined: BTreeMap<String, String> = BTreeMap::new();
+    for path in collect_profile_files(&plugin_dir) {
+        if path.extension().is_none_or(|e| e != "toml") {
+            continue;
+        }
+        let rel = path
+            .strip_prefix(plugins_dir)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let bytes = fs::read(&path).with_context(|| PathContext::new("failed to read", &path))?;
+        if warn_size > 0 && bytes.len() as u64 > warn_size {
+            findings.push(CheckFinding::warning(
+                "profile-large",
+                id,
+                format!(
+                    "{} is {} (over {}); profiles are copied into every bundle",
+                    rel,
+                    human_bytes(bytes.len() as u64),
+                    human_bytes(warn_size)
+                ),
+            ));
+        }
+        let text = String::from_utf8_lossy(&bytes);
+        let table: toml::Table = match toml::from_str(&text) {
+            Ok(table) => table,
+            Err(e) => {
+                let line = e.span().map_or(1, |span| text[..span.start].matches('\n').count() + 1);
+                findings.push(CheckFinding::error(
+                    "profile-invalid",
+                    id,
+                    format!("{}:{}: {}", rel, line, e.message().trim()),
+                ));
+                continue;
+            }
+        };
+        let names: Vec<&String> = table.iter().filter(|(_, v)| v.is_table()).map(|(k, _)| k).collect();
+        if names.is_empty() {
+            findings.push(CheckFinding::warning(
+                "profile-empty",
+                id,
+                format!("{} defines no profile (no [table])", rel),
+            ));
+        }
+        for name in names {
+            match defined.get(name) {
+                Some(first) => findings.push(CheckFinding::error(
+                    "profile-duplicate",
+                    id,
+                    format!("profile `{}` is defined in both {} and {}", name, first, rel),
+                )),
+                None => {
+                    defined.insert(name.clone(), rel.clone());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Module entries resolve against the shared plugins dir too, so two plugins importing the same
+/// module shadow each other; `path.py` entries live in their own folders and cannot collide
+fn check_entry_collisions(plugins: &[PluginRecord], findings: &mut Vec<CheckFinding>) {
+    use std::collections::BTreeMap;
+    let mut by_module: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for p in plugins {
+        let module = p.entry.split(':').next().unwrap_or_default().trim();
+        if module.is_empty() || module.ends_with(".py") {
+            continue;
+        }
+        let ids = by_module.entry(module).or_default();
+        if !ids.contains(&p.id.as_str()) {
+            ids.push(&p.id);
+        }
+    }
+    for (module, mut ids) in by_module {
+        if ids.len() > 1 {
+            ids.sort();
+            findings.push(CheckFinding::error(
+                "entry-duplicate",
+                Some(ids[0]),
+                format!("entry module `{}` is used by several plugins: {}", module, ids.join(",")),
+            ));
+        }
+    }
+}
+
+/// Folders differing only by case are one folder on case-insensitive filesystems (Windows, macOS)
+fn check_folder_collisions(plugins: &[PluginRecord], findings: &mut Vec<CheckFinding>) {
+    use std::collections::BTreeMap;
+    let mut by_lower: BTreeMap<String, Vec<&PluginRecord>> = BTreeMap::new();
+    for p in plugins {
+        by_lower.entry(p.folder.to_lowercase()).or_default().push(p);
+    }
+    for mut group in by_lower.into_values() {
+        if group.len() > 1 {
+            group.sort_by(|a, b| a.folder.cmp(&b.folder));
+            let folders: Vec<&str> = group.iter().map(|p| p.folder.as_str()).collect();
+            findings.push(CheckFinding::error(
+                "folder-case-collision",
+                Some(&group[0].id),
+                format!(
+                    "plugin folders {} differ only by case and collide on case-insensitive filesystems",
+                    folders.join(",")
+                ),
+            ));
+        }
+    }
+}
+
+fn check_id_matches_folder(plugins: &[PluginRecord], findings: &mut Vec<CheckFinding>) {
+    for p in plugins.iter().filter(|p| p.id != p.folder) {
+        findings.push(CheckFinding::error(
+            "id-folder-mismatch",
+            Some(&p.id),
+            format!("plugin id {} differs from its folder name {} (strict_naming)", p.id, p.folder),
+        ));
+    }
+}
+
+/// How a plugin's `[plugin.sdk]` ranges relate to one SDK version
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SdkCompat {
+    /// Inside `supported` (or no `supported` at all)
+    Ok,
+    /// Outside `supported` but inside `untested`
+    Untested,
+    Unsupported,
+    /// Matched by one of the `conflicts` ranges, whatever the others say
+    Conflict,
+}
+
+impl SdkCompat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SdkCompat::Ok => "ok",
+            SdkCompat::Untested => "untested",
+            SdkCompat::Unsupported => "unsupported",
+            SdkCompat::Conflict => "conflict",
+        }
+    }
+}
+
+/// The classification behind the sdk-* findings; fails on a range that does not parse
+fn classify_sdk_compat(sdk: &PluginSdkDecl, sdk_version: &Version) -> Result<SdkCompat> {
+    if any_req_matches(&sdk.conflicts, sdk_version) {
+        return Ok(SdkCompat::Conflict);
+    }
+    let matches = |range: Option<&str>, default: bool| -> Result<bool> {
+        Ok(range
+            .map(|r| parse_req(r).map(|req| req.matches(sdk_version)))
+            .transpose()?
+            .unwrap_or(default))
+    };
+    if matches(sdk.supported.as_deref(), true)? {
+        Ok(SdkCompat::Ok)
+    } else if matches(sdk.untested.as_deref(), false)? {
+        Ok(SdkCompat::Untested)
+    } else {
+        Ok(SdkCompat::Unsupported)
+    }
+}
+
+fn check_sdk_compat(plugins: &[PluginRecord], sdk_version: &Version, findings: &mut Vec<CheckFinding>) -> Result<()> {
+    for p in plugins {
+        let id = Some(p.id.as_str());
+        let has_sdk_table = p.sdk.recommended.is_some() || p.sdk.untested.is_some() || !p.sdk.conflicts.is_empty();
+        if p.sdk.supported.is_none() && has_sdk_table {
+            findings.push(CheckFinding::warning(
+                "sdk-supported-missing",
+                id,
+                format!(
+                    "plugin {} has [plugin.sdk] without `supported`, so every SDK version counts as supported",
+                    p.id
+                ),
+            ));
+        }
+        match classify_sdk_compat(&p.sdk, sdk_version)? {
+            SdkCompat::Ok => {}
+            SdkCompat::Conflict => findings.push(CheckFinding::error(
+                "sdk-conflict",
+                id,
+                format!(
+                    "plugin {} conflicts with SDK_VERSION {} (conflicts={:?})",
+                    p.id, sdk_version, p.sdk.conflicts
+                ),
+            )),
+            SdkCompat::Untested => findings.push(CheckFinding::warning(
+                "sdk-untested",
+                id,
+                format!(
+                    "plugin {} SDK_VERSION {} is in untested range ({})",
+                    p.id,
+                    sdk_version,
+                    p.sdk.untested.clone().unwrap_or_default()
+                ),
+            )),
+            SdkCompat::Unsupported => findings.push(CheckFinding::error(
+                "sdk-unsupported",
+                id,
+                format!(
+                    "plugin {} SDK_VERSION {} not supported (supported={:?} untested={:?})",
+                    p.id, sdk_version, p.sdk.supported, p.sdk.untested
+                ),
+            )),
+        }
+    }
+    Ok(())
+}
+
+enum ExistingState {
+    /// Matches the bundle; carries what was compared ("md5", "sha256" or "per-file sha256")
+    Identical(&'static str),
+    Differs,
+    /// The manifest has nothing to compare against
+    Unknown,
+}
+
+fn existing_plugin_state(p: &ManifestPluginDe, target_folder: &Path, excludes: &Excludes) -> Result<ExistingState> {
+    if let Some((algo, expected)) = p.expected_hash() {
+        let local = folder_hash(target_folder, excludes, algo)
+            .with_context(|| PathContext::new("failed to hash", target_folder))?;
+        return Ok(if local == expected {
+            ExistingState::Identical(algo.as_str())
+        } else {
+            ExistingState::Differs
+        });
+    }
+    if let Some(files) = &p.files {
+        return Ok(if diff_plugin_files(target_folder, files, excludes)?.is_empty() {
+            ExistingState::Identical("per-file sha256")
+        } else {
+            ExistingState::Differs
+        });
+    }
+    Ok(ExistingState::Unknown)
+}
+
+/// Compare an installed plugin folder against the per-file checksums of a v2 manifest
+fn diff_plugin_files(plugin_dir: &Path, files: &[ManifestFileDe], excludes: &Excludes) -> Result<Vec<FileDiff>> {
+    use std::collections::HashMap;
+    let mut local: HashMap<String, PathBuf> = list_plugin_files(plugin_dir, excludes)?.into_iter().collect();
+
+    let mut diffs = Vec::new();
+    // Files the excludes leave out were never meant to be installed
+    for f in files.iter().filter(|f| !excludes.is_match(&f.path)) {
+        let Some(path) = local.remove(&f.path) else {
+            diffs.push(FileDiff {
+                path: f.path.clone(),
+                kind: FileDiffKind::Missing,
+            });
+            continue;
+        };
+        let size = fs::metadata(&path)
+            .with_context(|| PathContext::new("failed to stat", &path))?
+            .len();
+        if size != f.size || file_sha256(&path)? != f.sha256 {
+            diffs.push(FileDiff {
+                path: f.path.clone(),
+                kind: FileDiffKind::Modified,
+            });
+        }
+    }
+    diffs.extend(local.into_keys().map(|path| FileDiff {
+        path,
+        kind: FileDiffKind::Extra,
+    }));
+    diffs.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(diffs)
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut f = fs::File::open(path).with_context(|| PathContext::new("failed to open", path))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut f, &mut hasher).with_context(|| PathContext::new("failed to read", path))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// What `unpack --dry-run` reports: one item per plugin plus totals and the bundle metadata
+#[derive(Debug, Serialize, Clone)]
+pub struct UnpackPreview {
+    pub plugins: Vec<UnpackPreviewItem>,
+    pub summary: UnpackPreviewSummary,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UnpackPreviewSummary {
+    pub install: usize,
+    pub skip: usize,
+    /// The manifest's [bundle] table; None for bundles packed without one
+    pub bundle: Option<BundleMeta>,
+}
+
+pub fn preview_unpack(
+    zip_path: &Path,
+    dest_dir: &Path,
+    force: bool,
+    excludes: &Excludes,
+    signature: Option<&SignaturePolicy>,
+) -> Result<Vec<UnpackPreviewItem>> {
+    Ok(preview_unpack_report(zip_path, dest_dir, force, excludes, signature, None, false)?.plugins)
+}
+
+pub fn preview_unpack_report(
+    zip_path: &Path,
+    dest_dir: &Path,
+    force: bool,
+    excludes: &Excludes,
+    signature: Option<&SignaturePolicy>,
+    assume_version: Option<u32>,
+    no_manifest: bool,
+) -> Result<UnpackPreview> {
+    preview_archive(open_bundle(zip_path)?, zip_path, dest_dir, force, excludes, signature, assume_version, no_manifest)
+}
+
+/// preview_unpack_report for a zip that is not on disk (`unpack - --dry-run`); `source` names it in errors
+#[allow(clippy::too_many_arguments)]
+pub fn preview_reader<R: Read + std::io::Seek>(
+    reader: R,
+    source: &Path,
+    dest_dir: &Path,
+    force: bool,
+    excludes: &Excludes,
+    signature: Option<&SignaturePolicy>,
+    assume_version: Option<u32>,
+    no_manifest: bool,
+) -> Result<UnpackPreview> {
+    let archive = ZipArchive::new(reader).with_context(|| PathContext::new("failed to read zip", source))?;
+    preview_archive(archive, source, dest_dir, force, excludes, signature, assume_version, no_manifest)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn preview_archive<R: Read + std::io::Seek>(
+    mut archive: ZipArchive<R>,
+    zip_path: &Path,
+    dest_dir: &Path,
+    force: bool,
+    excludes: &Excludes,
+    signature: Option<&SignaturePolicy>,
+    assume_version: Option<u32>,
+    no_manifest: bool,
+) -> Result<UnpackPreview> {
+    if let Some(prefix) = plain_plugin_prefix(&archive, zip_path, no_manifest)? {
+        let (bundle, _) = plain_plugin_bundle(&mut archive, zip_path, &prefix, excludes, UNPACK_MAX_UNCOMPRESSED_BYTES)?;
+        let bundle = std::io::Cursor::new(bundle);
+        return preview_reader(bundle, zip_path, dest_dir, force, excludes, signature, assume_version, false);
+    }
+    if let Some(policy) = signature {
+        verify_bundle_signature(&mut archive, policy)
+            .with_context(|| PathContext::new("signature verification failed for", zip_path))?;
+    }
+    let manifest = read_manifest_as(&mut archive, assume_version)
+        .with_context(|| PathContext::new("invalid bundle", zip_path))?;
+
+    let mut items = Vec::new();
+
+    for p in &manifest.plugins {
+        let folder_rel = p.folder.trim_end_matches('/');
+        let folder_name = folder_rel
+            .split('/')
+            .nth(1)
+            .ok_or_else(|| anyhow::anyhow!("invalid manifest folder: {}", p.folder))?
+            .to_string();
+
+        let target_folder = dest_dir.join(&folder_name);
+
+        if !target_folder.is_dir() {
+            items.push(UnpackPreviewItem {
+                id: p.id.clone(),
+                folder: folder_name,
+                will_install: true,
+                reason: "destination folder does not exist; will install / 目标目录不存在，将安装".to_string(),
+                changed_files: Vec::new(),
+                conflict: false,
+            });
+            continue;
+        }
+
+        // Folder exists already
+        let changed_files = match &p.files {
+            Some(files) => diff_plugin_files(&target_folder, files, excludes)?,
+            None => Vec::new(),
+        };
+        let state = existing_plugin_state(p, &target_folder, excludes)?;
+        let conflict = !matches!(state, ExistingState::Identical(_));
+        let (will_install, mut reason) = match state {
+            ExistingState::Identical(method) => (
+                false,
+                format!("existing plugin is identical ({0} match); will skip / 已有插件 {0} 一致，将跳过", method),
+            ),
+            ExistingState::Differs if force => (
+                true,
+                "existing plugin differs; will overwrite (--force) / 已有插件不同，将使用 --force 覆盖".to_string(),
+            ),
+            ExistingState::Differs => (
+                false,
+                "existing plugin differs; use --force to overwrite / 已有插件不同，需使用 --force 覆盖".to_string(),
+            ),
+            ExistingState::Unknown if force => (
+                true,
+                "existing folder without md5; will overwrite (--force) / 目标目录已存在且无 md5，将使用 --force 覆盖"
+                    .to_string(),
+            ),
+            ExistingState::Unknown => (
+                false,
+                "existing folder without md5; use --force to overwrite / 目标目录已存在且无 md5，需使用 --force 覆盖"
+                    .to_string(),
+            ),
+        };
+        // A folder installed with --exclude never matches the bundle's hash again
+        if let Some(globs) = read_excludes_marker(&target_folder).filter(|_| conflict) {
+            reason.push_str(&format!(
+                "; installed earlier with --exclude {0} / 此前安装时使用了 --exclude {0}",
+                globs.join(", ")
+            ));
+        }
+        items.push(UnpackPreviewItem {
+            id: p.id.clone(),
+            folder: folder_name,
+            will_install,
+            reason,
+            changed_files,
+            conflict,
+        });
+    }
+
+    let install = items.iter().filter(|i| i.will_install).count();
+    Ok(UnpackPreview {
+        summary: UnpackPreviewSummary {
+            install,
+            skip: items.len() - install,
+            bundle: manifest.bundle.as_ref().map(ManifestBundleDe::meta),
+        },
+        plugins: items,
+    })
+}
+
+/// Human-readable preview lines shared by `unpack --dry-run` and the TUI
+pub fn format_unpack_preview(items: &[UnpackPreviewItem]) -> String {
+    use std::fmt::Write as _;
+    let mut out = String::new();
+    if items.is_empty() {
+        writeln!(&mut out, "(manifest has no plugins)").ok();
+    }
+    for item in items {
+        let action = if item.will_install { "INSTALL" } else { "SKIP" };
+        writeln!(
+            &mut out,
+            "- [{}] id={} folder={}\n    {}",
+            action, item.id, item.folder, item.reason
+        )
+        .ok();
+        for f in &item.changed_files {
+            writeln!(&mut out, "      {:?}: {}", f.kind, f.path).ok();
+        }
+    }
+    out
+}
+
+/// One side of a `diff`: a bundle, or the installed plugins a bundle would replace
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffSource {
+    /// The zip path, or the plugins dir for the installed tree
+    pub label: String,
+    /// The manifest's [bundle] table; None for the installed tree and bundles without one
+    pub bundle: Option<BundleMeta>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffPluginVersion {
+    pub id: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentChange {
+    Unchanged,
+    Modified,
+    /// Neither per-file checksums nor folder hashes of one algorithm exist on both sides
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleFileChange {
+    /// Relative to the plugin folder, with '/'
+    pub path: String,
+    pub change: FileChange,
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+    /// `new_size - old_size`, a missing side counting as 0
+    pub bytes_delta: i64,
+}
+
+impl BundleFileChange {
+    fn new(path: &str, change: FileChange, old_size: Option<u64>, new_size: Option<u64>) -> BundleFileChange {
+        BundleFileChange {
+            path: path.to_string(),
+            change,
+            old_size,
+            new_size,
+            bytes_delta: new_size.unwrap_or(0) as i64 - old_size.unwrap_or(0) as i64,
+        }
+    }
+}
+
+/// A plugin on both sides whose version or contents differ
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginDiff {
+    pub id: String,
+    pub old_version: String,
+    pub new_version: String,
+    pub content: ContentChange,
+    /// "files" (per-file sha256), "md5" or "sha256" (folder hash); None when nothing was comparable
+    pub compared_by: Option<&'static str>,
+    /// Per-file changes, sorted by path; only when compared by files
+    pub files: Vec<BundleFileChange>,
+    /// Sum of the per-file deltas; only when compared by files
+    pub bytes_delta: Option<i64>,
+}
+
+/// What changed from `old` to `new`, by plugin id
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleDiff {
+    pub old: DiffSource,
+    pub new: DiffSource,
+    pub added: Vec<DiffPluginVersion>,
+    pub removed: Vec<DiffPluginVersion>,
+    pub changed: Vec<PluginDiff>,
+    /// Ids with the same version and contents on both sides
+    pub unchanged: Vec<String>,
+}
+
+/// A plugin as `diff` sees it. Installed plugins carry their folder instead of checksums, which
+/// are computed only when the other side has something to compare them with.
+struct DiffPlugin {
+    version: String,
+    hash: Option<(HashAlgo, String)>,
+    files: Option<std::collections::BTreeMap<String, (u64, String)>>,
+    dir: Option<PathBuf>,
+}
+
+impl DiffPlugin {
+    fn from_manifest(p: &ManifestPluginDe, excludes: &Excludes) -> DiffPlugin {
+        DiffPlugin {
+            version: p.version.clone(),
+            hash: p.expected_hash().map(|(algo, h)| (algo, h.to_string())),
+            // Like diff_plugin_files: excluded files never end up installed
+            files: p.files.as_ref().map(|files| {
+                files
+                    .iter()
+                    .filter(|f| !excludes.is_match(&f.path))
+                    .map(|f| (f.path.clone(), (f.size, f.sha256.clone())))
+                    .collect()
+            }),
+            dir: None,
+        }
+    }
+
+    fn files(&self, excludes: &Excludes) -> Result<Option<std::collections::BTreeMap<String, (u64, String)>>> {
+        if let Some(files) = &self.files {
+            return Ok(Some(files.clone()));
+        }
+        let Some(dir) = &self.dir else {
+            return Ok(None);
+        };
+        let mut out = std::collections::BTreeMap::new();
+        for (rel, path) in list_plugin_files(dir, excludes)? {
+            let size = fs::metadata(&path).with_context(|| PathContext::new("failed to stat", &path))?.len();
+            out.insert(rel, (size, file_sha256(&path)?));
+        }
+        Ok(Some(out))
+    }
+
+    fn hash(&self, algo: HashAlgo, excludes: &Excludes) -> Result<Option<String>> {
+        match (&self.hash, &self.dir) {
+            (Some((a, h)), _) if *a == algo => Ok(Some(h.clone())),
+            (_, Some(dir)) => Ok(Some(folder_hash(dir, excludes, algo)?)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The manifest of a bundle; a plain plugin-folder zip is read as the one-plugin bundle unpack would make of it
+fn read_diff_manifest(zip_path: &Path, excludes: &Excludes) -> Result<ManifestDe> {
+    let mut archive = open_bundle(zip_path)?;
+    if let Some(prefix) = plain_plugin_prefix(&archive, zip_path, false)? {
+        let (bundle, _) = plain_plugin_bundle(&mut archive, zip_path, &prefix, excludes, UNPACK_MAX_UNCOMPRESSED_BYTES)?;
+        let mut archive = ZipArchive::new(std::io::Cursor::new(bundle))?;
+        return read_manifest(&mut archive);
+    }
+    read_manifest(&mut archive).with_context(|| PathContext::new("invalid bundle", zip_path))
+}
+
+fn diff_source(zip_path: &Path, manifest: &ManifestDe) -> DiffSource {
+    DiffSource {
+        label: zip_path.display().to_string(),
+        bundle: manifest.bundle.as_ref().map(ManifestBundleDe::meta),
+    }
+}
+
+fn manifest_diff_plugins(manifest: &ManifestDe, excludes: &Excludes) -> std::collections::BTreeMap<String, DiffPlugin> {
+    manifest
+        .plugins
+        .iter()
+        .map(|p| (p.id.clone(), DiffPlugin::from_manifest(p, excludes)))
+        .collect()
+}
+
+/// Compare two bundles: plugins added, removed or changed from `old` to `new`, with per-file
+/// changes when both manifests carry per-file checksums (format_version 2) and folder-hash
+/// equality otherwise
+pub fn diff_bundles(old: &Path, new: &Path) -> Result<BundleDiff> {
+    let excludes = build_excludes(&[])?;
+    let old_manifest = read_diff_manifest(old, &excludes)?;
+    let new_manifest = read_diff_manifest(new, &excludes)?;
+    diff_plugin_sets(
+        diff_source(old, &old_manifest),
+        manifest_diff_plugins(&old_manifest, &excludes),
+        diff_source(new, &new_manifest),
+        manifest_diff_plugins(&new_manifest, &excludes),
+        &excludes,
+    )
+}
+
+/// What installing `zip_path` into `plugins_dir` would change: the installed folders the bundle's
+/// plugins would replace are the old side, the bundle the new one. Plugins the bundle does not
+/// contain are left out, since unpack leaves them alone.
+pub fn diff_bundle_installed(zip_path: &Path, plugins_dir: &Path) -> Result<BundleDiff> {
+    let excludes = build_excludes(&[])?;
+    let manifest = read_diff_manifest(zip_path, &excludes)?;
+    let mut installed = std::collections::BTreeMap::new();
+    for p in &manifest.plugins {
+        let folder_name = p
+            .folder
+            .trim_end_matches('/')
+            .split('/')
+            .nth(1)
+            .ok_or_else(|| anyhow::anyhow!("invalid manifest folder: {}", p.folder))?;
+        let dir = plugins_dir.join(folder_name);
+        let plugin_toml = dir.join("plugin.toml");
+        if !plugin_toml.is_file() {
+            continue;
+        }
+        let Some(record) = read_plugin_record(folder_name.to_string(), &plugin_toml, None)? else {
+            continue;
+        };
+        installed.insert(
+            p.id.clone(),
+            DiffPlugin {
+                version: record.version,
+                hash: None,
+                files: None,
+                dir: Some(dir),
+            },
+        );
+    }
+    let old = DiffSource {
+        label: plugins_dir.display().to_string(),
+        bundle: None,
+    };
+    diff_plugin_sets(old, installed, diff_source(zip_path, &manifest), manifest_diff_plugins(&manifest, &excludes), &excludes)
+}
+
+fn diff_plugin_sets(
+    old_source: DiffSource,
+    old: std::collections::BTreeMap<String, DiffPlugin>,
+    new_source: DiffSource,
+    new: std::collections::BTreeMap<String, DiffPlugin>,
+    excludes: &Excludes,
+) -> Result<BundleDiff> {
+    let version = |id: &String, p: &DiffPlugin| DiffPluginVersion {
+        id: id.clone(),
+        version: p.version.clone(),
+    };
+    let mut diff = BundleDiff {
+        old: old_source,
+        new: new_source,
+        added: new.iter().filter(|(id, _)| !old.contains_key(*id)).map(|(id, p)| version(id, p)).collect(),
+        removed: old.iter().filter(|(id, _)| !new.contains_key(*id)).map(|(id, p)| version(id, p)).collect(),
+        changed: Vec::new(),
+        unchanged: Vec::new(),
+    };
+    for (id, a) in &old {
+        let Some(b) = new.get(id) else {
+            continue;
+        };
+        let (content, compared_by, files) = compare_diff_plugins(a, b, excludes)?;
+        if content == ContentChange::Unchanged && a.version == b.version {
+            diff.unchanged.push(id.clone());
+            continue;
+        }
+        let bytes_delta = (compared_by == Some("files")).then(|| files.iter().map(|f| f.bytes_delta).sum());
+        diff.changed.push(PluginDiff {
+            id: id.clone(),
+            old_version: a.version.clone(),
+            new_version: b.version.clone(),
+            content,
+            compared_by,
+            files,
+            bytes_delta,
+        });
+    }
+    Ok(diff)
+}
+
+fn compare_diff_plugins(
+    old: &DiffPlugin,
+    new: &DiffPlugin,
+    excludes: &Excludes,
+) -> Result<(ContentChange, Option<&'static str>, Vec<BundleFileChange>)> {
+    let changed = |differs: bool| if differs { ContentChange::Modified } else { ContentChange::Unchanged };
+    // Per-file checksums of a bundle can be compared with an installed folder, not with a folder hash
+    let files = if old.files.is_some() || new.files.is_some() {
+        old.files(excludes)?.zip(new.files(excludes)?)
+    } else {
+        None
+    };
+    if let Some((a, b)) = files {
+        let mut files: Vec<BundleFileChange> = Vec::new();
+        for (path, (size, sha)) in &a {
+            match b.get(path) {
+                None => files.push(BundleFileChange::new(path, FileChange::Removed, Some(*size), None)),
+                Some((new_size, new_sha)) if new_size != size || new_sha != sha => {
+                    files.push(BundleFileChange::new(path, FileChange::Modified, Some(*size), Some(*new_size)))
+                }
+                Some(_) => {}
+            }
+        }
+        files.extend(
+            b.iter()
+                .filter(|(path, _)| !a.contains_key(*path))
+                .map(|(path, (size, _))| BundleFileChange::new(path, FileChange::Added, None, Some(*size))),
+        );
+        files.sort_by(|x, y| x.path.cmp(&y.path));
+        return Ok((changed(!files.is_empty()), Some("files"), files));
+    }
+    for algo in [&old.hash, &new.hash].into_iter().flatten().map(|(algo, _)| *algo) {
+        if let (Some(a), Some(b)) = (old.hash(algo, excludes)?, new.hash(algo, excludes)?) {
+            return Ok((changed(a != b), Some(algo.as_str()), Vec::new()));
+        }
+    }
+    Ok((ContentChange::Unknown, None, Vec::new()))
+}
+
+fn signed_bytes(delta: i64) -> String {
+    format!("{}{}", if delta < 0 { "-" } else { "+" }, human_bytes(delta.unsigned_abs()))
+}
+
+/// Text form of a `diff`: `+`/`-` for added/removed plugins, `~` for changed ones with their file
+/// changes (`+`/`-`/`M`) indented below, `?` when the contents could not be compared
+pub fn format_bundle_diff(diff: &BundleDiff) -> String {
+    use std::fmt::Write as _;
+    let source = |s: &DiffSource| match s.bundle.as_ref().and_then(|b| b.name.as_deref()) {
+        Some(name) => match s.bundle.as_ref().and_then(|b| b.version.as_deref()) {
+            Some(version) => format!("{} ({} {})", s.label, name, version),
+            None => format!("{} ({})", s.label, name),
+        },
+        None => s.label.clone(),
+    };
+    let mut out = String::new();
+    writeln!(&mut out, "--- {}", source(&diff.old)).ok();
+    writeln!(&mut out, "+++ {}", source(&diff.new)).ok();
+    for p in &diff.added {
+        writeln!(&mut out, "+ {} {}", p.id, p.version).ok();
+    }
+    for p in &diff.removed {
+        writeln!(&mut out, "- {} {}", p.id, p.version).ok();
+    }
+    for p in &diff.changed {
+        let version = if p.old_version == p.new_version {
+            p.new_version.clone()
+        } else {
+            format!("{} -> {}", p.old_version, p.new_version)
+        };
+        let detail = match (p.content, p.compared_by, p.bytes_delta) {
+            (ContentChange::Unknown, _, _) => "contents not comparable".to_string(),
+            (ContentChange::Unchanged, _, _) => "same contents".to_string(),
+            (ContentChange::Modified, _, Some(delta)) => format!("{} file(s), {}", p.files.len(), signed_bytes(delta)),
+            (ContentChange::Modified, by, None) => format!("{} differs", by.unwrap_or("hash")),
+        };
+        let marker = if p.content == ContentChange::Unknown { '?' } else { '~' };
+        writeln!(&mut out, "{} {} {} ({})", marker, p.id, version, detail).ok();
+        for f in &p.files {
+            let (marker, sizes) = match f.change {
+                FileChange::Added => ('+', signed_bytes(f.bytes_delta)),
+                FileChange::Removed => ('-', signed_bytes(f.bytes_delta)),
+                FileChange::Modified => (
+                    'M',
+                    format!(
+                        "{} -> {}, {}",
+                        human_bytes(f.old_size.unwrap_or(0)),
+                        human_bytes(f.new_size.unwrap_or(0)),
+                        signed_bytes(f.bytes_delta)
+                    ),
+                ),
+            };
+            writeln!(&mut out, "    {} {} ({})", marker, f.path, sizes).ok();
+        }
+    }
+    if !diff.unchanged.is_empty() {
+        writeln!(&mut out, "= {} unchanged plugin(s)", diff.unchanged.len()).ok();
+    }
+    out
+}
+
+fn check_dependencies(plugins: &[PluginRecord], available: &[PluginRecord], findings: &mut Vec<CheckFinding>) -> Result<()> {
+    use std::collections::HashMap;
+    let mut by_id: HashMap<&str, &PluginRecord> = HashMap::new();
+    for p in available {
+        by_id.insert(&p.id, p);
+    }
+
+    for p in plugins {
+        let id = Some(p.id.as_str());
+        for dep in &p.deps {
+            let finding = match dependency_status(dep, by_id.get(dep.id.as_str()).copied())? {
+                DepStatus::Supported => match dep.supported.as_deref().and_then(pinned_version) {
+                    Some(v) => CheckFinding::warning(
+                        "dep-pinned",
+                        id,
+                        format!(
+                            "plugin {} pins dependency {} to ={}; any other version of {} is rejected",
+                            p.id, dep.id, v, dep.id
+                        ),
+                    ),
+                    None => continue,
+                },
+                DepStatus::Missing => CheckFinding::error(
+                    "dep-missing",
+                    id,
+                    format!("plugin {} depends on missing plugin {}", p.id, dep.id),
+                ),
+                DepStatus::InvalidVersion(v) => CheckFinding::error(
+                    "dep-invalid-version",
+                    id,
+                    format!("plugin {} dependency {} has invalid version {}", p.id, dep.id, v),
+                ),
+                DepStatus::Conflict(tv) => CheckFinding::error(
+                    "dep-conflict",
+                    id,
+                    format!(
+                        "plugin {} dependency {} version {} hits conflicts {:?}",
+                        p.id, dep.id, tv, dep.conflicts
+                    ),
+                ),
+                DepStatus::Untested(tv) => CheckFinding::warning(
+                    "dep-untested",
+                    id,
+                    format!(
+                        "plugin {} dependency {} version {} is in untested range ({})",
+                        p.id,
+                        dep.id,
+                        tv,
+                        dep.untested.clone().unwrap_or_default()
+                    ),
+                ),
+                DepStatus::Unsupported(tv) => CheckFinding::error(
+                    "dep-unsupported",
+                    id,
+                    format!(
+                        "plugin {} dependency {} version {} not supported (supported={:?} untested={:?})",
+                        p.id, dep.id, tv, dep.supported, dep.untested
+                    ),
+                ),
+            };
+            findings.push(finding);
+        }
+    }
+    Ok(())
+}
+
+/// How one declared dependency resolves against the plugin it names
+enum DepStatus<'a> {
+    Supported,
+    Missing,
+    InvalidVersion(&'a str),
+    Conflict(Version),
+    Untested(Version),
+    Unsupported(Version),
+}
+
+impl DepStatus<'_> {
+    /// Short reason for a status check_dependencies reports as an error
+    fn problem(&self) -> Option<&'static str> {
+        match self {
+            DepStatus::Supported | DepStatus::Untested(_) => None,
+            DepStatus::Missing => Some("missing"),
+            DepStatus::InvalidVersion(_) => Some("invalid version"),
+            DepStatus::Conflict(_) => Some("conflict"),
+            DepStatus::Unsupported(_) => Some("unsupported"),
+        }
+    }
+}
+
+fn dependency_status<'a>(dep: &PluginDependencyDecl, target: Option<&'a PluginRecord>) -> Result<DepStatus<'a>> {
+    let Some(target) = target else {
+        return Ok(DepStatus::Missing);
+    };
+    let Ok(tv) = Version::parse(&target.version) else {
+        return Ok(DepStatus::InvalidVersion(&target.version));
+    };
+
+    if any_req_matches(&dep.conflicts, &tv) {
+        return Ok(DepStatus::Conflict(tv));
+    }
+
+    let supported_ok = dep
+        .supported
+        .as_deref()
+        .map(|r| parse_req(r).map(|req| req.matches(&tv)))
+        .transpose()?
+        .unwrap_or(true);
+    if supported_ok {
+        return Ok(DepStatus::Supported);
+    }
+
+    let untested_ok = dep
+        .untested
+        .as_deref()
+        .map(|r| parse_req(r).map(|req| req.matches(&tv)))
+        .transpose()?
+        .unwrap_or(false);
+    Ok(if untested_ok {
+        DepStatus::Untested(tv)
+    } else {
+        DepStatus::Unsupported(tv)
+    })
+}
+
+/// Cycles deadlock the loader. Each cycle through one of `plugins` is reported once, starting
+/// from its smallest id; the graph spans `available` so cycles through unchecked plugins count.
+fn check_dependency_cycles(plugins: &[PluginRecord], available: &[PluginRecord], findings: &mut Vec<CheckFinding>) {
+    for p in plugins {
+        if p.deps.iter().any(|d| d.id == p.id) {
+            findings.push(CheckFinding::error(
+                "dep-self",
+                Some(&p.id),
+                format!("plugin {} depends on itself", p.id),
+            ));
+        }
+    }
+    for cycle in dependency_cycles(available) {
+        if cycle.iter().any(|id| plugins.iter().any(|p| p.id == *id)) {
+            findings.push(CheckFinding::error(
+                "dep-cycle",
+                Some(cycle[0]),
+                format!("dependency cycle: {} -> {}", cycle.join(" -> "), cycle[0]),
+            ));
+        }
+    }
+}
+
+/// Every cycle (of two or more plugins) a depth-first walk finds, each rotated to start at its
+/// smallest id
+fn dependency_cycles(available: &[PluginRecord]) -> std::collections::BTreeSet<Vec<&str>> {
+    use std::collections::{BTreeMap, BTreeSet};
+    let mut graph: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for p in available {
+        let edges = graph.entry(p.id.as_str()).or_default();
+        edges.extend(p.deps.iter().map(|d| d.id.as_str()).filter(|id| *id != p.id));
+    }
+
+    let mut done: BTreeSet<&str> = BTreeSet::new();
+    let mut cycles: BTreeSet<Vec<&str>> = BTreeSet::new();
+    for &start in graph.keys() {
+        if done.contains(start) {
+            continue;
+        }
+        // Depth-first with an explicit stack of (plugin, next edge); an edge back into the
+        // stack closes a cycle
+        let mut stack: Vec<(&str, usize)> = vec![(start, 0)];
+        while let Some(top) = stack.last_mut() {
+            let node = top.0;
+            let Some(&next) = graph[node].get(top.1) else {
+                done.insert(node);
+                stack.pop();
+                continue;
+            };
+            top.1 += 1;
+            if let Some(pos) = stack.iter().position(|&(n, _)| n == next) {
+                let mut cycle: Vec<&str> = stack[pos..].iter().map(|&(n, _)| n).collect();
+                let first = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap_or(0);
+                cycle.rotate_left(first);
+                cycles.insert(cycle);
+            } else if graph.contains_key(next) && !done.contains(next) {
+                stack.push((next, 0));
+            }
+        }
+    }
+    cycles
+}
+
+/// `check --graph`: the dependency graph of `plugin_id` (or of every plugin) as DOT or Mermaid.
+/// Nodes are labeled id@version and edges carry the supported range; edges check_dependencies
+/// would reject or that close a cycle, and plugins that do not exist, are drawn in red.
+pub fn dependency_graph(plugins_dir: &Path, plugin_id: Option<&str>, format: GraphFormat) -> Result<String> {
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+    let available = read_plugin_records(plugins_dir, None)?;
+    let by_id: HashMap<&str, &PluginRecord> = available.iter().map(|p| (p.id.as_str(), p)).collect();
+    let cycle_edges: BTreeSet<(&str, &str)> = dependency_cycles(&available)
+        .iter()
+        .flat_map(|c| (0..c.len()).map(move |i| (c[i], c[(i + 1) % c.len()])))
+        .collect();
+
+    // id -> label; None for plugins nothing provides
+    let mut nodes: BTreeMap<&str, Option<String>> = BTreeMap::new();
+    let mut edges: Vec<GraphEdge> = Vec::new();
+    let mut selected: Vec<&PluginRecord> = available.iter().filter(|p| plugin_id.is_none_or(|id| p.id == id)).collect();
+    selected.sort_by(|a, b| a.id.cmp(&b.id));
+    for p in selected {
+        nodes.insert(&p.id, Some(format!("{}@{}", p.id, p.version)));
+        for dep in &p.deps {
+            let target = by_id.get(dep.id.as_str()).copied();
+            nodes.entry(&dep.id).or_insert_with(|| target.map(|t| format!("{}@{}", t.id, t.version)));
+            let mut problems: Vec<&str> = dependency_status(dep, target)?.problem().into_iter().collect();
+            if dep.id == p.id || cycle_edges.contains(&(p.id.as_str(), dep.id.as_str())) {
+                problems.push("cycle");
+            }
+            let range = dep.supported.as_deref().unwrap_or("*");
+            edges.push(GraphEdge {
+                from: &p.id,
+                to: &dep.id,
+                label: if problems.is_empty() {
+                    range.to_string()
+                } else {
+                    format!("{} ({})", range, problems.join(", "))
+                },
+                problem: !problems.is_empty(),
+            });
+        }
+    }
+
+    Ok(match format {
+        GraphFormat::Dot => render_dot(&nodes, &edges),
+        GraphFormat::Mermaid => render_mermaid(&nodes, &edges),
+    })
+}
+
+struct GraphEdge<'a> {
+    from: &'a str,
+    to: &'a str,
+    label: String,
+    problem: bool,
+}
+
+fn render_dot(nodes: &std::collections::BTreeMap<&str, Option<String>>, edges: &[GraphEdge]) -> String {
+    use std::fmt::Write as _;
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut out = String::from("digraph plugins {\n    rankdir=LR;\n    node [shape=box];\n");
+    for (id, label) in nodes {
+        match label {
+            Some(label) => writeln!(out, "    {} [label={}];", quote(id), quote(label)),
+            None => writeln!(out, "    {} [label={}, style=dashed, color=red, fontcolor=red];", quote(id), quote(id)),
+        }
+        .ok();
+    }
+    for e in edges {
+        let style = if e.problem { ", color=red, fontcolor=red" } else { "" };
+        writeln!(out, "    {} -> {} [label={}{}];", quote(e.from), quote(e.to), quote(&e.label), style).ok();
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_mermaid(nodes: &std::collections::BTreeMap<&str, Option<String>>, edges: &[GraphEdge]) -> String {
+    use std::fmt::Write as _;
+    // Plugin ids may contain characters Mermaid node ids cannot, so nodes are numbered
+    let index: std::collections::HashMap<&str, usize> = nodes.keys().enumerate().map(|(i, id)| (*id, i)).collect();
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "#quot;"));
+    let mut out = String::from("graph LR\n");
+    for (id, label) in nodes {
+        writeln!(out, "    n{}[{}]", index[id], quote(label.as_deref().unwrap_or(id))).ok();
+    }
+    for e in edges {
+        writeln!(out, "    n{} -->|{}| n{}", index[e.from], quote(&e.label), index[e.to]).ok();
+    }
+    for (i, e) in edges.iter().enumerate() {
+        if e.problem {
+            writeln!(out, "    linkStyle {} stroke:red,color:red", i).ok();
+        }
+    }
+    let missing: Vec<String> = nodes
+        .iter()
+        .filter(|(_, label)| label.is_none())
+        .map(|(id, _)| format!("n{}", index[id]))
+        .collect();
+    if !missing.is_empty() {
+        out.push_str("    classDef missing stroke:red,stroke-dasharray:5 5\n");
+        writeln!(out, "    class {} missing", missing.join(",")).ok();
+    }
+    out
+}
+
+/// How a dependency edge of `deps` resolves; the non-`ok` ones other than `untested` are what
+/// check_dependencies reports as errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DepEdgeStatus {
+    Ok,
+    Untested,
+    Missing,
+    InvalidVersion,
+    Conflict,
+    Unsupported,
+    /// The dependency is already on the path from the root
+    Cycle,
+}
+
+impl DepEdgeStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DepEdgeStatus::Ok => "ok",
+            DepEdgeStatus::Untested => "untested",
+            DepEdgeStatus::Missing => "missing",
+            DepEdgeStatus::InvalidVersion => "invalid-version",
+            DepEdgeStatus::Conflict => "conflict",
+            DepEdgeStatus::Unsupported => "unsupported",
+            DepEdgeStatus::Cycle => "cycle",
+        }
+    }
+}
+
+/// A plugin in the `deps` tree, with the edge that leads to it from its parent
+#[derive(Debug, Clone, Serialize)]
+pub struct DepTreeNode {
+    pub id: String,
+    /// The local plugin's version; None when no plugin provides the id
+    pub version: Option<String>,
+    /// The parent's declared `supported` range; None for roots and dependencies without one
+    pub supported: Option<String>,
+    /// None for roots
+    pub status: Option<DepEdgeStatus>,
+    /// Expanded earlier in the output; its dependencies are not repeated (`(*)` in the text tree)
+    pub duplicate: bool,
+    pub dependencies: Vec<DepTreeNode>,
+}
+
+/// `deps`: the dependency tree of `plugin_id`, or of every plugin no other plugin depends on
+/// (plus, if some plugins are only reachable through cycles, the smallest id of each such group).
+/// Edges are classified like check_dependencies does; a plugin whose dependencies were already
+/// expanded is marked `duplicate` instead of being expanded again.
+pub fn dependency_tree(plugins_dir: &Path, plugin_id: Option<&str>) -> Result<Vec<DepTreeNode>> {
+    use std::collections::{HashMap, HashSet};
+    let mut available = read_plugin_records(plugins_dir, None)?;
+    available.sort_by(|a, b| a.id.cmp(&b.id));
+    let by_id: HashMap<&str, &PluginRecord> = available.iter().map(|p| (p.id.as_str(), p)).collect();
+    let roots: Vec<&PluginRecord> = match plugin_id {
+        Some(id) => vec![*by_id
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("plugin {} not found in {}", id, plugins_dir.display()))?],
+        None => {
+            let depended: HashSet<&str> = available.iter().flat_map(|p| p.deps.iter().map(|d| d.id.as_str())).collect();
+            available.iter().filter(|p| !depended.contains(p.id.as_str())).collect()
+        }
+    };
+
+    let mut expanded: HashSet<String> = HashSet::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut tree = Vec::new();
+    for root in roots {
+        tree.push(dep_tree_node(root, None, None, &by_id, &mut expanded, &mut path)?);
+    }
+    if plugin_id.is_none() {
+        for p in &available {
+            if !expanded.contains(&p.id) {
+                tree.push(dep_tree_node(p, None, None, &by_id, &mut expanded, &mut path)?);
+            }
+        }
+    }
+    Ok(tree)
+}
+
+fn dep_tree_node(
+    p: &PluginRecord,
+    supported: Option<String>,
+    status: Option<DepEdgeStatus>,
+    by_id: &std::collections::HashMap<&str, &PluginRecord>,
+    expanded: &mut std::collections::HashSet<String>,
+    path: &mut Vec<String>,
+) -> Result<DepTreeNode> {
+    let mut node = DepTreeNode {
+        id: p.id.clone(),
+        version: Some(p.version.clone()),
+        supported,
+        status,
+        duplicate: false,
+        dependencies: Vec::new(),
+    };
+    if !expanded.insert(p.id.clone()) {
+        node.duplicate = !p.deps.is_empty();
+        return Ok(node);
+    }
+    path.push(p.id.clone());
+    for dep in &p.deps {
+        let target = by_id.get(dep.id.as_str()).copied();
+        let status = match dependency_status(dep, target)? {
+            _ if path.contains(&dep.id) => DepEdgeStatus::Cycle,
+            DepStatus::Supported => DepEdgeStatus::Ok,
+            DepStatus::Untested(_) => DepEdgeStatus::Untested,
+            DepStatus::Missing => DepEdgeStatus::Missing,
+            DepStatus::InvalidVersion(_) => DepEdgeStatus::InvalidVersion,
+            DepStatus::Conflict(_) => DepEdgeStatus::Conflict,
+            DepStatus::Unsupported(_) => DepEdgeStatus::Unsupported,
+        };
+        node.dependencies.push(match target {
+            Some(t) if status != DepEdgeStatus::Cycle => dep_tree_node(t, dep.supported.clone(), Some(status), by_id, expanded, path)?,
+            _ => DepTreeNode {
+                id: dep.id.clone(),
+                version: target.map(|t| t.version.clone()),
+                supported: dep.supported.clone(),
+                status: Some(status),
+                duplicate: false,
+                dependencies: Vec::new(),
+            },
+        });
+    }
+    path.pop();
+    Ok(node)
+}
+
+/// ASCII tree like `cargo tree`: `id version (requires range)`, with `[status]` on edges that are
+/// not ok and `(*)` on plugins whose dependencies were shown earlier
+pub fn format_dependency_tree(tree: &[DepTreeNode]) -> String {
+    fn walk(out: &mut String, node: &DepTreeNode, prefix: &str, branch: &str) {
+        let mut line = format!("{}{}{}", prefix, branch, node.id);
+        if let Some(version) = &node.version {
+            line.push_str(&format!(" {}", version));
+        }
+        if node.status.is_some() {
+            line.push_str(&format!(" (requires {})", node.supported.as_deref().unwrap_or("*")));
+        }
+        if let Some(status) = node.status.filter(|s| *s != DepEdgeStatus::Ok) {
+            line.push_str(&format!(" [{}]", status.as_str()));
+        }
+        if node.duplicate {
+            line.push_str(" (*)");
+        }
+        out.push_str(&line);
+        out.push('\n');
+        let child_prefix = match branch {
+            "├── " => format!("{}│   ", prefix),
+            "└── " => format!("{}    ", prefix),
+            _ => prefix.to_string(),
+        };
+        for (i, child) in node.dependencies.iter().enumerate() {
+            let last = i + 1 == node.dependencies.len();
+            walk(out, child, &child_prefix, if last { "└── " } else { "├── " });
+        }
+    }
+    let mut out = String::new();
+    for (i, root) in tree.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        walk(&mut out, root, "", "");
+    }
+    out
+}
+
+#[derive(Debug, Serialize)]
+pub struct InfoOutput {
+    pub neko_version: String,
+    pub repo_root: PathBuf,
+    pub plugins: Vec<PluginMeta>,
+    /// Bundle metadata `pack` falls back to when neither flags nor a spec set it
+    pub bundle_defaults: RepoBundleDefaults,
+    /// Sum of the plugins' disk usage; only with `--sizes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totals: Option<DiskUsage>,
+    /// SDK_VERSION the plugins' `sdk_compat` refers to; only with `--compat`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sdk_version: Option<String>,
+    /// Bundles the plugins were installed from, per their provenance records; only with `--bundles`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundles: Option<Vec<InstalledBundle>>,
+}
+
+/// One bundle that unpack installed plugins from, in install order
+#[derive(Debug, Serialize)]
+pub struct InstalledBundle {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub packed_at: String,
+    pub source_zip: String,
+    pub source_sha256: String,
+    pub plugins: Vec<InstalledBundlePlugin>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstalledBundlePlugin {
+    pub id: String,
+    /// As installed; plugin.toml may say otherwise when `modified`
+    pub version: String,
+    pub folder: String,
+    pub installed_at: String,
+    /// The folder's files changed since the install
+    pub modified: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginMeta {
+    pub id: String,
+    pub version: String,
+    pub entry: String,
+    /// Directory name under plugin/plugins
+    pub folder: String,
+    /// Only with `--sizes`
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub disk_usage: Option<DiskUsage>,
+    /// Against the repo's SDK_VERSION; only with `--compat`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sdk_compat: Option<SdkCompat>,
+    /// Listed in plugin/plugins/.disabled.toml
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+}
+
+/// Files of a plugin folder (or several) on disk, as `info --sizes` reports them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiskUsage {
+    /// Files pack would include, i.e. after the default excludes and `.nekopackignore`
+    pub file_count: u64,
+    pub total_bytes: u64,
+    /// Bytes of the files those excludes leave out
+    pub excluded_bytes: u64,
+}
+
+impl std::ops::Add for DiskUsage {
+    type Output = DiskUsage;
+
+    fn add(self, other: DiskUsage) -> DiskUsage {
+        DiskUsage {
+            file_count: self.file_count + other.file_count,
+            total_bytes: self.total_bytes + other.total_bytes,
+            excluded_bytes: self.excluded_bytes + other.excluded_bytes,
+        }
+    }
+}
+
+/// What `info` does beyond reading plugin.toml files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InfoOptions {
+    /// Walk every plugin folder for DiskUsage
+    pub sizes: bool,
+    pub sort: InfoSort,
+    /// Classify every plugin against SDK_VERSION
+    pub compat: bool,
+    /// Group plugins by the bundle they were installed from
+    pub bundles: bool,
+}
+
+/// Plugin order of `info`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum InfoSort {
+    #[default]
+    Id,
+    /// Largest first; implies `--sizes`
+    Size,
+}
+
+impl std::str::FromStr for InfoSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "id" => Ok(InfoSort::Id),
+            "size" => Ok(InfoSort::Size),
+            other => anyhow::bail!("unsupported info sort: {} (expected id or size)", other),
+        }
+    }
+}
+
+/// Output format of `info`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum InfoFormat {
+    /// Summary lines for humans
+    #[default]
+    Text,
+    /// Plugins as aligned columns
+    Table,
+    /// InfoOutput as JSON, like `--json`
+    Json,
+    /// InfoOutput as YAML
+    Yaml,
+}
+
+impl std::str::FromStr for InfoFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(InfoFormat::Text),
+            "table" => Ok(InfoFormat::Table),
+            "json" => Ok(InfoFormat::Json),
+            "yaml" => Ok(InfoFormat::Yaml),
+            other => anyhow::bail!("unsupported info format: {} (expected text, table, json or yaml)", other),
+        }
+    }
+}
+
+/// A PluginMeta field `info --columns` can select
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum InfoColumn {
+    Id,
+    Version,
+    Entry,
+    Folder,
+    /// `file_count`, with `--sizes`
+    Files,
+    /// `total_bytes`, with `--sizes`
+    Size,
+    /// `excluded_bytes`, with `--sizes`
+    Excluded,
+    /// `sdk_compat`, with `--compat`
+    Compat,
+    /// Whether `disable` took the plugin out of rotation
+    Disabled,
+}
+
+impl InfoColumn {
+    pub const ALL: [InfoColumn; 4] = [InfoColumn::Id, InfoColumn::Version, InfoColumn::Entry, InfoColumn::Folder];
+    pub const SIZES: [InfoColumn; 3] = [InfoColumn::Files, InfoColumn::Size, InfoColumn::Excluded];
+
+    /// The name `--columns` takes, upper-cased as the table header
+    fn name(self) -> &'static str {
+        match self {
+            InfoColumn::Id => "id",
+            InfoColumn::Version => "version",
+            InfoColumn::Entry => "entry",
+            InfoColumn::Folder => "folder",
+            InfoColumn::Files => "files",
+            InfoColumn::Size => "size",
+            InfoColumn::Excluded => "excluded",
+            InfoColumn::Compat => "compat",
+            InfoColumn::Disabled => "disabled",
+        }
+    }
+
+    /// The PluginMeta field name, used as the JSON/YAML key
+    fn key(self) -> &'static str {
+        match self {
+            InfoColumn::Files => "file_count",
+            InfoColumn::Size => "total_bytes",
+            InfoColumn::Excluded => "excluded_bytes",
+            InfoColumn::Compat => "sdk_compat",
+            other => other.name(),
+        }
+    }
+
+    fn text(self, p: &PluginMeta) -> Option<&str> {
+        match self {
+            InfoColumn::Id => Some(&p.id),
+            InfoColumn::Version => Some(&p.version),
+            InfoColumn::Entry => Some(&p.entry),
+            InfoColumn::Folder => Some(&p.folder),
+            InfoColumn::Compat => p.sdk_compat.map(SdkCompat::as_str),
+            InfoColumn::Disabled => Some(if p.disabled { "yes" } else { "no" }),
+            _ => None,
+        }
+    }
+
+    fn count(self, usage: &DiskUsage) -> Option<u64> {
+        match self {
+            InfoColumn::Files => Some(usage.file_count),
+            InfoColumn::Size => Some(usage.total_bytes),
+            InfoColumn::Excluded => Some(usage.excluded_bytes),
+            _ => None,
+        }
+    }
+
+    /// Table cell; sizes are human readable, "-" when they were not collected
+    fn display(self, p: &PluginMeta) -> String {
+        if let Some(text) = self.text(p) {
+            return text.to_string();
+        }
+        match (self, p.disk_usage.as_ref().and_then(|u| self.count(u))) {
+            (_, None) => "-".to_string(),
+            (InfoColumn::Files, Some(n)) => n.to_string(),
+            (_, Some(n)) => human_bytes(n),
+        }
+    }
+}
+
+impl std::str::FromStr for InfoColumn {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        InfoColumn::ALL
+            .into_iter()
+            .chain(InfoColumn::SIZES)
+            .chain([InfoColumn::Compat, InfoColumn::Disabled])
+            .find(|c| c.name() == s.trim().to_ascii_lowercase())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown info column: {} (expected id, version, entry, folder, files, size, excluded, compat or disabled)",
+                    s
+                )
+            })
+    }
+}
+
+/// One plugin limited to the selected columns, serialized as a map in column order
+struct InfoRow<'a> {
+    plugin: &'a PluginMeta,
+    columns: &'a [InfoColumn],
+}
+
+impl Serialize for InfoRow<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for c in self.columns {
+            if *c == InfoColumn::Disabled {
+                map.serialize_entry(c.key(), &self.plugin.disabled)?;
+                continue;
+            }
+            match c.text(self.plugin) {
+                Some(text) => map.serialize_entry(c.key(), text)?,
+                None => map.serialize_entry(c.key(), &self.plugin.disk_usage.as_ref().and_then(|u| c.count(u)))?,
+            }
+        }
+        map.end()
+    }
+}
+
+/// InfoOutput with its plugins reduced to the selected columns; with the default columns it
+/// serializes exactly like InfoOutput
+#[derive(Serialize)]
+struct InfoView<'a> {
+    neko_version: &'a str,
+    repo_root: &'a Path,
+    plugins: Vec<InfoRow<'a>>,
+    bundle_defaults: &'a RepoBundleDefaults,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    totals: Option<&'a DiskUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sdk_version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundles: Option<&'a [InstalledBundle]>,
+}
+
+/// Render `info` in `format`; `columns` picks the plugin fields and their order (default: all of
+/// them, the size and compat columns only when they were collected)
+pub fn render_info(info: &InfoOutput, format: InfoFormat, columns: Option<&[InfoColumn]>) -> Result<String> {
+    use std::fmt::Write as _;
+    let mut default_columns = InfoColumn::ALL.to_vec();
+    if info.totals.is_some() {
+        default_columns.extend(InfoColumn::SIZES);
+    }
+    if info.sdk_version.is_some() {
+        default_columns.push(InfoColumn::Compat);
+    }
+    let columns = columns.unwrap_or(&default_columns);
+    if columns.is_empty() {
+        anyhow::bail!("info needs at least one column");
+    }
+    let view = InfoView {
+        neko_version: &info.neko_version,
+        repo_root: &info.repo_root,
+        plugins: info.plugins.iter().map(|plugin| InfoRow { plugin, columns }).collect(),
+        bundle_defaults: &info.bundle_defaults,
+        totals: info.totals.as_ref(),
+        sdk_version: info.sdk_version.as_deref(),
+        bundles: info.bundles.as_deref(),
+    };
+    let mut out = String::new();
+    match format {
+        InfoFormat::Json => {
+            out = serde_json::to_string_pretty(&view)?;
+            out.push('\n');
+        }
+        InfoFormat::Yaml => out = serde_yaml::to_string(&view)?,
+        InfoFormat::Table => {
+            // The JSON keeps `disabled` out of the default columns; a table has no other way to show it
+            let mut table_columns = columns.to_vec();
+            if columns == default_columns && info.plugins.iter().any(|p| p.disabled) {
+                table_columns.push(InfoColumn::Disabled);
+            }
+            let columns = &table_columns;
+            let mut rows: Vec<Vec<String>> =
+                vec![columns.iter().map(|c| c.name().to_ascii_uppercase()).collect()];
+            rows.extend(info.plugins.iter().map(|p| columns.iter().map(|c| c.display(p)).collect()));
+            if let Some(totals) = &info.totals {
+                // Totals go under the size columns, the label under the first other one
+                let mut labelled = false;
+                rows.push(
+                    columns
+                        .iter()
+                        .map(|c| match c.count(totals) {
+                            Some(n) if *c == InfoColumn::Files => n.to_string(),
+                            Some(n) => human_bytes(n),
+                            None if !labelled => {
+                                labelled = true;
+                                "TOTAL".to_string()
+                            }
+                            None => String::new(),
+                        })
+                        .collect(),
+                );
+            }
+            out = render_table(&rows);
+            if let Some(bundles) = &info.bundles {
+                let mut rows = vec![["BUNDLE", "VERSION", "PLUGIN", "INSTALLED", "MODIFIED"].map(String::from).to_vec()];
+                for b in bundles {
+                    for p in &b.plugins {
+                        rows.push(vec![
+                            b.name.clone().unwrap_or_else(|| b.source_zip.clone()),
+                            b.version.clone().unwrap_or_else(|| "-".to_string()),
+                            format!("{} {}", p.id, p.version),
+                            p.installed_at.clone(),
+                            if p.modified { "yes" } else { "no" }.to_string(),
+                        ]);
+                    }
+                }
+                out.push('\n');
+                out.push_str(&render_table(&rows));
+            }
+        }
+        InfoFormat::Text => {
+            writeln!(out, "N.E.K.O version: {}", info.neko_version).ok();
+            writeln!(out, "Repo root: {}", info.repo_root.display()).ok();
+            if let Some(sdk_version) = &info.sdk_version {
+                writeln!(out, "SDK version: {}", sdk_version).ok();
+            }
+            writeln!(out, "Plugin count: {}", info.plugins.len()).ok();
+            for p in &info.plugins {
+                // Without --columns this stays the `id vVERSION (entry)` line scripts may grep for
+                let mut line = if columns == default_columns {
+                    format!("{} v{} ({})", p.id, p.version, p.entry)
+                } else {
+                    columns.iter().map(|c| c.display(p)).collect::<Vec<_>>().join(" ")
+                };
+                if columns == default_columns {
+                    if let Some(usage) = &p.disk_usage {
+                        line.push_str(&format!(", {}", describe_disk_usage(usage)));
+                    }
+                    if let Some(compat) = p.sdk_compat {
+                        line.push_str(&format!(" [{}]", compat.as_str()));
+                    }
+                    if p.disabled {
+                        line.push_str(" [disabled]");
+                    }
+                }
+                writeln!(out, "- {}", line).ok();
+            }
+            if let Some(totals) = &info.totals {
+                writeln!(out, "Total: {}", describe_disk_usage(totals)).ok();
+            }
+            if let Some(bundles) = &info.bundles {
+                writeln!(out, "Installed bundles: {}", bundles.len()).ok();
+                for b in bundles {
+                    let version = b.version.as_deref().map(|v| format!(" {}", v)).unwrap_or_default();
+                    writeln!(
+                        out,
+                        "- {}{} (from {}, packed {}, sha256 {})",
+                        b.name.as_deref().unwrap_or(&b.source_zip),
+                        version,
+                        b.source_zip,
+                        b.packed_at,
+                        &b.source_sha256[..b.source_sha256.len().min(12)]
+                    )
+                    .ok();
+                    for p in &b.plugins {
+                        let modified = if p.modified { ", modified since" } else { "" };
+                        writeln!(out, "    {} v{} installed {}{}", p.id, p.version, p.installed_at, modified).ok();
+                    }
+                }
+            }
+            let defaults = &info.bundle_defaults;
+            if !defaults.sources.is_empty() {
+                let sources: Vec<String> = defaults.sources.iter().map(|p| p.display().to_string()).collect();
+                writeln!(out, "Bundle defaults ({}):", sources.join(", ")).ok();
+                let fields = [("name", &defaults.meta.name), ("version", &defaults.meta.version), ("author", &defaults.meta.author)];
+                for (key, value) in fields {
+                    writeln!(out, "  {}: {}", key, value.as_deref().unwrap_or("-")).ok();
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// "3 file(s), 1.2 MiB (4.0 KiB excluded)"
+fn describe_disk_usage(usage: &DiskUsage) -> String {
+    format!(
+        "{} file(s), {} ({} excluded)",
+        usage.file_count,
+        human_bytes(usage.total_bytes),
+        human_bytes(usage.excluded_bytes)
+    )
+}
+
+/// Left-aligned columns two spaces apart, padded by display width so CJK and emoji line up;
+/// trailing padding is trimmed
+fn render_table(rows: &[Vec<String>]) -> String {
+    use unicode_width::UnicodeWidthStr;
+    let ncols = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..ncols)
+        .map(|i| rows.iter().filter_map(|r| r.get(i)).map(|c| c.width()).max().unwrap_or(0))
+        .collect();
+    let mut out = String::new();
+    for row in rows {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(&widths) {
+            line.push_str(cell);
+            line.push_str(&" ".repeat(width - cell.width() + 2));
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Contents of a bundle's manifest.toml as written by pack_to_zip.
+/// Fields serialize in declaration order, which `pack --list-only --json` documents as stable.
+#[derive(Debug, Serialize, Clone)]
+pub struct Manifest {
+    format_version: u32,
+    neko_base_version: String,
+    packed_at: String,
+    /// "neko-plugin-cli <version>" of the build that wrote the bundle
+    packed_by: String,
+    root_layout: String,
+    /// Informational only; readers take the method from each zip entry
+    compression: String,
+    compression_level: Option<i64>,
+    bundle: Option<ManifestBundle>,
+    bundle_profiles_root: Option<String>,
+    plugins: Vec<ManifestPlugin>,
+    /// Repo-level files under extras/ (`pack --include-root`); paths are relative to extras/
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    extras: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ManifestBundle {
+    name: String,
+    version: Option<String>,
+    author: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ManifestPlugin {
+    id: String,
+    name: String,
+    version: String,
+    entry: String,
+    folder: String,
+    md5: Option<String>,
+    hash_algo: Option<String>,
+    hash: Option<String>,
+    bundled_profiles: Vec<String>,
+    /// Where each bundled_profiles entry came from, relative to the plugin folder (same order)
+    bundled_profile_paths: Vec<String>,
+    /// Added by `pack --with-deps` as a dependency of a requested plugin
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    implicit: bool,
+    files: Option<Vec<ManifestFile>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ManifestFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestDe {
+    format_version: u32,
+    neko_base_version: String,
+    packed_at: String,
+    /// Missing in bundles from before the field was added
+    packed_by: Option<String>,
+    root_layout: String,
+    compression: Option<String>,
+    compression_level: Option<i64>,
+    bundle: Option<ManifestBundleDe>,
+    bundle_profiles_root: Option<String>,
+    plugins: Vec<ManifestPluginDe>,
+    #[serde(default)]
+    extras: Vec<ManifestFileDe>,
+    /// The bundle's own format_version when it was read as an older one (`--assume-version`)
+    #[serde(skip)]
+    assumed_from: Option<u32>,
+}
+
+/// What every manifest format_version has in common; read first to pick how to parse the rest
+#[derive(Debug, Deserialize)]
+struct ManifestHeader {
+    format_version: u32,
+    packed_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestBundleDe {
+    name: String,
+    version: Option<String>,
+    author: Option<String>,
+}
+
+impl ManifestBundleDe {
+    fn meta(&self) -> BundleMeta {
+        BundleMeta {
+            name: Some(self.name.clone()),
+            version: self.version.clone(),
+            author: self.author.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestPluginDe {
+    id: String,
+    name: String,
+    version: String,
+    entry: String,
+    folder: String,
+    md5: Option<String>,
+    hash_algo: Option<String>,
+    hash: Option<String>,
+    bundled_profiles: Option<Vec<String>>,
+    /// Missing in bundles from before the field was added
+    bundled_profile_paths: Option<Vec<String>>,
+    #[serde(default)]
+    implicit: bool,
+    files: Option<Vec<ManifestFileDe>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestFileDe {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+impl ManifestPluginDe {
+    /// Folder hash to compare against; bundles from before `hash_algo` only carry `md5`
+    fn expected_hash(&self) -> Option<(HashAlgo, &str)> {
+        let declared = self
+            .hash_algo
+            .as_deref()
+            .and_then(|a| a.parse::<HashAlgo>().ok())
+            .zip(self.hash.as_deref());
+        declared.or_else(|| self.md5.as_deref().map(|m| (HashAlgo::Md5, m)))
+    }
+}
+
+fn sanitize_for_filename(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        let keep = ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.');
+        out.push(if keep { ch } else { '_' });
+    }
+    if out.is_empty() {
+        "bundle".to_string()
+    } else {
+        out
+    }
+}
+
+fn derive_bundle_name(out_path: &Path) -> String {
+    out_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| sanitize_for_filename(s))
+        .unwrap_or_else(|| "bundle".to_string())
+}
+
+fn read_file_to_zip<W: Write + std::io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    zip_path: &str,
+    src: &Path,
+    options: FileOptions<()>,
+    mut digest: Option<&mut Sha256>,
+) -> Result<u64> {
+    zip.start_file(zip_path, options)?;
+    let mut f = fs::File::open(src).with_context(|| format!("failed to open {}", src.display()))?;
+    let mut buf = [0u8; 1024 * 64];
+    let mut size = 0u64;
+    loop {
+        let n = f.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if let Some(d) = digest.as_deref_mut() {
+            d.update(&buf[..n]);
+        }
+        zip.write_all(&buf[..n])?;
+        size += n as u64;
+    }
+    Ok(size)
+}
+
+fn write_manifest_entry<W: Write + std::io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    manifest: &Manifest,
+    options: FileOptions<()>,
+) -> Result<()> {
+    let manifest_text = toml::to_string(manifest).context("failed to serialize manifest")?;
+    zip.start_file("manifest.toml", options)?;
+    zip.write_all(manifest_text.as_bytes())?;
+    Ok(())
+}
+
+fn collect_profile_files(plugin_dir: &Path) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    let p1 = plugin_dir.join("profiles.toml");
+    if p1.is_file() {
+        out.push(p1);
+    }
+    let pdir = plugin_dir.join("profiles");
+    if pdir.is_dir() {
+        for e in WalkDir::new(&pdir).follow_links(false) {
+            if let Ok(e) = e {
+                if e.file_type().is_file() {
+                    out.push(e.path().to_path_buf());
+                }
+            }
+        }
+    }
+    out.sort();
+    out
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginPackItem {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub entry: String,
+    pub folder: String,
+    pub path: PathBuf,
+    pub hash: Option<String>,
+    pub hash_algo: HashAlgo,
+    /// Pulled in by `--with-deps` rather than requested
+    pub implicit: bool,
+}
+
+pub fn find_repo_root(mut start: PathBuf) -> Result<PathBuf> {
+    for _ in 0..10 {
+        let pyproject = start.join("pyproject.toml");
+        let plugins_dir = start.join("plugin").join("plugins");
+        if pyproject.is_file() && plugins_dir.is_dir() {
+            return Ok(start);
+        }
+        if !start.pop() {
+            break;
+        }
+    }
+    anyhow::bail!("failed to locate repo root from cwd")
+}
+
+fn read_neko_base_version(repo_root: &Path) -> Result<String> {
+    let pyproject_path = repo_root.join("pyproject.toml");
+    let pyproject_text = fs::read_to_string(&pyproject_path)
+        .with_context(|| format!("failed to read {}", pyproject_path.display()))?;
+    let pyproject: toml::Value = toml::from_str(&pyproject_text)
+        .with_context(|| format!("failed to parse {}", pyproject_path.display()))?;
+    Ok(pyproject
+        .get("project")
+        .and_then(|v| v.get("version"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string())
+}
+
+/// Default bundle metadata configured in the repo: `[tool.neko.bundle]` in pyproject.toml,
+/// overridden field by field by a `neko_bundle.toml` next to it
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepoBundleDefaults {
+    #[serde(flatten)]
+    pub meta: BundleMeta,
+    /// Files the defaults were read from, in the order they were applied
+    pub sources: Vec<PathBuf>,
+    /// Unknown keys in those tables
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+pub const REPO_BUNDLE_FILE: &str = "neko_bundle.toml";
+
+pub fn load_repo_bundle_defaults(repo_root: &Path) -> Result<RepoBundleDefaults> {
+    let mut out = RepoBundleDefaults::default();
+
+    let pyproject_path = repo_root.join("pyproject.toml");
+    if pyproject_path.is_file() {
+        let text = fs::read_to_string(&pyproject_path).with_context(|| PathContext::new("failed to read", &pyproject_path))?;
+        let pyproject: toml::Table =
+            toml::from_str(&text).with_context(|| PathContext::new("failed to parse", &pyproject_path))?;
+        let table = pyproject
+            .get("tool")
+            .and_then(|v| v.get("neko"))
+            .and_then(|v| v.get("bundle"));
+        if let Some(table) = table {
+            let table = table
+                .as_table()
+                .with_context(|| format!("[tool.neko.bundle] in {} must be a table", pyproject_path.display()))?;
+            apply_bundle_defaults(&mut out, table, &pyproject_path, "tool.neko.bundle.")?;
+        }
+    }
+
+    let file_path = repo_root.join(REPO_BUNDLE_FILE);
+    if file_path.is_file() {
+        let text = fs::read_to_string(&file_path).with_context(|| PathContext::new("failed to read", &file_path))?;
+        let table: toml::Table = toml::from_str(&text).with_context(|| PathContext::new("failed to parse", &file_path))?;
+        apply_bundle_defaults(&mut out, &table, &file_path, "")?;
+    }
+    Ok(out)
+}
+
+fn apply_bundle_defaults(out: &mut RepoBundleDefaults, table: &toml::Table, path: &Path, prefix: &str) -> Result<()> {
+    let field = |key: &str| -> Result<Option<String>> {
+        match table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+            Some(other) => anyhow::bail!(
+                "`{}{}` in {} must be a string, not {}",
+                prefix,
+                key,
+                path.display(),
+                other.type_str()
+            ),
+        }
+    };
+    let meta = BundleMeta {
+        name: field("name")?,
+        version: field("version")?,
+        author: field("author")?,
+    };
+    for key in table.keys().filter(|k| !SPEC_BUNDLE_KEYS.contains(&k.as_str())) {
+        out.warnings.push(format!("unknown key `{}{}` in {} (ignored)", prefix, key, path.display()));
+    }
+    out.meta = meta.or(&out.meta);
+    out.sources.push(path.to_path_buf());
+    Ok(())
+}
+
+/// Repository-wide `check` settings from `[tool.neko.check]` in pyproject.toml
+#[derive(Debug, Clone, Default)]
+pub struct RepoCheckConfig {
+    /// `strict_naming = true`: every plugin id must equal its folder name
+    pub strict_naming: bool,
+}
+
+pub fn load_repo_check_config(repo_root: &Path) -> Result<RepoCheckConfig> {
+    let mut out = RepoCheckConfig::default();
+    let pyproject_path = repo_root.join("pyproject.toml");
+    if !pyproject_path.is_file() {
+        return Ok(out);
+    }
+    let text = fs::read_to_string(&pyproject_path).with_context(|| PathContext::new("failed to read", &pyproject_path))?;
+    let pyproject: toml::Table =
+        toml::from_str(&text).with_context(|| PathContext::new("failed to parse", &pyproject_path))?;
+    let table = pyproject
+        .get("tool")
+        .and_then(|v| v.get("neko"))
+        .and_then(|v| v.get("check"));
+    if let Some(value) = table.and_then(|t| t.get("strict_naming")) {
+        out.strict_naming = value.as_bool().with_context(|| {
+            format!(
+                "`tool.neko.check.strict_naming` in {} must be a boolean, not {}",
+                pyproject_path.display(),
+                value.type_str()
+            )
+        })?;
+    }
+    Ok(out)
+}
+
+pub fn collect_info(root: Option<&Path>, options: InfoOptions) -> Result<InfoOutput> {
+    let repo_root = match root {
+        Some(p) => p.to_path_buf(),
+        None => find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
+    };
+
+    let pyproject_path = repo_root.join("pyproject.toml");
+    let pyproject_text = fs::read_to_string(&pyproject_path)
+        .with_context(|| format!("failed to read {}", pyproject_path.display()))?;
+
+    let pyproject: toml::Value = toml::from_str(&pyproject_text)
+        .with_context(|| format!("failed to parse {}", pyproject_path.display()))?;
+
+    let neko_version = pyproject
+        .get("project")
+        .and_then(|v| v.get("version"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let plugins_dir = repo_root.join("plugin").join("plugins");
+    let mut plugins = scan_plugins(&plugins_dir)?;
+    let bundle_defaults = load_repo_bundle_defaults(&repo_root)?;
+
+    let mut totals = None;
+    if options.sizes || options.sort == InfoSort::Size {
+        let excludes = build_excludes(&[])?;
+        let usages: Vec<Result<DiskUsage>> = plugins
+            .par_iter()
+            .map(|p| plugin_disk_usage(&plugins_dir.join(&p.folder), &excludes))
+            .collect();
+        let mut sum = DiskUsage::default();
+        for (p, usage) in plugins.iter_mut().zip(usages) {
+            let usage = usage?;
+            sum = sum + usage;
+            p.disk_usage = Some(usage);
+        }
+        totals = Some(sum);
+    }
+    let mut sdk_version = None;
+    if options.compat {
+        let version = read_sdk_version(&repo_root)?;
+        for record in read_plugin_records(&plugins_dir, None)? {
+            if let Some(p) = plugins.iter_mut().find(|p| p.folder == record.folder) {
+                let compat = classify_sdk_compat(&record.sdk, &version)
+                    .with_context(|| format!("plugin {}: invalid [plugin.sdk] range", record.id))?;
+                p.sdk_compat = Some(compat);
+            }
+        }
+        sdk_version = Some(version.to_string());
+    }
+    let bundles = if options.bundles {
+        Some(installed_bundles(&plugins_dir, &plugins)?)
+    } else {
+        None
+    };
+    if options.sort == InfoSort::Size {
+        plugins.sort_by_key(|p| std::cmp::Reverse(p.disk_usage.map_or(0, |u| u.total_bytes)));
+    }
+
+    Ok(InfoOutput {
+        neko_version,
+        repo_root,
+        plugins,
+        bundle_defaults,
+        totals,
+        sdk_version,
+        bundles,
+    })
+}
+
+/// Read the provenance records of `plugins` and group them by source bundle
+fn installed_bundles(plugins_dir: &Path, plugins: &[PluginMeta]) -> Result<Vec<InstalledBundle>> {
+    let excludes = build_excludes(&[])?;
+    let records: Vec<Result<Option<(String, BundleProvenance, bool)>>> = plugins
+        .par_iter()
+        .map(|p| {
+            let dir = plugins_dir.join(&p.folder);
+            let Some(record) = BundleProvenance::read(&dir)? else {
+                return Ok(None);
+            };
+            let modified = folder_hash(&dir, &excludes, HashAlgo::Sha256)? != record.folder_sha256;
+            Ok(Some((p.folder.clone(), record, modified)))
+        })
+        .collect();
+    let mut bundles: Vec<InstalledBundle> = Vec::new();
+    let mut first_install: Vec<String> = Vec::new();
+    for record in records {
+        let Some((folder, r, modified)) = record? else {
+            continue;
+        };
+        let i = match bundles.iter().position(|b| b.source_sha256 == r.source_sha256) {
+            Some(i) => i,
+            None => {
+                bundles.push(InstalledBundle {
+                    name: r.bundle_name.clone(),
+                    version: r.bundle_version.clone(),
+                    author: r.bundle_author.clone(),
+                    packed_at: r.packed_at.clone(),
+                    source_zip: r.source_zip.clone(),
+                    source_sha256: r.source_sha256.clone(),
+                    plugins: Vec::new(),
+                });
+                first_install.push(r.installed_at.clone());
+                bundles.len() - 1
+            }
+        };
+        if r.installed_at < first_install[i] {
+            first_install[i] = r.installed_at.clone();
+        }
+        bundles[i].plugins.push(InstalledBundlePlugin {
+            id: r.plugin_id,
+            version: r.plugin_version,
+            folder,
+            installed_at: r.installed_at,
+            modified,
+        });
+    }
+    // Installs within the same second come out by name
+    let mut ordered: Vec<(String, InstalledBundle)> = first_install.into_iter().zip(bundles).collect();
+    ordered.sort_by(|a, b| (&a.0, &a.1.name, &a.1.source_sha256).cmp(&(&b.0, &b.1.name, &b.1.source_sha256)));
+    Ok(ordered
+        .into_iter()
+        .map(|(_, mut b)| {
+            b.plugins.sort_by(|x, y| x.id.cmp(&y.id));
+            b
+        })
+        .collect())
+}
+
+/// Files pack would take from `plugin_dir` against every regular file in it
+fn plugin_disk_usage(plugin_dir: &Path, excludes: &Excludes) -> Result<DiskUsage> {
+    let size = |path: &Path| -> Result<u64> {
+        Ok(fs::metadata(path).with_context(|| PathContext::new("failed to stat", path))?.len())
+    };
+    let mut usage = DiskUsage::default();
+    for (_, path) in list_plugin_files(plugin_dir, excludes)? {
+        usage.file_count += 1;
+        usage.total_bytes += size(&path)?;
+    }
+    let mut all_bytes = 0;
+    for e in WalkDir::new(plugin_dir) {
+        let e = e?;
+        if e.file_type().is_file() {
+            all_bytes += size(e.path())?;
+        }
+    }
+    usage.excluded_bytes = all_bytes.saturating_sub(usage.total_bytes);
+    Ok(usage)
+}
+
+/// Which plugins `list` prints; every set criterion must hold
+#[derive(Debug, Clone, Default)]
+pub struct PluginFilter {
+    /// Must match the whole id
+    pub id_match: Option<Regex>,
+    /// Plugins whose version does not parse never match
+    pub version_req: Option<VersionReq>,
+    pub entry_contains: Option<String>,
+}
+
+impl PluginFilter {
+    /// Parse the `list` options; a malformed one is reported with what is wrong and, for versions, an example
+    pub fn new(id_match: Option<&str>, version_req: Option<&str>, entry_contains: Option<&str>) -> Result<Self> {
+        let id_match = id_match
+            .map(|pat| {
+                Regex::new(&format!("^(?:{})$", pat))
+                    .map_err(|e| anyhow::anyhow!("invalid id pattern {:?}: {}", pat, regex_error_summary(&e)))
+            })
+            .transpose()?;
+        let version_req = version_req
+            .map(|req| {
+                VersionReq::parse(req).map_err(|e| {
+                    anyhow::anyhow!("invalid version requirement {:?}: {} (expected e.g. \">=1.2, <2\" or \"^0.3\")", req, e)
+                })
+            })
+            .transpose()?;
+        Ok(PluginFilter {
+            id_match,
+            version_req,
+            entry_contains: entry_contains.map(str::to_string),
+        })
+    }
+
+    pub fn matches(&self, p: &PluginMeta) -> bool {
+        if self.id_match.as_ref().is_some_and(|re| !re.is_match(&p.id)) {
+            return false;
+        }
+        if let Some(req) = &self.version_req {
+            let version = Version::parse(&p.version)
+                .ok()
+                .or_else(|| padded_version(&p.version).and_then(|v| Version::parse(&v).ok()));
+            if !version.is_some_and(|v| req.matches(&v)) {
+                return false;
+            }
+        }
+        self.entry_contains.as_ref().is_none_or(|needle| p.entry.contains(needle.as_str()))
+    }
+}
+
+/// The last line of a regex error, which says what is wrong without the multi-line pattern excerpt
+fn regex_error_summary(e: &regex::Error) -> String {
+    let text = e.to_string();
+    text.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or(&text).trim().to_string()
+}
+
+/// The plugins of the repo at `root` (auto-detected when None) that `filter` accepts, by id
+pub fn list_plugins(root: Option<&Path>, filter: &PluginFilter) -> Result<Vec<PluginMeta>> {
+    let repo_root = match root {
+        Some(p) => p.to_path_buf(),
+        None => find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
+    };
+    let mut plugins = scan_plugins(&repo_root.join("plugin").join("plugins"))?;
+    plugins.retain(|p| filter.matches(p));
+    Ok(plugins)
+}
+
+/// Output format of `list`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// One id per line
+    #[default]
+    Ids,
+    /// Array of plugin objects, as in `info --json`
+    Json,
+    /// Aligned columns like `info --format table`
+    Table,
+}
+
+pub fn render_plugin_list(plugins: &[PluginMeta], format: ListFormat) -> Result<String> {
+    Ok(match format {
+        ListFormat::Ids => plugins.iter().map(|p| format!("{}\n", p.id)).collect(),
+        ListFormat::Json => serde_json::to_string_pretty(plugins)? + "\n",
+        ListFormat::Table => {
+            let mut columns = InfoColumn::ALL.to_vec();
+            if plugins.iter().any(|p| p.disabled) {
+                columns.push(InfoColumn::Disabled);
+            }
+            let mut rows: Vec<Vec<String>> = vec![columns.iter().map(|c| c.name().to_ascii_uppercase()).collect()];
+            rows.extend(plugins.iter().map(|p| columns.iter().map(|c| c.display(p)).collect()));
+            render_table(&rows)
+        }
+    })
+}
+
+/// Prefills for `new`; unset fields fall back to defaults derived from the id and the repo
+#[derive(Debug, Clone, Default)]
+pub struct ScaffoldOptions {
+    /// Display name; defaults to the id in title case (`weather_bot` -> `Weather Bot`)
+    pub name: Option<String>,
+    pub author: Option<String>,
+    /// `[plugin.sdk] supported`; defaults to the current SDK_VERSION up to its next breaking release
+    pub sdk_supported: Option<String>,
+    /// Directory copied instead of the built-in skeleton, with placeholders substituted
+    pub template: Option<PathBuf>,
+}
+
+/// A plugin folder created by `new`
+#[derive(Debug, Clone, Serialize)]
+pub struct ScaffoldResult {
+    pub id: String,
+    pub path: PathBuf,
+    /// Created files and directories, relative to `path` with '/', sorted
+    pub files: Vec<String>,
+}
+
+/// Placeholders replaced in template file names and UTF-8 file contents
+const SCAFFOLD_PLACEHOLDERS: &[&str] = &["id", "name", "author", "sdk_supported"];
+
+const SCAFFOLD_PACK_IGNORE: &str = "\
+# Files pack leaves out of this plugin, one gitignore-style pattern per line (no `!` negation).
+# Caches, VCS folders and logs are excluded already.
+# tests/
+";
+
+fn scaffold_display_name(id: &str) -> String {
+    id.split(['_', '-', '.'])
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Create `plugin/plugins/<id>/` from the built-in skeleton (plugin.toml, `__init__.py` with a
+/// `main` entry, an empty profiles/ and a `.nekopackignore`) or from `options.template`. Refuses
+/// an id that check would reject, an existing folder and an id another plugin already uses. A
+/// template whose rendered plugin.toml fails validation is removed again.
+pub fn scaffold_plugin(root: Option<&Path>, id: &str, options: &ScaffoldOptions) -> Result<ScaffoldResult> {
+    if let Some(problem) = plugin_id_problem(id) {
+        anyhow::bail!("invalid plugin id: {}", problem);
+    }
+    let repo_root = match root {
+        Some(p) => p.to_path_buf(),
+        None => find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
+    };
+    let plugins_dir = repo_root.join("plugin").join("plugins");
+    let plugin_dir = plugins_dir.join(id);
+    if plugin_dir.exists() {
+        anyhow::bail!("{} already exists; pick another id or remove it first", plugin_dir.display());
+    }
+    if let Some(other) = scan_plugins(&plugins_dir)?.into_iter().find(|p| p.id == id) {
+        anyhow::bail!("plugin id {} is already used by folder {}", id, other.folder);
+    }
+    let sdk_supported = match &options.sdk_supported {
+        Some(req) => {
+            VersionReq::parse(req).map_err(|e| anyhow::anyhow!("invalid --sdk-supported {:?}: {}", req, e))?;
+            Some(req.clone())
+        }
+        None => read_sdk_version(&repo_root).ok().map(|v| seeded_sdk_range(&v)),
+    };
+    let name = options.name.clone().unwrap_or_else(|| scaffold_display_name(id));
+    let values = [id, name.as_str(), options.author.as_deref().unwrap_or(""), sdk_supported.as_deref().unwrap_or("")];
+
+    fs::create_dir_all(&plugins_dir).with_context(|| PathContext::new("failed to create", &plugins_dir))?;
+    fs::create_dir(&plugin_dir).with_context(|| PathContext::new("failed to create", &plugin_dir))?;
+    let written = match &options.template {
+        Some(template) => render_scaffold_template(template, &plugin_dir, &values),
+        None => write_scaffold_skeleton(&plugin_dir, id, &name, options.author.as_deref(), sdk_supported.as_deref()),
+    }
+    .and_then(|files| {
+        let validation = validate_plugin_toml(&plugin_dir)?;
+        if !validation.errors.is_empty() {
+            let problems: Vec<String> = validation.error_messages().collect();
+            anyhow::bail!("the generated plugin.toml is invalid:\n  {}", problems.join("\n  "));
+        }
+        let record = read_plugin_record(id.to_string(), &plugin_dir.join("plugin.toml"), None)?;
+        match record {
+            Some(r) if r.id == id => Ok(files),
+            Some(r) => anyhow::bail!("the template's plugin.toml declares id {} instead of {} (use {{{{id}}}})", r.id, id),
+            None => anyhow::bail!("the template has no plugin.toml"),
+        }
+    });
+    match written {
+        Ok(mut files) => {
+            files.sort();
+            Ok(ScaffoldResult { id: id.to_string(), path: plugin_dir, files })
+        }
+        Err(e) => {
+            let _ = fs::remove_dir_all(&plugin_dir);
+            Err(e)
+        }
+    }
+}
+
+fn write_scaffold_skeleton(
+    plugin_dir: &Path,
+    id: &str,
+    name: &str,
+    author: Option<&str>,
+    sdk_supported: Option<&str>,
+) -> Result<Vec<String>> {
+    let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+    // Ids that are not Python identifiers (`-`, `.`) cannot be imported as a module name
+    let module_ok = id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let entry = if module_ok { format!("{}:main", id) } else { "__init__.py:main".to_string() };
+    let mut toml = format!(
+        "[plugin]\nid = {}\nname = {}\nversion = \"0.1.0\"\nentry = {}\n",
+        quote(id),
+        quote(name),
+        quote(&entry)
+    );
+    if let Some(author) = author {
+        toml.push_str(&format!("\n[plugin.author]\nname = {}\n", quote(author)));
+    }
+    if let Some(req) = sdk_supported {
+        toml.push_str(&format!("\n[plugin.sdk]\nsupported = {}\n", quote(req)));
+    }
+    let init = format!("\"\"\"{} plugin.\"\"\"\n\n\ndef main():\n    pass\n", name);
+    let files = [
+        ("plugin.toml", toml),
+        ("__init__.py", init),
+        (PACK_IGNORE_FILE, SCAFFOLD_PACK_IGNORE.to_string()),
+    ];
+    for (rel, text) in &files {
+        let path = plugin_dir.join(rel);
+        fs::write(&path, text).with_context(|| PathContext::new("failed to write", &path))?;
+    }
+    let profiles = plugin_dir.join("profiles");
+    fs::create_dir(&profiles).with_context(|| PathContext::new("failed to create", &profiles))?;
+    let mut out: Vec<String> = files.iter().map(|(rel, _)| rel.to_string()).collect();
+    out.push("profiles/".to_string());
+    Ok(out)
+}
+
+/// Copy `template` into `plugin_dir`, replacing `{{id}}`, `{{name}}`, `{{author}}` and
+/// `{{sdk_supported}}` in file names and in files that are valid UTF-8; other files are copied as is
+fn render_scaffold_template(template: &Path, plugin_dir: &Path, values: &[&str]) -> Result<Vec<String>> {
+    if !template.join("plugin.toml").is_file() {
+        anyhow::bail!("template {} has no plugin.toml", template.display());
+    }
+    let render = |text: &str| -> String {
+        SCAFFOLD_PLACEHOLDERS
+            .iter()
+            .zip(values)
+            .fold(text.to_string(), |acc, (key, value)| acc.replace(&format!("{{{{{}}}}}", key), value))
+    };
+    let mut out = Vec::new();
+    for entry in WalkDir::new(template).min_depth(1).follow_links(false).sort_by_file_name() {
+        let entry = entry.with_context(|| PathContext::new("failed to read template", template))?;
+        let rel = entry.path().strip_prefix(template).unwrap_or(entry.path());
+        let rel = render(&rel.to_string_lossy().replace('\\', "/"));
+        if !is_safe_rel_path(&rel) {
+            anyhow::bail!("template path renders to an unsafe path: {}", rel);
+        }
+        let dest = plugin_dir.join(&rel);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest).with_context(|| PathContext::new("failed to create", &dest))?;
+            out.push(format!("{}/", rel));
+        } else if entry.file_type().is_file() {
+            let bytes = fs::read(entry.path()).with_context(|| PathContext::new("failed to read", entry.path()))?;
+            let bytes = match String::from_utf8(bytes) {
+                Ok(text) => render(&text).into_bytes(),
+                Err(e) => e.into_bytes(),
+            };
+            fs::write(&dest, bytes).with_context(|| PathContext::new("failed to write", &dest))?;
+            out.push(rel);
+        }
+    }
+    Ok(out)
+}
+
+/// How `remove` treats dependents and backups
+#[derive(Debug, Clone, Default)]
+pub struct RemoveOptions {
+    /// Remove even when other plugins depend on the targets
+    pub force: bool,
+    /// Only report what would be removed
+    pub dry_run: bool,
+    /// Copy each folder to `<dir>/<folder>_<timestamp>` before deleting it, as `unpack --backup` does
+    pub backup_dir: Option<PathBuf>,
+}
+
+/// A plugin that declares a dependency on one being removed
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginDependent {
+    pub id: String,
+    pub depends_on: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemovedPlugin {
+    pub id: String,
+    pub version: String,
+    pub folder: String,
+    pub path: PathBuf,
+    pub backup: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoveReport {
+    pub dry_run: bool,
+    /// Removed plugins, or with `dry_run` the ones that would be
+    pub removed: Vec<RemovedPlugin>,
+    /// Remaining plugins left with a missing dependency, sorted
+    pub dependents: Vec<PluginDependent>,
+}
+
+/// Delete the folders of `ids` from the repo at `root` (auto-detected when None). Other plugins
+/// depending on a target make it fail unless `options.force` is set; with `dry_run` they are
+/// only reported. Every id is resolved before anything is deleted.
+pub fn remove_plugins(root: Option<&Path>, ids: &[String], options: &RemoveOptions) -> Result<RemoveReport> {
+    let repo_root = match root {
+        Some(p) => p.to_path_buf(),
+        None => find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
+    };
+    let plugins_dir = repo_root.join("plugin").join("plugins");
+    let records = read_plugin_records(&plugins_dir, None)?;
+    let mut targets: Vec<&PluginRecord> = Vec::new();
+    for id in ids {
+        let matches: Vec<&PluginRecord> = records.iter().filter(|p| &p.id == id).collect();
+        match matches.as_slice() {
+            [] => anyhow::bail!("plugin {} not found in {}", id, plugins_dir.display()),
+            [p] => {
+                if !targets.iter().any(|t| t.folder == p.folder) {
+                    targets.push(p);
+                }
+            }
+            _ => {
+                let folders: Vec<&str> = matches.iter().map(|p| p.folder.as_str()).collect();
+                anyhow::bail!("plugin id {} is used by several folders ({}); remove the right one by hand", id, folders.join(", "));
+            }
+        }
+    }
+
+    let mut dependents: Vec<PluginDependent> = records
+        .iter()
+        .filter(|p| !targets.iter().any(|t| t.folder == p.folder))
+        .flat_map(|p| {
+            p.deps.iter().filter(|d| targets.iter().any(|t| t.id == d.id)).map(|d| PluginDependent {
+                id: p.id.clone(),
+                depends_on: d.id.clone(),
+            })
+        })
+        .collect();
+    dependents.sort_by(|a, b| (&a.id, &a.depends_on).cmp(&(&b.id, &b.depends_on)));
+    dependents.dedup_by(|a, b| a.id == b.id && a.depends_on == b.depends_on);
+    if !dependents.is_empty() && !options.force && !options.dry_run {
+        let lines: Vec<String> = dependents.iter().map(|d| format!("{} depends on {}", d.id, d.depends_on)).collect();
+        anyhow::bail!(
+            "other plugins depend on the plugins to remove (use --force to remove anyway):\n  {}",
+            lines.join("\n  ")
+        );
+    }
+
+    let excludes = build_excludes(&[])?;
+    let mut removed = Vec::new();
+    for p in targets {
+        let path = plugins_dir.join(&p.folder);
+        let mut backup = None;
+        if !options.dry_run {
+            if let Some(dir) = &options.backup_dir {
+                backup = Some(backup_plugin(&path, dir, &p.folder, &excludes)?);
+            }
+            fs::remove_dir_all(&path).with_context(|| PathContext::new("failed to remove", &path))?;
+        }
+        removed.push(RemovedPlugin {
+            id: p.id.clone(),
+            version: p.version.clone(),
+            folder: p.folder.clone(),
+            path,
+            backup,
+        });
+    }
+    Ok(RemoveReport {
+        dry_run: options.dry_run,
+        removed,
+        dependents,
+    })
+}
+
+/// Repo-level list of plugin ids taken out of rotation by `disable`, relative to the plugin directory
+pub const DISABLED_STATE_FILE: &str = ".disabled.toml";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DisabledState {
+    #[serde(default)]
+    disabled: std::collections::BTreeSet<String>,
+}
+
+/// Ids listed in `plugins_dir/.disabled.toml`; empty when the file does not exist
+pub fn read_disabled_plugins(plugins_dir: &Path) -> Result<std::collections::BTreeSet<String>> {
+    let path = plugins_dir.join(DISABLED_STATE_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => return Err(e).with_context(|| PathContext::new("failed to read", &path)),
+    };
+    let state: DisabledState = toml::from_str(&text).with_context(|| PathContext::new("failed to parse", &path))?;
+    Ok(state.disabled)
+}
+
+fn write_disabled_plugins(plugins_dir: &Path, ids: &std::collections::BTreeSet<String>) -> Result<()> {
+    let path = plugins_dir.join(DISABLED_STATE_FILE);
+    if ids.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| PathContext::new("failed to remove", &path))
+            }
+            _ => Ok(()),
+        };
+    }
+    let state = DisabledState { disabled: ids.clone() };
+    let text = format!(
+        "# Plugins taken out of rotation by `neko_plugin_cli disable`; `enable` puts them back\n{}",
+        toml::to_string(&state)?
+    );
+    fs::write(&path, text).with_context(|| PathContext::new("failed to write", &path))
+}
+
+/// Result of `enable`/`disable`
+#[derive(Debug, Clone, Serialize)]
+pub struct ToggleReport {
+    pub disabled: bool,
+    /// Ids whose state changed
+    pub changed: Vec<String>,
+    /// Ids that already were in the requested state
+    pub unchanged: Vec<String>,
+}
+
+/// Add `ids` to (`disabled`) or drop them from the repo's disabled list; plugin folders are never
+/// touched. Disabling needs an installed plugin, while an id already listed can always be enabled,
+/// so entries of plugins removed in the meantime can be cleared.
+pub fn set_plugins_disabled(root: Option<&Path>, ids: &[String], disabled: bool) -> Result<ToggleReport> {
+    let repo_root = match root {
+        Some(p) => p.to_path_buf(),
+        None => find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
+    };
+    let plugins_dir = repo_root.join("plugin").join("plugins");
+    let records = read_plugin_records(&plugins_dir, None)?;
+    let mut state = read_disabled_plugins(&plugins_dir)?;
+    for id in ids {
+        let installed = records.iter().any(|p| &p.id == id);
+        let stale = !disabled && state.contains(id);
+        if !installed && !stale {
+            anyhow::bail!("plugin {} not found in {}", id, plugins_dir.display());
+        }
+    }
+    let mut report = ToggleReport {
+        disabled,
+        changed: Vec::new(),
+        unchanged: Vec::new(),
+    };
+    for id in ids {
+        let changed = if disabled { state.insert(id.clone()) } else { state.remove(id) };
+        let list = if changed { &mut report.changed } else { &mut report.unchanged };
+        if !list.contains(id) {
+            list.push(id.clone());
+        }
+    }
+    if !report.changed.is_empty() {
+        write_disabled_plugins(&plugins_dir, &state)?;
+    }
+    Ok(report)
+}
+
+/// Drop disabled plugins from a pack selection. Plugins named in `requested` are an error instead
+/// of being dropped silently; the ids that were left out are returned.
+pub fn skip_disabled_plugins(
+    plugins_dir: &Path,
+    plugins: &mut Vec<PluginPackItem>,
+    requested: &[String],
+) -> Result<Vec<String>> {
+    let disabled = read_disabled_plugins(plugins_dir)?;
+    if let Some(id) = requested.iter().find(|id| disabled.contains(*id)) {
+        anyhow::bail!("plugin {} is disabled (enable it or pass --include-disabled)", id);
+    }
+    let mut skipped = Vec::new();
+    plugins.retain(|p| {
+        let keep = !disabled.contains(&p.id);
+        if !keep {
+            skipped.push(p.id.clone());
+        }
+        keep
+    });
+    Ok(skipped)
+}
+
+/// What `clean` removes
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    /// Everything under `<cache dir>/neko_plugin_cli`: check result cache, hash cache, python-online files
+    pub cache: bool,
+    /// Leftover `*.zip.tmp` files and `.neko_unpack_tmp` staging dirs under the repo root
+    pub tmp: bool,
+    /// Only report what would be removed
+    pub dry_run: bool,
+    /// Same override as `check --cache-dir`/`pack --cache-dir`
+    pub cache_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CleanKind {
+    Cache,
+    Tmp,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanEntry {
+    pub kind: CleanKind,
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// A candidate left alone because it resolves outside the root it was found under
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanSkipped {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanReport {
+    pub dry_run: bool,
+    /// Removed entries, or with `dry_run` the ones that would be
+    pub removed: Vec<CleanEntry>,
+    pub skipped: Vec<CleanSkipped>,
+    /// Bytes reclaimed (or reclaimable with `dry_run`)
+    pub bytes: u64,
+}
+
+/// Delete caches and temporary artifacts of the repo at `repo_root`. Every candidate is
+/// canonicalized and only removed when it stays inside the canonical cache root or repo root it
+/// was found under, so a symlink inside those never leads the deletion elsewhere.
+pub fn clean(repo_root: &Path, options: &CleanOptions) -> Result<CleanReport> {
+    if !options.cache && !options.tmp {
+        anyhow::bail!("nothing to clean: choose the cache, the temporary files or both");
+    }
+    let mut report = CleanReport {
+        dry_run: options.dry_run,
+        removed: Vec::new(),
+        skipped: Vec::new(),
+        bytes: 0,
+    };
+    if options.cache {
+        let cache_root = resolve_cache_dir(repo_root, options.cache_dir.as_deref()).join("neko_plugin_cli");
+        if cache_root.is_dir() {
+            let mut candidates = Vec::new();
+            for entry in fs::read_dir(&cache_root).with_context(|| PathContext::new("failed to read dir", &cache_root))? {
+                candidates.push(entry?.path());
+            }
+            candidates.sort();
+            clean_candidates(&cache_root, candidates, CleanKind::Cache, &mut report)?;
+        }
+    }
+    if options.tmp {
+        let mut candidates = Vec::new();
+        let mut walk = WalkDir::new(repo_root)
+            .follow_links(false)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !(e.file_type().is_dir() && e.file_name() == ".git"));
+        while let Some(entry) = walk.next() {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy();
+            if entry.file_type().is_dir() && name == UNPACK_STAGING_DIR {
+                candidates.push(entry.into_path());
+                walk.skip_current_dir();
+            } else if entry.file_type().is_file() && name.ends_with(".zip.tmp") {
+                candidates.push(entry.into_path());
+            }
+        }
+        clean_candidates(repo_root, candidates, CleanKind::Tmp, &mut report)?;
+    }
+    Ok(report)
+}
+
+fn clean_candidates(root: &Path, candidates: Vec<PathBuf>, kind: CleanKind, report: &mut CleanReport) -> Result<()> {
+    let root = fs::canonicalize(root).with_context(|| PathContext::new("failed to resolve", root))?;
+    for path in candidates {
+        let resolved = match fs::canonicalize(&path) {
+            Ok(p) => p,
+            Err(e) => {
+                report.skipped.push(CleanSkipped {
+                    path,
+                    reason: format!("cannot resolve: {}", e),
+                });
+                continue;
+            }
+        };
+        if resolved == root || !resolved.starts_with(&root) {
+            report.skipped.push(CleanSkipped {
+                path,
+                reason: format!("resolves to {} outside {}", resolved.display(), root.display()),
+            });
+            continue;
+        }
+        // A symlink that stays inside the root is unlinked, never followed
+        let meta = fs::symlink_metadata(&path).with_context(|| PathContext::new("failed to stat", &path))?;
+        let bytes = if meta.file_type().is_symlink() {
+            0
+        } else {
+            WalkDir::new(&path)
+                .follow_links(false)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter_map(|e| e.metadata().ok())
+                .map(|m| m.len())
+                .sum()
+        };
+        if !report.dry_run {
+            let removed = if meta.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            removed.with_context(|| PathContext::new("failed to remove", &path))?;
+        }
+        report.bytes += bytes;
+        report.removed.push(CleanEntry { kind, path, bytes });
+    }
+    Ok(())
+}
+
+fn scan_plugins(plugins_dir: &Path) -> Result<Vec<PluginMeta>> {
+    let mut out = Vec::new();
+    if !plugins_dir.is_dir() {
+        return Ok(out);
+    }
+
+    for entry in fs::read_dir(plugins_dir)
+        .with_context(|| format!("failed to read dir {}", plugins_dir.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let plugin_toml = path.join("plugin.toml");
+        if !plugin_toml.is_file() {
+            continue;
+        }
+
+        let txt = fs::read_to_string(&plugin_toml)
+            .with_context(|| format!("failed to read {}", plugin_toml.display()))?;
+        let val: toml::Value = toml::from_str(&txt)
+            .with_context(|| format!("failed to parse {}", plugin_toml.display()))?;
+
+        let plugin = val.get("plugin");
+        let id = plugin
+            .and_then(|v| v.get("id"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let version = plugin
+            .and_then(|v| v.get("version"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let entry_str = plugin
+            .and_then(|v| v.get("entry"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        out.push(PluginMeta {
+            id,
+            version,
+            entry: entry_str,
+            folder: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            disk_usage: None,
+            sdk_compat: None,
+            disabled: false,
+        });
+    }
+
+    let disabled = read_disabled_plugins(plugins_dir)?;
+    for p in &mut out {
+        p.disabled = disabled.contains(&p.id);
+    }
+    out.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(out)
+}
+
+pub fn scan_plugins_for_pack(
+    plugins_dir: &Path,
+    plugin_ids: Option<&[String]>,
+) -> Result<Vec<PluginPackItem>> {
+    let mut out = Vec::new();
+    if !plugins_dir.is_dir() {
+        return Ok(out);
+    }
+
+    for entry in fs::read_dir(plugins_dir)
+        .with_context(|| format!("failed to read dir {}", plugins_dir.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let folder = match path.file_name().and_then(|v| v.to_str()) {
+            Some(s) => s.to_string(),
+            None => continue,
+        };
+
+        let plugin_toml = path.join("plugin.toml");
+        if !plugin_toml.is_file() {
+            continue;
+        }
+
+        let txt = fs::read_to_string(&plugin_toml)
+            .with_context(|| format!("failed to read {}", plugin_toml.display()))?;
+        let val: toml::Value = toml::from_str(&txt)
+            .with_context(|| format!("failed to parse {}", plugin_toml.display()))?;
+        let plugin = val.get("plugin");
+
+        let id = plugin
+            .and_then(|v| v.get("id"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        if let Some(wants) = plugin_ids {
+            if !wants.is_empty() && !wants.iter().any(|w| w == &id) {
+                continue;
+            }
+        }
+
+        let name = plugin
+            .and_then(|v| v.get("name"))
+            .and_then(|v| v.as_str())
+            .unwrap_or(&id)
+            .to_string();
+        let version = plugin
+            .and_then(|v| v.get("version"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let entry_str = plugin
+            .and_then(|v| v.get("entry"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        out.push(PluginPackItem {
+            id,
+            name,
+            version,
+            entry: entry_str,
+            folder,
+            path,
+            hash: None,
+            hash_algo: HashAlgo::default(),
+            implicit: false,
+        });
+    }
+
+    out.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(out)
+}
+
+/// Plugins that `ids` (all plugins when empty) transitively depend on but did not list
+/// themselves, sorted; errors when a declared dependency is not available locally.
+pub fn resolve_pack_dependencies(plugins_dir: &Path, ids: &[String]) -> Result<Vec<String>> {
+    use std::collections::{BTreeSet, HashMap};
+    let records = read_plugin_records(plugins_dir, None)?;
+    let by_id: HashMap<&str, &PluginRecord> = records.iter().map(|r| (r.id.as_str(), r)).collect();
+
+    let mut queue: Vec<&str> = if ids.is_empty() {
+        records.iter().map(|r| r.id.as_str()).collect()
+    } else {
+        ids.iter().map(String::as_str).collect()
+    };
+    let mut seen: BTreeSet<&str> = queue.iter().copied().collect();
+    let mut added: BTreeSet<String> = BTreeSet::new();
+    while let Some(id) = queue.pop() {
+        let Some(record) = by_id.get(id) else {
+            continue;
+        };
+        for dep in &record.deps {
+            if !by_id.contains_key(dep.id.as_str()) {
+                anyhow::bail!(
+                    "plugin {} depends on {}, which is not available in {}",
+                    record.id,
+                    dep.id,
+                    plugins_dir.display()
+                );
+            }
+            if seen.insert(dep.id.as_str()) {
+                added.insert(dep.id.clone());
+                queue.push(dep.id.as_str());
+            }
+        }
+    }
+    Ok(added.into_iter().collect())
+}
+
+/// scan_plugins_for_pack plus the dependency closure of the selection (`pack --with-deps`)
+pub fn scan_plugins_for_pack_with_deps(plugins_dir: &Path, plugin_ids: &[String]) -> Result<Vec<PluginPackItem>> {
+    let added = resolve_pack_dependencies(plugins_dir, plugin_ids)?;
+    if plugin_ids.is_empty() {
+        return scan_plugins_for_pack(plugins_dir, None);
+    }
+    let wanted: Vec<String> = plugin_ids.iter().chain(&added).cloned().collect();
+    let mut plugins = scan_plugins_for_pack(plugins_dir, Some(&wanted))?;
+    for p in &mut plugins {
+        p.implicit = added.contains(&p.id);
+    }
+    Ok(plugins)
+}
+
+/// Declarative pack settings (`pack --spec bundle.toml`)
+#[derive(Debug, Default, Deserialize)]
+pub struct BundleSpec {
+    #[serde(default)]
+    pub bundle: BundleSpecMeta,
+    /// Plugin ids or id globs; empty means all plugins
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Repo-level files to add under extras/, as `<path>[:zip_path]`
+    #[serde(default)]
+    pub include_root: Vec<String>,
+    pub exclude_profile: Option<ExcludeProfile>,
+    pub hash: Option<HashAlgo>,
+    pub compression: Option<CompressionKind>,
+    pub compression_level: Option<i64>,
+    pub manifest_version: Option<u32>,
+    /// Output path template with {name}, {version} and {date}; relative to the spec file
+    pub out: Option<String>,
+    /// Directory of the spec file
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+    /// Keys the spec sets that this build does not know
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BundleSpecMeta {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+}
+
+const SPEC_KEYS: &[&str] = &[
+    "bundle",
+    "plugins",
+    "exclude",
+    "include_root",
+    "exclude_profile",
+    "hash",
+    "compression",
+    "compression_level",
+    "manifest_version",
+    "out",
+];
+const SPEC_BUNDLE_KEYS: &[&str] = &["name", "version", "author"];
+
+/// Explicit pack arguments. Anything left empty falls back to the bundle spec, if one is given,
+/// then to the repo's bundle defaults (bundle metadata only) and then to the usual defaults.
+#[derive(Debug, Default)]
+pub struct PackOverrides {
+    pub plugin_ids: Vec<String>,
+    pub excludes: Vec<String>,
+    pub include_root: Vec<String>,
+    pub bundle: BundleMeta,
+    pub exclude_profile: Option<ExcludeProfile>,
+    pub hash: Option<HashAlgo>,
+    pub compression: Option<CompressionKind>,
+    pub compression_level: Option<i64>,
+    pub manifest_version: Option<u32>,
+    pub out: Option<PathBuf>,
+}
+
+pub fn load_bundle_spec(path: &Path) -> Result<BundleSpec> {
+    let text = fs::read_to_string(path).with_context(|| PathContext::new("failed to read", path))?;
+    let mut spec: BundleSpec = toml::from_str(&text).with_context(|| PathContext::new("invalid bundle spec", path))?;
+
+    let table: toml::Table = toml::from_str(&text).with_context(|| PathContext::new("invalid bundle spec", path))?;
+    for (key, value) in &table {
+        if !SPEC_KEYS.contains(&key.as_str()) {
+            spec.warnings.push(format!("unknown key `{}` in {} (ignored)", key, path.display()));
+        }
+        if let (Some(bundle), "bundle") = (value.as_table(), key.as_str()) {
+            for key in bundle.keys().filter(|k| !SPEC_BUNDLE_KEYS.contains(&k.as_str())) {
+                spec.warnings.push(format!("unknown key `bundle.{}` in {} (ignored)", key, path.display()));
+            }
+        }
+    }
+    spec.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    Ok(spec)
+}
+
+impl BundleSpec {
+    /// Fill whatever `args` leaves open from the spec; `plugins` globs are resolved against `plugins_dir`.
+    /// Bundle metadata the spec does not set either comes from `repo_defaults`.
+    pub fn apply(
+        &self,
+        plugins_dir: &Path,
+        args: PackOverrides,
+        repo_defaults: &BundleMeta,
+        source_date_epoch: Option<i64>,
+    ) -> Result<PackOverrides> {
+        let plugin_ids = if args.plugin_ids.is_empty() {
+            resolve_spec_plugins(plugins_dir, &self.plugins)?
+        } else {
+            args.plugin_ids
+        };
+        let spec_bundle = BundleMeta {
+            name: self.bundle.name.clone(),
+            version: self.bundle.version.clone(),
+            author: self.bundle.author.clone(),
+        };
+        let bundle = args.bundle.or(&spec_bundle.or(repo_defaults));
+        let out = match (args.out, &self.out) {
+            (Some(out), _) => Some(out),
+            (None, Some(template)) => Some(self.base_dir.join(render_out_template(template, &bundle, source_date_epoch)?)),
+            (None, None) => None,
+        };
+        // A level from the spec belongs to the spec's method, so it is dropped when the method is overridden
+        let compression_level = match args.compression {
+            Some(_) => args.compression_level,
+            None => args.compression_level.or(self.compression_level),
+        };
+        Ok(PackOverrides {
+            plugin_ids,
+            excludes: self.exclude.iter().chain(&args.excludes).cloned().collect(),
+            include_root: self.include_root.iter().chain(&args.include_root).cloned().collect(),
+            bundle,
+            exclude_profile: args.exclude_profile.or(self.exclude_profile),
+            hash: args.hash.or(self.hash),
+            compression: args.compression.or(self.compression),
+            compression_level,
+            manifest_version: args.manifest_version.or(self.manifest_version),
+            out,
+        })
+    }
+}
+
+/// Expand the spec's plugin ids and id globs, keeping their order; each entry must match something
+fn resolve_spec_plugins(plugins_dir: &Path, patterns: &[String]) -> Result<Vec<String>> {
+    let available = list_packable_plugin_ids(plugins_dir)?;
+    let mut out: Vec<String> = Vec::new();
+    for pattern in patterns {
+        let matcher = Glob::new(pattern)
+            .with_context(|| format!("invalid `plugins` entry {}", pattern))?
+            .compile_matcher();
+        let matched: Vec<&String> = available.iter().filter(|id| matcher.is_match(id.as_str())).collect();
+        if matched.is_empty() {
+            anyhow::bail!("`plugins` entry {} matches no plugin in {}", pattern, plugins_dir.display());
+        }
+        for id in matched {
+            if !out.contains(id) {
+                out.push(id.clone());
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn render_out_template(template: &str, bundle: &BundleMeta, source_date_epoch: Option<i64>) -> Result<PathBuf> {
+    let re = Regex::new(r"\{([^{}]*)\}")?;
+    if let Some(c) = re
+        .captures_iter(template)
+        .find(|c| !matches!(&c[1], "name" | "version" | "date"))
+    {
+        anyhow::bail!(
+            "unknown placeholder {{{}}} in `out` (expected {{name}}, {{version}} or {{date}})",
+            &c[1]
+        );
+    }
+    let date = match source_date_epoch {
+        Some(epoch) => DateTime::<Utc>::from_timestamp(epoch, 0).with_context(|| format!("timestamp out of range: {}", epoch))?,
+        None => Utc::now(),
+    }
+    .format("%Y-%m-%d")
+    .to_string();
+    let rendered = re.replace_all(template, |c: &regex::Captures<'_>| match &c[1] {
+        "name" => sanitize_for_filename(bundle.name.as_deref().unwrap_or("bundle")),
+        "version" => sanitize_for_filename(bundle.version.as_deref().unwrap_or("unknown")),
+        _ => date.clone(),
+    });
+    Ok(PathBuf::from(rendered.into_owned()))
+}
+
+pub fn default_pack_output(plugins: &[PluginPackItem], single: bool) -> PathBuf {
+    if single {
+        let p = plugins.iter().find(|p| !p.implicit).unwrap_or(&plugins[0]);
+        return PathBuf::from(format!("neko_plugin_{}_{}.zip", p.id, p.version));
+    }
+    let ts = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    PathBuf::from(format!("neko_plugins_bundle_{}.zip", ts.replace(':', "-")))
+}
+
+/// Outcome for one plugin of `pack --split`
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum SplitOutput {
+    Packed { path: PathBuf, size: u64 },
+    Failed { error: String },
+}
+
+/// Run `pack_one` once per plugin, each writing `<out_dir>/neko_plugin_<id>_<version>.zip` from a
+/// one-plugin slice it may hash in place. A failure is recorded and the rest still run, unless
+/// `fail_fast` is set.
+pub fn pack_split<F>(
+    out_dir: &Path,
+    plugins: &[PluginPackItem],
+    fail_fast: bool,
+    mut pack_one: F,
+) -> Result<std::collections::BTreeMap<String, SplitOutput>>
+where
+    F: FnMut(&mut [PluginPackItem], &Path) -> Result<()>,
+{
+    if out_dir.exists() && !out_dir.is_dir() {
+        anyhow::bail!("--split output must be a directory: {}", out_dir.display());
+    }
+    fs::create_dir_all(out_dir).with_context(|| PathContext::new("failed to create", out_dir))?;
+
+    let mut outputs = std::collections::BTreeMap::new();
+    for p in plugins {
+        let mut one = [p.clone()];
+        let path = out_dir.join(default_pack_output(&one, true));
+        let packed = pack_one(&mut one, &path).and_then(|()| {
+            let size = fs::metadata(&path)
+                .with_context(|| PathContext::new("failed to stat", &path))?
+                .len();
+            Ok(SplitOutput::Packed { path, size })
+        });
+        let output = match packed {
+            Ok(output) => output,
+            Err(e) if fail_fast => return Err(e.context(format!("failed to pack plugin {}", p.id))),
+            Err(e) => SplitOutput::Failed {
+                error: format!("{:#}", e),
+            },
+        };
+        outputs.insert(p.id.clone(), output);
+    }
+    Ok(outputs)
+}
+
+pub fn list_packable_plugin_ids(plugins_dir: &Path) -> Result<Vec<String>> {
+    let plugins = scan_plugins_for_pack(plugins_dir, None)?;
+    Ok(plugins.into_iter().map(|p| p.id).collect())
+}
+
+/// Built-in exclusion sets for `pack --exclude-profile`; each one extends the previous
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExcludeProfile {
+    /// Bytecode, VCS metadata, virtualenvs and logs; what every bundle has always left out
+    #[default]
+    Minimal,
+    /// Also tool caches and node_modules; keeps tests/ and docs/
+    Dev,
+    /// Also tests, docs, markdown files and example data
+    Release,
+}
+
+impl ExcludeProfile {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExcludeProfile::Minimal => "minimal",
+            ExcludeProfile::Dev => "dev",
+            ExcludeProfile::Release => "release",
+        }
+    }
+
+    pub fn patterns(self) -> Vec<&'static str> {
+        let mut out = vec![
+            "**/__pycache__/**",
+            "**/*.pyc",
+            "**/.git/**",
+            "**/.venv/**",
+            "**/log/**",
+            "**/logs/**",
+        ];
+        if self == ExcludeProfile::Minimal {
+            return out;
+        }
+        out.extend([
+            "**/.pytest_cache/**",
+            "**/.mypy_cache/**",
+            "**/.ruff_cache/**",
+            "**/node_modules/**",
+        ]);
+        if self == ExcludeProfile::Dev {
+            return out;
+        }
+        out.extend([
+            "**/tests/**",
+            "**/test/**",
+            "**/docs/**",
+            "**/*.md",
+            "**/examples/**",
+            "**/example_data/**",
+        ]);
+        out
+    }
+}
+
+impl std::str::FromStr for ExcludeProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "minimal" => Ok(ExcludeProfile::Minimal),
+            "dev" => Ok(ExcludeProfile::Dev),
+            "release" => Ok(ExcludeProfile::Release),
+            other => anyhow::bail!("unknown exclude profile: {} (expected minimal, dev or release)", other),
+        }
+    }
+}
+
+/// The default (minimal) exclusions plus `extra`
+pub fn build_excludes(extra: &[String]) -> Result<Excludes> {
+    build_excludes_for(ExcludeProfile::default(), extra)
+}
+
+pub fn build_excludes_for(profile: ExcludeProfile, extra: &[String]) -> Result<Excludes> {
+    let mut b = GlobSetBuilder::new();
+    for pat in profile.patterns() {
+        b.add(Glob::new(pat)?);
+    }
+    for pat in extra {
+        b.add(Glob::new(pat)?);
+    }
+    Ok(Excludes {
+        globs: b.build()?,
+        patterns: profile.patterns().into_iter().map(String::from).chain(extra.iter().cloned()).collect(),
+        gitignore_root: None,
+        symlinks: SymlinkPolicy::Skip,
+        hash_cache: None,
+    })
+}
+
+/// File selection rules shared by folder hashing and pack file collection, so both always see the same files
+#[derive(Debug, Clone)]
+pub struct Excludes {
+    globs: GlobSet,
+    /// The glob sources of `globs`, which key the hash cache
+    patterns: Vec<String>,
+    /// Repo root for `pack --respect-gitignore`: `.gitignore` files from here down also apply
+    gitignore_root: Option<PathBuf>,
+    symlinks: SymlinkPolicy,
+    hash_cache: Option<Arc<HashCache>>,
+}
+
+impl Excludes {
+    /// How symlinks in plugin folders are treated (`pack --symlinks`); links are skipped by default
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Also skip what the `.gitignore` files of `repo_root`, the directories down to each plugin
+    /// folder and the plugin folder itself ignore (negated patterns included)
+    pub fn respect_gitignore(mut self, repo_root: &Path) -> Self {
+        self.gitignore_root = Some(repo_root.to_path_buf());
+        self
+    }
+
+    /// Reuse digests of unchanged files from the on-disk cache under `cache_dir` (or the per-user
+    /// cache dir). Call after the other builders: each set of file selection rules gets its own cache.
+    pub fn hash_cache(mut self, repo_root: &Path, cache_dir: Option<&Path>) -> Self {
+        let mut key = Sha256::new();
+        for pat in &self.patterns {
+            key.update(pat.as_bytes());
+            key.update([0u8]);
+        }
+        key.update(format!("gitignore={}\0symlinks={:?}", self.gitignore_root.is_some(), self.symlinks));
+        let name = format!("{:x}", key.finalize());
+        let path = resolve_cache_dir(repo_root, cache_dir)
+            .join("neko_plugin_cli")
+            .join("hash_cache")
+            .join(format!("{}.json", &name[..16]));
+        self.hash_cache = Some(Arc::new(HashCache::open(path)));
+        self
+    }
+
+    /// What the hash cache reused and read so far; None without `hash_cache`
+    pub fn hash_cache_stats(&self) -> Option<HashCacheStats> {
+        self.hash_cache.as_ref().map(|c| c.stats())
+    }
+
+    pub fn is_match(&self, rel: &str) -> bool {
+        self.globs.is_match(rel)
+    }
+
+    /// The glob sources that match `rel`
+    fn matching_patterns(&self, rel: &str) -> Vec<&str> {
+        self.globs.matches(rel).into_iter().map(|i| self.patterns[i].as_str()).collect()
+    }
+
+    fn gitignore_for(&self, plugin_dir: &Path) -> Option<GitignoreStack> {
+        let root = self.gitignore_root.as_deref()?;
+        let root = if plugin_dir.starts_with(root) { root } else { plugin_dir };
+        Some(GitignoreStack {
+            root: root.to_path_buf(),
+            loaded: std::collections::HashMap::new(),
+        })
+    }
+}
+
+/// `.gitignore` matchers per directory, read on first use
+struct GitignoreStack {
+    root: PathBuf,
+    loaded: std::collections::HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl GitignoreStack {
+    /// Like git, the deepest `.gitignore` with a matching pattern decides; callers prune ignored
+    /// directories, so nothing below them can be re-included
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> Result<bool> {
+        for dir in path.ancestors().skip(1).take_while(|d| d.starts_with(&self.root)) {
+            if !self.loaded.contains_key(dir) {
+                let gitignore = load_gitignore(dir)?;
+                self.loaded.insert(dir.to_path_buf(), gitignore);
+            }
+            match self.loaded[dir].as_ref().map(|g| g.matched(path, is_dir)) {
+                Some(Match::Ignore(_)) => return Ok(true),
+                Some(Match::Whitelist(_)) => return Ok(false),
+                _ => {}
+            }
+        }
+        Ok(false)
+    }
+}
+
+fn load_gitignore(dir: &Path) -> Result<Option<Gitignore>> {
+    let path = dir.join(".gitignore");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&path) {
+        return Err(e).with_context(|| PathContext::new("invalid ignore file", &path));
+    }
+    let gitignore = builder.build().with_context(|| PathContext::new("invalid ignore file", &path))?;
+    Ok(Some(gitignore))
+}
+
+/// Per-plugin ignore file, read from the plugin folder root
+pub const PACK_IGNORE_FILE: &str = ".nekopackignore";
+
+/// Parse a `.nekopackignore` (gitignore-style, without negation) into globs relative to the plugin folder.
+fn parse_pack_ignore(text: &str) -> Result<GlobSet> {
+    let mut b = GlobSetBuilder::new();
+    for (idx, raw) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = raw.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('!') {
+            anyhow::bail!("line {}: negation patterns are not supported: {}", line_no, line);
+        }
+        // "\#" and "\!" escape a literal leading '#' or '!'
+        let line = line.strip_prefix('\\').unwrap_or(line);
+        let (line, dir_only) = match line.strip_suffix('/') {
+            Some(l) => (l, true),
+            None => (line, false),
+        };
+        // Like gitignore: a pattern with a slash (other than a trailing one) is anchored to the folder root
+        let pattern = match line.strip_prefix('/') {
+            Some(l) => l.to_string(),
+            None if line.contains('/') => line.to_string(),
+            None => format!("**/{}", line),
+        };
+        let mut globs = vec![format!("{}/**", pattern)];
+        if !dir_only {
+            globs.push(pattern);
+        }
+        for g in globs {
+            let glob = globset::GlobBuilder::new(&g)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("line {}: invalid pattern {}", line_no, line))?;
+            b.add(glob);
+        }
+    }
+    Ok(b.build()?)
+}
+
+fn load_pack_ignore(plugin_dir: &Path) -> Result<Option<GlobSet>> {
+    let path = plugin_dir.join(PACK_IGNORE_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| PathContext::new("failed to read", &path)),
+    };
+    parse_pack_ignore(&text)
+        .map(Some)
+        .with_context(|| PathContext::new("invalid ignore file", &path))
+}
+
+/// Files under a plugin folder as (relative path with '/', absolute path), sorted by relative path.
+/// Honors `excludes` plus the folder's own `.nekopackignore`.
+fn list_plugin_files(plugin_dir: &Path, excludes: &Excludes) -> Result<Vec<(String, PathBuf)>> {
+    let local_ignore = load_pack_ignore(plugin_dir)?;
+    let mut gitignore = excludes.gitignore_for(plugin_dir);
+    let follow = excludes.symlinks == SymlinkPolicy::Follow;
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    let mut links: Vec<String> = Vec::new();
+    let mut walk = WalkDir::new(plugin_dir).follow_links(follow).into_iter();
+    while let Some(e) = walk.next() {
+        let e = e?;
+        if let Some(stack) = gitignore.as_mut().filter(|_| e.depth() > 0) {
+            let is_dir = e.file_type().is_dir();
+            if stack.is_ignored(e.path(), is_dir)? {
+                if is_dir {
+                    walk.skip_current_dir();
+                }
+                continue;
+            }
+        }
+        let is_link = e.path_is_symlink() && !follow;
+        if !is_link && !e.file_type().is_file() {
+            continue;
+        }
+        let rel = e
+            .path()
+            .strip_prefix(plugin_dir)
+            .unwrap_or(e.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+        if excludes.is_match(&rel) || local_ignore.as_ref().is_some_and(|g| g.is_match(&rel)) || rel == UNPACK_EXCLUDES_MARKER || rel == UNPACK_PROVENANCE_FILE {
+            continue;
+        }
+        if is_link {
+            links.push(rel);
+            continue;
+        }
+        files.push((rel, e.path().to_path_buf()));
+    }
+    if excludes.symlinks == SymlinkPolicy::Error && !links.is_empty() {
+        links.sort();
+        anyhow::bail!(
+            "{} contains symlinks (use --symlinks skip or --symlinks follow): {}",
+            plugin_dir.display(),
+            links.join(", ")
+        );
+    }
+    // Order by the native relative path, as folder hashes of existing bundles were computed that way
+    files.sort_by(|a, b| {
+        a.1.strip_prefix(plugin_dir)
+            .unwrap_or(&a.1)
+            .to_string_lossy()
+            .cmp(&b.1.strip_prefix(plugin_dir).unwrap_or(&b.1).to_string_lossy())
+    });
+    Ok(files)
+}
+
+pub fn folder_hash(plugin_dir: &Path, excludes: &Excludes, algo: HashAlgo) -> Result<String> {
+    let files = list_plugin_files(plugin_dir, excludes)?;
+    match &excludes.hash_cache {
+        Some(cache) => cache.folder_hash(plugin_dir, &files, algo),
+        None => stream_folder_hash(&files, algo, |_, _| {}),
+    }
+}
+
+/// Hash `rel \0 content \0` of every file as one stream; `on_file` also gets each file's sha256
+fn stream_folder_hash(
+    files: &[(String, PathBuf)],
+    algo: HashAlgo,
+    mut on_file: impl FnMut(usize, Option<String>),
+) -> Result<String> {
+    let mut hasher = FolderHasher::new(algo);
+    for (i, (rel, p)) in files.iter().enumerate() {
+        hasher.update(rel.as_bytes());
+        hasher.update(&[0u8]);
+
+        let mut f = fs::File::open(p).with_context(|| format!("failed to open {}", p.display()))?;
+        let mut file_hasher = Sha256::new();
+        let mut buf = [0u8; 1024 * 64];
+        loop {
+            let n = f.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            file_hasher.update(&buf[..n]);
+        }
+        hasher.update(&[0u8]);
+        on_file(i, Some(format!("{:x}", file_hasher.finalize())));
+    }
+
+    Ok(hasher.finish_hex())
+}
+
+const HASH_CACHE_VERSION: u32 = 1;
+
+/// Files modified this recently are not cached: a second write within the same mtime tick keeps
+/// size and mtime, so the cache could not tell it apart
+const HASH_CACHE_RACY_SECS: u64 = 2;
+
+/// Size and mtime a cached digest was computed for; any difference means re-hashing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileStamp {
+    size: u64,
+    mtime_ns: u64,
+}
+
+impl FileStamp {
+    /// None when the file is too fresh (or its mtime unusable) to be cached
+    fn of(path: &Path) -> Result<Option<FileStamp>> {
+        let meta = fs::metadata(path).with_context(|| PathContext::new("failed to stat", path))?;
+        let Some(mtime) = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()) else {
+            return Ok(None);
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        if mtime + Duration::from_secs(HASH_CACHE_RACY_SECS) > now {
+            return Ok(None);
+        }
+        Ok(u64::try_from(mtime.as_nanos()).ok().map(|mtime_ns| FileStamp {
+            size: meta.len(),
+            mtime_ns,
+        }))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFile {
+    #[serde(flatten)]
+    stamp: FileStamp,
+    sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFolder {
+    files: Vec<(String, FileStamp)>,
+    hash: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HashCacheData {
+    version: u32,
+    files: std::collections::BTreeMap<PathBuf, CachedFile>,
+    /// Keyed by "{algo}:{folder}"
+    folders: std::collections::BTreeMap<String, CachedFolder>,
+}
+
+/// What the hash cache reused and which files it had to read
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HashCacheStats {
+    /// Digests (per file or per folder) taken from the cache
+    pub reused: usize,
+    /// Files read to compute a digest
+    pub hashed: std::collections::BTreeSet<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct HashCacheState {
+    data: HashCacheData,
+    stats: HashCacheStats,
+    dirty: bool,
+}
+
+/// Digests keyed by (path, size, mtime), saved as JSON when dropped (`pack --no-hash-cache` skips it).
+/// Per-file sha256 digests are reused file by file. A folder hash is a single stream over all of a
+/// plugin's files, so it is reused only while none of them changed, otherwise the folder is re-read.
+#[derive(Debug)]
+pub struct HashCache {
+    path: PathBuf,
+    state: Mutex<HashCacheState>,
+}
+
+impl HashCache {
+    /// A missing, unreadable or outdated cache file simply starts an empty cache
+    fn open(path: PathBuf) -> HashCache {
+        let data = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<HashCacheData>(&bytes).ok())
+            .filter(|d| d.version == HASH_CACHE_VERSION)
+            .unwrap_or_else(|| HashCacheData {
+                version: HASH_CACHE_VERSION,
+                ..Default::default()
+            });
+        HashCache {
+            path,
+            state: Mutex::new(HashCacheState {
+                data,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashCacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn stats(&self) -> HashCacheStats {
+        self.lock().stats.clone()
+    }
+
+    fn file_sha256(&self, path: &Path) -> Result<String> {
+        let key = std::path::absolute(path).with_context(|| PathContext::new("failed to resolve", path))?;
+        let stamp = FileStamp::of(path)?;
+        if let Some(stamp) = &stamp {
+            let mut state = self.lock();
+            if let Some(hit) = state.data.files.get(&key).filter(|c| &c.stamp == stamp) {
+                let sha256 = hit.sha256.clone();
+                state.stats.reused += 1;
+                return Ok(sha256);
+            }
+        }
+        let sha256 = file_sha256(path)?;
+        let mut state = self.lock();
+        state.stats.hashed.insert(path.to_path_buf());
+        if let Some(stamp) = stamp {
+            let entry = CachedFile {
+                stamp,
+                sha256: sha256.clone(),
+            };
+            state.data.files.insert(key, entry);
+            state.dirty = true;
+        }
+        Ok(sha256)
+    }
+
+    fn folder_hash(&self, plugin_dir: &Path, files: &[(String, PathBuf)], algo: HashAlgo) -> Result<String> {
+        let dir = std::path::absolute(plugin_dir).with_context(|| PathContext::new("failed to resolve", plugin_dir))?;
+        let key = format!("{}:{}", algo.as_str(), dir.display());
+        let stamps = files
+            .iter()
+            .map(|(_, p)| FileStamp::of(p))
+            .collect::<Result<Vec<_>>>()?;
+        let listed: Option<Vec<(String, FileStamp)>> = files
+            .iter()
+            .zip(&stamps)
+            .map(|((rel, _), stamp)| stamp.clone().map(|s| (rel.clone(), s)))
+            .collect();
+        if let Some(listed) = &listed {
+            let mut state = self.lock();
+            if let Some(hit) = state.data.folders.get(&key).filter(|c| &c.files == listed) {
+                let hash = hit.hash.clone();
+                state.stats.reused += 1;
+                return Ok(hash);
+            }
+        }
+
+        let mut digests = vec![None; files.len()];
+        let hash = stream_folder_hash(files, algo, |i, sha256| digests[i] = sha256)?;
+        let mut state = self.lock();
+        for (((_, p), stamp), sha256) in files.iter().zip(stamps).zip(digests) {
+            state.stats.hashed.insert(p.clone());
+            if let (Some(stamp), Some(sha256), Ok(path)) = (stamp, sha256, std::path::absolute(p)) {
+                state.data.files.insert(path, CachedFile { stamp, sha256 });
+            }
+        }
+        if let Some(files) = listed {
+            state.data.folders.insert(key, CachedFolder { files, hash: hash.clone() });
+        }
+        state.dirty = true;
+        Ok(hash)
+    }
+
+    /// Entries of deleted files and folders are dropped; the file is replaced atomically
+    fn save(&self) -> Result<()> {
+        let mut state = self.lock();
+        if !state.dirty {
+            return Ok(());
+        }
+        state.data.files.retain(|path, _| path.exists());
+        state
+            .data
+            .folders
+            .retain(|key, _| key.split_once(':').is_some_and(|(_, dir)| Path::new(dir).is_dir()));
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir).with_context(|| PathContext::new("failed to create cache dir", dir))?;
+        let tmp = self.path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp, serde_json::to_vec(&state.data)?)
+            .with_context(|| PathContext::new("failed to write", &tmp))?;
+        fs::rename(&tmp, &self.path).with_context(|| PathContext::new("failed to write", &self.path))?;
+        state.dirty = false;
+        Ok(())
+    }
+}
+
+impl Drop for HashCache {
+    // Best effort: a cache that cannot be written only costs speed next time
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
+}
+
+/// sha256 of a file, through the hash cache when there is one
+fn cached_file_sha256(cache: Option<&HashCache>, path: &Path) -> Result<String> {
+    match cache {
+        Some(cache) => cache.file_sha256(path),
+        None => file_sha256(path),
+    }
+}
+
+/// Progress events from compute_plugin_hash_for_pack and pack_to_zip
+#[derive(Debug, Clone, Copy)]
+pub enum PackProgress<'a> {
+    /// A plugin folder finished hashing (hashing runs in parallel, so order varies)
+    Hashed { plugin: &'a str, done: usize, total: usize },
+    /// Writing is about to start; totals cover payload and bundled profile files
+    Start { plugins: usize, files: usize, bytes: u64 },
+    PluginStart { plugin: &'a str, files: usize, bytes: u64 },
+    FileWritten { plugin: &'a str, name: &'a str, bytes: u64 },
+    PluginDone { plugin: &'a str },
+}
+
+pub type ProgressFn<'a> = &'a (dyn Fn(PackProgress<'_>) + Sync);
+
+/// One file pack_to_zip copies into the bundle
+#[derive(Debug, Serialize, Clone)]
+pub struct PackEntry {
+    pub zip_path: String,
+    /// Path relative to the plugin folder
+    pub rel: String,
+    #[serde(skip)]
+    pub src: PathBuf,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PlannedPlugin {
+    pub id: String,
+    pub folder: String,
+    pub files: Vec<PackEntry>,
+    pub profiles: Vec<PackEntry>,
+}
+
+/// Everything pack_to_zip would write, computed without creating the output file
+#[derive(Debug, Serialize)]
+pub struct PackPlan {
+    pub manifest: Manifest,
+    pub plugins: Vec<PlannedPlugin>,
+    pub extras: Vec<PackEntry>,
+    #[serde(skip)]
+    hash_cache: Option<Arc<HashCache>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackDryRun {
+    pub out_path: PathBuf,
+    pub plugins: Vec<PlannedPluginSummary>,
+    pub total_files: usize,
+    pub total_bytes: u64,
+    pub largest_files: Vec<PackEntry>,
+    pub extras: Vec<PackEntry>,
+    pub manifest: Manifest,
+    /// --max-size/--warn-size checked against the uncompressed total
+    pub budget_warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlannedPluginSummary {
+    pub id: String,
+    pub folder: String,
+    pub file_count: usize,
+    pub bytes: u64,
+    pub files: Vec<PackEntry>,
+}
+
+impl PackPlan {
+    /// The manifest pack_to_zip would write for this plan, checksums included, without creating
+    /// the zip (`pack --list-only`). Folder hashes must already be on the plugins passed to plan_pack.
+    pub fn into_manifest(self) -> Result<Manifest> {
+        let mut manifest = self.manifest;
+        for entry in &self.extras {
+            manifest.extras.push(ManifestFile {
+                path: entry.rel.clone(),
+                size: entry.size,
+                sha256: cached_file_sha256(self.hash_cache.as_deref(), &entry.src)?,
+            });
+        }
+        if manifest.format_version >= 2 {
+            for (plugin, manifest_plugin) in self.plugins.iter().zip(manifest.plugins.iter_mut()) {
+                let files = plugin
+                    .files
+                    .par_iter()
+                    .map(|e| {
+                        Ok(ManifestFile {
+                            path: e.rel.clone(),
+                            size: e.size,
+                            sha256: cached_file_sha256(self.hash_cache.as_deref(), &e.src)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                manifest_plugin.files = Some(files);
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn into_dry_run(self, out_path: &Path, largest: usize) -> PackDryRun {
+        let mut all: Vec<PackEntry> = Vec::new();
+        let plugins = self
+            .plugins
+            .into_iter()
+            .map(|p| {
+                let files: Vec<PackEntry> = p.files.into_iter().chain(p.profiles).collect();
+                all.extend(files.iter().cloned());
+                PlannedPluginSummary {
+                    id: p.id,
+                    folder: p.folder,
+                    file_count: files.len(),
+                    bytes: files.iter().map(|f| f.size).sum(),
+                    files,
+                }
+            })
+            .collect();
+        all.extend(self.extras.iter().cloned());
+        let total_files = all.len();
+        let total_bytes = all.iter().map(|f| f.size).sum();
+        all.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.zip_path.cmp(&b.zip_path)));
+        all.truncate(largest);
+        PackDryRun {
+            out_path: out_path.to_path_buf(),
+            plugins,
+            total_files,
+            total_bytes,
+            largest_files: all,
+            extras: self.extras,
+            manifest: self.manifest,
+            budget_warnings: Vec::new(),
+        }
+    }
+}
+
+impl PackDryRun {
+    /// Evaluate the size budgets against uncompressed sizes. Compression usually shrinks the bundle,
+    /// so these are estimates: the real pack may still fit.
+    pub fn apply_size_budget(&mut self, max_size: Option<u64>, warn_size: Option<u64>) {
+        let sizes: Vec<PluginSize> = self
+            .plugins
+            .iter()
+            .map(|p| PluginSize {
+                id: p.id.clone(),
+                bytes: p.bytes,
+            })
+            .collect();
+        for (flag, limit) in [("--max-size", max_size), ("--warn-size", warn_size)] {
+            if let Some(limit) = limit.filter(|l| self.total_bytes > *l) {
+                self.budget_warnings.push(format!(
+                    "estimate: uncompressed size {} bytes ({}) exceeds {} {} bytes; the compressed bundle may still fit; by plugin: {}",
+                    self.total_bytes,
+                    human_bytes(self.total_bytes),
+                    flag,
+                    limit,
+                    format_plugin_sizes(&sizes)
+                ));
+            }
+        }
+    }
+}
+
+/// Archive prefix for repo-level files added with `pack --include-root`
+pub const EXTRAS_PREFIX: &str = "extras/";
+
+/// A repo-level file (LICENSE, release notes, ...) to add to the bundle under extras/
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtraFile {
+    pub src: PathBuf,
+    /// Path inside extras/
+    pub zip_path: String,
+}
+
+/// `extras/` paths are plain relative paths; anything that could escape the prefix (or the
+/// unpack destination) is rejected
+fn is_valid_extra_path(path: &str) -> bool {
+    !path.is_empty() && !path.contains('\\') && path.split('/').all(|c| !c.is_empty() && c != "." && c != "..")
+}
+
+/// Parse `<path>[:zip_path]` entries of --include-root (or the spec's `include_root`).
+/// Paths are relative to the repo root; zip_path defaults to the file name.
+pub fn resolve_extras(repo_root: &Path, entries: &[String]) -> Result<Vec<ExtraFile>> {
+    let mut extras: Vec<ExtraFile> = Vec::new();
+    for entry in entries {
+        let (path, zip_path) = match entry.split_once(':') {
+            Some((path, zip_path)) => (path, zip_path.to_string()),
+            None => {
+                let name = Path::new(entry).file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                (entry.as_str(), name.to_string())
+            }
+        };
+        if !is_valid_extra_path(&zip_path) {
+            anyhow::bail!(
+                "include-root entry `{}`: zip path `{}` must be a relative path without `.` or `..` components",
+                entry,
+                zip_path
+            );
+        }
+        let src = repo_root.join(path);
+        if !src.is_file() {
+            anyhow::bail!("include-root entry `{}`: {} is not a file", entry, src.display());
+        }
+        if let Some(prev) = extras.iter().find(|e| e.zip_path == zip_path) {
+            anyhow::bail!(
+                "include-root entry `{}`: extras/{} is already taken by {}",
+                entry,
+                zip_path,
+                prev.src.display()
+            );
+        }
+        extras.push(ExtraFile { src, zip_path });
+    }
+    Ok(extras)
+}
+
+/// Work out the manifest and file list for a bundle; shared by pack_to_zip and `pack --dry-run`
+/// so the two cannot disagree. Per-file checksums (manifest v2) are only filled in while writing.
+#[allow(clippy::too_many_arguments)]
+pub fn plan_pack(
+    out_path: &Path,
+    plugins: &[PluginPackItem],
+    excludes: &Excludes,
+    extras: &[ExtraFile],
+    bundle_meta: BundleMeta,
+    manifest_version: u32,
+    compression: PackCompression,
+    max_name_len: usize,
+    source_date_epoch: Option<i64>,
+) -> Result<PackPlan> {
+    if !(1..=MANIFEST_VERSION_MAX).contains(&manifest_version) {
+        anyhow::bail!("unsupported manifest version {} (expected 1..={})", manifest_version, MANIFEST_VERSION_MAX);
+    }
+    compression.file_options()?;
+    let packed_at = match source_date_epoch {
+        Some(epoch) => DateTime::<Utc>::from_timestamp(epoch, 0)
+            .with_context(|| format!("timestamp out of range: {}", epoch))?,
+        None => Utc::now(),
+    };
+
+    let repo_root = plugins
+        .first()
+        .and_then(|p| p.path.parent())
+        .and_then(|p| p.parent())
+        .and_then(|p| p.parent())
+        .map(Path::to_path_buf)
+        .context("failed to infer repo root from plugin path")?;
+
+    let neko_base_version = read_neko_base_version(&repo_root)?;
+
+    let bundle_name = bundle_meta
+        .name
+        .clone()
+        .unwrap_or_else(|| derive_bundle_name(out_path));
+    let bundle_name_safe = sanitize_for_filename(&bundle_name);
+    let bundle_version_safe = bundle_meta
+        .version
+        .as_deref()
+        .map(sanitize_for_filename)
+        .unwrap_or_else(|| "unknown".to_string());
+    let bundle_profiles_root = format!("bundle_profiles/{}/", bundle_name_safe);
+
+    let file_size = |p: &Path| -> Result<u64> {
+        Ok(fs::metadata(p)
+            .with_context(|| format!("failed to stat {}", p.display()))?
+            .len())
+    };
+
+    let mut planned: Vec<PlannedPlugin> = Vec::new();
+    for p in plugins {
+        let mut files = Vec::new();
+        for (rel, src) in list_plugin_files(&p.path, excludes)? {
+            files.push(PackEntry {
+                zip_path: format!("plugins/{}/{}", p.folder, rel),
+                size: file_size(&src)?,
+                rel,
+                src,
+            });
+        }
+
+        // Bundle profiles are stored under bundle_profiles/<bundle_name>/plugins/<plugin_id>/... with renamed files.
+        let mut profiles = Vec::new();
+        for src in collect_profile_files(&p.path) {
+            let rel = src
+                .strip_prefix(&p.path)
+                .unwrap_or(&src)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let rel_name = sanitize_for_filename(&rel.replace('/', "__"));
+            let zip_path = format!(
+                "{}plugins/{}/{}__{}__{}__{}",
+                bundle_profiles_root,
+                sanitize_for_filename(&p.id),
+                bundle_name_safe,
+                bundle_version_safe,
+                sanitize_for_filename(&p.id),
+                rel_name
+            );
+            profiles.push(PackEntry {
+                zip_path,
+                size: file_size(&src)?,
+                rel,
+                src,
+            });
+        }
+
+        planned.push(PlannedPlugin {
+            id: p.id.clone(),
+            folder: p.folder.clone(),
+            files,
+            profiles,
+        });
+    }
+
+    let manifest = Manifest {
+        format_version: manifest_version,
+        neko_base_version,
+        packed_at: packed_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        packed_by: PACKED_BY.to_string(),
+        root_layout: "plugins/".to_string(),
+        compression: compression.method.as_str().to_string(),
+        compression_level: compression.level,
+        bundle: Some(ManifestBundle {
+            name: bundle_name,
+            version: bundle_meta.version,
+            author: bundle_meta.author,
+        }),
+        bundle_profiles_root: Some(bundle_profiles_root),
+        plugins: plugins
+            .iter()
+            .zip(planned.iter())
+            .map(|(p, pp)| ManifestPlugin {
+                id: p.id.clone(),
+                name: p.name.clone(),
+                version: p.version.clone(),
+                entry: p.entry.clone(),
+                folder: format!("plugins/{}", p.folder),
+                md5: p.hash.clone().filter(|_| p.hash_algo == HashAlgo::Md5),
+                hash_algo: p.hash.as_ref().map(|_| p.hash_algo.as_str().to_string()),
+                hash: p.hash.clone(),
+                bundled_profiles: pp.profiles.iter().map(|e| e.zip_path.clone()).collect(),
+                bundled_profile_paths: pp.profiles.iter().map(|e| e.rel.clone()).collect(),
+                implicit: p.implicit,
+                files: None,
+            })
+            .collect(),
+        // Checksums are filled in while writing; a dry run lists the files under `extras`
+        extras: Vec::new(),
+    };
+
+    let extras = extras
+        .iter()
+        .map(|e| {
+            Ok(PackEntry {
+                zip_path: format!("{}{}", EXTRAS_PREFIX, e.zip_path),
+                rel: e.zip_path.clone(),
+                size: file_size(&e.src)?,
+                src: e.src.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    check_entry_names(&planned, &extras, max_name_len)?;
+
+    Ok(PackPlan {
+        manifest,
+        plugins: planned,
+        extras,
+        hash_cache: excludes.hash_cache.clone(),
+    })
+}
+
+/// Default for `pack --max-name-len`, counted in UTF-16 code units like Windows counts paths.
+/// Long enough for deep plugin trees; unpack itself copes with longer paths, other unzip tools may not.
+pub const ZIP_NAME_MAX_LEN: usize = 400;
+
+/// Every entry name must be valid UTF-8 and within `max_len`; all offending files are listed
+fn check_entry_names(planned: &[PlannedPlugin], extras: &[PackEntry], max_len: usize) -> Result<()> {
+    let mut problems = Vec::new();
+    for entry in planned.iter().flat_map(|p| p.files.iter().chain(&p.profiles)).chain(extras) {
+        if entry.rel.contains(char::REPLACEMENT_CHARACTER) && entry.src.to_str().is_none() {
+            problems.push(format!("{}: file name is not valid UTF-8", entry.src.display()));
+            continue;
+        }
+        let len = entry.zip_path.encode_utf16().count();
+        if len > max_len {
+            problems.push(format!(
+                "{}: zip entry name is {} characters long (limit {}, see --max-name-len)",
+                entry.zip_path, len, max_len
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        anyhow::bail!("cannot pack {} file(s):\n  {}", problems.len(), problems.join("\n  "));
+    }
+    Ok(())
+}
+
+/// Bytes attributed to one plugin (compressed in a bundle, uncompressed in a dry run)
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginSize {
+    pub id: String,
+    pub bytes: u64,
+}
+
+pub fn human_bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut v = n as f64;
+    let mut unit = 0;
+    while v >= 1024.0 && unit < UNITS.len() - 1 {
+        v /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", n)
+    } else {
+        format!("{:.1} {}", v, UNITS[unit])
+    }
+}
+
+/// Parse `--max-size`/`--warn-size`: a byte count, optionally with a unit. KB/MB/GB/TB are powers
+/// of 1000; K/M/G/T and KiB/MiB/GiB/TiB are powers of 1024.
+pub fn parse_size(text: &str) -> Result<u64> {
+    let t = text.trim();
+    let split = t.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(t.len());
+    let (number, unit) = t.split_at(split);
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        other => anyhow::bail!("invalid size {:?}: unknown unit {:?}", text, other),
+    };
+    if number.is_empty() {
+        anyhow::bail!("invalid size {:?}: expected a number such as 500MB", text);
+    }
+    if number.contains('.') {
+        let v: f64 = number.parse().with_context(|| format!("invalid size {:?}", text))?;
+        return Ok((v * multiplier as f64).round() as u64);
+    }
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .with_context(|| format!("invalid size {:?}", text))
+}
+
+/// "alpha 1.2 MiB, beta 300 B", largest first
+fn format_plugin_sizes(sizes: &[PluginSize]) -> String {
+    let mut sorted: Vec<&PluginSize> = sizes.iter().collect();
+    sorted.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.id.cmp(&b.id)));
+    sorted
+        .iter()
+        .map(|p| format!("{} {}", p.id, human_bytes(p.bytes)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Compressed size of each plugin's entries (payload plus bundled profiles), largest first
+pub fn bundle_size_breakdown(zip_path: &Path) -> Result<Vec<PluginSize>> {
+    let mut archive = open_bundle(zip_path)?;
+    let manifest = read_manifest(&mut archive).with_context(|| PathContext::new("invalid bundle", zip_path))?;
+    let profiles_root = manifest
+        .bundle_profiles_root
+        .as_deref()
+        .map(|r| format!("{}/plugins/", r.trim_end_matches('/')));
+    let mut sizes: Vec<PluginSize> = manifest
+        .plugins
+        .iter()
+        .map(|p| PluginSize {
+            id: p.id.clone(),
+            bytes: 0,
+        })
+        .collect();
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(i)
+            .with_context(|| PathContext::new("failed to read zip", zip_path))?;
+        let name = entry.name();
+        let profile_owner = profiles_root
+            .as_deref()
+            .and_then(|root| name.strip_prefix(root))
+            .and_then(|rest| rest.split('/').next());
+        let owner = manifest.plugins.iter().position(|p| {
+            name.starts_with(&format!("{}/", p.folder.trim_end_matches('/'))) || profile_owner == Some(p.id.as_str())
+        });
+        if let Some(k) = owner {
+            sizes[k].bytes += entry.compressed_size();
+        }
+    }
+    sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.id.cmp(&b.id)));
+    Ok(sizes)
+}
+
+/// `--warn-size`: None when the bundle fits, otherwise a message naming plugins by compressed size
+pub fn check_warn_size(zip_path: &Path, warn_size: u64) -> Result<Option<String>> {
+    let total = fs::metadata(zip_path)
+        .with_context(|| PathContext::new("failed to stat", zip_path))?
+        .len();
+    if total <= warn_size {
+        return Ok(None);
+    }
+    let sizes = bundle_size_breakdown(zip_path)?;
+    Ok(Some(format!(
+        "bundle is {} bytes ({}), over --warn-size {} bytes; by plugin: {}",
+        total,
+        human_bytes(total),
+        warn_size,
+        format_plugin_sizes(&sizes)
+    )))
+}
+
+/// Write the planned entries into `f`. With `max_size`, the output size is checked after every entry
+/// (compressors may still hold a little buffered data) and exactly once the archive is finished.
+/// Previous bundle for `pack --base`. Plugins whose folder hash still matches the one in its
+/// manifest are copied from it entry by entry, already compressed, instead of being read from
+/// disk and compressed again.
+pub struct PackBase {
+    path: PathBuf,
+    archive: ZipArchive<fs::File>,
+    manifest: ManifestDe,
+    reused: Vec<String>,
+}
+
+impl PackBase {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut archive = open_bundle(path)?;
+        let manifest = read_manifest(&mut archive).with_context(|| PathContext::new("invalid base bundle", path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            archive,
+            manifest,
+            reused: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Ids of the plugins copied from this bundle so far
+    pub fn reused(&self) -> &[String] {
+        &self.reused
+    }
+
+    /// Per-file checksums to carry over when `plugin` can be copied as it is: same folder and
+    /// folder hash, entries stored with the same compression, and (for format_version 2 output)
+    /// a file list in the base manifest. None means the plugin has to be packed from disk.
+    fn reusable(
+        &self,
+        plugin: &PlannedPlugin,
+        manifest_plugin: &ManifestPlugin,
+        compression: &(String, Option<i64>),
+        need_files: bool,
+    ) -> Option<Vec<ManifestFile>> {
+        if self.manifest.compression.as_deref() != Some(compression.0.as_str())
+            || self.manifest.compression_level != compression.1
+        {
+            return None;
+        }
+        let old = self.manifest.plugins.iter().find(|p| p.id == plugin.id)?;
+        let hash = manifest_plugin.hash_algo.as_deref().zip(manifest_plugin.hash.as_deref())?;
+        if old.folder.trim_end_matches('/') != manifest_plugin.folder
+            || old.expected_hash().map(|(algo, h)| (algo.as_str(), h)) != Some(hash)
+        {
+            return None;
+        }
+        if !plugin.files.iter().all(|e| self.archive.index_for_name(&e.zip_path).is_some()) {
+            return None;
+        }
+        if !need_files {
+            return Some(Vec::new());
+        }
+        let files = old.files.as_ref()?;
+        plugin
+            .files
+            .iter()
+            .map(|e| {
+                files.iter().find(|f| f.path == e.rel).map(|f| ManifestFile {
+                    path: f.path.clone(),
+                    size: f.size,
+                    sha256: f.sha256.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+struct BaseCopy<'a> {
+    base: &'a mut PackBase,
+    /// Timestamp for copied entries under --reproducible; otherwise they keep the base's
+    mtime: Option<zip::DateTime>,
+}
+
+/// Write + Seek adapter that remembers how far the output extends, so --max-size works for
+/// any sink rather than only for files it can stat
+struct TrackedWriter<W> {
+    inner: W,
+    pos: u64,
+    len: std::rc::Rc<std::cell::Cell<u64>>,
+}
+
+impl<W: Write> Write for TrackedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.pos += n as u64;
+        self.len.set(self.len.get().max(self.pos));
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: std::io::Seek> std::io::Seek for TrackedWriter<W> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.pos = self.inner.seek(pos)?;
+        Ok(self.pos)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_bundle<W: Write + std::io::Seek>(
+    mut out: W,
+    planned: &[PlannedPlugin],
+    extras: &[PackEntry],
+    hash_cache: Option<&HashCache>,
+    manifest: &mut Manifest,
+    manifest_version: u32,
+    options: FileOptions<()>,
+    max_size: Option<u64>,
+    mut base: Option<BaseCopy<'_>>,
+    report: &dyn Fn(PackProgress<'_>),
+) -> Result<W> {
+    let start = out.stream_position().context("failed to seek pack output")?;
+    let len = std::rc::Rc::new(std::cell::Cell::new(start));
+    let written = || -> Result<u64> { Ok(len.get() - start) };
+    let check = |total: u64, by_plugin: &[PluginSize]| -> Result<()> {
+        match max_size {
+            Some(max) if total > max => anyhow::bail!(
+                "bundle exceeds --max-size: {} bytes written, limit {} bytes ({}); by plugin: {}",
+                total,
+                max,
+                human_bytes(max),
+                format_plugin_sizes(by_plugin)
+            ),
+            _ => Ok(()),
+        }
+    };
+    // Output growth attributed to each plugin, for the --max-size message
+    let mut by_plugin: Vec<PluginSize> = planned
+        .iter()
+        .map(|p| PluginSize {
+            id: p.id.clone(),
+            bytes: 0,
+        })
+        .collect();
+    let last = std::cell::Cell::new(0u64);
+    let track = |i: usize, by_plugin: &mut Vec<PluginSize>| -> Result<()> {
+        if max_size.is_none() {
+            return Ok(());
+        }
+        let now = written()?;
+        by_plugin[i].bytes += now.saturating_sub(last.get());
+        last.set(now);
+        check(now, by_plugin)
+    };
+
+    let mut zip = zip::ZipWriter::new(TrackedWriter {
+        inner: out,
+        pos: start,
+        len: len.clone(),
+    });
+
+    // Extras are small repo-level files; hash them up front so v1 can list them in its leading manifest
+    for entry in extras {
+        manifest.extras.push(ManifestFile {
+            path: entry.rel.clone(),
+            size: entry.size,
+            sha256: cached_file_sha256(hash_cache, &entry.src)?,
+        });
+    }
+
+    // v1 keeps manifest.toml as the first entry; v2 needs the per-file checksums gathered
+    // while copying, so its manifest is written after the payload.
+    if manifest_version == 1 {
+        write_manifest_entry(&mut zip, manifest, options)?;
+        last.set(written()?);
+    }
+
+    let entries = planned.iter().flat_map(|p| p.files.iter().chain(&p.profiles)).chain(extras);
+    report(PackProgress::Start {
+        plugins: planned.len(),
+        files: entries.clone().count(),
+        bytes: entries.map(|e| e.size).sum(),
+    });
+
+    let compression = (manifest.compression.clone(), manifest.compression_level);
+    for (i, (plugin, manifest_plugin)) in planned.iter().zip(manifest.plugins.iter_mut()).enumerate() {
+        report(PackProgress::PluginStart {
+            plugin: &plugin.id,
+            files: plugin.files.len(),
+            bytes: plugin.files.iter().map(|e| e.size).sum(),
+        });
+        let reusable = base
+            .as_ref()
+            .and_then(|b| b.base.reusable(plugin, manifest_plugin, &compression, manifest_version >= 2));
+        if let (Some(checksums), Some(b)) = (reusable, base.as_mut()) {
+            for entry in &plugin.files {
+                let file = b
+                    .base
+                    .archive
+                    .by_name(&entry.zip_path)
+                    .with_context(|| PathContext::new("failed to read base bundle", &b.base.path))?;
+                let size = file.size();
+                match b.mtime {
+                    Some(mtime) => zip.raw_copy_file_touch(file, mtime, None)?,
+                    None => zip.raw_copy_file(file)?,
+                }
+                report(PackProgress::FileWritten {
+                    plugin: &plugin.id,
+                    name: &entry.zip_path,
+                    bytes: size,
+                });
+                track(i, &mut by_plugin)?;
+            }
+            if manifest_version >= 2 {
+                manifest_plugin.files = Some(checksums);
+            }
+            b.base.reused.push(plugin.id.clone());
+            report(PackProgress::PluginDone { plugin: &plugin.id });
+            continue;
+        }
+        let mut checksums = Vec::new();
+        for entry in &plugin.files {
+            let size = if manifest_version >= 2 {
+                let mut digest = Sha256::new();
+                let size = read_file_to_zip(&mut zip, &entry.zip_path, &entry.src, options, Some(&mut digest))?;
+                checksums.push(ManifestFile {
+                    path: entry.rel.clone(),
+                    size,
+                    sha256: format!("{:x}", digest.finalize()),
+                });
+                size
+            } else {
+                read_file_to_zip(&mut zip, &entry.zip_path, &entry.src, options, None)?
+            };
+            report(PackProgress::FileWritten {
+                plugin: &plugin.id,
+                name: &entry.zip_path,
+                bytes: size,
+            });
+            track(i, &mut by_plugin)?;
+        }
+        if manifest_version >= 2 {
+            manifest_plugin.files = Some(checksums);
+        }
+        report(PackProgress::PluginDone { plugin: &plugin.id });
+    }
+
+    for (i, plugin) in planned.iter().enumerate() {
+        for entry in &plugin.profiles {
+            let size = read_file_to_zip(&mut zip, &entry.zip_path, &entry.src, options, None)?;
+            report(PackProgress::FileWritten {
+                plugin: &plugin.id,
+                name: &entry.zip_path,
+                bytes: size,
+            });
+            track(i, &mut by_plugin)?;
+        }
+    }
+
+    for entry in extras {
+        read_file_to_zip(&mut zip, &entry.zip_path, &entry.src, options, None)?;
+    }
+
+    if manifest_version >= 2 {
+        write_manifest_entry(&mut zip, manifest, options)?;
+    }
+
+    out = zip.finish()?.inner;
+    check(written()?, &by_plugin)?;
+    Ok(out)
+}
+
+/// Writes the bundle for `plugins` into any seekable sink and hands the sink back.
+/// `out_path` only names the bundle when `bundle_meta.name` is unset; nothing is written there.
+#[allow(clippy::too_many_arguments)]
+pub fn pack_to_writer<W: Write + std::io::Seek>(
+    out: W,
+    out_path: &Path,
+    plugins: &[PluginPackItem],
+    excludes: &Excludes,
+    extras: &[ExtraFile],
+    bundle_meta: BundleMeta,
+    manifest_version: u32,
+    compression: PackCompression,
+    max_name_len: usize,
+    source_date_epoch: Option<i64>,
+    max_size: Option<u64>,
+    base: Option<&mut PackBase>,
+    progress: Option<ProgressFn<'_>>,
+) -> Result<(W, Manifest)> {
+    let report = |ev: PackProgress<'_>| {
+        if let Some(cb) = progress {
+            cb(ev);
+        }
+    };
+    let PackPlan {
+        mut manifest,
+        plugins: planned,
+        extras,
+        hash_cache,
+    } = plan_pack(
+        out_path,
+        plugins,
+        excludes,
+        extras,
+        bundle_meta,
+        manifest_version,
+        compression,
+        max_name_len,
+        source_date_epoch,
+    )?;
+
+    let mut options = compression.file_options()?;
+    let mtime = source_date_epoch.map(zip_datetime).transpose()?;
+    if let Some(mtime) = mtime {
+        options = options.last_modified_time(mtime);
+    }
+
+    let base = base.map(|base| BaseCopy { base, mtime });
+    let out = write_bundle(
+        out,
+        &planned,
+        &extras,
+        hash_cache.as_deref(),
+        &mut manifest,
+        manifest_version,
+        options,
+        max_size,
+        base,
+        &report,
+    )?;
+    Ok((out, manifest))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn pack_to_zip(
+    out_path: &Path,
+    plugins: &[PluginPackItem],
+    excludes: &Excludes,
+    extras: &[ExtraFile],
+    bundle_meta: BundleMeta,
+    manifest_version: u32,
+    compression: PackCompression,
+    max_name_len: usize,
+    source_date_epoch: Option<i64>,
+    max_size: Option<u64>,
+    base: Option<&mut PackBase>,
+    progress: Option<ProgressFn<'_>>,
+) -> Result<Manifest> {
+    let tmp_path = out_path.with_extension("zip.tmp");
+    let f = fs::File::create(&tmp_path).with_context(|| format!("failed to create {}", tmp_path.display()))?;
+    let written = pack_to_writer(
+        f,
+        out_path,
+        plugins,
+        excludes,
+        extras,
+        bundle_meta,
+        manifest_version,
+        compression,
+        max_name_len,
+        source_date_epoch,
+        max_size,
+        base,
+        progress,
+    );
+    let manifest = match written {
+        Ok((f, manifest)) => {
+            drop(f);
+            manifest
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+    };
+    fs::rename(&tmp_path, out_path)
+        .with_context(|| format!("failed to rename {} -> {}", tmp_path.display(), out_path.display()))?;
+    Ok(manifest)
+}
+
+/// Bundle path meaning stdout for `pack --out` and stdin for `unpack`
+pub const STREAM_PATH: &str = "-";
+
+pub fn is_stream_path(out_path: &Path) -> bool {
+    out_path.as_os_str() == STREAM_PATH
+}
+
+/// Scratch file behind `--out -`. ZipWriter needs Seek to patch local headers and write the
+/// central directory, which a pipe cannot offer, so the bundle is built (and signed/verified)
+/// here and then copied to the stream. The directory is removed on drop.
+pub struct StreamSpool {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl StreamSpool {
+    /// `file_name` becomes the spool file's name, so the bundle name derived from it matches
+    /// what a regular pack to that file would have used
+    pub fn new(file_name: &Path) -> Result<Self> {
+        static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("neko-plugin-cli-{}-{}", std::process::id(), n));
+        fs::create_dir_all(&dir).with_context(|| PathContext::new("failed to create spool dir", &dir))?;
+        let path = dir.join(file_name.file_name().unwrap_or(file_name.as_os_str()));
+        Ok(Self { dir, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Copies the finished bundle to `sink` and returns its size
+    pub fn copy_to(&self, sink: &mut dyn Write) -> Result<u64> {
+        let mut f = fs::File::open(&self.path).with_context(|| PathContext::new("failed to open", &self.path))?;
+        let n = std::io::copy(&mut f, sink).context("failed to stream bundle")?;
+        sink.flush().context("failed to stream bundle")?;
+        Ok(n)
+    }
+}
+
+impl Drop for StreamSpool {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Newest manifest format_version pack_to_zip can write and unpack can read
+pub const MANIFEST_VERSION_MAX: u32 = 2;
+
+/// Written to the manifest's `packed_by`, so version errors can name the build that made a bundle
+pub const PACKED_BY: &str = concat!("neko-plugin-cli ", env!("CARGO_PKG_VERSION"));
+
+fn open_bundle(zip_path: &Path) -> Result<ZipArchive<fs::File>> {
+    let f = fs::File::open(zip_path).with_context(|| PathContext::new("failed to open zip", zip_path))?;
+    ZipArchive::new(f).with_context(|| PathContext::new("failed to read zip", zip_path))
+}
 
-        let plugin_toml = path.join("plugin.toml");
-        if !plugin_toml.is_file() {
+fn read_manifest<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Result<ManifestDe> {
+    read_manifest_as(archive, None)
+}
+
+/// read_manifest that reads a newer-than-supported manifest as `assume_version` instead of failing
+fn read_manifest_as<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    assume_version: Option<u32>,
+) -> Result<ManifestDe> {
+    parse_manifest_as(&read_manifest_bytes(archive)?, assume_version)
+}
+
+/// manifest.toml exactly as stored, which is what bundle signatures cover
+fn read_manifest_bytes<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Result<Vec<u8>> {
+    let mut file = archive
+        .by_name("manifest.toml")
+        .context("manifest.toml not found in zip")?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)
+        .context("failed to read manifest.toml")?;
+    Ok(buf)
+}
+
+/// find_plain_plugin for zips without manifest.toml, or for any zip with `no_manifest`
+fn plain_plugin_prefix<R: Read + std::io::Seek>(
+    archive: &ZipArchive<R>,
+    zip_path: &Path,
+    no_manifest: bool,
+) -> Result<Option<String>> {
+    if !no_manifest && archive.index_for_name("manifest.toml").is_some() {
+        return Ok(None);
+    }
+    match find_plain_plugin(archive)? {
+        None if no_manifest => anyhow::bail!(
+            "{} has no plugin.toml at the top level or one directory deep",
+            zip_path.display()
+        ),
+        prefix => Ok(prefix),
+    }
+}
+
+/// Where a zip without a manifest keeps its single plugin: the directory prefix of its plugin.toml,
+/// "" at the top level or "<dir>/" one directory deep. None when there is no such plugin.toml.
+fn find_plain_plugin<R: Read + std::io::Seek>(archive: &ZipArchive<R>) -> Result<Option<String>> {
+    if archive.index_for_name("plugin.toml").is_some() {
+        return Ok(Some(String::new()));
+    }
+    let mut dirs: Vec<&str> = archive
+        .file_names()
+        .filter_map(|name| name.strip_suffix("/plugin.toml"))
+        .filter(|dir| !dir.is_empty() && !dir.contains('/'))
+        .collect();
+    dirs.sort();
+    match dirs.as_slice() {
+        [] => Ok(None),
+        [dir] => Ok(Some(format!("{}/", dir))),
+        _ => anyhow::bail!(
+            "zip has no manifest.toml and {} plugin folders ({}); only a single plugin folder can be unpacked without a manifest",
+            dirs.len(),
+            dirs.join(", ")
+        ),
+    }
+}
+
+/// Rewrite a plain plugin-folder zip as a one-plugin bundle, with the manifest pack would write:
+/// the folder hash and per-file checksums are computed from the archive, so installed copies are
+/// compared the same way. Entries are copied without recompressing; files `excludes` matches are
+/// left out like pack leaves them out. The files read count against `max_uncompressed`.
+/// Returns the bundle and warnings about dropped entries.
+fn plain_plugin_bundle<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    zip_path: &Path,
+    prefix: &str,
+    excludes: &Excludes,
+    max_uncompressed: u64,
+) -> Result<(Vec<u8>, Vec<String>)> {
+    let budget = UnpackBudget::new(max_uncompressed);
+    let toml_name = format!("{}plugin.toml", prefix);
+    let mut text = String::new();
+    {
+        let mut file = archive
+            .by_name(&toml_name)
+            .with_context(|| PathContext::new("failed to read zip", zip_path))?;
+        BoundedEntry::new(&mut file, &budget)
+            .read_to_string(&mut text)
+            .with_context(|| format!("failed to read {}", toml_name))?;
+    }
+    let val: toml::Value = toml::from_str(&text).with_context(|| format!("failed to parse {}", toml_name))?;
+    let field = |key: &str| val.get("plugin").and_then(|p| p.get(key)).and_then(|v| v.as_str());
+    let id = field("id").ok_or_else(|| anyhow::anyhow!("{} has no [plugin] id", toml_name))?.to_string();
+    let folder = match prefix.strip_suffix('/') {
+        Some(dir) => dir.to_string(),
+        None => id.clone(),
+    };
+    if folder.contains(['/', '\\']) || !is_safe_rel_path(&folder) {
+        anyhow::bail!("cannot use '{}' as a plugin folder name", folder);
+    }
+
+    let mut warnings = Vec::new();
+    let mut entries: Vec<(String, usize)> = Vec::new();
+    let mut outside = 0;
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index_raw(i)
+            .with_context(|| PathContext::new("failed to read zip", zip_path))?;
+        if file.is_dir() {
+            continue;
+        }
+        match file.name().strip_prefix(prefix) {
+            Some(rel) if !excludes.is_match(rel) => entries.push((rel.to_string(), i)),
+            Some(_) => {}
+            None => outside += 1,
+        }
+    }
+    if outside > 0 {
+        warnings.push(format!("ignored {} zip entries outside the plugin folder {}", outside, prefix));
+    }
+    entries.sort();
+
+    // Unsafe paths and links are copied for unpack to refuse as usual, but never checksummed
+    let mut hasher = FolderHasher::new(HashAlgo::Md5);
+    let mut files = Vec::new();
+    for (rel, i) in &entries {
+        let mut file = archive
+            .by_index(*i)
+            .with_context(|| PathContext::new("failed to read zip", zip_path))?;
+        if !is_safe_rel_path(rel) || file.is_symlink() {
             continue;
         }
+        let mut data = Vec::new();
+        BoundedEntry::new(&mut file, &budget)
+            .read_to_end(&mut data)
+            .with_context(|| format!("failed to read {} from {}", rel, zip_path.display()))?;
+        hasher.update(rel.as_bytes());
+        hasher.update(&[0u8]);
+        hasher.update(&data);
+        hasher.update(&[0u8]);
+        files.push(ManifestFile {
+            path: rel.clone(),
+            size: data.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(&data)),
+        });
+    }
+    let hash = hasher.finish_hex();
+    let manifest = Manifest {
+        format_version: MANIFEST_VERSION_MAX,
+        neko_base_version: "unknown".to_string(),
+        packed_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        packed_by: PACKED_BY.to_string(),
+        root_layout: "plugins/".to_string(),
+        compression: "deflated".to_string(),
+        compression_level: None,
+        bundle: None,
+        bundle_profiles_root: None,
+        plugins: vec![ManifestPlugin {
+            name: field("name").unwrap_or(&id).to_string(),
+            version: field("version").unwrap_or("unknown").to_string(),
+            entry: field("entry").unwrap_or("").to_string(),
+            id,
+            folder: format!("plugins/{}", folder),
+            md5: Some(hash.clone()),
+            hash_algo: Some(HashAlgo::Md5.as_str().to_string()),
+            hash: Some(hash),
+            bundled_profiles: Vec::new(),
+            bundled_profile_paths: Vec::new(),
+            implicit: false,
+            files: Some(files),
+        }],
+        extras: Vec::new(),
+    };
 
-        let txt = fs::read_to_string(&plugin_toml)
-            .with_context(|| format!("failed to read {}", plugin_toml.display()))?;
-        let val: toml::Value = toml::from_str(&txt)
-            .with_context(|| format!("failed to parse {}", plugin_toml.display()))?;
-        let plugin = val.get("plugin");
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("manifest.toml", FileOptions::<()>::default())?;
+    zip.write_all(toml::to_string(&manifest)?.as_bytes())?;
+    for (rel, i) in &entries {
+        let file = archive
+            .by_index_raw(*i)
+            .with_context(|| PathContext::new("failed to read zip", zip_path))?;
+        zip.raw_copy_file_rename(file, format!("plugins/{}/{}", folder, rel))?;
+    }
+    Ok((zip.finish()?.into_inner(), warnings))
+}
 
-        let id = plugin
-            .and_then(|v| v.get("id"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-        if let Some(wants) = plugin_ids {
-            if !wants.is_empty() && !wants.iter().any(|w| w == &id) {
+fn parse_manifest(bytes: &[u8]) -> Result<ManifestDe> {
+    parse_manifest_as(bytes, None)
+}
+
+/// Check format_version before parsing the rest, so a newer layout is reported as such instead of
+/// as a confusing parse error or, worse, half understood. Versions 1 and 2 share ManifestDe:
+/// version 1 just lacks the per-file checksums, which stay `None`.
+fn parse_manifest_as(bytes: &[u8], assume_version: Option<u32>) -> Result<ManifestDe> {
+    let text = std::str::from_utf8(bytes).context("manifest.toml is not valid UTF-8")?;
+    let header: ManifestHeader = toml::from_str(text).context("failed to parse manifest.toml")?;
+    let version = match header.format_version {
+        0 => anyhow::bail!("unsupported manifest format_version 0 (this build reads 1..={})", MANIFEST_VERSION_MAX),
+        v if v <= MANIFEST_VERSION_MAX => v,
+        v => match assume_version {
+            Some(assumed) if (1..=MANIFEST_VERSION_MAX).contains(&assumed) => assumed,
+            Some(assumed) => anyhow::bail!(
+                "cannot assume manifest format_version {} (this build reads 1..={})",
+                assumed,
+                MANIFEST_VERSION_MAX
+            ),
+            None => anyhow::bail!(
+                "unsupported manifest format_version {}: newer than this build reads (1..={}); packed by {}, you have {}. \
+                 Upgrade neko-plugin-cli, or use unpack --assume-version {} to read it as version {} anyway",
+                v,
+                MANIFEST_VERSION_MAX,
+                header.packed_by.as_deref().unwrap_or("an unknown neko-plugin-cli"),
+                PACKED_BY,
+                MANIFEST_VERSION_MAX,
+                MANIFEST_VERSION_MAX
+            ),
+        },
+    };
+    let mut m: ManifestDe = toml::from_str(text).context("failed to parse manifest.toml")?;
+    if version != m.format_version {
+        m.assumed_from = Some(m.format_version);
+        m.format_version = version;
+    }
+    Ok(m)
+}
+
+fn is_safe_rel_path(rel: &str) -> bool {
+    let p = Path::new(rel);
+    if p.is_absolute() {
+        return false;
+    }
+    for c in p.components() {
+        if matches!(c, std::path::Component::ParentDir | std::path::Component::Prefix(_)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// `base` joined with a '/'-separated zip path, one component at a time so the result uses
+/// native separators (extended-length paths on Windows take separators literally)
+fn join_rel(base: &Path, rel: &str) -> PathBuf {
+    rel.split('/').filter(|c| !c.is_empty()).fold(base.to_path_buf(), |p, c| p.join(c))
+}
+
+/// On Windows, the absolute extended-length (`\\?\`) form of `path`, so unpack can create files
+/// whose full path exceeds MAX_PATH; elsewhere `path` unchanged
+fn long_path(path: &Path) -> Result<PathBuf> {
+    #[cfg(windows)]
+    {
+        let abs = std::path::absolute(path).with_context(|| PathContext::new("failed to resolve", path))?;
+        let Some(s) = abs.to_str() else {
+            return Ok(abs);
+        };
+        if s.starts_with(r"\\?\") {
+            return Ok(abs);
+        }
+        if let Some(unc) = s.strip_prefix(r"\\") {
+            return Ok(PathBuf::from(format!(r"\\?\UNC\{}", unc)));
+        }
+        Ok(PathBuf::from(format!(r"\\?\{}", s)))
+    }
+    #[cfg(not(windows))]
+    {
+        Ok(path.to_path_buf())
+    }
+}
+
+/// Whether a directory between `root` and `path` is a symlink
+fn has_symlink_parent(root: &Path, path: &Path) -> bool {
+    path.ancestors()
+        .skip(1)
+        .take_while(|p| p.starts_with(root) && *p != root)
+        .any(|p| p.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()))
+}
+
+/// A link at `link` (relative to the destination) may point at `target` when the target is
+/// relative, only climbs with leading `..` components and does not climb above the destination.
+/// Links below other links are refused separately, so the lexical check matches the real path.
+fn is_safe_symlink_target(link: &str, target: &str) -> bool {
+    if target.is_empty() || target.starts_with('/') || target.contains('\\') {
+        return false;
+    }
+    let depth = link.split('/').count() - 1;
+    let mut ups = 0;
+    let mut descended = false;
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." if descended => return false,
+            ".." => ups += 1,
+            _ => descended = true,
+        }
+    }
+    ups <= depth
+}
+
+/// Create a symlink; false where the platform cannot (links are then skipped)
+fn create_symlink(target: &str, link: &Path) -> Result<bool> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link).with_context(|| PathContext::new("failed to create symlink", link))?;
+        Ok(true)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (target, link);
+        Ok(false)
+    }
+}
+
+/// Archive entry holding the ed25519 signature over manifest.toml
+pub const SIGNATURE_ENTRY: &str = "manifest.sig";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SignatureFile {
+    algorithm: String,
+    key_fingerprint: String,
+    signature: String,
+}
+
+/// What unpack demands of the bundle signature (`--verify-key`, `--require-signature`)
+pub struct SignaturePolicy {
+    pub key: VerifyingKey,
+    /// Reject unsigned bundles instead of warning about them
+    pub required: bool,
+}
+
+/// Key pair written by `keygen`
+#[derive(Debug, Serialize)]
+pub struct GeneratedKey {
+    pub secret_key: PathBuf,
+    pub public_key: PathBuf,
+    pub fingerprint: String,
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode<const N: usize>(text: &str) -> Result<[u8; N]> {
+    let text = text.trim();
+    if text.len() != N * 2 || !text.is_ascii() {
+        anyhow::bail!("expected {} hex characters, got {}", N * 2, text.len());
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).context("invalid hex digit")?;
+    }
+    Ok(out)
+}
+
+/// sha256 of the raw public key; recorded in manifest.sig and printed by keygen
+pub fn key_fingerprint(key: &VerifyingKey) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Write a new ed25519 key pair: the hex secret key to `out`, the hex public key to `<out>.pub`
+pub fn generate_signing_key(out: &Path, force: bool) -> Result<GeneratedKey> {
+    let mut public_key = out.as_os_str().to_owned();
+    public_key.push(".pub");
+    let public_key = PathBuf::from(public_key);
+    if !force {
+        for p in [out, public_key.as_path()] {
+            if p.exists() {
+                return Err(anyhow::anyhow!("already exists (use --force to overwrite)"))
+                    .with_context(|| PathContext::new("refusing to overwrite", p));
+            }
+        }
+    }
+
+    let key = SigningKey::generate(&mut OsRng);
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+    let mut f = opts.open(out).with_context(|| PathContext::new("failed to create", out))?;
+    writeln!(f, "{}", hex_encode(&key.to_bytes())).with_context(|| PathContext::new("failed to write", out))?;
+    fs::write(&public_key, format!("{}\n", hex_encode(key.verifying_key().as_bytes())))
+        .with_context(|| PathContext::new("failed to write", &public_key))?;
+
+    Ok(GeneratedKey {
+        secret_key: out.to_path_buf(),
+        public_key,
+        fingerprint: key_fingerprint(&key.verifying_key()),
+    })
+}
+
+pub fn read_signing_key(path: &Path) -> Result<SigningKey> {
+    let text = fs::read_to_string(path).with_context(|| PathContext::new("failed to read", path))?;
+    let bytes = hex_decode::<32>(&text).with_context(|| PathContext::new("invalid signing key", path))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+pub fn read_verifying_key(path: &Path) -> Result<VerifyingKey> {
+    let text = fs::read_to_string(path).with_context(|| PathContext::new("failed to read", path))?;
+    hex_decode::<32>(&text)
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).map_err(|e| anyhow::anyhow!("{}", e)))
+        .with_context(|| PathContext::new("invalid public key", path))
+}
+
+/// Append manifest.sig to a packed bundle. Only format_version 2 manifests are accepted, since
+/// their per-file checksums are what extends the signature to the plugin files.
+pub fn sign_bundle(zip_path: &Path, key: &SigningKey, source_date_epoch: Option<i64>) -> Result<String> {
+    let mut archive = open_bundle(zip_path)?;
+    let manifest_bytes = read_manifest_bytes(&mut archive).with_context(|| PathContext::new("invalid bundle", zip_path))?;
+    let manifest = parse_manifest(&manifest_bytes).with_context(|| PathContext::new("invalid bundle", zip_path))?;
+    if manifest.format_version < 2 || manifest.plugins.iter().any(|p| p.files.is_none()) {
+        anyhow::bail!("signing requires a format_version 2 manifest with per-file checksums (pack with --manifest-version 2)");
+    }
+    if archive.index_for_name(SIGNATURE_ENTRY).is_some() {
+        anyhow::bail!("bundle is already signed: {}", zip_path.display());
+    }
+    drop(archive);
+
+    let fingerprint = key_fingerprint(&key.verifying_key());
+    let sig = SignatureFile {
+        algorithm: "ed25519".to_string(),
+        key_fingerprint: fingerprint.clone(),
+        signature: hex_encode(&key.sign(&manifest_bytes).to_bytes()),
+    };
+
+    let f = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(zip_path)
+        .with_context(|| PathContext::new("failed to open zip", zip_path))?;
+    let mut zip = zip::ZipWriter::new_append(f).with_context(|| PathContext::new("failed to read zip", zip_path))?;
+    let mut options: FileOptions<()> = FileOptions::default().compression_method(CompressionMethod::Stored);
+    if let Some(epoch) = source_date_epoch {
+        options = options.last_modified_time(zip_datetime(epoch)?);
+    }
+    zip.start_file(SIGNATURE_ENTRY, options)?;
+    zip.write_all(toml::to_string(&sig).context("failed to serialize signature")?.as_bytes())?;
+    zip.finish().with_context(|| PathContext::new("failed to write", zip_path))?;
+    Ok(fingerprint)
+}
+
+/// Check manifest.sig against `policy` and, once it verifies, every payload entry against the signed
+/// manifest. Returns the signer fingerprint, or None for an unsigned bundle the policy tolerates.
+fn verify_bundle_signature<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    policy: &SignaturePolicy,
+) -> Result<Option<String>> {
+    let sig_text = match archive.by_name(SIGNATURE_ENTRY) {
+        Ok(mut f) => {
+            let mut s = String::new();
+            f.read_to_string(&mut s).context("failed to read manifest.sig")?;
+            s
+        }
+        Err(zip::result::ZipError::FileNotFound) if policy.required => {
+            anyhow::bail!("bundle is not signed (--require-signature)")
+        }
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e).context("failed to read manifest.sig"),
+    };
+    let sig: SignatureFile = toml::from_str(&sig_text).context("failed to parse manifest.sig")?;
+    if sig.algorithm != "ed25519" {
+        anyhow::bail!("unsupported signature algorithm: {}", sig.algorithm);
+    }
+    let expected = key_fingerprint(&policy.key);
+    if sig.key_fingerprint != expected {
+        anyhow::bail!("bundle was signed by key {}, not by the verify key {}", sig.key_fingerprint, expected);
+    }
+    let signature = Signature::from_bytes(&hex_decode::<64>(&sig.signature).context("invalid signature in manifest.sig")?);
+    let manifest_bytes = read_manifest_bytes(archive)?;
+    policy
+        .key
+        .verify_strict(&manifest_bytes, &signature)
+        .map_err(|_| anyhow::anyhow!("signature does not match manifest.toml (bundle was modified after signing)"))?;
+
+    let manifest = parse_manifest(&manifest_bytes)?;
+    verify_signed_payload(archive, &manifest)?;
+    Ok(Some(expected))
+}
+
+/// Signed bundles must carry per-file checksums for every plugin, and every entry must be accounted
+/// for by the manifest. Bundled profiles carry no checksum and are only checked by name.
+fn verify_signed_payload<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, manifest: &ManifestDe) -> Result<()> {
+    if let Some(p) = manifest.plugins.iter().find(|p| p.files.is_none()) {
+        anyhow::bail!("plugin {} has no per-file checksums in the signed manifest", p.id);
+    }
+    let mut problems = VerifyProblems {
+        fail_fast: true,
+        issues: Vec::new(),
+    };
+    let (_, unlisted) = verify_archive_contents(archive, manifest, &mut problems)?;
+    if let Some(name) = unlisted.first() {
+        anyhow::bail!("{} is not listed in the signed manifest", name);
+    }
+    Ok(())
+}
+
+/// What verify_bundle checked
+#[derive(Debug, Serialize, Default)]
+pub struct VerifyReport {
+    pub plugins: usize,
+    pub entries: usize,
+    pub bytes: u64,
+    /// Plugins whose folder hash was recomputed from the archive
+    pub folder_hashes: usize,
+    /// Files compared against their per-file sha256 (format_version 2)
+    pub file_checksums: usize,
+    /// The bundle carries a manifest.sig
+    pub signed: bool,
+    /// Fingerprint of the key the signature was verified with
+    pub signed_by: Option<String>,
+    /// Problems found by verify_bundle_report, in archive order; verify_bundle fails on the first one instead
+    pub issues: Vec<VerifyIssue>,
+}
+
+/// One integrity problem of a bundle
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyIssue {
+    /// Stable identifier: `entry-unreadable`, `checksum-mismatch`, `file-missing`, `profile-missing`,
+    /// `plugin-empty`, `entry-unlisted`, `hash-mismatch` or `signature-invalid`
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Where verification problems go: the first one fails the run, or all are collected
+struct VerifyProblems {
+    fail_fast: bool,
+    issues: Vec<VerifyIssue>,
+}
+
+impl VerifyProblems {
+    fn add(&mut self, code: &'static str, err: anyhow::Error) -> Result<()> {
+        if self.fail_fast {
+            return Err(err);
+        }
+        self.issues.push(VerifyIssue {
+            code,
+            message: format!("{:#}", err),
+        });
+        Ok(())
+    }
+}
+
+/// Re-read a bundle end to end: every entry must decompress with a valid CRC, every file the manifest
+/// lists must be present and match its sha256, every plugin folder must have entries, nothing may
+/// sit outside what the manifest accounts for, and folder hashes recomputed from the archive must
+/// equal the manifest values. Fails on the first problem; used by `pack --verify` as well.
+pub fn verify_bundle(zip_path: &Path) -> Result<VerifyReport> {
+    verify_bundle_with(zip_path, None, true)
+}
+
+/// verify_bundle that collects every problem into `issues` instead of failing on the first, and
+/// checks the signature against `signature` when given. Fails only when the zip or its manifest
+/// cannot be read at all.
+pub fn verify_bundle_report(zip_path: &Path, signature: Option<&SignaturePolicy>) -> Result<VerifyReport> {
+    verify_bundle_with(zip_path, signature, false)
+}
+
+fn verify_bundle_with(zip_path: &Path, signature: Option<&SignaturePolicy>, fail_fast: bool) -> Result<VerifyReport> {
+    let run = || -> Result<VerifyReport> {
+        let mut archive = open_bundle(zip_path)?;
+        let manifest = read_manifest(&mut archive)?;
+        let mut problems = VerifyProblems {
+            fail_fast,
+            issues: Vec::new(),
+        };
+        let signed_by = match signature {
+            Some(policy) => match verify_bundle_signature(&mut archive, policy) {
+                Ok(signed_by) => signed_by,
+                Err(e) => {
+                    problems.add("signature-invalid", e)?;
+                    None
+                }
+            },
+            None => None,
+        };
+        let (mut report, unlisted) = verify_archive_contents(&mut archive, &manifest, &mut problems)?;
+        for name in unlisted {
+            problems.add("entry-unlisted", anyhow::anyhow!("{} is not referenced by the manifest", name))?;
+        }
+        report.signed = archive.index_for_name(SIGNATURE_ENTRY).is_some();
+        report.signed_by = signed_by;
+        report.issues = problems.issues;
+        Ok(report)
+    };
+    run().with_context(|| PathContext::new("verification failed for", zip_path))
+}
+
+/// verify_bundle for a freshly packed bundle; a bundle that fails is deleted rather than left behind
+pub fn verify_pack_output(out_path: &Path) -> Result<VerifyReport> {
+    verify_bundle(out_path).inspect_err(|_| {
+        let _ = fs::remove_file(out_path);
+    })
+}
+
+/// How `migrate` rewrites a bundle's manifest
+#[derive(Debug, Clone, Default)]
+pub struct MigrateOptions {
+    /// Target format_version; MANIFEST_VERSION_MAX when None. Older than the bundle is refused.
+    pub format_version: Option<u32>,
+    /// Fills the `[bundle]` fields the manifest lacks; fields it already has are kept
+    pub bundle: BundleMeta,
+    /// Sign the result (needs format_version 2); an existing signature is always dropped, since
+    /// it covers the old manifest
+    pub sign_key: Option<SigningKey>,
+    /// manifest.sig timestamp, as for `pack --reproducible`
+    pub source_date_epoch: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigrateReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub out: PathBuf,
+    /// `[bundle]` fields filled from the options
+    pub filled: Vec<String>,
+    /// Per-file checksums computed from the archive
+    pub file_checksums: usize,
+    /// The input carried a manifest.sig that no longer applies
+    pub dropped_signature: bool,
+    pub signed_by: Option<String>,
+    /// verify run on the output
+    pub verify: VerifyReport,
+}
+
+/// Rewrite the manifest of the bundle at `src` into `out` (which may be `src` itself) at the target
+/// format_version, computing per-file checksums from the archive. Every other entry is copied raw,
+/// so payload bytes stay exactly as they were. The input must pass verify first, so corruption is
+/// never blessed with fresh checksums; the output is written next to `out`, verified, and only
+/// then moved into place.
+pub fn migrate_bundle(src: &Path, out: &Path, options: &MigrateOptions) -> Result<MigrateReport> {
+    let to_version = options.format_version.unwrap_or(MANIFEST_VERSION_MAX);
+    if !(1..=MANIFEST_VERSION_MAX).contains(&to_version) {
+        anyhow::bail!("unsupported manifest format_version {} (this build writes 1..={})", to_version, MANIFEST_VERSION_MAX);
+    }
+    if options.sign_key.is_some() && to_version < 2 {
+        anyhow::bail!("signing requires format_version 2 (its per-file checksums are what the signature covers)");
+    }
+    let checked = verify_bundle_report(src, None)?;
+    if !checked.issues.is_empty() {
+        let lines: Vec<String> = checked.issues.iter().map(|i| format!("[{}] {}", i.code, i.message)).collect();
+        anyhow::bail!("{} fails verification, refusing to migrate it:\n  {}", src.display(), lines.join("\n  "));
+    }
+
+    let mut archive = open_bundle(src)?;
+    let manifest = read_manifest(&mut archive).with_context(|| PathContext::new("invalid bundle", src))?;
+    if to_version < manifest.format_version {
+        anyhow::bail!(
+            "{} already has format_version {}; migrate does not downgrade to {}",
+            src.display(),
+            manifest.format_version,
+            to_version
+        );
+    }
+    let dropped_signature = archive.index_for_name(SIGNATURE_ENTRY).is_some();
+
+    let mut filled = Vec::new();
+    let old_meta = manifest.bundle.as_ref().map(ManifestBundleDe::meta).unwrap_or_default();
+    for (field, old, new) in [
+        ("name", &old_meta.name, &options.bundle.name),
+        ("version", &old_meta.version, &options.bundle.version),
+        ("author", &old_meta.author, &options.bundle.author),
+    ] {
+        if old.is_none() && new.is_some() {
+            filled.push(field.to_string());
+        }
+    }
+    let meta = old_meta.or(&options.bundle);
+    let bundle = match (manifest.bundle.is_some() || !filled.is_empty(), meta) {
+        (false, _) => None,
+        (true, meta) => Some(ManifestBundle {
+            name: meta.name.unwrap_or_else(|| derive_bundle_name(out)),
+            version: meta.version,
+            author: meta.author,
+        }),
+    };
+
+    let mut file_checksums = 0;
+    let mut plugins = Vec::with_capacity(manifest.plugins.len());
+    for p in &manifest.plugins {
+        let files = if to_version >= 2 {
+            let files = bundle_plugin_files(&mut archive, &p.folder)?;
+            file_checksums += files.len();
+            Some(files)
+        } else {
+            None
+        };
+        plugins.push(ManifestPlugin {
+            id: p.id.clone(),
+            name: p.name.clone(),
+            version: p.version.clone(),
+            entry: p.entry.clone(),
+            folder: p.folder.clone(),
+            md5: p.md5.clone(),
+            hash_algo: p.hash_algo.clone(),
+            hash: p.hash.clone(),
+            bundled_profiles: p.bundled_profiles.clone().unwrap_or_default(),
+            bundled_profile_paths: p.bundled_profile_paths.clone().unwrap_or_default(),
+            implicit: p.implicit,
+            files,
+        });
+    }
+    let migrated = Manifest {
+        format_version: to_version,
+        neko_base_version: manifest.neko_base_version.clone(),
+        packed_at: manifest.packed_at.clone(),
+        packed_by: manifest.packed_by.clone().unwrap_or_else(|| PACKED_BY.to_string()),
+        root_layout: manifest.root_layout.clone(),
+        compression: manifest.compression.clone().unwrap_or_else(|| "deflated".to_string()),
+        compression_level: manifest.compression_level,
+        bundle,
+        bundle_profiles_root: manifest.bundle_profiles_root.clone(),
+        plugins,
+        extras: manifest
+            .extras
+            .iter()
+            .map(|f| ManifestFile {
+                path: f.path.clone(),
+                size: f.size,
+                sha256: f.sha256.clone(),
+            })
+            .collect(),
+    };
+
+    let tmp_path = out.with_extension("zip.tmp");
+    let written = (|| -> Result<()> {
+        let f = fs::File::create(&tmp_path).with_context(|| PathContext::new("failed to create", &tmp_path))?;
+        let mut zip = zip::ZipWriter::new(f);
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i).with_context(|| PathContext::new("failed to read zip", src))?;
+            match file.name() {
+                SIGNATURE_ENTRY => continue,
+                "manifest.toml" => {
+                    // Same compression and timestamp as the manifest it replaces
+                    let options = FileOptions::<()>::default()
+                        .compression_method(file.compression())
+                        .last_modified_time(file.last_modified().unwrap_or_default());
+                    drop(file);
+                    write_manifest_entry(&mut zip, &migrated, options)?;
+                }
+                _ => zip.raw_copy_file(file)?,
+            }
+        }
+        zip.finish().with_context(|| PathContext::new("failed to write", &tmp_path))?;
+        Ok(())
+    })();
+    drop(archive);
+    let finished = written.and_then(|()| {
+        let signed_by = options
+            .sign_key
+            .as_ref()
+            .map(|key| sign_bundle(&tmp_path, key, options.source_date_epoch))
+            .transpose()?;
+        let policy = options.sign_key.as_ref().map(|key| SignaturePolicy {
+            key: key.verifying_key(),
+            required: true,
+        });
+        let verify = verify_bundle_report(&tmp_path, policy.as_ref())?;
+        if let Some(issue) = verify.issues.first() {
+            anyhow::bail!("migrated bundle fails verification: [{}] {}", issue.code, issue.message);
+        }
+        fs::rename(&tmp_path, out).with_context(|| PathContext::new("failed to write", out))?;
+        Ok((signed_by, verify))
+    });
+    let (signed_by, verify) = finished.inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_path);
+    })?;
+    Ok(MigrateReport {
+        from_version: manifest.format_version,
+        to_version,
+        out: out.to_path_buf(),
+        filled,
+        file_checksums,
+        dropped_signature,
+        signed_by,
+        verify,
+    })
+}
+
+/// Per-file checksums of the entries under a plugin's bundle folder, in folder_hash order
+fn bundle_plugin_files<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, folder: &str) -> Result<Vec<ManifestFile>> {
+    let prefix = format!("{}/", folder.trim_end_matches('/'));
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).context("failed to read zip entry")?;
+        let Some(rel) = file.name().strip_prefix(prefix.as_str()).map(str::to_string) else {
+            continue;
+        };
+        if !file.is_file() {
+            continue;
+        }
+        let mut digest = Sha256::new();
+        let size = std::io::copy(&mut file, &mut digest).with_context(|| format!("failed to read {}", file.name()))?;
+        files.push(ManifestFile {
+            path: rel,
+            size,
+            sha256: format!("{:x}", digest.finalize()),
+        });
+    }
+    files.sort_by_cached_key(|f| f.path.replace('/', std::path::MAIN_SEPARATOR_STR));
+    Ok(files)
+}
+
+/// Read every entry once. Plugin files are checked against the per-file checksums (when present) and
+/// fed to a folder hasher in folder_hash order. Also returns the entries that belong to no plugin and
+/// are neither the manifest, its signature nor a listed bundled profile.
+fn verify_archive_contents<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    manifest: &ManifestDe,
+    problems: &mut VerifyProblems,
+) -> Result<(VerifyReport, Vec<String>)> {
+    use std::collections::{HashMap, HashSet};
+    let folders: Vec<String> = manifest
+        .plugins
+        .iter()
+        .map(|p| format!("{}/", p.folder.trim_end_matches('/')))
+        .collect();
+    let profiles: HashSet<&str> = manifest
+        .plugins
+        .iter()
+        .flat_map(|p| p.bundled_profiles.iter().flatten())
+        .map(String::as_str)
+        .collect();
+    let extras: HashMap<&str, &ManifestFileDe> = manifest.extras.iter().map(|f| (f.path.as_str(), f)).collect();
+
+    let mut report = VerifyReport {
+        plugins: manifest.plugins.len(),
+        ..Default::default()
+    };
+    let mut per_plugin: Vec<Vec<(String, usize)>> = vec![Vec::new(); manifest.plugins.len()];
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut unlisted = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).context("failed to read zip entry")?;
+        if !file.is_file() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let plugin = folders
+            .iter()
+            .enumerate()
+            .find_map(|(k, f)| name.strip_prefix(f.as_str()).map(|rel| (k, rel.to_string())));
+        if let Some((k, rel)) = plugin {
+            per_plugin[k].push((rel, i));
+            continue;
+        }
+        // Outside the plugin folders: read it anyway so CRC errors surface
+        report.entries += 1;
+        let extra = name.strip_prefix(EXTRAS_PREFIX).and_then(|rel| extras.get(rel));
+        let mut digest = Sha256::new();
+        let size = match std::io::copy(&mut file, &mut digest) {
+            Ok(size) => size,
+            Err(e) => {
+                problems.add("entry-unreadable", anyhow::Error::new(e).context(format!("failed to read {}", name)))?;
+                seen.insert(name);
+                continue;
+            }
+        };
+        report.bytes += size;
+        if let Some(f) = extra {
+            if size != f.size || format!("{:x}", digest.finalize()) != f.sha256 {
+                problems.add("checksum-mismatch", anyhow::anyhow!("{} does not match its manifest checksum", name))?;
+            } else {
+                report.file_checksums += 1;
+            }
+        } else if name != "manifest.toml" && name != SIGNATURE_ENTRY && !profiles.contains(name.as_str()) {
+            unlisted.push(name.clone());
+        }
+        seen.insert(name);
+    }
+    let mut missing: Vec<&&str> = profiles.iter().filter(|n| !seen.contains(**n)).collect();
+    missing.sort();
+    for name in missing {
+        problems.add("profile-missing", anyhow::anyhow!("bundled profile {} is missing from the bundle", name))?;
+    }
+    let mut missing: Vec<&&str> = extras
+        .keys()
+        .filter(|rel| !seen.contains(&format!("{}{}", EXTRAS_PREFIX, rel)))
+        .collect();
+    missing.sort();
+    for rel in missing {
+        problems.add(
+            "file-missing",
+            anyhow::anyhow!("{}{} is listed in the manifest but missing from the bundle", EXTRAS_PREFIX, rel),
+        )?;
+    }
+
+    for (p, mut entries) in manifest.plugins.iter().zip(per_plugin) {
+        if entries.is_empty() {
+            problems.add(
+                "plugin-empty",
+                anyhow::anyhow!("plugin {} has no entries under {}/", p.id, p.folder.trim_end_matches('/')),
+            )?;
+            continue;
+        }
+        // folder_hash orders files by their native relative path
+        entries.sort_by_cached_key(|(rel, _)| rel.replace('/', std::path::MAIN_SEPARATOR_STR));
+        let mut listed: Option<HashMap<&str, &ManifestFileDe>> = p
+            .files
+            .as_ref()
+            .map(|files| files.iter().map(|f| (f.path.as_str(), f)).collect());
+        let mut folder = p.expected_hash().map(|(algo, expected)| (FolderHasher::new(algo), algo, expected));
+
+        for (rel, i) in &entries {
+            let mut file = archive.by_index(*i).context("failed to read zip entry")?;
+            let name = file.name().to_string();
+            let expected = match listed.as_mut() {
+                Some(listed) => match listed.remove(rel.as_str()) {
+                    Some(f) => Some(f),
+                    None => {
+                        problems.add("entry-unlisted", anyhow::anyhow!("{} is not listed in the manifest", name))?;
+                        None
+                    }
+                },
+                None => None,
+            };
+            if let Some((h, ..)) = folder.as_mut() {
+                h.update(rel.as_bytes());
+                h.update(&[0u8]);
+            }
+            let mut digest = Sha256::new();
+            let mut buf = [0u8; 1024 * 64];
+            let mut size = 0u64;
+            let read = loop {
+                let n = match file.read(&mut buf) {
+                    Ok(n) => n,
+                    Err(e) => break Err(e),
+                };
+                if n == 0 {
+                    break Ok(());
+                }
+                if expected.is_some() {
+                    digest.update(&buf[..n]);
+                }
+                if let Some((h, ..)) = folder.as_mut() {
+                    h.update(&buf[..n]);
+                }
+                size += n as u64;
+            };
+            if let Err(e) = read {
+                problems.add("entry-unreadable", anyhow::Error::new(e).context(format!("failed to read {}", name)))?;
+                // The folder hash cannot be finished over a file that does not read back
+                folder = None;
+                report.entries += 1;
                 continue;
             }
+            if let Some((h, ..)) = folder.as_mut() {
+                h.update(&[0u8]);
+            }
+            if let Some(f) = expected {
+                if size != f.size || format!("{:x}", digest.finalize()) != f.sha256 {
+                    problems.add("checksum-mismatch", anyhow::anyhow!("{} does not match its manifest checksum", name))?;
+                } else {
+                    report.file_checksums += 1;
+                }
+            }
+            report.entries += 1;
+            report.bytes += size;
         }
 
-        let name = plugin
-            .and_then(|v| v.get("name"))
-            .and_then(|v| v.as_str())
-            .unwrap_or(&id)
-            .to_string();
-        let version = plugin
-            .and_then(|v| v.get("version"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-        let entry_str = plugin
-            .and_then(|v| v.get("entry"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-
-        out.push(PluginPackItem {
-            id,
-            name,
-            version,
-            entry: entry_str,
-            folder,
-            path,
-            md5: None,
-        });
+        let mut missing: Vec<&str> = listed.map(|l| l.into_keys().collect()).unwrap_or_default();
+        missing.sort();
+        for rel in missing {
+            problems.add(
+                "file-missing",
+                anyhow::anyhow!(
+                    "{}/{} is listed in the manifest but missing from the bundle",
+                    p.folder.trim_end_matches('/'),
+                    rel
+                ),
+            )?;
+        }
+        if let Some((h, algo, expected)) = folder {
+            let actual = h.finish_hex();
+            if actual != expected {
+                problems.add(
+                    "hash-mismatch",
+                    anyhow::anyhow!(
+                        "{} hash of plugin {} does not match the manifest (manifest {}, archive {})",
+                        algo.as_str(),
+                        p.id,
+                        expected,
+                        actual
+                    ),
+                )?;
+            } else {
+                report.folder_hashes += 1;
+            }
+        }
     }
+    Ok((report, unlisted))
+}
 
-    out.sort_by(|a, b| a.id.cmp(&b.id));
-    Ok(out)
+/// Progress events from unpack_zip and unpack_reader
+#[derive(Debug, Clone, Copy)]
+pub enum UnpackProgress<'a> {
+    /// A plugin is about to be extracted
+    PluginStart { plugin: &'a str, folder: &'a str },
+    /// `index` counts the files extracted so far; `total` is the number of entries in the archive
+    FileExtracted { name: &'a str, bytes: u64, index: usize, total: usize },
+    /// Sent for each plugin left alone, before extraction starts
+    PluginSkipped { plugin: &'a str, reason: &'a str },
+    Done { installed: usize, skipped: usize },
 }
 
-pub(crate) fn default_pack_output(plugins: &[PluginPackItem], single: bool) -> PathBuf {
-    if single {
-        let p = &plugins[0];
-        return PathBuf::from(format!("neko_plugin_{}_{}.zip", p.id, p.version));
+pub type UnpackProgressFn<'a> = &'a (dyn Fn(UnpackProgress<'_>) + Sync);
+
+/// Forwards UnpackProgress events and numbers the extracted files
+struct UnpackReporter<'a> {
+    progress: Option<UnpackProgressFn<'a>>,
+    /// Held while an event is delivered, so events from parallel workers arrive one at a time and in index order
+    extracted: std::sync::Mutex<usize>,
+    total: usize,
+}
+
+impl UnpackReporter<'_> {
+    fn report(&self, ev: UnpackProgress<'_>) {
+        if let Some(cb) = self.progress {
+            let _serialized = self.extracted.lock().unwrap_or_else(|e| e.into_inner());
+            cb(ev);
+        }
+    }
+
+    fn file(&self, name: &str, bytes: u64) {
+        let mut extracted = self.extracted.lock().unwrap_or_else(|e| e.into_inner());
+        *extracted += 1;
+        if let Some(cb) = self.progress {
+            cb(UnpackProgress::FileExtracted {
+                name,
+                bytes,
+                index: *extracted,
+                total: self.total,
+            });
+        }
     }
-    let ts = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
-    PathBuf::from(format!("neko_plugins_bundle_{}.zip", ts.replace(':', "-")))
 }
 
-pub(crate) fn list_packable_plugin_ids(plugins_dir: &Path) -> Result<Vec<String>> {
-    let plugins = scan_plugins_for_pack(plugins_dir, None)?;
-    Ok(plugins.into_iter().map(|p| p.id).collect())
+/// Install the plugins of a bundle into `dest_dir`. The bundle's extras/ files are written to
+/// `extras_dest` when given and ignored otherwise. A bundle with symlink entries is refused
+/// unless `allow_symlinks` is set. With `backup_dir`, plugin folders about to be overwritten
+/// are first copied there (minus excluded files). Extracted files are checked against the
+/// manifest's per-file checksums unless `no_verify` is set; a plugin that fails the check is not installed.
+/// `assume_version` reads a manifest newer than MANIFEST_VERSION_MAX as that older version.
+/// `apply_profiles` also copies bundled profiles into the plugin's profiles.toml / profiles/.
+/// With `check`, the installed plugins are checked afterwards; a strict check that finds errors
+/// moves the previous versions back and fails. Plugin folders in `force_folders` are overwritten as
+/// if `force` were set (per-plugin answers from `unpack --interactive`). With `prune`, files of an
+/// overwritten plugin that the bundle does not carry are removed, except excluded files and profiles.
+/// Before anything is written, archives with NUL bytes, Windows device names, duplicate or case-colliding
+/// entry or directory names, implausible compression ratios or more than `max_uncompressed` declared bytes are
+/// refused. While extracting, an entry that holds more than its declared size fails as corrupt, and the bytes
+/// actually written count against `max_uncompressed` as well.
+/// With `staging`, every plugin is extracted and verified in that directory before any is moved into
+/// place, and a failed move puts the plugins already moved back the way they were.
+/// A zip without manifest.toml that holds a single plugin folder (plugin.toml at the top level or one
+/// directory deep) is installed as that plugin, with a warning; `no_manifest` always reads it that way.
+/// `progress` receives an UnpackProgress event per plugin and per extracted file.
+#[allow(clippy::too_many_arguments)]
+pub fn unpack_zip(
+    zip_path: &Path,
+    dest_dir: &Path,
+    force: bool,
+    excludes: &Excludes,
+    signature: Option<&SignaturePolicy>,
+    extras_dest: Option<&Path>,
+    allow_symlinks: bool,
+    backup_dir: Option<&Path>,
+    no_verify: bool,
+    assume_version: Option<u32>,
+    apply_profiles: ApplyProfiles,
+    check: Option<&UnpackCheck>,
+    force_folders: &[String],
+    prune: bool,
+    max_uncompressed: u64,
+    staging: Option<&Path>,
+    no_manifest: bool,
+    progress: Option<UnpackProgressFn<'_>>,
+) -> Result<UnpackResult> {
+    let archive = open_bundle(zip_path)?;
+    unpack_archive(
+        archive,
+        &|| open_bundle(zip_path),
+        zip_path,
+        dest_dir,
+        force,
+        excludes,
+        signature,
+        extras_dest,
+        allow_symlinks,
+        backup_dir,
+        no_verify,
+        assume_version,
+        apply_profiles,
+        check,
+        force_folders,
+        prune,
+        max_uncompressed,
+        staging,
+        no_manifest,
+        progress,
+        None,
+    )
 }
 
-pub(crate) fn build_excludes(extra: &[String]) -> Result<GlobSet> {
-    let mut b = GlobSetBuilder::new();
-    for pat in [
-        "**/__pycache__/**",
-        "**/*.pyc",
-        "**/.git/**",
-        "**/.venv/**",
-        "**/log/**",
-        "**/logs/**",
-    ] {
-        b.add(Glob::new(pat)?);
+/// unpack_zip for a bundle that is not a file, e.g. read from a pipe into memory.
+/// `source` only labels error messages. The reader is cloned for each extraction worker, so it
+/// should be cheap to clone, e.g. a `Cursor<Arc<[u8]>>`.
+#[allow(clippy::too_many_arguments)]
+pub fn unpack_reader<R: Read + std::io::Seek + Clone + Send + Sync>(
+    reader: R,
+    source: &Path,
+    dest_dir: &Path,
+    force: bool,
+    excludes: &Excludes,
+    signature: Option<&SignaturePolicy>,
+    extras_dest: Option<&Path>,
+    allow_symlinks: bool,
+    backup_dir: Option<&Path>,
+    no_verify: bool,
+    assume_version: Option<u32>,
+    apply_profiles: ApplyProfiles,
+    check: Option<&UnpackCheck>,
+    force_folders: &[String],
+    prune: bool,
+    max_uncompressed: u64,
+    staging: Option<&Path>,
+    no_manifest: bool,
+    progress: Option<UnpackProgressFn<'_>>,
+) -> Result<UnpackResult> {
+    let archive = ZipArchive::new(reader).with_context(|| PathContext::new("failed to read zip", source))?;
+    let template = archive.clone();
+    unpack_archive(
+        archive,
+        &|| Ok(template.clone()),
+        source,
+        dest_dir,
+        force,
+        excludes,
+        signature,
+        extras_dest,
+        allow_symlinks,
+        backup_dir,
+        no_verify,
+        assume_version,
+        apply_profiles,
+        check,
+        force_folders,
+        prune,
+        max_uncompressed,
+        staging,
+        no_manifest,
+        progress,
+        None,
+    )
+}
+
+/// `source_sha256` is the hash of the bundle as the user supplied it, when `archive` was derived
+/// from another zip
+#[allow(clippy::too_many_arguments)]
+fn unpack_archive<R: Read + std::io::Seek>(
+    archive: ZipArchive<R>,
+    reopen: &(dyn Fn() -> Result<ZipArchive<R>> + Sync),
+    zip_path: &Path,
+    dest_dir: &Path,
+    force: bool,
+    excludes: &Excludes,
+    signature: Option<&SignaturePolicy>,
+    extras_dest: Option<&Path>,
+    allow_symlinks: bool,
+    backup_dir: Option<&Path>,
+    no_verify: bool,
+    assume_version: Option<u32>,
+    apply_profiles: ApplyProfiles,
+    check: Option<&UnpackCheck>,
+    force_folders: &[String],
+    prune: bool,
+    max_uncompressed: u64,
+    staging: Option<&Path>,
+    no_manifest: bool,
+    progress: Option<UnpackProgressFn<'_>>,
+    source_sha256: Option<String>,
+) -> Result<UnpackResult> {
+    let archive = check_archive_entries(archive, max_uncompressed)
+        .with_context(|| PathContext::new("refusing to unpack", zip_path))?;
+    let (mut archive, source_sha256) = match source_sha256 {
+        Some(known) => (archive, known),
+        None => archive_sha256(archive).with_context(|| PathContext::new("failed to read zip", zip_path))?,
+    };
+    if let Some(prefix) = plain_plugin_prefix(&archive, zip_path, no_manifest)? {
+        let (bundle, mut warnings) = plain_plugin_bundle(&mut archive, zip_path, &prefix, excludes, max_uncompressed)?;
+        if !no_manifest {
+            warnings.insert(
+                0,
+                format!("{} has no manifest.toml; unpacking it as a single plugin folder", zip_path.display()),
+            );
+        }
+        let bundle = ZipArchive::new(std::io::Cursor::new(std::sync::Arc::<[u8]>::from(bundle)))
+            .context("failed to read the rewritten plain plugin zip")?;
+        let template = bundle.clone();
+        let mut result = unpack_archive(
+            bundle,
+            &|| Ok(template.clone()),
+            zip_path,
+            dest_dir,
+            force,
+            excludes,
+            signature,
+            extras_dest,
+            allow_symlinks,
+            backup_dir,
+            no_verify,
+            assume_version,
+            apply_profiles,
+            check,
+            force_folders,
+            prune,
+            max_uncompressed,
+            staging,
+            false,
+            progress,
+            Some(source_sha256),
+        )?;
+        warnings.append(&mut result.warnings);
+        result.warnings = warnings;
+        return Ok(result);
     }
-    for pat in extra {
-        b.add(Glob::new(pat)?);
+    let mut result = UnpackResult::default();
+    if let Some(policy) = signature {
+        result.signed_by = verify_bundle_signature(&mut archive, policy)
+            .with_context(|| PathContext::new("signature verification failed for", zip_path))?;
+        if result.signed_by.is_none() {
+            result
+                .warnings
+                .push("bundle is not signed; its contents were not verified".to_string());
+        }
     }
-    Ok(b.build()?)
-}
 
-pub(crate) fn folder_md5(plugin_dir: &Path, excludes: &GlobSet) -> Result<String> {
-    let mut files: Vec<PathBuf> = Vec::new();
-    for e in WalkDir::new(plugin_dir).follow_links(false) {
-        let e = e?;
-        if !e.file_type().is_file() {
-            continue;
+    // pack never writes links, so they only show up in hand-made zips; refuse before writing anything
+    let links: Vec<String> = (0..archive.len())
+        .filter_map(|i| archive.by_index_raw(i).ok().filter(|f| f.is_symlink()).map(|f| f.name().to_string()))
+        .collect();
+    if !links.is_empty() && !allow_symlinks {
+        anyhow::bail!(
+            "{} contains symlink entries (use --allow-symlinks to create them): {}",
+            zip_path.display(),
+            links.join(", ")
+        );
+    }
+
+    let dest_dir = &long_path(dest_dir)?;
+    fs::create_dir_all(dest_dir).with_context(|| PathContext::new("failed to create dest dir", dest_dir))?;
+    let manifest = read_manifest_as(&mut archive, assume_version)
+        .with_context(|| PathContext::new("invalid bundle", zip_path))?;
+    if let Some(actual) = manifest.assumed_from {
+        result.warnings.push(format!(
+            "bundle manifest format_version {} read as version {} (--assume-version); newer fields are ignored",
+            actual, manifest.format_version
+        ));
+    }
+    result.bundle = manifest.bundle.as_ref().map(ManifestBundleDe::meta);
+    let provenance = BundleProvenance::for_bundle(&manifest, zip_path, &source_sha256);
+    let reporter = UnpackReporter {
+        progress,
+        extracted: std::sync::Mutex::new(0),
+        total: archive.len(),
+    };
+    let root_layout = manifest.root_layout.trim_end_matches('/');
+
+    // Map plugin_id -> folder_name (the folder under <dest_dir>)
+    let mut id_to_folder: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    for p in &manifest.plugins {
+        let folder_rel = p.folder.trim_end_matches('/');
+        let folder_name = folder_rel
+            .split('/')
+            .nth(1)
+            .ok_or_else(|| anyhow::anyhow!("invalid manifest folder: {}", p.folder))?
+            .to_string();
+        id_to_folder.insert(p.id.clone(), folder_name);
+    }
+
+    let bundle_profiles_root = manifest
+        .bundle_profiles_root
+        .as_deref()
+        .map(|s| s.trim_end_matches('/').to_string());
+
+    // zip entry name -> per-file checksum (format_version 2)
+    let mut expected_files: std::collections::HashMap<String, &ManifestFileDe> = std::collections::HashMap::new();
+    // bundled profile entry name -> (plugin id, path relative to the plugin folder)
+    let mut profile_paths: std::collections::HashMap<&str, (&str, Option<&str>)> = std::collections::HashMap::new();
+    // plugins to write, with the number of files their checksums cover
+    let mut to_install: Vec<(UnpackedPlugin, usize)> = Vec::new();
+
+    for p in &manifest.plugins {
+        let folder_name = id_to_folder[&p.id].clone();
+
+        if let Some(files) = p.files.as_ref().filter(|_| !no_verify) {
+            for f in files {
+                expected_files.insert(format!("{}/{}/{}", root_layout, folder_name, f.path), f);
+            }
         }
-        let p = e.path().to_path_buf();
-        let rel = p
-            .strip_prefix(plugin_dir)
-            .unwrap_or(e.path())
-            .to_string_lossy()
-            .replace('\\', "/");
-        if excludes.is_match(&rel) {
-            continue;
+        if apply_profiles != ApplyProfiles::Skip {
+            let sources = p.bundled_profile_paths.as_deref().unwrap_or_default();
+            for (i, name) in p.bundled_profiles.iter().flatten().enumerate() {
+                profile_paths.insert(name, (&p.id, sources.get(i).map(String::as_str)));
+            }
+        }
+
+        let target_folder = dest_dir.join(&folder_name);
+        let mut reason = "installed".to_string();
+        let mut changed_files = Vec::new();
+        if target_folder.is_dir() {
+            result
+                .warnings
+                .push(format!("plugin folder already exists: {}", target_folder.display()));
+            if let Some(files) = &p.files {
+                changed_files = diff_plugin_files(&target_folder, files, excludes)?;
+            }
+
+            let chosen = force_folders.contains(&folder_name);
+            let skip_reason = match existing_plugin_state(p, &target_folder, excludes)? {
+                ExistingState::Identical(method) => Some(format!("identical ({} match)", method)),
+                _ if force || chosen => None,
+                ExistingState::Differs => Some("differs from existing (use --force to overwrite)".to_string()),
+                ExistingState::Unknown => Some("exists without checksum (use --force to overwrite)".to_string()),
+            };
+            if let Some(reason) = skip_reason {
+                reporter.report(UnpackProgress::PluginSkipped {
+                    plugin: &p.id,
+                    reason: &reason,
+                });
+                result.skipped.push(UnpackedPlugin {
+                    id: p.id.clone(),
+                    folder: folder_name,
+                    reason,
+                    changed_files,
+                    verification: None,
+                    pruned: Vec::new(),
+                    filtered: Vec::new(),
+                    written: None,
+                    overwrote: false,
+                });
+                continue;
+            }
+            reason = if force { "overwritten (--force)" } else { "overwritten (chosen)" }.to_string();
         }
-        files.push(p);
+        let overwrote = target_folder.is_dir();
+        let (verification, expected) = match &p.files {
+            _ if no_verify => (Verification::Skipped, 0),
+            Some(files) => (Verification::Verified, files.len()),
+            None => (Verification::NoChecksums, 0),
+        };
+        to_install.push((
+            UnpackedPlugin {
+                id: p.id.clone(),
+                folder: folder_name,
+                reason,
+                changed_files,
+                verification: Some(verification),
+                pruned: Vec::new(),
+                filtered: Vec::new(),
+                written: None,
+                overwrote,
+            },
+            expected,
+        ));
     }
 
-    files.sort_by(|a, b| {
-        a.strip_prefix(plugin_dir)
-            .unwrap_or(a)
-            .to_string_lossy()
-            .cmp(&b.strip_prefix(plugin_dir).unwrap_or(b).to_string_lossy())
-    });
+    let layout = UnpackLayout {
+        plugins_prefix: format!("{}/", root_layout),
+        profiles_prefix: bundle_profiles_root.as_ref().map(|root| format!("{}/plugins/", root)),
+        profiles_root: bundle_profiles_root.unwrap_or_default(),
+        expected_files,
+        apply_profiles,
+        profile_paths,
+        excludes,
+        prune,
+        budget: UnpackBudget::new(max_uncompressed),
+    };
+    let mut entries = group_plugin_entries(&mut archive, zip_path, &layout, &id_to_folder, &mut result)?;
+
+    // Bundled profiles still land in skipped plugin folders (they never overwrite without --force)
+    let skipped: Vec<String> = result.skipped.iter().map(|p| p.folder.clone()).collect();
+    for folder in skipped {
+        if let Some(group) = entries.remove(&folder) {
+            let target = dest_dir.join(&folder);
+            write_plugin_entries(
+                &mut archive,
+                zip_path,
+                &group.profiles,
+                &layout,
+                &folder,
+                &target,
+                &target,
+                force,
+                &reporter,
+                &mut WrittenEntries::default(),
+                &mut result,
+            )?;
+        }
+    }
 
-    let mut hasher = Md5Context::new();
-    for p in files {
-        let rel = p
-            .strip_prefix(plugin_dir)
-            .unwrap_or(&p)
-            .to_string_lossy()
-            .replace('\\', "/");
-        hasher.consume(rel.as_bytes());
-        hasher.consume([0u8]);
+    // Each plugin is extracted into its own staging folder and only moved into place once all of its
+    // files were written, so a failure never leaves a half-written plugin behind. Plugins are staged
+    // in parallel, each worker reading the bundle through its own archive, then moved into place one
+    // at a time in bundle order: a plugin that fails leaves those before it installed, the rest untouched.
+    let staging_root = match staging {
+        Some(dir) => long_path(dir)?,
+        None => dest_dir.join(UNPACK_STAGING_DIR),
+    };
+    fs::create_dir_all(&staging_root).with_context(|| PathContext::new("failed to create", &staging_root))?;
+    let backup_dir = backup_dir.map(long_path).transpose()?;
+    let mut jobs: Vec<(UnpackedPlugin, usize, PluginEntries)> = to_install
+        .into_iter()
+        .map(|(p, expected)| {
+            let group = entries.remove(&p.folder).unwrap_or_default();
+            (p, expected, group)
+        })
+        .collect();
+    let mut staged: Vec<Result<(StagedPlugin, UnpackResult)>> = jobs
+        .par_iter()
+        .map_init(reopen, |archive, (p, expected, group)| {
+            let archive = archive.as_mut().map_err(|e| anyhow::anyhow!("{:#}", e))?;
+            reporter.report(UnpackProgress::PluginStart {
+                plugin: &p.id,
+                folder: &p.folder,
+            });
+            let force = force || force_folders.contains(&p.folder);
+            let mut local = UnpackResult::default();
+            let staged = stage_plugin(
+                archive,
+                zip_path,
+                group,
+                &layout,
+                &p.folder,
+                dest_dir,
+                &staging_root,
+                force,
+                *expected,
+                &reporter,
+                &mut local,
+            )?;
+            if let Some(plugin) = manifest.plugins.iter().find(|m| m.id == p.id) {
+                provenance.write(plugin, &staging_root.join(&p.folder))?;
+            }
+            Ok((staged, local))
+        })
+        .collect();
 
-        let mut f = fs::File::open(&p).with_context(|| format!("failed to open {}", p.display()))?;
-        let mut buf = [0u8; 1024 * 64];
-        loop {
-            let n = f.read(&mut buf)?;
-            if n == 0 {
-                break;
+    let folders: Vec<String> = jobs.iter().map(|(p, ..)| p.folder.clone()).collect();
+    // Staged activation: nothing is moved into place unless every plugin was staged
+    if let Some(i) = staging.and_then(|_| staged.iter().position(Result::is_err)) {
+        for folder in &folders {
+            let _ = fs::remove_dir_all(staging_root.join(folder));
+        }
+        let _ = fs::remove_dir(&staging_root);
+        let ((p, ..), Err(e)) = (jobs.swap_remove(i), staged.swap_remove(i)) else {
+            unreachable!("position() found a failed plugin");
+        };
+        return Err(unpack_stopped(e, p, result, None));
+    }
+    for (i, ((mut p, ..), staged)) in jobs.into_iter().zip(staged).enumerate() {
+        let target = dest_dir.join(&p.folder);
+        let mut backup = None;
+        let swapped = staged.and_then(|staged| {
+            if let Some(dir) = backup_dir.as_ref().filter(|_| target.is_dir()) {
+                backup = Some(backup_plugin(&target, dir, &p.folder, excludes)?);
+            }
+            swap_in_staged(&staging_root, dest_dir, &p.folder)?;
+            Ok(staged)
+        });
+        let local = match swapped {
+            Ok((staged, local)) => {
+                p.pruned = staged.pruned;
+                p.filtered = staged.filtered;
+                p.written = Some(staged.written);
+                local
+            }
+            Err(e) => {
+                for folder in &folders[i..] {
+                    let _ = fs::remove_dir_all(staging_root.join(folder));
+                }
+                // Nothing was overwritten, so the copy would only be clutter
+                if let Some(path) = &backup {
+                    let _ = fs::remove_dir_all(path);
+                }
+                let rollback = if staging.is_some() {
+                    let failed = roll_back_installed(dest_dir, &staging_root, &result.installed);
+                    for b in result.backups.drain(..) {
+                        let _ = fs::remove_dir_all(&b.path);
+                    }
+                    let restored = std::mem::take(&mut result.installed)
+                        .into_iter()
+                        .map(|p| p.id)
+                        .filter(|id| !failed.iter().any(|(f, _)| f == id))
+                        .collect();
+                    Some(UnpackRollback {
+                        restored,
+                        failed: failed.into_iter().map(|(id, e)| format!("{}: {}", id, e)).collect(),
+                    })
+                } else {
+                    discard_previous(&staging_root, &result.installed);
+                    None
+                };
+                let _ = fs::remove_dir(&staging_root);
+                return Err(unpack_stopped(e, p, result, rollback));
+            }
+        };
+        result.warnings.extend(local.warnings);
+        result.symlinks.extend(local.symlinks);
+        result.profiles.extend(local.profiles);
+        if let Some(path) = backup {
+            result.backups.push(UnpackBackup {
+                id: p.id.clone(),
+                folder: p.folder.clone(),
+                path,
+            });
+        }
+        result.installed.push(p);
+        // A strict check or a later failed activation may still need the previous version
+        if !check.is_some_and(|c| c.strict) && staging.is_none() {
+            discard_previous(&staging_root, &result.installed[result.installed.len() - 1..]);
+        }
+    }
+    if let Some(check) = check {
+        let ids: Vec<String> = result.installed.iter().map(|p| p.id.clone()).collect();
+        let report = run_checks_for(dest_dir, &ids, &check.sdk_version, check.checks);
+        match report {
+            Ok(report) if check.strict && !report.errors.is_empty() => {
+                roll_back_installed(dest_dir, &staging_root, &result.installed);
+                for b in &result.backups {
+                    let _ = fs::remove_dir_all(&b.path);
+                }
+                let _ = fs::remove_dir(&staging_root);
+                anyhow::bail!(
+                    "post-unpack check found {} error(s), so the install was rolled back:\n  {}",
+                    report.errors.len(),
+                    report.errors.join("\n  ")
+                );
+            }
+            Ok(report) => result.check = Some(report),
+            Err(e) => {
+                if check.strict {
+                    roll_back_installed(dest_dir, &staging_root, &result.installed);
+                } else {
+                    discard_previous(&staging_root, &result.installed);
+                }
+                let _ = fs::remove_dir(&staging_root);
+                return Err(e.context("post-unpack check failed"));
             }
-            hasher.consume(&buf[..n]);
         }
-        hasher.consume([0u8]);
+    }
+    discard_previous(&staging_root, &result.installed);
+    let _ = fs::remove_dir(&staging_root);
+    let mut unlisted: Vec<String> = entries
+        .into_iter()
+        .filter(|(_, group)| !group.payload.is_empty())
+        .map(|(folder, _)| folder)
+        .collect();
+    unlisted.sort();
+    for folder in unlisted {
+        result
+            .warnings
+            .push(format!("skipped folder not listed in the manifest: {}{}", layout.plugins_prefix, folder));
+    }
+
+    if let Some(extras_dest) = extras_dest {
+        unpack_extras(
+            &mut archive,
+            &manifest,
+            zip_path,
+            &long_path(extras_dest)?,
+            force,
+            &layout.budget,
+            &mut result,
+        )?;
     }
 
-    Ok(format!("{:x}", hasher.compute()))
+    reporter.report(UnpackProgress::Done {
+        installed: result.installed.len(),
+        skipped: result.skipped.len(),
+    });
+    Ok(result)
 }
 
-pub(crate) fn pack_to_zip(
-    out_path: &Path,
-    plugins: &[PluginPackItem],
-    excludes: &GlobSet,
-    bundle_meta: BundleMeta,
-) -> Result<()> {
-    let tmp_path = out_path.with_extension("zip.tmp");
-    let f = fs::File::create(&tmp_path).with_context(|| format!("failed to create {}", tmp_path.display()))?;
-    let mut zip = zip::ZipWriter::new(f);
+/// Drop the `<folder>.old` copies stage_plugin leaves behind once a plugin is final
+fn discard_previous(staging_root: &Path, installed: &[UnpackedPlugin]) {
+    for p in installed {
+        let _ = remove_stale(&staging_root.join(format!("{}.old", p.folder)));
+    }
+}
 
-    let options = FileOptions::<()>::default().compression_method(CompressionMethod::Deflated);
+/// Undo this run's installs: remove each installed folder and move its previous version back.
+/// Returns the plugins that could not be restored, with the error.
+fn roll_back_installed(dest_dir: &Path, staging_root: &Path, installed: &[UnpackedPlugin]) -> Vec<(String, String)> {
+    let mut failed = Vec::new();
+    for p in installed.iter().rev() {
+        let target = dest_dir.join(&p.folder);
+        let previous = staging_root.join(format!("{}.old", p.folder));
+        let undone = fs::remove_dir_all(&target)
+            .with_context(|| PathContext::new("failed to remove", &target))
+            .and_then(|_| if previous.is_dir() { move_dir(&previous, &target) } else { Ok(()) });
+        if let Err(e) = undone {
+            failed.push((p.id.clone(), format!("{:#}", e)));
+        }
+    }
+    failed
+}
 
-    let repo_root = plugins
-        .first()
-        .and_then(|p| p.path.parent())
-        .and_then(|p| p.parent())
-        .and_then(|p| p.parent())
-        .map(Path::to_path_buf)
-        .context("failed to infer repo root from plugin path")?;
+fn unpack_stopped(
+    e: anyhow::Error,
+    plugin: UnpackedPlugin,
+    done: UnpackResult,
+    rollback: Option<UnpackRollback>,
+) -> anyhow::Error {
+    e.context(UnpackStopped { plugin, done, rollback })
+}
 
-    let neko_base_version = read_neko_base_version(&repo_root)?;
+/// What a staged unpack (`--staged`) undid after moving a plugin into place failed
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct UnpackRollback {
+    /// Plugins put back the way they were before the run
+    pub restored: Vec<String>,
+    /// Plugins that could not be put back, as "id: error"
+    pub failed: Vec<String>,
+}
 
-    let bundle_name = bundle_meta
-        .name
-        .clone()
-        .unwrap_or_else(|| derive_bundle_name(out_path));
-    let bundle_name_safe = sanitize_for_filename(&bundle_name);
-    let bundle_version_safe = bundle_meta
-        .version
-        .as_deref()
-        .map(sanitize_for_filename)
-        .unwrap_or_else(|| "unknown".to_string());
-    let bundle_profiles_root = format!("bundle_profiles/{}/", bundle_name_safe);
+/// Context of an unpack that failed part way: the plugin it stopped at and what it did before.
+/// Reach it with `err.downcast_ref::<UnpackStopped>()`, e.g. to report the partial run.
+#[derive(Debug)]
+pub struct UnpackStopped {
+    pub plugin: UnpackedPlugin,
+    /// Plugins installed and skipped before the failure; a staged unpack lists none once rolled back
+    pub done: UnpackResult,
+    /// Set when a staged unpack failed while moving plugins into place
+    pub rollback: Option<UnpackRollback>,
+}
 
-    // Pre-compute bundled profile paths for each plugin.
-    let mut bundled_profiles_map: Vec<Vec<String>> = Vec::new();
-    for p in plugins {
-        let mut paths: Vec<String> = Vec::new();
-        for src in collect_profile_files(&p.path) {
-            let rel = src
-                .strip_prefix(&p.path)
-                .unwrap_or(&src)
-                .to_string_lossy()
-                .replace('\\', "/");
-            let rel_name = sanitize_for_filename(&rel.replace('/', "__"));
-            let zip_path = format!(
-                "{}plugins/{}/{}__{}__{}__{}",
-                bundle_profiles_root,
-                sanitize_for_filename(&p.id),
-                bundle_name_safe,
-                bundle_version_safe,
-                sanitize_for_filename(&p.id),
-                rel_name
+impl std::fmt::Display for UnpackStopped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |ids: Vec<&str>| if ids.is_empty() { "none".to_string() } else { ids.join(", ") };
+        let Some(rollback) = &self.rollback else {
+            let done = self.done.installed.iter().map(|p| p.id.as_str()).collect();
+            return write!(
+                f,
+                "unpack stopped at plugin '{}'; fully installed before the failure: {}",
+                self.plugin.id,
+                list(done)
             );
-            paths.push(zip_path);
+        };
+        write!(
+            f,
+            "unpack stopped while activating plugin '{}'; rolled back: {}",
+            self.plugin.id,
+            list(rollback.restored.iter().map(String::as_str).collect())
+        )?;
+        if !rollback.failed.is_empty() {
+            write!(f, "; could not roll back: {}", rollback.failed.join("; "))?;
         }
-        bundled_profiles_map.push(paths);
+        Ok(())
     }
+}
 
-    let manifest = Manifest {
-        format_version: 1,
-        neko_base_version,
-        packed_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
-        root_layout: "plugins/".to_string(),
-        bundle: Some(ManifestBundle {
-            name: bundle_name,
-            version: bundle_meta.version,
-            author: bundle_meta.author,
-        }),
-        bundle_profiles_root: Some(bundle_profiles_root.clone()),
-        plugins: plugins
-            .iter()
-            .zip(bundled_profiles_map.iter())
-            .map(|(p, paths)| ManifestPlugin {
-                id: p.id.clone(),
-                name: p.name.clone(),
-                version: p.version.clone(),
-                entry: p.entry.clone(),
-                folder: format!("plugins/{}", p.folder),
-                md5: p.md5.clone(),
-                bundled_profiles: paths.clone(),
-            })
-            .collect(),
-    };
+/// Copy an installed plugin folder to `<backup_dir>/<folder>_<timestamp>`, leaving out what
+/// `excludes` leaves out of folder hashes (virtualenvs, logs, caches)
+fn backup_plugin(target: &Path, backup_dir: &Path, folder: &str, excludes: &Excludes) -> Result<PathBuf> {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut path = backup_dir.join(format!("{}_{}", folder, stamp));
+    let mut n = 2;
+    while path.symlink_metadata().is_ok() {
+        path = backup_dir.join(format!("{}_{}_{}", folder, stamp, n));
+        n += 1;
+    }
+    fs::create_dir_all(&path).with_context(|| PathContext::new("failed to create backup dir", &path))?;
+    for (rel, src) in list_plugin_files(target, excludes)? {
+        let out = join_rel(&path, &rel);
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent).with_context(|| PathContext::new("failed to create", parent))?;
+        }
+        fs::copy(&src, &out).with_context(|| PathContext::new("failed to back up", &src))?;
+    }
+    Ok(path)
+}
+
+/// Default for `unpack --backup`, relative to the plugin directory
+pub const UNPACK_BACKUP_DIR: &str = ".backups";
+
+/// Directory under the unpack destination where plugins are assembled before being moved into place
+pub const UNPACK_STAGING_DIR: &str = ".neko_unpack_tmp";
+
+/// Written into a plugin folder that unpack installed without some of its files (`--exclude`);
+/// lists the globs that left files out. Never hashed, packed or pruned.
+pub const UNPACK_EXCLUDES_MARKER: &str = ".neko_unpack_excludes";
+
+/// Written into every plugin folder unpack installs: the bundle it came from and when (BundleProvenance).
+/// Never hashed, packed or pruned.
+pub const UNPACK_PROVENANCE_FILE: &str = ".neko_bundle.toml";
+
+/// Where an installed plugin came from, as unpack records it in UNPACK_PROVENANCE_FILE
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleProvenance {
+    pub plugin_id: String,
+    pub plugin_version: String,
+    pub bundle_name: Option<String>,
+    pub bundle_version: Option<String>,
+    pub bundle_author: Option<String>,
+    pub packed_at: String,
+    /// File name of the bundle (or what a piped bundle was labelled with)
+    pub source_zip: String,
+    pub source_sha256: String,
+    pub installed_at: String,
+    /// sha256 folder hash right after the install, so later edits show up
+    pub folder_sha256: String,
+}
+
+impl BundleProvenance {
+    /// The bundle half of the record; the plugin fields are filled in by `write`
+    fn for_bundle(manifest: &ManifestDe, zip_path: &Path, source_sha256: &str) -> Self {
+        let bundle = manifest.bundle.as_ref();
+        BundleProvenance {
+            plugin_id: String::new(),
+            plugin_version: String::new(),
+            bundle_name: bundle.map(|b| b.name.clone()),
+            bundle_version: bundle.and_then(|b| b.version.clone()),
+            bundle_author: bundle.and_then(|b| b.author.clone()),
+            packed_at: manifest.packed_at.clone(),
+            source_zip: zip_path
+                .file_name()
+                .map_or_else(|| zip_path.display().to_string(), |n| n.to_string_lossy().into_owned()),
+            source_sha256: source_sha256.to_string(),
+            installed_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            folder_sha256: String::new(),
+        }
+    }
+
+    /// Record `plugin` as installed into `plugin_dir`, hashing the folder as it is now
+    fn write(&self, plugin: &ManifestPluginDe, plugin_dir: &Path) -> Result<()> {
+        let record = BundleProvenance {
+            plugin_id: plugin.id.clone(),
+            plugin_version: plugin.version.clone(),
+            folder_sha256: folder_hash(plugin_dir, &build_excludes(&[])?, HashAlgo::Sha256)?,
+            ..self.clone()
+        };
+        let path = plugin_dir.join(UNPACK_PROVENANCE_FILE);
+        let text = format!(
+            "# Written by neko-plugin-cli unpack: the bundle this plugin was installed from\n{}",
+            toml::to_string(&record)?
+        );
+        fs::write(&path, text).with_context(|| PathContext::new("failed to write", &path))
+    }
+
+    /// The record unpack left in `plugin_dir`, if any
+    pub fn read(plugin_dir: &Path) -> Result<Option<Self>> {
+        let path = plugin_dir.join(UNPACK_PROVENANCE_FILE);
+        let text = match fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| PathContext::new("failed to read", &path)),
+        };
+        Ok(Some(toml::from_str(&text).with_context(|| PathContext::new("failed to parse", &path))?))
+    }
+}
+
+/// sha256 of the whole bundle, read through the archive's own reader
+fn archive_sha256<R: Read + std::io::Seek>(archive: ZipArchive<R>) -> Result<(ZipArchive<R>, String)> {
+    let mut reader = archive.into_inner();
+    reader.seek(std::io::SeekFrom::Start(0))?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 1024 * 64];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    let archive = ZipArchive::new(reader).context("failed to re-read zip")?;
+    Ok((archive, format!("{:x}", hasher.finalize())))
+}
 
-    let manifest_text = toml::to_string(&manifest).context("failed to serialize manifest")?;
-    zip.start_file("manifest.toml", options)?;
-    zip.write_all(manifest_text.as_bytes())?;
+/// Default for `unpack --max-uncompressed-bytes`: the most a bundle may expand to, by its declared sizes
+/// and by the bytes actually extracted
+pub const UNPACK_MAX_UNCOMPRESSED_BYTES: u64 = 8 << 30;
+
+/// Entries over 1 MiB that claim to expand more than this are refused as likely zip bombs
+const MAX_COMPRESSION_RATIO: u64 = 1000;
+
+/// Names Windows maps to devices regardless of extension (`con.txt` opens the console)
+const WINDOWS_DEVICE_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Where plugin payload and bundled profiles live in a bundle
+struct UnpackLayout<'a> {
+    /// "<root_layout>/"
+    plugins_prefix: String,
+    /// "<bundle_profiles_root>/plugins/"
+    profiles_prefix: Option<String>,
+    profiles_root: String,
+    expected_files: std::collections::HashMap<String, &'a ManifestFileDe>,
+    apply_profiles: ApplyProfiles,
+    /// Bundled profile entry name -> (plugin id, original path); only filled when applying profiles
+    profile_paths: std::collections::HashMap<&'a str, (&'a str, Option<&'a str>)>,
+    /// Plugin files matching these are not extracted, and never pruned
+    excludes: &'a Excludes,
+    prune: bool,
+    /// Bytes extracted so far, against the unpack limit
+    budget: UnpackBudget,
+}
 
-    for plugin in plugins {
-        let mut files: Vec<PathBuf> = Vec::new();
-        for e in WalkDir::new(&plugin.path).follow_links(false) {
-            let e = e?;
-            if !e.file_type().is_file() {
-                continue;
-            }
-            let p = e.path().to_path_buf();
-            let rel = p
-                .strip_prefix(&plugin.path)
-                .unwrap_or(e.path())
-                .to_string_lossy()
-                .replace('\\', "/");
-            if excludes.is_match(&rel) {
-                continue;
+/// Archive indices of one plugin folder's entries
+#[derive(Default)]
+struct PluginEntries {
+    payload: Vec<usize>,
+    profiles: Vec<usize>,
+}
+
+fn group_plugin_entries<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    zip_path: &Path,
+    layout: &UnpackLayout<'_>,
+    id_to_folder: &std::collections::HashMap<String, String>,
+    result: &mut UnpackResult,
+) -> Result<std::collections::HashMap<String, PluginEntries>> {
+    let mut entries: std::collections::HashMap<String, PluginEntries> = std::collections::HashMap::new();
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index_raw(i)
+            .with_context(|| PathContext::new("failed to read zip", zip_path))?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name();
+        if let Some(remainder) = name.strip_prefix(&layout.plugins_prefix) {
+            if let Some(folder) = remainder.split('/').next().filter(|f| !f.is_empty()) {
+                entries.entry(folder.to_string()).or_default().payload.push(i);
             }
-            files.push(p);
+            continue;
         }
-
-        files.sort_by(|a, b| {
-            a.strip_prefix(&plugin.path)
-                .unwrap_or(a)
-                .to_string_lossy()
-                .cmp(&b.strip_prefix(&plugin.path).unwrap_or(b).to_string_lossy())
-        });
-
-        for p in files {
-            let rel = p
-                .strip_prefix(&plugin.path)
-                .unwrap_or(&p)
-                .to_string_lossy()
-                .replace('\\', "/");
-            let zip_path = format!("plugins/{}/{}", plugin.folder, rel);
-            read_file_to_zip(&mut zip, &zip_path, &p, options)?;
+        let Some(remainder) = layout.profiles_prefix.as_deref().and_then(|p| name.strip_prefix(p)) else {
+            continue;
+        };
+        let Some(plugin_id) = remainder.split('/').next().filter(|id| !id.is_empty()) else {
+            continue;
+        };
+        match id_to_folder.get(plugin_id) {
+            Some(folder) => entries.entry(folder.clone()).or_default().profiles.push(i),
+            None => result
+                .warnings
+                .push(format!("bundled profile references unknown plugin id: {}", plugin_id)),
         }
     }
+    Ok(entries)
+}
 
-    // Bundle profiles are stored under bundle_profiles/<bundle_name>/plugins/<plugin_id>/... with renamed files.
-    for (plugin, zip_paths) in plugins.iter().zip(bundled_profiles_map.iter()) {
-        let sources = collect_profile_files(&plugin.path);
-        for (src, zip_path) in sources.iter().zip(zip_paths.iter()) {
-            read_file_to_zip(&mut zip, zip_path, src, options)?;
-        }
+/// Assemble a plugin under `staging_root`, starting from a copy of the installed folder so files the
+/// bundle does not carry survive an overwrite unless the layout prunes them. Fails when fewer than
+/// `expected` files could be checked against the manifest (files left out by the excludes aside).
+#[allow(clippy::too_many_arguments)]
+fn stage_plugin<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    zip_path: &Path,
+    entries: &PluginEntries,
+    layout: &UnpackLayout<'_>,
+    folder: &str,
+    dest_dir: &Path,
+    staging_root: &Path,
+    force: bool,
+    expected: usize,
+    reporter: &UnpackReporter<'_>,
+    result: &mut UnpackResult,
+) -> Result<StagedPlugin> {
+    let staged = staging_root.join(folder);
+    let target = dest_dir.join(folder);
+    remove_stale(&staged)?;
+    fs::create_dir_all(&staged).with_context(|| PathContext::new("failed to create", &staged))?;
+    if target.is_dir() {
+        copy_tree(&target, &staged)?;
+    }
+    let all: Vec<usize> = entries.payload.iter().chain(&entries.profiles).copied().collect();
+    let mut written = WrittenEntries::default();
+    write_plugin_entries(
+        archive,
+        zip_path,
+        &all,
+        layout,
+        folder,
+        &staged,
+        &target,
+        force,
+        reporter,
+        &mut written,
+        result,
+    )?;
+    let WrittenEntries {
+        verified,
+        mut filtered,
+        files,
+    } = written;
+    let prefix = format!("{}{}/", layout.plugins_prefix, folder);
+    let filtered_expected = filtered
+        .iter()
+        .filter(|rel| layout.expected_files.contains_key(&format!("{}{}", prefix, rel)))
+        .count();
+    if verified.len() + filtered_expected < expected {
+        let mut missing: Vec<&str> = layout
+            .expected_files
+            .iter()
+            .filter(|(name, f)| name.starts_with(&prefix) && !verified.contains(*name) && !filtered.contains(&f.path))
+            .map(|(_, f)| f.path.as_str())
+            .collect();
+        missing.sort();
+        anyhow::bail!(
+            "{} file(s) listed in the manifest are missing from the bundle: {}",
+            missing.len(),
+            missing.join(", ")
+        );
     }
+    let pruned = if layout.prune && force && target.is_dir() {
+        prune_staged(archive, zip_path, &entries.payload, layout, &staged)?
+    } else {
+        Vec::new()
+    };
+    write_excludes_marker(&staged, layout.excludes, &filtered)?;
+    filtered.sort();
+    Ok(StagedPlugin {
+        pruned,
+        filtered,
+        written: files,
+    })
+}
 
-    zip.finish()?;
-    fs::rename(&tmp_path, out_path)
-        .with_context(|| format!("failed to rename {} -> {}", tmp_path.display(), out_path.display()))?;
-    Ok(())
+/// What stage_plugin did to a plugin: files it wrote, files pruned from the previous version and
+/// bundled files skipped by the excludes
+struct StagedPlugin {
+    pruned: Vec<String>,
+    filtered: Vec<String>,
+    written: WrittenFiles,
 }
 
-fn read_manifest<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Result<ManifestDe> {
-    let mut file = archive
-        .by_name("manifest.toml")
-        .context("manifest.toml not found in zip")?;
-    let mut buf = String::new();
-    file.read_to_string(&mut buf)
-        .context("failed to read manifest.toml")?;
-    let m: ManifestDe = toml::from_str(&buf).context("failed to parse manifest.toml")?;
-    Ok(m)
+/// Record which globs kept files of this plugin from being installed, or drop a stale record
+fn write_excludes_marker(staged: &Path, excludes: &Excludes, filtered: &[String]) -> Result<()> {
+    let marker = staged.join(UNPACK_EXCLUDES_MARKER);
+    if filtered.is_empty() {
+        return remove_stale(&marker);
+    }
+    let mut patterns: Vec<&str> = filtered.iter().flat_map(|rel| excludes.matching_patterns(rel)).collect();
+    patterns.sort();
+    patterns.dedup();
+    let text = format!(
+        "# neko-plugin-cli unpack --exclude: {} bundled file(s) matching these globs were not installed\n{}\n",
+        filtered.len(),
+        patterns.join("\n")
+    );
+    fs::write(&marker, text).with_context(|| PathContext::new("failed to write", &marker))
 }
 
-fn is_safe_rel_path(rel: &str) -> bool {
-    let p = Path::new(rel);
-    if p.is_absolute() {
-        return false;
+/// The globs recorded by write_excludes_marker for an installed plugin, if any
+fn read_excludes_marker(plugin_dir: &Path) -> Option<Vec<String>> {
+    let text = fs::read_to_string(plugin_dir.join(UNPACK_EXCLUDES_MARKER)).ok()?;
+    Some(
+        text.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Move a plugin assembled by stage_plugin into `dest_dir`. The replaced version is left at
+/// `<staging_root>/<folder>.old` for the caller to discard or restore.
+fn swap_in_staged(staging_root: &Path, dest_dir: &Path, folder: &str) -> Result<()> {
+    let staged = staging_root.join(folder);
+    let target = dest_dir.join(folder);
+    if !target.is_dir() {
+        return move_dir(&staged, &target);
     }
-    for c in p.components() {
-        if matches!(c, std::path::Component::ParentDir | std::path::Component::Prefix(_)) {
-            return false;
-        }
+    let backup = staging_root.join(format!("{}.old", folder));
+    remove_stale(&backup)?;
+    move_dir(&target, &backup)?;
+    if let Err(e) = move_dir(&staged, &target) {
+        // Put the previous install back; the failed copy is cleaned up by the caller
+        let _ = fs::remove_dir_all(&target);
+        let _ = move_dir(&backup, &target);
+        return Err(e);
     }
-    true
+    Ok(())
 }
 
-pub(crate) fn unpack_zip(zip_path: &Path, dest_dir: &Path, force: bool, excludes: &GlobSet) -> Result<()> {
-    fs::create_dir_all(dest_dir)
-        .with_context(|| format!("failed to create dest dir {}", dest_dir.display()))?;
-
-    let f = fs::File::open(zip_path)
-        .with_context(|| format!("failed to open zip {}", zip_path.display()))?;
-    let mut archive = ZipArchive::new(f).context("failed to read zip")?;
+/// Remove the files of a staged overwrite that the bundle no longer carries, so stale modules do not
+/// keep importing. Files `excludes` skips (venvs, logs) and the plugin's profiles are kept. Returns
+/// the removed paths relative to the plugin folder.
+fn prune_staged<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    zip_path: &Path,
+    payload: &[usize],
+    layout: &UnpackLayout<'_>,
+    staged: &Path,
+) -> Result<Vec<String>> {
+    let mut bundled = std::collections::HashSet::new();
+    for &i in payload {
+        let file = archive
+            .by_index_raw(i)
+            .with_context(|| PathContext::new("failed to read zip", zip_path))?;
+        if let Some((_, rel)) = file.name().strip_prefix(&layout.plugins_prefix).and_then(|r| r.split_once('/')) {
+            bundled.insert(rel.to_string());
+        }
+    }
+    let mut pruned = Vec::new();
+    for (rel, path) in list_plugin_files(staged, layout.excludes)? {
+        let is_profile = rel == "profiles.toml" || rel.starts_with("profiles/") || rel.starts_with("_bundle_profiles/");
+        if bundled.contains(&rel) || is_profile {
+            continue;
+        }
+        fs::remove_file(&path).with_context(|| PathContext::new("failed to remove", &path))?;
+        // Drop the directories this leaves empty
+        for dir in path.ancestors().skip(1).take_while(|d| *d != staged) {
+            if fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+        pruned.push(rel);
+    }
+    pruned.sort();
+    Ok(pruned)
+}
 
-    let manifest = read_manifest(&mut archive)?;
-    let root_layout = manifest.root_layout.trim_end_matches('/');
+/// Validate every entry of a bundle before extraction; returns the archive re-opened from its reader.
+/// The zip crate keys entries by name and keeps only the last of duplicates, so those are found by
+/// walking the central directory itself.
+fn check_archive_entries<R: Read + std::io::Seek>(archive: ZipArchive<R>, max_uncompressed: u64) -> Result<ZipArchive<R>> {
+    let dir_start = archive.central_directory_start();
+    let mut reader = archive.into_inner();
+    let names = central_directory_names(&mut reader, dir_start)?;
+    let mut archive = ZipArchive::new(reader).context("failed to re-read zip")?;
+
+    let mut seen = std::collections::HashSet::new();
+    let mut folded: std::collections::HashMap<String, &str> = std::collections::HashMap::new();
+    for name in &names {
+        if name.contains('\0') {
+            anyhow::bail!("entry name contains a NUL byte: {:?}", name);
+        }
+        if !seen.insert(name.as_str()) {
+            anyhow::bail!("duplicate entry {}: the later copy would silently replace the first", name);
+        }
+        for part in name.split('/') {
+            let stem = part.split('.').next().unwrap_or_default().trim_end();
+            if let Some(device) = WINDOWS_DEVICE_NAMES.iter().find(|d| d.eq_ignore_ascii_case(stem)) {
+                anyhow::bail!("entry {} uses the reserved Windows device name {}", name, device);
+            }
+        }
+        // Directories count too: plugins/a/Data/x and plugins/a/data/y share one folder there
+        let dirs = name.match_indices('/').map(|(at, _)| &name[..at]);
+        let file = Some(name.as_str()).filter(|n| !n.ends_with('/'));
+        for path in dirs.chain(file) {
+            match folded.entry(path.to_lowercase()) {
+                std::collections::hash_map::Entry::Vacant(e) => {
+                    e.insert(path);
+                }
+                std::collections::hash_map::Entry::Occupied(e) if *e.get() != path => {
+                    anyhow::bail!("entries {} and {} collide on case-insensitive file systems", e.get(), path);
+                }
+                std::collections::hash_map::Entry::Occupied(_) => {}
+            }
+        }
+    }
 
-    // Map plugin_id -> folder_name (the folder under <dest_dir>)
-    let mut id_to_folder: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-    for p in &manifest.plugins {
-        let folder_rel = p.folder.trim_end_matches('/');
-        let folder_name = folder_rel
-            .split('/')
-            .nth(1)
-            .ok_or_else(|| anyhow::anyhow!("invalid manifest folder: {}", p.folder))?
-            .to_string();
-        id_to_folder.insert(p.id.clone(), folder_name);
+    let mut total: u64 = 0;
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i).context("failed to read zip")?;
+        let (size, compressed) = (file.size(), file.compressed_size());
+        if size > 1 << 20 && size / compressed.max(1) > MAX_COMPRESSION_RATIO {
+            anyhow::bail!(
+                "entry {} claims to expand {} to {} (over {}:1); refusing a likely zip bomb",
+                file.name(),
+                human_bytes(compressed),
+                human_bytes(size),
+                MAX_COMPRESSION_RATIO
+            );
+        }
+        total = total.saturating_add(size);
+    }
+    if total > max_uncompressed {
+        anyhow::bail!(
+            "bundle declares {} of uncompressed data, over the limit of {} (raise it with --max-uncompressed-bytes)",
+            human_bytes(total),
+            human_bytes(max_uncompressed)
+        );
     }
+    Ok(archive)
+}
 
-    let bundle_profiles_root = manifest
-        .bundle_profiles_root
-        .as_deref()
-        .map(|s| s.trim_end_matches('/').to_string());
+/// Entry names in central directory order, duplicates included
+fn central_directory_names<R: Read + std::io::Seek>(reader: &mut R, dir_start: u64) -> Result<Vec<String>> {
+    const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+    reader.seek(std::io::SeekFrom::Start(dir_start)).context("failed to seek to the zip central directory")?;
+    let mut reader = std::io::BufReader::new(reader);
+    let mut names = Vec::new();
+    loop {
+        let mut header = [0u8; 46];
+        if reader.read_exact(&mut header).is_err()
+            || u32::from_le_bytes([header[0], header[1], header[2], header[3]]) != CENTRAL_HEADER_SIGNATURE
+        {
+            break;
+        }
+        let field = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]) as usize;
+        let mut name = vec![0u8; field(28)];
+        reader.read_exact(&mut name).context("truncated zip central directory")?;
+        std::io::copy(&mut reader.by_ref().take((field(30) + field(32)) as u64), &mut std::io::sink())
+            .context("truncated zip central directory")?;
+        names.push(String::from_utf8_lossy(&name).into_owned());
+    }
+    Ok(names)
+}
 
-    use std::collections::HashSet;
-    let mut skip_folders: HashSet<String> = HashSet::new();
+/// Counts the bytes actually extracted from a bundle against `unpack --max-uncompressed-bytes`.
+/// check_archive_entries only sees the sizes the zip declares, which a crafted archive can understate.
+struct UnpackBudget {
+    limit: u64,
+    used: std::sync::atomic::AtomicU64,
+}
 
-    for p in &manifest.plugins {
-        let folder_rel = p.folder.trim_end_matches('/');
-        let folder_name = folder_rel
-            .split('/')
-            .nth(1)
-            .ok_or_else(|| anyhow::anyhow!("invalid manifest folder: {}", p.folder))?
-            .to_string();
+impl UnpackBudget {
+    fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
 
-        let target_folder = dest_dir.join(&folder_name);
-        if target_folder.is_dir() {
-            eprintln!("WARN: plugin folder already exists: {}", target_folder.display());
+    fn spend(&self, n: u64) -> std::io::Result<()> {
+        let used = self.used.fetch_add(n, std::sync::atomic::Ordering::Relaxed).saturating_add(n);
+        if used > self.limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::FileTooLarge,
+                format!(
+                    "bundle expands to more than the limit of {} (raise it with --max-uncompressed-bytes)",
+                    human_bytes(self.limit)
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
 
-            if let Some(md5_expected) = &p.md5 {
-                let md5_local = folder_md5(&target_folder, excludes)?;
-                if &md5_local == md5_expected {
-                    eprintln!("INFO: plugin '{}' is identical (md5 match), skipping", p.id);
-                    skip_folders.insert(folder_name);
-                    continue;
-                }
-            }
+/// An archive entry reader that fails once the entry yields more than its declared size, or the
+/// bundle more than its budget. The zip crate only bounds the compressed bytes it reads.
+struct BoundedEntry<'a, F> {
+    inner: std::io::Take<F>,
+    name: String,
+    declared: u64,
+    read: u64,
+    budget: &'a UnpackBudget,
+}
 
-            if !force {
-                eprintln!(
-                    "WARN: plugin '{}' differs from existing; skipping (use --force to overwrite)",
-                    p.id
-                );
-                skip_folders.insert(folder_name);
-            }
+impl<'a, 'z> BoundedEntry<'a, &'a mut zip::read::ZipFile<'z>> {
+    fn new(file: &'a mut zip::read::ZipFile<'z>, budget: &'a UnpackBudget) -> Self {
+        let (name, declared) = (file.name().to_string(), file.size());
+        Self {
+            inner: file.take(declared.saturating_add(1)),
+            name,
+            declared,
+            read: 0,
+            budget,
         }
     }
+}
 
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        if !file.is_file() {
-            continue;
+impl<F: Read> Read for BoundedEntry<'_, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        if self.read > self.declared {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "entry {} holds more than the {} its zip header declares",
+                    self.name,
+                    human_bytes(self.declared)
+                ),
+            ));
         }
+        self.budget.spend(n as u64)?;
+        Ok(n)
+    }
+}
+
+/// Remove what an interrupted unpack may have left at a staging path
+fn remove_stale(path: &Path) -> Result<()> {
+    let removed = match path.symlink_metadata() {
+        Ok(m) if m.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(_) => return Ok(()),
+    };
+    removed.with_context(|| PathContext::new("failed to remove", path))
+}
 
+/// Write archive entries of one plugin folder below `root` (the staging or the installed folder);
+/// checksum errors name the file under `target`, where the plugin ends up. Payload files matching the
+/// layout's excludes are only added to `written.filtered`; entries checked against their manifest checksum
+/// are recorded in `written.verified`.
+#[allow(clippy::too_many_arguments)]
+fn write_plugin_entries<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    zip_path: &Path,
+    indices: &[usize],
+    layout: &UnpackLayout<'_>,
+    folder: &str,
+    root: &Path,
+    target: &Path,
+    force: bool,
+    reporter: &UnpackReporter<'_>,
+    written: &mut WrittenEntries,
+    result: &mut UnpackResult,
+) -> Result<()> {
+    for &i in indices {
+        let mut file = archive
+            .by_index(i)
+            .with_context(|| PathContext::new("failed to read zip", zip_path))?;
+        let is_symlink = file.is_symlink();
         let name = file.name().to_string();
-        if name == "manifest.toml" {
-            continue;
-        }
 
         // 1) Normal plugin payload: <root_layout>/<folder>/<rel>
-        let prefix_plugins = format!("{}/", root_layout);
-        if name.starts_with(&prefix_plugins) {
-            let remainder = &name[prefix_plugins.len()..];
-            let mut parts = remainder.splitn(2, '/');
-            let folder = match parts.next() {
-                Some(s) if !s.is_empty() => s,
-                _ => continue,
-            };
-            let rel = match parts.next() {
-                Some(s) if !s.is_empty() => s,
+        if let Some(remainder) = name.strip_prefix(&layout.plugins_prefix) {
+            let rel = match remainder.split_once('/') {
+                Some((_, rel)) if !rel.is_empty() => rel,
                 _ => continue,
             };
 
-            if skip_folders.contains(folder) {
+            if !is_safe_rel_path(rel) {
+                result.warnings.push(format!("skipped unsafe path in zip: {}", name));
                 continue;
             }
-
-            if !is_safe_rel_path(rel) {
-                eprintln!("WARN: skipped unsafe path in zip: {}", name);
+            if layout.excludes.is_match(rel) {
+                written.filtered.push(rel.to_string());
                 continue;
             }
 
-            let out_path = dest_dir.join(folder).join(rel);
+            let out_path = join_rel(root, rel);
+            // Writing below a link could land outside dest_dir
+            if has_symlink_parent(root, &out_path) {
+                result.warnings.push(format!("skipped path below a symlink: {}", name));
+                continue;
+            }
             if let Some(parent) = out_path.parent() {
-                fs::create_dir_all(parent)?;
+                fs::create_dir_all(parent).with_context(|| PathContext::new("failed to create", parent))?;
+            }
+
+            let existing = out_path.symlink_metadata().ok();
+            if existing.is_some() && !force {
+                result
+                    .warnings
+                    .push(format!("file conflict, skipping: {}", out_path.display()));
+                continue;
+            }
+            // Replace an existing link instead of writing through it
+            if existing.is_some_and(|m| m.file_type().is_symlink()) {
+                fs::remove_file(&out_path).with_context(|| PathContext::new("failed to remove", &out_path))?;
             }
 
-            if out_path.exists() && !force {
-                eprintln!("WARN: file conflict, skipping: {}", out_path.display());
+            if is_symlink {
+                let mut target = String::new();
+                file.by_ref()
+                    .take(4096)
+                    .read_to_string(&mut target)
+                    .with_context(|| format!("failed to read symlink entry {}", name))?;
+                let link = format!("{}/{}", folder, rel);
+                if !is_safe_symlink_target(&link, &target) {
+                    result
+                        .warnings
+                        .push(format!("skipped symlink pointing outside the destination: {} -> {}", name, target));
+                    continue;
+                }
+                if create_symlink(&target, &out_path)? {
+                    result.symlinks.push(UnpackedSymlink { path: link, target });
+                } else {
+                    result
+                        .warnings
+                        .push(format!("skipped symlink (not supported on this platform): {}", name));
+                }
                 continue;
             }
 
-            let mut out = fs::File::create(&out_path)
-                .with_context(|| format!("failed to create {}", out_path.display()))?;
-            std::io::copy(&mut file, &mut out)
-                .with_context(|| format!("failed to write {}", out_path.display()))?;
+            let mut entry = BoundedEntry::new(&mut file, &layout.budget);
+            let bytes = match layout.expected_files.get(&name) {
+                Some(expected) => extract_verified(&mut entry, &out_path, &join_rel(target, rel), expected, zip_path)?,
+                None => {
+                    extract_entry(&mut entry, &out_path, &join_rel(target, rel), zip_path, None)?
+                }
+            };
+            reporter.file(&name, bytes);
+            written.files.files += 1;
+            written.files.bytes += bytes;
+            if layout.expected_files.contains_key(&name) {
+                written.verified.insert(name);
+            }
             continue;
         }
 
         // 2) Bundled profiles payload: <bundle_profiles_root>/plugins/<plugin_id>/<renamed_file>
-        if let Some(root) = &bundle_profiles_root {
-            let prefix_profiles = format!("{}/plugins/", root);
-            if name.starts_with(&prefix_profiles) {
-                let remainder = &name[prefix_profiles.len()..];
-                let mut parts = remainder.splitn(2, '/');
-                let plugin_id = match parts.next() {
-                    Some(s) if !s.is_empty() => s,
-                    _ => continue,
-                };
-                let rel = match parts.next() {
-                    Some(s) if !s.is_empty() => s,
-                    _ => continue,
-                };
+        let Some(remainder) = layout.profiles_prefix.as_deref().and_then(|p| name.strip_prefix(p)) else {
+            continue;
+        };
+        let rel = match remainder.split_once('/') {
+            Some((_, rel)) if !rel.is_empty() => rel,
+            _ => continue,
+        };
+        if !is_safe_rel_path(rel) || is_symlink {
+            result
+                .warnings
+                .push(format!("skipped unsafe bundled profile path in zip: {}", name));
+            continue;
+        }
 
-                if !is_safe_rel_path(rel) {
-                    eprintln!("WARN: skipped unsafe bundled profile path in zip: {}", name);
-                    continue;
-                }
+        // Place bundled profiles inside plugin folder without touching user profiles.
+        // Use a dedicated internal directory to avoid overwriting ./profiles and ./profiles.toml.
+        let out_path = join_rel(&join_rel(&root.join("_bundle_profiles"), &layout.profiles_root), rel);
 
-                let Some(folder_name) = id_to_folder.get(plugin_id).cloned() else {
-                    eprintln!("WARN: bundled profile references unknown plugin id: {}", plugin_id);
-                    continue;
-                };
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).with_context(|| PathContext::new("failed to create", parent))?;
+        }
 
-                // Place bundled profiles inside plugin folder without touching user profiles.
-                // Use a dedicated internal directory to avoid overwriting ./profiles and ./profiles.toml.
-                let out_path = dest_dir
-                    .join(folder_name)
-                    .join("_bundle_profiles")
-                    .join(root)
-                    .join(rel);
+        // Never overwrite bundled profiles unless --force.
+        if !out_path.exists() || force {
+            let mut entry = BoundedEntry::new(&mut file, &layout.budget);
+            let bytes = extract_entry(&mut entry, &out_path, &out_path, zip_path, None)?;
+            reporter.file(&name, bytes);
+            written.files.files += 1;
+            written.files.bytes += bytes;
+        }
+        // The stored copy is named after the bundle version, so an existing one has the same content
+        if let Some(&(id, source)) = layout.profile_paths.get(name.as_str()) {
+            apply_profile(&out_path, id, source, root, layout.apply_profiles, result)?;
+        }
+    }
+    Ok(())
+}
 
-                if let Some(parent) = out_path.parent() {
-                    fs::create_dir_all(parent)?;
-                }
+/// What write_plugin_entries did with a plugin's entries
+#[derive(Default)]
+struct WrittenEntries {
+    /// Entries checked against their manifest checksum
+    verified: std::collections::HashSet<String>,
+    /// Payload files left out because they match the excludes
+    filtered: Vec<String>,
+    files: WrittenFiles,
+}
 
-                // Never overwrite bundled profiles unless --force.
-                if out_path.exists() && !force {
-                    continue;
-                }
+/// Copy a bundled profile stored under _bundle_profiles to its original place in the plugin
+fn apply_profile(
+    bundled: &Path,
+    id: &str,
+    source: Option<&str>,
+    root: &Path,
+    mode: ApplyProfiles,
+    result: &mut UnpackResult,
+) -> Result<()> {
+    let Some(rel) = source else {
+        result.profiles.push(AppliedProfile {
+            id: id.to_string(),
+            path: bundled.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            status: AppliedProfileStatus::UnknownPath,
+        });
+        return Ok(());
+    };
+    // The path comes from the manifest; only ever let it reach the plugin's own profile files
+    let is_profile = rel == "profiles.toml" || rel.strip_prefix("profiles/").is_some_and(|r| !r.is_empty());
+    let live = join_rel(root, rel);
+    if !is_profile || !is_safe_rel_path(rel) || has_symlink_parent(root, &live) {
+        result.warnings.push(format!("skipped bundled profile with unsafe path: {}", rel));
+        return Ok(());
+    }
+    let status = match live.symlink_metadata() {
+        Err(_) => AppliedProfileStatus::Applied,
+        Ok(m) if m.is_file() && fs::read(&live).ok() == fs::read(bundled).ok() => AppliedProfileStatus::Identical,
+        Ok(_) if mode == ApplyProfiles::Merge => AppliedProfileStatus::Kept,
+        Ok(m) => {
+            if !m.is_file() {
+                remove_stale(&live)?;
+            }
+            AppliedProfileStatus::Overwritten
+        }
+    };
+    if matches!(status, AppliedProfileStatus::Applied | AppliedProfileStatus::Overwritten) {
+        if let Some(parent) = live.parent() {
+            fs::create_dir_all(parent).with_context(|| PathContext::new("failed to create", parent))?;
+        }
+        fs::copy(bundled, &live).with_context(|| PathContext::new("failed to write", &live))?;
+    }
+    result.profiles.push(AppliedProfile {
+        id: id.to_string(),
+        path: rel.to_string(),
+        status,
+    });
+    Ok(())
+}
 
-                let mut out = fs::File::create(&out_path)
-                    .with_context(|| format!("failed to create {}", out_path.display()))?;
-                std::io::copy(&mut file, &mut out)
-                    .with_context(|| format!("failed to write {}", out_path.display()))?;
-                continue;
+/// Rename a directory, falling back to copy + remove when `from` and `to` are on different devices
+fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_tree(from, to) {
+                let _ = fs::remove_dir_all(to);
+                return Err(e);
             }
+            fs::remove_dir_all(from).with_context(|| PathContext::new("failed to remove", from))
         }
+        Err(e) => Err(e).with_context(|| PathContext::new("failed to move", from)),
     }
+}
+
+/// Copy a directory tree; symlinks are recreated rather than followed
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    for e in WalkDir::new(from) {
+        let e = e?;
+        let out = to.join(e.path().strip_prefix(from).unwrap_or(e.path()));
+        let file_type = e.file_type();
+        if file_type.is_dir() {
+            fs::create_dir_all(&out).with_context(|| PathContext::new("failed to create", &out))?;
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(e.path()).with_context(|| PathContext::new("failed to read link", e.path()))?;
+            create_symlink(&target.to_string_lossy(), &out)?;
+        } else {
+            fs::copy(e.path(), &out).with_context(|| PathContext::new("failed to copy", e.path()))?;
+        }
+    }
+    Ok(())
+}
 
+/// Write the bundle's extras/ files under `extras_dest`; existing files are only replaced with `force`
+fn unpack_extras<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    manifest: &ManifestDe,
+    zip_path: &Path,
+    extras_dest: &Path,
+    force: bool,
+    budget: &UnpackBudget,
+    result: &mut UnpackResult,
+) -> Result<()> {
+    for f in &manifest.extras {
+        if !is_valid_extra_path(&f.path) {
+            result
+                .warnings
+                .push(format!("skipped unsafe extras path in manifest: {}", f.path));
+            continue;
+        }
+        let target = join_rel(extras_dest, &f.path);
+        let extra = |reason: &str| UnpackedExtra {
+            path: f.path.clone(),
+            target: target.clone(),
+            reason: reason.to_string(),
+        };
+        let mut reason = "installed";
+        if target.exists() {
+            if file_sha256(&target)? == f.sha256 {
+                result.extras_skipped.push(extra("identical (sha256 match)"));
+                continue;
+            }
+            if !force {
+                result
+                    .extras_skipped
+                    .push(extra("differs from existing (use --force to overwrite)"));
+                continue;
+            }
+            reason = "overwritten (--force)";
+        }
+
+        let name = format!("{}{}", EXTRAS_PREFIX, f.path);
+        let mut file = archive
+            .by_name(&name)
+            .with_context(|| format!("{} is listed in the manifest but missing from the bundle", name))
+            .with_context(|| PathContext::new("invalid bundle", zip_path))?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| PathContext::new("failed to create", parent))?;
+        }
+        extract_verified(&mut BoundedEntry::new(&mut file, budget), &target, &target, f, zip_path)?;
+        result.extras_installed.push(extra(reason));
+    }
     Ok(())
 }
 
-pub(crate) fn compute_plugin_md5_for_pack(plugins: &mut [PluginPackItem], excludes: &GlobSet, no_md5: bool) -> Result<()> {
-    if no_md5 {
+/// Copy an archive entry to `out_path` while checking it against its manifest checksum;
+/// a file that does not match is removed again. A mismatch is reported for `shown_path`.
+fn extract_verified(
+    file: &mut dyn Read,
+    out_path: &Path,
+    shown_path: &Path,
+    expected: &ManifestFileDe,
+    zip_path: &Path,
+) -> Result<u64> {
+    let mut hasher = Sha256::new();
+    let size = extract_entry(file, out_path, shown_path, zip_path, Some(&mut hasher))?;
+    let actual = format!("{:x}", hasher.finalize());
+    if size != expected.size || actual != expected.sha256 {
+        let _ = fs::remove_file(out_path);
+        return Err(anyhow::anyhow!(
+            "expected {} bytes sha256 {}, got {} bytes sha256 {}",
+            expected.size,
+            expected.sha256,
+            size,
+            actual
+        ))
+        .with_context(|| PathContext::new("checksum mismatch for", shown_path));
+    }
+    Ok(size)
+}
+
+/// Copy an archive entry to `out_path`, feeding `hasher` along the way. A damaged entry, or one a
+/// BoundedEntry cuts off, is reported for `shown_path` and the partly written file removed again.
+fn extract_entry(
+    file: &mut dyn Read,
+    out_path: &Path,
+    shown_path: &Path,
+    zip_path: &Path,
+    mut hasher: Option<&mut Sha256>,
+) -> Result<u64> {
+    let mut out = fs::File::create(out_path).with_context(|| PathContext::new("failed to create", out_path))?;
+    let mut buf = [0u8; 1024 * 64];
+    let mut size = 0u64;
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(n) => n,
+            // A damaged deflate stream or CRC is the same corruption a checksum mismatch reports
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::InvalidData | std::io::ErrorKind::InvalidInput) => {
+                drop(out);
+                let _ = fs::remove_file(out_path);
+                return Err(anyhow::anyhow!("{}", e)).with_context(|| PathContext::new("corrupt data for", shown_path));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::FileTooLarge => {
+                drop(out);
+                let _ = fs::remove_file(out_path);
+                return Err(anyhow::anyhow!("{}", e)).with_context(|| PathContext::new("refusing to extract", shown_path));
+            }
+            Err(e) => return Err(e).with_context(|| PathContext::new("failed to read zip", zip_path)),
+        };
+        if n == 0 {
+            break;
+        }
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&buf[..n]);
+        }
+        out.write_all(&buf[..n])
+            .with_context(|| PathContext::new("failed to write", out_path))?;
+        size += n as u64;
+    }
+    Ok(size)
+}
+
+pub fn compute_plugin_hash_for_pack(
+    plugins: &mut [PluginPackItem],
+    excludes: &Excludes,
+    algo: HashAlgo,
+    no_hash: bool,
+    progress: Option<ProgressFn<'_>>,
+) -> Result<()> {
+    if no_hash {
         return Ok(());
     }
+    let total = plugins.len();
+    let done = std::sync::atomic::AtomicUsize::new(0);
     plugins.par_iter_mut().try_for_each(|p| -> Result<()> {
-        let md5 = folder_md5(&p.path, excludes)?;
-        p.md5 = Some(md5);
+        p.hash = Some(folder_hash(&p.path, excludes, algo)?);
+        p.hash_algo = algo;
+        if let Some(cb) = progress {
+            let done = done.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+            cb(PackProgress::Hashed {
+                plugin: &p.id,
+                done,
+                total,
+            });
+        }
         Ok(())
     })?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// plugins/<id>/plugin.toml for each (id, version, extra toml), in a fresh temp dir
+    fn fixture_repo(name: &str, plugins: &[(&str, &str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neko_plugin_cli_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (id, version, extra) in plugins {
+            fs::create_dir_all(dir.join(id)).unwrap();
+            let toml = format!("[plugin]\nid = \"{id}\"\nversion = \"{version}\"\nentry = \"{id}:main\"\n{extra}");
+            fs::write(dir.join(id).join("plugin.toml"), toml).unwrap();
+        }
+        dir
+    }
+
+    fn graph_repo() -> PathBuf {
+        fixture_repo(
+            "graph",
+            &[
+                ("alpha", "1.0.0", ""),
+                ("beta", "0.1.0", "[[plugin.dependency]]\nid = \"alpha\"\nsupported = \">=1.0.0\"\n"),
+                (
+                    "gamma",
+                    "1.0.0",
+                    "[[plugin.dependency]]\nid = \"alpha\"\nconflicts = [\">=1.0.0\"]\n\n\
+                     [[plugin.dependency]]\nid = \"ghost\"\n\n\
+                     [[plugin.dependency]]\nid = \"delta\"\n",
+                ),
+                ("delta", "2.0.0", "[[plugin.dependency]]\nid = \"gamma\"\nsupported = \"^1\"\n"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_dependency_graph_dot() {
+        let dir = graph_repo();
+        let dot = dependency_graph(&dir, None, GraphFormat::Dot).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            dot,
+            r#"digraph plugins {
+    rankdir=LR;
+    node [shape=box];
+    "alpha" [label="alpha@1.0.0"];
+    "beta" [label="beta@0.1.0"];
+    "delta" [label="delta@2.0.0"];
+    "gamma" [label="gamma@1.0.0"];
+    "ghost" [label="ghost", style=dashed, color=red, fontcolor=red];
+    "beta" -> "alpha" [label=">=1.0.0"];
+    "delta" -> "gamma" [label="^1 (cycle)", color=red, fontcolor=red];
+    "gamma" -> "alpha" [label="* (conflict)", color=red, fontcolor=red];
+    "gamma" -> "ghost" [label="* (missing)", color=red, fontcolor=red];
+    "gamma" -> "delta" [label="* (cycle)", color=red, fontcolor=red];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_dependency_graph_single_plugin_mermaid() {
+        let dir = graph_repo();
+        let mermaid = dependency_graph(&dir, Some("gamma"), GraphFormat::Mermaid).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            mermaid,
+            r#"graph LR
+    n0["alpha@1.0.0"]
+    n1["delta@2.0.0"]
+    n2["gamma@1.0.0"]
+    n3["ghost"]
+    n2 -->|"* (conflict)"| n0
+    n2 -->|"* (missing)"| n3
+    n2 -->|"* (cycle)"| n1
+    linkStyle 0 stroke:red,color:red
+    linkStyle 1 stroke:red,color:red
+    linkStyle 2 stroke:red,color:red
+    classDef missing stroke:red,stroke-dasharray:5 5
+    class n3 missing
+"#
+        );
+    }
+}
@@ -6,6 +6,18 @@ pub fn version() -> &'static str {
     env!("CARGO_PKG_VERSION")
 }
 
+/// Pack/unpack/check implementation shared by the binary and the Python bindings. Not part of
+/// the supported API: use [`api`], which re-exports the core types its signatures mention.
+#[doc(hidden)]
+pub mod core;
+
+/// Typed options, reports and errors over `core` for use from other Rust code
+pub mod api;
+
+pub use api::{
+    CheckOptions, CheckPass, NekoCliError, PackOptions, PackReport, PreparedPack, UnpackOptions, check, pack, unpack,
+};
+
 #[cfg(feature = "python")]
 mod python;
 
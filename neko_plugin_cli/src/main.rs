@@ -1,10 +1,10 @@
 mod cli;
-mod core;
 mod tui;
+mod watch;
 
 fn main() {
     if let Err(e) = cli::run() {
         eprintln!("{e:#}");
-        std::process::exit(1);
+        std::process::exit(e.downcast_ref::<cli::ExitStatus>().map_or(1, |s| s.code));
     }
 }
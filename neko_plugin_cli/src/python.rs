@@ -1,4 +1,12 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use pyo3::exceptions::{PyOSError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use serde::Serialize;
+
+use crate::core;
 
 #[pyfunction]
 fn py_add(left: u64, right: u64) -> u64 {
@@ -10,9 +18,1147 @@ fn py_version() -> &'static str {
     crate::version()
 }
 
+/// Flatten the anyhow context chain into one message; I/O failures become OSError.
+/// When the chain records a core::PathContext, the path is exposed as `.filename`.
+fn to_py_err(e: anyhow::Error) -> PyErr {
+    let msg = format!("{e:#}");
+    let path = e.downcast_ref::<core::PathContext>().map(|c| c.path.clone());
+    let io = e.chain().find_map(|c| c.downcast_ref::<std::io::Error>());
+    match (io, path) {
+        (Some(io), Some(path)) => PyOSError::new_err((io.raw_os_error().unwrap_or(0), msg, path)),
+        (Some(_), None) => PyOSError::new_err(msg),
+        (None, path) => {
+            let err = PyValueError::new_err(msg);
+            if let Some(path) = path {
+                Python::with_gil(|py| err.value(py).setattr("filename", path)).ok();
+            }
+            err
+        }
+    }
+}
+
+fn json_to_py(py: Python<'_>, v: &serde_json::Value) -> PyResult<PyObject> {
+    use serde_json::Value;
+    Ok(match v {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.into_pyobject(py)?.to_owned().into_any().unbind(),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_pyobject(py)?.into_any().unbind(),
+            (None, Some(u)) => u.into_pyobject(py)?.into_any().unbind(),
+            _ => n.as_f64().unwrap_or(0.0).into_pyobject(py)?.into_any().unbind(),
+        },
+        Value::String(s) => s.into_pyobject(py)?.into_any().unbind(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for it in items {
+                list.append(json_to_py(py, it)?)?;
+            }
+            list.into_any().unbind()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (k, it) in map {
+                dict.set_item(k, json_to_py(py, it)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    })
+}
+
+/// Convert any report struct to plain Python dicts/lists via its Serialize impl
+fn to_py<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let v = serde_json::to_value(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    json_to_py(py, &v)
+}
+
+fn repo_root(root: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    match root {
+        Some(p) => Ok(p),
+        None => core::find_repo_root(std::env::current_dir().context("failed to get cwd")?),
+    }
+}
+
+/// PackProgress as a dict: {"event": "file_written", "plugin": ..., "name": ..., "bytes": ...}
+fn progress_event<'py>(py: Python<'py>, ev: core::PackProgress<'_>) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new(py);
+    match ev {
+        core::PackProgress::Hashed { plugin, done, total } => {
+            d.set_item("event", "hashed")?;
+            d.set_item("plugin", plugin)?;
+            d.set_item("done", done)?;
+            d.set_item("total", total)?;
+        }
+        core::PackProgress::Start { plugins, files, bytes } => {
+            d.set_item("event", "start")?;
+            d.set_item("plugins", plugins)?;
+            d.set_item("files", files)?;
+            d.set_item("bytes", bytes)?;
+        }
+        core::PackProgress::PluginStart { plugin, files, bytes } => {
+            d.set_item("event", "plugin_start")?;
+            d.set_item("plugin", plugin)?;
+            d.set_item("files", files)?;
+            d.set_item("bytes", bytes)?;
+        }
+        core::PackProgress::FileWritten { plugin, name, bytes } => {
+            d.set_item("event", "file_written")?;
+            d.set_item("plugin", plugin)?;
+            d.set_item("name", name)?;
+            d.set_item("bytes", bytes)?;
+        }
+        core::PackProgress::PluginDone { plugin } => {
+            d.set_item("event", "plugin_done")?;
+            d.set_item("plugin", plugin)?;
+        }
+    }
+    Ok(d)
+}
+
+/// UnpackProgress as a dict: {"event": "file_extracted", "name": ..., "bytes": ..., "index": ..., "total": ...}
+fn unpack_progress_event<'py>(py: Python<'py>, ev: core::UnpackProgress<'_>) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new(py);
+    match ev {
+        core::UnpackProgress::PluginStart { plugin, folder } => {
+            d.set_item("event", "plugin_start")?;
+            d.set_item("plugin", plugin)?;
+            d.set_item("folder", folder)?;
+        }
+        core::UnpackProgress::FileExtracted { name, bytes, index, total } => {
+            d.set_item("event", "file_extracted")?;
+            d.set_item("name", name)?;
+            d.set_item("bytes", bytes)?;
+            d.set_item("index", index)?;
+            d.set_item("total", total)?;
+        }
+        core::UnpackProgress::PluginSkipped { plugin, reason } => {
+            d.set_item("event", "plugin_skipped")?;
+            d.set_item("plugin", plugin)?;
+            d.set_item("reason", reason)?;
+        }
+        core::UnpackProgress::Done { installed, skipped } => {
+            d.set_item("event", "done")?;
+            d.set_item("installed", installed)?;
+            d.set_item("skipped", skipped)?;
+        }
+    }
+    Ok(d)
+}
+
+/// Emit a UserWarning from code running without the GIL
+fn py_warn(message: &str) -> PyResult<()> {
+    let msg = std::ffi::CString::new(message).unwrap_or_default();
+    Python::with_gil(|py| PyErr::warn(py, &py.get_type::<pyo3::exceptions::PyUserWarning>(), &msg, 1))
+}
+
+/// A size given as a byte count or as a string like "500MB"
+#[derive(FromPyObject)]
+enum SizeArg {
+    Bytes(u64),
+    Text(String),
+}
+
+impl SizeArg {
+    fn bytes(self) -> anyhow::Result<u64> {
+        match self {
+            SizeArg::Bytes(n) => Ok(n),
+            SizeArg::Text(s) => core::parse_size(&s),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum PackOutput {
+    Packed {
+        path: PathBuf,
+        manifest: core::Manifest,
+        /// Plugins copied from `base`; only present when a base bundle was given
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reused: Option<Vec<String>>,
+        /// Files the hash cache reused or had to read; absent with no_hash_cache=True
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hash_cache: Option<core::HashCacheStats>,
+    },
+    DryRun(core::PackDryRun),
+    /// list_only=True: the manifest pack would write, without the zip
+    Listed {
+        manifest: core::Manifest,
+    },
+    Split(std::collections::BTreeMap<String, core::SplitOutput>),
+    /// out="-": the bundle comes back as bytes under "data" instead of being written to a file
+    Streamed {
+        path: Option<PathBuf>,
+        manifest: core::Manifest,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reused: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hash_cache: Option<core::HashCacheStats>,
+        #[serde(skip)]
+        data: Vec<u8>,
+    },
+}
+
+/// Pack plugins like `neko_plugin_cli pack`; returns {"path": ..., "manifest": {...}}.
+/// With dry_run=True nothing is written and the `pack --dry-run --json` report is returned;
+/// with list_only=True only {"manifest": {...}} is returned, as `pack --list-only --json` prints it;
+/// with split=True `out` is a directory and the result maps plugin id -> {"path", "size"} or {"error"};
+/// with out="-" the zip is returned as bytes under "data" and "path" is None;
+/// with base=<previous.zip> unchanged plugins are copied from it and listed under "reused".
+#[pyfunction]
+#[pyo3(signature = (
+    root=None,
+    plugin_ids=None,
+    out=None,
+    no_md5=false,
+    excludes=None,
+    exclude_profile=None,
+    respect_gitignore=false,
+    symlinks=None,
+    no_hash_cache=false,
+    cache_dir=None,
+    include_root=None,
+    bundle_name=None,
+    bundle_version=None,
+    bundle_author=None,
+    hash=None,
+    manifest_version=None,
+    compression=None,
+    compression_level=None,
+    reproducible=false,
+    source_date_epoch=None,
+    dry_run=false,
+    list_only=false,
+    allow_invalid=false,
+    progress=None,
+    with_deps=false,
+    include_disabled=false,
+    base=None,
+    sign_key=None,
+    verify=false,
+    split=false,
+    fail_fast=false,
+    spec=None,
+    max_size=None,
+    warn_size=None,
+    max_name_len=core::ZIP_NAME_MAX_LEN,
+))]
+#[allow(clippy::too_many_arguments)]
+fn pack(
+    py: Python<'_>,
+    root: Option<PathBuf>,
+    plugin_ids: Option<Vec<String>>,
+    out: Option<PathBuf>,
+    no_md5: bool,
+    excludes: Option<Vec<String>>,
+    exclude_profile: Option<&str>,
+    respect_gitignore: bool,
+    symlinks: Option<&str>,
+    no_hash_cache: bool,
+    cache_dir: Option<PathBuf>,
+    include_root: Option<Vec<String>>,
+    bundle_name: Option<String>,
+    bundle_version: Option<String>,
+    bundle_author: Option<String>,
+    hash: Option<&str>,
+    manifest_version: Option<u32>,
+    compression: Option<&str>,
+    compression_level: Option<i64>,
+    reproducible: bool,
+    source_date_epoch: Option<i64>,
+    dry_run: bool,
+    list_only: bool,
+    allow_invalid: bool,
+    progress: Option<PyObject>,
+    with_deps: bool,
+    include_disabled: bool,
+    base: Option<PathBuf>,
+    sign_key: Option<PathBuf>,
+    verify: bool,
+    split: bool,
+    fail_fast: bool,
+    spec: Option<PathBuf>,
+    max_size: Option<SizeArg>,
+    warn_size: Option<SizeArg>,
+    max_name_len: usize,
+) -> PyResult<PyObject> {
+    let max_size = max_size.map(SizeArg::bytes).transpose().map_err(to_py_err)?;
+    let warn_size = warn_size.map(SizeArg::bytes).transpose().map_err(to_py_err)?;
+    let plugin_ids = plugin_ids.unwrap_or_default();
+    let requested = plugin_ids.clone();
+    let excludes = excludes.unwrap_or_default();
+    let result = py.allow_threads(|| -> anyhow::Result<PackOutput> {
+        let repo_root = repo_root(root)?;
+        let plugins_dir = repo_root.join("plugin").join("plugins");
+        let source_date_epoch = match source_date_epoch {
+            Some(epoch) => Some(epoch),
+            None => core::resolve_source_date_epoch(reproducible)?,
+        };
+
+        let mut args = core::PackOverrides {
+            plugin_ids,
+            excludes,
+            include_root: include_root.unwrap_or_default(),
+            bundle: core::BundleMeta {
+                name: bundle_name,
+                version: bundle_version,
+                author: bundle_author,
+            },
+            exclude_profile: exclude_profile.map(str::parse).transpose()?,
+            hash: hash.map(str::parse).transpose()?,
+            compression: compression.map(str::parse).transpose()?,
+            compression_level,
+            manifest_version,
+            out,
+        };
+        let defaults = core::load_repo_bundle_defaults(&repo_root)?;
+        for w in &defaults.warnings {
+            py_warn(w)?;
+        }
+        if let Some(path) = &spec {
+            let spec = core::load_bundle_spec(path)?;
+            for w in &spec.warnings {
+                py_warn(w)?;
+            }
+            args = spec.apply(&plugins_dir, args, &defaults.meta, source_date_epoch)?;
+        } else {
+            args.bundle = args.bundle.or(&defaults.meta);
+        }
+        let symlinks = symlinks.map(str::parse).transpose()?.unwrap_or_default();
+        let profile = args.exclude_profile.unwrap_or_default();
+        let mut excludes = core::build_excludes_for(profile, &args.excludes)?.symlinks(symlinks);
+        if respect_gitignore {
+            excludes = excludes.respect_gitignore(&repo_root);
+        }
+        if !no_hash_cache {
+            excludes = excludes.hash_cache(&repo_root, cache_dir.as_deref());
+        }
+        let extras = core::resolve_extras(&repo_root, &args.include_root)?;
+        let algo = args.hash.unwrap_or_default();
+        let manifest_version = args.manifest_version.unwrap_or(1);
+        let compression = core::PackCompression {
+            method: args.compression.unwrap_or_default(),
+            level: args.compression_level,
+        };
+
+        let ids: Option<&[String]> = if args.plugin_ids.is_empty() { None } else { Some(&args.plugin_ids) };
+        let mut plugins = if with_deps {
+            core::scan_plugins_for_pack_with_deps(&plugins_dir, &args.plugin_ids)?
+        } else {
+            core::scan_plugins_for_pack(&plugins_dir, ids)?
+        };
+        if !include_disabled {
+            for id in core::skip_disabled_plugins(&plugins_dir, &mut plugins, &requested)? {
+                py_warn(&format!("skipping disabled plugin {}", id))?;
+            }
+        }
+        if plugins.is_empty() {
+            anyhow::bail!("no plugins found to pack");
+        }
+        for w in core::validate_pack_plugins(&plugins, allow_invalid)? {
+            py_warn(&w)?;
+        }
+        let out_path = match args.out {
+            Some(p) => p,
+            None if split => PathBuf::from("."),
+            None => core::default_pack_output(&plugins, !args.plugin_ids.is_empty()),
+        };
+        let stream = core::is_stream_path(&out_path);
+        if stream && split {
+            anyhow::bail!("out=\"-\" cannot be combined with split=True");
+        }
+        let bundle_meta = args.bundle;
+        let sign_key = sign_key.as_deref().map(core::read_signing_key).transpose()?;
+        if sign_key.is_some() && manifest_version < 2 {
+            anyhow::bail!("sign_key requires manifest_version=2 (its per-file checksums are what the signature covers)");
+        }
+
+        if base.is_some() && no_md5 {
+            anyhow::bail!("base needs folder hashes to find unchanged plugins; it cannot be combined with no_md5");
+        }
+
+        if list_only {
+            if dry_run || split || base.is_some() || sign_key.is_some() || verify {
+                anyhow::bail!("list_only cannot be combined with dry_run, split, base, sign_key or verify");
+            }
+            core::compute_plugin_hash_for_pack(&mut plugins, &excludes, algo, no_md5, None)?;
+            let name_hint = if stream {
+                core::default_pack_output(&plugins, !args.plugin_ids.is_empty())
+            } else {
+                out_path
+            };
+            let manifest = core::plan_pack(
+                &name_hint,
+                &plugins,
+                &excludes,
+                &extras,
+                bundle_meta,
+                manifest_version,
+                compression,
+                max_name_len,
+                source_date_epoch,
+            )?
+            .into_manifest()?;
+            return Ok(PackOutput::Listed { manifest });
+        }
+
+        if dry_run {
+            let plan = core::plan_pack(
+                &out_path,
+                &plugins,
+                &excludes,
+                &extras,
+                bundle_meta,
+                manifest_version,
+                compression,
+                max_name_len,
+                source_date_epoch,
+            )?;
+            let mut report = plan.into_dry_run(&out_path, 10);
+            report.apply_size_budget(max_size, warn_size);
+            return Ok(PackOutput::DryRun(report));
+        }
+
+        let on_progress = |ev: core::PackProgress<'_>| {
+            if let Some(cb) = &progress {
+                Python::with_gil(|py| {
+                    if let Err(e) = progress_event(py, ev).and_then(|d| cb.call1(py, (d,))) {
+                        e.write_unraisable(py, Some(cb.bind(py)));
+                    }
+                });
+            }
+        };
+        let progress_cb: Option<core::ProgressFn<'_>> = if progress.is_some() { Some(&on_progress) } else { None };
+
+        let mut base = base.as_deref().map(core::PackBase::open).transpose()?;
+        let mut pack_one = |plugins: &mut [core::PluginPackItem], out_path: &std::path::Path| {
+            core::compute_plugin_hash_for_pack(plugins, &excludes, algo, no_md5, progress_cb)?;
+            let manifest = core::pack_to_zip(
+                out_path,
+                plugins,
+                &excludes,
+                &extras,
+                bundle_meta.clone(),
+                manifest_version,
+                compression,
+                max_name_len,
+                source_date_epoch,
+                max_size,
+                base.as_mut(),
+                progress_cb,
+            )?;
+            if let Some(warning) = warn_size.map(|w| core::check_warn_size(out_path, w)).transpose()?.flatten() {
+                py_warn(&warning)?;
+            }
+            if let Some(key) = &sign_key {
+                core::sign_bundle(out_path, key, source_date_epoch)?;
+            }
+            if verify {
+                core::verify_pack_output(out_path)?;
+            }
+            Ok(manifest)
+        };
+
+        if split {
+            let outputs = core::pack_split(&out_path, &plugins, fail_fast, |one, path| pack_one(one, path).map(drop))?;
+            return Ok(PackOutput::Split(outputs));
+        }
+        if stream {
+            let spool = core::StreamSpool::new(&core::default_pack_output(&plugins, !args.plugin_ids.is_empty()))?;
+            let manifest = pack_one(&mut plugins, spool.path())?;
+            let mut data = Vec::new();
+            spool.copy_to(&mut data)?;
+            return Ok(PackOutput::Streamed {
+                path: None,
+                manifest,
+                reused: base.map(|b| b.reused().to_vec()),
+                hash_cache: excludes.hash_cache_stats(),
+                data,
+            });
+        }
+        let manifest = pack_one(&mut plugins, &out_path)?;
+        Ok(PackOutput::Packed {
+            path: out_path,
+            manifest,
+            reused: base.map(|b| b.reused().to_vec()),
+            hash_cache: excludes.hash_cache_stats(),
+        })
+    });
+    let result = result.map_err(to_py_err)?;
+    let obj = to_py(py, &result)?;
+    if let PackOutput::Streamed { data, .. } = &result {
+        obj.bind(py).set_item("data", PyBytes::new(py, data))?;
+    }
+    Ok(obj)
+}
+
+/// Run `neko_plugin_cli check`; returns the report as a dict instead of printing it
+/// profiles=True validates profiles.toml and profiles/*.toml; profile_warn_size (int or "64KiB", 0 = off) sets the size warning.
+/// graph="dot" | "mermaid" adds the dependency graph as a string under "graph".
+/// baseline=path suppresses the findings listed there (counted under "baselined"); write_baseline=True first writes every finding to it.
+/// fix=True rewrites plugin.toml for fixable findings before checking and lists the edits under "fixes" (dry_run=True only lists them).
+/// py_syntax=True compiles the plugins' .py files offline and reports syntax errors (py_syntax_strict=True: a missing interpreter is an error).
+/// lock=path (with python_online) writes the compiled result to a lockfile plus its requirements.in; verify_lock=True compares against it instead.
+/// Unchanged plugins reuse their findings from the check cache under cache_dir (listed under "cache"); no_cache=True skips it.
+/// zip=path checks the plugins inside a bundle without unpacking it (id/deps/base, plus manifest vs plugin.toml).
+/// skip_disabled=True leaves plugins turned off with disable() out of the checked set.
+#[pyfunction]
+#[pyo3(signature = (
+    root=None,
+    plugin_id=None,
+    id=false,
+    deps=false,
+    base=false,
+    toml=false,
+    entry=false,
+    profiles=false,
+    profile_warn_size=None,
+    zip=None,
+    graph=None,
+    baseline=None,
+    write_baseline=false,
+    fix=false,
+    dry_run=false,
+    py_syntax=false,
+    py_syntax_strict=false,
+    python_online=false,
+    python_strict=false,
+    lock=None,
+    verify_lock=false,
+    cache_dir=None,
+    no_cache=false,
+    skip_disabled=false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn check(
+    py: Python<'_>,
+    root: Option<PathBuf>,
+    plugin_id: Option<String>,
+    id: bool,
+    deps: bool,
+    base: bool,
+    toml: bool,
+    entry: bool,
+    profiles: bool,
+    profile_warn_size: Option<SizeArg>,
+    zip: Option<PathBuf>,
+    graph: Option<&str>,
+    baseline: Option<PathBuf>,
+    write_baseline: bool,
+    fix: bool,
+    dry_run: bool,
+    py_syntax: bool,
+    py_syntax_strict: bool,
+    python_online: bool,
+    python_strict: bool,
+    lock: Option<PathBuf>,
+    verify_lock: bool,
+    cache_dir: Option<PathBuf>,
+    no_cache: bool,
+    skip_disabled: bool,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| -> anyhow::Result<(core::CheckReport, Option<String>)> {
+        let repo_root = repo_root(root)?;
+        let plugins_dir = repo_root.join("plugin").join("plugins");
+        let sdk_version = core::read_sdk_version(&repo_root)?;
+        let graph = graph
+            .map(|f| core::dependency_graph(&plugins_dir, plugin_id.as_deref(), f.parse()?))
+            .transpose()?;
+
+        let mut checks = core::resolve_check_flags(id, deps, base, toml, entry, profiles);
+        checks.strict_naming = core::load_repo_check_config(&repo_root)?.strict_naming;
+        if let Some(size) = profile_warn_size {
+            checks.profile_warn_size = size.bytes()?;
+        }
+        checks.skip_disabled = skip_disabled;
+        if dry_run && !fix {
+            anyhow::bail!("dry_run needs fix");
+        }
+        if py_syntax_strict && !py_syntax {
+            anyhow::bail!("py_syntax_strict needs py_syntax");
+        }
+        if lock.is_some() && !python_online {
+            anyhow::bail!("lock needs python_online");
+        }
+        if verify_lock && lock.is_none() {
+            anyhow::bail!("verify_lock needs lock");
+        }
+        if zip.is_some() && (graph.is_some() || fix || py_syntax || python_online) {
+            anyhow::bail!("zip cannot be combined with graph, fix, py_syntax or python_online");
+        }
+        let cache = if no_cache || zip.is_some() {
+            None
+        } else {
+            Some(core::CheckCache::open(&repo_root, cache_dir.as_deref())?)
+        };
+        let fixes = if fix {
+            let before = core::run_checks(&plugins_dir, plugin_id.as_deref(), &sdk_version, checks, cache.as_ref())?;
+            Some(core::fix_check_findings(&plugins_dir, &before, &sdk_version, dry_run)?)
+        } else {
+            None
+        };
+        let mut report = match &zip {
+            Some(zip) => core::run_checks_zip(zip, plugin_id.as_deref(), &plugins_dir, &sdk_version, checks)?,
+            None => core::run_checks(&plugins_dir, plugin_id.as_deref(), &sdk_version, checks, cache.as_ref())?,
+        };
+        report.fixes = fixes;
+        if py_syntax {
+            report.attach_py_syntax(core::run_py_syntax_check(
+                &plugins_dir,
+                plugin_id.as_deref(),
+                py_syntax_strict,
+                cache.as_ref(),
+            )?);
+        }
+        report.cache = cache.as_ref().map(core::CheckCache::stats);
+        if python_online {
+            let mut online = core::run_python_online_check(
+                &repo_root,
+                &plugins_dir,
+                plugin_id.as_deref(),
+                python_strict,
+                cache_dir.as_deref(),
+            )?;
+            if let Some(path) = &lock {
+                core::apply_python_lock(&repo_root, &mut online, path, verify_lock)?;
+            }
+            report.attach_python_online(online);
+        }
+        match &baseline {
+            Some(path) => {
+                let known = if write_baseline {
+                    let known = report.to_baseline()?;
+                    known.write(path)?;
+                    known
+                } else {
+                    core::CheckBaseline::read(path)?
+                };
+                report.apply_baseline(&known)?;
+            }
+            None if write_baseline => anyhow::bail!("write_baseline needs baseline"),
+            None => {}
+        }
+        Ok((report, graph))
+    });
+    let (report, graph) = result.map_err(to_py_err)?;
+    let obj = to_py(py, &report)?;
+    if let Some(graph) = graph {
+        obj.bind(py).set_item("graph", graph)?;
+    }
+    Ok(obj)
+}
+
+fn signature_policy(verify_key: Option<PathBuf>, require_signature: bool) -> anyhow::Result<Option<core::SignaturePolicy>> {
+    match verify_key {
+        Some(path) => Ok(Some(core::SignaturePolicy {
+            key: core::read_verifying_key(&path)?,
+            required: require_signature,
+        })),
+        None if require_signature => anyhow::bail!("require_signature needs verify_key"),
+        None => Ok(None),
+    }
+}
+
+/// Describe what unpack() would do for each plugin in the bundle, without writing anything
+#[pyfunction]
+#[pyo3(signature = (zip_path, dest, force=false, verify_key=None, require_signature=false))]
+fn preview_unpack(
+    py: Python<'_>,
+    zip_path: PathBuf,
+    dest: PathBuf,
+    force: bool,
+    verify_key: Option<PathBuf>,
+    require_signature: bool,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| -> anyhow::Result<Vec<core::UnpackPreviewItem>> {
+        let excludes = core::build_excludes(&[])?;
+        let signature = signature_policy(verify_key, require_signature)?;
+        core::preview_unpack(&zip_path, &dest, force, &excludes, signature.as_ref())
+    });
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// A bundle given as a path or as the zip's bytes (e.g. pack(out="-")["data"])
+#[derive(FromPyObject)]
+enum BundleSource {
+    Bytes(Vec<u8>),
+    Path(PathBuf),
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum UnpackOutput {
+    Unpacked(Box<core::UnpackResult>),
+    /// dry_run=True: {"plugins": [...], "summary": {"install": n, "skip": n, "bundle": ...}}
+    DryRun(core::UnpackPreview),
+}
+
+/// Install a bundle into `dest`; returns {"installed": [...], "skipped": [...], "warnings": [...], "signed_by": ...}.
+/// The bundle's extras/ files are written under `extras_dest` when it is given.
+/// Plugin files matching `excludes` (globs relative to the plugin folder) are not extracted; see "filtered".
+/// Symlink entries in the zip are refused unless allow_symlinks=True; created links are listed under "symlinks".
+/// dry_run=True writes nothing and returns the `unpack --dry-run --json` report instead.
+/// backup=True (or a backup_dir, default `<dest>/.backups`) copies plugin folders to be overwritten first.
+/// Extracted files are checked against the manifest's per-file checksums unless no_verify=True;
+/// each installed plugin reports "verification": "verified" | "skipped" | "no_checksums".
+/// progress, if given, is called with event dicts ("plugin_start", "file_extracted", "plugin_skipped", "done").
+/// assume_version reads a bundle whose manifest is newer than this build supports as that older version.
+/// apply_profiles="merge" | "overwrite" also copies bundled profiles into the plugin's profiles; see "profiles".
+/// check=True runs the plugin checks on the installed plugins ("check" in the result) against the SDK of
+/// `root` (default: found from cwd); check_strict=True undoes the install and raises when they report errors.
+/// overwrite lists plugin folders to replace as if force=True, like the answers to `unpack --interactive`.
+/// prune=True deletes files of overwritten plugins that the bundle no longer has (each plugin's "pruned").
+/// Hostile archives (NUL bytes, device names, duplicate or case-colliding names, zip bombs) raise ValueError;
+/// max_uncompressed_bytes (int or "20GiB", default 8 GiB) caps the declared and the extracted uncompressed size.
+/// report=<path> writes the `unpack --report` JSON there, also when the unpack raises.
+/// staged=True (or a staging_dir) extracts every plugin before swapping any into place and rolls back on a failed swap.
+/// A zip holding a single plugin folder and no manifest.toml is installed as that plugin (with a warning);
+/// no_manifest=True always reads the zip that way.
+#[pyfunction]
+#[pyo3(signature = (zip_path, dest, force=false, excludes=None, verify_key=None, require_signature=false, extras_dest=None, allow_symlinks=false, dry_run=false, backup=false, backup_dir=None, no_verify=false, progress=None, assume_version=None, apply_profiles=None, check=false, check_strict=false, root=None, overwrite=None, prune=false, max_uncompressed_bytes=None, report=None, staged=false, staging_dir=None, no_manifest=false))]
+#[allow(clippy::too_many_arguments)]
+fn unpack(
+    py: Python<'_>,
+    zip_path: BundleSource,
+    dest: PathBuf,
+    force: bool,
+    excludes: Option<Vec<String>>,
+    verify_key: Option<PathBuf>,
+    require_signature: bool,
+    extras_dest: Option<PathBuf>,
+    allow_symlinks: bool,
+    dry_run: bool,
+    backup: bool,
+    backup_dir: Option<PathBuf>,
+    no_verify: bool,
+    progress: Option<PyObject>,
+    assume_version: Option<u32>,
+    apply_profiles: Option<&str>,
+    check: bool,
+    check_strict: bool,
+    root: Option<PathBuf>,
+    overwrite: Option<Vec<String>>,
+    prune: bool,
+    max_uncompressed_bytes: Option<SizeArg>,
+    report: Option<PathBuf>,
+    staged: bool,
+    staging_dir: Option<PathBuf>,
+    no_manifest: bool,
+) -> PyResult<PyObject> {
+    let max_uncompressed = match max_uncompressed_bytes {
+        Some(size) => size.bytes().map_err(to_py_err)?,
+        None => core::UNPACK_MAX_UNCOMPRESSED_BYTES,
+    };
+    let excludes = excludes.unwrap_or_default();
+    let overwrite = overwrite.unwrap_or_default();
+    let result = py.allow_threads(|| -> anyhow::Result<UnpackOutput> {
+        let excludes = core::build_excludes(&excludes)?;
+        let signature = signature_policy(verify_key, require_signature)?;
+        if prune && !force && overwrite.is_empty() {
+            anyhow::bail!("prune requires force=True or overwrite");
+        }
+        let apply_profiles: core::ApplyProfiles = apply_profiles.map(str::parse).transpose()?.unwrap_or_default();
+        let unpack_check = if (check || check_strict) && !dry_run {
+            let repo_root = repo_root(root)?;
+            let mut checks = core::resolve_check_flags(false, false, false, false, false, false);
+            checks.strict_naming = core::load_repo_check_config(&repo_root)?.strict_naming;
+            Some(core::UnpackCheck {
+                sdk_version: core::read_sdk_version(&repo_root)?,
+                checks,
+                strict: check_strict,
+            })
+        } else {
+            None
+        };
+        let backup_dir = match backup_dir {
+            Some(dir) => Some(dir),
+            None if backup => Some(dest.join(core::UNPACK_BACKUP_DIR)),
+            None => None,
+        };
+        let staging_dir = match staging_dir {
+            Some(dir) => Some(dir),
+            None if staged => Some(dest.join(core::UNPACK_STAGING_DIR)),
+            None => None,
+        };
+        if dry_run {
+            if extras_dest.is_some() {
+                anyhow::bail!("dry_run cannot be combined with extras_dest");
+            }
+            let preview = match zip_path {
+                BundleSource::Path(path) => {
+                    core::preview_unpack_report(
+                        &path,
+                        &dest,
+                        force,
+                        &excludes,
+                        signature.as_ref(),
+                        assume_version,
+                        no_manifest,
+                    )?
+                }
+                BundleSource::Bytes(data) => {
+                    let source = std::path::Path::new("<bytes>");
+                    core::preview_reader(
+                        std::io::Cursor::new(data),
+                        source,
+                        &dest,
+                        force,
+                        &excludes,
+                        signature.as_ref(),
+                        assume_version,
+                        no_manifest,
+                    )?
+                }
+            };
+            return Ok(UnpackOutput::DryRun(preview));
+        }
+        let on_progress = |ev: core::UnpackProgress<'_>| {
+            if let Some(cb) = &progress {
+                Python::with_gil(|py| {
+                    if let Err(e) = unpack_progress_event(py, ev).and_then(|d| cb.call1(py, (d,))) {
+                        e.write_unraisable(py, Some(cb.bind(py)));
+                    }
+                });
+            }
+        };
+        let progress_cb: Option<core::UnpackProgressFn<'_>> = if progress.is_some() { Some(&on_progress) } else { None };
+        let started = std::time::Instant::now();
+        let source = match &zip_path {
+            BundleSource::Path(path) => path.display().to_string(),
+            BundleSource::Bytes(_) => "<bytes>".to_string(),
+        };
+        let outcome = match zip_path {
+            BundleSource::Path(path) => {
+                core::unpack_zip(
+                    &path,
+                    &dest,
+                    force,
+                    &excludes,
+                    signature.as_ref(),
+                    extras_dest.as_deref(),
+                    allow_symlinks,
+                    backup_dir.as_deref(),
+                    no_verify,
+                    assume_version,
+                    apply_profiles,
+                    unpack_check.as_ref(),
+                    &overwrite,
+                    prune,
+                    max_uncompressed,
+                    staging_dir.as_deref(),
+                    no_manifest,
+                    progress_cb,
+                )
+            }
+            BundleSource::Bytes(data) => {
+                let source = std::path::Path::new("<bytes>");
+                core::unpack_reader(
+                    std::io::Cursor::new(std::sync::Arc::<[u8]>::from(data)),
+                    source,
+                    &dest,
+                    force,
+                    &excludes,
+                    signature.as_ref(),
+                    extras_dest.as_deref(),
+                    allow_symlinks,
+                    backup_dir.as_deref(),
+                    no_verify,
+                    assume_version,
+                    apply_profiles,
+                    unpack_check.as_ref(),
+                    &overwrite,
+                    prune,
+                    max_uncompressed,
+                    staging_dir.as_deref(),
+                    no_manifest,
+                    progress_cb,
+                )
+            }
+        };
+        if let Some(path) = &report {
+            let written = core::UnpackReport::new(&source, &dest, started.elapsed(), &outcome).write(path);
+            match written {
+                Err(e) if outcome.is_ok() => return Err(e),
+                Err(e) => py_warn(&format!("{:#}", e))?,
+                Ok(()) => {}
+            }
+        }
+        Ok(UnpackOutput::Unpacked(Box::new(outcome?)))
+    });
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Repo summary like `neko_plugin_cli info --json`, including the bundle defaults pack would use.
+/// format="text"|"table"|"json"|"yaml" returns the rendered string instead of a dict; columns
+/// (e.g. ["id", "version"]) picks the plugin fields of either.
+/// sizes=True adds file_count/total_bytes/excluded_bytes per plugin and "totals"; sort="size" lists the largest first.
+/// compat=True adds each plugin's "sdk_compat" (ok/untested/unsupported/conflict) and the "sdk_version" it refers to.
+/// bundles=True adds "bundles": the installed plugins grouped by the bundle unpack installed them from.
+#[pyfunction]
+#[pyo3(signature = (root=None, format=None, columns=None, sizes=false, sort=None, compat=false, bundles=false))]
+#[allow(clippy::too_many_arguments)]
+fn info(
+    py: Python<'_>,
+    root: Option<PathBuf>,
+    format: Option<&str>,
+    columns: Option<Vec<String>>,
+    sizes: bool,
+    sort: Option<&str>,
+    compat: bool,
+    bundles: bool,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| -> anyhow::Result<serde_json::Value> {
+        let sort = sort.map(str::parse).transpose()?.unwrap_or_default();
+        let info = core::collect_info(root.as_deref(), core::InfoOptions { sizes, sort, compat, bundles })?;
+        let columns = columns
+            .map(|cols| cols.iter().map(|c| c.parse()).collect::<anyhow::Result<Vec<core::InfoColumn>>>())
+            .transpose()?;
+        Ok(match format {
+            Some(f) => serde_json::Value::String(core::render_info(&info, f.parse()?, columns.as_deref())?),
+            None => serde_json::from_str(&core::render_info(&info, core::InfoFormat::Json, columns.as_deref())?)?,
+        })
+    });
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Plugins like `neko_plugin_cli list --json`: `match` must match the whole id (regex), `version_req`
+/// is a semver requirement and `entry_contains` a substring of the entry
+#[pyfunction]
+#[pyo3(signature = (root=None, r#match=None, version_req=None, entry_contains=None))]
+fn list_plugins(
+    py: Python<'_>,
+    root: Option<PathBuf>,
+    r#match: Option<&str>,
+    version_req: Option<&str>,
+    entry_contains: Option<&str>,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| {
+        let filter = core::PluginFilter::new(r#match, version_req, entry_contains)?;
+        core::list_plugins(root.as_deref(), &filter)
+    });
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Scaffold plugin/plugins/<id>/ like `neko_plugin_cli new`; returns the created path and files
+#[pyfunction]
+#[pyo3(signature = (id, root=None, name=None, author=None, sdk_supported=None, template=None))]
+fn new_plugin(
+    py: Python<'_>,
+    id: &str,
+    root: Option<PathBuf>,
+    name: Option<String>,
+    author: Option<String>,
+    sdk_supported: Option<String>,
+    template: Option<PathBuf>,
+) -> PyResult<PyObject> {
+    let options = core::ScaffoldOptions {
+        name,
+        author,
+        sdk_supported,
+        template,
+    };
+    let result = py.allow_threads(|| core::scaffold_plugin(root.as_deref(), id, &options));
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Remove plugins like `neko_plugin_cli remove --json`; `backup=True` backs up to plugin/plugins/.backups
+#[pyfunction]
+#[pyo3(signature = (plugin_ids, root=None, force=false, dry_run=false, backup=false, backup_dir=None))]
+fn remove(
+    py: Python<'_>,
+    plugin_ids: Vec<String>,
+    root: Option<PathBuf>,
+    force: bool,
+    dry_run: bool,
+    backup: bool,
+    backup_dir: Option<PathBuf>,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| {
+        let repo_root = repo_root(root)?;
+        let backup_dir = match backup_dir {
+            Some(dir) => Some(dir),
+            None if backup => Some(repo_root.join("plugin").join("plugins").join(core::UNPACK_BACKUP_DIR)),
+            None => None,
+        };
+        let options = core::RemoveOptions {
+            force,
+            dry_run,
+            backup_dir,
+        };
+        core::remove_plugins(Some(&repo_root), &plugin_ids, &options)
+    });
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Take plugins out of rotation like `neko_plugin_cli disable`; returns `changed`/`unchanged` ids
+#[pyfunction]
+#[pyo3(signature = (plugin_ids, root=None))]
+fn disable(py: Python<'_>, plugin_ids: Vec<String>, root: Option<PathBuf>) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| core::set_plugins_disabled(root.as_deref(), &plugin_ids, true));
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Undo disable() like `neko_plugin_cli enable`
+#[pyfunction]
+#[pyo3(signature = (plugin_ids, root=None))]
+fn enable(py: Python<'_>, plugin_ids: Vec<String>, root: Option<PathBuf>) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| core::set_plugins_disabled(root.as_deref(), &plugin_ids, false));
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Remove caches and temporary files like `neko_plugin_cli clean --json`; pass `cache` and/or `tmp`
+#[pyfunction]
+#[pyo3(signature = (root=None, cache=false, tmp=false, dry_run=false, cache_dir=None))]
+fn clean(
+    py: Python<'_>,
+    root: Option<PathBuf>,
+    cache: bool,
+    tmp: bool,
+    dry_run: bool,
+    cache_dir: Option<PathBuf>,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| {
+        let options = core::CleanOptions {
+            cache,
+            tmp,
+            dry_run,
+            cache_dir,
+        };
+        core::clean(&repo_root(root)?, &options)
+    });
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Compare two bundles like `neko_plugin_cli diff --json`; `against_installed=True` compares `old`
+/// with the installed plugins (`dest`, default plugin/plugins under `root`) instead. `text=True`
+/// returns the text report.
+#[pyfunction]
+#[pyo3(signature = (old, new=None, against_installed=false, root=None, dest=None, text=false))]
+fn diff(
+    py: Python<'_>,
+    old: PathBuf,
+    new: Option<PathBuf>,
+    against_installed: bool,
+    root: Option<PathBuf>,
+    dest: Option<PathBuf>,
+    text: bool,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| -> anyhow::Result<core::BundleDiff> {
+        match new {
+            Some(_) if against_installed => anyhow::bail!("new cannot be combined with against_installed"),
+            Some(new) => core::diff_bundles(&old, &new),
+            None if !against_installed => anyhow::bail!("diff needs new or against_installed=True"),
+            None => {
+                let dest_dir = match dest {
+                    Some(dir) => dir,
+                    None => repo_root(root)?.join("plugin").join("plugins"),
+                };
+                core::diff_bundle_installed(&old, &dest_dir)
+            }
+        }
+    });
+    let diff = result.map_err(to_py_err)?;
+    if text {
+        return to_py(py, &core::format_bundle_diff(&diff));
+    }
+    to_py(py, &diff)
+}
+
+/// Like `neko_plugin_cli verify --json`: every integrity problem of the bundle under `issues`
+/// (empty when it is intact), plus the signature check with `verify_key`
+#[pyfunction]
+#[pyo3(signature = (zip_path, verify_key=None, require_signature=false))]
+fn verify(py: Python<'_>, zip_path: PathBuf, verify_key: Option<PathBuf>, require_signature: bool) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| {
+        let signature = signature_policy(verify_key, require_signature)?;
+        core::verify_bundle_report(&zip_path, signature.as_ref())
+    });
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Upgrade a bundle's manifest like `neko_plugin_cli migrate --json`; `out=None` rewrites
+/// `zip_path` in place
+#[pyfunction]
+#[pyo3(signature = (zip_path, out=None, format_version=None, sign_key=None, reproducible=false, bundle_name=None, bundle_version=None, bundle_author=None))]
+#[allow(clippy::too_many_arguments)]
+fn migrate(
+    py: Python<'_>,
+    zip_path: PathBuf,
+    out: Option<PathBuf>,
+    format_version: Option<u32>,
+    sign_key: Option<PathBuf>,
+    reproducible: bool,
+    bundle_name: Option<String>,
+    bundle_version: Option<String>,
+    bundle_author: Option<String>,
+) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| {
+        let options = core::MigrateOptions {
+            format_version,
+            bundle: core::BundleMeta {
+                name: bundle_name,
+                version: bundle_version,
+                author: bundle_author,
+            },
+            sign_key: sign_key.as_deref().map(core::read_signing_key).transpose()?,
+            source_date_epoch: core::resolve_source_date_epoch(reproducible)?,
+        };
+        let out = out.unwrap_or_else(|| zip_path.clone());
+        core::migrate_bundle(&zip_path, &out, &options)
+    });
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Dependency tree like `neko_plugin_cli deps --json`: a list of root nodes with nested
+/// `dependencies`; `text=True` returns the ASCII tree instead
+#[pyfunction]
+#[pyo3(signature = (plugin_id=None, root=None, text=false))]
+fn deps(py: Python<'_>, plugin_id: Option<&str>, root: Option<PathBuf>, text: bool) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| {
+        let plugins_dir = repo_root(root)?.join("plugin").join("plugins");
+        core::dependency_tree(&plugins_dir, plugin_id)
+    });
+    let tree = result.map_err(to_py_err)?;
+    if text {
+        return to_py(py, &core::format_dependency_tree(&tree));
+    }
+    to_py(py, &tree)
+}
+
+/// Re-read a bundle and check it against its manifest (CRCs, per-file checksums, folder hashes)
+#[pyfunction]
+fn verify_bundle(py: Python<'_>, zip_path: PathBuf) -> PyResult<PyObject> {
+    let result = py.allow_threads(|| core::verify_bundle(&zip_path));
+    to_py(py, &result.map_err(to_py_err)?)
+}
+
+/// Generate an ed25519 key pair like `neko_plugin_cli keygen`
+#[pyfunction]
+#[pyo3(signature = (out, force=false))]
+fn keygen(py: Python<'_>, out: PathBuf, force: bool) -> PyResult<PyObject> {
+    let key = core::generate_signing_key(&out, force).map_err(to_py_err)?;
+    to_py(py, &key)
+}
+
 #[pymodule]
 pub fn neko_plugin_cli(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(py_add, m)?)?;
     m.add_function(wrap_pyfunction!(py_version, m)?)?;
+    m.add_function(wrap_pyfunction!(pack, m)?)?;
+    m.add_function(wrap_pyfunction!(check, m)?)?;
+    m.add_function(wrap_pyfunction!(info, m)?)?;
+    m.add_function(wrap_pyfunction!(list_plugins, m)?)?;
+    m.add_function(wrap_pyfunction!(new_plugin, m)?)?;
+    m.add_function(wrap_pyfunction!(remove, m)?)?;
+    m.add_function(wrap_pyfunction!(clean, m)?)?;
+    m.add_function(wrap_pyfunction!(disable, m)?)?;
+    m.add_function(wrap_pyfunction!(enable, m)?)?;
+    m.add_function(wrap_pyfunction!(diff, m)?)?;
+    m.add_function(wrap_pyfunction!(deps, m)?)?;
+    m.add_function(wrap_pyfunction!(preview_unpack, m)?)?;
+    m.add_function(wrap_pyfunction!(unpack, m)?)?;
+    m.add_function(wrap_pyfunction!(keygen, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_bundle, m)?)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    m.add_function(wrap_pyfunction!(migrate, m)?)?;
     Ok(())
 }
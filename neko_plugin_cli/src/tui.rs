@@ -1,7 +1,8 @@
+use std::cell::Cell;
 use std::cmp::Ordering;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::mpsc::Receiver;
 use std::time::{Duration, Instant};
@@ -15,12 +16,12 @@ use crossterm::terminal::{Clear, ClearType};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Span};
+use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Wrap};
 use ratatui::Terminal;
 use ratatui::{backend::CrosstermBackend, Frame};
 
-use crate::core;
+use neko_plugin_cli::{api, core};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Screen {
@@ -57,6 +58,9 @@ fn load_pack_list(app: &mut App) -> Result<()> {
     };
     let plugins_dir = repo_root.join("plugin").join("plugins");
     let ids = core::list_packable_plugin_ids(&plugins_dir)?;
+    // Only informational; a broken bundle.toml is reported by pack itself
+    app.bundle_defaults = core::load_repo_bundle_defaults(&repo_root).map(|d| d.meta).unwrap_or_default();
+    app.editing_mode_field = false;
     app.pack_items = ids;
     app.pack_selected = vec![true; app.pack_items.len()];
     app.pack_cursor = 0;
@@ -68,6 +72,9 @@ fn load_pack_list(app: &mut App) -> Result<()> {
 }
 
 fn init_path_root(app: &mut App) -> Result<()> {
+    app.path_pick_dest = false;
+    app.editing_new_dir = false;
+    app.editing_path_entry = false;
     if !matches!(app.cmd, CmdKind::Pack | CmdKind::Unpack) {
         return Ok(());
     }
@@ -86,7 +93,7 @@ fn sync_path_to_args(app: &mut App) -> Result<()> {
     }
 
     match app.cmd {
-        CmdKind::Pack => {
+        _ if picks_directory(app) => {
             if let Some(dest) = &app.args.dest {
                 if dest.is_dir() {
                     app.path_current_dir = dest.clone();
@@ -166,7 +173,7 @@ fn refresh_path_entries(app: &mut App) -> Result<()> {
                 is_parent: false,
             });
         } else if md.is_file() {
-            if matches!(app.cmd, CmdKind::Unpack) {
+            if matches!(app.cmd, CmdKind::Unpack) && !app.path_pick_dest {
                 let is_zip = name.to_lowercase().ends_with(".zip");
                 if is_zip {
                     entries.push(PathEntry {
@@ -191,6 +198,133 @@ fn refresh_path_entries(app: &mut App) -> Result<()> {
     Ok(())
 }
 
+/// Whether the Path tab chooses a directory (Pack output, Unpack destination) rather than a .zip
+fn picks_directory(app: &App) -> bool {
+    matches!(app.cmd, CmdKind::Pack) || (matches!(app.cmd, CmdKind::Unpack) && app.path_pick_dest)
+}
+
+/// Tab in Unpack's Path tab: switch between choosing the .zip and the destination directory
+fn toggle_unpack_path_target(app: &mut App) -> Result<()> {
+    app.path_pick_dest = !app.path_pick_dest;
+    app.editing_new_dir = false;
+    refresh_path_entries(app)?;
+    sync_path_to_args(app)
+}
+
+/// Create `app.new_dir_name` in the directory being browsed and choose it, like Space on a directory
+fn create_dir_from_prompt(app: &mut App) {
+    let name = app.new_dir_name.trim().to_string();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        app.new_dir_error = Some("invalid directory name".to_string());
+        return;
+    }
+    let dir = app.path_current_dir.join(&name);
+    if let Err(e) = fs::create_dir(&dir) {
+        app.new_dir_error = Some(format!("failed to create {}: {e}", dir.display()));
+        return;
+    }
+    app.editing_new_dir = false;
+    app.new_dir_name.clear();
+    app.new_dir_error = None;
+    app.path_current_dir = dir.clone();
+    app.args.dest = Some(dir);
+    let _ = refresh_path_entries(app);
+}
+
+/// 'e' or a click on the directory line: type a path, starting from the one being browsed
+fn start_path_entry(app: &mut App) {
+    let mut text = app.path_current_dir.display().to_string();
+    if !text.ends_with(std::path::MAIN_SEPARATOR) {
+        text.push(std::path::MAIN_SEPARATOR);
+    }
+    app.path_entry = text;
+    app.path_entry_error = None;
+    app.path_entry_candidates.clear();
+    app.editing_path_entry = true;
+}
+
+/// A typed path; relative ones are taken from the directory being browsed
+fn resolve_typed_path(base: &Path, typed: &str) -> PathBuf {
+    let path = Path::new(typed.trim());
+    if path.is_absolute() { path.to_path_buf() } else { base.join(path) }
+}
+
+/// Tab completion for a typed path: the entries of its parent directory that start with its last
+/// component, directories (with a trailing separator) and, when `zips`, .zip files. Returns the
+/// input extended by the longest common prefix of the matches, and the matches themselves.
+fn complete_path(typed: &str, base: &Path, zips: bool) -> (String, Vec<String>) {
+    let ends_with_sep = typed.ends_with('/') || typed.ends_with(std::path::MAIN_SEPARATOR);
+    let (dir_part, prefix) = if typed.is_empty() || ends_with_sep {
+        (typed, "")
+    } else {
+        let cut = typed.rfind(['/', std::path::MAIN_SEPARATOR]).map_or(0, |i| i + 1);
+        (&typed[..cut], &typed[cut..])
+    };
+    let dir = resolve_typed_path(base, dir_part);
+    let Ok(read) = fs::read_dir(&dir) else {
+        return (typed.to_string(), Vec::new());
+    };
+    let mut matches: Vec<String> = read
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            if !name.starts_with(prefix) || (prefix.is_empty() && name.starts_with('.')) {
+                return None;
+            }
+            let path = e.path();
+            if path.is_dir() {
+                Some(format!("{name}{}", std::path::MAIN_SEPARATOR))
+            } else if zips && name.to_lowercase().ends_with(".zip") {
+                Some(name)
+            } else {
+                None
+            }
+        })
+        .collect();
+    matches.sort();
+    let Some(first) = matches.first() else {
+        return (typed.to_string(), matches);
+    };
+    // Longest common prefix, cut at char boundaries
+    let common = matches[1..].iter().fold(first.as_str(), |acc, m| {
+        let len = acc
+            .char_indices()
+            .zip(m.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+        &acc[..len]
+    });
+    (format!("{dir_part}{common}"), matches)
+}
+
+/// Enter on a typed path: go to a directory, or for Unpack take a .zip as the input. Anything
+/// else leaves the entry open with an error.
+fn apply_path_entry(app: &mut App) {
+    let path = resolve_typed_path(&app.path_current_dir, &app.path_entry);
+    let picks_zip = matches!(app.cmd, CmdKind::Unpack) && !app.path_pick_dest;
+    if path.is_dir() {
+        let dir = fs::canonicalize(&path).unwrap_or(path);
+        app.path_current_dir = dir.clone();
+        if picks_directory(app) {
+            app.args.dest = Some(dir);
+        }
+        let _ = refresh_path_entries(app);
+    } else if picks_zip && path.is_file() && path.to_string_lossy().to_lowercase().ends_with(".zip") {
+        app.args.zip_path = Some(fs::canonicalize(&path).unwrap_or(path));
+        let _ = sync_path_to_args(app);
+    } else if path.exists() {
+        app.path_entry_error = Some(format!("not a directory{}: {}", if picks_zip { " or .zip" } else { "" }, path.display()));
+        return;
+    } else {
+        app.path_entry_error = Some(format!("no such path: {}", path.display()));
+        return;
+    }
+    app.editing_path_entry = false;
+    app.path_entry_error = None;
+    app.path_entry_candidates.clear();
+}
+
 fn toggle_pack_cursor(app: &mut App) {
     if app.pack_items.is_empty() {
         return;
@@ -367,8 +501,28 @@ fn selected_pack_ids(app: &App) -> Vec<String> {
     }
 }
 
+/// Commands whose Select tab shows the plugin grid
+fn selects_plugins(cmd: CmdKind) -> bool {
+    matches!(cmd, CmdKind::Pack | CmdKind::Check)
+}
+
+/// Plugins Check runs `check <id>` for, one run each. Empty when none or all are selected: a
+/// single plain `check` then covers the whole repo, conflicts between plugins included.
+fn selected_check_ids(app: &App) -> Vec<String> {
+    let ids = selected_pack_ids(app);
+    if ids.len() == app.pack_items.len() {
+        Vec::new()
+    } else {
+        ids
+    }
+}
+
 fn draw_pack_select(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
-    let title = "Pack Select / 打包选择  (↑↓ move, Space toggle, a all, x none, / filter regex)";
+    let title = if matches!(app.cmd, CmdKind::Check) {
+        "Check Select / 检查选择  (↑↓ move, Space toggle, a all, x none, / filter regex)"
+    } else {
+        "Pack Select / 打包选择  (↑↓ move, Space toggle, a all, x none, / filter regex)"
+    };
     let border_style = if highlight {
         Style::default().fg(Color::Green)
     } else {
@@ -399,7 +553,9 @@ fn draw_pack_select(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
 
     // Render plugin grid
     if total_filtered == 0 {
-        let msg = if app.pack_filter.is_empty() {
+        let msg = if app.pack_filter.is_empty() && matches!(app.cmd, CmdKind::Check) {
+            "(no plugins)".to_string()
+        } else if app.pack_filter.is_empty() {
             "(no packable plugins)".to_string()
         } else if app.pack_filter_invalid {
             "(regex invalid, press Esc or edit filter)".to_string()
@@ -501,12 +657,45 @@ fn draw_pack_select(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
 
 fn draw_path_panel(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
     let mut items: Vec<ListItem> = Vec::new();
-    let cwd = app.path_current_dir.display().to_string();
+    // First header line: the directory being browsed, or the path being typed over it
+    let cwd = if app.editing_path_entry {
+        format!("Path / 路径: {}_", app.path_entry)
+    } else {
+        app.path_current_dir.display().to_string()
+    };
     items.push(ListItem::new(Line::from(Span::styled(
         cwd,
         Style::default().fg(Color::Cyan),
     ))));
-    items.push(ListItem::new(Line::from("")));
+    // Second header line: completions or an error for a typed path, the new-directory prompt
+    // while typing, else the chosen destination
+    if app.editing_path_entry {
+        let line = match (&app.path_entry_error, app.path_entry_candidates.is_empty()) {
+            (Some(err), _) => Line::from(Span::styled(err.clone(), Style::default().fg(Color::Red))),
+            (None, false) => Line::from(app.path_entry_candidates.join("  ")),
+            (None, true) => Line::from("Tab 补全, Enter 跳转, Esc 取消"),
+        };
+        items.push(ListItem::new(line));
+    } else if app.editing_new_dir {
+        let mut spans = vec![Span::styled(
+            format!("New directory / 新建目录: {}_", app.new_dir_name),
+            Style::default().fg(Color::Cyan),
+        )];
+        if let Some(err) = &app.new_dir_error {
+            spans.push(Span::styled(format!("  ({err})"), Style::default().fg(Color::Red)));
+        }
+        items.push(ListItem::new(Line::from(spans)));
+    } else if matches!(app.cmd, CmdKind::Unpack) && app.path_pick_dest {
+        let dest = app
+            .args
+            .dest
+            .as_ref()
+            .map(|d| d.display().to_string())
+            .unwrap_or_else(|| "<plugin/plugins>".to_string());
+        items.push(ListItem::new(Line::from(format!("dest: {dest}"))));
+    } else {
+        items.push(ListItem::new(Line::from("")));
+    }
 
     let total = app.path_entries.len();
     if total == 0 {
@@ -575,8 +764,11 @@ fn draw_path_panel(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
     }
 
     let title = match app.cmd {
-        CmdKind::Pack => "Path / 路径 (Pack 输出目录: ↑↓ move, Space 进入/选择)",
-        CmdKind::Unpack => "Path / 路径 (Unpack 输入 .zip: ↑↓ move, Space 进入/选择)",
+        CmdKind::Pack => "Path / 路径 (Pack 输出目录: ↑↓ move, Space 进入/选择, n 新建目录, e 输入路径)",
+        CmdKind::Unpack if app.path_pick_dest => {
+            "Path / 路径 (Unpack 目标目录: ↑↓ move, Space 进入/选择, n 新建目录, e 输入路径, Tab 切换到 .zip)"
+        }
+        CmdKind::Unpack => "Path / 路径 (Unpack 输入 .zip: ↑↓ move, Space 进入/选择, e 输入路径, Tab 切换到目标目录)",
         _ => "Path / 路径",
     };
     let border_style = if highlight {
@@ -594,7 +786,7 @@ fn available_tabs(app: &App) -> Vec<Tab> {
         CmdKind::Info => vec![Tab::Run, Tab::Output],
         CmdKind::Pack => vec![Tab::Select, Tab::Mode, Tab::Path, Tab::Run, Tab::Output],
         CmdKind::Unpack => vec![Tab::Mode, Tab::Path, Tab::Run, Tab::Output],
-        CmdKind::Check => vec![Tab::Mode, Tab::Run, Tab::Output],
+        CmdKind::Check => vec![Tab::Select, Tab::Mode, Tab::Run, Tab::Output],
     }
 }
 
@@ -642,7 +834,7 @@ fn draw_exec(f: &mut Frame<'_>, app: &App, area: Rect) {
     let right_highlight = app.focus;
     match active_tab {
         Tab::Select => {
-            if matches!(app.cmd, CmdKind::Pack) {
+            if selects_plugins(app.cmd) {
                 draw_pack_select(f, app, right, right_highlight);
             } else {
                 let p = Paragraph::new("No selection for this command")
@@ -674,7 +866,9 @@ fn draw_exec(f: &mut Frame<'_>, app: &App, area: Rect) {
 }
 
 fn draw_mode_panel(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
-    let items = mode_items(app)
+    let toggles = mode_items(app);
+    let n_toggles = toggles.len();
+    let mut items = toggles
         .into_iter()
         .enumerate()
         .map(|(i, (label, value))| {
@@ -687,8 +881,30 @@ fn draw_mode_panel(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
             ListItem::new(Line::from(Span::styled(text, style)))
         })
         .collect::<Vec<_>>();
+    for (j, label) in mode_text_fields(app).iter().enumerate() {
+        let i = n_toggles + j;
+        let value = bundle_field(&app.args, j);
+        let editing = app.editing_mode_field && i == app.mode_cursor;
+        let mut style = Style::default();
+        if editing {
+            style = style.fg(Color::Cyan);
+        } else if app.focus && i == app.mode_cursor {
+            style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        }
+        let mut spans = vec![Span::styled(format!("    {}: {}{}", label, value, if editing { "_" } else { "" }), style)];
+        if j == 1 && !value.is_empty() && !is_semver_ish(value) {
+            spans.push(Span::styled("  (not semver, e.g. 1.2.0)", Style::default().fg(Color::Yellow)));
+        }
+        items.push(ListItem::new(Line::from(spans)));
+    }
 
-    let title = if app.focus { "Mode / 模式 (focused: ↑↓ Space, ← exit)" } else { "Mode / 模式 (Enter/→ to focus)" };
+    let title = if app.editing_mode_field {
+        "Mode / 模式 (editing: type, ↑↓ next field, Enter/Esc done)"
+    } else if app.focus {
+        "Mode / 模式 (focused: ↑↓ Space, ← exit)"
+    } else {
+        "Mode / 模式 (Enter/→ to focus)"
+    };
     let border_style = if highlight {
         Style::default().fg(Color::Green)
     } else {
@@ -711,8 +927,64 @@ fn mode_items(app: &App) -> Vec<(String, bool)> {
     }
 }
 
+/// Text fields listed after the toggles; Pack's bundle metadata, empty meaning unset
+fn mode_text_fields(app: &App) -> &'static [&'static str] {
+    match app.cmd {
+        CmdKind::Pack => &["bundle_name", "bundle_version", "bundle_author"],
+        _ => &[],
+    }
+}
+
+fn bundle_field(args: &CmdArgs, i: usize) -> &String {
+    match i {
+        0 => &args.bundle_name,
+        1 => &args.bundle_version,
+        _ => &args.bundle_author,
+    }
+}
+
+fn bundle_field_mut(args: &mut CmdArgs, i: usize) -> &mut String {
+    match i {
+        0 => &mut args.bundle_name,
+        1 => &mut args.bundle_version,
+        _ => &mut args.bundle_author,
+    }
+}
+
+/// The bundle field under the Mode cursor, if the cursor is on one
+fn mode_text_field_at_cursor(app: &App) -> Option<usize> {
+    let j = app.mode_cursor.checked_sub(mode_items(app).len())?;
+    (j < mode_text_fields(app).len()).then_some(j)
+}
+
+/// Loosely semver: 1, 1.2 or 1.2.3 with an optional v prefix and -pre / +build suffix
+fn is_semver_ish(version: &str) -> bool {
+    let version = version.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let numbers = version.split(['-', '+']).next().unwrap_or("");
+    let parts: Vec<&str> = numbers.split('.').collect();
+    parts.len() <= 3 && parts.iter().all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// What the bundle metadata will be: the typed value, else the repo default, else what pack falls back to
+fn effective_bundle_field(app: &App, i: usize) -> String {
+    let typed = bundle_field(&app.args, i).trim();
+    if !typed.is_empty() {
+        return typed.to_string();
+    }
+    let (default, fallback) = match i {
+        0 => (&app.bundle_defaults.name, "<from zip name>"),
+        1 => (&app.bundle_defaults.version, "unknown"),
+        _ => (&app.bundle_defaults.author, "<none>"),
+    };
+    match default {
+        Some(v) => format!("{v} (repo default)"),
+        None => fallback.to_string(),
+    }
+}
+
 fn mode_items_len(app: &App) -> usize {
-    mode_items(app).len()
+    mode_items(app).len() + mode_text_fields(app).len()
 }
 
 fn toggle_mode_at_cursor(app: &mut App) {
@@ -720,6 +992,8 @@ fn toggle_mode_at_cursor(app: &mut App) {
         CmdKind::Pack => {
             if app.mode_cursor == 0 {
                 app.args.no_md5 = !app.args.no_md5;
+            } else if mode_text_field_at_cursor(app).is_some() {
+                app.editing_mode_field = true;
             }
         }
         CmdKind::Unpack => {
@@ -744,12 +1018,269 @@ fn draw_output_panel(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool)
     } else {
         Style::default()
     };
-    let out = Paragraph::new(app.output.clone())
-        .block(Block::default().borders(Borders::ALL).border_style(border_style).title("Output / 输出"))
-        .wrap(Wrap { trim: false });
+    let mut title = if app.focus {
+        "Output / 输出 (↑↓ PgUp PgDn Home End, / search, ← exit)".to_string()
+    } else {
+        "Output / 输出".to_string()
+    };
+    let matches = match &app.output_search_re {
+        Some(re) => find_output_matches(&app.output, re),
+        None => Vec::new(),
+    };
+    if app.editing_output_search || !app.output_search.is_empty() {
+        title.push_str(&format!("  /{}", app.output_search));
+        if app.editing_output_search {
+            title.push('_');
+        }
+        if app.output_search_invalid {
+            title.push_str(" (regex invalid)");
+        } else if matches.is_empty() {
+            title.push_str(" (no match)");
+        } else {
+            let current = app.output_match.min(matches.len() - 1);
+            title.push_str(&format!(" match {}/{} (n/N, Esc exit)", current + 1, matches.len()));
+        }
+    }
+    let text = highlight_output_matches(&app.output, &matches, app.output_match);
+    draw_scrolled_output(f, app, area, border_style, &title, text);
+}
+
+/// A search match in app.output: the line it is on and its byte range within that line
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct OutputMatch {
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Matches of `re` line by line, on the stored output rather than the wrapped rows; empty matches
+/// are skipped since there is nothing to highlight or jump to
+fn find_output_matches(text: &str, re: &Regex) -> Vec<OutputMatch> {
+    text.lines()
+        .enumerate()
+        .flat_map(|(line, s)| {
+            re.find_iter(s)
+                .filter(|m| !m.is_empty())
+                .map(move |m| OutputMatch { line, start: m.start(), end: m.end() })
+        })
+        .collect()
+}
+
+/// The output with every match highlighted and the current one stronger. Regex ranges always fall
+/// on char boundaries, so slicing at them is safe for multi-byte text.
+fn highlight_output_matches<'a>(text: &'a str, matches: &[OutputMatch], current: usize) -> Text<'a> {
+    if matches.is_empty() {
+        return Text::from(text);
+    }
+    let hit = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let current_hit = Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let current = current.min(matches.len() - 1);
+    let mut pending = matches.iter().enumerate().peekable();
+    let lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let mut spans = Vec::new();
+            let mut pos = 0;
+            while let Some((n, m)) = pending.next_if(|(_, m)| m.line == i) {
+                spans.push(Span::raw(&line[pos..m.start]));
+                let style = if n == current { current_hit } else { hit };
+                spans.push(Span::styled(&line[m.start..m.end], style));
+                pos = m.end;
+            }
+            spans.push(Span::raw(&line[pos..]));
+            Line::from(spans)
+        })
+        .collect::<Vec<_>>();
+    Text::from(lines)
+}
+
+/// Wrapped row a match starts on in a pane `width` columns wide. Measures the text up to and
+/// including the match's first char, so a match that starts a new row is placed on that row.
+fn output_match_row(text: &str, m: &OutputMatch, width: u16) -> usize {
+    let rows = |s: &str| Paragraph::new(s).wrap(Wrap { trim: false }).line_count(width);
+    let above = if m.line == 0 { 0 } else { rows(&text.lines().take(m.line).collect::<Vec<_>>().join("\n")) };
+    let line = text.lines().nth(m.line).unwrap_or("");
+    let upto = m.start + line[m.start..].chars().next().map_or(0, char::len_utf8);
+    above + rows(&line[..upto]).saturating_sub(1)
+}
+
+/// Index of the match after (or before) `current`, wrapping around at either end
+fn step_output_match(current: usize, total: usize, forward: bool) -> usize {
+    if total == 0 {
+        0
+    } else if forward {
+        (current.min(total - 1) + 1) % total
+    } else {
+        (current.min(total - 1) + total - 1) % total
+    }
+}
+
+/// Scroll so app.output_match is in view
+fn reveal_output_match(app: &mut App, matches: &[OutputMatch]) {
+    let Some(m) = matches.get(app.output_match) else {
+        return;
+    };
+    let (total, height, width) = app.output_view.get();
+    let row = output_match_row(&app.output, m, width);
+    app.output_scroll.reveal(row, total, height, app.running);
+}
+
+/// Recompile the query after an edit and jump to the first match at or below the top of the view
+fn update_output_search(app: &mut App) {
+    app.output_match = 0;
+    if app.output_search.is_empty() {
+        app.output_search_re = None;
+        app.output_search_invalid = false;
+        return;
+    }
+    match Regex::new(&app.output_search) {
+        Ok(re) => {
+            app.output_search_re = Some(re);
+            app.output_search_invalid = false;
+        }
+        Err(_) => {
+            app.output_search_re = None;
+            app.output_search_invalid = true;
+            return;
+        }
+    }
+    let Some(re) = &app.output_search_re else {
+        return;
+    };
+    let matches = find_output_matches(&app.output, re);
+    let (total, height, width) = app.output_view.get();
+    let top = app.output_scroll.top(total, height, app.running);
+    app.output_match = matches
+        .iter()
+        .position(|m| output_match_row(&app.output, m, width) >= top)
+        .unwrap_or(0);
+    reveal_output_match(app, &matches);
+}
+
+/// n / N: move to the next or previous match
+fn jump_output_match(app: &mut App, forward: bool) {
+    let Some(re) = &app.output_search_re else {
+        return;
+    };
+    let matches = find_output_matches(&app.output, re);
+    if matches.is_empty() {
+        return;
+    }
+    app.output_match = step_output_match(app.output_match, matches.len(), forward);
+    reveal_output_match(app, &matches);
+}
+
+fn clear_output_search(app: &mut App) {
+    app.output_search.clear();
+    app.output_search_re = None;
+    app.output_search_invalid = false;
+    app.editing_output_search = false;
+    app.output_match = 0;
+}
+
+/// Render `text` into `area` at the current scroll position, with the visible rows in the title,
+/// and remember the pane's size for the scroll keys
+fn draw_scrolled_output(f: &mut Frame<'_>, app: &App, area: Rect, border_style: Style, title: &str, text: Text<'_>) {
+    let out = Paragraph::new(text).wrap(Wrap { trim: false });
+    let height = area.height.saturating_sub(2) as usize;
+    let width = area.width.saturating_sub(2);
+    let total = out.line_count(width);
+    app.output_view.set((total, height, width));
+    let top = app.output_scroll.top(total, height, app.running);
+    let title = if total > height {
+        format!("{title} [{}-{}/{}]", top + 1, (top + height).min(total), total)
+    } else {
+        title.to_string()
+    };
+    let out = out
+        .block(Block::default().borders(Borders::ALL).border_style(border_style).title(title))
+        .scroll((top.min(u16::MAX as usize) as u16, 0));
     f.render_widget(out, area);
 }
 
+/// Scroll position of the output, shared by the Output tab and the Run tab's preview
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct OutputScroll {
+    /// First visible row
+    offset: usize,
+    /// Keep the last rows in view while a task runs; scrolling up clears it, reaching the
+    /// bottom sets it again
+    follow: bool,
+}
+
+impl Default for OutputScroll {
+    fn default() -> Self {
+        OutputScroll { offset: 0, follow: true }
+    }
+}
+
+impl OutputScroll {
+    /// First visible row for `total` rows shown `height` at a time
+    fn top(&self, total: usize, height: usize, running: bool) -> usize {
+        let max = total.saturating_sub(height);
+        if self.follow && running { max } else { self.offset.min(max) }
+    }
+
+    fn scroll_by(&mut self, delta: isize, total: usize, height: usize, running: bool) {
+        let max = total.saturating_sub(height);
+        let top = self.top(total, height, running).saturating_add_signed(delta).min(max);
+        self.offset = top;
+        self.follow = top == max;
+    }
+
+    fn home(&mut self) {
+        self.offset = 0;
+        self.follow = false;
+    }
+
+    fn end(&mut self, total: usize, height: usize) {
+        self.offset = total.saturating_sub(height);
+        self.follow = true;
+    }
+
+    /// Scroll just enough to show `row`, placing it a third of the way down when the view has to
+    /// move; stops following so the row stays put
+    fn reveal(&mut self, row: usize, total: usize, height: usize, running: bool) {
+        let top = self.top(total, height, running);
+        self.offset = if row < top || row >= top + height {
+            row.saturating_sub(height / 3).min(total.saturating_sub(height))
+        } else {
+            top
+        };
+        self.follow = false;
+    }
+}
+
+/// Up/Down/PageUp/PageDown/Home/End on the output pane, sized by its last render
+fn scroll_output(app: &mut App, code: KeyCode) {
+    let (total, height, _) = app.output_view.get();
+    let page = height.saturating_sub(1).max(1) as isize;
+    let running = app.running;
+    let scroll = &mut app.output_scroll;
+    match code {
+        KeyCode::Up => scroll.scroll_by(-1, total, height, running),
+        KeyCode::Down => scroll.scroll_by(1, total, height, running),
+        KeyCode::PageUp => scroll.scroll_by(-page, total, height, running),
+        KeyCode::PageDown => scroll.scroll_by(page, total, height, running),
+        KeyCode::Home => scroll.home(),
+        KeyCode::End => scroll.end(total, height),
+        _ => {}
+    }
+}
+
+/// Where draw_run puts the output preview inside the right pane
+fn run_preview_rect(right: Rect) -> Rect {
+    let h = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(40), Constraint::Min(0)])
+        .split(right);
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(h[1])[1]
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum CmdKind {
     Info,
@@ -772,13 +1303,15 @@ impl CmdKind {
 #[derive(Debug, Default, Clone)]
 struct CmdArgs {
     root: Option<PathBuf>,
-    plugin_id: Option<String>,
     zip_path: Option<PathBuf>,
     dest: Option<PathBuf>,
     force: bool,
     python: bool,
     python_strict: bool,
     no_md5: bool,
+    bundle_name: String,
+    bundle_version: String,
+    bundle_author: String,
 }
 
 struct App {
@@ -797,6 +1330,15 @@ struct App {
     started_at: Option<Instant>,
     spinner_i: usize,
     output: String,
+    output_scroll: OutputScroll,
+    /// Wrapped rows of the output, the pane height and its inner width, as of the last draw
+    output_view: Cell<(usize, usize, u16)>,
+    output_search: String,
+    output_search_re: Option<Regex>,
+    output_search_invalid: bool,
+    editing_output_search: bool,
+    /// Index of the current search match
+    output_match: usize,
     last_status: Option<i32>,
     task_rx: Option<Receiver<anyhow::Result<std::process::Output>>>,
 
@@ -807,16 +1349,91 @@ struct App {
     pack_filter_re: Option<Regex>,
     pack_filter_invalid: bool,
     editing_pack_filter: bool,
+    /// Repo bundle defaults, shown in the Run tab for the bundle fields left empty
+    bundle_defaults: core::BundleMeta,
+    editing_mode_field: bool,
 
     path_entries: Vec<PathEntry>,
     path_cursor: usize,
     path_current_dir: PathBuf,
+    /// Unpack only: the Path tab browses for the destination directory instead of the .zip
+    path_pick_dest: bool,
+    editing_new_dir: bool,
+    new_dir_name: String,
+    new_dir_error: Option<String>,
+    editing_path_entry: bool,
+    path_entry: String,
+    path_entry_error: Option<String>,
+    /// Matches listed by the last Tab completion
+    path_entry_candidates: Vec<String>,
 
     clipboard: Option<Clipboard>,
 
     show_help: bool,
 }
 
+impl App {
+    /// The TUI's starting state: Home screen, browsing `repo_root` (or the cwd)
+    fn new(repo_root: Option<PathBuf>, clipboard: Option<Clipboard>) -> Self {
+        App {
+            screen: Screen::Home,
+            selected: 0,
+            tab_selected: 0,
+            tab_active: 0,
+            focus: false,
+            mode_cursor: 0,
+            last_home_click: None,
+            last_quit_key: None,
+            last_back_click: None,
+            cmd: CmdKind::Info,
+            args: CmdArgs {
+                root: repo_root.clone(),
+                ..CmdArgs::default()
+            },
+            running: false,
+            started_at: None,
+            spinner_i: 0,
+            output: String::new(),
+            output_scroll: OutputScroll::default(),
+            output_view: Cell::new((0, 0, 0)),
+            output_search: String::new(),
+            output_search_re: None,
+            output_search_invalid: false,
+            editing_output_search: false,
+            output_match: 0,
+            last_status: None,
+            task_rx: None,
+
+            pack_items: Vec::new(),
+            pack_selected: Vec::new(),
+            pack_cursor: 0,
+            pack_filter: String::new(),
+            pack_filter_re: None,
+            pack_filter_invalid: false,
+            editing_pack_filter: false,
+            bundle_defaults: core::BundleMeta::default(),
+            editing_mode_field: false,
+
+            path_entries: Vec::new(),
+            path_cursor: 0,
+            path_current_dir: repo_root
+                .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))),
+            path_pick_dest: false,
+            editing_new_dir: false,
+            new_dir_name: String::new(),
+            new_dir_error: None,
+            editing_path_entry: false,
+            path_entry: String::new(),
+            path_entry_error: None,
+            path_entry_candidates: Vec::new(),
+
+            clipboard,
+
+            show_help: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct PathEntry {
     name: String,
@@ -832,45 +1449,7 @@ pub fn run(repo_root: Option<PathBuf>) -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend).context("create terminal")?;
 
-    let mut app = App {
-        screen: Screen::Home,
-        selected: 0,
-        tab_selected: 0,
-        tab_active: 0,
-        focus: false,
-        mode_cursor: 0,
-        last_home_click: None,
-        last_quit_key: None,
-        last_back_click: None,
-        cmd: CmdKind::Info,
-        args: CmdArgs {
-            root: repo_root.clone(),
-            ..CmdArgs::default()
-        },
-        running: false,
-        started_at: None,
-        spinner_i: 0,
-        output: String::new(),
-        last_status: None,
-        task_rx: None,
-
-        pack_items: Vec::new(),
-        pack_selected: Vec::new(),
-        pack_cursor: 0,
-        pack_filter: String::new(),
-        pack_filter_re: None,
-        pack_filter_invalid: false,
-        editing_pack_filter: false,
-
-        path_entries: Vec::new(),
-        path_cursor: 0,
-        path_current_dir: repo_root
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))),
-
-        clipboard: Clipboard::new().ok(),
-
-        show_help: false,
-    };
+    let mut app = App::new(repo_root, Clipboard::new().ok());
 
     let tick_rate = Duration::from_millis(100);
 
@@ -986,6 +1565,15 @@ pub fn run(repo_root: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
+/// Whether keys currently go into a text field rather than acting as shortcuts
+fn typing_text(app: &App) -> bool {
+    app.editing_pack_filter
+        || app.editing_new_dir
+        || app.editing_path_entry
+        || app.editing_output_search
+        || app.editing_mode_field
+}
+
 fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
     // Ctrl-based global shortcuts
     if key.modifiers.contains(KeyModifiers::CONTROL) {
@@ -1042,20 +1630,39 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
         return Ok(false);
     }
 
-    if matches!(code, KeyCode::Char('q') | KeyCode::Char('Q')) {
+    // While text is being typed, q is just a letter
+    if matches!(code, KeyCode::Char('q') | KeyCode::Char('Q')) && !typing_text(app) {
         app.show_help = true;
         return Ok(false);
     }
 
-    // Esc: if editing Pack Select filter, cancel editing first; otherwise back from Exec to Home (no quit)
+    // Esc: if editing the Select filter, cancel editing first; otherwise back from Exec to Home (no quit)
     if matches!(code, KeyCode::Esc) {
         if matches!(app.screen, Screen::Exec) {
             let tabs = available_tabs(app);
             let active_tab = tabs.get(app.tab_active).copied().unwrap_or(Tab::Run);
-            if matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) && app.editing_pack_filter {
+            if matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) && app.editing_pack_filter {
                 app.editing_pack_filter = false;
                 return Ok(false);
             }
+            if matches!(active_tab, Tab::Mode) && app.editing_mode_field {
+                app.editing_mode_field = false;
+                return Ok(false);
+            }
+            if matches!(active_tab, Tab::Path) && app.editing_path_entry {
+                app.editing_path_entry = false;
+                return Ok(false);
+            }
+            if matches!(active_tab, Tab::Output) && (app.editing_output_search || !app.output_search.is_empty()) {
+                clear_output_search(app);
+                return Ok(false);
+            }
+            if matches!(active_tab, Tab::Path) && app.editing_new_dir {
+                app.editing_new_dir = false;
+                app.new_dir_name.clear();
+                app.new_dir_error = None;
+                return Ok(false);
+            }
             app.screen = Screen::Home;
         }
         return Ok(false);
@@ -1078,8 +1685,9 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                 app.focus = false;
                 app.mode_cursor = 0;
                 app.output.clear();
+                app.output_scroll = OutputScroll::default();
                 app.last_status = None;
-                if matches!(app.cmd, CmdKind::Pack) {
+                if selects_plugins(app.cmd) {
                     load_pack_list(app)?;
                 }
                 if matches!(app.cmd, CmdKind::Pack | CmdKind::Unpack) {
@@ -1097,10 +1705,10 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                 return Ok(false);
             }
             let active_tab = tabs.get(app.tab_active).copied().unwrap_or(Tab::Run);
-            let focusable = matches!(active_tab, Tab::Select | Tab::Mode | Tab::Path);
+            let focusable = matches!(active_tab, Tab::Select | Tab::Mode | Tab::Path | Tab::Output);
 
-            // When editing Pack Select filter, intercept keys for text editing.
-            if matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) && app.editing_pack_filter {
+            // When editing the Select filter, intercept keys for text editing.
+            if matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) && app.editing_pack_filter {
                 match code {
                     KeyCode::Enter => {
                         recompile_pack_filter(app);
@@ -1119,6 +1727,86 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                 return Ok(false);
             }
 
+            // Editing a Mode text field; Up/Down move to the neighbouring field and keep editing there
+            if matches!(active_tab, Tab::Mode) && app.editing_mode_field {
+                let Some(j) = mode_text_field_at_cursor(app) else {
+                    app.editing_mode_field = false;
+                    return Ok(false);
+                };
+                let n_toggles = mode_items(app).len();
+                let n_fields = mode_text_fields(app).len();
+                match code {
+                    KeyCode::Enter => app.editing_mode_field = false,
+                    KeyCode::Up if j > 0 => app.mode_cursor -= 1,
+                    KeyCode::Down if j + 1 < n_fields => app.mode_cursor = n_toggles + j + 1,
+                    KeyCode::Backspace => {
+                        bundle_field_mut(&mut app.args, j).pop();
+                    }
+                    KeyCode::Char(c) => bundle_field_mut(&mut app.args, j).push(c),
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
+            // Typing an Output search query: every keystroke re-runs the search
+            if matches!(active_tab, Tab::Output) && app.editing_output_search {
+                match code {
+                    KeyCode::Enter => app.editing_output_search = false,
+                    KeyCode::Backspace => {
+                        app.output_search.pop();
+                        update_output_search(app);
+                    }
+                    KeyCode::Char(c) => {
+                        app.output_search.push(c);
+                        update_output_search(app);
+                    }
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
+            // Typing a path in the Path tab
+            if matches!(active_tab, Tab::Path) && app.editing_path_entry {
+                match code {
+                    KeyCode::Enter => apply_path_entry(app),
+                    KeyCode::Tab => {
+                        let zips = matches!(app.cmd, CmdKind::Unpack) && !app.path_pick_dest;
+                        let (completed, candidates) = complete_path(&app.path_entry, &app.path_current_dir, zips);
+                        app.path_entry_error = candidates.is_empty().then(|| "no match".to_string());
+                        app.path_entry = completed;
+                        // One match is already complete; only list real choices
+                        app.path_entry_candidates = if candidates.len() > 1 { candidates } else { Vec::new() };
+                    }
+                    KeyCode::Backspace => {
+                        app.path_entry.pop();
+                        app.path_entry_error = None;
+                    }
+                    KeyCode::Char(c) => {
+                        app.path_entry.push(c);
+                        app.path_entry_error = None;
+                    }
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
+            // Typing the name of a new directory in the Path tab
+            if matches!(active_tab, Tab::Path) && app.editing_new_dir {
+                match code {
+                    KeyCode::Enter => create_dir_from_prompt(app),
+                    KeyCode::Backspace => {
+                        app.new_dir_name.pop();
+                        app.new_dir_error = None;
+                    }
+                    KeyCode::Char(c) => {
+                        app.new_dir_name.push(c);
+                        app.new_dir_error = None;
+                    }
+                    _ => {}
+                }
+                return Ok(false);
+            }
+
             match code {
                 // Enter/Right enters focus for focusable tabs when not already focused.
                 KeyCode::Enter | KeyCode::Right if !app.focus => {
@@ -1136,11 +1824,9 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                         }
                     }
                 }
-                // Left exits focus for Mode/Path/other, but NOT for Pack Select grid (there Left/Right are used for 2D navigation).
+                // Left exits focus for Mode/Path/Output, but NOT for the Select grid (there Left/Right are used for 2D navigation).
                 KeyCode::Left
-                    if app.focus
-                        && !matches!(active_tab, Tab::Select)
-                        && !matches!(app.cmd, CmdKind::Pack) =>
+                    if app.focus && !(matches!(active_tab, Tab::Select) && selects_plugins(app.cmd)) =>
                 {
                     app.focus = false;
                 }
@@ -1155,14 +1841,33 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                     app.tab_active = app.tab_selected;
                 }
 
+                // Output: ↑↓ scroll once focused; page keys also scroll the Run tab's preview.
+                KeyCode::Up | KeyCode::Down if app.focus && matches!(active_tab, Tab::Output) => {
+                    scroll_output(app, code);
+                }
+                KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End
+                    if matches!(active_tab, Tab::Output | Tab::Run) =>
+                {
+                    scroll_output(app, code);
+                }
+                KeyCode::Char('/') if matches!(active_tab, Tab::Output) => {
+                    app.editing_output_search = true;
+                }
+                KeyCode::Char('n') if matches!(active_tab, Tab::Output) => {
+                    jump_output_match(app, true);
+                }
+                KeyCode::Char('N') if matches!(active_tab, Tab::Output) => {
+                    jump_output_match(app, false);
+                }
+
                 // Focused Select: 2D navigation within filtered grid using arrow keys, Space toggles.
-                KeyCode::Up if app.focus && matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) => {
+                KeyCode::Up if app.focus && matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) => {
                     move_pack_cursor_2d(app, 0, -1);
                 }
-                KeyCode::Down if app.focus && matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) => {
+                KeyCode::Down if app.focus && matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) => {
                     move_pack_cursor_2d(app, 0, 1);
                 }
-                KeyCode::Left if app.focus && matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) => {
+                KeyCode::Left if app.focus && matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) => {
                     // In Pack Select grid: if not at the leftmost column, move left; if already in
                     // the leftmost column of the current grid, exit focus back to the left tab bar.
                     let filtered = pack_filtered_indices(app);
@@ -1179,23 +1884,23 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                         }
                     }
                 }
-                KeyCode::Right if app.focus && matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) => {
+                KeyCode::Right if app.focus && matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) => {
                     move_pack_cursor_2d(app, 1, 0);
                 }
-                KeyCode::Char(' ') if app.focus && matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) => {
+                KeyCode::Char(' ') if app.focus && matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) => {
                     toggle_pack_cursor(app);
                 }
-                KeyCode::Char('a') if app.focus && matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) => {
+                KeyCode::Char('a') if app.focus && matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) => {
                     for v in &mut app.pack_selected {
                         *v = true;
                     }
                 }
-                KeyCode::Char('x') if app.focus && matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) => {
+                KeyCode::Char('x') if app.focus && matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) => {
                     for v in &mut app.pack_selected {
                         *v = false;
                     }
                 }
-                KeyCode::Char('/') if matches!(active_tab, Tab::Select) && matches!(app.cmd, CmdKind::Pack) => {
+                KeyCode::Char('/') if matches!(active_tab, Tab::Select) && selects_plugins(app.cmd) => {
                     app.editing_pack_filter = true;
                 }
 
@@ -1212,6 +1917,9 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                 KeyCode::Char(' ') if app.focus && matches!(active_tab, Tab::Mode) => {
                     toggle_mode_at_cursor(app);
                 }
+                KeyCode::Enter if app.focus && matches!(active_tab, Tab::Mode) && mode_text_field_at_cursor(app).is_some() => {
+                    app.editing_mode_field = true;
+                }
 
                 // Focused Path: browse directories / choose dest or zip
                 KeyCode::Up if app.focus && matches!(active_tab, Tab::Path) => {
@@ -1235,7 +1943,7 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                             new_dir.push(&ent.name);
                             app.path_current_dir = new_dir;
                             refresh_path_entries(app)?;
-                            if matches!(app.cmd, CmdKind::Pack) {
+                            if picks_directory(app) {
                                 app.args.dest = Some(app.path_current_dir.clone());
                             }
                         } else if ent.is_zip && matches!(app.cmd, CmdKind::Unpack) {
@@ -1246,6 +1954,19 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                     }
                 }
 
+                KeyCode::Tab if matches!(active_tab, Tab::Path) && matches!(app.cmd, CmdKind::Unpack) => {
+                    toggle_unpack_path_target(app)?;
+                }
+                KeyCode::Char('e') if matches!(active_tab, Tab::Path) && matches!(app.cmd, CmdKind::Pack | CmdKind::Unpack) => {
+                    app.focus = true;
+                    start_path_entry(app);
+                }
+                KeyCode::Char('n') if app.focus && matches!(active_tab, Tab::Path) && picks_directory(app) => {
+                    app.editing_new_dir = true;
+                    app.new_dir_name.clear();
+                    app.new_dir_error = None;
+                }
+
                 // Run tab shortcuts
                 KeyCode::Char('r') if !app.running && matches!(active_tab, Tab::Run) => {
                     run_command(app)?;
@@ -1312,8 +2033,9 @@ fn handle_mouse(app: &mut App, m: MouseEvent, area: Rect) {
                         app.focus = false;
                         app.mode_cursor = 0;
                         app.output.clear();
+                        app.output_scroll = OutputScroll::default();
                         app.last_status = None;
-                        if matches!(app.cmd, CmdKind::Pack) {
+                        if selects_plugins(app.cmd) {
                             if let Err(e) = load_pack_list(app) {
                                 app.output = format!("load pack list failed: {e:?}");
                             }
@@ -1358,7 +2080,7 @@ fn handle_mouse(app: &mut App, m: MouseEvent, area: Rect) {
                     let scroll_up = matches!(m.kind, MouseEventKind::ScrollUp);
 
                     match active_tab {
-                        Tab::Select if matches!(app.cmd, CmdKind::Pack) => {
+                        Tab::Select if selects_plugins(app.cmd) => {
                             let filtered = pack_filtered_indices(app);
                             if filtered.is_empty() {
                                 return;
@@ -1391,6 +2113,12 @@ fn handle_mouse(app: &mut App, m: MouseEvent, area: Rect) {
                                 app.path_cursor += 1;
                             }
                         }
+                        Tab::Output => {
+                            scroll_output(app, if scroll_up { KeyCode::Up } else { KeyCode::Down });
+                        }
+                        Tab::Run if point_in_rect(m.column, m.row, run_preview_rect(right)) => {
+                            scroll_output(app, if scroll_up { KeyCode::Up } else { KeyCode::Down });
+                        }
                         _ => {}
                     }
                 }
@@ -1404,6 +2132,12 @@ fn handle_mouse(app: &mut App, m: MouseEvent, area: Rect) {
                             if idx < tabs.len() {
                                 app.tab_selected = idx;
                                 app.tab_active = idx;
+                                // Leaving a tab ends any text entry in it
+                                app.editing_pack_filter = false;
+                                app.editing_new_dir = false;
+                                app.editing_path_entry = false;
+                                app.editing_output_search = false;
+                                app.editing_mode_field = false;
                             }
                         }
                         return;
@@ -1438,7 +2172,7 @@ fn handle_mouse(app: &mut App, m: MouseEvent, area: Rect) {
                     }
 
                     match active_tab {
-                        Tab::Select if matches!(app.cmd, CmdKind::Pack) => {
+                        Tab::Select if selects_plugins(app.cmd) => {
                             let filtered = pack_filtered_indices(app);
                             let total_filtered = filtered.len();
                             if total_filtered == 0 {
@@ -1538,6 +2272,14 @@ fn handle_mouse(app: &mut App, m: MouseEvent, area: Rect) {
                             }
                         }
                         Tab::Path => {
+                            // The directory line (just under the border) opens path entry
+                            if m.row == right.y.saturating_add(1) {
+                                app.focus = true;
+                                if !app.editing_path_entry {
+                                    start_path_entry(app);
+                                }
+                                return;
+                            }
                             let total = app.path_entries.len();
                             if total == 0 {
                                 return;
@@ -1578,7 +2320,7 @@ fn handle_mouse(app: &mut App, m: MouseEvent, area: Rect) {
                                     new_dir.push(&ent.name);
                                     app.path_current_dir = new_dir;
                                     let _ = refresh_path_entries(app);
-                                    if matches!(app.cmd, CmdKind::Pack) {
+                                    if picks_directory(app) {
                                         app.args.dest = Some(app.path_current_dir.clone());
                                     }
                                 } else if ent.is_zip && matches!(app.cmd, CmdKind::Unpack) {
@@ -1620,6 +2362,16 @@ fn run_command(app: &mut App) -> Result<()> {
             if app.args.no_md5 {
                 args.push("--no-md5".to_string());
             }
+            for (flag, value) in [
+                ("--bundle-name", &app.args.bundle_name),
+                ("--bundle-version", &app.args.bundle_version),
+                ("--bundle-author", &app.args.bundle_author),
+            ] {
+                if !value.trim().is_empty() {
+                    args.push(flag.to_string());
+                    args.push(value.trim().to_string());
+                }
+            }
         }
         CmdKind::Unpack => {
             args.push("unpack".to_string());
@@ -1635,11 +2387,6 @@ fn run_command(app: &mut App) -> Result<()> {
         }
         CmdKind::Check => {
             args.push("check".to_string());
-            if let Some(pid) = &app.args.plugin_id {
-                if !pid.trim().is_empty() {
-                    args.push(pid.clone());
-                }
-            }
             args.push("--json".to_string());
             if app.args.python {
                 args.push("--python".to_string());
@@ -1675,19 +2422,30 @@ fn run_command(app: &mut App) -> Result<()> {
         _ => {}
     }
 
+    // `check` takes a single plugin id, so a Check selection becomes one run per plugin
+    let check_ids = if matches!(app.cmd, CmdKind::Check) { selected_check_ids(app) } else { Vec::new() };
+
     app.running = true;
     app.started_at = Some(Instant::now());
     app.output.clear();
+    app.output_scroll = OutputScroll::default();
     app.last_status = None;
 
     let (tx, rx) = std::sync::mpsc::channel();
     std::thread::spawn(move || {
-        let out = Command::new(exe)
-            .args(&args)
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output();
+        let run = |args: &[String]| {
+            Command::new(&exe)
+                .args(args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+        };
+        let out = if check_ids.is_empty() {
+            run(&args)
+        } else {
+            run_each_check(&check_ids, &args, run)
+        };
         let _ = tx.send(out.map_err(|e| anyhow::anyhow!(e)));
     });
 
@@ -1696,51 +2454,79 @@ fn run_command(app: &mut App) -> Result<()> {
     Ok(())
 }
 
-fn run_pack_quick_check(app: &mut App) -> Result<()> {
-    let exe = std::env::current_exe().context("current_exe")?;
-    let repo_root = if let Some(r) = &app.args.root {
-        r.clone()
-    } else {
-        core::find_repo_root(std::env::current_dir().context("failed to get cwd")?)?
-    };
+/// Run `check <id> ...` for each id and merge the outputs under the same `=== check <id> ===`
+/// headers as the quick check; the status is the worst one, so errors anywhere show as exit=2
+fn run_each_check(
+    ids: &[String],
+    args: &[String],
+    run: impl Fn(&[String]) -> io::Result<std::process::Output>,
+) -> io::Result<std::process::Output> {
+    let mut merged: Option<std::process::Output> = None;
+    let mut text = String::new();
+    for id in ids {
+        let mut check_args = vec![args[0].clone(), id.clone()];
+        check_args.extend_from_slice(&args[1..]);
+        let out = run(&check_args)?;
+        text.push_str(&format!("=== check {id} (exit={}) ===\n", out.status.code().unwrap_or(-1)));
+        text.push_str(&String::from_utf8_lossy(&out.stdout));
+        if !out.stderr.is_empty() {
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&String::from_utf8_lossy(&out.stderr));
+        }
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        let worse = merged
+            .as_ref()
+            .is_none_or(|m| out.status.code().unwrap_or(-1) > m.status.code().unwrap_or(-1));
+        if worse {
+            merged = Some(out);
+        }
+    }
+    let mut merged = merged.expect("run_each_check needs at least one id");
+    merged.stdout = text.into_bytes();
+    merged.stderr.clear();
+    Ok(merged)
+}
 
+fn run_pack_quick_check(app: &mut App) -> Result<()> {
     let selected = selected_pack_ids(app);
     if selected.is_empty() {
         app.output = "No plugin selected (treat as all). Quick check requires explicit selection.\n".to_string();
+        app.output_scroll = OutputScroll::default();
         app.last_status = Some(0);
         return Ok(());
     }
 
     let mut out_all = String::new();
     for id in selected {
-        let output = Command::new(&exe)
-            .arg("check")
-            .arg(id.clone())
-            .arg("--root")
-            .arg(repo_root.to_string_lossy().to_string())
-            .arg("--json")
-            .output()
-            .with_context(|| format!("failed to run check for {id}"))?;
-        out_all.push_str(&format!("=== check {id} (exit={}) ===\n", output.status.code().unwrap_or(-1)));
-        out_all.push_str(&String::from_utf8_lossy(&output.stdout));
-        if !output.stderr.is_empty() {
-            if !out_all.ends_with('\n') {
-                out_all.push('\n');
-            }
-            out_all.push_str(&String::from_utf8_lossy(&output.stderr));
-        }
-        if !out_all.ends_with('\n') {
-            out_all.push('\n');
+        let mut options = api::CheckOptions::new().plugin(&id);
+        if let Some(r) = &app.args.root {
+            options = options.root(r);
         }
+        // Same exit codes as `check`: 2 for errors, 3 when the check could not run
+        let (code, body) = match api::check(&options) {
+            Ok(report) => (
+                if report.errors.is_empty() { 0 } else { 2 },
+                serde_json::to_string_pretty(&report)?,
+            ),
+            Err(e) => (3, format!("{e:#}")),
+        };
+        out_all.push_str(&format!("=== check {id} (exit={code}) ===\n"));
+        out_all.push_str(&body);
+        out_all.push('\n');
     }
 
     app.output = out_all;
+    app.output_scroll = OutputScroll::default();
     app.last_status = Some(0);
     Ok(())
 }
 
 fn run_unpack_preview(app: &mut App) -> Result<()> {
-    use crate::core;
+    use neko_plugin_cli::core;
 
     let repo_root = if let Some(r) = &app.args.root {
         r.clone()
@@ -1761,7 +2547,7 @@ fn run_unpack_preview(app: &mut App) -> Result<()> {
         .unwrap_or_else(|| PathBuf::from("neko_plugins_bundle.zip"));
 
     let excludes = core::build_excludes(&[])?;
-    let preview_items = core::preview_unpack(&zip_path, &dest_dir, app.args.force, &excludes)?;
+    let preview_items = core::preview_unpack(&zip_path, &dest_dir, app.args.force, &excludes, None)?;
 
     let mut out = String::new();
     use std::fmt::Write as _;
@@ -1774,21 +2560,10 @@ fn run_unpack_preview(app: &mut App) -> Result<()> {
     )
     .ok();
 
-    if preview_items.is_empty() {
-        writeln!(&mut out, "(manifest has no plugins)").ok();
-    } else {
-        for item in preview_items {
-            let action = if item.will_install { "INSTALL" } else { "SKIP" };
-            writeln!(
-                &mut out,
-                "- [{}] id={} folder={}\n    {}",
-                action, item.id, item.folder, item.reason
-            )
-            .ok();
-        }
-    }
+    out.push_str(&core::format_unpack_preview(&preview_items));
 
     app.output = out;
+    app.output_scroll = OutputScroll::default();
     app.last_status = Some(0);
     Ok(())
 }
@@ -1892,20 +2667,24 @@ fn draw_help(f: &mut Frame<'_>, area: Rect) {
         Line::from(""),
         Line::from(Span::styled("Pack", Style::default().add_modifier(Modifier::BOLD))),
         Line::from("  Select: ↑↓ 移动, Space 选中/取消, a 全选, x 全不选"),
-        Line::from("  Mode: ↑↓ 移动, Space 切换 no_md5"),
-        Line::from("  Path: ↑↓ 目录移动, Space 进入目录并设置输出目录"),
+        Line::from("  Mode: ↑↓ 移动, Space 切换 no_md5; 在 bundle_name/version/author 上 Space/Enter 编辑"),
+        Line::from("  Path: ↑↓ 目录移动, Space 进入目录并设置输出目录, n 新建目录"),
         Line::from("  Run: r 执行 pack, c 对选中插件 quick check"),
         Line::from(""),
         Line::from(Span::styled("Unpack", Style::default().add_modifier(Modifier::BOLD))),
         Line::from("  Mode: Space 切换 force"),
-        Line::from("  Path: ↑↓ 目录/zip 移动, Space 选择 .zip"),
+        Line::from("  Path: ↑↓ 目录/zip 移动, Space 选择 .zip; Tab 切换为选择目标目录 (--dest), n 新建目录"),
+        Line::from("  Path (Pack/Unpack): e 或点击路径行直接输入路径, Tab 补全, Enter 跳转 (Unpack 输入 .zip 直接选中)"),
         Line::from("  Run: r 执行 unpack, p 预览将安装/跳过哪些插件"),
         Line::from(""),
         Line::from(Span::styled("Check / Info", Style::default().add_modifier(Modifier::BOLD))),
+        Line::from("  Select (Check): 同 Pack; 全选或全不选时检查整个仓库, 否则逐个插件运行 check <id>"),
         Line::from("  Mode: ↑↓/Space 切换 python / python_strict 等选项"),
         Line::from("  Run: r 运行 info/check"),
         Line::from(""),
         Line::from(Span::styled("Output", Style::default().add_modifier(Modifier::BOLD))),
+        Line::from("  Enter/→ 聚焦后 ↑↓ 滚动; PgUp/PgDn/Home/End 与鼠标滚轮在 Output 与 Run 预览中滚动"),
+        Line::from("  /: 正则搜索输出, n/N 下一个/上一个匹配, Esc 退出搜索"),
         Line::from("  Ctrl-Y / Ctrl-Insert: 复制输出到剪贴板 / copy output to clipboard"),
         Line::from(""),
         Line::from("鼠标: Home 双击命令进入 Exec；Exec 左侧点击切换 Tab；Run 进度条区域点击跳转到 Output"),
@@ -1968,6 +2747,15 @@ fn draw_run(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
             app.pack_items.len()
         )));
         lines.push(Line::from(format!("no_md5: {} (set in Mode)", app.args.no_md5)));
+        for (j, label) in mode_text_fields(app).iter().enumerate() {
+            lines.push(Line::from(format!("{}: {}", label, effective_bundle_field(app, j))));
+        }
+        if !app.args.bundle_version.trim().is_empty() && !is_semver_ish(&app.args.bundle_version) {
+            lines.push(Line::from(Span::styled(
+                "bundle_version is not semver",
+                Style::default().fg(Color::Yellow),
+            )));
+        }
     }
     if matches!(app.cmd, CmdKind::Unpack) {
         if let Some(zip) = &app.args.zip_path {
@@ -1975,9 +2763,22 @@ fn draw_run(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
         } else {
             lines.push(Line::from("zip: <在 Path 中选择 .zip>"));
         }
+        if let Some(dest) = &app.args.dest {
+            lines.push(Line::from(format!("dest: {}", dest.display())));
+        } else {
+            lines.push(Line::from("dest: <plugin/plugins> (Path 中按 Tab 选择)"));
+        }
         lines.push(Line::from(format!("force: {} (set in Mode)", app.args.force)));
     }
     if matches!(app.cmd, CmdKind::Check) {
+        let selected_count = app.pack_selected.iter().filter(|x| **x).count();
+        let scope = if selected_check_ids(app).is_empty() { "whole repo" } else { "one run each" };
+        lines.push(Line::from(format!(
+            "selected: {} / {} ({})",
+            selected_count,
+            app.pack_items.len(),
+            scope
+        )));
         lines.push(Line::from(format!("python: {} (set in Mode)", app.args.python)));
         lines.push(Line::from(format!(
             "python_strict: {} (set in Mode)",
@@ -2033,13 +2834,324 @@ fn draw_run(f: &mut Frame<'_>, app: &App, area: Rect, highlight: bool) {
     } else {
         Style::default()
     };
-    let out = Paragraph::new(app.output.clone())
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(out_border)
-                .title("Output / 输出（preview）"),
-        )
-        .wrap(Wrap { trim: false });
-    f.render_widget(out, right_chunks[1]);
+    draw_scrolled_output(f, app, right_chunks[1], out_border, "Output / 输出（preview）", Text::from(app.output.as_str()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_scroll_clamps_and_pages() {
+        let mut s = OutputScroll::default();
+        // 100 rows in a 10-row pane after the task finished: starts at the top
+        assert_eq!(s.top(100, 10, false), 0);
+        s.scroll_by(-1, 100, 10, false);
+        assert_eq!(s.top(100, 10, false), 0);
+        s.scroll_by(9, 100, 10, false);
+        assert_eq!(s.top(100, 10, false), 9);
+        assert!(!s.follow);
+        s.scroll_by(1000, 100, 10, false);
+        assert_eq!(s.top(100, 10, false), 90);
+        assert!(s.follow);
+        s.home();
+        assert_eq!(s.top(100, 10, false), 0);
+        s.end(100, 10);
+        assert_eq!(s.top(100, 10, false), 90);
+        // Output that fits never scrolls
+        s.scroll_by(5, 4, 10, false);
+        assert_eq!(s.top(4, 10, false), 0);
+    }
+
+    #[test]
+    fn test_output_scroll_follows_running_output_until_scrolled_up() {
+        let mut s = OutputScroll::default();
+        assert_eq!(s.top(30, 10, true), 20);
+        assert_eq!(s.top(50, 10, true), 40);
+
+        s.scroll_by(-5, 50, 10, true);
+        assert_eq!(s.top(50, 10, true), 35);
+        // More output arrives; the view stays where the user left it
+        assert_eq!(s.top(80, 10, true), 35);
+
+        // Scrolling back to the bottom sticks again
+        s.scroll_by(100, 80, 10, true);
+        assert!(s.follow);
+        assert_eq!(s.top(120, 10, true), 110);
+    }
+
+    #[test]
+    fn test_output_search_finds_matches_in_multibyte_lines() {
+        let text = "检查 alpha: ok\nERROR 插件 beta: 依赖缺失\n\nwarn: ERROR in 配置 ERROR\n";
+        let re = Regex::new("ERROR|插件").unwrap();
+        let matches = find_output_matches(text, &re);
+        let found: Vec<(usize, &str)> =
+            matches.iter().map(|m| (m.line, &text.lines().nth(m.line).unwrap()[m.start..m.end])).collect();
+        assert_eq!(found, [(1, "ERROR"), (1, "插件"), (3, "ERROR"), (3, "ERROR")]);
+        // Empty matches have nothing to show
+        assert!(find_output_matches(text, &Regex::new("x*").unwrap()).is_empty());
+
+        let rendered = highlight_output_matches(text, &matches, 1);
+        assert_eq!(rendered.lines.len(), 4);
+        let line: String = rendered.lines[1].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(line, "ERROR 插件 beta: 依赖缺失");
+        assert_eq!(rendered.lines[1].spans[3].content, "插件");
+        assert_eq!(rendered.lines[1].spans[3].style.bg, Some(Color::Cyan));
+        assert_eq!(rendered.lines[3].spans[1].style.bg, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn test_output_search_rows_and_stepping() {
+        let text = "one\ntwo\n你好你好你好ERROR\nERROR";
+        let re = Regex::new("ERROR").unwrap();
+        let matches = find_output_matches(text, &re);
+        // Width 4 wraps line 2 as 你好|你好|你好|ERRO|R, so its match starts on row 2 + 3
+        assert_eq!(output_match_row(text, &matches[0], 4), 5);
+        assert_eq!(output_match_row(text, &matches[1], 4), 7);
+        assert_eq!(output_match_row(text, &matches[1], 80), 3);
+
+        assert_eq!(step_output_match(0, 3, true), 1);
+        assert_eq!(step_output_match(2, 3, true), 0);
+        assert_eq!(step_output_match(0, 3, false), 2);
+        // A stale index from longer output is clamped first
+        assert_eq!(step_output_match(9, 3, false), 1);
+        assert_eq!(step_output_match(0, 0, true), 0);
+    }
+
+    #[test]
+    fn test_bundle_version_semver_hint() {
+        for ok in ["1", "1.2", "1.2.3", "v1.2.3", "2.0.0-rc.1", "1.0.0+build.5", " 1.0.0 "] {
+            assert!(is_semver_ish(ok), "{ok}");
+        }
+        for bad in ["", "latest", "1.2.3.4", "1..2", "v", "1.x", "１.0"] {
+            assert!(!is_semver_ish(bad), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_complete_path() {
+        let base = std::env::temp_dir().join(format!("neko_plugin_cli_tui_complete_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        for dir in ["plugins/alpha", "plugins/alpine", "plugins/插件", "dist", ".hidden"] {
+            fs::create_dir_all(base.join(dir)).unwrap();
+        }
+        fs::write(base.join("dist").join("bundle.zip"), b"").unwrap();
+        fs::write(base.join("dist").join("notes.txt"), b"").unwrap();
+        let sep = std::path::MAIN_SEPARATOR;
+
+        // A unique match completes fully, with a separator for directories
+        let (done, all) = complete_path("plu", &base, false);
+        assert_eq!(done, format!("plugins{sep}"));
+        assert_eq!(all, [format!("plugins{sep}")]);
+        // Several: extend to the common prefix and list them
+        let (done, all) = complete_path("plugins/al", &base, false);
+        assert_eq!(done, "plugins/alp");
+        assert_eq!(all, [format!("alpha{sep}"), format!("alpine{sep}")]);
+        let (done, _) = complete_path("plugins/插", &base, false);
+        assert_eq!(done, format!("plugins/插件{sep}"));
+        // Zips only where a .zip can be chosen; other files never; dot entries only when asked for
+        assert_eq!(complete_path("dist/", &base, true).1, ["bundle.zip"]);
+        assert!(complete_path("dist/", &base, false).1.is_empty());
+        assert_eq!(complete_path("", &base, false).1, [format!("dist{sep}"), format!("plugins{sep}")]);
+        assert_eq!(complete_path(".h", &base, false).1, [format!(".hidden{sep}")]);
+        // Absolute input and unreadable parents
+        let abs = format!("{}{sep}di", base.display());
+        assert_eq!(complete_path(&abs, Path::new("/nonexistent"), false).0, format!("{}{sep}dist{sep}", base.display()));
+        assert_eq!(complete_path("missing/x", &base, false), ("missing/x".to_string(), Vec::new()));
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn press(app: &mut App, codes: &[KeyCode]) {
+        for &code in codes {
+            handle_key(app, key(code)).unwrap();
+        }
+    }
+
+    /// A repo with plugins alpha, beta and gamma, for driving the TUI state without a terminal
+    fn tui_repo(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("neko_plugin_cli_tui_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for id in ["alpha", "beta", "gamma"] {
+            let dir = root.join("plugin").join("plugins").join(id);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("plugin.toml"), format!("[plugin]\nid = \"{id}\"\nversion = \"1.0.0\"\n")).unwrap();
+        }
+        root
+    }
+
+    /// From Home, open the `index`th command (Info, Pack, Unpack, Check)
+    fn open_cmd(app: &mut App, index: usize) {
+        app.selected = index;
+        press(app, &[KeyCode::Enter]);
+    }
+
+    #[test]
+    fn test_check_select_picks_plugins() {
+        let root = tui_repo("check_select");
+        let mut app = App::new(Some(root.clone()), None);
+        open_cmd(&mut app, 3);
+        assert_eq!(app.cmd, CmdKind::Check);
+        assert_eq!(available_tabs(&app)[app.tab_active], Tab::Select);
+        assert_eq!(app.pack_items, ["alpha", "beta", "gamma"]);
+        // Everything selected checks the whole repo in one run
+        assert!(selected_check_ids(&app).is_empty());
+
+        // Same keys as the Pack grid: focus, x clears, Space toggles the cursor
+        press(&mut app, &[KeyCode::Enter, KeyCode::Char('x')]);
+        assert!(selected_check_ids(&app).is_empty());
+        press(&mut app, &[KeyCode::Char(' '), KeyCode::Right, KeyCode::Char(' ')]);
+        assert_eq!(selected_check_ids(&app), ["alpha", "beta"]);
+
+        // The regex filter narrows what the cursor walks over
+        press(&mut app, &[KeyCode::Char('x'), KeyCode::Char('/'), KeyCode::Char('^'), KeyCode::Char('g'), KeyCode::Enter]);
+        assert_eq!(pack_filtered_indices(&app), [2]);
+        press(&mut app, &[KeyCode::Char(' ')]);
+        assert_eq!(selected_check_ids(&app), ["gamma"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_each_check_merges_runs_and_keeps_the_worst_status() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let ids = ["alpha".to_string(), "beta".to_string()];
+        let args = ["check".to_string(), "--root".to_string(), "/r".to_string()];
+        let seen = std::cell::RefCell::new(Vec::new());
+        let out = run_each_check(&ids, &args, |a| {
+            seen.borrow_mut().push(a.join(" "));
+            let code = if a[1] == "beta" { 2 } else { 0 };
+            Ok(std::process::Output {
+                status: std::process::ExitStatus::from_raw(code << 8),
+                stdout: format!("checked {}\n", a[1]).into_bytes(),
+                stderr: if code == 0 { Vec::new() } else { b"1 error".to_vec() },
+            })
+        })
+        .unwrap();
+        assert_eq!(*seen.borrow(), ["check alpha --root /r", "check beta --root /r"]);
+        assert_eq!(out.status.code(), Some(2));
+        assert_eq!(
+            String::from_utf8(out.stdout).unwrap(),
+            "=== check alpha (exit=0) ===\nchecked alpha\n=== check beta (exit=2) ===\nchecked beta\n1 error\n"
+        );
+        assert!(out.stderr.is_empty());
+    }
+
+    /// Put the Path cursor on `name` and press Space, as a user picking it would
+    fn choose_path(app: &mut App, name: &str) {
+        app.path_cursor = app.path_entries.iter().position(|e| e.name == name).expect(name);
+        press(app, &[KeyCode::Char(' ')]);
+    }
+
+    /// Open Unpack and focus its Path tab, with `dist/` and `bundle.zip` in the repo root
+    fn unpack_path_app(name: &str) -> (PathBuf, App) {
+        let root = tui_repo(name);
+        fs::create_dir_all(root.join("dist")).unwrap();
+        fs::write(root.join("bundle.zip"), b"").unwrap();
+        let mut app = App::new(Some(root.clone()), None);
+        open_cmd(&mut app, 2);
+        press(&mut app, &[KeyCode::Down, KeyCode::Enter]);
+        assert_eq!(available_tabs(&app)[app.tab_active], Tab::Path);
+        assert!(app.focus);
+        (root, app)
+    }
+
+    #[test]
+    fn test_unpack_path_tab_switches_between_zip_and_dest() {
+        let (root, mut app) = unpack_path_app("unpack_switch");
+        assert!(!picks_directory(&app));
+        assert!(app.path_entries.iter().any(|e| e.is_zip));
+        choose_path(&mut app, "bundle.zip");
+        assert_eq!(app.args.zip_path, Some(root.join("bundle.zip")));
+        // No new directories while choosing the .zip
+        press(&mut app, &[KeyCode::Char('n')]);
+        assert!(!app.editing_new_dir);
+
+        // Tab: browse for the destination; zips are hidden and Space on a directory sets --dest
+        press(&mut app, &[KeyCode::Tab]);
+        assert!(app.path_pick_dest && picks_directory(&app));
+        assert!(!app.path_entries.iter().any(|e| e.is_zip));
+        choose_path(&mut app, "dist");
+        assert_eq!(app.args.dest, Some(root.join("dist")));
+        assert_eq!(app.path_current_dir, root.join("dist"));
+
+        // Tab back: the browser returns to the chosen .zip and both choices stay
+        press(&mut app, &[KeyCode::Tab]);
+        assert!(!app.path_pick_dest);
+        assert_eq!(app.path_current_dir, root);
+        assert_eq!(app.args.zip_path, Some(root.join("bundle.zip")));
+        assert_eq!(app.args.dest, Some(root.join("dist")));
+
+        // Reopening Unpack starts on the .zip again but keeps the destination
+        press(&mut app, &[KeyCode::Esc]);
+        open_cmd(&mut app, 2);
+        assert!(!app.path_pick_dest);
+        assert_eq!(app.args.dest, Some(root.join("dist")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unpack_dest_new_directory_prompt() {
+        let (root, mut app) = unpack_path_app("unpack_new_dir");
+        press(&mut app, &[KeyCode::Tab]);
+        choose_path(&mut app, "dist");
+
+        // n opens the prompt; q is part of the name, not the help overlay
+        press(&mut app, &[KeyCode::Char('n'), KeyCode::Char('q'), KeyCode::Char('a'), KeyCode::Enter]);
+        assert!(!app.show_help);
+        assert!(!app.editing_new_dir);
+        let created = root.join("dist").join("qa");
+        assert!(created.is_dir());
+        assert_eq!(app.args.dest, Some(created.clone()));
+        assert_eq!(app.path_current_dir, created);
+
+        // Names that are not a single path component are refused without touching the disk
+        press(&mut app, &[KeyCode::Char('n'), KeyCode::Char('a'), KeyCode::Char('/'), KeyCode::Char('b'), KeyCode::Enter]);
+        assert!(app.editing_new_dir);
+        assert_eq!(app.new_dir_error.as_deref(), Some("invalid directory name"));
+        assert!(!created.join("a").exists());
+
+        // A failed create keeps the prompt open with the error and leaves --dest alone
+        fs::create_dir(created.join("taken")).unwrap();
+        press(&mut app, &[KeyCode::Esc, KeyCode::Char('n')]);
+        for c in "taken".chars() {
+            press(&mut app, &[KeyCode::Char(c)]);
+        }
+        press(&mut app, &[KeyCode::Enter]);
+        assert!(app.editing_new_dir);
+        let err = app.new_dir_error.clone().unwrap();
+        assert!(err.starts_with("failed to create"), "{err}");
+        assert_eq!(app.args.dest, Some(created.clone()));
+        // Typing clears the error; Esc cancels the prompt but stays on the Unpack screen
+        press(&mut app, &[KeyCode::Char('2')]);
+        assert!(app.new_dir_error.is_none());
+        press(&mut app, &[KeyCode::Esc]);
+        assert!(!app.editing_new_dir && app.new_dir_name.is_empty());
+        assert!(matches!(app.screen, Screen::Exec));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_output_scroll_reveal() {
+        let mut s = OutputScroll::default();
+        s.home();
+        // Already visible: stay
+        s.reveal(5, 100, 10, false);
+        assert_eq!(s.top(100, 10, false), 0);
+        // Below the view: put it a third of the way down
+        s.reveal(50, 100, 10, false);
+        assert_eq!(s.top(100, 10, false), 47);
+        // Near the end: clamp to the last page
+        s.reveal(99, 100, 10, false);
+        assert_eq!(s.top(100, 10, false), 90);
+        // While running, revealing stops following
+        let mut s = OutputScroll::default();
+        s.reveal(3, 100, 10, true);
+        assert!(!s.follow);
+        assert_eq!(s.top(100, 10, true), 0);
+    }
 }
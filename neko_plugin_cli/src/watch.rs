@@ -0,0 +1,412 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use crossterm::style::{Color, Stylize};
+use notify::{RecursiveMode, Watcher};
+
+use neko_plugin_cli::core;
+
+/// Default `watch --debounce`: editors often write a file several times in a row
+pub(crate) const WATCH_DEBOUNCE_MS: u64 = 300;
+
+enum WatchMsg {
+    Changed(Vec<PathBuf>),
+    Stop,
+}
+
+/// The plugins a batch of changes asks to re-check
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Affected {
+    /// plugin/sdk/version.py changed, so every plugin's base check may have
+    All,
+    /// Folder names under plugin/plugins
+    Folders(BTreeSet<String>),
+}
+
+impl Affected {
+    fn merge(self, other: Affected) -> Affected {
+        match (self, other) {
+            (Affected::Folders(mut a), Affected::Folders(b)) => {
+                a.extend(b);
+                Affected::Folders(a)
+            }
+            _ => Affected::All,
+        }
+    }
+}
+
+/// Collects changed paths and hands them out once none arrived for `window`
+struct Debouncer {
+    plugins_dir: PathBuf,
+    sdk_file: PathBuf,
+    window: Duration,
+    pending: Option<Affected>,
+    last: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(repo_root: &Path, window: Duration) -> Self {
+        Debouncer {
+            plugins_dir: repo_root.join("plugin").join("plugins"),
+            sdk_file: repo_root.join("plugin").join("sdk").join("version.py"),
+            window,
+            pending: None,
+            last: None,
+        }
+    }
+
+    /// None for paths no check reads: bytecode, VCS data, and the dot entries of plugin/plugins
+    /// (backups, unpack staging, the disabled list)
+    fn classify(&self, path: &Path) -> Option<Affected> {
+        if path == self.sdk_file {
+            return Some(Affected::All);
+        }
+        let rel = path.strip_prefix(&self.plugins_dir).ok()?;
+        let mut parts = rel.iter().map(|c| c.to_string_lossy());
+        let folder = parts.next()?.into_owned();
+        if folder.starts_with('.') || (rel.iter().count() == 1 && path.is_file()) {
+            return None;
+        }
+        let noise = |name: &str| name == "__pycache__" || name == ".git" || name.ends_with(".pyc");
+        if noise(&folder) || parts.any(|name| noise(&name)) {
+            return None;
+        }
+        Some(Affected::Folders(BTreeSet::from([folder])))
+    }
+
+    fn push(&mut self, paths: &[PathBuf], now: Instant) {
+        for path in paths {
+            if let Some(affected) = self.classify(path) {
+                self.pending = Some(match self.pending.take() {
+                    Some(pending) => pending.merge(affected),
+                    None => affected,
+                });
+                self.last = Some(now);
+            }
+        }
+    }
+
+    /// How long to wait for more events before the pending batch is due; None when nothing is pending
+    fn wait(&self, now: Instant) -> Option<Duration> {
+        self.pending.as_ref()?;
+        Some(self.window.saturating_sub(now.duration_since(self.last?)))
+    }
+
+    fn take_ready(&mut self, now: Instant) -> Option<Affected> {
+        if self.wait(now)? > Duration::ZERO {
+            return None;
+        }
+        self.last = None;
+        self.pending.take()
+    }
+}
+
+/// Runs the checks for one batch and prints the summary
+struct CheckRunner {
+    repo_root: PathBuf,
+    plugin_id: Option<String>,
+    checks: core::CheckFlags,
+    exec: Option<String>,
+    color: bool,
+}
+
+impl CheckRunner {
+    /// Ids of the installed plugins in `affected`, limited to `plugin_id`
+    fn select(&self, affected: &Affected) -> Result<Vec<String>> {
+        let plugins = core::list_plugins(Some(&self.repo_root), &core::PluginFilter::default())?;
+        Ok(plugins
+            .into_iter()
+            .filter(|p| match affected {
+                Affected::All => true,
+                Affected::Folders(folders) => folders.contains(&p.folder),
+            })
+            .filter(|p| self.plugin_id.as_ref().is_none_or(|id| id == &p.id))
+            .map(|p| p.id)
+            .collect())
+    }
+
+    /// Check the affected plugins; a check that cannot run is printed, not returned, so one
+    /// half-saved plugin.toml does not end the watch. Returns whether the check came out clean.
+    fn run(&self, affected: &Affected, out: &mut impl Write) -> Result<bool> {
+        let stamp = chrono::Local::now().format("%H:%M:%S").to_string();
+        let report = self.select(affected).and_then(|ids| {
+            if ids.is_empty() {
+                return Ok(None);
+            }
+            let sdk_version = core::read_sdk_version(&self.repo_root)?;
+            let plugins_dir = self.repo_root.join("plugin").join("plugins");
+            Ok(Some((core::run_checks_for(&plugins_dir, &ids, &sdk_version, self.checks)?, ids)))
+        });
+        let (report, ids) = match report {
+            Ok(Some(done)) => done,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                writeln!(out, "[{}] {} {:#}", stamp, self.paint("ERROR", Color::Red), e)?;
+                return Ok(false);
+            }
+        };
+        let clean = report.errors.is_empty();
+        let status = match (clean, report.warnings.is_empty()) {
+            (false, _) => self.paint("FAIL", Color::Red),
+            (true, false) => self.paint("WARN", Color::Yellow),
+            (true, true) => self.paint("OK", Color::Green),
+        };
+        writeln!(
+            out,
+            "[{}] {} {}: {} error(s), {} warning(s)",
+            stamp,
+            status,
+            ids.join(", "),
+            report.errors.len(),
+            report.warnings.len()
+        )?;
+        for f in &report.findings {
+            let label = match f.severity {
+                core::Severity::Error => self.paint("ERROR", Color::Red),
+                core::Severity::Warning => self.paint("WARN", Color::Yellow),
+            };
+            writeln!(out, "  {} [{}] {}", label, f.code, f.message)?;
+        }
+        if let Some(cmd) = self.exec.as_deref().filter(|_| clean) {
+            out.flush()?;
+            let status = shell_command(cmd).status().with_context(|| format!("failed to run {:?}", cmd))?;
+            if !status.success() {
+                writeln!(out, "  {} {:?} exited with {}", self.paint("WARN", Color::Yellow), cmd, status)?;
+            }
+        }
+        Ok(clean)
+    }
+
+    fn paint(&self, label: &str, color: Color) -> String {
+        if self.color { label.with(color).bold().to_string() } else { label.to_string() }
+    }
+}
+
+fn shell_command(cmd: &str) -> Command {
+    if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.args(["/C", cmd]);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.args(["-c", cmd]);
+        c
+    }
+}
+
+/// Watch plugin/plugins (or just `plugin_id`) and plugin/sdk/version.py, re-checking the plugins
+/// whose files changed once `debounce` passed without further events. Runs until Ctrl-C.
+pub(crate) fn run(root: Option<PathBuf>, plugin_id: Option<String>, debounce: Duration, exec: Option<String>) -> Result<()> {
+    let repo_root = match root {
+        Some(p) => p,
+        None => core::find_repo_root(std::env::current_dir().context("failed to get cwd")?)?,
+    };
+    // Watchers report resolved paths, which only strip_prefix against a resolved root
+    let repo_root = fs::canonicalize(&repo_root).with_context(|| format!("failed to resolve {}", repo_root.display()))?;
+    let plugins_dir = repo_root.join("plugin").join("plugins");
+    let mut checks = core::resolve_check_flags(false, false, false, false, false, false);
+    checks.strict_naming = core::load_repo_check_config(&repo_root)?.strict_naming;
+    let runner = CheckRunner {
+        repo_root: repo_root.clone(),
+        plugin_id,
+        checks,
+        exec,
+        color: std::io::stdout().is_terminal(),
+    };
+    if let Some(id) = &runner.plugin_id
+        && runner.select(&Affected::All)?.is_empty()
+    {
+        anyhow::bail!("plugin {} not found in {}", id, plugins_dir.display());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let stop = tx.clone();
+    ctrlc::set_handler(move || {
+        let _ = stop.send(WatchMsg::Stop);
+    })
+    .context("failed to install the Ctrl-C handler")?;
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        // Checks open and read the files they look at; only writes, creations and removals count
+        match event {
+            Ok(event) if !matches!(event.kind, notify::EventKind::Access(_)) => {
+                let _ = tx.send(WatchMsg::Changed(event.paths));
+            }
+            _ => {}
+        }
+    })
+    .context("failed to start the file watcher")?;
+    watcher
+        .watch(&plugins_dir, RecursiveMode::Recursive)
+        .with_context(|| format!("failed to watch {}", plugins_dir.display()))?;
+    // The directory rather than the file, so editors that save by renaming keep being seen
+    let sdk_dir = repo_root.join("plugin").join("sdk");
+    if sdk_dir.is_dir() {
+        watcher
+            .watch(&sdk_dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("failed to watch {}", sdk_dir.display()))?;
+    }
+    log::info!("watching {} (Ctrl-C to stop)", plugins_dir.display());
+
+    let mut stdout = std::io::stdout();
+    runner.run(&Affected::All, &mut stdout)?;
+    let mut debouncer = Debouncer::new(&repo_root, debounce);
+    loop {
+        let msg = match debouncer.wait(Instant::now()) {
+            Some(wait) => match rx.recv_timeout(wait) {
+                Ok(msg) => Some(msg),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            None => match rx.recv() {
+                Ok(msg) => Some(msg),
+                Err(_) => break,
+            },
+        };
+        match msg {
+            Some(WatchMsg::Stop) => break,
+            Some(WatchMsg::Changed(paths)) => debouncer.push(&paths, Instant::now()),
+            None => {}
+        }
+        if let Some(affected) = debouncer.take_ready(Instant::now()) {
+            runner.run(&affected, &mut stdout)?;
+        }
+    }
+    log::info!("stopped watching");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_repo(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("neko_plugin_cli_watch_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("plugin").join("sdk")).unwrap();
+        fs::write(root.join("plugin").join("sdk").join("version.py"), "SDK_VERSION = \"1.2.0\"\n").unwrap();
+        fs::write(root.join("pyproject.toml"), "[project]\nname = \"n.e.k.o\"\nversion = \"0.5.0\"\n").unwrap();
+        for (folder, module, toml) in [
+            ("alpha", "alpha", "[plugin]\nid = \"alpha\"\nversion = \"1.0.0\"\nentry = \"alpha:main\"\n"),
+            (
+                "beta_dir",
+                "beta",
+                "[plugin]\nid = \"beta\"\nversion = \"0.3.0\"\nentry = \"beta:main\"\n\n\
+                 [[plugin.dependency]]\nid = \"alpha\"\nsupported = \">=2.0.0\"\n",
+            ),
+        ] {
+            let dir = root.join("plugin").join("plugins").join(folder);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("plugin.toml"), toml).unwrap();
+            fs::write(dir.join(format!("{module}.py")), "def main():\n    pass\n").unwrap();
+        }
+        root
+    }
+
+    fn runner(root: &Path, plugin_id: Option<&str>) -> CheckRunner {
+        CheckRunner {
+            repo_root: root.to_path_buf(),
+            plugin_id: plugin_id.map(str::to_string),
+            checks: core::resolve_check_flags(false, false, false, false, false, false),
+            exec: None,
+            color: false,
+        }
+    }
+
+    fn folders(names: &[&str]) -> Affected {
+        Affected::Folders(names.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_debouncer_batches_events_until_quiet() {
+        let root = Path::new("/repo");
+        let plugins = root.join("plugin").join("plugins");
+        let mut d = Debouncer::new(root, Duration::from_millis(300));
+        let t0 = Instant::now();
+        assert_eq!(d.wait(t0), None);
+
+        d.push(&[plugins.join("alpha").join("plugin.toml")], t0);
+        d.push(&[plugins.join("beta_dir").join("sub").join("x.py")], t0 + Duration::from_millis(200));
+        assert_eq!(d.wait(t0 + Duration::from_millis(250)), Some(Duration::from_millis(250)));
+        assert_eq!(d.take_ready(t0 + Duration::from_millis(400)), None);
+        assert_eq!(d.take_ready(t0 + Duration::from_millis(500)), Some(folders(&["alpha", "beta_dir"])));
+        assert_eq!(d.take_ready(t0 + Duration::from_millis(900)), None);
+
+        d.push(&[plugins.join("alpha").join("a.py"), root.join("plugin").join("sdk").join("version.py")], t0);
+        assert_eq!(d.take_ready(t0 + Duration::from_millis(300)), Some(Affected::All));
+    }
+
+    #[test]
+    fn test_debouncer_ignores_noise() {
+        let root = Path::new("/repo");
+        let plugins = root.join("plugin").join("plugins");
+        let mut d = Debouncer::new(root, Duration::from_millis(300));
+        d.push(
+            &[
+                plugins.join("alpha").join("__pycache__").join("a.cpython-312.pyc"),
+                plugins.join("alpha").join("b.pyc"),
+                plugins.join(".backups").join("alpha_20240101").join("plugin.toml"),
+                plugins.join(".disabled.toml"),
+                root.join("plugin").join("sdk").join("other.py"),
+                root.join("README.md"),
+            ],
+            Instant::now(),
+        );
+        assert_eq!(d.wait(Instant::now()), None);
+    }
+
+    #[test]
+    fn test_runner_checks_only_affected_plugins() {
+        let root = fixture_repo("runner");
+        let mut out = Vec::new();
+        assert!(runner(&root, None).run(&folders(&["alpha"]), &mut out).unwrap());
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.ends_with("] OK alpha: 0 error(s), 0 warning(s)\n"), "{text}");
+
+        let mut out = Vec::new();
+        assert!(!runner(&root, None).run(&Affected::All, &mut out).unwrap());
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("] FAIL alpha, beta: 1 error(s), 0 warning(s)\n"), "{text}");
+        assert!(text.contains("  ERROR [dep-unsupported] "), "{text}");
+
+        let mut out = Vec::new();
+        assert!(runner(&root, Some("alpha")).run(&Affected::All, &mut out).unwrap());
+        assert!(!String::from_utf8(out).unwrap().contains("beta"));
+
+        // A folder that no longer holds a plugin prints nothing
+        let mut out = Vec::new();
+        assert!(!runner(&root, None).run(&folders(&["gone"]), &mut out).unwrap());
+        assert!(out.is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_runner_reports_broken_toml_and_keeps_going() {
+        let root = fixture_repo("broken");
+        fs::write(root.join("plugin").join("plugins").join("alpha").join("plugin.toml"), "[plugin\n").unwrap();
+        let mut out = Vec::new();
+        assert!(!runner(&root, None).run(&folders(&["alpha"]), &mut out).unwrap());
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("] ERROR failed to parse"), "{text}");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_runs_only_after_a_clean_check() {
+        let root = fixture_repo("exec");
+        let marker = root.join("ran");
+        let mut r = runner(&root, None);
+        r.exec = Some(format!("echo ok >> '{}'", marker.display()));
+        r.run(&Affected::All, &mut Vec::new()).unwrap();
+        assert!(!marker.exists());
+        r.run(&folders(&["alpha"]), &mut Vec::new()).unwrap();
+        assert_eq!(fs::read_to_string(&marker).unwrap(), "ok\n");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+//! The library API round-trips a bundle the way the CLI does and sorts failures into
+//! NekoCliError variants.
+
+use std::fs;
+use std::path::PathBuf;
+
+use neko_plugin_cli::{CheckOptions, CheckPass, NekoCliError, PackOptions, UnpackOptions};
+
+fn fixture_repo(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("neko_plugin_cli_api_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("plugin").join("sdk")).unwrap();
+    fs::write(root.join("plugin").join("sdk").join("version.py"), "SDK_VERSION = \"1.2.0\"\n").unwrap();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"n.e.k.o\"\nversion = \"0.5.0\"\n").unwrap();
+    for (folder, module, toml) in [
+        ("alpha", "alpha", "[plugin]\nid = \"alpha\"\nversion = \"1.0.0\"\nentry = \"alpha:main\"\n"),
+        (
+            "beta_dir",
+            "beta",
+            "[plugin]\nid = \"beta\"\nversion = \"0.3.0\"\nentry = \"beta:main\"\n\n\
+             [[plugin.dependency]]\nid = \"alpha\"\nsupported = \">=2.0.0\"\n",
+        ),
+    ] {
+        let dir = root.join("plugin").join("plugins").join(folder);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("plugin.toml"), toml).unwrap();
+        fs::write(dir.join(format!("{module}.py")), "def main():\n    pass\n").unwrap();
+    }
+    root
+}
+
+fn empty_repo(name: &str) -> PathBuf {
+    let root = fixture_repo(name);
+    fs::remove_dir_all(root.join("plugin").join("plugins")).unwrap();
+    fs::create_dir_all(root.join("plugin").join("plugins")).unwrap();
+    root
+}
+
+#[test]
+fn test_pack_unpack_check_round_trip() {
+    let src = fixture_repo("src");
+    let out = src.join("bundle.zip");
+    let report = neko_plugin_cli::pack(&PackOptions::new().root(&src).out(&out).verify(true)).unwrap();
+    assert_eq!(report.path.as_deref(), Some(out.as_path()));
+    assert_eq!(report.size, fs::metadata(&out).unwrap().len());
+    let mut ids: Vec<&str> = report.plugins.iter().map(String::as_str).collect();
+    ids.sort();
+    assert_eq!(ids, ["alpha", "beta"]);
+    assert!(report.verify.is_some());
+
+    let dst = empty_repo("dst");
+    let unpacked = neko_plugin_cli::unpack(&UnpackOptions::new(&out).root(&dst)).unwrap();
+    assert_eq!(unpacked.installed.len(), 2);
+    assert!(dst.join("plugin").join("plugins").join("beta_dir").join("beta.py").is_file());
+
+    let checked = neko_plugin_cli::check(&CheckOptions::new().root(&dst).cache(false)).unwrap();
+    assert_eq!(checked.errors.len(), 1, "{:?}", checked.errors);
+    let only_ids = neko_plugin_cli::check(&CheckOptions::new().root(&dst).cache(false).pass(CheckPass::Id)).unwrap();
+    assert!(only_ids.errors.is_empty(), "{:?}", only_ids.errors);
+
+    fs::remove_dir_all(&src).unwrap();
+    fs::remove_dir_all(&dst).unwrap();
+}
+
+#[test]
+fn test_errors_are_classified() {
+    let root = fixture_repo("errors");
+    let err = neko_plugin_cli::pack(&PackOptions::new().root(&root).plugins(["missing"])).unwrap_err();
+    assert!(matches!(err, NekoCliError::Validation { .. }), "{err:?}");
+
+    let missing = root.join("nope.zip");
+    let err = neko_plugin_cli::unpack(&UnpackOptions::new(&missing).root(&root)).unwrap_err();
+    match &err {
+        NekoCliError::Io { source, .. } => assert_eq!(source.kind(), std::io::ErrorKind::NotFound),
+        other => panic!("expected an io error, got {other:?}"),
+    }
+    assert!(err.to_string().contains("nope.zip"), "{err}");
+
+    let garbage = root.join("garbage.zip");
+    fs::write(&garbage, b"not a zip").unwrap();
+    let err = neko_plugin_cli::unpack(&UnpackOptions::new(&garbage).root(&root)).unwrap_err();
+    assert!(matches!(err, NekoCliError::Manifest { .. }), "{err:?}");
+
+    let err = neko_plugin_cli::check(&CheckOptions::new().root(root.join("no_such_repo"))).unwrap_err();
+    assert!(matches!(err, NekoCliError::Io { .. } | NekoCliError::Validation { .. }), "{err:?}");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_fail_on_conflict_names_changed_plugins() {
+    let root = fixture_repo("conflict");
+    let out = root.join("bundle.zip");
+    neko_plugin_cli::pack(&PackOptions::new().root(&root).out(&out)).unwrap();
+    fs::write(root.join("plugin").join("plugins").join("alpha").join("alpha.py"), "def main():\n    return 1\n").unwrap();
+
+    let options = UnpackOptions::new(&out).root(&root).fail_on_conflict(true);
+    match neko_plugin_cli::unpack(&options).unwrap_err() {
+        NekoCliError::Conflict { plugins, message } => {
+            assert!(plugins.contains(&"alpha".to_string()), "{plugins:?}");
+            assert!(message.contains("--force"), "{message}");
+        }
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+    // The installed copy was left alone
+    let alpha = fs::read_to_string(root.join("plugin").join("plugins").join("alpha").join("alpha.py")).unwrap();
+    assert!(alpha.contains("return 1"));
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,99 @@
+//! Runs the CLI binary and checks that log lines stay on stderr, away from the stdout output
+//! that scripts parse.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn fixture_repo(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("neko_plugin_cli_output_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("plugin").join("sdk")).unwrap();
+    fs::write(root.join("plugin").join("sdk").join("version.py"), "SDK_VERSION = \"1.2.0\"\n").unwrap();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"n.e.k.o\"\nversion = \"0.5.0\"\n").unwrap();
+    for (folder, module, toml) in [
+        ("alpha", "alpha", "[plugin]\nid = \"alpha\"\nversion = \"1.0.0\"\nentry = \"alpha:main\"\n"),
+        (
+            "beta_dir",
+            "beta",
+            "[plugin]\nid = \"beta\"\nversion = \"0.3.0\"\nentry = \"beta:main\"\n\n\
+             [[plugin.dependency]]\nid = \"alpha\"\nsupported = \">=2.0.0\"\n",
+        ),
+    ] {
+        let dir = root.join("plugin").join("plugins").join(folder);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("plugin.toml"), toml).unwrap();
+        fs::write(dir.join(format!("{module}.py")), "def main():\n    pass\n").unwrap();
+    }
+    root
+}
+
+fn run(root: &Path, args: &[&str], rust_log: Option<&str>) -> (Output, String, String) {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_neko_plugin_cli"));
+    cmd.args(args).current_dir(root).env_remove("RUST_LOG");
+    if let Some(filter) = rust_log {
+        cmd.env("RUST_LOG", filter);
+    }
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+    (output, stdout, stderr)
+}
+
+#[test]
+fn test_pack_prints_only_the_path_on_stdout() {
+    let root = fixture_repo("pack");
+    let out = root.join("bundle.zip");
+    let out_arg = out.to_str().unwrap();
+
+    let (output, stdout, stderr) = run(&root, &["pack", "--verify", "--out", out_arg], None);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(stdout, format!("{out_arg}\n"));
+    assert!(stderr.lines().any(|l| l.starts_with("INFO: verified ")), "{stderr}");
+    assert!(!stderr.contains("DEBUG:"), "{stderr}");
+
+    let (output, stdout, stderr) = run(&root, &["pack", "-v", "--verify", "--out", out_arg], None);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(stdout, format!("{out_arg}\n"));
+    assert!(stderr.contains("DEBUG: packing plugin 'alpha' 1.0.0 from "), "{stderr}");
+
+    let (output, stdout, stderr) = run(&root, &["--quiet", "pack", "--verify", "--out", out_arg], None);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(stdout, format!("{out_arg}\n"));
+    assert_eq!(stderr, "");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_check_json_keeps_log_lines_off_stdout() {
+    let root = fixture_repo("check");
+    let (output, stdout, stderr) = run(&root, &["check", "--json", "-v"], None);
+    assert_eq!(output.status.code(), Some(2), "{stderr}");
+    let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(report["errors"].as_array().unwrap().len(), 1);
+    assert!(stderr.contains("DEBUG: plugin alpha: checked"), "{stderr}");
+
+    // -q leaves only the final error; the report is unchanged
+    let (output, quiet_stdout, stderr) = run(&root, &["check", "--json", "-q"], None);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stderr, "check failed\n");
+    let quiet: serde_json::Value = serde_json::from_str(&quiet_stdout).unwrap();
+    assert_eq!(quiet["errors"], report["errors"]);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_rust_log_applies_without_flags() {
+    let root = fixture_repo("rust_log");
+    let out = root.join("bundle.zip");
+    let out_arg = out.to_str().unwrap();
+
+    let (_, _, stderr) = run(&root, &["pack", "--verify", "--out", out_arg], Some("error"));
+    assert_eq!(stderr, "");
+    let (_, _, stderr) = run(&root, &["pack", "--verify", "--out", out_arg], Some("neko_plugin_cli=debug"));
+    assert!(stderr.contains("DEBUG: packing plugin 'beta' 0.3.0 from "), "{stderr}");
+    // An explicit flag wins over RUST_LOG for this crate; other crates still follow RUST_LOG
+    let (_, _, stderr) = run(&root, &["pack", "-q", "--verify", "--out", out_arg], Some("debug"));
+    assert!(!stderr.contains("DEBUG: packing plugin") && !stderr.contains("INFO: verified"), "{stderr}");
+    fs::remove_dir_all(&root).unwrap();
+}
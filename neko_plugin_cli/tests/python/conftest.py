@@ -0,0 +1,135 @@
+from __future__ import annotations
+
+import zipfile
+from pathlib import Path
+
+import pytest
+
+import neko_plugin_cli
+
+
+def write_plugin(root: Path, folder: str, plugin_toml: str, files: dict[str, str] | None = None) -> Path:
+    plugin_dir = root / "plugin" / "plugins" / folder
+    plugin_dir.mkdir(parents=True, exist_ok=True)
+    (plugin_dir / "plugin.toml").write_text(plugin_toml, encoding="utf-8")
+    for rel, text in (files or {}).items():
+        p = plugin_dir / rel
+        p.parent.mkdir(parents=True, exist_ok=True)
+        p.write_text(text, encoding="utf-8")
+    return plugin_dir
+
+
+def make_repo(root: Path, sdk_version: str = "1.2.0") -> Path:
+    """Minimal N.E.K.O checkout: pyproject.toml, SDK version and a plugins dir."""
+    (root / "plugin" / "plugins").mkdir(parents=True, exist_ok=True)
+    (root / "plugin" / "sdk").mkdir(parents=True, exist_ok=True)
+    (root / "pyproject.toml").write_text('[project]\nname = "n.e.k.o"\nversion = "0.5.0"\ndependencies = []\n', encoding="utf-8")
+    (root / "plugin" / "sdk" / "version.py").write_text(f'SDK_VERSION = "{sdk_version}"\n', encoding="utf-8")
+    return root
+
+
+def pack_bundle(root: Path, out: Path, **kwargs) -> Path:
+    """Pack `root` into `out` and return `out`."""
+    neko_plugin_cli.pack(root=root, out=out, **kwargs)
+    return out
+
+
+def rewrite_zip(src_zip: Path, dst_zip: Path, edit) -> Path:
+    """Copy a bundle, passing each entry through `edit(name, data)` and returning `dst_zip`.
+
+    `edit` returns the new data, a `(name, data)` pair to also rename the entry, or None to drop it.
+    """
+    with zipfile.ZipFile(src_zip) as src, zipfile.ZipFile(dst_zip, "w") as dst:
+        for info in src.infolist():
+            out = edit(info.filename, src.read(info))
+            if out is None:
+                continue
+            if isinstance(out, tuple):
+                info.filename, out = out
+            dst.writestr(info, out)
+    return dst_zip
+
+
+def rewrite_manifest(src_zip: Path, dst_zip: Path, edit) -> Path:
+    """Copy a bundle with its manifest.toml text passed through `edit`."""
+    return rewrite_zip(
+        src_zip, dst_zip, lambda name, data: edit(data.decode()).encode() if name == "manifest.toml" else data
+    )
+
+
+def alpha_names(path: Path) -> set[str]:
+    """Files packed for the alpha plugin, relative to its folder."""
+    prefix = "plugins/alpha/"
+    return {n[len(prefix):] for n in zipfile.ZipFile(path).namelist() if n.startswith(prefix)}
+
+
+def alpha_hash(res: dict) -> str:
+    """Folder hash the pack result's manifest records for alpha."""
+    [alpha] = [p for p in res["manifest"]["plugins"] if p["id"] == "alpha"]
+    return alpha["hash"]
+
+
+def snapshot(root: Path) -> dict[Path, bytes]:
+    """Contents of every file under `root`, to check a directory was left untouched."""
+    return {p.relative_to(root): p.read_bytes() for p in sorted(root.rglob("*")) if p.is_file()}
+
+
+def break_checksum(src_zip: Path, dst_zip: Path, path: str) -> Path:
+    """Fault injection: the manifest checksum of `path` no longer matches, so extraction fails on it"""
+
+    def edit(text):
+        lines = text.splitlines()
+        i = lines.index(f'path = "{path}"')
+        j = next(k for k in range(i, len(lines)) if lines[k].startswith("sha256 = "))
+        lines[j] = 'sha256 = "' + "0" * 64 + '"'
+        return "\n".join(lines)
+
+    return rewrite_manifest(src_zip, dst_zip, edit)
+
+
+def finding_codes(report: dict) -> list[tuple[str, str]]:
+    """(code, plugin_id) of each finding in a check report."""
+    return [(f["code"], f["plugin_id"]) for f in report["findings"]]
+
+
+def issue_codes(report: dict) -> list[tuple[str, str]]:
+    """(code, message) of each issue in a verify report."""
+    return [(i["code"], i["message"]) for i in report["issues"]]
+
+
+@pytest.fixture
+def repo(tmp_path):
+    """Repo without plugins; tests add their own with write_plugin."""
+    return make_repo(tmp_path / "repo")
+
+
+@pytest.fixture
+def neko_repo(tmp_path):
+    """Repo with two compatible plugins; beta depends on alpha."""
+    root = make_repo(tmp_path / "repo")
+    write_plugin(
+        root,
+        "alpha",
+        '[plugin]\nid = "alpha"\nname = "Alpha"\nversion = "1.0.0"\nentry = "alpha:main"\n\n'
+        '[plugin.sdk]\nsupported = ">=1.0.0, <2.0.0"\n',
+        {
+            "__init__.py": "def main():\n    pass\n",
+            "data/config.json": '{"a": 1}\n',
+            "profiles.toml": "[default]\nlevel = 1\n",
+            "__pycache__/junk.pyc": "compiled",
+        },
+    )
+    write_plugin(
+        root,
+        "beta_dir",
+        '[plugin]\nid = "beta"\nname = "Beta"\nversion = "0.3.0"\nentry = "beta:main"\n\n'
+        '[[plugin.dependency]]\nid = "alpha"\nsupported = ">=1.0.0"\n',
+        {"beta.py": "def main():\n    return 2\n"},
+    )
+    return root
+
+
+@pytest.fixture
+def bundle(neko_repo, tmp_path):
+    """neko_repo packed into bundle.zip; manifest format 2, so it carries per-file checksums."""
+    return pack_bundle(neko_repo, tmp_path / "bundle.zip", manifest_version=2)
@@ -0,0 +1,114 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+from conftest import pack_bundle, rewrite_manifest, write_plugin
+
+import neko_plugin_cli
+
+LEVEL_1 = "[default]\nlevel = 1\n"
+DEV = "[dev]\ndebug = true\n"
+
+
+@pytest.fixture
+def profiles_bundle(repo, tmp_path):
+    write_plugin(
+        repo,
+        "alpha",
+        '[plugin]\nid = "alpha"\nname = "Alpha"\nversion = "1.0.0"\nentry = "alpha:main"\n',
+        {"__init__.py": "def main():\n    pass\n", "profiles.toml": LEVEL_1, "profiles/dev.toml": DEV},
+    )
+    return pack_bundle(repo, tmp_path / "bundle.zip", bundle_name="demo")
+
+
+@pytest.fixture
+def customized(profiles_bundle, tmp_path):
+    """alpha is installed, then its user raised the level and dropped the dev profile"""
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(profiles_bundle, dest)
+    (dest / "alpha" / "profiles.toml").write_text("[default]\nlevel = 5\n")
+    (dest / "alpha" / "profiles" / "dev.toml").unlink()
+    return dest
+
+
+def _manifest(bundle):
+    return zipfile.ZipFile(bundle).read("manifest.toml").decode()
+
+
+def _statuses(res):
+    return {p["path"]: p["status"] for p in res["profiles"]}
+
+
+def test_manifest_records_original_paths(profiles_bundle):
+    res = neko_plugin_cli.pack(root=profiles_bundle.parent / "repo", out=profiles_bundle.parent / "again.zip", bundle_name="demo")
+    [alpha] = res["manifest"]["plugins"]
+    sources = dict(zip(alpha["bundled_profiles"], alpha["bundled_profile_paths"]))
+    assert sources == {
+        "bundle_profiles/demo/plugins/alpha/demo__unknown__alpha__profiles__dev.toml": "profiles/dev.toml",
+        "bundle_profiles/demo/plugins/alpha/demo__unknown__alpha__profiles.toml": "profiles.toml",
+    }
+    assert 'bundled_profile_paths = ["profiles/dev.toml", "profiles.toml"]' in _manifest(profiles_bundle)
+
+
+def test_skip_leaves_live_profiles_alone(profiles_bundle, customized):
+    res = neko_plugin_cli.unpack(profiles_bundle, customized)
+    assert res["profiles"] == []
+    assert (customized / "alpha" / "profiles.toml").read_text() == "[default]\nlevel = 5\n"
+    assert not (customized / "alpha" / "profiles" / "dev.toml").exists()
+    assert (customized / "alpha" / "_bundle_profiles" / "bundle_profiles" / "demo").is_dir()
+
+
+def test_merge_only_adds_missing_profiles(profiles_bundle, customized):
+    res = neko_plugin_cli.unpack(profiles_bundle, customized, apply_profiles="merge")
+    assert [p["folder"] for p in res["skipped"]] == ["alpha"]
+    assert _statuses(res) == {"profiles.toml": "kept", "profiles/dev.toml": "applied"}
+    assert all(p["id"] == "alpha" for p in res["profiles"])
+    assert (customized / "alpha" / "profiles.toml").read_text() == "[default]\nlevel = 5\n"
+    assert (customized / "alpha" / "profiles" / "dev.toml").read_text() == DEV
+
+    again = neko_plugin_cli.unpack(profiles_bundle, customized, apply_profiles="merge")
+    assert _statuses(again) == {"profiles.toml": "kept", "profiles/dev.toml": "identical"}
+
+
+def test_overwrite_replaces_live_profiles(profiles_bundle, customized):
+    res = neko_plugin_cli.unpack(profiles_bundle, customized, apply_profiles="overwrite")
+    assert _statuses(res) == {"profiles.toml": "overwritten", "profiles/dev.toml": "applied"}
+    assert (customized / "alpha" / "profiles.toml").read_text() == LEVEL_1
+    assert (customized / "alpha" / "profiles" / "dev.toml").read_text() == DEV
+
+
+def test_fresh_install_reports_identical(profiles_bundle, tmp_path):
+    res = neko_plugin_cli.unpack(profiles_bundle, tmp_path / "dest", apply_profiles="overwrite")
+    assert _statuses(res) == {"profiles.toml": "identical", "profiles/dev.toml": "identical"}
+
+
+def test_bundle_without_recorded_paths(profiles_bundle, customized, tmp_path):
+    old = rewrite_manifest(
+        profiles_bundle,
+        tmp_path / "old.zip",
+        lambda t: "\n".join(l for l in t.splitlines() if not l.startswith("bundled_profile_paths")),
+    )
+    res = neko_plugin_cli.unpack(old, customized, apply_profiles="overwrite")
+    assert {p["status"] for p in res["profiles"]} == {"unknown_path"}
+    assert (customized / "alpha" / "profiles.toml").read_text() == "[default]\nlevel = 5\n"
+
+
+def test_paths_outside_the_profiles_are_refused(profiles_bundle, customized, tmp_path):
+    hostile = rewrite_manifest(
+        profiles_bundle,
+        tmp_path / "hostile.zip",
+        lambda t: t.replace('["profiles/dev.toml", "profiles.toml"]', '["../../escape.toml", "plugin.toml"]'),
+    )
+    before = (customized / "alpha" / "plugin.toml").read_text()
+    res = neko_plugin_cli.unpack(hostile, customized, apply_profiles="overwrite")
+    assert res["profiles"] == []
+    assert "skipped bundled profile with unsafe path: plugin.toml" in res["warnings"]
+    assert "skipped bundled profile with unsafe path: ../../escape.toml" in res["warnings"]
+    assert (customized / "alpha" / "plugin.toml").read_text() == before
+    assert not (tmp_path / "escape.toml").exists()
+
+
+def test_unknown_mode(profiles_bundle, tmp_path):
+    with pytest.raises(ValueError, match="unknown apply-profiles mode: all"):
+        neko_plugin_cli.unpack(profiles_bundle, tmp_path / "dest", apply_profiles="all")
@@ -0,0 +1,84 @@
+from __future__ import annotations
+
+import warnings
+
+import pytest
+
+import neko_plugin_cli
+
+
+def _add_pyproject_defaults(repo, body):
+    pyproject = repo / "pyproject.toml"
+    pyproject.write_text(pyproject.read_text() + "\n[tool.neko.bundle]\n" + body)
+
+
+@pytest.fixture
+def defaults_repo(neko_repo):
+    _add_pyproject_defaults(neko_repo, 'name = "repo-pack"\nversion = "0.9"\nauthor = "repo-team"\n')
+    return neko_repo
+
+
+def _bundle(res):
+    return res["manifest"]["bundle"]
+
+
+def test_repo_defaults_fill_missing_flags(defaults_repo, tmp_path):
+    res = neko_plugin_cli.pack(root=defaults_repo, out=tmp_path / "x.zip")
+    assert _bundle(res) == {"name": "repo-pack", "version": "0.9", "author": "repo-team"}
+    assert res["manifest"]["bundle_profiles_root"] == "bundle_profiles/repo-pack/"
+
+
+def test_precedence_cli_over_spec_over_repo(defaults_repo, tmp_path):
+    spec = tmp_path / "bundle.toml"
+    spec.write_text('[bundle]\nname = "spec-pack"\nversion = "2.0"\n')
+    res = neko_plugin_cli.pack(root=defaults_repo, spec=spec, out=tmp_path / "x.zip", bundle_name="cli-pack")
+    assert _bundle(res) == {"name": "cli-pack", "version": "2.0", "author": "repo-team"}
+
+
+def test_spec_out_template_sees_repo_defaults(defaults_repo, tmp_path):
+    spec = tmp_path / "bundle.toml"
+    spec.write_text('out = "{name}-{version}.zip"\n')
+    res = neko_plugin_cli.pack(root=defaults_repo, spec=spec)
+    assert res["path"] == str(tmp_path / "repo-pack-0.9.zip")
+
+
+def test_neko_bundle_toml_overrides_pyproject_per_field(defaults_repo, tmp_path):
+    (defaults_repo / "neko_bundle.toml").write_text('version = "1.0"\n')
+    res = neko_plugin_cli.pack(root=defaults_repo, out=tmp_path / "x.zip")
+    assert _bundle(res) == {"name": "repo-pack", "version": "1.0", "author": "repo-team"}
+
+
+def test_name_derived_from_filename_is_last_resort(neko_repo, tmp_path):
+    (neko_repo / "neko_bundle.toml").write_text('author = "someone"\n')
+    res = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "my release.zip")
+    assert _bundle(res) == {"name": "my_release", "version": None, "author": "someone"}
+
+
+def test_info_reports_defaults(defaults_repo):
+    (defaults_repo / "neko_bundle.toml").write_text('author = "file-team"\n')
+    info = neko_plugin_cli.info(root=defaults_repo)
+    assert info["bundle_defaults"] == {
+        "name": "repo-pack",
+        "version": "0.9",
+        "author": "file-team",
+        "sources": [str(defaults_repo / "pyproject.toml"), str(defaults_repo / "neko_bundle.toml")],
+    }
+
+
+def test_info_without_defaults(neko_repo):
+    info = neko_plugin_cli.info(root=neko_repo)
+    assert info["bundle_defaults"] == {"name": None, "version": None, "author": None, "sources": []}
+
+
+def test_bad_defaults(neko_repo, tmp_path):
+    _add_pyproject_defaults(neko_repo, "version = 3\nlicense = \"MIT\"\n")
+    with pytest.raises(ValueError, match="`tool.neko.bundle.version` in .*pyproject.toml must be a string, not integer"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip")
+
+    (neko_repo / "pyproject.toml").write_text(
+        (neko_repo / "pyproject.toml").read_text().replace("version = 3\n", "")
+    )
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip")
+    assert any("unknown key `tool.neko.bundle.license`" in str(w.message) for w in caught)
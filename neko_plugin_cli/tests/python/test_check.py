@@ -0,0 +1,106 @@
+from __future__ import annotations
+
+import pytest
+from conftest import make_repo, write_plugin
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def conflict_repo(tmp_path):
+    """alpha 2.0.0 is explicitly in gamma's conflict range; delta needs a missing plugin."""
+    root = make_repo(tmp_path / "conflict")
+    package = {"__init__.py": "def main():\n    pass\n"}
+    write_plugin(root, "alpha", '[plugin]\nid = "alpha"\nversion = "2.0.0"\nentry = "alpha:main"\n', package)
+    write_plugin(
+        root,
+        "gamma",
+        '[plugin]\nid = "gamma"\nversion = "1.0.0"\nentry = "gamma:main"\n\n'
+        '[[plugin.dependency]]\nid = "alpha"\nsupported = ">=1.0.0"\nconflicts = [">=2.0.0"]\n',
+        package,
+    )
+    write_plugin(
+        root,
+        "delta",
+        '[plugin]\nid = "delta"\nversion = "1.0.0"\nentry = "delta:main"\n\n'
+        '[[plugin.dependency]]\nid = "missing"\n',
+        package,
+    )
+    return root
+
+
+def test_clean_repo(neko_repo):
+    report = neko_plugin_cli.check(root=neko_repo)
+    assert report == {
+        "sdk_version": "1.2.0",
+        "plugins_checked": 2,
+        "findings": [],
+        "errors": [],
+        "warnings": [],
+        "baselined": 0,
+        "python_online": None,
+        "cache": {"cached": [], "checked": ["alpha", "beta"]},
+    }
+
+
+def test_dependency_conflict_is_reported(conflict_repo):
+    report = neko_plugin_cli.check(root=conflict_repo, deps=True)
+    assert report["plugins_checked"] == 3
+    assert len(report["errors"]) == 2
+    assert any("gamma dependency alpha version 2.0.0 hits conflicts" in e for e in report["errors"])
+    assert any("delta depends on missing plugin missing" in e for e in report["errors"])
+
+
+def test_findings_carry_code_severity_and_plugin(conflict_repo):
+    write_plugin(
+        conflict_repo,
+        "eps",
+        '[plugin]\nid = "eps"\nversion = "1.0.0"\nentry = "eps:main"\n\n[plugin.sdk]\nsupported = ">=9"\nuntested = ">=1"\n',
+        {"__init__.py": "def main():\n    pass\n"},
+    )
+    report = neko_plugin_cli.check(root=conflict_repo)
+    assert [(f["plugin_id"], f["severity"], f["code"]) for f in report["findings"]] == [
+        ("delta", "error", "dep-missing"),
+        ("eps", "warning", "sdk-untested"),
+        ("gamma", "error", "dep-conflict"),
+    ]
+    assert report["errors"] == sorted(f["message"] for f in report["findings"] if f["severity"] == "error")
+    assert report["warnings"] == ["plugin eps SDK_VERSION 1.2.0 is in untested range (>=1)"]
+
+
+def test_single_plugin(conflict_repo):
+    report = neko_plugin_cli.check(root=conflict_repo, plugin_id="alpha")
+    assert report["plugins_checked"] == 1
+    assert report["errors"] == []
+
+
+def test_id_only_skips_dependency_checks(conflict_repo):
+    write_plugin(conflict_repo, "alpha_copy", '[plugin]\nid = "alpha"\nversion = "1.0.0"\nentry = "a:main"\n')
+    report = neko_plugin_cli.check(root=conflict_repo, id=True)
+    assert len(report["errors"]) == 1
+    assert "alpha" in report["errors"][0]
+
+
+def test_python_online_report(neko_repo, tmp_path):
+    report = neko_plugin_cli.check(root=neko_repo, python_online=True, cache_dir=tmp_path / "cache")
+    online = report["python_online"]
+    assert online["enabled"] is True
+    assert online["requirements_in"].startswith(str(tmp_path / "cache"))
+    if not online["uv_found"]:
+        assert online["exit_code"] is None
+        assert any("python-online check skipped" in w for w in report["warnings"])
+
+
+def test_missing_sdk_version_raises(neko_repo):
+    (neko_repo / "plugin" / "sdk" / "version.py").unlink()
+    with pytest.raises(OSError):
+        neko_plugin_cli.check(root=neko_repo)
+
+
+def test_graph_is_added_to_the_report(conflict_repo):
+    report = neko_plugin_cli.check(root=conflict_repo, graph="mermaid")
+    assert report["graph"].startswith("graph LR\n")
+    assert '-->|"* (missing)"|' in report["graph"]
+    assert "graph" not in neko_plugin_cli.check(root=conflict_repo)
+    with pytest.raises(ValueError, match="unsupported graph format: svg"):
+        neko_plugin_cli.check(root=conflict_repo, graph="svg")
@@ -0,0 +1,77 @@
+from __future__ import annotations
+
+import json
+
+import pytest
+from conftest import make_repo, write_plugin
+
+import neko_plugin_cli
+
+
+def _write(root, plugin_id, *deps, sdk=None):
+    toml = f'[plugin]\nid = "{plugin_id}"\nversion = "1.0.0"\nentry = "{plugin_id}:main"\n'
+    if sdk:
+        toml += f'\n[plugin.sdk]\nsupported = ">=9"\nuntested = "{sdk}"\n'
+    for dep in deps:
+        toml += f'\n[[plugin.dependency]]\nid = "{dep}"\n'
+    write_plugin(root, plugin_id, toml, {"__init__.py": "def main():\n    pass\n"})
+
+
+@pytest.fixture
+def legacy(repo):
+    """alpha depends on a plugin that is gone, beta is only untested against the SDK"""
+    _write(repo, "alpha", "gone")
+    _write(repo, "beta", sdk=">=1")
+    return repo
+
+
+def test_baseline_only_lets_new_findings_fail(legacy, tmp_path):
+    baseline = tmp_path / "baseline.json"
+    written = neko_plugin_cli.check(root=legacy, baseline=baseline, write_baseline=True)
+    assert (written["errors"], written["warnings"], written["baselined"]) == ([], [], 2)
+
+    _write(legacy, "gamma", "also_gone")
+    report = neko_plugin_cli.check(root=legacy, baseline=baseline)
+    assert report["baselined"] == 2
+    assert report["errors"] == ["plugin gamma depends on missing plugin also_gone"]
+    assert report["warnings"] == []
+    assert [(f["plugin_id"], f["code"]) for f in report["findings"]] == [("gamma", "dep-missing")]
+
+
+def test_baseline_file_is_sorted_and_machine_independent(legacy, tmp_path):
+    baseline = tmp_path / "baseline.json"
+    neko_plugin_cli.check(root=legacy, baseline=baseline, write_baseline=True)
+    data = json.loads(baseline.read_text(encoding="utf-8"))
+    assert data["version"] == 1
+    assert [(e["code"], e["plugin_id"]) for e in data["findings"]] == [("dep-missing", "alpha"), ("sdk-untested", "beta")]
+    assert all(len(e["hash"]) == 16 for e in data["findings"])
+    assert str(tmp_path) not in baseline.read_text(encoding="utf-8")
+
+    # Same repo content elsewhere gives the same fingerprints
+    other = make_repo(tmp_path / "elsewhere")
+    _write(other, "beta", sdk=">=1")
+    _write(other, "alpha", "gone")
+    again = tmp_path / "again.json"
+    neko_plugin_cli.check(root=other, baseline=again, write_baseline=True)
+    assert again.read_text(encoding="utf-8") == baseline.read_text(encoding="utf-8")
+
+
+def test_changed_message_is_not_baselined(legacy, tmp_path):
+    baseline = tmp_path / "baseline.toml"
+    neko_plugin_cli.check(root=legacy, baseline=baseline, write_baseline=True)
+    assert "[[findings]]" in baseline.read_text(encoding="utf-8")
+    _write(legacy, "beta", sdk=">=1.1")
+    report = neko_plugin_cli.check(root=legacy, baseline=baseline)
+    assert report["baselined"] == 1
+    assert report["warnings"] == ["plugin beta SDK_VERSION 1.2.0 is in untested range (>=1.1)"]
+
+
+def test_baseline_errors(legacy, tmp_path):
+    with pytest.raises(ValueError, match="write_baseline needs baseline"):
+        neko_plugin_cli.check(root=legacy, write_baseline=True)
+    bad = tmp_path / "bad.json"
+    bad.write_text('{"version": 7, "findings": []}')
+    with pytest.raises(ValueError, match="unsupported baseline version 7"):
+        neko_plugin_cli.check(root=legacy, baseline=bad)
+    with pytest.raises(OSError, match="failed to read baseline"):
+        neko_plugin_cli.check(root=legacy, baseline=tmp_path / "missing.json")
@@ -0,0 +1,99 @@
+from __future__ import annotations
+
+import json
+
+import pytest
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def check(neko_repo, tmp_path):
+    def run(**kwargs):
+        kwargs.setdefault("cache_dir", tmp_path / "cache")
+        return neko_plugin_cli.check(root=neko_repo, **kwargs)
+
+    return run
+
+
+def _plugin(repo, folder):
+    return repo / "plugin" / "plugins" / folder
+
+
+def test_unchanged_repo_is_served_from_cache(check):
+    first = check()
+    assert first["cache"] == {"cached": [], "checked": ["alpha", "beta"]}
+
+    second = check()
+    assert second["cache"] == {"cached": ["alpha", "beta"], "checked": []}
+    assert second["findings"] == first["findings"]
+
+
+def test_cached_findings_are_reported_again(neko_repo, check):
+    (_plugin(neko_repo, "alpha") / "plugin.toml").write_text(
+        '[plugin]\nid = "alpha"\nversion = "1.0"\nentry = "alpha:main"\n', encoding="utf-8"
+    )
+    first = check()
+    assert "version-missing-patch" in [f["code"] for f in first["findings"]]
+    second = check()
+    assert second["cache"]["cached"] == ["alpha", "beta"]
+    assert second["findings"] == first["findings"]
+    assert second["warnings"] == first["warnings"]
+
+
+def test_touching_a_file_invalidates_only_its_plugin(neko_repo, check):
+    check()
+    (_plugin(neko_repo, "beta_dir") / "beta.py").write_text("def other():\n    pass\n", encoding="utf-8")
+
+    report = check()
+    assert report["cache"] == {"cached": ["alpha"], "checked": ["beta"]}
+    assert [f["code"] for f in report["findings"]] == ["entry-attr-missing"]
+
+
+def test_dependency_findings_key_on_the_target(neko_repo, check):
+    check()
+    toml = _plugin(neko_repo, "alpha") / "plugin.toml"
+    toml.write_text(toml.read_text(encoding="utf-8").replace('"1.0.0"', '"0.9.0"'), encoding="utf-8")
+
+    # Only alpha changed, but beta's range is evaluated against alpha's version
+    report = check(deps=True)
+    assert report["cache"]["checked"] == ["alpha", "beta"]
+    assert [(f["plugin_id"], f["code"]) for f in report["findings"]] == [("beta", "dep-unsupported")]
+
+
+def test_flags_and_sdk_version_are_part_of_the_key(neko_repo, check):
+    check()
+    assert check(toml=True)["cache"]["cached"] == []
+    (neko_repo / "plugin" / "sdk" / "version.py").write_text('SDK_VERSION = "2.0.0"\n', encoding="utf-8")
+    report = check()
+    assert report["cache"]["cached"] == []
+    assert [f["code"] for f in report["findings"]] == ["sdk-unsupported"]
+
+
+def test_py_syntax_findings_are_cached(neko_repo, check):
+    (_plugin(neko_repo, "beta_dir") / "broken.py").write_text("def f(:\n", encoding="utf-8")
+    first = check(py_syntax=True)
+    assert [f["code"] for f in first["findings"]] == ["py-syntax-error"]
+    second = check(py_syntax=True)
+    assert second["cache"]["checked"] == []
+    assert second["findings"] == first["findings"]
+
+
+def test_no_cache_neither_reads_nor_writes(tmp_path, check):
+    check(no_cache=True)
+    assert not (tmp_path / "cache" / "neko_plugin_cli" / "check_cache.json").exists()
+    check()
+    report = check(no_cache=True)
+    assert "cache" not in report
+
+
+def test_cache_drops_deleted_plugins(neko_repo, tmp_path, check):
+    import shutil
+
+    check()
+    path = tmp_path / "cache" / "neko_plugin_cli" / "check_cache.json"
+    assert len(json.loads(path.read_text())["slots"]) == 2
+    shutil.rmtree(_plugin(neko_repo, "beta_dir"))
+    (_plugin(neko_repo, "alpha") / "extra.py").write_text("x = 1\n", encoding="utf-8")
+    check()
+    assert [k.rsplit("/", 1)[-1] for k in json.loads(path.read_text())["slots"]] == ["alpha"]
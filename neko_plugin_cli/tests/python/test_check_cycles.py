@@ -0,0 +1,49 @@
+from __future__ import annotations
+
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+def _write(root, plugin_id, *deps):
+    toml = f'[plugin]\nid = "{plugin_id}"\nversion = "1.0.0"\nentry = "{plugin_id}:main"\n'
+    for dep in deps:
+        toml += f'\n[[plugin.dependency]]\nid = "{dep}"\n'
+    write_plugin(root, plugin_id, toml, {"__init__.py": "def main():\n    pass\n"})
+
+
+def test_two_cycle(repo):
+    _write(repo, "b", "a")
+    _write(repo, "a", "b")
+    report = neko_plugin_cli.check(root=repo, deps=True)
+    assert report["errors"] == ["dependency cycle: a -> b -> a"]
+
+
+def test_three_cycle_is_reported_once(repo):
+    _write(repo, "a", "b")
+    _write(repo, "b", "c")
+    _write(repo, "c", "a")
+    _write(repo, "d", "c")
+    report = neko_plugin_cli.check(root=repo, deps=True)
+    assert report["errors"] == ["dependency cycle: a -> b -> c -> a"]
+
+
+def test_diamond_is_not_a_cycle(repo):
+    _write(repo, "top", "left", "right")
+    _write(repo, "left", "bottom")
+    _write(repo, "right", "bottom")
+    _write(repo, "bottom")
+    report = neko_plugin_cli.check(root=repo, deps=True)
+    assert report["errors"] == []
+
+
+def test_self_dependency(repo):
+    _write(repo, "a", "a")
+    report = neko_plugin_cli.check(root=repo, deps=True)
+    assert report["errors"] == ["plugin a depends on itself"]
+
+
+def test_id_only_skips_cycles(repo):
+    _write(repo, "a", "b")
+    _write(repo, "b", "a")
+    assert neko_plugin_cli.check(root=repo, id=True)["errors"] == []
@@ -0,0 +1,63 @@
+from __future__ import annotations
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+def _toml(plugin_id, entry):
+    return f'[plugin]\nid = "{plugin_id}"\nversion = "1.0.0"\nentry = "{entry}"\n'
+
+
+def test_valid_entries(repo):
+    write_plugin(repo, "pkg", _toml("pkg", "pkg.core.app:Plugin"), {"core/app.py": "class Plugin:\n    pass\n"})
+    write_plugin(repo, "script", _toml("script", "src/run.py:start"), {"src/run.py": "async def start():\n    pass\n"})
+    write_plugin(repo, "reexport", _toml("reexport", "reexport:main"), {"__init__.py": "from .impl import main\n"})
+    write_plugin(repo, "bare", _toml("bare", "bare"), {"__init__.py": ""})
+    report = neko_plugin_cli.check(root=repo, entry=True)
+    assert report["plugins_checked"] == 4
+    assert (report["errors"], report["warnings"]) == ([], [])
+
+
+def test_missing_file_names_plugin_and_paths(repo):
+    write_plugin(repo, "alpha", _toml("alpha", "alpha.app:main"), {"__init__.py": ""})
+    report = neko_plugin_cli.check(root=repo, entry=True)
+    assert report["errors"] == [
+        "plugin alpha entry `alpha.app:main` points at a missing file "
+        "(looked for alpha/alpha/app.py, alpha/alpha/app/__init__.py, alpha/app.py, alpha/app/__init__.py)"
+    ]
+
+
+def test_entry_outside_the_plugin_folder_is_missing(repo):
+    write_plugin(repo, "alpha", _toml("alpha", "beta:main"), {"__init__.py": ""})
+    write_plugin(repo, "beta", _toml("beta", "beta:main"), {"__init__.py": "def main():\n    pass\n"})
+    report = neko_plugin_cli.check(root=repo, entry=True)
+    assert len(report["errors"]) == 1
+    assert report["errors"][0].startswith("plugin alpha entry `beta:main` points at a missing file")
+
+
+def test_missing_attr_is_a_warning(repo):
+    write_plugin(repo, "alpha", _toml("alpha", "alpha:main"), {"__init__.py": "def helper():\n    main_loop = 1\n"})
+    report = neko_plugin_cli.check(root=repo, entry=True)
+    assert report["errors"] == []
+    assert report["warnings"] == [
+        "plugin alpha entry `alpha:main`: no def, class, assignment or import of `main` found in alpha/__init__.py"
+    ]
+
+
+@pytest.mark.parametrize("entry", ["alpha:main:extra", "alpha-mod:main", "../alpha.py:main", "alpha:1main"])
+def test_malformed_entry(repo, entry):
+    write_plugin(repo, "alpha", _toml("alpha", entry), {"__init__.py": "def main():\n    pass\n"})
+    report = neko_plugin_cli.check(root=repo, entry=True)
+    assert report["errors"] == [
+        f"plugin alpha entry `{entry}` is not `module[:attr]` or a relative `path.py[:attr]`"
+    ]
+
+
+def test_default_check_reports_entry_once(repo):
+    write_plugin(repo, "alpha", _toml("alpha", "alpha.app:main"), {"__init__.py": ""})
+    report = neko_plugin_cli.check(root=repo)
+    assert len([e for e in report["errors"] if "entry" in e]) == 1
+    toml_only = neko_plugin_cli.check(root=repo, toml=True)
+    assert any("plugin.toml: entry" in e for e in toml_only["errors"])
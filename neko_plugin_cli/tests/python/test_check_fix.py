@@ -0,0 +1,92 @@
+from __future__ import annotations
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+ALPHA = """# alpha keeps its comments
+[plugin]
+id = "alpha"
+name = "Alpha"   # display name
+version = "1.0"  # bumped by hand
+entry = "alpha:main"
+
+[plugin.sdk]
+# works on the current SDK
+untested = ">=1.0.0"
+"""
+
+BETA = """[plugin]
+id = "beta"
+version = "0.3.0"
+entry = "beta:main"
+
+# needs alpha
+[[plugin.dependency]]
+id = "alpha"
+supported = "=1.0.0"  # pinned for now
+"""
+
+
+@pytest.fixture
+def fixable_repo(repo):
+    package = {"__init__.py": "def main():\n    pass\n"}
+    write_plugin(repo, "alpha", ALPHA, package)
+    write_plugin(repo, "beta", BETA, package)
+    return repo
+
+
+def _toml(root, folder):
+    return (root / "plugin" / "plugins" / folder / "plugin.toml").read_text(encoding="utf-8")
+
+
+def test_check_reports_the_fixable_findings(fixable_repo):
+    report = neko_plugin_cli.check(root=fixable_repo)
+    assert sorted((f["plugin_id"], f["code"]) for f in report["findings"]) == [
+        ("alpha", "sdk-supported-missing"),
+        ("alpha", "version-missing-patch"),
+        ("beta", "dep-invalid-version"),
+    ]
+    assert any("did you mean `1.0.0`?" in e for e in report["errors"])
+
+
+def test_fix_rewrites_only_the_edited_keys(fixable_repo):
+    report = neko_plugin_cli.check(root=fixable_repo, fix=True)
+    assert [(f["folder"], f["code"], f["key"], f["old"], f["new"]) for f in report["fixes"]] == [
+        ("alpha", "sdk-supported-missing", "sdk.supported", None, ">=1.2.0, <2.0.0"),
+        ("alpha", "version-missing-patch", "version", "1.0", "1.0.0"),
+    ]
+    assert _toml(fixable_repo, "alpha") == ALPHA.replace('"1.0"', '"1.0.0"') + 'supported = ">=1.2.0, <2.0.0"\n'
+    assert _toml(fixable_repo, "beta") == BETA
+
+    # alpha is 1.0.0 now, so beta's pin matches exactly and becomes fixable
+    assert [f["code"] for f in report["findings"]] == ["dep-pinned"]
+    again = neko_plugin_cli.check(root=fixable_repo, fix=True)
+    assert [(f["key"], f["old"], f["new"]) for f in again["fixes"]] == [
+        ("dependency[0].supported", "=1.0.0", "^1.0.0"),
+    ]
+    assert _toml(fixable_repo, "beta") == BETA.replace('"=1.0.0"', '"^1.0.0"')
+    assert (again["errors"], again["warnings"]) == ([], [])
+
+
+def test_dry_run_writes_nothing(fixable_repo):
+    report = neko_plugin_cli.check(root=fixable_repo, fix=True, dry_run=True)
+    assert len(report["fixes"]) == 2
+    assert _toml(fixable_repo, "alpha") == ALPHA
+    assert len(report["errors"]) == 2
+    with pytest.raises(ValueError, match="dry_run needs fix"):
+        neko_plugin_cli.check(root=fixable_repo, dry_run=True)
+
+
+def test_only_selected_checks_are_fixed(fixable_repo):
+    report = neko_plugin_cli.check(root=fixable_repo, toml=True, fix=True)
+    assert [f["code"] for f in report["fixes"]] == ["version-missing-patch"]
+    assert "supported" not in _toml(fixable_repo, "alpha")
+    assert "fixes" not in neko_plugin_cli.check(root=fixable_repo)
+
+
+def test_pin_on_another_version_is_not_rewritten(fixable_repo):
+    write_plugin(fixable_repo, "beta", BETA.replace('"=1.0.0"', '"=0.9.0"'))
+    neko_plugin_cli.check(root=fixable_repo, fix=True)
+    assert '"=0.9.0"' in _toml(fixable_repo, "beta")
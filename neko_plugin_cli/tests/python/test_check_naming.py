@@ -0,0 +1,62 @@
+from __future__ import annotations
+
+import pytest
+from conftest import finding_codes, write_plugin
+
+import neko_plugin_cli
+
+
+def _toml(plugin_id, entry=None):
+    return f'[plugin]\nid = "{plugin_id}"\nversion = "1.0.0"\nentry = "{entry or plugin_id + ":main"}"\n'
+
+
+def test_clean_repo_has_no_naming_findings(neko_repo):
+    pyproject = neko_repo / "pyproject.toml"
+    pyproject.write_text(pyproject.read_text() + "\n[tool.neko.check]\nstrict_naming = false\n")
+    assert neko_plugin_cli.check(root=neko_repo, id=True)["findings"] == []
+
+
+def test_shared_entry_module_is_reported_once(repo):
+    write_plugin(repo, "alpha", _toml("alpha", "common.loader:main"), {"common/loader.py": "def main(): ...\n"})
+    write_plugin(repo, "beta", _toml("beta", "common.loader:start"), {"common/loader.py": "def start(): ...\n"})
+    write_plugin(repo, "gamma", _toml("gamma"), {"gamma.py": "def main(): ...\n"})
+    report = neko_plugin_cli.check(root=repo, id=True)
+    assert finding_codes(report) == [("entry-duplicate", "alpha")]
+    assert report["errors"] == ["entry module `common.loader` is used by several plugins: alpha,beta"]
+
+
+def test_identical_file_entries_do_not_collide(repo):
+    for plugin_id in ["alpha", "beta"]:
+        write_plugin(repo, plugin_id, _toml(plugin_id, "main.py:run"), {"main.py": "def run(): ...\n"})
+    assert neko_plugin_cli.check(root=repo, id=True)["findings"] == []
+
+
+def test_folders_differing_only_by_case_collide(repo):
+    write_plugin(repo, "Tools", _toml("tools_upper"), {"tools_upper.py": "def main(): ...\n"})
+    write_plugin(repo, "tools", _toml("tools_lower"), {"tools_lower.py": "def main(): ...\n"})
+    if len(list((repo / "plugin" / "plugins").iterdir())) < 2:
+        pytest.skip("case-insensitive filesystem")
+    report = neko_plugin_cli.check(root=repo, id=True)
+    assert finding_codes(report) == [("folder-case-collision", "tools_upper")]
+    assert "Tools,tools differ only by case" in report["errors"][0]
+
+
+def test_strict_naming_requires_id_to_match_folder(repo):
+    write_plugin(repo, "alpha", _toml("alpha"), {"alpha.py": "def main(): ...\n"})
+    write_plugin(repo, "beta_dir", _toml("beta"), {"beta.py": "def main(): ...\n"})
+    assert neko_plugin_cli.check(root=repo)["findings"] == []
+
+    pyproject = repo / "pyproject.toml"
+    pyproject.write_text(pyproject.read_text() + "\n[tool.neko.check]\nstrict_naming = true\n")
+    report = neko_plugin_cli.check(root=repo)
+    assert finding_codes(report) == [("id-folder-mismatch", "beta")]
+    assert report["errors"] == ["plugin id beta differs from its folder name beta_dir (strict_naming)"]
+    # Only the id family runs the naming checks
+    assert neko_plugin_cli.check(root=repo, deps=True)["findings"] == []
+
+
+def test_strict_naming_must_be_a_boolean(repo):
+    pyproject = repo / "pyproject.toml"
+    pyproject.write_text(pyproject.read_text() + '\n[tool.neko.check]\nstrict_naming = "yes"\n')
+    with pytest.raises(ValueError, match="strict_naming.*must be a boolean"):
+        neko_plugin_cli.check(root=repo)
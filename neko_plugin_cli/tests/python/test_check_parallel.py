@@ -0,0 +1,47 @@
+from __future__ import annotations
+
+import time
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+PLUGINS = 500
+
+
+@pytest.fixture
+def big_repo(repo):
+    """500 plugins in a dependency chain; every tenth pins an SDK range the repo does not meet"""
+    for i in range(PLUGINS):
+        toml = f'[plugin]\nid = "p{i:03}"\nversion = "1.0.{i}"\nentry = "p{i:03}:main"\n'
+        if i % 10 == 0:
+            toml += '\n[plugin.sdk]\nsupported = ">=2.0.0"\n'
+        if i:
+            toml += f'\n[[plugin.dependency]]\nid = "p{i - 1:03}"\nsupported = ">=1.0.0"\n'
+        write_plugin(repo, f"folder_{PLUGINS - i:03}", toml, {f"p{i:03}.py": "def main():\n    pass\n"})
+    return repo
+
+
+def test_large_repo_checks_deterministically(big_repo):
+    start = time.perf_counter()
+    first = neko_plugin_cli.check(root=big_repo, no_cache=True)
+    elapsed = time.perf_counter() - start
+    print(f"check of {PLUGINS} plugins: {elapsed:.2f}s")
+    # Generous bound: this guards against accidental quadratic blowups, not machine speed
+    assert elapsed < 60
+
+    assert first["plugins_checked"] == PLUGINS
+    assert [f["plugin_id"] for f in first["findings"]] == [f"p{i:03}" for i in range(0, PLUGINS, 10)]
+    assert {f["code"] for f in first["findings"]} == {"sdk-unsupported"}
+    for _ in range(3):
+        assert neko_plugin_cli.check(root=big_repo, no_cache=True) == first
+
+
+def test_first_broken_folder_reports_the_error(repo):
+    for folder in ["c_broken", "a_broken", "b_fine"]:
+        toml = '[plugin]\nid = "x"\n' if folder == "b_fine" else "[plugin\n"
+        write_plugin(repo, folder, toml)
+    for _ in range(5):
+        with pytest.raises(ValueError, match="a_broken"):
+            neko_plugin_cli.check(root=repo, no_cache=True)
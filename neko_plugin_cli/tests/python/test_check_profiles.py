@@ -0,0 +1,70 @@
+from __future__ import annotations
+
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+TOML = '[plugin]\nid = "alpha"\nversion = "1.0.0"\nentry = "alpha:main"\n'
+
+
+def _findings(report):
+    return [(f["code"], f["severity"], f["message"]) for f in report["findings"]]
+
+
+def test_valid_profiles_pass(neko_repo):
+    write_plugin(neko_repo, "alpha", TOML, {"profiles/dev.toml": "[dev]\ndebug = true\n"})
+    assert neko_plugin_cli.check(root=neko_repo, profiles=True)["findings"] == []
+
+
+def test_broken_profile_names_file_and_line(repo):
+    write_plugin(
+        repo,
+        "alpha",
+        TOML,
+        {
+            "alpha.py": "def main(): ...\n",
+            "profiles.toml": "[default]\nlevel = 1\n",
+            "profiles/dev.toml": "[dev]\ndebug = true\nlevel = = 2\n",
+            "profiles/notes.txt": "not a profile",
+        },
+    )
+    report = neko_plugin_cli.check(root=repo, profiles=True)
+    [(code, severity, message)] = _findings(report)
+    assert (code, severity) == ("profile-invalid", "error")
+    assert message.startswith("alpha/profiles/dev.toml:3: ")
+
+
+def test_duplicate_profile_names_across_files(repo):
+    write_plugin(
+        repo,
+        "alpha",
+        TOML,
+        {
+            "profiles.toml": "[default]\nlevel = 1\n\n[dev]\ndebug = false\n",
+            "profiles/dev.toml": "[dev]\ndebug = true\n",
+        },
+    )
+    report = neko_plugin_cli.check(root=repo, profiles=True)
+    assert _findings(report) == [
+        ("profile-duplicate", "error", "profile `dev` is defined in both alpha/profiles/dev.toml and alpha/profiles.toml"),
+    ]
+    assert report["findings"][0]["plugin_id"] == "alpha"
+
+
+def test_empty_and_large_profiles_warn(repo):
+    big = "[big]\n" + "".join(f"key_{i} = {i}\n" for i in range(200))
+    write_plugin(repo, "alpha", TOML, {"profiles.toml": "# nothing yet\n", "profiles/big.toml": big})
+    report = neko_plugin_cli.check(root=repo, profiles=True, profile_warn_size="1KiB")
+    assert [(c, s) for c, s, _ in _findings(report)] == [("profile-empty", "warning"), ("profile-large", "warning")]
+    assert any(w.startswith("alpha/profiles/big.toml is 2.5 KiB (over 1.0 KiB)") for w in report["warnings"])
+
+    assert [f["code"] for f in neko_plugin_cli.check(root=repo, profiles=True)["findings"]] == ["profile-empty"]
+    assert neko_plugin_cli.check(root=repo, profiles=True, profile_warn_size=0)["warnings"] == [
+        "alpha/profiles.toml defines no profile (no [table])"
+    ]
+
+
+def test_profiles_run_by_default(repo):
+    write_plugin(repo, "alpha", TOML, {"alpha.py": "def main(): ...\n", "profiles.toml": "[default\n"})
+    assert [f["code"] for f in neko_plugin_cli.check(root=repo)["findings"]] == ["profile-invalid"]
+    assert neko_plugin_cli.check(root=repo, toml=True)["findings"] == []
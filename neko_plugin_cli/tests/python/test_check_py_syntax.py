@@ -0,0 +1,79 @@
+from __future__ import annotations
+
+import os
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+def _toml(plugin_id):
+    return f'[plugin]\nid = "{plugin_id}"\nversion = "1.0.0"\nentry = "{plugin_id}:main"\n'
+
+
+def test_clean_sources_pass(neko_repo):
+    report = neko_plugin_cli.check(root=neko_repo, py_syntax=True)
+    assert (report["errors"], report["warnings"]) == ([], [])
+
+
+def test_syntax_errors_name_file_and_line(repo):
+    write_plugin(
+        repo,
+        "alpha",
+        _toml("alpha"),
+        {
+            "__init__.py": "def main():\n    pass\n",
+            "broken.py": "x = 1\n\ndef f(:\n    pass\n",
+            "sub/also_broken.py": "if True\n    pass\n",
+            "sub/fine.py": "y = 2\n",
+        },
+    )
+    write_plugin(repo, "beta", _toml("beta"), {"__init__.py": "def main(:\n"})
+    report = neko_plugin_cli.check(root=repo, py_syntax=True)
+    codes = [(f["code"], f["plugin_id"]) for f in report["findings"]]
+    assert codes == [("py-syntax-error", "alpha")] * 2 + [("py-syntax-error", "beta")]
+    messages = [f["message"] for f in report["findings"]]
+    assert messages[0].startswith("alpha/broken.py:3: SyntaxError")
+    assert messages[1].startswith("alpha/sub/also_broken.py:1: SyntaxError")
+    assert messages[2].startswith("beta/__init__.py:1: SyntaxError")
+
+
+def test_excluded_files_are_not_compiled(repo):
+    write_plugin(
+        repo,
+        "alpha",
+        _toml("alpha"),
+        {"__init__.py": "def main():\n    pass\n", ".venv/lib/bad.py": "def (:\n", "logs/bad.py": "def (:\n"},
+    )
+    report = neko_plugin_cli.check(root=repo, py_syntax=True)
+    assert report["errors"] == []
+    assert not (repo / "plugin" / "plugins" / "alpha" / "__pycache__").exists()
+
+
+def test_single_plugin(repo):
+    write_plugin(repo, "alpha", _toml("alpha"), {"__init__.py": "def main(:\n"})
+    write_plugin(repo, "beta", _toml("beta"), {"__init__.py": "def main(:\n"})
+    report = neko_plugin_cli.check(root=repo, plugin_id="beta", py_syntax=True)
+    assert [f["plugin_id"] for f in report["findings"]] == ["beta"]
+
+
+def test_missing_interpreter_is_a_warning_unless_strict(repo, tmp_path):
+    write_plugin(repo, "alpha", _toml("alpha"), {"__init__.py": "def main(:\n"})
+    empty = tmp_path / "empty_path"
+    empty.mkdir()
+    old = os.environ["PATH"]
+    os.environ["PATH"] = str(empty)
+    try:
+        report = neko_plugin_cli.check(root=repo, py_syntax=True)
+        strict = neko_plugin_cli.check(root=repo, py_syntax=True, py_syntax_strict=True)
+    finally:
+        os.environ["PATH"] = old
+    [finding] = report["findings"]
+    assert (finding["code"], finding["severity"], finding["plugin_id"]) == ("py-syntax-no-python", "warning", None)
+    assert strict["errors"] == ["py-syntax check skipped: no python3, python or uv found"]
+
+
+def test_strict_needs_py_syntax(repo):
+    with pytest.raises(ValueError, match="py_syntax_strict needs py_syntax"):
+        neko_plugin_cli.check(root=repo, py_syntax_strict=True)
@@ -0,0 +1,119 @@
+from __future__ import annotations
+
+import os
+import stat
+import sys
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+# Stands in for `uv pip compile <in> -o <out>`: pins every requirement to the version in
+# FAKE_UV_VERSION and annotates it the way uv does
+FAKE_UV = """#!{python}
+import os, sys
+args = sys.argv[1:]
+src, out = args[2], args[args.index("-o") + 1]
+version = os.environ.get("FAKE_UV_VERSION", "1.0")
+lines = ["# This file was autogenerated by uv via the following command:", "#    uv pip compile " + src]
+for req in open(src).read().splitlines():
+    if req.startswith("-e "):
+        lines.append(req)
+        continue
+    lines.append(req.split(">")[0].split("=")[0] + "==" + version)
+    lines.append("    # via -r " + src)
+open(out, "w").write("\\n".join(lines) + "\\n")
+"""
+
+
+@pytest.fixture
+def fake_uv(tmp_path, monkeypatch):
+    bin_dir = tmp_path / "bin"
+    bin_dir.mkdir()
+    uv = bin_dir / "uv"
+    uv.write_text(FAKE_UV.format(python=sys.executable), encoding="utf-8")
+    uv.chmod(uv.stat().st_mode | stat.S_IXUSR)
+    monkeypatch.setenv("PATH", str(bin_dir) + os.pathsep + os.environ["PATH"])
+    monkeypatch.delenv("FAKE_UV_VERSION", raising=False)
+    return uv
+
+
+@pytest.fixture
+def deps_repo(neko_repo):
+    write_plugin(
+        neko_repo,
+        "alpha",
+        '[plugin]\nid = "alpha"\nname = "Alpha"\nversion = "1.0.0"\nentry = "alpha:main"\n',
+        {"pyproject.toml": '[project]\nname = "alpha"\ndependencies = ["requests>=2"]\n'},
+    )
+    return neko_repo
+
+
+def _check(repo, tmp_path, **kwargs):
+    return neko_plugin_cli.check(root=repo, python_online=True, cache_dir=tmp_path / "cache", **kwargs)
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="fake uv is a shebang script")
+def test_lock_is_written_with_inputs(fake_uv, deps_repo, tmp_path):
+    lock = deps_repo / "requirements.txt"
+    report = _check(deps_repo, tmp_path, lock=lock)
+    assert report["errors"] == []
+    info = report["python_online"]["lock"]
+    assert (info["written"], info["requirements_in"]) == (True, str(deps_repo / "requirements.in"))
+
+    text = lock.read_text(encoding="utf-8")
+    header, inputs, *body = text.splitlines()
+    assert header.startswith("# Generated by neko_plugin_cli")
+    assert inputs == "# inputs-sha256: " + info["inputs_sha256"]
+    assert body == ["-e .", "requests==1.0", "    # via -r requirements.in"]
+    # The machine-specific paths are gone, so the files can be committed
+    assert (deps_repo / "requirements.in").read_text(encoding="utf-8") == "-e .\nrequests>=2\n"
+    assert str(tmp_path) not in text
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="fake uv is a shebang script")
+def test_verify_lock_passes_until_the_resolution_changes(fake_uv, deps_repo, tmp_path, monkeypatch):
+    lock = deps_repo / "requirements.txt"
+    _check(deps_repo, tmp_path, lock=lock)
+    # Comment lines do not count
+    lock.write_text(lock.read_text(encoding="utf-8") + "# reviewed\n", encoding="utf-8")
+    written = lock.read_text(encoding="utf-8")
+
+    ok = _check(deps_repo, tmp_path, lock=lock, verify_lock=True)
+    assert ok["errors"] == []
+    assert ok["python_online"]["lock"]["written"] is False
+    assert lock.read_text(encoding="utf-8") == written
+
+    monkeypatch.setenv("FAKE_UV_VERSION", "2.0")
+    changed = _check(deps_repo, tmp_path, lock=lock, verify_lock=True)
+    [finding] = changed["findings"]
+    assert finding["code"] == "python-lock-mismatch"
+    assert "-requests==1.0, +requests==2.0" in finding["message"]
+    assert (changed["python_online"]["lock"]["removed"], changed["python_online"]["lock"]["added"]) == (
+        ["requests==1.0"],
+        ["requests==2.0"],
+    )
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="fake uv is a shebang script")
+def test_verify_lock_without_a_lockfile_fails(fake_uv, deps_repo, tmp_path):
+    report = _check(deps_repo, tmp_path, lock=deps_repo / "requirements.txt", verify_lock=True)
+    assert [f["code"] for f in report["findings"]] == ["python-lock-missing"]
+    assert not (deps_repo / "requirements.txt").exists()
+
+
+def test_lock_without_uv_is_not_written(deps_repo, tmp_path, monkeypatch):
+    empty = tmp_path / "empty_path"
+    empty.mkdir()
+    monkeypatch.setenv("PATH", str(empty))
+    report = _check(deps_repo, tmp_path, lock=deps_repo / "requirements.txt")
+    assert sorted(f["code"] for f in report["findings"]) == ["python-lock-unverified", "python-uv-missing"]
+    assert not (deps_repo / "requirements.txt").exists()
+
+
+def test_lock_arguments_are_validated(deps_repo):
+    with pytest.raises(ValueError, match="lock needs python_online"):
+        neko_plugin_cli.check(root=deps_repo, lock=deps_repo / "requirements.txt")
+    with pytest.raises(ValueError, match="verify_lock needs lock"):
+        neko_plugin_cli.check(root=deps_repo, python_online=True, verify_lock=True)
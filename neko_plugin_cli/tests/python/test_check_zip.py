@@ -0,0 +1,86 @@
+from __future__ import annotations
+
+import pytest
+from conftest import finding_codes, make_repo, pack_bundle, rewrite_zip, write_plugin
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def broken_repo(neko_repo):
+    # beta's dependency on alpha no longer resolves, gamma needs a newer SDK
+    write_plugin(
+        neko_repo,
+        "beta_dir",
+        '[plugin]\nid = "beta"\nversion = "0.3.0"\nentry = "beta:main"\n\n'
+        '[[plugin.dependency]]\nid = "alpha"\nsupported = ">=2.0.0"\n',
+    )
+    write_plugin(
+        neko_repo,
+        "gamma",
+        '[plugin]\nid = "gamma"\nversion = "1.0.0"\nentry = "gamma:main"\n\n'
+        '[plugin.sdk]\nsupported = ">=3.0.0"\n',
+        {"gamma.py": "def main(): ...\n"},
+    )
+    return neko_repo
+
+
+@pytest.fixture
+def broken_bundle(broken_repo, tmp_path):
+    return pack_bundle(broken_repo, tmp_path / "bundle.zip")
+
+
+def test_zip_matches_source_tree(broken_repo, broken_bundle, tmp_path):
+    target = make_repo(tmp_path / "target")
+    tree = neko_plugin_cli.check(root=broken_repo, id=True, deps=True, base=True, no_cache=True)
+    report = neko_plugin_cli.check(root=target, zip=broken_bundle)
+    assert report["findings"] == tree["findings"]
+    assert report["plugins_checked"] == tree["plugins_checked"] == 3
+    assert finding_codes(report) == [("dep-unsupported", "beta"), ("sdk-unsupported", "gamma")]
+    assert "cache" not in report
+
+
+def test_dependencies_resolve_against_installed_plugins(tmp_path):
+    src = make_repo(tmp_path / "src")
+    write_plugin(
+        src,
+        "beta",
+        '[plugin]\nid = "beta"\nversion = "0.3.0"\nentry = "beta:main"\n\n'
+        '[[plugin.dependency]]\nid = "alpha"\nsupported = ">=1.0.0"\n',
+        {"beta.py": "def main(): ...\n"},
+    )
+    out = tmp_path / "beta.zip"
+    neko_plugin_cli.pack(root=src, out=out)
+
+    target = make_repo(tmp_path / "target")
+    assert neko_plugin_cli.check(root=target, zip=out)["errors"]
+    write_plugin(target, "alpha", '[plugin]\nid = "alpha"\nversion = "1.1.0"\nentry = "alpha:main"\n')
+    assert neko_plugin_cli.check(root=target, zip=out)["findings"] == []
+
+
+def test_missing_plugin_toml_is_an_error(broken_bundle, tmp_path):
+    broken = tmp_path / "broken.zip"
+    rewrite_zip(broken_bundle, broken, lambda name, data: None if name == "plugins/gamma/plugin.toml" else data)
+    report = neko_plugin_cli.check(root=make_repo(tmp_path / "target"), zip=broken)
+    assert ("zip-plugin-toml-missing", "gamma") in finding_codes(report)
+    assert "plugin gamma: plugins/gamma/plugin.toml is missing from the bundle" in report["errors"]
+    assert report["plugins_checked"] == 2
+
+
+def test_manifest_version_must_match_plugin_toml(broken_bundle, tmp_path):
+    edited = tmp_path / "edited.zip"
+
+    def edit(name, data):
+        if name == "plugins/gamma/plugin.toml":
+            return data.replace(b'version = "1.0.0"', b'version = "1.0.1"')
+        return data
+
+    rewrite_zip(broken_bundle, edited, edit)
+    report = neko_plugin_cli.check(root=make_repo(tmp_path / "target"), zip=edited)
+    assert ("manifest-mismatch", "gamma") in finding_codes(report)
+    assert "plugin gamma: manifest lists version 1.0.0 but plugins/gamma/plugin.toml declares 1.0.1" in report["errors"]
+
+
+def test_zip_cannot_be_combined_with_fix(broken_repo, broken_bundle):
+    with pytest.raises(ValueError, match="zip cannot be combined"):
+        neko_plugin_cli.check(root=broken_repo, zip=broken_bundle, fix=True)
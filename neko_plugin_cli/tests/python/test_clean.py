@@ -0,0 +1,86 @@
+from __future__ import annotations
+
+import os
+
+import pytest
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def cache_dir(tmp_path):
+    """Cache dir holding what check/pack leave behind."""
+    cache = tmp_path / "cache"
+    ours = cache / "neko_plugin_cli"
+    (ours / "hash_cache").mkdir(parents=True)
+    (ours / "hash_cache" / "0123456789abcdef.json").write_text("x" * 100)
+    (ours / "check_python").mkdir()
+    (ours / "check_python" / "requirements.in").write_text("requests\n")
+    (ours / "check_cache.json").write_text("{}")
+    (cache / "other_tool.json").write_text("keep")
+    return cache
+
+
+def test_clean_tmp_dry_run_then_remove(neko_repo, tmp_path):
+    (neko_repo / "bundle.zip.tmp").write_bytes(b"z" * 10)
+    staging = neko_repo / "plugin" / "plugins" / ".neko_unpack_tmp"
+    (staging / "alpha").mkdir(parents=True)
+    (staging / "alpha" / "a.py").write_text("12345")
+    (neko_repo / ".git" / "objects").mkdir(parents=True)
+    (neko_repo / ".git" / "objects" / "pack.zip.tmp").write_text("git's own")
+    (neko_repo / "bundle.zip").write_text("keep")
+
+    report = neko_plugin_cli.clean(root=neko_repo, tmp=True, dry_run=True)
+    assert [(e["kind"], e["path"], e["bytes"]) for e in report["removed"]] == [
+        ("tmp", str(neko_repo / "bundle.zip.tmp"), 10),
+        ("tmp", str(staging), 5),
+    ]
+    assert report["bytes"] == 15 and report["dry_run"]
+    assert staging.exists()
+
+    report = neko_plugin_cli.clean(root=neko_repo, tmp=True)
+    assert report["bytes"] == 15 and not report["dry_run"]
+    assert not staging.exists() and not (neko_repo / "bundle.zip.tmp").exists()
+    assert (neko_repo / "bundle.zip").exists() and (neko_repo / ".git" / "objects" / "pack.zip.tmp").exists()
+    assert (neko_repo / "plugin" / "plugins" / "alpha" / "__init__.py").exists()
+
+
+def test_clean_cache_only_touches_own_dir(neko_repo, cache_dir):
+    report = neko_plugin_cli.clean(root=neko_repo, cache=True, cache_dir=cache_dir)
+    ours = cache_dir / "neko_plugin_cli"
+    assert [e["path"] for e in report["removed"]] == [str(ours / n) for n in ("check_cache.json", "check_python", "hash_cache")]
+    assert report["bytes"] == 100 + 9 + 2
+    assert list(ours.iterdir()) == []
+    assert (cache_dir / "other_tool.json").read_text() == "keep"
+
+
+@pytest.mark.skipif(not hasattr(os, "symlink"), reason="needs symlinks")
+def test_clean_symlinked_cache_dir(neko_repo, cache_dir, tmp_path):
+    link = tmp_path / "cache_link"
+    link.symlink_to(cache_dir, target_is_directory=True)
+    report = neko_plugin_cli.clean(root=neko_repo, cache=True, cache_dir=link)
+    assert len(report["removed"]) == 3 and report["skipped"] == []
+    assert list((cache_dir / "neko_plugin_cli").iterdir()) == []
+
+
+@pytest.mark.skipif(not hasattr(os, "symlink"), reason="needs symlinks")
+def test_clean_never_follows_symlinks_out_of_the_root(neko_repo, cache_dir, tmp_path):
+    precious = tmp_path / "precious"
+    precious.mkdir()
+    (precious / "data.txt").write_text("do not delete")
+    (cache_dir / "neko_plugin_cli" / "escape").symlink_to(precious, target_is_directory=True)
+    (precious / "x.zip.tmp").write_text("outside")
+    (neko_repo / "plugin" / "link.zip.tmp").symlink_to(precious / "x.zip.tmp")
+
+    report = neko_plugin_cli.clean(root=neko_repo, cache=True, tmp=True, cache_dir=cache_dir)
+    skipped = {s["path"]: s["reason"] for s in report["skipped"]}
+    assert str(cache_dir / "neko_plugin_cli" / "escape") in skipped
+    assert "outside" in skipped[str(cache_dir / "neko_plugin_cli" / "escape")]
+    assert (precious / "data.txt").read_text() == "do not delete"
+    assert (precious / "x.zip.tmp").exists()
+    assert (cache_dir / "neko_plugin_cli" / "escape").is_symlink()
+
+
+def test_clean_needs_a_target(neko_repo):
+    with pytest.raises(ValueError, match="nothing to clean"):
+        neko_plugin_cli.clean(root=neko_repo)
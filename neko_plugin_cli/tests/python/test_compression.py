@@ -0,0 +1,61 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+ZIP_METHODS = {
+    "deflate": zipfile.ZIP_DEFLATED,
+    "stored": zipfile.ZIP_STORED,
+    "bzip2": zipfile.ZIP_BZIP2,
+    "zstd": 93,
+}
+
+
+def _pack_or_skip(neko_repo, out, method, level=None):
+    try:
+        return neko_plugin_cli.pack(root=neko_repo, out=out, compression=method, compression_level=level, manifest_version=2)
+    except ValueError as e:
+        if "not compiled in" in str(e):
+            pytest.skip(str(e))
+        raise
+
+
+@pytest.mark.parametrize("method", ["deflate", "stored", "bzip2", "zstd"])
+def test_round_trip(neko_repo, tmp_path, method):
+    out = tmp_path / f"{method}.zip"
+    res = _pack_or_skip(neko_repo, out, method)
+    assert res["manifest"]["compression"] == method
+    assert res["manifest"]["compression_level"] is None
+    entries = zipfile.ZipFile(out).infolist()
+    assert {e.compress_type for e in entries} == {ZIP_METHODS[method]}
+
+    dest = tmp_path / "dest"
+    installed = neko_plugin_cli.unpack(out, dest)["installed"]
+    assert sorted(p["folder"] for p in installed) == ["alpha", "beta_dir"]
+    assert (dest / "alpha" / "data" / "config.json").read_text() == '{"a": 1}\n'
+    assert (dest / "beta_dir" / "beta.py").read_text() == "def main():\n    return 2\n"
+
+
+@pytest.mark.parametrize("method, level", [("deflate", 1), ("deflate", 9), ("bzip2", 9), ("zstd", 19), ("zstd", -5)])
+def test_levels_in_range(neko_repo, tmp_path, method, level):
+    out = tmp_path / "x.zip"
+    res = _pack_or_skip(neko_repo, out, method, level)
+    assert res["manifest"]["compression_level"] == level
+    neko_plugin_cli.unpack(out, tmp_path / "dest")
+
+
+@pytest.mark.parametrize("method, level", [("deflate", 0), ("deflate", 10), ("bzip2", 0), ("zstd", 23), ("stored", 1)])
+def test_levels_out_of_range(neko_repo, tmp_path, method, level):
+    out = tmp_path / "x.zip"
+    with pytest.raises(ValueError, match="compression level|does not take a level"):
+        neko_plugin_cli.pack(root=neko_repo, out=out, compression=method, compression_level=level)
+    assert not out.exists()
+    assert not out.with_suffix(".zip.tmp").exists()
+
+
+def test_unknown_method(neko_repo, tmp_path):
+    with pytest.raises(ValueError, match="unsupported compression method"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", compression="lzma")
@@ -0,0 +1,71 @@
+from __future__ import annotations
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+def _plugin(root, plugin_id, version, deps=()):
+    toml = f'[plugin]\nid = "{plugin_id}"\nversion = "{version}"\nentry = "{plugin_id}:main"\n'
+    for dep_id, supported in deps:
+        toml += f'\n[[plugin.dependency]]\nid = "{dep_id}"\nsupported = "{supported}"\n'
+    write_plugin(root, plugin_id, toml, {"__init__.py": "def main():\n    pass\n"})
+
+
+@pytest.fixture
+def diamond(repo):
+    """app -> left, right -> base -> util; right wants a base that is not installed, app also
+    needs a missing plugin, and lone has no edges at all."""
+    _plugin(repo, "app", "1.0.0", [("left", ">=1.0.0"), ("right", "^0.2"), ("ghost", ">=1.0.0")])
+    _plugin(repo, "left", "1.1.0", [("base", ">=1.0.0, <2.0.0")])
+    _plugin(repo, "right", "0.2.3", [("base", ">=2.0.0")])
+    _plugin(repo, "base", "1.4.0", [("util", "*")])
+    _plugin(repo, "util", "0.1.0")
+    _plugin(repo, "lone", "3.0.0")
+    return repo
+
+
+def test_deps_text_tree(diamond):
+    assert neko_plugin_cli.deps(root=diamond, text=True) == (
+        "app 1.0.0\n"
+        "├── left 1.1.0 (requires >=1.0.0)\n"
+        "│   └── base 1.4.0 (requires >=1.0.0, <2.0.0)\n"
+        "│       └── util 0.1.0 (requires *)\n"
+        "├── right 0.2.3 (requires ^0.2)\n"
+        "│   └── base 1.4.0 (requires >=2.0.0) [unsupported] (*)\n"
+        "└── ghost (requires >=1.0.0) [missing]\n"
+        "\n"
+        "lone 3.0.0\n"
+    )
+
+
+def test_deps_json_marks_shared_subtree(diamond):
+    app, lone = neko_plugin_cli.deps(root=diamond)
+    assert lone == {"id": "lone", "version": "3.0.0", "supported": None, "status": None, "duplicate": False, "dependencies": []}
+    left, right, ghost = app["dependencies"]
+    assert [d["status"] for d in (left, right, ghost)] == ["ok", "ok", "missing"]
+    assert left["dependencies"][0]["dependencies"][0]["id"] == "util"
+    shared = right["dependencies"][0]
+    assert (shared["id"], shared["status"], shared["duplicate"], shared["dependencies"]) == ("base", "unsupported", True, [])
+    assert ghost["version"] is None
+
+
+def test_deps_rooted_at_plugin(diamond):
+    assert neko_plugin_cli.deps("right", root=diamond, text=True) == (
+        "right 0.2.3\n"
+        "└── base 1.4.0 (requires >=2.0.0) [unsupported]\n"
+        "    └── util 0.1.0 (requires *)\n"
+    )
+    with pytest.raises(ValueError, match="plugin nope not found"):
+        neko_plugin_cli.deps("nope", root=diamond)
+
+
+def test_deps_cycle(repo):
+    _plugin(repo, "ping", "1.0.0", [("pong", "*")])
+    _plugin(repo, "pong", "1.0.0", [("ping", "*")])
+    assert neko_plugin_cli.deps(root=repo, text=True) == (
+        "ping 1.0.0\n"
+        "└── pong 1.0.0 (requires *)\n"
+        "    └── ping 1.0.0 (requires *) [cycle]\n"
+    )
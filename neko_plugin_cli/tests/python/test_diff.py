@@ -0,0 +1,106 @@
+from __future__ import annotations
+
+import shutil
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def bundles(neko_repo, tmp_path):
+    """release-1.4 (alpha 1.0.0, beta 0.3.0, delta 2.0.0) and release-1.5: alpha 1.1.0 with a file
+    added, removed and modified, delta edited in place, beta dropped and gamma new; plus v1 copies."""
+    plugins = neko_repo / "plugin" / "plugins"
+    write_plugin(neko_repo, "delta", '[plugin]\nid = "delta"\nversion = "2.0.0"\nentry = "delta:main"\n', {"__init__.py": "def main():\n    pass\n"})
+    out = {}
+    out["old"] = tmp_path / "release-1.4.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out["old"], bundle_name="release", bundle_version="1.4", manifest_version=2)
+    out["old_v1"] = tmp_path / "release-1.4-v1.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out["old_v1"], manifest_version=1)
+
+    alpha = plugins / "alpha"
+    (alpha / "plugin.toml").write_text((alpha / "plugin.toml").read_text().replace('"1.0.0"', '"1.1.0"'))
+    (alpha / "__init__.py").write_text("def main():\n    return 'a much longer body'\n")
+    (alpha / "data" / "config.json").unlink()
+    (alpha / "data" / "extra.json").write_text('{"b": 2, "c": 3}\n')
+    (plugins / "delta" / "__init__.py").write_text("def main():\n    return 1\n")
+    shutil.rmtree(plugins / "beta_dir")
+    write_plugin(neko_repo, "gamma", '[plugin]\nid = "gamma"\nversion = "0.1.0"\nentry = "gamma:main"\n', {"gamma.py": "def main():\n    pass\n"})
+    out["new"] = tmp_path / "release-1.5.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out["new"], bundle_name="release", bundle_version="1.5", manifest_version=2)
+    out["new_v1"] = tmp_path / "release-1.5-v1.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out["new_v1"], manifest_version=1)
+    return out
+
+
+def test_diff_json(bundles):
+    diff = neko_plugin_cli.diff(bundles["old"], bundles["new"])
+    assert diff["old"] == {"label": str(bundles["old"]), "bundle": {"name": "release", "version": "1.4", "author": None}}
+    assert diff["added"] == [{"id": "gamma", "version": "0.1.0"}]
+    assert diff["removed"] == [{"id": "beta", "version": "0.3.0"}]
+    assert diff["unchanged"] == []
+    alpha, delta = diff["changed"]
+    assert alpha == {
+        "id": "alpha",
+        "old_version": "1.0.0",
+        "new_version": "1.1.0",
+        "content": "modified",
+        "compared_by": "files",
+        "files": [
+            {"path": "__init__.py", "change": "modified", "old_size": 21, "new_size": 44, "bytes_delta": 23},
+            {"path": "data/config.json", "change": "removed", "old_size": 9, "new_size": None, "bytes_delta": -9},
+            {"path": "data/extra.json", "change": "added", "old_size": None, "new_size": 17, "bytes_delta": 17},
+            {"path": "plugin.toml", "change": "modified", "old_size": 120, "new_size": 120, "bytes_delta": 0},
+        ],
+        "bytes_delta": 31,
+    }
+    assert (delta["id"], delta["old_version"], delta["new_version"], delta["bytes_delta"]) == ("delta", "2.0.0", "2.0.0", 4)
+
+
+def test_diff_text_snapshot(bundles):
+    text = neko_plugin_cli.diff(bundles["old"], bundles["new"], text=True)
+    assert text.replace(str(bundles["old"].parent) + "/", "") == (
+        "--- release-1.4.zip (release 1.4)\n"
+        "+++ release-1.5.zip (release 1.5)\n"
+        "+ gamma 0.1.0\n"
+        "- beta 0.3.0\n"
+        "~ alpha 1.0.0 -> 1.1.0 (4 file(s), +31 B)\n"
+        "    M __init__.py (21 B -> 44 B, +23 B)\n"
+        "    - data/config.json (-9 B)\n"
+        "    + data/extra.json (+17 B)\n"
+        "    M plugin.toml (120 B -> 120 B, +0 B)\n"
+        "~ delta 2.0.0 (1 file(s), +4 B)\n"
+        "    M __init__.py (21 B -> 25 B, +4 B)\n"
+    )
+
+
+def test_v1_manifests_compare_folder_hashes(bundles):
+    diff = neko_plugin_cli.diff(bundles["old_v1"], bundles["new_v1"])
+    assert [(p["id"], p["content"], p["compared_by"], p["files"], p["bytes_delta"]) for p in diff["changed"]] == [
+        ("alpha", "modified", "md5", [], None),
+        ("delta", "modified", "md5", [], None),
+    ]
+    same = neko_plugin_cli.diff(bundles["old_v1"], bundles["old"])
+    assert same["changed"] == [] and same["unchanged"] == ["alpha", "beta", "delta"]
+    assert "= 3 unchanged plugin(s)" in neko_plugin_cli.diff(bundles["old_v1"], bundles["old"], text=True)
+
+
+def test_against_installed(neko_repo, bundles):
+    diff = neko_plugin_cli.diff(bundles["old"], against_installed=True, root=neko_repo)
+    assert diff["old"] == {"label": str(neko_repo / "plugin" / "plugins"), "bundle": None}
+    # beta is not installed anymore, so installing the old bundle would add it back; gamma is
+    # not in the bundle and is left out
+    assert diff["added"] == [{"id": "beta", "version": "0.3.0"}]
+    assert diff["removed"] == []
+    assert [(p["id"], p["old_version"], p["new_version"], p["bytes_delta"]) for p in diff["changed"]] == [
+        ("alpha", "1.1.0", "1.0.0", -31),
+        ("delta", "2.0.0", "2.0.0", -4),
+    ]
+    assert neko_plugin_cli.diff(bundles["new"], against_installed=True, root=neko_repo)["unchanged"] == ["alpha", "delta", "gamma"]
+    # v1 bundles fall back to hashing the installed folders
+    v1 = neko_plugin_cli.diff(bundles["new_v1"], against_installed=True, root=neko_repo)
+    assert v1["changed"] == [] and v1["unchanged"] == ["alpha", "delta", "gamma"]
+    with pytest.raises(ValueError, match="diff needs new or against_installed=True"):
+        neko_plugin_cli.diff(bundles["old"])
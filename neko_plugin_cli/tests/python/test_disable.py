@@ -0,0 +1,75 @@
+from __future__ import annotations
+
+import warnings
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+def _packed_ids(result):
+    return sorted(p["id"] for p in result["manifest"]["plugins"])
+
+
+def test_disable_and_enable_round_trip(neko_repo):
+    state = neko_repo / "plugin" / "plugins" / ".disabled.toml"
+    assert neko_plugin_cli.disable(["alpha"], root=neko_repo) == {"disabled": True, "changed": ["alpha"], "unchanged": []}
+    assert 'disabled = ["alpha"]' in state.read_text()
+    assert (neko_repo / "plugin" / "plugins" / "alpha" / "plugin.toml").is_file()
+    assert neko_plugin_cli.disable(["alpha"], root=neko_repo)["unchanged"] == ["alpha"]
+
+    plugins = {p["id"]: p for p in neko_plugin_cli.list_plugins(root=neko_repo)}
+    assert plugins["alpha"]["disabled"] is True
+    assert "disabled" not in plugins["beta"]
+    info = neko_plugin_cli.info(root=neko_repo, format="text")
+    assert "- alpha v1.0.0 (alpha:main) [disabled]" in info
+    assert "- beta v0.3.0 (beta:main)\n" in info
+
+    assert neko_plugin_cli.enable(["alpha"], root=neko_repo)["changed"] == ["alpha"]
+    assert not state.exists()
+    with pytest.raises(ValueError, match="plugin nope not found"):
+        neko_plugin_cli.disable(["nope"], root=neko_repo)
+
+
+def test_enable_clears_ids_of_removed_plugins(neko_repo):
+    neko_plugin_cli.disable(["beta"], root=neko_repo)
+    neko_plugin_cli.remove(["beta"], root=neko_repo)
+    assert neko_plugin_cli.enable(["beta"], root=neko_repo)["changed"] == ["beta"]
+    with pytest.raises(ValueError, match="plugin beta not found"):
+        neko_plugin_cli.enable(["beta"], root=neko_repo)
+
+
+def test_pack_skips_disabled_plugins(neko_repo, tmp_path):
+    write_plugin(neko_repo, "gamma", '[plugin]\nid = "gamma"\nversion = "0.1.0"\nentry = "gamma:main"\n', {"gamma.py": ""})
+    neko_plugin_cli.disable(["gamma"], root=neko_repo)
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        result = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "all.zip")
+    assert _packed_ids(result) == ["alpha", "beta"]
+    assert any("skipping disabled plugin gamma" in str(w.message) for w in caught)
+
+    result = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "with.zip", include_disabled=True)
+    assert _packed_ids(result) == ["alpha", "beta", "gamma"]
+    with pytest.raises(ValueError, match="plugin gamma is disabled"):
+        neko_plugin_cli.pack(root=neko_repo, plugin_ids=["gamma"], out=tmp_path / "gamma.zip")
+    result = neko_plugin_cli.pack(root=neko_repo, plugin_ids=["gamma"], out=tmp_path / "gamma.zip", include_disabled=True)
+    assert _packed_ids(result) == ["gamma"]
+
+
+def test_pack_with_deps_leaves_out_a_disabled_dependency(neko_repo, tmp_path):
+    neko_plugin_cli.disable(["alpha"], root=neko_repo)
+    with warnings.catch_warnings():
+        warnings.simplefilter("ignore")
+        result = neko_plugin_cli.pack(root=neko_repo, plugin_ids=["beta"], with_deps=True, out=tmp_path / "beta.zip")
+    assert _packed_ids(result) == ["beta"]
+
+
+def test_check_skip_disabled(neko_repo):
+    write_plugin(neko_repo, "broken", '[plugin]\nid = "broken"\nversion = "1.0.0"\nentry = "broken:main"\n\n'
+                 '[[plugin.dependency]]\nid = "missing"\n', {"broken.py": "def main():\n    pass\n"})
+    neko_plugin_cli.disable(["broken", "alpha"], root=neko_repo)
+    assert neko_plugin_cli.check(root=neko_repo)["errors"]
+    report = neko_plugin_cli.check(root=neko_repo, skip_disabled=True)
+    # beta still resolves its dependency on the disabled alpha
+    assert (report["plugins_checked"], report["errors"], report["warnings"]) == (1, [], [])
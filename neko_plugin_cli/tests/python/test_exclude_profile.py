@@ -0,0 +1,84 @@
+from __future__ import annotations
+
+import pytest
+from conftest import alpha_hash, alpha_names
+
+import neko_plugin_cli
+
+EXTRA_FILES = {
+    "tests/test_alpha.py": "def test(): pass\n",
+    "docs/guide.txt": "guide\n",
+    "README.md": "# alpha\n",
+    "examples/demo.py": "print('demo')\n",
+    ".pytest_cache/v/cache": "{}\n",
+    "node_modules/lib.js": "x\n",
+}
+
+
+@pytest.fixture
+def extra_files_repo(neko_repo):
+    alpha = neko_repo / "plugin" / "plugins" / "alpha"
+    for rel, text in EXTRA_FILES.items():
+        (alpha / rel).parent.mkdir(parents=True, exist_ok=True)
+        (alpha / rel).write_text(text)
+    return neko_repo
+
+
+def test_release_drops_tests_while_dev_keeps_them(extra_files_repo, tmp_path):
+    neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "dev.zip", exclude_profile="dev")
+    neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "release.zip", exclude_profile="release")
+    dev = alpha_names(tmp_path / "dev.zip")
+    release = alpha_names(tmp_path / "release.zip")
+
+    assert {"tests/test_alpha.py", "docs/guide.txt", "README.md", "examples/demo.py"} <= dev
+    assert ".pytest_cache/v/cache" not in dev and "node_modules/lib.js" not in dev
+    assert release.isdisjoint(EXTRA_FILES)
+    assert {"__init__.py", "data/config.json"} <= release
+
+
+def test_minimal_is_the_default(extra_files_repo, tmp_path):
+    default = neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "a.zip")
+    minimal = neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "b.zip", exclude_profile="minimal")
+    assert alpha_names(tmp_path / "a.zip") == alpha_names(tmp_path / "b.zip")
+    assert set(EXTRA_FILES) <= alpha_names(tmp_path / "a.zip")
+    assert "__pycache__/junk.pyc" not in alpha_names(tmp_path / "a.zip")
+    assert alpha_hash(default) == alpha_hash(minimal)
+
+
+def test_folder_hash_follows_the_profile(extra_files_repo, tmp_path):
+    alpha = extra_files_repo / "plugin" / "plugins" / "alpha"
+    release = neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "a.zip", exclude_profile="release")
+    dev = neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "b.zip", exclude_profile="dev")
+    assert alpha_hash(release) != alpha_hash(dev)
+
+    (alpha / "tests" / "test_alpha.py").write_text("def test(): assert False\n")
+    again = neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "c.zip", exclude_profile="release")
+    assert alpha_hash(again) == alpha_hash(release)
+    changed = neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "d.zip", exclude_profile="dev")
+    assert alpha_hash(changed) != alpha_hash(dev)
+
+
+def test_composes_with_excludes_and_nekopackignore(extra_files_repo, tmp_path):
+    alpha = extra_files_repo / "plugin" / "plugins" / "alpha"
+    (alpha / ".nekopackignore").write_text("data/\n")
+    out = tmp_path / "x.zip"
+    neko_plugin_cli.pack(root=extra_files_repo, out=out, exclude_profile="dev", excludes=["**/*.md"])
+    names = alpha_names(out)
+    assert "tests/test_alpha.py" in names
+    assert "README.md" not in names
+    assert not any(n.startswith("data/") for n in names)
+
+
+def test_spec_profile_and_override(extra_files_repo, tmp_path):
+    spec = tmp_path / "bundle.toml"
+    spec.write_text('exclude_profile = "release"\n')
+    neko_plugin_cli.pack(root=extra_files_repo, spec=spec, out=tmp_path / "a.zip")
+    assert "tests/test_alpha.py" not in alpha_names(tmp_path / "a.zip")
+
+    neko_plugin_cli.pack(root=extra_files_repo, spec=spec, out=tmp_path / "b.zip", exclude_profile="dev")
+    assert "tests/test_alpha.py" in alpha_names(tmp_path / "b.zip")
+
+
+def test_unknown_profile_rejected(extra_files_repo, tmp_path):
+    with pytest.raises(ValueError, match=r"unknown exclude profile: tiny \(expected minimal, dev or release\)"):
+        neko_plugin_cli.pack(root=extra_files_repo, out=tmp_path / "x.zip", exclude_profile="tiny")
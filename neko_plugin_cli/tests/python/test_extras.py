@@ -0,0 +1,140 @@
+from __future__ import annotations
+
+import hashlib
+import tomllib
+import zipfile
+
+import pytest
+from conftest import pack_bundle, rewrite_zip
+
+import neko_plugin_cli
+
+LICENSE = "MIT License\n"
+NOTES = "## 1.0\n- first release\n"
+
+
+@pytest.fixture
+def extras_repo(neko_repo):
+    (neko_repo / "LICENSE").write_text(LICENSE)
+    (neko_repo / "docs").mkdir()
+    (neko_repo / "docs" / "notes.md").write_text(NOTES)
+    return neko_repo
+
+
+@pytest.fixture
+def extras_bundle(extras_repo, tmp_path):
+    return pack_bundle(extras_repo, tmp_path / "bundle.zip", include_root=["LICENSE", "docs/notes.md:RELEASE_NOTES.md"])
+
+
+@pytest.mark.parametrize("manifest_version", [1, 2])
+def test_extras_are_packed_and_listed(extras_repo, tmp_path, manifest_version):
+    out = tmp_path / "bundle.zip"
+    res = neko_plugin_cli.pack(
+        root=extras_repo,
+        out=out,
+        manifest_version=manifest_version,
+        include_root=["LICENSE", "docs/notes.md:RELEASE_NOTES.md"],
+    )
+
+    with zipfile.ZipFile(out) as z:
+        assert z.read("extras/LICENSE").decode() == LICENSE
+        assert z.read("extras/RELEASE_NOTES.md").decode() == NOTES
+        manifest = tomllib.loads(z.read("manifest.toml").decode())
+    assert manifest["extras"] == res["manifest"]["extras"]
+    assert manifest["extras"] == [
+        {"path": "LICENSE", "size": len(LICENSE), "sha256": hashlib.sha256(LICENSE.encode()).hexdigest()},
+        {"path": "RELEASE_NOTES.md", "size": len(NOTES), "sha256": hashlib.sha256(NOTES.encode()).hexdigest()},
+    ]
+    neko_plugin_cli.verify_bundle(out)
+
+
+def test_bundle_without_extras_has_no_extras_key(neko_repo, tmp_path):
+    res = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "bundle.zip")
+    assert "extras" not in res["manifest"]
+
+
+@pytest.mark.parametrize("mapping", ["LICENSE:../LICENSE", "LICENSE:/etc/LICENSE", "LICENSE:a/./b", "LICENSE:a\\b", "LICENSE:"])
+def test_traversal_in_zip_path_rejected(extras_repo, tmp_path, mapping):
+    with pytest.raises(ValueError, match="must be a relative path"):
+        neko_plugin_cli.pack(root=extras_repo, out=tmp_path / "x.zip", include_root=[mapping])
+
+
+def test_missing_and_duplicate_extras_rejected(extras_repo, tmp_path):
+    with pytest.raises(ValueError, match="is not a file"):
+        neko_plugin_cli.pack(root=extras_repo, out=tmp_path / "x.zip", include_root=["CHANGES.md"])
+    with pytest.raises(ValueError, match="extras/LICENSE is already taken"):
+        neko_plugin_cli.pack(root=extras_repo, out=tmp_path / "x.zip", include_root=["LICENSE", "docs/notes.md:LICENSE"])
+
+
+def test_spec_include_root(extras_repo, tmp_path):
+    spec = tmp_path / "bundle.toml"
+    spec.write_text('include_root = ["LICENSE"]\n')
+    res = neko_plugin_cli.pack(
+        root=extras_repo, spec=spec, out=tmp_path / "x.zip", include_root=["docs/notes.md:RELEASE_NOTES.md"]
+    )
+    assert [e["path"] for e in res["manifest"]["extras"]] == ["LICENSE", "RELEASE_NOTES.md"]
+
+
+def test_dry_run_lists_extras(extras_repo, tmp_path):
+    res = neko_plugin_cli.pack(root=extras_repo, out=tmp_path / "x.zip", include_root=["LICENSE"], dry_run=True)
+    assert [e["zip_path"] for e in res["extras"]] == ["extras/LICENSE"]
+    assert not (tmp_path / "x.zip").exists()
+
+
+def test_unpack_ignores_extras_by_default(extras_bundle, tmp_path):
+    res = neko_plugin_cli.unpack(extras_bundle, tmp_path / "plugins")
+    assert res["extras_installed"] == []
+    assert not (tmp_path / "plugins" / "LICENSE").exists()
+
+
+def test_unpack_extras_never_overwrites_without_force(extras_bundle, tmp_path):
+    dest = tmp_path / "plugins"
+    extras = tmp_path / "extras"
+    res = neko_plugin_cli.unpack(extras_bundle, dest, extras_dest=extras)
+    assert [(e["path"], e["reason"]) for e in res["extras_installed"]] == [
+        ("LICENSE", "installed"),
+        ("RELEASE_NOTES.md", "installed"),
+    ]
+    assert (extras / "LICENSE").read_text() == LICENSE
+
+    again = neko_plugin_cli.unpack(extras_bundle, dest, extras_dest=extras)
+    assert {e["reason"] for e in again["extras_skipped"]} == {"identical (sha256 match)"}
+
+    (extras / "LICENSE").write_text("local edits\n")
+    kept = neko_plugin_cli.unpack(extras_bundle, dest, extras_dest=extras)
+    [skipped] = [e for e in kept["extras_skipped"] if e["path"] == "LICENSE"]
+    assert skipped["reason"] == "differs from existing (use --force to overwrite)"
+    assert (extras / "LICENSE").read_text() == "local edits\n"
+
+    forced = neko_plugin_cli.unpack(extras_bundle, dest, force=True, extras_dest=extras)
+    assert [e["reason"] for e in forced["extras_installed"]] == ["overwritten (--force)"]
+    assert (extras / "LICENSE").read_text() == LICENSE
+
+
+def test_tampered_extra_is_rejected(extras_bundle, tmp_path):
+    bad = tmp_path / "bad.zip"
+    rewrite_zip(extras_bundle, bad, lambda n, d: (n, b"GPL\n" if n == "extras/LICENSE" else d))
+    with pytest.raises(ValueError, match="extras/LICENSE does not match its manifest checksum"):
+        neko_plugin_cli.verify_bundle(bad)
+    extras = tmp_path / "extras"
+    with pytest.raises(ValueError, match="checksum mismatch for"):
+        neko_plugin_cli.unpack(bad, tmp_path / "plugins", extras_dest=extras)
+    assert not (extras / "LICENSE").exists()
+
+
+def test_traversal_in_manifest_is_skipped(extras_bundle, tmp_path):
+    evil = tmp_path / "evil.zip"
+
+    def edit(name, data):
+        if name == "manifest.toml":
+            return name, data.replace(b'path = "LICENSE"', b'path = "../LICENSE"')
+        if name == "extras/LICENSE":
+            return "extras/../LICENSE", data
+        return name, data
+
+    rewrite_zip(extras_bundle, evil, edit)
+    extras = tmp_path / "nested" / "extras"
+    res = neko_plugin_cli.unpack(evil, tmp_path / "plugins", extras_dest=extras)
+    assert "skipped unsafe extras path in manifest: ../LICENSE" in res["warnings"]
+    assert not (tmp_path / "nested" / "LICENSE").exists()
+    assert [e["path"] for e in res["extras_installed"]] == ["RELEASE_NOTES.md"]
@@ -0,0 +1,113 @@
+from __future__ import annotations
+
+import os
+import time
+from pathlib import Path
+
+import pytest
+
+import neko_plugin_cli
+
+# The cache ignores files modified within the last couple of seconds, so fixtures backdate them
+OLD = time.time() - 3600
+
+
+def _backdate(root, when=OLD):
+    for dirpath, _, names in os.walk(root):
+        for name in names:
+            os.utime(Path(dirpath) / name, (when, when))
+
+
+@pytest.fixture
+def backdated_repo(neko_repo):
+    _backdate(neko_repo)
+    return neko_repo
+
+
+def _pack_cached(repo, tmp_path, name, **kwargs):
+    kwargs.setdefault("cache_dir", tmp_path / "cache")
+    return neko_plugin_cli.pack(root=repo, out=tmp_path / name, **kwargs)
+
+
+def _hashed(res):
+    return {Path(p).resolve() for p in res["hash_cache"]["hashed"]}
+
+
+def _plugin_files(repo, folder):
+    root = repo / "plugin" / "plugins" / folder
+    return {p.resolve() for p in root.rglob("*") if p.is_file() and "__pycache__" not in p.parts}
+
+
+def _hashes(res):
+    return {p["id"]: p["hash"] for p in res["manifest"]["plugins"]}
+
+
+def test_unchanged_repo_reads_nothing(backdated_repo, tmp_path):
+    first = _pack_cached(backdated_repo, tmp_path, "a.zip", manifest_version=2)
+    assert _hashed(first) == _plugin_files(backdated_repo, "alpha") | _plugin_files(backdated_repo, "beta_dir")
+
+    second = _pack_cached(backdated_repo, tmp_path, "b.zip", manifest_version=2)
+    assert second["hash_cache"]["hashed"] == []
+    assert second["hash_cache"]["reused"] > 0
+    assert _hashes(second) == _hashes(first)
+    assert [p["files"] for p in second["manifest"]["plugins"]] == [p["files"] for p in first["manifest"]["plugins"]]
+
+
+def test_touched_file_rehashes_only_its_plugin(backdated_repo, tmp_path):
+    first = _pack_cached(backdated_repo, tmp_path, "a.zip")
+    beta = backdated_repo / "plugin" / "plugins" / "beta_dir" / "beta.py"
+    beta.write_text("def main():\n    return 3\n")
+    _backdate(beta.parent, OLD + 60)
+
+    second = _pack_cached(backdated_repo, tmp_path, "b.zip")
+    assert _hashed(second) == _plugin_files(backdated_repo, "beta_dir")
+    assert _hashes(second)["alpha"] == _hashes(first)["alpha"]
+    assert _hashes(second)["beta"] != _hashes(first)["beta"]
+    assert _hashes(second) == _hashes(_pack_cached(backdated_repo, tmp_path, "c.zip", no_hash_cache=True))
+
+
+def test_file_checksums_rehash_only_the_touched_file(backdated_repo, tmp_path):
+    for name in ["LICENSE", "NOTICE"]:
+        (backdated_repo / name).write_text(f"{name}\n")
+        os.utime(backdated_repo / name, (OLD, OLD))
+    common = dict(include_root=["LICENSE", "NOTICE"], no_md5=True)
+    _pack_cached(backdated_repo, tmp_path, "a.zip", **common)
+    (backdated_repo / "LICENSE").write_text("MIT\n")
+    os.utime(backdated_repo / "LICENSE", (OLD + 60, OLD + 60))
+
+    # Payload checksums are computed while copying, so only the extras are hashed separately
+    second = _pack_cached(backdated_repo, tmp_path, "b.zip", **common)
+    assert _hashed(second) == {(backdated_repo / "LICENSE").resolve()}
+    fresh = _pack_cached(backdated_repo, tmp_path, "c.zip", no_hash_cache=True, **common)
+    assert second["manifest"]["extras"] == fresh["manifest"]["extras"]
+
+
+def test_same_size_new_mtime_is_rehashed(backdated_repo, tmp_path):
+    first = _pack_cached(backdated_repo, tmp_path, "a.zip")
+    config = backdated_repo / "plugin" / "plugins" / "alpha" / "data" / "config.json"
+    config.write_text('{"a": 9}\n')
+    os.utime(config, (OLD + 1, OLD + 1))
+
+    second = _pack_cached(backdated_repo, tmp_path, "b.zip")
+    assert config.resolve() in _hashed(second)
+    assert _hashes(second)["alpha"] != _hashes(first)["alpha"]
+
+
+def test_recently_modified_files_are_not_cached(neko_repo, tmp_path):
+    _pack_cached(neko_repo, tmp_path, "a.zip")
+    second = _pack_cached(neko_repo, tmp_path, "b.zip")
+    assert _hashed(second) == _plugin_files(neko_repo, "alpha") | _plugin_files(neko_repo, "beta_dir")
+
+
+def test_different_excludes_use_their_own_cache(backdated_repo, tmp_path):
+    _pack_cached(backdated_repo, tmp_path, "a.zip")
+    res = _pack_cached(backdated_repo, tmp_path, "b.zip", excludes=["**/*.json"])
+    assert res["hash_cache"]["reused"] == 0
+    assert _hashes(res) == _hashes(_pack_cached(backdated_repo, tmp_path, "c.zip", excludes=["**/*.json"], no_hash_cache=True))
+    assert len(list((tmp_path / "cache").rglob("*.json"))) == 2
+
+
+def test_no_hash_cache(backdated_repo, tmp_path):
+    res = _pack_cached(backdated_repo, tmp_path, "a.zip", no_hash_cache=True)
+    assert "hash_cache" not in res
+    assert not (tmp_path / "cache").exists()
@@ -0,0 +1,123 @@
+from __future__ import annotations
+
+import json
+
+import pytest
+from conftest import make_repo, write_plugin
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def cat_repo(neko_repo):
+    write_plugin(neko_repo, "猫", '[plugin]\nid = "neko_cat"\nversion = "10.0.0"\nentry = "猫:main"\n')
+    return neko_repo
+
+
+def test_table_aligns_columns_by_display_width(cat_repo):
+    assert neko_plugin_cli.info(root=cat_repo, format="table") == (
+        "ID        VERSION  ENTRY       FOLDER\n"
+        "alpha     1.0.0    alpha:main  alpha\n"
+        "beta      0.3.0    beta:main   beta_dir\n"
+        "neko_cat  10.0.0   猫:main     猫\n"
+    )
+
+
+def test_columns_pick_fields_and_order(cat_repo):
+    assert neko_plugin_cli.info(root=cat_repo, format="table", columns=["folder", "id"]) == (
+        "FOLDER    ID\nalpha     alpha\nbeta_dir  beta\n猫        neko_cat\n"
+    )
+    info = neko_plugin_cli.info(root=cat_repo, columns=["id", "version"])
+    assert info["plugins"][0] == {"id": "alpha", "version": "1.0.0"}
+    text = neko_plugin_cli.info(root=cat_repo, format="text", columns=["id", "version"])
+    assert "- alpha 1.0.0\n" in text
+
+
+def test_json_format_is_the_info_output(cat_repo):
+    info = neko_plugin_cli.info(root=cat_repo)
+    assert json.loads(neko_plugin_cli.info(root=cat_repo, format="json")) == info
+    assert info["plugins"][1] == {"id": "beta", "version": "0.3.0", "entry": "beta:main", "folder": "beta_dir"}
+    assert info["neko_version"] == "0.5.0"
+    assert "- alpha v1.0.0 (alpha:main)\n" in neko_plugin_cli.info(root=cat_repo, format="text")
+
+
+def test_yaml_parses_back_to_the_same_data(cat_repo):
+    yaml = pytest.importorskip("yaml")
+    assert yaml.safe_load(neko_plugin_cli.info(root=cat_repo, format="yaml")) == neko_plugin_cli.info(root=cat_repo)
+    rows = yaml.safe_load(neko_plugin_cli.info(root=cat_repo, format="yaml", columns=["id"]))["plugins"]
+    assert rows == [{"id": "alpha"}, {"id": "beta"}, {"id": "neko_cat"}]
+
+
+def test_unknown_column_is_rejected(cat_repo):
+    with pytest.raises(ValueError, match="unknown info column: bytes"):
+        neko_plugin_cli.info(root=cat_repo, columns=["bytes"])
+
+
+@pytest.fixture
+def sized_repo(tmp_path):
+    root = make_repo(tmp_path / "sized")
+    write_plugin(root, "small", '[plugin]\nid = "small"\n')  # 22 bytes
+    big = {"big.py": "x" * 1000, "__pycache__/big.pyc": "y" * 300}
+    write_plugin(root, "big", '[plugin]\nid = "big"\n', big)  # 20 + 1000 bytes, 300 excluded
+    return root
+
+
+def test_sizes_count_files_and_excluded_bytes(sized_repo):
+    info = neko_plugin_cli.info(root=sized_repo, sizes=True)
+    big, small = info["plugins"]
+    assert big == {
+        "id": "big", "version": "unknown", "entry": "", "folder": "big",
+        "file_count": 2, "total_bytes": 1020, "excluded_bytes": 300,
+    }
+    assert (small["file_count"], small["total_bytes"], small["excluded_bytes"]) == (1, 22, 0)
+    assert info["totals"] == {"file_count": 3, "total_bytes": 1042, "excluded_bytes": 300}
+    assert "totals" not in neko_plugin_cli.info(root=sized_repo)
+
+
+def test_sort_by_size_in_every_format(sized_repo):
+    write_plugin(sized_repo, "aaa", '[plugin]\nid = "aaa"\n', {"a.py": "z" * 100})
+    ids = [p["id"] for p in neko_plugin_cli.info(root=sized_repo, sort="size")["plugins"]]
+    assert ids == ["big", "aaa", "small"]
+    assert neko_plugin_cli.info(root=sized_repo, format="table", sort="size", columns=["id", "files", "size", "excluded"]) == (
+        "ID     FILES  SIZE     EXCLUDED\n"
+        "big    2      1020 B   300 B\n"
+        "aaa    2      120 B    0 B\n"
+        "small  1      22 B     0 B\n"
+        "TOTAL  5      1.1 KiB  300 B\n"
+    )
+    text = neko_plugin_cli.info(root=sized_repo, format="text", sizes=True)
+    assert "- big vunknown (), 2 file(s), 1020 B (300 B excluded)\n" in text
+    assert "Total: 5 file(s), 1.1 KiB (300 B excluded)\n" in text
+
+
+@pytest.fixture
+def compat_repo(neko_repo):
+    def sdk_plugin(plugin_id, sdk):
+        toml = f'[plugin]\nid = "{plugin_id}"\nversion = "1.0.0"\nentry = "{plugin_id}:main"\n\n[plugin.sdk]\n{sdk}'
+        write_plugin(neko_repo, plugin_id, toml, {"__init__.py": "def main():\n    pass\n"})
+
+    sdk_plugin("eps", 'supported = ">=9"\nuntested = ">=1"\n')
+    sdk_plugin("zeta", 'supported = ">=9"\n')
+    sdk_plugin("eta", 'supported = ">=1"\nconflicts = ["=1.2.0"]\n')
+    return neko_repo
+
+
+def test_compat_agrees_with_check(compat_repo):
+    info = neko_plugin_cli.info(root=compat_repo, compat=True)
+    assert info["sdk_version"] == "1.2.0"
+    compat = {p["id"]: p["sdk_compat"] for p in info["plugins"]}
+    assert compat == {"alpha": "ok", "beta": "ok", "eps": "untested", "eta": "conflict", "zeta": "unsupported"}
+
+    findings = neko_plugin_cli.check(root=compat_repo, base=True, no_cache=True)["findings"]
+    from_check = {f["plugin_id"]: f["code"].removeprefix("sdk-") for f in findings}
+    assert from_check == {k: v for k, v in compat.items() if v != "ok"}
+    assert "sdk_compat" not in neko_plugin_cli.info(root=compat_repo)["plugins"][0]
+
+
+def test_compat_column_in_table_and_text(compat_repo):
+    assert neko_plugin_cli.info(root=compat_repo, format="table", compat=True, columns=["id", "compat"]) == (
+        "ID     COMPAT\nalpha  ok\nbeta   ok\neps    untested\neta    conflict\nzeta   unsupported\n"
+    )
+    text = neko_plugin_cli.info(root=compat_repo, format="text", compat=True)
+    assert "SDK version: 1.2.0\n" in text
+    assert "- zeta v1.0.0 (zeta:main) [unsupported]\n" in text
@@ -0,0 +1,59 @@
+from __future__ import annotations
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def catalog_repo(repo):
+    plugins = [
+        ("chat", "1.2.0", "chat:main"),
+        ("chat_bridge", "2.0.0", "chat.bridge:run"),
+        ("mychat", "1.5.0", "mychat:main"),
+        ("weather", "0.3", "weather.api:main"),
+        ("draft", "next", "draft:main"),
+    ]
+    for plugin_id, version, entry in plugins:
+        write_plugin(repo, plugin_id, f'[plugin]\nid = "{plugin_id}"\nversion = "{version}"\nentry = "{entry}"\n')
+    return repo
+
+
+def _ids(root, **filters):
+    return [p["id"] for p in neko_plugin_cli.list_plugins(root=root, **filters)]
+
+
+def test_no_filter_lists_everything(catalog_repo):
+    assert _ids(catalog_repo) == ["chat", "chat_bridge", "draft", "mychat", "weather"]
+    assert neko_plugin_cli.list_plugins(root=catalog_repo)[0] == {
+        "id": "chat", "version": "1.2.0", "entry": "chat:main", "folder": "chat",
+    }
+
+
+def test_match_covers_the_whole_id(catalog_repo):
+    assert _ids(catalog_repo, match="chat.*") == ["chat", "chat_bridge"]
+    assert _ids(catalog_repo, match=".*chat") == ["chat", "mychat"]
+
+
+def test_version_req(catalog_repo):
+    assert _ids(catalog_repo, version_req=">=1.2, <2") == ["chat", "mychat"]
+    # "0.3" is read as 0.3.0; "next" never matches
+    assert _ids(catalog_repo, version_req="<1") == ["weather"]
+
+
+def test_entry_contains(catalog_repo):
+    assert _ids(catalog_repo, entry_contains=":main") == ["chat", "draft", "mychat", "weather"]
+    assert _ids(catalog_repo, entry_contains="chat.") == ["chat_bridge"]
+
+
+def test_filters_combine(catalog_repo):
+    assert _ids(catalog_repo, match="chat.*", version_req="^1", entry_contains="main") == ["chat"]
+    assert _ids(catalog_repo, match="chat.*", version_req=">=3") == []
+
+
+def test_malformed_filters_say_what_is_wrong(catalog_repo):
+    with pytest.raises(ValueError, match=r'invalid id pattern "chat\(": .*unclosed group'):
+        _ids(catalog_repo, match="chat(")
+    with pytest.raises(ValueError, match=r'invalid version requirement "~>1": .*expected e.g.'):
+        _ids(catalog_repo, version_req="~>1")
@@ -0,0 +1,70 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+from conftest import rewrite_manifest
+
+import neko_plugin_cli
+
+
+def _from_the_future(text):
+    """A format_version 3 manifest from a newer build, with a top-level field this build has never seen"""
+    text = text.replace("format_version = 2", "format_version = 3")
+    text = text.replace(f'packed_by = "neko-plugin-cli {neko_plugin_cli.py_version()}"', 'packed_by = "neko-plugin-cli 9.0.0"')
+    return 'install_hooks = ["post.py"]\n' + text
+
+
+def test_pack_records_the_packing_build(bundle):
+    manifest = zipfile.ZipFile(bundle).read("manifest.toml").decode()
+    assert f'packed_by = "neko-plugin-cli {neko_plugin_cli.py_version()}"' in manifest.splitlines()
+
+
+def test_newer_version_names_both_builds(bundle, tmp_path):
+    future = rewrite_manifest(bundle, tmp_path / "v3.zip", _from_the_future)
+    dest = tmp_path / "dest"
+    for kwargs in ({}, {"dry_run": True}):
+        with pytest.raises(ValueError) as ei:
+            neko_plugin_cli.unpack(future, dest, **kwargs)
+        msg = str(ei.value)
+        assert "unsupported manifest format_version 3: newer than this build reads (1..=2)" in msg
+        assert f"packed by neko-plugin-cli 9.0.0, you have neko-plugin-cli {neko_plugin_cli.py_version()}" in msg
+        assert "--assume-version 2" in msg
+    assert not dest.exists() or list(dest.iterdir()) == []
+
+
+def test_unknown_packer_without_packed_by(bundle, tmp_path):
+    def strip(text):
+        return "\n".join(l for l in _from_the_future(text).splitlines() if not l.startswith("packed_by"))
+
+    future = rewrite_manifest(bundle, tmp_path / "v3.zip", strip)
+    with pytest.raises(ValueError, match="packed by an unknown neko-plugin-cli"):
+        neko_plugin_cli.unpack(future, tmp_path / "dest")
+
+
+def test_assume_version_reads_it_anyway(bundle, tmp_path):
+    future = rewrite_manifest(bundle, tmp_path / "v3.zip", _from_the_future)
+    dest = tmp_path / "dest"
+    report = neko_plugin_cli.unpack(future, dest, dry_run=True, assume_version=2)
+    assert report["summary"]["install"] == 2
+
+    res = neko_plugin_cli.unpack(future, dest, assume_version=2)
+    assert {p["verification"] for p in res["installed"]} == {"verified"}
+    assert "bundle manifest format_version 3 read as version 2 (--assume-version); newer fields are ignored" in res["warnings"]
+
+    with pytest.raises(ValueError, match="cannot assume manifest format_version 3"):
+        neko_plugin_cli.unpack(future, tmp_path / "other", assume_version=3)
+
+
+def test_assume_version_does_not_touch_supported_bundles(bundle, tmp_path):
+    res = neko_plugin_cli.unpack(bundle, tmp_path / "dest", assume_version=1)
+    assert {p["verification"] for p in res["installed"]} == {"verified"}
+    assert not any("--assume-version" in w for w in res["warnings"])
+
+
+def test_bundles_without_packed_by_still_unpack(bundle, tmp_path):
+    old = rewrite_manifest(
+        bundle, tmp_path / "old.zip", lambda t: "\n".join(l for l in t.splitlines() if not l.startswith("packed_by"))
+    )
+    res = neko_plugin_cli.unpack(old, tmp_path / "dest")
+    assert sorted(p["folder"] for p in res["installed"]) == ["alpha", "beta_dir"]
@@ -0,0 +1,100 @@
+from __future__ import annotations
+
+import tomllib
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def v1_bundle(neko_repo, tmp_path):
+    out = tmp_path / "v1.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out)
+    return out
+
+
+def _manifest(zip_path):
+    with zipfile.ZipFile(zip_path) as zf:
+        return tomllib.loads(zf.read("manifest.toml").decode("utf-8"))
+
+
+def _payload(zip_path):
+    """Raw (still compressed) bytes and CRC of every entry except the manifest."""
+    with zipfile.ZipFile(zip_path) as zf, open(zip_path, "rb") as raw:
+        out = {}
+        for info in zf.infolist():
+            if info.filename == "manifest.toml":
+                continue
+            raw.seek(info.header_offset + 26)
+            name_len, extra_len = int.from_bytes(raw.read(2), "little"), int.from_bytes(raw.read(2), "little")
+            raw.seek(info.header_offset + 30 + name_len + extra_len)
+            out[info.filename] = (info.CRC, info.compress_type, raw.read(info.compress_size))
+        return out
+
+
+def test_v1_bundle_migrates_and_verifies(v1_bundle, tmp_path):
+    assert _manifest(v1_bundle)["format_version"] == 1
+    out = tmp_path / "v2.zip"
+    report = neko_plugin_cli.migrate(v1_bundle, out)
+    assert (report["from_version"], report["to_version"]) == (1, 2)
+    assert report["verify"]["issues"] == []
+
+    verified = neko_plugin_cli.verify(out)
+    assert verified["issues"] == []
+    assert verified["file_checksums"] == report["file_checksums"] > 0
+
+    manifest = _manifest(out)
+    assert manifest["format_version"] == 2
+    assert all(p["files"] for p in manifest["plugins"])
+    old = _manifest(v1_bundle)
+    assert [(p["id"], p["hash"]) for p in manifest["plugins"]] == [(p["id"], p["hash"]) for p in old["plugins"]]
+    assert _payload(out) == _payload(v1_bundle)
+
+
+def test_missing_bundle_metadata_is_filled(v1_bundle, tmp_path):
+    out = tmp_path / "meta.zip"
+    # pack names the bundle after the zip; only the missing author is filled in
+    report = neko_plugin_cli.migrate(v1_bundle, out, format_version=1, bundle_name="Kit", bundle_author="neko")
+    assert (report["from_version"], report["to_version"], report["filled"]) == (1, 1, ["author"])
+    assert _manifest(out)["bundle"] == {"name": "v1", "author": "neko"}
+
+    again = neko_plugin_cli.migrate(out, None, bundle_version="2.0")
+    assert again["filled"] == ["version"]
+    assert _manifest(out)["bundle"] == {"name": "v1", "version": "2.0", "author": "neko"}
+    assert neko_plugin_cli.verify(out)["issues"] == []
+
+
+def test_resign_replaces_old_signature(neko_repo, tmp_path):
+    keys = neko_plugin_cli.keygen(tmp_path / "neko.key")
+    signed = tmp_path / "signed.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=signed, manifest_version=2, sign_key=keys["secret_key"])
+
+    unsigned = tmp_path / "unsigned.zip"
+    report = neko_plugin_cli.migrate(signed, unsigned, bundle_name="Kit")
+    assert (report["dropped_signature"], report["signed_by"]) == (True, None)
+    assert neko_plugin_cli.verify(unsigned)["signed"] is False
+
+    resigned = tmp_path / "resigned.zip"
+    report = neko_plugin_cli.migrate(signed, resigned, bundle_name="Kit", sign_key=keys["secret_key"])
+    assert report["signed_by"] == keys["fingerprint"]
+    verified = neko_plugin_cli.verify(resigned, verify_key=keys["public_key"], require_signature=True)
+    assert verified["issues"] == []
+
+
+def test_refuses_downgrade_and_corrupt_input(v1_bundle, neko_repo, tmp_path):
+    v2 = tmp_path / "v2.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=v2, manifest_version=2)
+    with pytest.raises(ValueError, match="does not downgrade"):
+        neko_plugin_cli.migrate(v2, tmp_path / "down.zip", format_version=1)
+
+    bad = tmp_path / "bad.zip"
+    with zipfile.ZipFile(v1_bundle) as src, zipfile.ZipFile(bad, "w") as dst:
+        for info in src.infolist():
+            data = src.read(info)
+            dst.writestr(info, data + b"# edited\n" if info.filename.endswith(".py") else data)
+    with pytest.raises(ValueError, match="fails verification"):
+        neko_plugin_cli.migrate(bad, tmp_path / "out.zip")
+    assert not (tmp_path / "out.zip").exists()
+    assert not (tmp_path / "out.zip.tmp").exists()
@@ -0,0 +1,90 @@
+from __future__ import annotations
+
+import tomllib
+
+import pytest
+from conftest import make_repo
+
+import neko_plugin_cli
+
+
+def _template(path, files):
+    for rel, data in files.items():
+        (path / rel).parent.mkdir(parents=True, exist_ok=True)
+        (path / rel).write_bytes(data if isinstance(data, bytes) else data.encode())
+    return path
+
+
+def _toml(plugin_dir):
+    return tomllib.loads((plugin_dir / "plugin.toml").read_text(encoding="utf-8"))["plugin"]
+
+
+def test_scaffold_passes_check(tmp_path):
+    root = make_repo(tmp_path / "repo", sdk_version="1.4.2")
+    res = neko_plugin_cli.new_plugin("weather_bot", root=root)
+    plugin_dir = root / "plugin" / "plugins" / "weather_bot"
+    assert res["path"] == str(plugin_dir)
+    assert res["files"] == [".nekopackignore", "__init__.py", "plugin.toml", "profiles/"]
+    assert (plugin_dir / "profiles").is_dir()
+    assert _toml(plugin_dir) == {
+        "id": "weather_bot",
+        "name": "Weather Bot",
+        "version": "0.1.0",
+        "entry": "weather_bot:main",
+        "sdk": {"supported": ">=1.4.2, <2.0.0"},
+    }
+    report = neko_plugin_cli.check(root=root)
+    assert report["errors"] == [] and report["warnings"] == []
+
+
+def test_ids_that_are_not_modules_use_a_path_entry(repo):
+    neko_plugin_cli.new_plugin("weather-bot.v2", root=repo)
+    assert _toml(repo / "plugin" / "plugins" / "weather-bot.v2")["entry"] == "__init__.py:main"
+    assert neko_plugin_cli.check(root=repo)["errors"] == []
+
+
+def test_options_prefill_the_toml(repo):
+    neko_plugin_cli.new_plugin("chat", root=repo, name='Chat "Pro"', author="Neko Team", sdk_supported=">=1.0, <3")
+    plugin = _toml(repo / "plugin" / "plugins" / "chat")
+    assert plugin["name"] == 'Chat "Pro"'
+    assert plugin["author"] == {"name": "Neko Team"}
+    assert plugin["sdk"] == {"supported": ">=1.0, <3"}
+
+
+def test_refuses_existing_folder_duplicate_id_and_invalid_input(neko_repo):
+    plugins = neko_repo / "plugin" / "plugins"
+    with pytest.raises(ValueError, match="already exists"):
+        neko_plugin_cli.new_plugin("alpha", root=neko_repo)
+    with pytest.raises(ValueError, match="already used by folder beta_dir"):
+        neko_plugin_cli.new_plugin("beta", root=neko_repo)
+    with pytest.raises(ValueError, match="invalid plugin id: .*may only contain"):
+        neko_plugin_cli.new_plugin("my plugin", root=neko_repo)
+    with pytest.raises(ValueError, match="invalid --sdk-supported"):
+        neko_plugin_cli.new_plugin("gamma", root=neko_repo, sdk_supported="~>1")
+    assert sorted(p.name for p in plugins.iterdir()) == ["alpha", "beta_dir"]
+    assert (plugins / "alpha" / "__init__.py").read_text() == "def main():\n    pass\n"
+
+
+def test_custom_template(repo, tmp_path):
+    template = _template(tmp_path / "tpl", {
+        "plugin.toml": '[plugin]\nid = "{{id}}"\nname = "{{name}}"\nversion = "0.0.1"\nentry = "{{id}}_impl:run"\n',
+        "{{id}}_impl.py": "# {{name}} by {{author}}\ndef run():\n    pass\n",
+        "assets/logo.bin": b"\xff{{id}}\x00",
+    })
+    res = neko_plugin_cli.new_plugin("radio", root=repo, name="Radio", author="me", template=template)
+    plugin_dir = repo / "plugin" / "plugins" / "radio"
+    assert res["files"] == ["assets/", "assets/logo.bin", "plugin.toml", "radio_impl.py"]
+    assert _toml(plugin_dir)["entry"] == "radio_impl:run"
+    assert (plugin_dir / "radio_impl.py").read_text().startswith("# Radio by me\n")
+    assert (plugin_dir / "assets" / "logo.bin").read_bytes() == b"\xff{{id}}\x00"
+    assert neko_plugin_cli.check(root=repo)["errors"] == []
+
+
+def test_template_with_a_fixed_id_is_rolled_back(repo, tmp_path):
+    template = _template(tmp_path / "tpl", {
+        "plugin.toml": '[plugin]\nid = "old"\nversion = "1.0.0"\nentry = "x:main"\n',
+        "x.py": "def main():\n    pass\n",
+    })
+    with pytest.raises(ValueError, match=r"declares id old instead of radio \(use \{\{id\}\}\)"):
+        neko_plugin_cli.new_plugin("radio", root=repo, template=template)
+    assert not (repo / "plugin" / "plugins" / "radio").exists()
@@ -0,0 +1,93 @@
+from __future__ import annotations
+
+import os
+import threading
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+
+def test_pack_all_plugins(neko_repo, tmp_path):
+    out = tmp_path / "bundle.zip"
+    res = neko_plugin_cli.pack(root=str(neko_repo), out=str(out), bundle_version="1.0")
+
+    assert res["path"] == str(out)
+    assert out.is_file()
+    manifest = res["manifest"]
+    assert manifest["format_version"] == 1
+    assert manifest["neko_base_version"] == "0.5.0"
+    assert manifest["bundle"] == {"name": "bundle", "version": "1.0", "author": None}
+    plugins = {p["id"]: p for p in manifest["plugins"]}
+    assert sorted(plugins) == ["alpha", "beta"]
+    assert plugins["beta"]["folder"] == "plugins/beta_dir"
+    assert len(plugins["alpha"]["md5"]) == 32
+    assert plugins["alpha"]["bundled_profiles"]
+
+    names = zipfile.ZipFile(out).namelist()
+    assert "manifest.toml" in names
+    assert "plugins/alpha/data/config.json" in names
+    assert not any("__pycache__" in n for n in names)
+
+
+def test_pack_selected_plugin_without_md5(neko_repo, tmp_path):
+    out = tmp_path / "alpha.zip"
+    res = neko_plugin_cli.pack(
+        root=neko_repo,
+        plugin_ids=["alpha"],
+        out=out,
+        no_md5=True,
+        excludes=["**/data/**"],
+        bundle_name="demo",
+        bundle_author="me",
+    )
+    [plugin] = res["manifest"]["plugins"]
+    assert plugin["id"] == "alpha"
+    assert plugin["md5"] is None
+    assert res["manifest"]["bundle"]["author"] == "me"
+    assert res["manifest"]["bundle_profiles_root"] == "bundle_profiles/demo/"
+    assert not any("/data/" in n for n in zipfile.ZipFile(out).namelist())
+
+
+def test_unknown_plugin_raises_value_error(neko_repo, tmp_path):
+    with pytest.raises(ValueError, match="no plugins found"):
+        neko_plugin_cli.pack(root=neko_repo, plugin_ids=["nope"], out=tmp_path / "x.zip")
+
+
+def test_bad_glob_raises_value_error(neko_repo, tmp_path):
+    with pytest.raises(ValueError):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", excludes=["[unclosed"])
+
+
+def test_unwritable_output_raises_os_error(neko_repo, tmp_path):
+    with pytest.raises(OSError, match="failed to create"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "missing-dir" / "x.zip")
+
+
+def test_broken_plugin_toml_reports_context(neko_repo, tmp_path):
+    (neko_repo / "plugin" / "plugins" / "alpha" / "plugin.toml").write_text("[plugin\n")
+    with pytest.raises(ValueError, match=r"failed to parse .*plugin\.toml: "):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip")
+
+
+def test_gil_released_while_packing(neko_repo, tmp_path):
+    big = neko_repo / "plugin" / "plugins" / "alpha" / "data" / "big.bin"
+    big.write_bytes(os.urandom(32 * 1024 * 1024))
+    counter = [0]
+    stop = threading.Event()
+
+    def spin():
+        while not stop.is_set():
+            counter[0] += 1
+
+    t = threading.Thread(target=spin)
+    t.start()
+    try:
+        before = counter[0]
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "big.zip")
+        progressed = counter[0] - before
+    finally:
+        stop.set()
+        t.join()
+    assert progressed > 1000
@@ -0,0 +1,97 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+def _read_all(path):
+    with zipfile.ZipFile(path) as z:
+        return {n: z.read(n) for n in z.namelist() if n.startswith("plugins/")}
+
+
+def test_unchanged_plugin_is_reused_and_changed_one_repacked(neko_repo, tmp_path):
+    base = tmp_path / "base.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=base)
+    (neko_repo / "plugin" / "plugins" / "beta_dir" / "beta.py").write_text("def main():\n    return 3\n")
+
+    out = tmp_path / "next.zip"
+    res = neko_plugin_cli.pack(root=neko_repo, out=out, base=base)
+
+    assert res["reused"] == ["alpha"]
+    files = _read_all(out)
+    assert files["plugins/beta_dir/beta.py"] == b"def main():\n    return 3\n"
+    assert files["plugins/alpha/data/config.json"] == b'{"a": 1}\n'
+    assert neko_plugin_cli.verify_bundle(out)["folder_hashes"] == 2
+
+
+def test_plugin_missing_from_base_is_packed_from_disk(neko_repo, tmp_path):
+    base = tmp_path / "base.zip"
+    neko_plugin_cli.pack(root=neko_repo, plugin_ids=["alpha"], out=base)
+    write_plugin(
+        neko_repo,
+        "gamma",
+        '[plugin]\nid = "gamma"\nname = "Gamma"\nversion = "0.1.0"\nentry = "gamma:main"\n',
+        {"gamma.py": "x = 1\n"},
+    )
+
+    out = tmp_path / "next.zip"
+    res = neko_plugin_cli.pack(root=neko_repo, out=out, base=base)
+
+    assert res["reused"] == ["alpha"]
+    assert sorted(p["id"] for p in res["manifest"]["plugins"]) == ["alpha", "beta", "gamma"]
+    assert _read_all(out)["plugins/gamma/gamma.py"] == b"x = 1\n"
+
+
+def test_incremental_pack_matches_full_pack(neko_repo, tmp_path):
+    common = dict(root=neko_repo, bundle_name="demo", source_date_epoch=1700000000, manifest_version=2)
+    base = tmp_path / "base.zip"
+    neko_plugin_cli.pack(out=base, **common)
+    (neko_repo / "plugin" / "plugins" / "beta_dir" / "beta.py").write_text("def main():\n    return 3\n")
+
+    full = tmp_path / "full.zip"
+    neko_plugin_cli.pack(out=full, **common)
+    incremental = tmp_path / "incremental.zip"
+    res = neko_plugin_cli.pack(out=incremental, base=base, **common)
+
+    assert res["reused"] == ["alpha"]
+    assert incremental.read_bytes() == full.read_bytes()
+
+
+def test_different_compression_disables_reuse(neko_repo, tmp_path):
+    base = tmp_path / "base.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=base)
+    res = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "next.zip", base=base, compression="stored")
+    assert res["reused"] == []
+
+
+def test_hash_algorithm_change_disables_reuse(neko_repo, tmp_path):
+    base = tmp_path / "base.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=base)
+    res = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "next.zip", base=base, hash="sha256")
+    assert res["reused"] == []
+
+
+def test_v1_base_cannot_supply_v2_checksums(neko_repo, tmp_path):
+    base = tmp_path / "base.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=base)
+    out = tmp_path / "next.zip"
+    res = neko_plugin_cli.pack(root=neko_repo, out=out, base=base, manifest_version=2)
+    assert res["reused"] == []
+    assert all(p["files"] for p in res["manifest"]["plugins"])
+
+
+def test_base_requires_hashes(neko_repo, tmp_path):
+    base = tmp_path / "base.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=base)
+    with pytest.raises(ValueError, match="cannot be combined with no_md5"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "next.zip", base=base, no_md5=True)
+
+
+def test_missing_base_reports_path(neko_repo, tmp_path):
+    with pytest.raises(OSError, match="failed to open zip"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "next.zip", base=tmp_path / "nope.zip")
@@ -0,0 +1,53 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+
+@pytest.mark.parametrize("manifest_version", [1, 2])
+def test_dry_run_matches_real_pack(neko_repo, tmp_path, manifest_version):
+    out = tmp_path / "bundle.zip"
+    opts = dict(root=neko_repo, out=out, bundle_name="demo", excludes=["**/*.json"], manifest_version=manifest_version)
+
+    report = neko_plugin_cli.pack(dry_run=True, **opts)
+    assert not out.exists()
+    assert not out.with_suffix(".zip.tmp").exists()
+
+    planned = [f["zip_path"] for p in report["plugins"] for f in p["files"]]
+    neko_plugin_cli.pack(**opts)
+    actual = [n for n in zipfile.ZipFile(out).namelist() if n != "manifest.toml"]
+    assert sorted(planned) == sorted(actual)
+    assert report["total_files"] == len(actual)
+
+    sizes = {i.filename: i.file_size for i in zipfile.ZipFile(out).infolist()}
+    assert report["total_bytes"] == sum(v for k, v in sizes.items() if k != "manifest.toml")
+    for p in report["plugins"]:
+        assert p["file_count"] == len(p["files"])
+        assert p["bytes"] == sum(sizes[f["zip_path"]] for f in p["files"])
+
+
+def test_dry_run_report(neko_repo, tmp_path):
+    (neko_repo / "plugin" / "plugins" / "alpha" / "data" / "model.bin").write_bytes(b"\0" * 4096)
+    report = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", dry_run=True)
+    assert report["out_path"] == str(tmp_path / "x.zip")
+    assert report["largest_files"][0] == {
+        "zip_path": "plugins/alpha/data/model.bin",
+        "rel": "data/model.bin",
+        "size": 4096,
+    }
+    sizes = [f["size"] for f in report["largest_files"]]
+    assert sizes == sorted(sizes, reverse=True)
+    manifest = report["manifest"]
+    assert sorted(p["id"] for p in manifest["plugins"]) == ["alpha", "beta"]
+    # Hashing is skipped in a dry run
+    assert all(p["hash"] is None for p in manifest["plugins"])
+    alpha = next(p for p in manifest["plugins"] if p["id"] == "alpha")
+    assert alpha["bundled_profiles"] == [f["zip_path"] for f in next(p for p in report["plugins"] if p["id"] == "alpha")["files"] if f["zip_path"].startswith("bundle_profiles/")]
+
+
+def test_dry_run_validates_options(neko_repo, tmp_path):
+    with pytest.raises(ValueError, match="compression level"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", compression="deflate", compression_level=42, dry_run=True)
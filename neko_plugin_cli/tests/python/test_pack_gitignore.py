@@ -0,0 +1,78 @@
+from __future__ import annotations
+
+import pytest
+from conftest import alpha_hash, alpha_names
+
+import neko_plugin_cli
+
+IGNORED = [
+    "node_modules/lib.js",
+    "debug.log",
+    ".env",
+    "x.bak",
+    "data/scratch.tmp",
+    "data/cache/blob.bin",
+]
+KEPT = ["data/keep.log", "data/important.tmp", "data/config.json", ".gitignore", "data/.gitignore"]
+
+
+@pytest.fixture
+def gitignore_repo(neko_repo):
+    alpha = neko_repo / "plugin" / "plugins" / "alpha"
+    (neko_repo / ".gitignore").write_text("node_modules/\n*.log\n")
+    (neko_repo / "plugin" / "plugins" / ".gitignore").write_text("*.bak\n")
+    (alpha / ".gitignore").write_text("# plugin-local\n.env\n!data/keep.log\n")
+    (alpha / "data" / ".gitignore").write_text("*.tmp\n!important.tmp\ncache/\n")
+    for rel in IGNORED + KEPT:
+        path = alpha / rel
+        if not path.exists():
+            path.parent.mkdir(parents=True, exist_ok=True)
+            path.write_text(f"{rel}\n")
+    return neko_repo
+
+
+def test_gitignore_not_applied_by_default(gitignore_repo, tmp_path):
+    out = tmp_path / "x.zip"
+    neko_plugin_cli.pack(root=gitignore_repo, out=out)
+    assert set(IGNORED + KEPT) <= alpha_names(out)
+
+
+def test_nested_gitignores_and_negations(gitignore_repo, tmp_path):
+    out = tmp_path / "x.zip"
+    neko_plugin_cli.pack(root=gitignore_repo, out=out, respect_gitignore=True)
+    names = alpha_names(out)
+    assert set(KEPT) <= names
+    assert names.isdisjoint(IGNORED)
+
+
+def test_dry_run_lists_the_same_files(gitignore_repo, tmp_path):
+    out = tmp_path / "x.zip"
+    neko_plugin_cli.pack(root=gitignore_repo, out=out, respect_gitignore=True)
+    plan = neko_plugin_cli.pack(root=gitignore_repo, out=tmp_path / "plan.zip", respect_gitignore=True, dry_run=True)
+    [alpha] = [p for p in plan["plugins"] if p["id"] == "alpha"]
+    planned = {f["rel"] for f in alpha["files"] if f["zip_path"].startswith("plugins/")}
+    assert planned == alpha_names(out)
+
+
+def test_folder_hash_ignores_the_same_files(gitignore_repo, tmp_path):
+    alpha = gitignore_repo / "plugin" / "plugins" / "alpha"
+    first = neko_plugin_cli.pack(root=gitignore_repo, out=tmp_path / "a.zip", respect_gitignore=True)
+    plain = neko_plugin_cli.pack(root=gitignore_repo, out=tmp_path / "b.zip")
+
+    (alpha / "debug.log").write_text("changed\n")
+    (alpha / "data" / "cache" / "blob.bin").write_text("changed\n")
+    second = neko_plugin_cli.pack(root=gitignore_repo, out=tmp_path / "c.zip", respect_gitignore=True)
+    assert alpha_hash(second) == alpha_hash(first)
+    assert alpha_hash(neko_plugin_cli.pack(root=gitignore_repo, out=tmp_path / "d.zip")) != alpha_hash(plain)
+
+    (alpha / "data" / "keep.log").write_text("changed\n")
+    third = neko_plugin_cli.pack(root=gitignore_repo, out=tmp_path / "e.zip", respect_gitignore=True)
+    assert alpha_hash(third) != alpha_hash(first)
+
+
+def test_base_reuse_with_gitignore(gitignore_repo, tmp_path):
+    base = tmp_path / "base.zip"
+    neko_plugin_cli.pack(root=gitignore_repo, out=base, respect_gitignore=True)
+    (gitignore_repo / "plugin" / "plugins" / "alpha" / "debug.log").write_text("changed\n")
+    res = neko_plugin_cli.pack(root=gitignore_repo, out=tmp_path / "next.zip", base=base, respect_gitignore=True)
+    assert sorted(res["reused"]) == ["alpha", "beta"]
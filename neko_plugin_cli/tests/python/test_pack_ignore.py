@@ -0,0 +1,79 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+
+def _alpha(repo):
+    return repo / "plugin" / "plugins" / "alpha"
+
+
+def _packed(out, plugin="alpha"):
+    prefix = f"plugins/{plugin}/"
+    return sorted(n[len(prefix):] for n in zipfile.ZipFile(out).namelist() if n.startswith(prefix))
+
+
+@pytest.fixture
+def ignore_repo(neko_repo):
+    alpha = _alpha(neko_repo)
+    for rel in ["models/big.ckpt", "models/readme.md", "tests/data/sample.bin", "src/tests/keep.py", "notes.tmp", "src/cache.tmp"]:
+        p = alpha / rel
+        p.parent.mkdir(parents=True, exist_ok=True)
+        p.write_text(rel)
+    return neko_repo
+
+
+def test_nested_patterns(ignore_repo, tmp_path):
+    (_alpha(ignore_repo) / ".nekopackignore").write_text(
+        "# local only\n\n*.tmp\n/tests/\nmodels/*.ckpt\n"
+    )
+    out = tmp_path / "a.zip"
+    neko_plugin_cli.pack(root=ignore_repo, plugin_ids=["alpha"], out=out)
+    files = _packed(out)
+    assert ".nekopackignore" in files
+    assert "models/readme.md" in files
+    assert "src/tests/keep.py" in files
+    assert "models/big.ckpt" not in files
+    assert not any(f.endswith(".tmp") for f in files)
+    assert not any(f.startswith("tests/") for f in files)
+
+
+def test_ignore_applies_only_to_its_plugin(ignore_repo, tmp_path):
+    (_alpha(ignore_repo) / ".nekopackignore").write_text("*.py\n")
+    out = tmp_path / "all.zip"
+    neko_plugin_cli.pack(root=ignore_repo, out=out)
+    assert "__init__.py" not in _packed(out)
+    assert "beta.py" in _packed(out, "beta_dir")
+
+
+def test_combined_with_cli_excludes(ignore_repo, tmp_path):
+    (_alpha(ignore_repo) / ".nekopackignore").write_text("*.tmp\n")
+    out = tmp_path / "a.zip"
+    neko_plugin_cli.pack(root=ignore_repo, plugin_ids=["alpha"], out=out, excludes=["**/models/**"])
+    files = _packed(out)
+    assert not any(f.endswith(".tmp") or f.startswith("models/") for f in files)
+    assert "src/tests/keep.py" in files
+
+
+def test_ignored_files_do_not_affect_identical_skip(ignore_repo, tmp_path):
+    beta = ignore_repo / "plugin" / "plugins" / "beta_dir"
+    (beta / ".nekopackignore").write_text("*.tmp\n")
+    out = tmp_path / "b.zip"
+    neko_plugin_cli.pack(root=ignore_repo, plugin_ids=["beta"], out=out)
+    dest = tmp_path / "installed"
+    neko_plugin_cli.unpack(out, dest)
+
+    (dest / "beta_dir" / "scratch.tmp").write_text("local state")
+    [item] = neko_plugin_cli.preview_unpack(out, dest)
+    assert item["will_install"] is False
+    assert "identical" in item["reason"]
+
+
+def test_negation_rejected(ignore_repo, tmp_path):
+    (_alpha(ignore_repo) / ".nekopackignore").write_text("*.tmp\n!keep.tmp\n")
+    with pytest.raises(ValueError, match=r"line 2: negation patterns are not supported") as exc:
+        neko_plugin_cli.pack(root=ignore_repo, plugin_ids=["alpha"], out=tmp_path / "x.zip")
+    assert str(exc.value.filename).endswith(".nekopackignore")
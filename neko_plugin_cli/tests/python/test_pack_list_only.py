@@ -0,0 +1,60 @@
+from __future__ import annotations
+
+import tomllib
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+EPOCH = 1700000000
+
+
+def _drop_none(value):
+    # TOML has no null, so None fields are simply absent from manifest.toml
+    if isinstance(value, dict):
+        return {k: _drop_none(v) for k, v in value.items() if v is not None}
+    if isinstance(value, list):
+        return [_drop_none(v) for v in value]
+    return value
+
+
+def _packed_manifest(path):
+    with zipfile.ZipFile(path) as z:
+        return tomllib.loads(z.read("manifest.toml").decode())
+
+
+@pytest.mark.parametrize("manifest_version,no_md5", [(1, False), (2, False), (1, True), (2, True)])
+def test_listed_manifest_matches_packed_one(neko_repo, tmp_path, manifest_version, no_md5):
+    (neko_repo / "LICENSE").write_text("MIT\n")
+    common = dict(
+        root=neko_repo,
+        out=tmp_path / "demo.zip",
+        manifest_version=manifest_version,
+        no_md5=no_md5,
+        include_root=["LICENSE"],
+        bundle_version="1.0",
+        source_date_epoch=EPOCH,
+    )
+    listed = neko_plugin_cli.pack(list_only=True, **common)
+    assert set(listed) == {"manifest"}
+    assert not (tmp_path / "demo.zip").exists()
+
+    neko_plugin_cli.pack(**common)
+    assert _drop_none(listed["manifest"]) == _packed_manifest(tmp_path / "demo.zip")
+
+
+def test_listed_manifest_resolves_bundle_metadata(neko_repo, tmp_path):
+    res = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "my pack.zip", list_only=True, bundle_author="me")
+    manifest = res["manifest"]
+    assert manifest["bundle"] == {"name": "my_pack", "version": None, "author": "me"}
+    [alpha] = [p for p in manifest["plugins"] if p["id"] == "alpha"]
+    assert alpha["bundled_profiles"] == [
+        "bundle_profiles/my_pack/plugins/alpha/my_pack__unknown__alpha__profiles.toml"
+    ]
+    assert len(alpha["md5"]) == 32
+
+
+def test_list_only_rejects_write_options(neko_repo, tmp_path):
+    with pytest.raises(ValueError, match="list_only cannot be combined"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", list_only=True, dry_run=True)
@@ -0,0 +1,46 @@
+from __future__ import annotations
+
+import zipfile
+
+import neko_plugin_cli
+
+
+def test_progress_fires_for_every_file(neko_repo, tmp_path):
+    events = []
+    out = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, progress=events.append)
+
+    written = [e["name"] for e in events if e["event"] == "file_written"]
+    names = [n for n in zipfile.ZipFile(out).namelist() if n != "manifest.toml"]
+    assert written == names
+
+    sizes = {i.filename: i.file_size for i in zipfile.ZipFile(out).infolist()}
+    assert all(e["bytes"] == sizes[e["name"]] for e in events if e["event"] == "file_written")
+
+    [start] = [e for e in events if e["event"] == "start"]
+    assert start == {"event": "start", "plugins": 2, "files": len(names), "bytes": sum(sizes[n] for n in names)}
+
+    hashed = sorted(e["plugin"] for e in events if e["event"] == "hashed")
+    assert hashed == ["alpha", "beta"]
+    assert max(e["done"] for e in events if e["event"] == "hashed") == 2
+
+    kinds = [e["event"] for e in events if e["event"] in ("plugin_start", "plugin_done")]
+    assert kinds == ["plugin_start", "plugin_done"] * 2
+    assert events.index(start) > max(i for i, e in enumerate(events) if e["event"] == "hashed")
+
+
+def test_no_hash_events_without_hashing(neko_repo, tmp_path):
+    events = []
+    neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", no_md5=True, progress=events.append)
+    assert not any(e["event"] == "hashed" for e in events)
+    assert any(e["event"] == "file_written" for e in events)
+
+
+def test_failing_callback_does_not_abort_pack(neko_repo, tmp_path):
+    def boom(event):
+        raise RuntimeError("callback failed")
+
+    out = tmp_path / "x.zip"
+    res = neko_plugin_cli.pack(root=neko_repo, out=out, progress=boom)
+    assert res["path"] == str(out)
+    assert out.is_file()
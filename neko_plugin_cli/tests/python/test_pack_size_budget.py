@@ -0,0 +1,83 @@
+from __future__ import annotations
+
+import os
+import warnings
+
+import pytest
+
+import neko_plugin_cli
+
+EPOCH = 1_700_000_000
+
+
+def _pack_at_epoch(repo, out_dir, **kwargs):
+    out_dir.mkdir(exist_ok=True)
+    out = out_dir / "bundle.zip"
+    return out, neko_plugin_cli.pack(root=repo, out=out, source_date_epoch=EPOCH, **kwargs)
+
+
+@pytest.fixture
+def packed_size(neko_repo, tmp_path):
+    out, _ = _pack_at_epoch(neko_repo, tmp_path / "ref")
+    return out.stat().st_size
+
+
+def test_max_size_boundary(neko_repo, tmp_path, packed_size):
+    out, _ = _pack_at_epoch(neko_repo, tmp_path / "exact", max_size=packed_size)
+    assert out.stat().st_size == packed_size
+
+    with pytest.raises(ValueError, match=f"bundle exceeds --max-size: {packed_size} bytes written, limit {packed_size - 1} bytes"):
+        _pack_at_epoch(neko_repo, tmp_path / "over", max_size=packed_size - 1)
+    assert list((tmp_path / "over").iterdir()) == []
+
+
+def test_max_size_aborts_early_naming_plugins(neko_repo, tmp_path):
+    big = neko_repo / "plugin" / "plugins" / "alpha" / "data" / "model.bin"
+    big.write_bytes(os.urandom(4 * 1024 * 1024))
+    with pytest.raises(ValueError, match=r"by plugin: alpha [\d.]+ MiB, beta"):
+        _pack_at_epoch(neko_repo, tmp_path / "big", max_size="1MiB")
+    assert list((tmp_path / "big").iterdir()) == []
+
+
+def test_warn_size_boundary(neko_repo, tmp_path, packed_size):
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        _pack_at_epoch(neko_repo, tmp_path / "fits", warn_size=packed_size)
+    assert caught == []
+
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        out, _ = _pack_at_epoch(neko_repo, tmp_path / "warn", warn_size=packed_size - 1)
+    [w] = caught
+    assert f"bundle is {packed_size} bytes" in str(w.message)
+    assert "by plugin: alpha" in str(w.message)
+    assert out.is_file()
+
+
+def test_dry_run_estimates_from_uncompressed(neko_repo, tmp_path):
+    report = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", dry_run=True)
+    total = report["total_bytes"]
+    assert report["budget_warnings"] == []
+
+    fits = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", dry_run=True, max_size=total, warn_size=total)
+    assert fits["budget_warnings"] == []
+
+    over = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", dry_run=True, max_size=total - 1)
+    [msg] = over["budget_warnings"]
+    assert msg.startswith(f"estimate: uncompressed size {total} bytes")
+    assert "--max-size" in msg
+    assert not (tmp_path / "x.zip").exists()
+
+
+@pytest.mark.parametrize(
+    "text, expected",
+    [("1024", 1024), ("2KiB", 2048), ("2k", 2048), ("1.5MB", 1_500_000), ("1 GiB", 1 << 30)],
+)
+def test_size_strings(neko_repo, tmp_path, text, expected):
+    report = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", dry_run=True, max_size=text)
+    assert bool(report["budget_warnings"]) == (report["total_bytes"] > expected)
+
+
+def test_bad_size_unit(neko_repo, tmp_path):
+    with pytest.raises(ValueError, match="unknown unit"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", max_size="10 parsecs")
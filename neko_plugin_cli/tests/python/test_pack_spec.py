@@ -0,0 +1,107 @@
+from __future__ import annotations
+
+import tomllib
+import warnings
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+FULL_SPEC = """
+plugins = ["al*", "beta"]
+exclude = ["**/data/**"]
+hash = "sha256"
+compression = "bzip2"
+compression_level = 9
+manifest_version = 2
+out = "dist/{name}-{version}-{date}.zip"
+
+[bundle]
+name = "Demo Pack"
+version = "1.2"
+author = "release-eng"
+"""
+
+
+def _write_spec(tmp_path, text):
+    spec_dir = tmp_path / "spec"
+    (spec_dir / "dist").mkdir(parents=True, exist_ok=True)
+    path = spec_dir / "bundle.toml"
+    path.write_text(text)
+    return path
+
+
+def _manifest(path):
+    with zipfile.ZipFile(path) as z:
+        return tomllib.loads(z.read("manifest.toml").decode()), z
+
+
+def test_full_spec(neko_repo, tmp_path):
+    spec = _write_spec(tmp_path, FULL_SPEC)
+    res = neko_plugin_cli.pack(root=neko_repo, spec=spec, source_date_epoch=365 * 86400)
+
+    assert res["path"] == str(spec.parent / "dist" / "Demo_Pack-1.2-1971-01-01.zip")
+    manifest, z = _manifest(res["path"])
+    assert manifest["bundle"] == {"name": "Demo Pack", "version": "1.2", "author": "release-eng"}
+    assert manifest["format_version"] == 2
+    assert [p["id"] for p in manifest["plugins"]] == ["alpha", "beta"]
+    assert all(p["hash_algo"] == "sha256" for p in manifest["plugins"])
+    assert manifest["compression"] == "bzip2"
+    assert manifest["compression_level"] == 9
+    assert not any("/data/" in n for n in z.namelist())
+
+
+def test_flags_override_spec(neko_repo, tmp_path):
+    spec = _write_spec(tmp_path, FULL_SPEC)
+    out = tmp_path / "override.zip"
+    res = neko_plugin_cli.pack(
+        root=neko_repo,
+        spec=spec,
+        plugin_ids=["beta"],
+        out=out,
+        hash="md5",
+        compression="stored",
+        bundle_version="2.0",
+        excludes=["**/*.py"],
+    )
+    assert res["path"] == str(out)
+    manifest, z = _manifest(out)
+    assert [p["id"] for p in manifest["plugins"]] == ["beta"]
+    assert manifest["plugins"][0]["hash_algo"] == "md5"
+    # The spec's level belonged to bzip2 and is dropped along with it
+    assert manifest["compression"] == "stored"
+    assert "compression_level" not in manifest
+    assert manifest["bundle"] == {"name": "Demo Pack", "version": "2.0", "author": "release-eng"}
+    assert not any(n.endswith(".py") for n in z.namelist())
+
+
+def test_unknown_keys_warn(neko_repo, tmp_path):
+    spec = _write_spec(tmp_path, 'compresion = "stored"\n\n[bundle]\nname = "x"\nnmae = "typo"\n')
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        res = neko_plugin_cli.pack(root=neko_repo, spec=spec, out=tmp_path / "x.zip")
+    messages = sorted(str(w.message) for w in caught)
+    assert len(messages) == 2
+    assert "unknown key `bundle.nmae`" in messages[0]
+    assert "unknown key `compresion`" in messages[1]
+    assert res["manifest"]["bundle"]["name"] == "x"
+
+
+def test_bad_value_names_the_key(neko_repo, tmp_path):
+    spec = _write_spec(tmp_path, 'hash = "sha1"\n')
+    with pytest.raises(ValueError, match=r"(?s)invalid bundle spec .*hash = \"sha1\".*unknown variant `sha1`") as exc:
+        neko_plugin_cli.pack(root=neko_repo, spec=spec, out=tmp_path / "x.zip")
+    assert exc.value.filename == str(spec)
+
+
+def test_plugin_glob_must_match(neko_repo, tmp_path):
+    spec = _write_spec(tmp_path, 'plugins = ["gamma*"]\n')
+    with pytest.raises(ValueError, match="`plugins` entry gamma\\* matches no plugin"):
+        neko_plugin_cli.pack(root=neko_repo, spec=spec, out=tmp_path / "x.zip")
+
+
+def test_unknown_placeholder(neko_repo, tmp_path):
+    spec = _write_spec(tmp_path, 'out = "dist/{id}.zip"\n')
+    with pytest.raises(ValueError, match=r"unknown placeholder \{id\}"):
+        neko_plugin_cli.pack(root=neko_repo, spec=spec)
@@ -0,0 +1,60 @@
+from __future__ import annotations
+
+import tomllib
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+
+def _manifest(path):
+    with zipfile.ZipFile(path) as z:
+        return tomllib.loads(z.read("manifest.toml").decode()), z.namelist()
+
+
+def test_split_writes_one_bundle_per_plugin(neko_repo, tmp_path):
+    out_dir = tmp_path / "dist"
+    res = neko_plugin_cli.pack(root=neko_repo, out=out_dir, split=True)
+
+    assert sorted(res) == ["alpha", "beta"]
+    assert res["alpha"]["path"] == str(out_dir / "neko_plugin_alpha_1.0.0.zip")
+    assert res["beta"]["path"] == str(out_dir / "neko_plugin_beta_0.3.0.zip")
+
+    for pid, info in res.items():
+        manifest, names = _manifest(info["path"])
+        assert info["size"] == (out_dir / info["path"].rsplit("/", 1)[-1]).stat().st_size
+        assert [p["id"] for p in manifest["plugins"]] == [pid]
+        folder = manifest["plugins"][0]["folder"]
+        assert all(n.startswith(folder) for n in names if n.startswith("plugins/"))
+
+    alpha_manifest, alpha_names = _manifest(res["alpha"]["path"])
+    assert alpha_manifest["plugins"][0]["bundled_profiles"]
+    assert all(p in alpha_names for p in alpha_manifest["plugins"][0]["bundled_profiles"])
+    beta_manifest, beta_names = _manifest(res["beta"]["path"])
+    assert not beta_manifest["plugins"][0].get("bundled_profiles")
+    assert not any(n.startswith("bundle_profiles/") for n in beta_names)
+
+
+def test_failure_does_not_abort_other_plugins(neko_repo, tmp_path):
+    out_dir = tmp_path / "dist"
+    # A directory squatting on alpha's output name makes only that plugin fail
+    (out_dir / "neko_plugin_alpha_1.0.0.zip").mkdir(parents=True)
+    res = neko_plugin_cli.pack(root=neko_repo, out=out_dir, split=True)
+    assert "failed to rename" in res["alpha"]["error"]
+    assert (out_dir / "neko_plugin_beta_0.3.0.zip").is_file()
+
+
+def test_fail_fast(neko_repo, tmp_path):
+    out_dir = tmp_path / "dist"
+    (out_dir / "neko_plugin_alpha_1.0.0.zip").mkdir(parents=True)
+    with pytest.raises(OSError, match="failed to pack plugin alpha"):
+        neko_plugin_cli.pack(root=neko_repo, out=out_dir, split=True, fail_fast=True)
+    assert not (out_dir / "neko_plugin_beta_0.3.0.zip").exists()
+
+
+def test_split_output_must_be_directory(neko_repo, tmp_path):
+    out = tmp_path / "file.zip"
+    out.write_text("")
+    with pytest.raises(ValueError, match="--split output must be a directory"):
+        neko_plugin_cli.pack(root=neko_repo, out=out, split=True)
@@ -0,0 +1,59 @@
+from __future__ import annotations
+
+import io
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+
+def test_streamed_bundle_unpacks_from_bytes(neko_repo, tmp_path):
+    res = neko_plugin_cli.pack(root=neko_repo, out="-", manifest_version=2)
+
+    assert res["path"] is None
+    data = res["data"]
+    assert isinstance(data, bytes)
+    names = zipfile.ZipFile(io.BytesIO(data)).namelist()
+    assert "manifest.toml" in names
+    assert res["manifest"]["bundle"]["name"].startswith("neko_plugins_bundle_")
+
+    dest = tmp_path / "installed"
+    out = neko_plugin_cli.unpack(data, dest)
+    assert sorted(p["id"] for p in out["installed"]) == ["alpha", "beta"]
+    assert (dest / "alpha" / "data" / "config.json").read_text() == '{"a": 1}\n'
+    assert (dest / "beta_dir" / "beta.py").is_file()
+
+
+def test_single_plugin_stream_is_named_like_its_default_output(neko_repo):
+    res = neko_plugin_cli.pack(root=neko_repo, plugin_ids=["beta"], out="-")
+    assert res["manifest"]["bundle"]["name"] == "neko_plugin_beta_0.3.0"
+
+
+def test_stream_matches_file_output(neko_repo, tmp_path):
+    common = dict(root=neko_repo, bundle_name="demo", source_date_epoch=1700000000, manifest_version=2)
+    out = tmp_path / "demo.zip"
+    neko_plugin_cli.pack(out=out, **common)
+    streamed = neko_plugin_cli.pack(out="-", **common)
+    assert streamed["data"] == out.read_bytes()
+
+
+def test_stream_can_be_signed_and_verified(neko_repo, tmp_path):
+    key = neko_plugin_cli.keygen(tmp_path / "key")
+    res = neko_plugin_cli.pack(
+        root=neko_repo, out="-", manifest_version=2, sign_key=key["secret_key"], verify=True
+    )
+    out = neko_plugin_cli.unpack(
+        res["data"], tmp_path / "installed", verify_key=key["public_key"], require_signature=True
+    )
+    assert out["signed_by"] == key["fingerprint"]
+
+
+def test_stream_with_split_is_rejected(neko_repo):
+    with pytest.raises(ValueError, match="cannot be combined with split"):
+        neko_plugin_cli.pack(root=neko_repo, out="-", split=True)
+
+
+def test_garbage_bytes_are_rejected(tmp_path):
+    with pytest.raises(ValueError, match="failed to read zip <bytes>"):
+        neko_plugin_cli.unpack(b"not a zip", tmp_path / "installed")
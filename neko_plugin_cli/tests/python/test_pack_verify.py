@@ -0,0 +1,70 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+from conftest import rewrite_manifest
+
+import neko_plugin_cli
+
+
+def _flip_byte_in(zip_path, entry):
+    with zipfile.ZipFile(zip_path) as z:
+        info = z.getinfo(entry)
+    data = bytearray(zip_path.read_bytes())
+    # Local header is 30 bytes plus the name and extra field; the stored payload follows
+    header = info.header_offset
+    name_len = int.from_bytes(data[header + 26 : header + 28], "little")
+    extra_len = int.from_bytes(data[header + 28 : header + 30], "little")
+    data[header + 30 + name_len + extra_len] ^= 0xFF
+    zip_path.write_bytes(bytes(data))
+
+
+@pytest.mark.parametrize("manifest_version", [1, 2])
+def test_pack_with_verify(neko_repo, tmp_path, manifest_version):
+    out = tmp_path / "b.zip"
+    res = neko_plugin_cli.pack(root=neko_repo, out=out, manifest_version=manifest_version, verify=True)
+    report = neko_plugin_cli.verify_bundle(out)
+    assert report["plugins"] == 2
+    assert report["folder_hashes"] == 2
+    listed = sum(len(p.get("files") or []) for p in res["manifest"]["plugins"])
+    assert report["file_checksums"] == listed
+    assert (listed > 0) == (manifest_version == 2)
+
+
+@pytest.mark.parametrize("manifest_version", [1, 2])
+def test_corrupt_byte_detected(neko_repo, tmp_path, manifest_version):
+    out = tmp_path / "b.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, manifest_version=manifest_version, compression="stored")
+    _flip_byte_in(out, "plugins/beta_dir/beta.py")
+    with pytest.raises(OSError, match=r"verification failed for .*b\.zip: failed to read plugins/beta_dir/beta\.py"):
+        neko_plugin_cli.verify_bundle(out)
+
+
+def test_truncated_bundle_detected(neko_repo, tmp_path):
+    out = tmp_path / "b.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out)
+    out.write_bytes(out.read_bytes()[:-100])
+    with pytest.raises(Exception, match="verification failed"):
+        neko_plugin_cli.verify_bundle(out)
+
+
+def test_folder_hash_mismatch_detected(neko_repo, tmp_path):
+    out = tmp_path / "b.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, hash="sha256")
+    bad = tmp_path / "bad.zip"
+    rewrite_manifest(out, bad, lambda t: t.replace('hash = "', 'hash = "0', 1))
+    with pytest.raises(ValueError, match="sha256 hash of plugin alpha does not match the manifest"):
+        neko_plugin_cli.verify_bundle(bad)
+
+
+def test_missing_listed_file_detected(neko_repo, tmp_path):
+    out = tmp_path / "b.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, manifest_version=2, no_md5=True)
+    bad = tmp_path / "bad.zip"
+    with zipfile.ZipFile(out) as src, zipfile.ZipFile(bad, "w") as dst:
+        for info in src.infolist():
+            if info.filename != "plugins/beta_dir/beta.py":
+                dst.writestr(info, src.read(info))
+    with pytest.raises(ValueError, match="plugins/beta_dir/beta.py is listed in the manifest but missing"):
+        neko_plugin_cli.verify_bundle(bad)
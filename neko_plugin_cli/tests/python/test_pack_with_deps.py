@@ -0,0 +1,54 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+from conftest import write_plugin
+
+
+def _plugin_toml(pid: str, deps: list[str] = ()) -> str:
+    text = f'[plugin]\nid = "{pid}"\nname = "{pid}"\nversion = "1.0.0"\nentry = "{pid}:main"\n'
+    for dep in deps:
+        text += f'\n[[plugin.dependency]]\nid = "{dep}"\nsupported = ">=1.0.0"\n'
+    return text
+
+
+@pytest.fixture
+def chain_repo(neko_repo):
+    """neko_repo plus top -> mid -> alpha, and an unrelated plugin."""
+    write_plugin(neko_repo, "mid", _plugin_toml("mid", ["alpha"]), {"mid.py": "x = 1\n"})
+    write_plugin(neko_repo, "top", _plugin_toml("top", ["mid"]), {"top.py": "x = 2\n"})
+    write_plugin(neko_repo, "lone", _plugin_toml("lone"))
+    return neko_repo
+
+
+def test_with_deps_includes_transitive_closure(chain_repo, tmp_path):
+    out = tmp_path / "top.zip"
+    res = neko_plugin_cli.pack(root=chain_repo, plugin_ids=["top"], out=out, with_deps=True)
+    plugins = {p["id"]: p for p in res["manifest"]["plugins"]}
+    assert sorted(plugins) == ["alpha", "mid", "top"]
+    assert plugins["top"].get("implicit", False) is False
+    assert plugins["mid"]["implicit"] is True
+    assert plugins["alpha"]["implicit"] is True
+
+    names = zipfile.ZipFile(out).namelist()
+    assert "plugins/alpha/__init__.py" in names
+    assert not any(n.startswith("plugins/lone/") for n in names)
+
+
+def test_without_flag_only_selected_plugin(chain_repo, tmp_path):
+    res = neko_plugin_cli.pack(root=chain_repo, plugin_ids=["top"], out=tmp_path / "top.zip")
+    assert [p["id"] for p in res["manifest"]["plugins"]] == ["top"]
+
+
+def test_default_output_named_after_requested_plugin(chain_repo):
+    res = neko_plugin_cli.pack(root=chain_repo, plugin_ids=["top"], with_deps=True, dry_run=True)
+    assert res["out_path"] == "neko_plugin_top_1.0.0.zip"
+
+
+def test_missing_dependency_is_an_error(chain_repo, tmp_path):
+    write_plugin(chain_repo, "broken", _plugin_toml("broken", ["ghost"]))
+    with pytest.raises(ValueError, match="plugin broken depends on ghost, which is not available"):
+        neko_plugin_cli.pack(root=chain_repo, plugin_ids=["broken"], out=tmp_path / "x.zip", with_deps=True)
@@ -0,0 +1,57 @@
+from __future__ import annotations
+
+from pathlib import Path
+
+import pytest
+
+import neko_plugin_cli
+
+
+def test_dependents_block_removal(neko_repo):
+    alpha = neko_repo / "plugin" / "plugins" / "alpha"
+    with pytest.raises(ValueError, match=r"use --force.*\n  beta depends on alpha"):
+        neko_plugin_cli.remove(["alpha"], root=neko_repo)
+    assert alpha.is_dir()
+
+    res = neko_plugin_cli.remove(["alpha"], root=neko_repo, dry_run=True)
+    assert res["dry_run"] is True
+    assert res["dependents"] == [{"id": "beta", "depends_on": "alpha"}]
+    assert [p["id"] for p in res["removed"]] == ["alpha"]
+    assert alpha.is_dir()
+
+    res = neko_plugin_cli.remove(["alpha"], root=neko_repo, force=True)
+    assert res["dependents"] == [{"id": "beta", "depends_on": "alpha"}]
+    assert not alpha.exists()
+    assert any("depends on missing plugin alpha" in e for e in neko_plugin_cli.check(root=neko_repo)["errors"])
+
+
+def test_removing_a_plugin_with_its_dependents_is_clean(neko_repo):
+    plugins = neko_repo / "plugin" / "plugins"
+    res = neko_plugin_cli.remove(["beta", "alpha"], root=neko_repo)
+    assert res["dependents"] == []
+    assert [(p["id"], p["version"], p["folder"], p["backup"]) for p in res["removed"]] == [
+        ("beta", "0.3.0", "beta_dir", None),
+        ("alpha", "1.0.0", "alpha", None),
+    ]
+    assert list(plugins.iterdir()) == []
+
+
+def test_backup_before_removal(neko_repo):
+    plugins = neko_repo / "plugin" / "plugins"
+    res = neko_plugin_cli.remove(["beta"], root=neko_repo, backup=True)
+    backup = res["removed"][0]["backup"]
+    assert backup.startswith(str(plugins / ".backups" / "beta_dir_"))
+    assert not (plugins / "beta_dir").exists()
+    assert (plugins / ".backups").is_dir()
+
+    res = neko_plugin_cli.remove(["alpha"], root=neko_repo, backup_dir=neko_repo / "bak")
+    backup = res["removed"][0]["backup"]
+    files = sorted(str(p.relative_to(backup)) for p in Path(backup).rglob("*") if p.is_file())
+    # excluded files (__pycache__) are not backed up, as with unpack --backup
+    assert files == ["__init__.py", "data/config.json", "plugin.toml", "profiles.toml"]
+
+
+def test_unknown_id_removes_nothing(neko_repo):
+    with pytest.raises(ValueError, match="plugin gamma not found"):
+        neko_plugin_cli.remove(["beta", "gamma"], root=neko_repo)
+    assert (neko_repo / "plugin" / "plugins" / "beta_dir").is_dir()
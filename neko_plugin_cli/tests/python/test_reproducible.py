@@ -0,0 +1,46 @@
+from __future__ import annotations
+
+import os
+import time
+import zipfile
+
+import neko_plugin_cli
+
+
+def _pack_demo(neko_repo, out, **kwargs):
+    return neko_plugin_cli.pack(root=neko_repo, out=out, bundle_name="demo", bundle_version="1.0", **kwargs)
+
+
+def test_reproducible_output_is_byte_identical(neko_repo, tmp_path):
+    first = tmp_path / "first.zip"
+    second = tmp_path / "second.zip"
+    _pack_demo(neko_repo, first, reproducible=True, manifest_version=2)
+
+    time.sleep(1.1)
+    for p in (neko_repo / "plugin" / "plugins").rglob("*"):
+        os.utime(p)
+    _pack_demo(neko_repo, second, reproducible=True, manifest_version=2)
+
+    assert first.read_bytes() == second.read_bytes()
+    manifest = zipfile.ZipFile(first).read("manifest.toml").decode()
+    assert 'packed_at = "1980-01-01T00:00:00Z"' in manifest
+    assert {i.date_time for i in zipfile.ZipFile(first).infolist()} == {(1980, 1, 1, 0, 0, 0)}
+
+
+def test_source_date_epoch(neko_repo, tmp_path):
+    epoch = 1_700_000_000  # 2023-11-14T22:13:20Z
+    res = _pack_demo(neko_repo, tmp_path / "a.zip", source_date_epoch=epoch)
+    assert res["manifest"]["packed_at"] == "2023-11-14T22:13:20Z"
+    assert {i.date_time for i in zipfile.ZipFile(tmp_path / "a.zip").infolist()} == {(2023, 11, 14, 22, 13, 20)}
+
+    os.environ["SOURCE_DATE_EPOCH"] = str(epoch)
+    try:
+        _pack_demo(neko_repo, tmp_path / "b.zip")
+    finally:
+        del os.environ["SOURCE_DATE_EPOCH"]
+    assert (tmp_path / "a.zip").read_bytes() == (tmp_path / "b.zip").read_bytes()
+
+
+def test_default_uses_current_time(neko_repo, tmp_path):
+    res = _pack_demo(neko_repo, tmp_path / "now.zip")
+    assert not res["manifest"]["packed_at"].startswith("1980")
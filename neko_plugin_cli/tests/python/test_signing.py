@@ -0,0 +1,94 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+from conftest import rewrite_zip
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def keys(tmp_path):
+    (tmp_path / "keys").mkdir()
+    return neko_plugin_cli.keygen(tmp_path / "keys" / "neko.key")
+
+
+@pytest.fixture
+def signed(neko_repo, tmp_path, keys):
+    out = tmp_path / "signed.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, manifest_version=2, sign_key=keys["secret_key"])
+    return out
+
+
+def test_keygen_writes_key_pair(keys, tmp_path):
+    assert keys["public_key"].endswith("neko.key.pub")
+    assert len(keys["fingerprint"]) == 64
+    assert len(open(keys["secret_key"]).read().strip()) == 64
+    with pytest.raises(ValueError, match="refusing to overwrite"):
+        neko_plugin_cli.keygen(keys["secret_key"])
+    again = neko_plugin_cli.keygen(keys["secret_key"], force=True)
+    assert again["fingerprint"] != keys["fingerprint"]
+
+
+def test_signed_bundle_verifies(signed, keys, tmp_path):
+    with zipfile.ZipFile(signed) as z:
+        sig = z.read("manifest.sig").decode()
+    assert 'algorithm = "ed25519"' in sig
+    assert keys["fingerprint"] in sig
+
+    res = neko_plugin_cli.unpack(signed, tmp_path / "dest", verify_key=keys["public_key"], require_signature=True)
+    assert res["signed_by"] == keys["fingerprint"]
+    assert sorted(p["id"] for p in res["installed"]) == ["alpha", "beta"]
+
+
+def test_signing_requires_manifest_v2(neko_repo, tmp_path, keys):
+    with pytest.raises(ValueError, match="manifest_version=2"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "v1.zip", sign_key=keys["secret_key"])
+
+
+def test_tampered_manifest_rejected(signed, keys, tmp_path):
+    bad = tmp_path / "bad.zip"
+    rewrite_zip(signed, bad, lambda n, d: d.replace(b'version = "0.3.0"', b'version = "9.9.9"') if n == "manifest.toml" else d)
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="signature does not match manifest.toml"):
+        neko_plugin_cli.unpack(bad, dest, verify_key=keys["public_key"])
+    assert not dest.exists()
+
+
+def test_tampered_plugin_file_rejected_before_extracting(signed, keys, tmp_path):
+    bad = tmp_path / "bad.zip"
+    rewrite_zip(signed, bad, lambda n, d: d + b"import os\n" if n == "plugins/beta_dir/beta.py" else d)
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="plugins/beta_dir/beta.py does not match its manifest checksum"):
+        neko_plugin_cli.preview_unpack(bad, dest, verify_key=keys["public_key"])
+    with pytest.raises(ValueError, match="does not match its manifest checksum"):
+        neko_plugin_cli.unpack(bad, dest, verify_key=keys["public_key"])
+    assert not dest.exists()
+
+
+def test_injected_file_rejected(signed, keys, tmp_path):
+    bad = tmp_path / "bad.zip"
+    bad.write_bytes(signed.read_bytes())
+    with zipfile.ZipFile(bad, "a") as z:
+        z.writestr("plugins/beta_dir/evil.py", "print('hi')\n")
+    with pytest.raises(ValueError, match="evil.py is not listed in the manifest"):
+        neko_plugin_cli.unpack(bad, tmp_path / "dest", verify_key=keys["public_key"])
+
+
+def test_wrong_key_rejected(signed, tmp_path):
+    (tmp_path / "other").mkdir()
+    other = neko_plugin_cli.keygen(tmp_path / "other" / "k")
+    with pytest.raises(ValueError, match="not by the verify key"):
+        neko_plugin_cli.unpack(signed, tmp_path / "dest", verify_key=other["public_key"])
+
+
+def test_unsigned_bundle(neko_repo, keys, tmp_path):
+    out = tmp_path / "plain.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, manifest_version=2)
+    with pytest.raises(ValueError, match="bundle is not signed"):
+        neko_plugin_cli.unpack(out, tmp_path / "dest", verify_key=keys["public_key"], require_signature=True)
+
+    res = neko_plugin_cli.unpack(out, tmp_path / "dest", verify_key=keys["public_key"])
+    assert res["signed_by"] is None
+    assert any("not signed" in w for w in res["warnings"])
@@ -0,0 +1,132 @@
+from __future__ import annotations
+
+import os
+import sys
+import zipfile
+
+import pytest
+from conftest import alpha_hash, alpha_names
+
+import neko_plugin_cli
+
+unix_only = pytest.mark.skipif(sys.platform == "win32", reason="symlinks need unix")
+
+
+@pytest.fixture
+def linked_repo(neko_repo, tmp_path):
+    if sys.platform == "win32":
+        pytest.skip("symlinks need unix")
+    alpha = neko_repo / "plugin" / "plugins" / "alpha"
+    shared = tmp_path / "shared"
+    (shared / "assets").mkdir(parents=True)
+    (shared / "assets" / "logo.txt").write_text("logo\n")
+    (shared / "readme.md").write_text("shared readme\n")
+    os.symlink(shared / "readme.md", alpha / "README.md")
+    os.symlink(shared / "assets", alpha / "assets")
+    return neko_repo
+
+
+def test_links_are_skipped_by_default(linked_repo, tmp_path):
+    out = tmp_path / "x.zip"
+    res = neko_plugin_cli.pack(root=linked_repo, out=out)
+    names = alpha_names(out)
+    assert "README.md" not in names
+    assert not any(n.startswith("assets/") for n in names)
+    assert alpha_hash(res) == alpha_hash(neko_plugin_cli.pack(root=linked_repo, out=tmp_path / "y.zip", symlinks="skip"))
+
+
+def test_follow_packs_link_targets_as_regular_files(linked_repo, tmp_path):
+    out = tmp_path / "x.zip"
+    res = neko_plugin_cli.pack(root=linked_repo, out=out, symlinks="follow", manifest_version=2)
+    with zipfile.ZipFile(out) as z:
+        assert z.read("plugins/alpha/README.md") == b"shared readme\n"
+        assert z.read("plugins/alpha/assets/logo.txt") == b"logo\n"
+        assert not z.getinfo("plugins/alpha/README.md").external_attr >> 16 & 0o120000 == 0o120000
+    skipped = neko_plugin_cli.pack(root=linked_repo, out=tmp_path / "y.zip")
+    assert alpha_hash(res) != alpha_hash(skipped)
+    neko_plugin_cli.verify_bundle(out)
+
+    dest = tmp_path / "installed"
+    neko_plugin_cli.unpack(out, dest)
+    assert not (dest / "alpha" / "README.md").is_symlink()
+    assert (dest / "alpha" / "README.md").read_text() == "shared readme\n"
+
+
+def test_error_lists_offending_links(linked_repo, tmp_path):
+    with pytest.raises(ValueError, match=r"contains symlinks \(use --symlinks skip or --symlinks follow\): README.md, assets"):
+        neko_plugin_cli.pack(root=linked_repo, out=tmp_path / "x.zip", symlinks="error")
+    with pytest.raises(ValueError, match="contains symlinks"):
+        neko_plugin_cli.pack(root=linked_repo, out=tmp_path / "x.zip", symlinks="error", no_md5=True)
+
+
+def test_excluded_links_do_not_error(linked_repo, tmp_path):
+    neko_plugin_cli.pack(
+        root=linked_repo, out=tmp_path / "x.zip", symlinks="error", excludes=["README.md", "assets"]
+    )
+
+
+def test_unknown_policy_rejected(neko_repo, tmp_path):
+    with pytest.raises(ValueError, match="unsupported symlink policy"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", symlinks="copy")
+
+
+def _with_links(bundle, out, links):
+    with zipfile.ZipFile(bundle) as src, zipfile.ZipFile(out, "w") as dst:
+        for info in src.infolist():
+            dst.writestr(info, src.read(info))
+        for name, target in links.items():
+            info = zipfile.ZipInfo(name)
+            info.create_system = 3
+            info.external_attr = (0o120777 << 16)
+            dst.writestr(info, target)
+    return out
+
+
+def test_unpack_refuses_symlink_entries(bundle, tmp_path):
+    evil = _with_links(bundle, tmp_path / "links.zip", {"plugins/alpha/cfg": "data/config.json"})
+    dest = tmp_path / "installed"
+    with pytest.raises(ValueError, match=r"contains symlink entries \(use --allow-symlinks to create them\): plugins/alpha/cfg"):
+        neko_plugin_cli.unpack(evil, dest)
+    assert not dest.exists()
+
+
+@unix_only
+def test_allow_symlinks_creates_links_inside_destination(bundle, tmp_path):
+    links = _with_links(
+        bundle,
+        tmp_path / "links.zip",
+        {
+            "plugins/alpha/cfg": "data/config.json",
+            "plugins/alpha/data/peer": "../../beta_dir/beta.py",
+            "plugins/alpha/abs": "/etc/passwd",
+            "plugins/alpha/up": "../../../outside",
+            "plugins/alpha/sneaky": "data/../../../outside",
+        },
+    )
+    dest = tmp_path / "installed"
+    res = neko_plugin_cli.unpack(links, dest, allow_symlinks=True)
+
+    assert os.readlink(dest / "alpha" / "cfg") == "data/config.json"
+    assert (dest / "alpha" / "cfg").read_text() == '{"a": 1}\n'
+    assert (dest / "alpha" / "data" / "peer").read_text() == "def main():\n    return 2\n"
+    assert sorted(l["path"] for l in res["symlinks"]) == ["alpha/cfg", "alpha/data/peer"]
+    for name in ["abs", "up", "sneaky"]:
+        assert not (dest / "alpha" / name).is_symlink()
+        assert any(w.startswith(f"skipped symlink pointing outside the destination: plugins/alpha/{name}") for w in res["warnings"])
+
+
+@unix_only
+def test_files_below_a_link_are_not_written(bundle, tmp_path):
+    dest = tmp_path / "installed"
+    links = tmp_path / "links.zip"
+    with zipfile.ZipFile(bundle) as src, zipfile.ZipFile(links, "w") as dst:
+        for info in src.infolist():
+            dst.writestr(info, src.read(info))
+        info = zipfile.ZipInfo("plugins/alpha/up")
+        info.create_system = 3
+        info.external_attr = (0o120777 << 16)
+        dst.writestr(info, "..")
+        dst.writestr("plugins/alpha/up/beta_dir/beta.py", "owned\n")
+    res = neko_plugin_cli.unpack(links, dest, allow_symlinks=True)
+    assert (dest / "beta_dir" / "beta.py").read_text() == "def main():\n    return 2\n"
+    assert "skipped path below a symlink: plugins/alpha/up/beta_dir/beta.py" in res["warnings"]
@@ -0,0 +1,99 @@
+from __future__ import annotations
+
+import os
+import sys
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+UNICODE_FILES = {
+    "数据/配置 文件.json": '{"名字": "猫"}\n',
+    "docs/read me.md": "spaces\n",
+    "émoji 🎉.txt": "party\n",
+}
+
+# Six 50-character folders put the installed path well past Windows' 260-character MAX_PATH
+DEEP = "/".join(f"{i}" * 50 for i in range(6)) + "/深い ファイル.txt"
+
+
+@pytest.fixture
+def unicode_repo(neko_repo):
+    alpha = neko_repo / "plugin" / "plugins" / "alpha"
+    for rel, text in {**UNICODE_FILES, DEEP: "deep\n"}.items():
+        path = alpha / rel
+        path.parent.mkdir(parents=True, exist_ok=True)
+        path.write_text(text, encoding="utf-8")
+    return neko_repo
+
+
+@pytest.mark.parametrize("manifest_version", [1, 2])
+def test_unicode_names_round_trip(unicode_repo, tmp_path, manifest_version):
+    out = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=unicode_repo, out=out, manifest_version=manifest_version)
+
+    with zipfile.ZipFile(out) as z:
+        for rel in [*UNICODE_FILES, DEEP]:
+            info = z.getinfo(f"plugins/alpha/{rel}")
+            assert info.flag_bits & 0x800 or info.filename.isascii()
+        assert not any("\\" in n for n in z.namelist())
+    neko_plugin_cli.verify_bundle(out)
+
+    dest = tmp_path / "installed"
+    res = neko_plugin_cli.unpack(out, dest)
+    assert res["warnings"] == []
+    for rel, text in UNICODE_FILES.items():
+        assert (dest / "alpha" / rel).read_text(encoding="utf-8") == text
+    deep = dest / "alpha" / DEEP
+    assert len(str(deep)) > 260
+    assert deep.read_text(encoding="utf-8") == "deep\n"
+
+
+def test_unicode_folder_hash_is_stable_across_unpack(neko_repo, tmp_path):
+    beta = neko_repo / "plugin" / "plugins" / "beta_dir"
+    for rel, text in UNICODE_FILES.items():
+        (beta / rel).parent.mkdir(parents=True, exist_ok=True)
+        (beta / rel).write_text(text, encoding="utf-8")
+    out = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=neko_repo, plugin_ids=["beta"], out=out)
+    dest = tmp_path / "installed"
+    neko_plugin_cli.unpack(out, dest)
+    again = neko_plugin_cli.unpack(out, dest)
+    assert [p["reason"] for p in again["skipped"]] == ["identical (md5 match)"]
+
+
+def test_name_length_limit_lists_every_offending_file(unicode_repo, tmp_path):
+    out = tmp_path / "bundle.zip"
+    with pytest.raises(ValueError) as exc:
+        neko_plugin_cli.pack(root=unicode_repo, out=out, max_name_len=40)
+    msg = str(exc.value)
+    assert "see --max-name-len" in msg
+    assert f"plugins/alpha/{DEEP}: zip entry name is {len('plugins/alpha/' + DEEP)} characters long (limit 40" in msg
+    assert "plugins/alpha/数据/配置 文件.json" not in msg
+    assert not out.exists()
+
+
+def test_name_length_counts_utf16_units(unicode_repo, tmp_path):
+    name = "plugins/alpha/émoji 🎉.txt"
+    limit = len(name.encode("utf-16-le")) // 2
+    assert limit == len(name) + 1
+    with pytest.raises(ValueError, match="émoji"):
+        neko_plugin_cli.pack(root=unicode_repo, out=tmp_path / "x.zip", max_name_len=limit - 1)
+    with pytest.raises(ValueError) as exc:
+        neko_plugin_cli.pack(root=unicode_repo, out=tmp_path / "x.zip", max_name_len=limit)
+    assert "émoji" not in str(exc.value)
+
+
+def test_dry_run_checks_names_too(unicode_repo, tmp_path):
+    with pytest.raises(ValueError, match="zip entry name is"):
+        neko_plugin_cli.pack(root=unicode_repo, out=tmp_path / "x.zip", max_name_len=40, dry_run=True)
+
+
+@pytest.mark.skipif(sys.platform != "linux", reason="only Linux allows non-UTF-8 file names")
+def test_non_utf8_name_rejected(neko_repo, tmp_path):
+    alpha = os.fsencode(neko_repo / "plugin" / "plugins" / "alpha")
+    with open(alpha + b"/bad\xff.txt", "wb") as f:
+        f.write(b"x")
+    with pytest.raises(ValueError, match=r"bad.*\.txt: file name is not valid UTF-8"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", no_md5=True)
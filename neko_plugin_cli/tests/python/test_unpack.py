@@ -0,0 +1,188 @@
+from __future__ import annotations
+
+import errno
+import zipfile
+
+import pytest
+from conftest import pack_bundle, rewrite_manifest
+
+import neko_plugin_cli
+
+
+def _by_folder(items):
+    return {it["folder"]: it for it in items}
+
+
+def test_preview_into_empty_dest(bundle, tmp_path):
+    items = _by_folder(neko_plugin_cli.preview_unpack(bundle, tmp_path / "dest"))
+    assert sorted(items) == ["alpha", "beta_dir"]
+    assert items["beta_dir"]["id"] == "beta"
+    assert all(it["will_install"] for it in items.values())
+    assert all(it["reason"] for it in items.values())
+
+
+def test_unpack_then_preview_and_reinstall(bundle, tmp_path):
+    dest = tmp_path / "dest"
+    res = neko_plugin_cli.unpack(bundle, dest)
+    assert sorted(p["folder"] for p in res["installed"]) == ["alpha", "beta_dir"]
+    assert res["skipped"] == []
+    assert res["warnings"] == []
+    assert (dest / "alpha" / "data" / "config.json").read_text() == '{"a": 1}\n'
+    assert (dest / "alpha" / "_bundle_profiles" / "bundle_profiles" / "bundle").is_dir()
+
+    items = _by_folder(neko_plugin_cli.preview_unpack(bundle, dest))
+    assert not any(it["will_install"] for it in items.values())
+
+    again = neko_plugin_cli.unpack(bundle, dest)
+    assert again["installed"] == []
+    skipped = _by_folder(again["skipped"])
+    assert sorted(skipped) == ["alpha", "beta_dir"]
+    assert skipped["beta_dir"]["reason"] == "identical (md5 match)"
+    assert len(again["warnings"]) == 2
+
+
+def test_modified_plugin_needs_force(bundle, tmp_path):
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+    (dest / "beta_dir" / "beta.py").write_text("changed\n")
+
+    assert _by_folder(neko_plugin_cli.preview_unpack(bundle, dest))["beta_dir"]["will_install"] is False
+    assert _by_folder(neko_plugin_cli.preview_unpack(bundle, dest, force=True))["beta_dir"]["will_install"] is True
+
+    res = neko_plugin_cli.unpack(bundle, dest)
+    assert "--force" in _by_folder(res["skipped"])["beta_dir"]["reason"]
+    assert (dest / "beta_dir" / "beta.py").read_text() == "changed\n"
+
+    res = neko_plugin_cli.unpack(bundle, dest, force=True)
+    assert _by_folder(res["installed"])["beta_dir"]["reason"] == "overwritten (--force)"
+    assert (dest / "beta_dir" / "beta.py").read_text() == "def main():\n    return 2\n"
+
+
+def test_missing_zip_carries_path(tmp_path):
+    missing = tmp_path / "nope.zip"
+    with pytest.raises(OSError) as ei:
+        neko_plugin_cli.preview_unpack(missing, tmp_path / "dest")
+    assert ei.value.errno == errno.ENOENT
+    assert ei.value.filename == str(missing)
+
+
+def test_corrupt_zip_carries_path(tmp_path):
+    bad = tmp_path / "bad.zip"
+    bad.write_bytes(b"not a zip")
+    with pytest.raises(ValueError, match="failed to read zip") as ei:
+        neko_plugin_cli.unpack(bad, tmp_path / "dest")
+    assert ei.value.filename == str(bad)
+
+
+def test_unwritable_dest_carries_path(bundle, tmp_path):
+    blocker = tmp_path / "file"
+    blocker.write_text("x")
+    with pytest.raises(OSError) as ei:
+        neko_plugin_cli.unpack(bundle, blocker / "dest")
+    assert ei.value.filename == str(blocker / "dest")
+
+
+def test_sha256_bundle_skips_identical(neko_repo, tmp_path):
+    out = tmp_path / "sha.zip"
+    res = neko_plugin_cli.pack(root=neko_repo, out=out, hash="sha256")
+    beta = next(p for p in res["manifest"]["plugins"] if p["id"] == "beta")
+    assert beta["hash_algo"] == "sha256"
+    assert len(beta["hash"]) == 64
+    assert beta["md5"] is None
+
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(out, dest)
+    assert _by_folder(neko_plugin_cli.preview_unpack(out, dest))["beta_dir"]["will_install"] is False
+    again = _by_folder(neko_plugin_cli.unpack(out, dest)["skipped"])
+    assert again["beta_dir"]["reason"] == "identical (sha256 match)"
+
+
+def test_legacy_md5_only_manifest(bundle, tmp_path):
+    # Bundles written before hash_algo existed only carry the md5 key
+    def strip_hash(text):
+        text = "\n".join(l for l in text.splitlines() if not l.startswith(("hash_algo", "hash ")))
+        assert "md5 =" in text and "hash" not in text
+        return text
+
+    legacy = tmp_path / "legacy.zip"
+    rewrite_manifest(bundle, legacy, strip_hash)
+
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(legacy, dest)
+    again = _by_folder(neko_plugin_cli.unpack(legacy, dest)["skipped"])
+    assert again["beta_dir"]["reason"] == "identical (md5 match)"
+
+
+def test_unknown_hash_algorithm_rejected(neko_repo, tmp_path):
+    with pytest.raises(ValueError, match="unsupported hash algorithm"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", hash="crc32")
+
+
+def test_v2_manifest_lists_files(bundle):
+    res = neko_plugin_cli.preview_unpack(bundle, bundle.parent / "empty")
+    assert all(it["changed_files"] == [] for it in res)
+    text = zipfile.ZipFile(bundle).read("manifest.toml").decode()
+    assert "format_version = 2" in text
+    assert 'path = "beta.py"' in text
+    assert "sha256 = " in text
+
+
+def test_v1_manifest_has_no_file_list(neko_repo, tmp_path):
+    v1 = pack_bundle(neko_repo, tmp_path / "v1.zip")
+    text = zipfile.ZipFile(v1).read("manifest.toml").decode()
+    assert "format_version = 1" in text
+    assert "sha256 = " not in text
+    assert zipfile.ZipFile(v1).namelist()[0] == "manifest.toml"
+
+
+def test_v2_reports_changed_files(bundle, tmp_path):
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+    (dest / "beta_dir" / "beta.py").write_text("changed\n")
+    (dest / "beta_dir" / "notes.txt").write_text("local\n")
+    (dest / "alpha" / "data" / "config.json").unlink()
+
+    items = _by_folder(neko_plugin_cli.preview_unpack(bundle, dest))
+    assert items["beta_dir"]["changed_files"] == [
+        {"path": "beta.py", "kind": "modified"},
+        {"path": "notes.txt", "kind": "extra"},
+    ]
+    assert {"path": "data/config.json", "kind": "missing"} in items["alpha"]["changed_files"]
+
+    skipped = _by_folder(neko_plugin_cli.unpack(bundle, dest)["skipped"])
+    assert [f["path"] for f in skipped["beta_dir"]["changed_files"]] == ["beta.py", "notes.txt"]
+
+
+def test_v2_without_folder_hash_uses_file_checksums(neko_repo, tmp_path):
+    out = tmp_path / "nohash.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, no_md5=True, manifest_version=2)
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(out, dest)
+    skipped = _by_folder(neko_plugin_cli.unpack(out, dest)["skipped"])
+    assert skipped["beta_dir"]["reason"] == "identical (per-file sha256 match)"
+
+
+def test_v2_checksum_mismatch_aborts(bundle, tmp_path):
+    def corrupt(text):
+        lines = text.splitlines()
+        i = lines.index('path = "beta.py"')
+        j = next(k for k in range(i, len(lines)) if lines[k].startswith("sha256 = "))
+        lines[j] = 'sha256 = "' + "0" * 64 + '"'
+        return "\n".join(lines)
+
+    bad = tmp_path / "bad.zip"
+    rewrite_manifest(bundle, bad, corrupt)
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="checksum mismatch") as ei:
+        neko_plugin_cli.unpack(bad, dest)
+    assert ei.value.filename == str(dest / "beta_dir" / "beta.py")
+    assert not (dest / "beta_dir" / "beta.py").exists()
+
+
+def test_newer_manifest_version_rejected(neko_repo, bundle, tmp_path):
+    newer = tmp_path / "v9.zip"
+    rewrite_manifest(bundle, newer, lambda t: t.replace("format_version = 2", "format_version = 9"))
+    with pytest.raises(ValueError, match="unsupported manifest format_version 9"):
+        neko_plugin_cli.preview_unpack(newer, tmp_path / "dest")
+    with pytest.raises(ValueError, match="unsupported manifest version"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", manifest_version=3)
@@ -0,0 +1,89 @@
+from __future__ import annotations
+
+import pytest
+from conftest import break_checksum, snapshot, write_plugin
+
+import neko_plugin_cli
+
+
+def test_failure_keeps_earlier_plugins_and_drops_the_failed_one(bundle, tmp_path):
+    bad = break_checksum(bundle, tmp_path / "bad.zip", "beta.py")
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError) as ei:
+        neko_plugin_cli.unpack(bad, dest)
+    msg = str(ei.value)
+    assert "unpack stopped at plugin 'beta'; fully installed before the failure: alpha" in msg
+    assert "checksum mismatch" in msg
+
+    assert (dest / "alpha" / "data" / "config.json").read_text() == '{"a": 1}\n'
+    assert (dest / "alpha" / "_bundle_profiles").is_dir()
+    assert not (dest / "beta_dir").exists()
+    assert sorted(p.name for p in dest.iterdir()) == ["alpha"]
+
+
+def test_failure_in_first_plugin_installs_nothing(bundle, tmp_path):
+    bad = break_checksum(bundle, tmp_path / "bad.zip", "data/config.json")
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="unpack stopped at plugin 'alpha'; fully installed before the failure: none"):
+        neko_plugin_cli.unpack(bad, dest)
+    assert list(dest.iterdir()) == []
+
+
+def test_failed_overwrite_leaves_existing_plugin_unchanged(bundle, tmp_path):
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+    beta = dest / "beta_dir"
+    (beta / "beta.py").write_text("local edit\n")
+    (beta / "notes.txt").write_text("user data\n")
+    before = snapshot(beta)
+
+    bad = break_checksum(bundle, tmp_path / "bad.zip", "beta.py")
+    with pytest.raises(ValueError, match="unpack stopped at plugin 'beta'"):
+        neko_plugin_cli.unpack(bad, dest, force=True)
+    assert snapshot(beta) == before
+    assert sorted(p.name for p in dest.iterdir()) == ["alpha", "beta_dir"]
+
+
+def test_overwrite_keeps_files_the_bundle_does_not_carry(bundle, tmp_path):
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+    beta = dest / "beta_dir"
+    (beta / "beta.py").write_text("local edit\n")
+    (beta / "notes.txt").write_text("user data\n")
+
+    res = neko_plugin_cli.unpack(bundle, dest, force=True)
+    assert "beta_dir" in [p["folder"] for p in res["installed"]]
+    assert (beta / "beta.py").read_text() == "def main():\n    return 2\n"
+    assert (beta / "notes.txt").read_text() == "user data\n"
+    assert sorted(p.name for p in dest.iterdir()) == ["alpha", "beta_dir"]
+
+
+def test_stale_staging_folder_is_replaced(bundle, tmp_path):
+    dest = tmp_path / "dest"
+    stale = dest / ".neko_unpack_tmp" / "alpha"
+    stale.mkdir(parents=True)
+    (stale / "leftover.txt").write_text("from a crashed run\n")
+    neko_plugin_cli.unpack(bundle, dest)
+    assert not (dest / "alpha" / "leftover.txt").exists()
+    assert sorted(p.name for p in dest.iterdir()) == ["alpha", "beta_dir"]
+
+
+def test_parallel_failure_installs_exactly_the_plugins_before_it(repo, tmp_path):
+    for n in range(8):
+        write_plugin(
+            repo,
+            f"p{n}",
+            f'[plugin]\nid = "p{n}"\nname = "P{n}"\nversion = "1.0.0"\nentry = "p{n}:main"\n',
+            {"__init__.py": "def main():\n    pass\n", f"m{n}.py": "x = 1\n"},
+        )
+    bundle = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=repo, out=bundle, manifest_version=2)
+    bad = break_checksum(bundle, tmp_path / "bad.zip", "m4.py")
+
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="unpack stopped at plugin 'p4'; fully installed before the failure: p0, p1, p2, p3"):
+        neko_plugin_cli.unpack(bad, dest)
+    assert sorted(p.name for p in dest.iterdir()) == ["p0", "p1", "p2", "p3"]
+
+    res = neko_plugin_cli.unpack(bundle, dest)
+    assert [p["folder"] for p in res["installed"]] == ["p4", "p5", "p6", "p7"]
@@ -0,0 +1,86 @@
+from __future__ import annotations
+
+import re
+import zipfile
+from pathlib import Path
+
+import pytest
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def installed(neko_repo, tmp_path):
+    bundle = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=bundle, manifest_version=2)
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+    beta = dest / "beta_dir"
+    (beta / "beta.py").write_text("local edit\n")
+    (beta / "notes.txt").write_text("user data\n")
+    (beta / ".venv" / "lib").mkdir(parents=True)
+    (beta / ".venv" / "lib" / "site.py").write_text("venv\n")
+    (beta / "logs").mkdir()
+    (beta / "logs" / "run.log").write_text("log\n")
+    return bundle, dest
+
+
+def _files(root):
+    return {p.relative_to(root).as_posix(): p.read_text() for p in root.rglob("*") if p.is_file()}
+
+
+def test_backup_holds_pre_overwrite_content(installed):
+    bundle, dest = installed
+    res = neko_plugin_cli.unpack(bundle, dest, force=True, backup=True)
+
+    [backup] = [b for b in res["backups"] if b["id"] == "beta"]
+    path = Path(backup["path"])
+    assert path.parent == dest / ".backups"
+    assert backup["folder"] == "beta_dir"
+    assert re.fullmatch(r"beta_dir_\d{8}T\d{6}Z", path.name)
+    assert _files(path) == {
+        "plugin.toml": (dest / "beta_dir" / "plugin.toml").read_text(),
+        "beta.py": "local edit\n",
+        "notes.txt": "user data\n",
+    }
+    assert (dest / "beta_dir" / "beta.py").read_text() == "def main():\n    return 2\n"
+
+
+def test_only_overwritten_plugins_are_backed_up(installed, tmp_path):
+    bundle, dest = installed
+    res = neko_plugin_cli.unpack(bundle, dest, backup=True)
+    assert res["backups"] == []
+    assert not (dest / ".backups").exists()
+
+    # alpha only gained bundled profiles and is overwritten too; both go to the custom dir
+    res = neko_plugin_cli.unpack(bundle, dest, force=True, backup_dir=tmp_path / "saved")
+    assert sorted(b["folder"] for b in res["backups"]) == sorted(p["folder"] for p in res["installed"])
+    assert all(b["path"].startswith(str(tmp_path / "saved")) for b in res["backups"])
+
+
+def test_repeated_backups_get_distinct_folders(installed):
+    bundle, dest = installed
+    first = neko_plugin_cli.unpack(bundle, dest, force=True, backup=True)
+    (dest / "beta_dir" / "beta.py").write_text("second edit\n")
+    second = neko_plugin_cli.unpack(bundle, dest, force=True, backup=True)
+    [a] = [b["path"] for b in first["backups"] if b["id"] == "beta"]
+    [b] = [b["path"] for b in second["backups"] if b["id"] == "beta"]
+    assert a != b
+
+
+def test_failed_overwrite_discards_its_backup(installed, tmp_path):
+    bundle, dest = installed
+    bad = tmp_path / "bad.zip"
+    with zipfile.ZipFile(bundle) as src, zipfile.ZipFile(bad, "w") as dst:
+        for info in src.infolist():
+            data = src.read(info)
+            if info.filename == "manifest.toml":
+                lines = data.decode().splitlines()
+                i = lines.index('path = "beta.py"')
+                j = next(k for k in range(i, len(lines)) if lines[k].startswith("sha256 = "))
+                lines[j] = 'sha256 = "' + "0" * 64 + '"'
+                data = "\n".join(lines).encode()
+            dst.writestr(info, data)
+    with pytest.raises(ValueError, match="unpack stopped at plugin 'beta'"):
+        neko_plugin_cli.unpack(bad, dest, force=True, backup_dir=tmp_path / "saved")
+    assert not any(p.name.startswith("beta_dir_") for p in (tmp_path / "saved").iterdir())
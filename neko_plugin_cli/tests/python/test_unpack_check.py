@@ -0,0 +1,82 @@
+from __future__ import annotations
+
+import pytest
+from conftest import make_repo, pack_bundle, write_plugin
+
+import neko_plugin_cli
+
+PACKAGE = {"__init__.py": "def main():\n    pass\n"}
+
+
+def _gamma_toml(version, supported):
+    return (
+        f'[plugin]\nid = "gamma"\nname = "Gamma"\nversion = "{version}"\nentry = "gamma:main"\n\n'
+        f'[plugin.sdk]\nsupported = "{supported}"\n\n'
+        '[[plugin.dependency]]\nid = "alpha"\nsupported = ">=1.0.0"\n'
+    )
+
+
+@pytest.fixture
+def gamma_bundle(tmp_path):
+    """gamma needs SDK 2.x (the target repo has 1.2.0) and depends on alpha, which only the target has"""
+    root = make_repo(tmp_path / "source", sdk_version="2.1.0")
+    write_plugin(root, "gamma", _gamma_toml("2.0.0", ">=2.0.0, <3.0.0"), PACKAGE)
+    return pack_bundle(root, tmp_path / "bundle.zip", allow_invalid=True)
+
+
+@pytest.fixture
+def plugins(neko_repo):
+    return neko_repo / "plugin" / "plugins"
+
+
+def test_no_check_by_default(gamma_bundle, neko_repo, plugins):
+    res = neko_plugin_cli.unpack(gamma_bundle, plugins)
+    assert res["check"] is None
+    assert (plugins / "gamma").is_dir()
+
+
+def test_check_reports_sdk_conflict(gamma_bundle, neko_repo, plugins):
+    res = neko_plugin_cli.unpack(gamma_bundle, plugins, check=True, root=neko_repo)
+    report = res["check"]
+    assert report["sdk_version"] == "1.2.0"
+    assert report["plugins_checked"] == 1
+    assert len(report["errors"]) == 1
+    assert "gamma" in report["errors"][0] and "SDK_VERSION 1.2.0" in report["errors"][0]
+    # alpha is already installed, so the dependency resolves
+    assert not any("missing plugin" in e for e in report["errors"])
+    assert (plugins / "gamma").is_dir()
+
+
+def test_only_installed_plugins_are_checked(gamma_bundle, neko_repo, plugins):
+    write_plugin(neko_repo, "broken", '[plugin]\nid = "broken"\nversion = "1.0.0"\n\n[[plugin.dependency]]\nid = "nope"\n')
+    res = neko_plugin_cli.unpack(gamma_bundle, plugins, check=True, root=neko_repo)
+    assert not any("broken" in e for e in res["check"]["errors"])
+
+
+def test_strict_check_rolls_back_a_new_plugin(gamma_bundle, neko_repo, plugins):
+    with pytest.raises(ValueError, match=r"post-unpack check found 1 error\(s\), so the install was rolled back"):
+        neko_plugin_cli.unpack(gamma_bundle, plugins, check_strict=True, root=neko_repo)
+    assert not (plugins / "gamma").exists()
+    assert sorted(p.name for p in plugins.iterdir()) == ["alpha", "beta_dir"]
+
+
+def test_strict_check_restores_the_previous_version(gamma_bundle, neko_repo, plugins):
+    write_plugin(neko_repo, "gamma", _gamma_toml("1.0.0", ">=1.0.0, <2.0.0"), PACKAGE)
+    (plugins / "gamma" / "notes.txt").write_text("user data\n")
+    before = (plugins / "gamma" / "plugin.toml").read_text()
+
+    with pytest.raises(ValueError, match="rolled back"):
+        neko_plugin_cli.unpack(gamma_bundle, plugins, force=True, check_strict=True, backup=True, root=neko_repo)
+    assert (plugins / "gamma" / "plugin.toml").read_text() == before
+    assert (plugins / "gamma" / "notes.txt").read_text() == "user data\n"
+    assert not (plugins / ".neko_unpack_tmp").exists()
+    assert not any((plugins / ".backups").glob("gamma_*"))
+
+
+def test_strict_check_passes_compatible_plugins(neko_repo, plugins, tmp_path):
+    out = tmp_path / "ok.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, plugin_ids=["beta"])
+    (plugins / "beta_dir" / "notes.txt").write_text("user data\n")
+    res = neko_plugin_cli.unpack(out, plugins, force=True, check_strict=True, root=neko_repo)
+    assert [p["id"] for p in res["installed"]] == ["beta"]
+    assert res["check"]["errors"] == []
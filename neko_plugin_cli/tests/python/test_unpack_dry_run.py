@@ -0,0 +1,63 @@
+from __future__ import annotations
+
+import shutil
+
+import pytest
+from conftest import rewrite_zip, snapshot
+
+import neko_plugin_cli
+
+ITEM_KEYS = {"id", "folder", "will_install", "reason", "changed_files"}
+
+
+@pytest.fixture
+def conflicting_dest(bundle, tmp_path):
+    """beta is installed but edited locally; alpha is not installed at all"""
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+    shutil.rmtree(dest / "alpha")
+    (dest / "beta_dir" / "beta.py").write_text("changed\n")
+    return dest
+
+
+def test_report_schema(bundle, conflicting_dest):
+    before = snapshot(conflicting_dest)
+    report = neko_plugin_cli.unpack(bundle, conflicting_dest, dry_run=True)
+
+    assert set(report) == {"plugins", "summary"}
+    assert report["summary"] == {
+        "install": 1,
+        "skip": 1,
+        "bundle": {"name": "bundle", "version": None, "author": None},
+    }
+    items = {it["folder"]: it for it in report["plugins"]}
+    assert all(set(it) == ITEM_KEYS for it in items.values())
+    assert items["alpha"]["will_install"] is True
+    assert items["beta_dir"]["will_install"] is False
+    assert "--force" in items["beta_dir"]["reason"]
+    assert items["beta_dir"]["changed_files"] == [{"path": "beta.py", "kind": "modified"}]
+    assert snapshot(conflicting_dest) == before
+
+
+def test_items_match_preview_unpack(bundle, conflicting_dest):
+    report = neko_plugin_cli.unpack(bundle, conflicting_dest, dry_run=True, force=True)
+    assert report["plugins"] == neko_plugin_cli.preview_unpack(bundle, conflicting_dest, force=True)
+    assert report["summary"]["install"] == 2
+    assert report["summary"]["skip"] == 0
+
+
+def test_bundle_bytes(bundle, conflicting_dest):
+    report = neko_plugin_cli.unpack(bundle.read_bytes(), conflicting_dest, dry_run=True)
+    assert report == neko_plugin_cli.unpack(bundle, conflicting_dest, dry_run=True)
+
+
+def test_unreadable_manifest_fails(bundle, tmp_path):
+    broken = tmp_path / "broken.zip"
+    rewrite_zip(bundle, broken, lambda name, data: None if name == "manifest.toml" else data)
+    with pytest.raises(ValueError, match="manifest.toml not found"):
+        neko_plugin_cli.unpack(broken, tmp_path / "dest", dry_run=True)
+
+
+def test_rejects_extras_dest(bundle, tmp_path):
+    with pytest.raises(ValueError, match="dry_run cannot be combined with extras_dest"):
+        neko_plugin_cli.unpack(bundle, tmp_path / "dest", dry_run=True, extras_dest=tmp_path)
@@ -0,0 +1,64 @@
+from __future__ import annotations
+
+import pytest
+from conftest import pack_bundle
+
+import neko_plugin_cli
+
+ASSETS = {"assets/big.bin": "x" * 4096, "assets/icons/logo.svg": "<svg/>\n"}
+
+
+@pytest.fixture
+def assets_bundle(neko_repo, tmp_path):
+    alpha = neko_repo / "plugin" / "plugins" / "alpha"
+    for rel, text in ASSETS.items():
+        (alpha / rel).parent.mkdir(parents=True, exist_ok=True)
+        (alpha / rel).write_text(text)
+    return pack_bundle(neko_repo, tmp_path / "bundle.zip", manifest_version=2)
+
+
+def test_excluded_subdirectory_is_not_extracted(assets_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    res = neko_plugin_cli.unpack(assets_bundle, dest, excludes=["assets/**"])
+    [alpha] = [p for p in res["installed"] if p["folder"] == "alpha"]
+    assert alpha["filtered"] == ["assets/big.bin", "assets/icons/logo.svg"]
+    assert alpha["verification"] == "verified"
+    assert not (dest / "alpha" / "assets").exists()
+    assert (dest / "alpha" / "data" / "config.json").is_file()
+    assert "filtered" not in [p for p in res["installed"] if p["folder"] == "beta_dir"][0]
+
+    marker = (dest / "alpha" / ".neko_unpack_excludes").read_text().splitlines()
+    assert marker[0].startswith("# neko-plugin-cli unpack --exclude: 2 bundled file(s)")
+    assert marker[1:] == ["assets/**"]
+    assert not (dest / "beta_dir" / ".neko_unpack_excludes").exists()
+
+
+def test_preview_explains_the_difference(assets_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(assets_bundle, dest, excludes=["assets/**"])
+    report = neko_plugin_cli.unpack(assets_bundle, dest, dry_run=True)
+    alpha = next(p for p in report["plugins"] if p["folder"] == "alpha")
+    assert not alpha["will_install"]
+    assert "installed earlier with --exclude assets/**" in alpha["reason"]
+    beta = next(p for p in report["plugins"] if p["folder"] == "beta_dir")
+    assert "--exclude" not in beta["reason"]
+
+
+def test_full_overwrite_drops_the_marker(assets_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(assets_bundle, dest, excludes=["assets/**"])
+    res = neko_plugin_cli.unpack(assets_bundle, dest, force=True, prune=True)
+    [alpha] = [p for p in res["installed"] if p["folder"] == "alpha"]
+    assert "filtered" not in alpha and "pruned" not in alpha
+    assert (dest / "alpha" / "assets" / "big.bin").is_file()
+    assert not (dest / "alpha" / ".neko_unpack_excludes").exists()
+
+
+def test_excluded_local_files_survive_prune(assets_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(assets_bundle, dest)
+    (dest / "alpha" / "assets" / "local.bin").write_text("mine\n")
+    res = neko_plugin_cli.unpack(assets_bundle, dest, force=True, prune=True, excludes=["assets/**"])
+    [alpha] = [p for p in res["installed"] if p["folder"] == "alpha"]
+    assert "pruned" not in alpha
+    assert (dest / "alpha" / "assets" / "local.bin").read_text() == "mine\n"
@@ -0,0 +1,123 @@
+from __future__ import annotations
+
+import struct
+import warnings
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+
+def _with_entries(src_zip, dst_zip, entries):
+    """Copy a bundle and append (name, data) entries; duplicates are written as given"""
+    with zipfile.ZipFile(src_zip) as src, zipfile.ZipFile(dst_zip, "w", zipfile.ZIP_DEFLATED) as dst:
+        for info in src.infolist():
+            dst.writestr(info, src.read(info))
+        with warnings.catch_warnings():
+            warnings.simplefilter("ignore")
+            for name, data in entries:
+                dst.writestr(name, data)
+    return dst_zip
+
+
+def _patch_name(zip_path, placeholder, raw):
+    """Rename an entry byte-for-byte, e.g. to put a NUL in it (zipfile cuts names at NUL)"""
+    assert len(placeholder) == len(raw)
+    data = zip_path.read_bytes()
+    assert data.count(placeholder) == 2  # local header and central directory
+    zip_path.write_bytes(data.replace(placeholder, raw))
+    return zip_path
+
+
+def _forge_size(zip_path, name, size):
+    """Make the central directory claim `name` expands to `size` bytes"""
+    data = bytearray(zip_path.read_bytes())
+    at = data.find(b"PK\x01\x02")
+    while at != -1:
+        (name_len,) = struct.unpack("<H", data[at + 28 : at + 30])
+        if data[at + 46 : at + 46 + name_len] == name.encode():
+            data[at + 24 : at + 28] = struct.pack("<I", size)
+        at = data.find(b"PK\x01\x02", at + 1)
+    zip_path.write_bytes(bytes(data))
+    return zip_path
+
+
+def _understate_size(zip_path, name, size):
+    """Make both the local header and the central directory claim `name` expands to only `size` bytes"""
+    data = bytearray(zip_path.read_bytes())
+    for signature, name_at, size_at in ((b"PK\x03\x04", 26, 22), (b"PK\x01\x02", 28, 24)):
+        at = data.find(signature)
+        while at != -1:
+            (name_len,) = struct.unpack("<H", data[at + name_at : at + name_at + 2])
+            start = at + 46 if signature == b"PK\x01\x02" else at + 30
+            if data[start : start + name_len] == name.encode():
+                data[at + size_at : at + size_at + 4] = struct.pack("<I", size)
+            at = data.find(signature, at + 1)
+    zip_path.write_bytes(bytes(data))
+    return zip_path
+
+
+def _refused(zip_path, dest, match, **kwargs):
+    with pytest.raises(ValueError, match=match):
+        neko_plugin_cli.unpack(zip_path, dest, **kwargs)
+    assert not dest.exists() or list(dest.iterdir()) == []
+
+
+def test_nul_byte_in_name(bundle, tmp_path):
+    hostile = _with_entries(bundle, tmp_path / "nul.zip", [("plugins/alpha/evilX.py", "x")])
+    _patch_name(hostile, b"evilX.py", b"evil\x00.py")
+    _refused(hostile, tmp_path / "dest", "entry name contains a NUL byte")
+
+
+@pytest.mark.parametrize("name", ["plugins/alpha/con.txt", "plugins/alpha/Aux", "plugins/alpha/lpt1/a.py", "plugins/NUL.tar.gz"])
+def test_windows_device_names(bundle, tmp_path, name):
+    hostile = _with_entries(bundle, tmp_path / "device.zip", [(name, "x")])
+    _refused(hostile, tmp_path / "dest", "uses the reserved Windows device name")
+
+
+def test_device_name_lookalikes_are_fine(bundle, tmp_path):
+    ok = _with_entries(bundle, tmp_path / "ok.zip", [("plugins/alpha/console.py", "x"), ("plugins/alpha/com10.py", "x")])
+    res = neko_plugin_cli.unpack(ok, tmp_path / "dest")
+    assert "alpha" in [p["folder"] for p in res["installed"]]
+
+
+def test_duplicate_entries(bundle, tmp_path):
+    hostile = _with_entries(
+        bundle, tmp_path / "dup.zip", [("plugins/alpha/run.py", "safe"), ("plugins/alpha/run.py", "evil")]
+    )
+    _refused(hostile, tmp_path / "dest", "duplicate entry plugins/alpha/run.py")
+
+
+def test_case_insensitive_collision(bundle, tmp_path):
+    hostile = _with_entries(bundle, tmp_path / "case.zip", [("plugins/alpha/Run.py", "a"), ("plugins/alpha/run.py", "b")])
+    _refused(hostile, tmp_path / "dest", "entries plugins/alpha/Run.py and plugins/alpha/run.py collide on case-insensitive")
+
+
+def test_case_insensitive_directory_collision(bundle, tmp_path):
+    hostile = _with_entries(
+        bundle, tmp_path / "case.zip", [("plugins/alpha/Lib/x.py", "a"), ("plugins/alpha/lib/y.py", "b")]
+    )
+    _refused(hostile, tmp_path / "dest", "entries plugins/alpha/Lib and plugins/alpha/lib collide on case-insensitive")
+
+
+def test_implausible_compression_ratio(bundle, tmp_path):
+    hostile = _with_entries(bundle, tmp_path / "bomb.zip", [("plugins/alpha/bomb.bin", b"\0" * 1024)])
+    _forge_size(hostile, "plugins/alpha/bomb.bin", 0xF000_0000)
+    _refused(hostile, tmp_path / "dest", "claims to expand .* refusing a likely zip bomb")
+
+
+def test_max_uncompressed_bytes(bundle, tmp_path):
+    _refused(bundle, tmp_path / "dest", r"over the limit of 64 B \(raise it with --max-uncompressed-bytes\)", max_uncompressed_bytes=64)
+    res = neko_plugin_cli.unpack(bundle, tmp_path / "dest", max_uncompressed_bytes="1MiB")
+    assert sorted(p["folder"] for p in res["installed"]) == ["alpha", "beta_dir"]
+
+
+def test_understated_entry_size(bundle, tmp_path):
+    hostile = _with_entries(bundle, tmp_path / "big.zip", [("plugins/alpha/big.bin", b"\0" * (4 << 20))])
+    _understate_size(hostile, "plugins/alpha/big.bin", 1000)
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="corrupt data for .*big.bin: entry plugins/alpha/big.bin holds more than the 1000 B its zip"):
+        neko_plugin_cli.unpack(hostile, dest, no_verify=True, max_uncompressed_bytes=100_000)
+    assert not (dest / "alpha").exists()
+    assert not any(p.stat().st_size > 100_000 for p in tmp_path.rglob("big.bin"))
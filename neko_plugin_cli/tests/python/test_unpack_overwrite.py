@@ -0,0 +1,41 @@
+from __future__ import annotations
+
+import pytest
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def changed(neko_repo, tmp_path):
+    """Both plugins are installed, then edited, so both conflict with the bundle"""
+    bundle = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=bundle, manifest_version=2)
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+    (dest / "alpha" / "__init__.py").write_text("edited\n")
+    (dest / "beta_dir" / "beta.py").write_text("edited\n")
+    return bundle, dest
+
+
+def test_overwrite_only_the_chosen_folders(changed):
+    bundle, dest = changed
+    res = neko_plugin_cli.unpack(bundle, dest, overwrite=["beta_dir"])
+    assert [(p["folder"], p["reason"]) for p in res["installed"]] == [("beta_dir", "overwritten (chosen)")]
+    assert [p["folder"] for p in res["skipped"]] == ["alpha"]
+    assert (dest / "alpha" / "__init__.py").read_text() == "edited\n"
+    assert (dest / "beta_dir" / "beta.py").read_text() != "edited\n"
+
+
+def test_overwrite_with_backup(changed):
+    bundle, dest = changed
+    res = neko_plugin_cli.unpack(bundle, dest, overwrite=["alpha"], backup=True)
+    [backup] = res["backups"]
+    assert backup["id"] == "alpha"
+    assert (dest / "alpha" / "__init__.py").read_text() != "edited\n"
+
+
+def test_default_still_skips_conflicts(changed):
+    bundle, dest = changed
+    res = neko_plugin_cli.unpack(bundle, dest, overwrite=[])
+    assert res["installed"] == []
+    assert sorted(p["folder"] for p in res["skipped"]) == ["alpha", "beta_dir"]
@@ -0,0 +1,114 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+
+import neko_plugin_cli
+
+PLUGIN_TOML = '[plugin]\nid = "solo"\nname = "Solo"\nversion = "1.0.0"\nentry = "solo:main"\n'
+FILES = {
+    "plugin.toml": PLUGIN_TOML,
+    "__init__.py": "def main():\n    pass\n",
+    "data/config.json": '{"a": 1}\n',
+    "__pycache__/junk.pyc": "compiled",
+}
+
+
+def _plain_zip(path, files, prefix=""):
+    with zipfile.ZipFile(path, "w") as z:
+        for rel, text in files.items():
+            z.writestr(prefix + rel, text)
+    return path
+
+
+def test_top_level_plugin_installs_under_its_id(tmp_path):
+    bundle = _plain_zip(tmp_path / "solo.zip", FILES)
+    dest = tmp_path / "dest"
+    res = neko_plugin_cli.unpack(bundle, dest)
+    [p] = res["installed"]
+    assert (p["id"], p["folder"], p["verification"]) == ("solo", "solo", "verified")
+    assert any("has no manifest.toml; unpacking it as a single plugin folder" in w for w in res["warnings"])
+    assert (dest / "solo" / "data" / "config.json").read_text() == '{"a": 1}\n'
+    assert not (dest / "solo" / "__pycache__").exists()
+
+
+def test_nested_plugin_keeps_its_folder_name(tmp_path):
+    files = {"README.md": "read me\n", **{"solo_dir/" + rel: text for rel, text in FILES.items()}}
+    bundle = _plain_zip(tmp_path / "solo.zip", files)
+    dest = tmp_path / "dest"
+    res = neko_plugin_cli.unpack(bundle, dest)
+    assert [p["folder"] for p in res["installed"]] == ["solo_dir"]
+    assert any("ignored 1 zip entries outside the plugin folder solo_dir/" in w for w in res["warnings"])
+    assert (dest / "solo_dir" / "plugin.toml").read_text() == PLUGIN_TOML
+    assert not (dest / "README.md").exists()
+
+
+def test_conflicts_use_the_archive_folder_hash(tmp_path):
+    bundle = _plain_zip(tmp_path / "solo.zip", FILES, prefix="solo/")
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+
+    again = neko_plugin_cli.unpack(bundle, dest)
+    assert [(p["folder"], p["reason"]) for p in again["skipped"]] == [("solo", "identical (md5 match)")]
+
+    (dest / "solo" / "data" / "config.json").write_text('{"a": 2}\n')
+    changed = neko_plugin_cli.unpack(bundle, dest)
+    assert "--force" in changed["skipped"][0]["reason"]
+    assert changed["skipped"][0]["changed_files"] == [{"path": "data/config.json", "kind": "modified"}]
+
+    forced = neko_plugin_cli.unpack(bundle, dest, force=True)
+    assert forced["installed"][0]["reason"] == "overwritten (--force)"
+    assert (dest / "solo" / "data" / "config.json").read_text() == '{"a": 1}\n'
+
+
+def test_dry_run_detects_plain_zips(tmp_path):
+    bundle = _plain_zip(tmp_path / "solo.zip", FILES, prefix="solo/")
+    dest = tmp_path / "dest"
+    preview = neko_plugin_cli.unpack(bundle, dest, dry_run=True)
+    assert [(p["folder"], p["will_install"]) for p in preview["plugins"]] == [("solo", True)]
+    assert preview["summary"]["bundle"] is None
+    assert not dest.exists() or list(dest.iterdir()) == []
+
+    neko_plugin_cli.unpack(bundle, dest)
+    [item] = neko_plugin_cli.preview_unpack(str(bundle), str(dest))
+    assert item["will_install"] is False
+    assert "identical" in item["reason"]
+
+
+def test_unsafe_paths_are_still_refused(tmp_path):
+    files = {"solo/" + rel: text for rel, text in FILES.items()}
+    files["solo/../escape.py"] = "boom\n"
+    bundle = _plain_zip(tmp_path / "solo.zip", files)
+    dest = tmp_path / "dest"
+    res = neko_plugin_cli.unpack(bundle, dest)
+    assert any("skipped unsafe path in zip" in w for w in res["warnings"])
+    assert not (dest / "escape.py").exists()
+    assert not (tmp_path / "escape.py").exists()
+
+
+def test_several_plugin_folders_are_refused(tmp_path):
+    files = {"a/plugin.toml": PLUGIN_TOML, "b/plugin.toml": PLUGIN_TOML}
+    bundle = _plain_zip(tmp_path / "two.zip", files)
+    with pytest.raises(ValueError, match=r"2 plugin folders \(a, b\); only a single plugin folder"):
+        neko_plugin_cli.unpack(bundle, tmp_path / "dest")
+
+
+def test_no_manifest_ignores_a_bundle_manifest(neko_repo, tmp_path):
+    bundle = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=bundle)
+    with pytest.raises(ValueError, match="has no plugin.toml at the top level or one directory deep"):
+        neko_plugin_cli.unpack(bundle, tmp_path / "dest", no_manifest=True)
+
+
+def test_no_manifest_skips_the_warning(tmp_path):
+    bundle = _plain_zip(tmp_path / "solo.zip", FILES)
+    res = neko_plugin_cli.unpack(bundle, tmp_path / "dest", no_manifest=True)
+    assert [p["folder"] for p in res["installed"]] == ["solo"]
+    assert not any("manifest.toml" in w for w in res["warnings"])
+
+
+def test_zip_without_plugin_or_manifest_still_fails(tmp_path):
+    bundle = _plain_zip(tmp_path / "junk.zip", {"notes.txt": "hi\n"})
+    with pytest.raises(ValueError, match="manifest.toml not found"):
+        neko_plugin_cli.unpack(bundle, tmp_path / "dest")
@@ -0,0 +1,82 @@
+from __future__ import annotations
+
+import zipfile
+
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+
+def test_progress_fires_for_every_file(neko_repo, tmp_path):
+    bundle = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=bundle)
+    events = []
+    neko_plugin_cli.unpack(bundle, tmp_path / "dest", progress=events.append)
+
+    infos = zipfile.ZipFile(bundle).infolist()
+    payload = {i.filename: i.file_size for i in infos if i.filename != "manifest.toml" and not i.is_dir()}
+    extracted = [e for e in events if e["event"] == "file_extracted"]
+    assert sorted(e["name"] for e in extracted) == sorted(payload)
+    assert all(e["bytes"] == payload[e["name"]] for e in extracted)
+    assert [e["index"] for e in extracted] == list(range(1, len(extracted) + 1))
+    assert {e["total"] for e in extracted} == {len(infos)}
+
+    # Plugins are extracted in parallel, so their starts may come in either order
+    starts = [e for e in events if e["event"] == "plugin_start"]
+    assert sorted(starts, key=lambda e: e["plugin"]) == [
+        {"event": "plugin_start", "plugin": "alpha", "folder": "alpha"},
+        {"event": "plugin_start", "plugin": "beta", "folder": "beta_dir"},
+    ]
+    assert events[-1] == {"event": "done", "installed": 2, "skipped": 0}
+
+
+def _plugin_toml(plugin_id):
+    return f'[plugin]\nid = "{plugin_id}"\nname = "{plugin_id}"\nversion = "1.0.0"\nentry = "{plugin_id}:main"\n'
+
+
+def test_parallel_events_stay_coherent(repo, tmp_path):
+    for n in range(12):
+        files = {f"pkg/mod{i}.py": f"X = {i}\n" * 200 for i in range(20)}
+        write_plugin(repo, f"p{n:02}", _plugin_toml(f"p{n:02}"), {"__init__.py": "def main():\n    pass\n", **files})
+    bundle = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=repo, out=bundle)
+
+    events = []
+    neko_plugin_cli.unpack(bundle, tmp_path / "dest", progress=events.append)
+    extracted = [e for e in events if e["event"] == "file_extracted"]
+    assert len(extracted) == 12 * 22
+    assert [e["index"] for e in extracted] == list(range(1, len(extracted) + 1))
+    started = set()
+    for e in events:
+        if e["event"] == "plugin_start":
+            assert e["folder"] not in started
+            started.add(e["folder"])
+        elif e["event"] == "file_extracted":
+            assert e["name"].split("/")[1] in started
+    assert len(started) == 12
+    assert events[-1] == {"event": "done", "installed": 12, "skipped": 0}
+
+
+def test_skipped_plugins_are_reported(neko_repo, tmp_path):
+    bundle = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=bundle)
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(bundle, dest)
+
+    events = []
+    res = neko_plugin_cli.unpack(bundle, dest, progress=events.append)
+    skipped = [e for e in events if e["event"] == "plugin_skipped"]
+    assert sorted(e["plugin"] for e in skipped) == ["alpha", "beta"]
+    assert [e["reason"] for e in skipped] == [p["reason"] for p in res["skipped"]]
+    assert not any(e["event"] == "plugin_start" for e in events)
+    assert events[-1] == {"event": "done", "installed": 0, "skipped": 2}
+
+
+def test_failing_callback_does_not_abort_unpack(neko_repo, tmp_path):
+    def boom(event):
+        raise RuntimeError("callback failed")
+
+    bundle = tmp_path / "bundle.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=bundle)
+    res = neko_plugin_cli.unpack(bundle, tmp_path / "dest", progress=boom)
+    assert [p["folder"] for p in res["installed"]] == ["alpha", "beta_dir"]
@@ -0,0 +1,67 @@
+from __future__ import annotations
+
+import hashlib
+import tomllib
+
+import pytest
+from conftest import make_repo, write_plugin
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def installed(neko_repo, tmp_path):
+    """Two bundles unpacked into one repo: demo (alpha, beta) and extra (gamma)."""
+    demo = tmp_path / "demo.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=demo, bundle_name="demo", bundle_version="1.0.0", bundle_author="neko")
+    other = make_repo(tmp_path / "other")
+    write_plugin(other, "gamma", '[plugin]\nid = "gamma"\nversion = "0.1.0"\nentry = "gamma:main"\n', {"gamma.py": "x = 1\n"})
+    extra = tmp_path / "extra.zip"
+    neko_plugin_cli.pack(root=other, out=extra, bundle_name="extra")
+
+    target = make_repo(tmp_path / "target")
+    for bundle in [demo, extra]:
+        neko_plugin_cli.unpack(bundle, target / "plugin" / "plugins")
+    return target, demo, extra
+
+
+def test_unpack_records_provenance(installed):
+    target, demo, _ = installed
+    record = tomllib.loads((target / "plugin" / "plugins" / "beta_dir" / ".neko_bundle.toml").read_text())
+    assert record["plugin_id"] == "beta"
+    assert record["plugin_version"] == "0.3.0"
+    assert (record["bundle_name"], record["bundle_version"], record["bundle_author"]) == ("demo", "1.0.0", "neko")
+    assert record["source_zip"] == "demo.zip"
+    assert record["source_sha256"] == hashlib.sha256(demo.read_bytes()).hexdigest()
+    assert record["installed_at"].endswith("Z")
+
+
+def test_info_groups_plugins_by_bundle(installed):
+    target, demo, extra = installed
+    bundles = neko_plugin_cli.info(root=target, bundles=True)["bundles"]
+    assert [(b["name"], b["version"], b["source_zip"]) for b in bundles] == [
+        ("demo", "1.0.0", "demo.zip"),
+        ("extra", None, "extra.zip"),
+    ]
+    assert [(p["id"], p["folder"], p["modified"]) for p in bundles[0]["plugins"]] == [
+        ("alpha", "alpha", False),
+        ("beta", "beta_dir", False),
+    ]
+    assert bundles[1]["source_sha256"] == hashlib.sha256(extra.read_bytes()).hexdigest()
+    assert "bundles" not in neko_plugin_cli.info(root=target)
+
+    (target / "plugin" / "plugins" / "alpha" / "__init__.py").write_text("def main():\n    return 1\n")
+    alpha = neko_plugin_cli.info(root=target, bundles=True)["bundles"][0]["plugins"][0]
+    assert (alpha["id"], alpha["modified"]) == ("alpha", True)
+    text = neko_plugin_cli.info(root=target, format="text", bundles=True)
+    assert "Installed bundles: 2\n" in text
+    assert "- demo 1.0.0 (from demo.zip" in text
+
+
+def test_provenance_does_not_change_folder_hashes(installed, tmp_path):
+    target, _, _ = installed
+    with_record = neko_plugin_cli.pack(root=target, out=tmp_path / "a.zip", hash="sha256")["manifest"]["plugins"]
+    for record in (target / "plugin" / "plugins").glob("*/.neko_bundle.toml"):
+        record.unlink()
+    without = neko_plugin_cli.pack(root=target, out=tmp_path / "b.zip", hash="sha256")["manifest"]["plugins"]
+    assert [p["hash"] for p in with_record] == [p["hash"] for p in without]
@@ -0,0 +1,56 @@
+from __future__ import annotations
+
+import pytest
+from conftest import pack_bundle
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def installed(neko_repo, tmp_path):
+    """Version 1 of the bundle is installed, then alpha drops data/config.json for version 2"""
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(pack_bundle(neko_repo, tmp_path / "v1.zip", manifest_version=2), dest)
+    (neko_repo / "plugin" / "plugins" / "alpha" / "data" / "config.json").unlink()
+    (neko_repo / "plugin" / "plugins" / "alpha" / "new.py").write_text("x = 1\n")
+    return dest, pack_bundle(neko_repo, tmp_path / "v2.zip", manifest_version=2)
+
+
+def test_prune_removes_files_dropped_from_the_bundle(installed):
+    dest, bundle = installed
+    alpha = dest / "alpha"
+    (alpha / ".venv" / "lib").mkdir(parents=True)
+    (alpha / ".venv" / "lib" / "site.py").write_text("venv\n")
+    (alpha / "logs").mkdir()
+    (alpha / "logs" / "run.log").write_text("log\n")
+
+    res = neko_plugin_cli.unpack(bundle, dest, force=True, prune=True)
+    [entry] = [p for p in res["installed"] if p["folder"] == "alpha"]
+    assert entry["pruned"] == ["data/config.json"]
+    assert not (alpha / "data").exists()
+    assert (alpha / "new.py").is_file()
+    assert (alpha / ".venv" / "lib" / "site.py").read_text() == "venv\n"
+    assert (alpha / "logs" / "run.log").read_text() == "log\n"
+    assert (alpha / "profiles.toml").is_file()
+
+
+def test_without_prune_stale_files_stay(installed):
+    dest, bundle = installed
+    res = neko_plugin_cli.unpack(bundle, dest, force=True)
+    assert all("pruned" not in p for p in res["installed"])
+    assert (dest / "alpha" / "data" / "config.json").is_file()
+
+
+def test_prune_only_touches_overwritten_plugins(installed):
+    dest, bundle = installed
+    (dest / "beta_dir" / "local.py").write_text("mine\n")
+    res = neko_plugin_cli.unpack(bundle, dest, overwrite=["alpha"], prune=True)
+    assert [p["folder"] for p in res["installed"]] == ["alpha"]
+    assert not (dest / "alpha" / "data" / "config.json").exists()
+    assert (dest / "beta_dir" / "local.py").read_text() == "mine\n"
+
+
+def test_prune_requires_an_overwrite(installed):
+    dest, bundle = installed
+    with pytest.raises(ValueError, match="prune requires force=True or overwrite"):
+        neko_plugin_cli.unpack(bundle, dest, prune=True)
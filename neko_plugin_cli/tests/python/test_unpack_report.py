@@ -0,0 +1,109 @@
+from __future__ import annotations
+
+import json
+import zipfile
+
+import pytest
+from conftest import pack_bundle, write_plugin
+
+import neko_plugin_cli
+
+DEMO = {"manifest_version": 2, "bundle_name": "demo", "bundle_version": "2.0.0"}
+
+
+@pytest.fixture
+def upgrade(neko_repo, tmp_path):
+    """alpha is installed unchanged, beta_dir was edited locally and gamma is new in the bundle"""
+    # Bundled profiles would make the installed alpha differ from the bundle
+    (neko_repo / "plugin" / "plugins" / "alpha" / "profiles.toml").unlink()
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(pack_bundle(neko_repo, tmp_path / "v1.zip", **DEMO), dest)
+    (dest / "beta_dir" / "beta.py").write_text("def main():\n    return 3\n")
+    write_plugin(
+        neko_repo,
+        "gamma",
+        '[plugin]\nid = "gamma"\nname = "Gamma"\nversion = "0.1.0"\nentry = "gamma:main"\n',
+        {"__init__.py": "def main():\n    pass\n"},
+    )
+    return dest, pack_bundle(neko_repo, tmp_path / "v2.zip", **DEMO)
+
+
+def _by_folder(report):
+    return {p["folder"]: p for p in report["plugins"]}
+
+
+def test_report_lists_each_plugin_action(upgrade, tmp_path):
+    dest, bundle = upgrade
+    out = tmp_path / "report.json"
+    neko_plugin_cli.unpack(bundle, dest, report=out)
+    report = json.loads(out.read_text(encoding="utf-8"))
+
+    assert report["ok"] is True
+    assert report["error"] is None
+    assert report["source"] == str(bundle)
+    assert report["bundle"] == {"name": "demo", "version": "2.0.0", "author": None}
+    assert isinstance(report["duration_ms"], int)
+
+    plugins = _by_folder(report)
+    assert plugins["alpha"]["action"] == "skipped"
+    assert plugins["alpha"]["reason"] == "identical (md5 match)"
+    assert plugins["beta_dir"]["action"] == "skipped"
+    assert "--force" in plugins["beta_dir"]["reason"]
+    assert plugins["beta_dir"]["changed_files"] == [{"path": "beta.py", "kind": "modified"}]
+    gamma = plugins["gamma"]
+    assert (gamma["action"], gamma["reason"], gamma["verification"]) == ("installed", "installed", "verified")
+    assert gamma["files"] == 2
+    installed = [p for p in (dest / "gamma").iterdir() if p.is_file() and p.name != ".neko_bundle.toml"]
+    assert gamma["bytes"] == sum(p.stat().st_size for p in installed)
+
+    assert report["summary"] == {
+        "installed": 1,
+        "overwritten": 0,
+        "skipped": 2,
+        "failed": 0,
+        "files": gamma["files"],
+        "bytes": gamma["bytes"],
+    }
+
+
+def test_forced_conflict_is_reported_as_overwritten(upgrade, tmp_path):
+    dest, bundle = upgrade
+    out = tmp_path / "report.json"
+    neko_plugin_cli.unpack(bundle, dest, force=True, report=out)
+    beta = _by_folder(json.loads(out.read_text(encoding="utf-8")))["beta_dir"]
+    assert (beta["action"], beta["reason"]) == ("overwritten", "overwritten (--force)")
+    assert beta["files"] == 2
+
+
+def test_report_is_written_when_unpack_fails(upgrade, tmp_path):
+    dest, bundle = upgrade
+    bad = tmp_path / "bad.zip"
+    with zipfile.ZipFile(bundle) as src, zipfile.ZipFile(bad, "w") as dst:
+        for info in src.infolist():
+            data = src.read(info)
+            if info.filename == "manifest.toml":
+                data = data.replace(b'path = "__init__.py"', b'path = "missing.py"')
+            dst.writestr(info, data)
+    out = tmp_path / "report.json"
+    with pytest.raises(ValueError, match="unpack stopped at plugin"):
+        neko_plugin_cli.unpack(bad, dest, report=out)
+
+    report = json.loads(out.read_text(encoding="utf-8"))
+    assert report["ok"] is False
+    assert "unpack stopped at plugin" in report["error"]
+    failed = [p for p in report["plugins"] if p["action"] == "failed"]
+    assert len(failed) == 1
+    assert "missing from the bundle" in failed[0]["reason"]
+    assert "unpack stopped" not in failed[0]["reason"]
+    assert report["summary"]["failed"] == 1
+
+
+def test_report_for_a_bundle_that_cannot_be_read(tmp_path):
+    bad = tmp_path / "not-a-zip.zip"
+    bad.write_text("nope")
+    out = tmp_path / "report.json"
+    with pytest.raises(ValueError):
+        neko_plugin_cli.unpack(bad, tmp_path / "dest", report=out)
+    report = json.loads(out.read_text(encoding="utf-8"))
+    assert (report["ok"], report["bundle"], report["plugins"]) == (False, None, [])
+    assert report["error"]
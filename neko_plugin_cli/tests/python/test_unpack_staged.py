@@ -0,0 +1,104 @@
+from __future__ import annotations
+
+import json
+import zipfile
+
+import pytest
+from conftest import pack_bundle, write_plugin
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def gamma_bundle(neko_repo, tmp_path):
+    """alpha, beta_dir and gamma, in that order"""
+    write_plugin(
+        neko_repo,
+        "gamma",
+        '[plugin]\nid = "gamma"\nname = "Gamma"\nversion = "0.1.0"\nentry = "gamma:main"\n',
+        {"__init__.py": "def main():\n    pass\n"},
+    )
+    return pack_bundle(neko_repo, tmp_path / "bundle.zip", manifest_version=2)
+
+
+def _block_swap(dest, folder):
+    """Fault injection: a plain file where the plugin folder goes makes moving it into place fail"""
+    dest.mkdir(parents=True, exist_ok=True)
+    (dest / folder).write_text("in the way\n")
+
+
+def test_staged_unpack_installs_every_plugin(gamma_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    res = neko_plugin_cli.unpack(gamma_bundle, dest, staged=True)
+    assert [p["folder"] for p in res["installed"]] == ["alpha", "beta_dir", "gamma"]
+    assert sorted(p.name for p in dest.iterdir()) == ["alpha", "beta_dir", "gamma"]
+
+
+def test_failed_swap_rolls_back_new_plugins(gamma_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    _block_swap(dest, "beta_dir")
+    with pytest.raises(OSError, match="unpack stopped while activating plugin 'beta'; rolled back: alpha"):
+        neko_plugin_cli.unpack(gamma_bundle, dest, staged=True)
+    assert sorted(p.name for p in dest.iterdir()) == ["beta_dir"]
+    assert (dest / "beta_dir").read_text() == "in the way\n"
+
+
+def test_failed_swap_restores_overwritten_plugins(neko_repo, gamma_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    neko_plugin_cli.unpack(gamma_bundle, dest)
+    (neko_repo / "plugin" / "plugins" / "alpha" / "data" / "config.json").write_text('{"a": 2}\n')
+    v2 = pack_bundle(neko_repo, tmp_path / "v2.zip", manifest_version=2)
+    (dest / "alpha" / "local.txt").write_text("mine\n")
+    for p in (dest / "beta_dir").iterdir():
+        p.unlink()
+    (dest / "beta_dir").rmdir()
+    _block_swap(dest, "beta_dir")
+
+    with pytest.raises(OSError, match="rolled back: alpha"):
+        neko_plugin_cli.unpack(v2, dest, force=True, staged=True)
+    assert (dest / "alpha" / "data" / "config.json").read_text() == '{"a": 1}\n'
+    assert (dest / "alpha" / "local.txt").read_text() == "mine\n"
+    assert not (dest / ".neko_unpack_tmp").exists()
+
+
+def test_without_staged_a_failed_swap_keeps_earlier_plugins(gamma_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    _block_swap(dest, "beta_dir")
+    with pytest.raises(OSError, match="fully installed before the failure: alpha"):
+        neko_plugin_cli.unpack(gamma_bundle, dest)
+    assert (dest / "alpha").is_dir()
+
+
+def test_staging_failure_installs_nothing(gamma_bundle, tmp_path):
+    bad = tmp_path / "bad.zip"
+    with zipfile.ZipFile(gamma_bundle) as src, zipfile.ZipFile(bad, "w") as dst:
+        for info in src.infolist():
+            data = src.read(info)
+            if info.filename == "manifest.toml":
+                data = data.replace(b'path = "__init__.py"', b'path = "missing.py"')
+            dst.writestr(info, data)
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="fully installed before the failure: none"):
+        neko_plugin_cli.unpack(bad, dest, staged=True)
+    assert list(dest.iterdir()) == []
+
+
+def test_staging_dir_outside_dest(gamma_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    staging = tmp_path / "staging"
+    res = neko_plugin_cli.unpack(gamma_bundle, dest, staging_dir=staging)
+    assert len(res["installed"]) == 3
+    assert sorted(p.name for p in dest.iterdir()) == ["alpha", "beta_dir", "gamma"]
+    assert not staging.exists()
+
+
+def test_report_records_the_rollback(gamma_bundle, tmp_path):
+    dest = tmp_path / "dest"
+    _block_swap(dest, "beta_dir")
+    out = tmp_path / "report.json"
+    with pytest.raises(OSError):
+        neko_plugin_cli.unpack(gamma_bundle, dest, staged=True, report=out)
+    report = json.loads(out.read_text(encoding="utf-8"))
+    assert report["rollback"] == {"restored": ["alpha"], "failed": []}
+    assert [(p["folder"], p["action"]) for p in report["plugins"]] == [("beta_dir", "failed")]
+
@@ -0,0 +1,62 @@
+from __future__ import annotations
+
+import struct
+import zipfile
+
+import pytest
+from conftest import break_checksum, rewrite_zip
+
+import neko_plugin_cli
+
+
+def _flip_payload_byte(src_zip, dst_zip, suffix):
+    """Corrupt the stored bytes of the entry ending in `suffix` without touching the zip structure"""
+    raw = bytearray(src_zip.read_bytes())
+    with zipfile.ZipFile(src_zip) as zf:
+        info = next(i for i in zf.infolist() if i.filename.endswith(suffix))
+    name_len, extra_len = struct.unpack("<HH", raw[info.header_offset + 26 : info.header_offset + 30])
+    raw[info.header_offset + 30 + name_len + extra_len] ^= 0xFF
+    dst_zip.write_bytes(bytes(raw))
+    return dst_zip
+
+
+def test_installed_plugins_report_verification(bundle, neko_repo, tmp_path):
+    res = neko_plugin_cli.unpack(bundle, tmp_path / "v2")
+    assert {p["folder"]: p["verification"] for p in res["installed"]} == {"alpha": "verified", "beta_dir": "verified"}
+
+    v1 = tmp_path / "v1.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=v1)
+    res = neko_plugin_cli.unpack(v1, tmp_path / "v1")
+    assert {p["verification"] for p in res["installed"]} == {"no_checksums"}
+    assert all("verification" not in p for p in neko_plugin_cli.unpack(v1, tmp_path / "v1")["skipped"])
+
+
+def test_corrupted_payload_is_not_installed(bundle, tmp_path):
+    bad = _flip_payload_byte(bundle, tmp_path / "bad.zip", "beta_dir/beta.py")
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="unpack stopped at plugin 'beta'; fully installed before the failure: alpha"):
+        neko_plugin_cli.unpack(bad, dest)
+    assert sorted(p.name for p in dest.iterdir()) == ["alpha"]
+
+
+def test_checksum_mismatch_names_the_file(bundle, tmp_path):
+    bad = break_checksum(bundle, tmp_path / "bad.zip", "beta.py")
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="checksum mismatch for .*beta.py"):
+        neko_plugin_cli.unpack(bad, dest)
+    assert not (dest / "beta_dir").exists()
+
+
+def test_no_verify_skips_the_check(bundle, tmp_path):
+    bad = break_checksum(bundle, tmp_path / "bad.zip", "beta.py")
+    res = neko_plugin_cli.unpack(bad, tmp_path / "dest", no_verify=True)
+    assert {p["verification"] for p in res["installed"]} == {"skipped"}
+    assert (tmp_path / "dest" / "beta_dir" / "beta.py").read_text() == "def main():\n    return 2\n"
+
+
+def test_entry_missing_from_bundle_fails(bundle, tmp_path):
+    bad = rewrite_zip(bundle, tmp_path / "bad.zip", lambda name, data: None if name.endswith("beta_dir/beta.py") else data)
+    dest = tmp_path / "dest"
+    with pytest.raises(ValueError, match="listed in the manifest are missing from the bundle: beta.py"):
+        neko_plugin_cli.unpack(bad, dest)
+    assert not (dest / "beta_dir").exists()
@@ -0,0 +1,140 @@
+from __future__ import annotations
+
+import re
+import warnings
+
+import pytest
+from conftest import write_plugin
+
+import neko_plugin_cli
+
+GOOD = {"id": '"broken"', "version": '"1.0.0"', "entry": '"broken:main"'}
+
+
+def _toml(**fields):
+    merged = {**GOOD, **fields}
+    return "[plugin]\n" + "".join(f"{k} = {v}\n" for k, v in merged.items() if v is not None)
+
+
+BROKEN = [
+    (_toml(version=None), "version: missing"),
+    (_toml(version='"1.x"'), "version: `1.x` is not a valid semver version"),
+    (_toml(version="1"), "version: must be a string, not integer"),
+    (_toml(id=None), "id: missing"),
+    (_toml(id='"Bad Id"'), "id: `Bad Id` may only contain ASCII letters, digits"),
+    (_toml(id='"-lead"'), "id: `-lead` must start with an ASCII letter or digit"),
+    (_toml(id='"' + "x" * 65 + '"'), "id: `x{65}` is longer than 64 characters"),
+    (_toml(entry='""'), "entry: must not be empty"),
+    (_toml(entry='"nope:main"'), "entry: `nope:main` does not point at an existing file"),
+    ('name = "no table"\n', "plugin: missing \\[plugin\\] table"),
+    (_toml(sdk='"1.0"'), "sdk: must be a table, not string"),
+    (_toml() + '[plugin.sdk]\nsupported = ">=x"\n', "sdk.supported: `>=x` is not a valid version requirement"),
+    (_toml() + "[plugin.sdk]\nuntested = 1\n", "sdk.untested: must be a version requirement string, not integer"),
+    (_toml() + '[plugin.sdk]\nconflicts = "<1"\n', "sdk.conflicts: must be an array of version requirements, not string"),
+    (_toml() + '[plugin.sdk]\nconflicts = ["<1", "nope"]\n', "sdk.conflicts\\[1\\]: `nope` is not a valid version requirement"),
+    (
+        _toml() + '[plugin.sdk]\nminimum = "1"\n',
+        "sdk.minimum: unknown key \\(expected recommended, supported, untested, conflicts\\)",
+    ),
+    (_toml(dependency='"alpha"'), "dependency: must be an array of tables \\(\\[\\[plugin.dependency\\]\\]\\), not string"),
+    (_toml(dependency='["alpha"]'), "dependency\\[0\\]: must be a table, not string"),
+    (_toml() + '[[plugin.dependency]]\nsupported = ">=1"\n', "dependency\\[0\\].id: missing"),
+    (_toml() + "[[plugin.dependency]]\nid = 3\n", "dependency\\[0\\].id: must be a string, not integer"),
+    (_toml() + '[[plugin.dependency]]\nid = "bad id"\n', "dependency\\[0\\].id: `bad id` may only contain"),
+    (
+        _toml() + '[[plugin.dependency]]\nid = "alpha"\n\n[[plugin.dependency]]\nid = "beta"\nsupported = "latest"\n',
+        "dependency\\[1\\].supported: `latest` is not a valid version requirement",
+    ),
+    (
+        _toml() + '[[plugin.dependency]]\nid = "alpha"\nconflicts = [1]\n',
+        "dependency\\[0\\].conflicts\\[0\\]: must be a version requirement string, not integer",
+    ),
+]
+
+
+@pytest.mark.parametrize("plugin_toml,message", BROKEN)
+def test_broken_plugin_toml_aborts_pack(neko_repo, tmp_path, plugin_toml, message):
+    write_plugin(neko_repo, "broken", plugin_toml, {"broken.py": "def main():\n    pass\n"})
+    out = tmp_path / "x.zip"
+    with pytest.raises(ValueError, match="broken/plugin.toml: " + message):
+        neko_plugin_cli.pack(root=neko_repo, out=out)
+    assert not out.exists()
+
+
+@pytest.mark.parametrize("plugin_toml,message", BROKEN)
+def test_check_reports_the_same_problems(neko_repo, plugin_toml, message):
+    write_plugin(neko_repo, "broken", plugin_toml, {"broken.py": "def main():\n    pass\n"})
+    report = neko_plugin_cli.check(root=neko_repo, toml=True)
+    assert any(re.search("broken/plugin.toml: " + message, e) for e in report["errors"])
+
+
+def test_every_offending_plugin_and_field_is_listed(neko_repo, tmp_path):
+    write_plugin(neko_repo, "one", _toml(id='"one"', version='"x"', entry='"one:main"'))
+    write_plugin(neko_repo, "two", _toml(id='"two"', version=None, entry='"two:main"'), {"two.py": ""})
+    with pytest.raises(ValueError) as exc:
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip")
+    msg = str(exc.value)
+    assert "use --allow-invalid" in msg
+    assert "one/plugin.toml: version: `x` is not a valid semver version" in msg
+    assert "one/plugin.toml: entry: `one:main` does not point at an existing file" in msg
+    assert "two/plugin.toml: version: missing" in msg
+
+
+def test_dry_run_validates_too(neko_repo, tmp_path):
+    write_plugin(neko_repo, "broken", _toml(version=None), {"broken.py": ""})
+    with pytest.raises(ValueError, match="version: missing"):
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", dry_run=True)
+
+
+def test_allow_invalid_packs_with_warnings(neko_repo, tmp_path):
+    write_plugin(neko_repo, "broken", _toml(version=None), {"broken.py": ""})
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        res = neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip", allow_invalid=True)
+    assert "broken" in [p["id"] for p in res["manifest"]["plugins"]]
+    assert any("broken/plugin.toml: version: missing (packed anyway" in str(w.message) for w in caught)
+
+
+def test_only_selected_plugins_are_validated(neko_repo, tmp_path):
+    write_plugin(neko_repo, "broken", _toml(version=None))
+    res = neko_plugin_cli.pack(root=neko_repo, plugin_ids=["alpha"], out=tmp_path / "x.zip")
+    assert [p["id"] for p in res["manifest"]["plugins"]] == ["alpha"]
+
+
+def test_unknown_top_level_key_only_warns(neko_repo, tmp_path):
+    write_plugin(neko_repo, "odd", _toml(id='"odd"', entry='"odd:main"') + "\n[extra]\nx = 1\n", {"odd.py": ""})
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        neko_plugin_cli.pack(root=neko_repo, out=tmp_path / "x.zip")
+    assert any("odd/plugin.toml: extra: unknown top-level key" in str(w.message) for w in caught)
+    report = neko_plugin_cli.check(root=neko_repo, toml=True)
+    assert report["errors"] == []
+    assert report["warnings"] == ["odd/plugin.toml: extra: unknown top-level key (ignored)"]
+
+
+@pytest.mark.parametrize(
+    "entry,files",
+    [
+        ('"pkg.mod:run"', {"pkg/mod.py": ""}),
+        ('"pkg:run"', {"pkg/__init__.py": ""}),
+        ('"main.py"', {"main.py": ""}),
+    ],
+)
+def test_entry_resolution(neko_repo, tmp_path, entry, files):
+    write_plugin(neko_repo, "ok", _toml(id='"ok"', entry=entry), files)
+    report = neko_plugin_cli.check(root=neko_repo, toml=True)
+    assert report["errors"] == []
+
+
+def test_bad_ranges_do_not_abort_the_other_checks(neko_repo):
+    write_plugin(
+        neko_repo,
+        "broken",
+        _toml() + '[plugin.sdk]\nsupported = ">=x"\n\n[[plugin.dependency]]\nid = "missing"\nsupported = "?"\n',
+        {"broken.py": "def main():\n    pass\n"},
+    )
+    report = neko_plugin_cli.check(root=neko_repo)
+    assert report["errors"] == [
+        "broken/plugin.toml: dependency[0].supported: `?` is not a valid version requirement (unexpected character '?' while parsing major version number)",
+        "broken/plugin.toml: sdk.supported: `>=x` is not a valid version requirement (unexpected character 'x' while parsing major version number)",
+    ]
@@ -0,0 +1,87 @@
+from __future__ import annotations
+
+import zipfile
+
+import pytest
+from conftest import issue_codes, pack_bundle, rewrite_zip
+
+import neko_plugin_cli
+
+
+@pytest.fixture
+def stored_bundle(neko_repo, tmp_path):
+    return pack_bundle(neko_repo, tmp_path / "b.zip", manifest_version=2, compression="stored")
+
+
+def test_clean_bundle_passes(stored_bundle):
+    report = neko_plugin_cli.verify(stored_bundle)
+    assert report["issues"] == []
+    assert (report["plugins"], report["folder_hashes"]) == (2, 2)
+    assert report["file_checksums"] > 0
+    assert (report["signed"], report["signed_by"]) == (False, None)
+
+
+def test_signature_is_checked(neko_repo, tmp_path):
+    keys = neko_plugin_cli.keygen(tmp_path / "neko.key")
+    out = tmp_path / "signed.zip"
+    neko_plugin_cli.pack(root=neko_repo, out=out, manifest_version=2, sign_key=keys["secret_key"])
+    assert neko_plugin_cli.verify(out)["signed"] is True
+    report = neko_plugin_cli.verify(out, verify_key=keys["public_key"])
+    assert report["issues"] == [] and report["signed_by"] == keys["fingerprint"]
+
+    other = neko_plugin_cli.keygen(tmp_path / "other.key")
+    report = neko_plugin_cli.verify(out, verify_key=other["public_key"])
+    assert [i["code"] for i in report["issues"]] == ["signature-invalid"]
+
+
+def test_missing_files_are_all_reported(stored_bundle, tmp_path):
+    def drop(name, data):
+        return None if name == "plugins/alpha/data/config.json" or name.startswith("plugins/beta_dir/") else data
+
+    bad = rewrite_zip(stored_bundle, tmp_path / "bad.zip", drop)
+    report = neko_plugin_cli.verify(bad)
+    assert issue_codes(report) == [
+        ("file-missing", "plugins/alpha/data/config.json is listed in the manifest but missing from the bundle"),
+        ("hash-mismatch", report["issues"][1]["message"]),
+        ("plugin-empty", "plugin beta has no entries under plugins/beta_dir/"),
+    ]
+    assert report["issues"][1]["message"].startswith("md5 hash of plugin alpha does not match the manifest")
+    # verify_bundle (and pack --verify) stop at the first of the same problems
+    with pytest.raises(ValueError, match="plugins/alpha/data/config.json is listed in the manifest but missing"):
+        neko_plugin_cli.verify_bundle(bad)
+
+
+def test_corrupted_and_tampered_files(stored_bundle, tmp_path):
+    tampered = rewrite_zip(stored_bundle, tmp_path / "tampered.zip", lambda n, d: b"evil()\n" if n == "plugins/beta_dir/beta.py" else d)
+    assert [i["code"] for i in neko_plugin_cli.verify(tampered)["issues"]] == ["checksum-mismatch", "hash-mismatch"]
+
+    with zipfile.ZipFile(stored_bundle) as z:
+        info = z.getinfo("plugins/beta_dir/beta.py")
+    data = bytearray(stored_bundle.read_bytes())
+    header = info.header_offset
+    name_len = int.from_bytes(data[header + 26 : header + 28], "little")
+    extra_len = int.from_bytes(data[header + 28 : header + 30], "little")
+    data[header + 30 + name_len + extra_len] ^= 0xFF
+    stored_bundle.write_bytes(bytes(data))
+    [issue] = neko_plugin_cli.verify(stored_bundle)["issues"]
+    assert issue["code"] == "entry-unreadable"
+    assert issue["message"].startswith("failed to read plugins/beta_dir/beta.py")
+
+
+def test_unreferenced_entries(stored_bundle, tmp_path):
+    bad = tmp_path / "extra.zip"
+    rewrite_zip(stored_bundle, bad, lambda n, d: d)
+    with zipfile.ZipFile(bad, "a") as z:
+        z.writestr("payload.sh", "rm -rf /\n")
+        z.writestr("plugins/alpha/injected.py", "x = 1\n")
+    report = neko_plugin_cli.verify(bad)
+    assert [i["code"] for i in report["issues"]] == ["entry-unlisted", "hash-mismatch", "entry-unlisted"]
+    assert report["issues"][0]["message"] == "plugins/alpha/injected.py is not listed in the manifest"
+    assert report["issues"][2]["message"] == "payload.sh is not referenced by the manifest"
+
+
+def test_unreadable_bundle_raises(tmp_path):
+    bad = tmp_path / "not-a.zip"
+    bad.write_bytes(b"nope")
+    with pytest.raises(ValueError, match="verification failed for"):
+        neko_plugin_cli.verify(bad)